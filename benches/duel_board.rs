@@ -0,0 +1,78 @@
+//! the 1v1 fixtures on a board with room for 4 snakes against a duel board with room for just
+//! the 2, for simulating, voronoi and random playouts, and what switching to a duel board costs
+
+use battlesnake_game_types::compact_representation::{
+    StandardCellBoard4Snakes11x11, StandardDuelCellBoard11x11,
+};
+use battlesnake_game_types::fixtures::{self, Fixture};
+use battlesnake_game_types::playout::playout;
+use battlesnake_game_types::types::{
+    HealthGettableGame, Move, RandomReasonableMovesGame, SimulableGame, SimulatorInstruments,
+    SnakeId, VictorDeterminableGame, VoronoiGame, VoronoiOptions, YouDeterminableGame,
+};
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use rand::{rngs::SmallRng, SeedableRng};
+
+#[derive(Debug)]
+struct Instruments {}
+
+impl SimulatorInstruments for Instruments {
+    fn observe_simulation(&self, _: std::time::Duration) {}
+}
+
+const PLAYOUT_TURNS: usize = 100;
+
+/// simulate, voronoi and playouts for `board`, named `kind` in each group
+fn bench_board<B, const N_SNAKES: usize>(c: &mut Criterion, fixture: &Fixture, kind: &str, board: B)
+where
+    B: SimulableGame<Instruments, N_SNAKES>
+        + VoronoiGame
+        + RandomReasonableMovesGame
+        + VictorDeterminableGame
+        + YouDeterminableGame<SnakeIDType = SnakeId>
+        + HealthGettableGame
+        + Clone,
+{
+    let instruments = Instruments {};
+    let snake_ids = board.get_snake_ids();
+    let id = format!("{} {}", fixture.name, kind);
+
+    c.benchmark_group("duel simulate_with_moves")
+        .bench_function(&id, |b| {
+            b.iter(|| {
+                black_box(&board)
+                    .simulate_with_moves(
+                        &instruments,
+                        snake_ids.iter().map(|sid| (*sid, Move::all())),
+                    )
+                    .for_each(|_| {})
+            })
+        });
+
+    c.benchmark_group("duel voronoi").bench_function(&id, |b| {
+        b.iter(|| black_box(&board).voronoi(VoronoiOptions::default()))
+    });
+
+    c.benchmark_group("duel random playout")
+        .bench_function(&id, |b| {
+            let mut rng = SmallRng::seed_from_u64(0);
+            b.iter(|| playout(board.clone(), &mut rng, PLAYOUT_TURNS, &instruments))
+        });
+}
+
+fn duel_boards(c: &mut Criterion) {
+    for fixture in fixtures::DUELS {
+        let general: StandardCellBoard4Snakes11x11 = fixture.compact().unwrap();
+        let duel: StandardDuelCellBoard11x11 = fixture.compact().unwrap();
+        bench_board(c, fixture, "4 snake board", general);
+        bench_board(c, fixture, "duel board", duel);
+
+        c.benchmark_group("respecialize")
+            .bench_function(fixture.name, |b| {
+                b.iter(|| black_box(&general).respecialized::<2>().unwrap())
+            });
+    }
+}
+
+criterion_group!(benches, duel_boards);
+criterion_main!(benches);
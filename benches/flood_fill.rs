@@ -0,0 +1,73 @@
+//! flood fills and path searches from your head over the fixtures module, on the wire game and on
+//! compact boards whose cells are numbered with a u8 and with a u16, to show what narrower
+//! positions save. To compare a change against the code before it, run
+//! `cargo bench --bench flood_fill -- --save-baseline before` first and then
+//! `cargo bench --bench flood_fill -- --baseline before` with the change
+
+use battlesnake_game_types::compact_representation::dimensions::Square;
+use battlesnake_game_types::compact_representation::{
+    StandardCellBoard, StandardCellBoard4Snakes11x11,
+};
+use battlesnake_game_types::fixtures::{self, Fixture};
+use battlesnake_game_types::types::{
+    AStarOptions, FloodFillOptions, FloodFillableGame, HeadGettableGame, PathfindableGame,
+    SnakeBodyGettableGame, SnakeIDGettableGame, YouDeterminableGame,
+};
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+
+/// an 11x11 board like [StandardCellBoard4Snakes11x11], but with cells twice as wide
+type WideCellBoard4Snakes11x11 = StandardCellBoard<u16, Square, { 11 * 11 }, 4>;
+
+/// flood fill and flood fill count from your head on `board`, and paths from your head to the
+/// tail of the first other snake, named `kind` in each group
+fn bench_board<B>(c: &mut Criterion, fixture: &Fixture, kind: &str, board: B)
+where
+    B: FloodFillableGame
+        + PathfindableGame
+        + HeadGettableGame
+        + SnakeBodyGettableGame
+        + SnakeIDGettableGame
+        + YouDeterminableGame,
+{
+    let id = format!("{} {}", fixture.name, kind);
+    let head = board.get_head_as_native_position(board.you_id());
+
+    c.benchmark_group("flood fill positions")
+        .bench_function(&id, |b| {
+            b.iter(|| black_box(&board).flood_fill(&head, FloodFillOptions::default()))
+        });
+
+    c.benchmark_group("flood fill count")
+        .bench_function(&id, |b| {
+            b.iter(|| black_box(&board).flood_fill_count(&head, FloodFillOptions::default()))
+        });
+
+    let tail = board
+        .get_snake_ids()
+        .into_iter()
+        .find(|sid| sid != board.you_id())
+        .and_then(|sid| board.get_snake_body_vec(&sid).pop())
+        .unwrap();
+    c.benchmark_group("a star").bench_function(&id, |b| {
+        b.iter(|| black_box(&board).a_star(&head, &tail, AStarOptions::default()))
+    });
+
+    c.benchmark_group("cost map").bench_function(&id, |b| {
+        b.iter(|| black_box(&board).cost_map(&head, AStarOptions::default()))
+    });
+}
+
+fn flood_fills(c: &mut Criterion) {
+    for fixture in fixtures::STANDARD_4_SNAKES {
+        bench_board(c, fixture, "wire", fixture.game());
+
+        let narrow: StandardCellBoard4Snakes11x11 = fixture.compact().unwrap();
+        bench_board(c, fixture, "u8 cells", narrow);
+
+        let wide: WideCellBoard4Snakes11x11 = fixture.compact().unwrap();
+        bench_board(c, fixture, "u16 cells", wide);
+    }
+}
+
+criterion_group!(benches, flood_fills);
+criterion_main!(benches);
@@ -0,0 +1,101 @@
+use std::sync::Arc;
+
+use battlesnake_game_types::compact_representation::dimensions::Square;
+use battlesnake_game_types::compact_representation::standard::CellBoardDelta;
+use battlesnake_game_types::compact_representation::StandardCellBoard4Snakes11x11;
+use battlesnake_game_types::types::{
+    build_snake_id_map, Move, SimulableGame, SimulatorInstruments, SnakeIDGettableGame, SnakeId,
+};
+use battlesnake_game_types::wire_representation::Game as DEGame;
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+
+#[derive(Debug)]
+struct Instruments {}
+
+impl SimulatorInstruments for Instruments {
+    fn observe_simulation(&self, _: std::time::Duration) {}
+}
+
+type Board = StandardCellBoard4Snakes11x11;
+type Delta = CellBoardDelta<u8, Square, { 11 * 11 }, 4>;
+
+const FRONTIER: usize = 100_000;
+
+fn late_stage() -> Board {
+    let g: DEGame = serde_json::from_str(include_str!("../fixtures/late_stage.json")).unwrap();
+    g.as_cell_board(&build_snake_id_map(&g)).unwrap()
+}
+
+fn all_moves(board: &Board) -> Vec<(SnakeId, [Move; 4])> {
+    board
+        .get_snake_ids()
+        .into_iter()
+        .map(|sid| (sid, Move::all()))
+        .collect()
+}
+
+/// a breadth first frontier of FRONTIER boards, each stored as a delta from its expanded parent
+fn frontier(root: Board) -> (Vec<Delta>, usize) {
+    let mut frontier = vec![root.delta_from(&Arc::new(root))];
+    let mut parents = 0;
+    let mut next = 0;
+    while frontier.len() - next < FRONTIER {
+        let parent = Arc::new(frontier[next].materialize());
+        next += 1;
+        parents += 1;
+        frontier.extend(
+            parent
+                .simulate_with_moves_as_deltas(&Instruments {}, all_moves(&parent))
+                .map(|(_, delta)| delta),
+        );
+    }
+    (frontier.split_off(next), parents)
+}
+
+/// not timed, how much memory a frontier of deltas takes next to one of whole boards
+fn print_frontier_memory() {
+    let (frontier, parents) = frontier(late_stage());
+    let deltas: usize = frontier.iter().map(|d| d.size_in_bytes()).sum();
+    let shared = parents * std::mem::size_of::<Board>();
+    let whole = frontier.len() * std::mem::size_of::<Board>();
+    println!(
+        "{} frontier nodes on 11x11: {:.1} MB as deltas plus {:.1} MB of {} shared parents, {:.1} MB as whole boards",
+        frontier.len(),
+        deltas as f64 / 1e6,
+        shared as f64 / 1e6,
+        parents,
+        whole as f64 / 1e6,
+    );
+}
+
+fn bench_deltas(c: &mut Criterion) {
+    print_frontier_memory();
+
+    let board = Arc::new(late_stage());
+    let moves = all_moves(&board);
+    c.bench_function("compact late stage - all moves as deltas", |b| {
+        b.iter(|| {
+            black_box(&board)
+                .simulate_with_moves_as_deltas(&Instruments {}, moves.iter().cloned())
+                .for_each(|_| {})
+        })
+    });
+    c.bench_function("compact late stage - all moves", |b| {
+        b.iter(|| {
+            black_box(&board)
+                .simulate_with_moves(&Instruments {}, moves.iter().cloned())
+                .for_each(|_| {})
+        })
+    });
+
+    let (_, delta) = board
+        .simulate_with_moves_as_deltas(&Instruments {}, moves.iter().cloned())
+        .next()
+        .unwrap();
+    c.bench_function("materialize a delta", |b| {
+        b.iter(|| black_box(&delta).materialize())
+    });
+}
+
+criterion_group!(benches, bench_deltas);
+criterion_main!(benches);
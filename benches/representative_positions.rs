@@ -0,0 +1,124 @@
+//! the paths a snake spends its time in, run over the positions in the fixtures module at both
+//! 11x11 with 4 snakes and 19x19 with 8 snakes
+
+use battlesnake_game_types::compact_representation::{
+    StandardCellBoard4Snakes11x11, StandardCellBoard8Snakes19x19,
+};
+use battlesnake_game_types::fixtures::{self, Fixture};
+use battlesnake_game_types::playout::playout;
+use battlesnake_game_types::types::{
+    FloodFillOptions, FloodFillableGame, HeadGettableGame, HealthGettableGame, Move,
+    RandomReasonableMovesGame, SimulableGame, SimulatorInstruments, SizeDeterminableGame,
+    VictorDeterminableGame, VoronoiGame, VoronoiOptions, YouDeterminableGame,
+};
+use battlesnake_game_types::wire_representation::Game;
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use rand::{rngs::SmallRng, SeedableRng};
+
+#[derive(Debug)]
+struct Instruments {}
+
+impl SimulatorInstruments for Instruments {
+    fn observe_simulation(&self, _: std::time::Duration) {}
+}
+
+const PLAYOUT_TURNS: usize = 100;
+
+fn wire_parse(c: &mut Criterion) {
+    let mut group = c.benchmark_group("wire parse");
+    for fixture in fixtures::STANDARD_4_SNAKES
+        .iter()
+        .chain(fixtures::LARGE_8_SNAKES)
+    {
+        group.bench_function(fixture.name, |b| {
+            b.iter(|| serde_json::from_str::<Game>(black_box(fixture.json)).unwrap())
+        });
+    }
+    group.finish();
+}
+
+fn wire_to_compact(c: &mut Criterion) {
+    let mut group = c.benchmark_group("wire to compact");
+    for fixture in fixtures::STANDARD_4_SNAKES {
+        let (game, snake_ids) = (fixture.game(), fixture.snake_ids());
+        group.bench_function(fixture.name, |b| {
+            b.iter(|| {
+                let board: StandardCellBoard4Snakes11x11 =
+                    black_box(&game).as_cell_board(&snake_ids).unwrap();
+                board
+            })
+        });
+    }
+    for fixture in fixtures::LARGE_8_SNAKES {
+        let (game, snake_ids) = (fixture.game(), fixture.snake_ids());
+        group.bench_function(fixture.name, |b| {
+            b.iter(|| {
+                let board: StandardCellBoard8Snakes19x19 =
+                    black_box(&game).as_cell_board(&snake_ids).unwrap();
+                board
+            })
+        });
+    }
+    group.finish();
+}
+
+/// simulate, flood fill, voronoi and playouts for `board`, one benchmark group each
+fn bench_board<B, const N_SNAKES: usize>(c: &mut Criterion, fixture: &Fixture, board: B)
+where
+    B: SimulableGame<Instruments, N_SNAKES>
+        + FloodFillableGame
+        + VoronoiGame
+        + HeadGettableGame
+        + RandomReasonableMovesGame
+        + VictorDeterminableGame
+        + YouDeterminableGame
+        + HealthGettableGame
+        + SizeDeterminableGame
+        + Clone,
+{
+    let instruments = Instruments {};
+    let snake_ids = board.get_snake_ids();
+    let size = board.get_width();
+    let id = format!("{} {}x{}", fixture.name, size, size);
+
+    c.benchmark_group("simulate_with_moves")
+        .bench_function(&id, |b| {
+            b.iter(|| {
+                black_box(&board)
+                    .simulate_with_moves(
+                        &instruments,
+                        snake_ids.iter().map(|sid| (sid.clone(), Move::all())),
+                    )
+                    .for_each(|_| {})
+            })
+        });
+
+    let head = board.get_head_as_native_position(board.you_id());
+    c.benchmark_group("flood fill").bench_function(&id, |b| {
+        b.iter(|| black_box(&board).flood_fill_count(&head, FloodFillOptions::default()))
+    });
+
+    c.benchmark_group("voronoi").bench_function(&id, |b| {
+        b.iter(|| black_box(&board).voronoi(VoronoiOptions::default()))
+    });
+
+    c.benchmark_group("random playout")
+        .bench_function(&id, |b| {
+            let mut rng = SmallRng::seed_from_u64(0);
+            b.iter(|| playout(board.clone(), &mut rng, PLAYOUT_TURNS, &instruments))
+        });
+}
+
+fn compact_boards(c: &mut Criterion) {
+    for fixture in fixtures::STANDARD_4_SNAKES {
+        let board: StandardCellBoard4Snakes11x11 = fixture.compact().unwrap();
+        bench_board(c, fixture, board);
+    }
+    for fixture in fixtures::LARGE_8_SNAKES {
+        let board: StandardCellBoard8Snakes19x19 = fixture.compact().unwrap();
+        bench_board(c, fixture, board);
+    }
+}
+
+criterion_group!(benches, wire_parse, wire_to_compact, compact_boards);
+criterion_main!(benches);
@@ -2,9 +2,13 @@ use battlesnake_game_types::types::SimulatorInstruments;
 use battlesnake_game_types::wire_representation::Game as DEGame;
 use battlesnake_game_types::{
     compact_representation::StandardCellBoard4Snakes11x11,
-    types::{build_snake_id_map, Move, SimulableGame, SnakeIDGettableGame, SnakeId},
+    types::{
+        build_snake_id_map, Move, RandomReasonableMovesGame, ReasonableMoveLevel, SimulableGame,
+        SnakeIDGettableGame, SnakeId, VoronoiGame, VoronoiOptions,
+    },
 };
 use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use rand::{rngs::SmallRng, SeedableRng};
 
 #[derive(Debug)]
 struct Instruments {}
@@ -68,10 +72,69 @@ fn late_stage_compact_repr(c: &mut Criterion) {
     });
 }
 
+fn late_stage_random_reasonable_moves(c: &mut Criterion) {
+    let game_fixture = include_str!("../fixtures/late_stage.json");
+    let g: Result<DEGame, _> = serde_json::from_slice(game_fixture.as_bytes());
+    let g = g.expect("the json literal is valid");
+    let snake_id_mapping = build_snake_id_map(&g);
+    let compact: StandardCellBoard4Snakes11x11 = g.as_cell_board(&snake_id_mapping).unwrap();
+    let mut rng = SmallRng::seed_from_u64(0);
+    for (name, level) in [
+        (
+            "compact late stage - basic random moves",
+            ReasonableMoveLevel::Basic,
+        ),
+        (
+            "compact late stage - space aware random moves",
+            ReasonableMoveLevel::SpaceAware,
+        ),
+    ] {
+        c.bench_function(name, |b| {
+            b.iter(|| {
+                black_box(&compact)
+                    .random_reasonable_move_at_level_for_each_snake(&mut rng, level)
+                    .for_each(|_| {})
+            })
+        });
+    }
+}
+
+fn late_stage_voronoi(c: &mut Criterion) {
+    let game_fixture = include_str!("../fixtures/late_stage.json");
+    let g: Result<DEGame, _> = serde_json::from_slice(game_fixture.as_bytes());
+    let g = g.expect("the json literal is valid");
+    let snake_id_mapping = build_snake_id_map(&g);
+    let compact: StandardCellBoard4Snakes11x11 =
+        g.clone().as_cell_board(&snake_id_mapping).unwrap();
+    c.bench_function("compact late stage voronoi", |b| {
+        b.iter(|| black_box(&compact).voronoi(VoronoiOptions::default()))
+    });
+    c.bench_function("vec late stage voronoi", |b| {
+        b.iter(|| black_box(&g).voronoi(VoronoiOptions::default()))
+    });
+}
+
+fn late_stage_conversion(c: &mut Criterion) {
+    let game_fixture = include_str!("../fixtures/late_stage.json");
+    let g: Result<DEGame, _> = serde_json::from_slice(game_fixture.as_bytes());
+    let g = g.expect("the json literal is valid");
+    let snake_id_mapping = build_snake_id_map(&g);
+    c.bench_function("late stage wire to compact conversion", |b| {
+        b.iter(|| {
+            let compact: StandardCellBoard4Snakes11x11 =
+                black_box(&g).as_cell_board(&snake_id_mapping).unwrap();
+            compact
+        })
+    });
+}
+
 criterion_group!(
     benches,
     bench_compact_repr_start_of_game,
     bench_compact_repr_start_of_game_full,
     late_stage_compact_repr,
+    late_stage_random_reasonable_moves,
+    late_stage_voronoi,
+    late_stage_conversion,
 );
 criterion_main!(benches);
@@ -0,0 +1,240 @@
+use crate::{
+    types::{try_build_snake_id_map, SnakeId},
+    wire_representation::{Game, Position, Settings},
+    Error,
+};
+
+use super::{dimensions::Dimensions, CellNum, StandardCellBoard, WrappedCellBoard};
+
+/// Builds a compact board from parts instead of from json. Snakes get ids in the order they are
+/// added, so the first snake added is you and gets `SnakeId(0)`. Everything added is checked in
+/// the same way as [crate::wire_representation::Board::add_snake] and friends
+#[derive(Debug, Clone)]
+pub struct CellBoardBuilder {
+    game: Game,
+}
+
+impl CellBoardBuilder {
+    /// an empty board of the given size
+    pub fn new(width: i32, height: i32) -> Self {
+        Self {
+            game: Game::empty(width, height),
+        }
+    }
+
+    /// sets how much damage hazards do each turn, the default is 15. Negative damage heals
+    pub fn hazard_damage(&mut self, damage: i16) -> &mut Self {
+        let settings = self.game.game.ruleset.settings.get_or_insert(Settings {
+            food_spawn_chance: 0,
+            minimum_food: 0,
+            hazard_damage_per_turn: 0,
+            hazard_map: None,
+            hazard_map_author: None,
+            royale: None,
+            squad: None,
+        });
+        settings.hazard_damage_per_turn = damage as i32;
+        self
+    }
+
+    /// Adds a snake with the given body, starting at the head, and returns the id it will have
+    /// on the built board
+    pub fn add_snake(&mut self, body: Vec<Position>, health: i32) -> Result<SnakeId, Error> {
+        let sid = SnakeId(self.game.board.snakes.len() as u8);
+        let id = sid.0.to_string();
+        if sid.0 == 0 {
+            self.game.add_you(id, body, health)?;
+        } else {
+            self.game.board.add_snake(id, body, health)?;
+        }
+        Ok(sid)
+    }
+
+    /// adds a food, see [crate::wire_representation::Board::add_food]
+    pub fn add_food(&mut self, position: Position) -> Result<&mut Self, Error> {
+        self.game.board.add_food(position)?;
+        Ok(self)
+    }
+
+    /// adds a hazard, see [crate::wire_representation::Board::add_hazard]
+    pub fn add_hazard(&mut self, position: Position) -> Result<&mut Self, Error> {
+        self.game.board.add_hazard(position)?;
+        Ok(self)
+    }
+
+    /// adds a wall, see [crate::wire_representation::Board::add_wall]
+    pub fn add_wall(&mut self, position: Position) -> Result<&mut Self, Error> {
+        self.game.board.add_wall(position)?;
+        Ok(self)
+    }
+
+    /// the wire game for everything added so far
+    pub fn game(&self) -> &Game {
+        &self.game
+    }
+
+    /// Builds a standard board, errors if the board or number of snakes doesn't fit
+    pub fn build<T: CellNum, D: Dimensions, const BOARD_SIZE: usize, const MAX_SNAKES: usize>(
+        &self,
+    ) -> Result<StandardCellBoard<T, D, BOARD_SIZE, MAX_SNAKES>, Error> {
+        self.check_has_snakes()?;
+        self.game
+            .as_cell_board(&try_build_snake_id_map(&self.game)?)
+    }
+
+    /// Builds a wrapped board, errors if the board or number of snakes doesn't fit
+    pub fn build_wrapped<
+        T: CellNum,
+        D: Dimensions,
+        const BOARD_SIZE: usize,
+        const MAX_SNAKES: usize,
+    >(
+        &self,
+    ) -> Result<WrappedCellBoard<T, D, BOARD_SIZE, MAX_SNAKES>, Error> {
+        self.check_has_snakes()?;
+        let mut game = self.game.clone();
+        game.game.ruleset.name = "wrapped".to_string();
+        let snake_ids = try_build_snake_id_map(&game)?;
+        WrappedCellBoard::convert_from_game(game, &snake_ids)
+    }
+
+    fn check_has_snakes(&self) -> Result<(), Error> {
+        if self.game.board.snakes.is_empty() {
+            return Err(Error::NoSnakes);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use itertools::Itertools;
+
+    use super::*;
+    use crate::{
+        compact_representation::{dimensions::Square, CellIndex, StandardCellBoard4Snakes11x11},
+        types::{
+            FoodGettableGame, HazardQueryableGame, HeadGettableGame, HealthGettableGame,
+            LengthGettableGame, Move, SimulableGame, SimulatorInstruments, VictorDeterminableGame,
+            WallQueryableGame,
+        },
+    };
+
+    #[derive(Debug)]
+    struct Instruments;
+    impl SimulatorInstruments for Instruments {
+        fn observe_simulation(&self, _: std::time::Duration) {}
+    }
+
+    fn duel() -> CellBoardBuilder {
+        let mut builder = CellBoardBuilder::new(11, 11);
+        let you = builder
+            .add_snake(
+                vec![
+                    Position::new(1, 5),
+                    Position::new(1, 4),
+                    Position::new(1, 3),
+                ],
+                100,
+            )
+            .unwrap();
+        let opponent = builder
+            .add_snake(
+                vec![
+                    Position::new(9, 5),
+                    Position::new(9, 6),
+                    Position::new(9, 7),
+                ],
+                100,
+            )
+            .unwrap();
+        assert_eq!((you, opponent), (SnakeId(0), SnakeId(1)));
+        builder
+            .add_food(Position::new(2, 5))
+            .unwrap()
+            .add_hazard(Position::new(9, 4))
+            .unwrap()
+            .hazard_damage(10);
+        builder
+    }
+
+    #[test]
+    fn test_build_duel_and_simulate() {
+        let board: StandardCellBoard4Snakes11x11 = duel().build().unwrap();
+        assert_eq!(
+            board.get_head_as_native_position(&SnakeId(0)),
+            CellIndex::new(Position::new(1, 5), 11)
+        );
+        assert!(board.is_hazard(&CellIndex::new(Position::new(9, 4), 11)));
+
+        let moves = [
+            (SnakeId(0), [Move::Right].as_slice()),
+            (SnakeId(1), [Move::Down].as_slice()),
+        ];
+        let results = board.simulate_with_moves(&Instruments, moves).collect_vec();
+        assert_eq!(results.len(), 1);
+        let next = results[0].1;
+        assert_eq!(next.get_health(&SnakeId(0)), 100);
+        assert_eq!(next.get_length(&SnakeId(0)), 4);
+        assert_eq!(next.get_health(&SnakeId(1)), 89);
+        assert!(next.get_all_food_as_positions().is_empty());
+        assert!(!next.is_over());
+    }
+
+    #[test]
+    fn test_walls() {
+        let mut builder = duel();
+        builder.add_wall(Position::new(1, 6)).unwrap();
+        assert!(matches!(
+            builder.add_wall(Position::new(2, 5)),
+            Err(Error::InvalidWall(_))
+        ));
+        assert!(matches!(
+            builder.add_food(Position::new(1, 6)),
+            Err(Error::InvalidFood(_))
+        ));
+        let board: StandardCellBoard4Snakes11x11 = builder.build().unwrap();
+        assert!(board.is_wall(&CellIndex::new(Position::new(1, 6), 11)));
+
+        let moves = [
+            (SnakeId(0), [Move::Up].as_slice()),
+            (SnakeId(1), [Move::Down].as_slice()),
+        ];
+        let (_, next) = board
+            .simulate_with_moves(&Instruments, moves)
+            .next()
+            .unwrap();
+        assert_eq!(next.get_health(&SnakeId(0)), 0);
+        assert!(next.is_over());
+    }
+
+    #[test]
+    fn test_build_wrapped() {
+        let board = duel()
+            .build_wrapped::<u8, Square, { 11 * 11 }, 4>()
+            .unwrap();
+        assert!(board.assert_consistency());
+    }
+
+    #[test]
+    fn test_build_errors() {
+        assert!(matches!(
+            CellBoardBuilder::new(11, 11).build::<u8, Square, { 11 * 11 }, 4>(),
+            Err(Error::NoSnakes)
+        ));
+        assert!(CellBoardBuilder::new(11, 11)
+            .add_food(Position::new(11, 0))
+            .is_err());
+
+        let mut builder = duel();
+        assert!(builder.add_snake(vec![Position::new(1, 3)], 100).is_err());
+        assert!(matches!(
+            builder.build::<u8, Square, { 7 * 7 }, 4>(),
+            Err(Error::BoardDoesNotFit { .. })
+        ));
+        assert!(matches!(
+            builder.build::<u8, Square, { 11 * 11 }, 1>(),
+            Err(Error::TooManySnakes(2))
+        ));
+    }
+}
@@ -0,0 +1,243 @@
+//! A fixed size set of cells, stored one bit per cell, for whole board operations like flood fills
+
+use super::CellIndex;
+use super::CellNum as CN;
+use super::EvaluateMode;
+
+/// enough words for the largest (50x50) board we provide
+const MAX_WORDS: usize = 40;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct BitBoard<const BOARD_SIZE: usize> {
+    words: [u64; MAX_WORDS],
+}
+
+impl<const BOARD_SIZE: usize> BitBoard<BOARD_SIZE> {
+    /// only the first WORDS words are ever used, the rest are always zero
+    const WORDS: usize = BOARD_SIZE.div_ceil(64);
+
+    pub fn empty() -> Self {
+        debug_assert!(Self::WORDS <= MAX_WORDS);
+        Self {
+            words: [0; MAX_WORDS],
+        }
+    }
+
+    /// a bitboard with the first `n` cells set
+    pub fn first_n(n: usize) -> Self {
+        let mut bb = Self::empty();
+        for i in 0..n / 64 {
+            bb.words[i] = u64::MAX;
+        }
+        if !n.is_multiple_of(64) {
+            bb.words[n / 64] = (1 << (n % 64)) - 1;
+        }
+        bb
+    }
+
+    pub fn set<T: CN>(&mut self, idx: CellIndex<T>) {
+        let i = idx.as_usize();
+        self.words[i / 64] |= 1 << (i % 64);
+    }
+
+    pub fn get<T: CN>(&self, idx: CellIndex<T>) -> bool {
+        let i = idx.as_usize();
+        self.words[i / 64] & (1 << (i % 64)) != 0
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.words[..Self::WORDS].iter().all(|w| *w == 0)
+    }
+
+    pub fn count(&self) -> usize {
+        self.words[..Self::WORDS]
+            .iter()
+            .map(|w| w.count_ones() as usize)
+            .sum()
+    }
+
+    pub fn and(mut self, other: &Self) -> Self {
+        for i in 0..Self::WORDS {
+            self.words[i] &= other.words[i];
+        }
+        self
+    }
+
+    pub fn and_not(mut self, other: &Self) -> Self {
+        for i in 0..Self::WORDS {
+            self.words[i] &= !other.words[i];
+        }
+        self
+    }
+
+    pub fn or_assign(&mut self, other: &Self) {
+        for i in 0..Self::WORDS {
+            self.words[i] |= other.words[i];
+        }
+    }
+
+    /// moves every cell `n` indices up
+    pub fn shl(&self, n: usize) -> Self {
+        let mut out = Self::empty();
+        let (word_shift, bit_shift) = (n / 64, n % 64);
+        for i in (word_shift..Self::WORDS).rev() {
+            out.words[i] = self.words[i - word_shift] << bit_shift;
+            if bit_shift != 0 && i > word_shift {
+                out.words[i] |= self.words[i - word_shift - 1] >> (64 - bit_shift);
+            }
+        }
+        out
+    }
+
+    /// moves every cell `n` indices down, dropping cells that go below zero
+    pub fn shr(&self, n: usize) -> Self {
+        let mut out = Self::empty();
+        let (word_shift, bit_shift) = (n / 64, n % 64);
+        for i in 0..Self::WORDS.saturating_sub(word_shift) {
+            out.words[i] = self.words[i + word_shift] >> bit_shift;
+            if bit_shift != 0 && i + word_shift + 1 < Self::WORDS {
+                out.words[i] |= self.words[i + word_shift + 1] << (64 - bit_shift);
+            }
+        }
+        out
+    }
+
+    /// iterates over the set cells in ascending order
+    pub fn iter<T: CN>(&self) -> impl Iterator<Item = CellIndex<T>> + '_ {
+        self.words[..Self::WORDS]
+            .iter()
+            .enumerate()
+            .flat_map(|(word_idx, word)| {
+                let mut word = *word;
+                core::iter::from_fn(move || {
+                    if word == 0 {
+                        None
+                    } else {
+                        let bit = word.trailing_zeros() as usize;
+                        word &= word - 1;
+                        Some(CellIndex::from_usize(word_idx * 64 + bit))
+                    }
+                })
+            })
+    }
+}
+
+/// precomputed edges of a board, used to move a whole bitboard one step in every direction at
+/// once without pieces spilling from one row in to the next
+#[derive(Debug, Copy, Clone)]
+pub struct BoardMasks<const BOARD_SIZE: usize> {
+    width: usize,
+    height: usize,
+    board: BitBoard<BOARD_SIZE>,
+    left_column: BitBoard<BOARD_SIZE>,
+    right_column: BitBoard<BOARD_SIZE>,
+    bottom_row: BitBoard<BOARD_SIZE>,
+    top_row: BitBoard<BOARD_SIZE>,
+}
+
+impl<const BOARD_SIZE: usize> BoardMasks<BOARD_SIZE> {
+    pub fn new(width: u8, height: u8) -> Self {
+        let (width, height) = (width as usize, height as usize);
+        let mut left_column = BitBoard::empty();
+        let mut right_column = BitBoard::empty();
+        for y in 0..height {
+            left_column.set(CellIndex::<u16>::from_usize(y * width));
+            right_column.set(CellIndex::<u16>::from_usize(y * width + width - 1));
+        }
+        let bottom_row = BitBoard::first_n(width);
+
+        Self {
+            width,
+            height,
+            board: BitBoard::first_n(width * height),
+            left_column,
+            right_column,
+            bottom_row,
+            top_row: bottom_row.shl(width * (height - 1)),
+        }
+    }
+
+    /// the cells one step away from any cell in `cells`, wrapping around the edges of the board
+    /// in `EvaluateMode::Wrapped`
+    pub fn neighbors(
+        &self,
+        cells: &BitBoard<BOARD_SIZE>,
+        mode: EvaluateMode,
+    ) -> BitBoard<BOARD_SIZE> {
+        let mut out = cells.and_not(&self.right_column).shl(1);
+        out.or_assign(&cells.and_not(&self.left_column).shr(1));
+        out.or_assign(&cells.shl(self.width));
+        out.or_assign(&cells.shr(self.width));
+
+        if let EvaluateMode::Wrapped = mode {
+            out.or_assign(&cells.and(&self.right_column).shr(self.width - 1));
+            out.or_assign(&cells.and(&self.left_column).shl(self.width - 1));
+            out.or_assign(&cells.and(&self.top_row).shr(self.width * (self.height - 1)));
+            out.or_assign(
+                &cells
+                    .and(&self.bottom_row)
+                    .shl(self.width * (self.height - 1)),
+            );
+        }
+
+        out.and(&self.board)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use itertools::Itertools;
+    use proptest::prelude::*;
+
+    use super::*;
+    use crate::{
+        compact_representation::dimensions::Custom,
+        strategies::{cell_board, wrapped_cell_board},
+        types::{NeighborDeterminableGame, SnakeBodyGettableGame, SnakeIDGettableGame},
+    };
+
+    /// the bitboard neighbors of every body piece, and the neighbors found one cell at a time
+    fn neighbors_both_ways<G>(board: &G, mode: EvaluateMode) -> (Vec<usize>, Vec<usize>)
+    where
+        G: NeighborDeterminableGame<NativePositionType = CellIndex<u8>>
+            + SnakeBodyGettableGame
+            + SnakeIDGettableGame,
+    {
+        let cells = board
+            .get_snake_ids()
+            .iter()
+            .flat_map(|sid| board.get_snake_body_vec(sid))
+            .collect_vec();
+        let mut bitboard = BitBoard::<{ 11 * 11 }>::empty();
+        for cell in &cells {
+            bitboard.set(*cell);
+        }
+        let masks = BoardMasks::new(9, 6);
+        let from_masks = masks
+            .neighbors(&bitboard, mode)
+            .iter::<u8>()
+            .map(|ci| ci.as_usize())
+            .collect_vec();
+        let one_at_a_time = cells
+            .iter()
+            .flat_map(|cell| board.neighbors(cell).collect_vec())
+            .map(|ci| ci.as_usize())
+            .sorted()
+            .dedup()
+            .collect_vec();
+        (from_masks, one_at_a_time)
+    }
+
+    proptest! {
+        #[test]
+        fn test_neighbors_match_single_cell_neighbors(
+            board in cell_board::<u8, Custom, { 11 * 11 }, 4>(9, 6, 1..=4),
+            wrapped in wrapped_cell_board::<u8, Custom, { 11 * 11 }, 4>(9, 6, 1..=4),
+        ) {
+            let (from_masks, one_at_a_time) = neighbors_both_ways(&board, EvaluateMode::Standard);
+            prop_assert_eq!(from_masks, one_at_a_time);
+            let (from_masks, one_at_a_time) = neighbors_both_ways(&wrapped, EvaluateMode::Wrapped);
+            prop_assert_eq!(from_masks, one_at_a_time);
+        }
+    }
+}
@@ -0,0 +1,148 @@
+use alloc::collections::BinaryHeap;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::cmp::Reverse;
+
+use crate::{
+    compact_representation::{
+        core::{dimensions::Dimensions, CellCoord},
+        CellNum,
+    },
+    types::{AStarOptions, Move},
+};
+
+use super::{CellBoard, CellIndex, EvaluateMode};
+
+impl<T: CellNum, D: Dimensions, const BOARD_SIZE: usize, const MAX_SNAKES: usize>
+    CellBoard<T, D, BOARD_SIZE, MAX_SNAKES>
+{
+    /// the cheapest sequence of moves from `from` to `to`, see [crate::types::PathfindableGame]
+    pub fn a_star(
+        &self,
+        from: CellIndex<T>,
+        to: CellIndex<T>,
+        options: AStarOptions,
+        mode: EvaluateMode,
+    ) -> Option<Vec<Move>> {
+        let (costs, came_from) = self.path_search(from, Some(to), options, mode);
+        if costs[to.as_usize()] == u32::MAX {
+            return None;
+        }
+
+        let width = self.get_actual_width();
+        let mut path = vec![];
+        let mut current = to.into_coord(width);
+        while let Some((previous, mv)) =
+            came_from[CellIndex::<T>::from_coord(current, width).as_usize()]
+        {
+            path.push(mv);
+            current = previous;
+        }
+        path.reverse();
+        Some(path)
+    }
+
+    /// the cost of the cheapest path from `from` to every cell, indexed by cell, counting
+    /// `hazard_cost` for every hazard on the way. Cells that can't be reached are None
+    pub fn cost_map(
+        &self,
+        from: CellIndex<T>,
+        options: AStarOptions,
+        mode: EvaluateMode,
+    ) -> [Option<u32>; BOARD_SIZE] {
+        let (costs, _) = self.path_search(from, None, options, mode);
+        let mut distances = [None; BOARD_SIZE];
+        for (distance, cost) in distances.iter_mut().zip(costs.iter()) {
+            if *cost != u32::MAX {
+                *distance = Some(*cost);
+            }
+        }
+        distances
+    }
+
+    /// searches outwards from `from` in order of path cost, returning the cheapest cost to each
+    /// cell (u32::MAX if unreachable) and the move used to get there. With a target this is A*
+    /// and stops once the target is reached, without one it is Dijkstra over the whole board.
+    /// The search works on [CellCoord]s, so neither the heuristic nor the neighbours of a cell
+    /// need dividing by the width
+    #[allow(clippy::type_complexity)]
+    fn path_search(
+        &self,
+        from: CellIndex<T>,
+        to: Option<CellIndex<T>>,
+        options: AStarOptions,
+        mode: EvaluateMode,
+    ) -> ([u32; BOARD_SIZE], [Option<(CellCoord, Move)>; BOARD_SIZE]) {
+        let width = self.get_actual_width();
+        let index = |coord: CellCoord| CellIndex::<T>::from_coord(coord, width).as_usize();
+        let from = from.into_coord(width);
+        let to = to.map(|to| to.into_coord(width));
+        let heuristic = |coord: CellCoord| match to {
+            Some(to) => self.path_heuristic(coord, to, mode),
+            None => 0,
+        };
+        let vacated = self.turns_until_vacated();
+        let mut costs = [u32::MAX; BOARD_SIZE];
+        let mut steps = [0u16; BOARD_SIZE];
+        let mut came_from = [None; BOARD_SIZE];
+        let mut open = BinaryHeap::new();
+
+        costs[index(from)] = 0;
+        open.push(Reverse((heuristic(from), 0, from)));
+
+        while let Some(Reverse((_, cost, current))) = open.pop() {
+            if Some(current) == to {
+                break;
+            }
+            if cost > costs[index(current)] {
+                // a cheaper path here was already expanded
+                continue;
+            }
+
+            let next_steps = steps[index(current)] + 1;
+            for mv in Move::all_iter() {
+                let next = match self.coord_in_direction(current, mv, mode) {
+                    Some(next) => next,
+                    None => continue,
+                };
+                let i = index(next);
+                let cell = self.cells[i];
+                let occupied = cell.is_body_segment() || cell.is_head();
+                if occupied && (options.bodies_block || vacated[i] > next_steps) {
+                    continue;
+                }
+                let next_cost = if cell.is_hazard() {
+                    if options.hazards_block {
+                        continue;
+                    }
+                    cost + 1 + options.hazard_cost
+                } else {
+                    cost + 1
+                };
+
+                if next_cost < costs[i] {
+                    costs[i] = next_cost;
+                    steps[i] = next_steps;
+                    came_from[i] = Some((current, mv));
+                    open.push(Reverse((next_cost + heuristic(next), next_cost, next)));
+                }
+            }
+        }
+
+        (costs, came_from)
+    }
+
+    /// manhattan distance between two cells, going around the edges of the board in
+    /// `EvaluateMode::Wrapped`
+    fn path_heuristic(&self, from: CellCoord, to: CellCoord, mode: EvaluateMode) -> u32 {
+        let width = self.get_actual_width();
+        let dx = from.x.abs_diff(to.x) as u32;
+        let dy = from.y.abs_diff(to.y) as u32;
+        match mode {
+            EvaluateMode::Standard => dx + dy,
+            EvaluateMode::Wrapped => {
+                dx.min(width as u32 - dx) + dy.min(self.get_actual_height() as u32 - dy)
+            }
+        }
+    }
+}
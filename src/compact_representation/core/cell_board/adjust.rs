@@ -0,0 +1,125 @@
+use alloc::format;
+use alloc::string::ToString;
+
+use itertools::Itertools;
+
+use crate::{
+    compact_representation::{core::dimensions::Dimensions, CellNum},
+    types::{Move, SnakeBodyGettableGame, SnakeId},
+    Error,
+};
+
+use super::{CellBoard, CellIndex, EvaluateMode, DOUBLE_STACK, TRIPLE_STACK};
+
+impl<T: CellNum, D: Dimensions, const BOARD_SIZE: usize, const MAX_SNAKES: usize>
+    CellBoard<T, D, BOARD_SIZE, MAX_SNAKES>
+{
+    /// A copy of this board where `snake_id` has `health`, health 0 eliminates the snake.
+    /// Snakes that have already been eliminated are left as they are
+    pub fn with_health(&self, snake_id: SnakeId, health: u8) -> Self {
+        let mut board = *self;
+        if !self.is_alive(snake_id) {
+            return board;
+        }
+        if health == 0 {
+            board.kill_and_remove(snake_id);
+        } else {
+            board.healths[snake_id.as_usize()] = health;
+        }
+        board
+    }
+
+    /// A copy of this board where `snake_id` is `delta` pieces longer, or shorter if `delta` is
+    /// negative. Shorter snakes lose pieces from the tail. Longer snakes grow straight out
+    /// from their tail, the way the tail was last moving, and stay stacked if they had just
+    /// eaten. Errors if the snake would grow off the board or on to anything but an empty
+    /// square, or would be left with less than 2 pieces
+    pub fn with_length_adjusted(
+        &self,
+        snake_id: SnakeId,
+        delta: i32,
+        mode: EvaluateMode,
+    ) -> Result<Self, Error> {
+        if !self.is_alive(snake_id) {
+            return Err(Error::UnknownSnake(format!("{:?}", snake_id)));
+        }
+        if delta == 0 {
+            return Ok(*self);
+        }
+        let invalid = |reason: &str| Error::InvalidSnake {
+            id: snake_id.0.to_string(),
+            reason: reason.to_string(),
+        };
+        let body = self.get_snake_body_vec(&snake_id);
+        let squares = body.iter().copied().dedup().collect_vec();
+
+        let new_body = if delta < 0 {
+            let length = body.len().saturating_sub(delta.unsigned_abs() as usize);
+            let new_body = body[..length].to_vec();
+            if length < 2 || new_body.iter().all(|c| *c == new_body[0]) {
+                return Err(invalid("would be left with less than 2 pieces"));
+            }
+            new_body
+        } else {
+            let (tail, before) = match squares.as_slice() {
+                [.., before, tail] => (*tail, *before),
+                _ => return Err(invalid("has no tail to grow from")),
+            };
+            let direction = Move::all_iter()
+                .find(|mv| self.cell_in_direction(before, *mv, mode) == Some(tail))
+                .expect("consecutive pieces are adjacent");
+
+            let mut grown = squares.clone();
+            for _ in 0..delta {
+                let next = self
+                    .cell_in_direction(*grown.last().unwrap(), direction, mode)
+                    .ok_or_else(|| invalid("would grow off the board"))?;
+                if !self.get_cell(next).is_empty() || grown.contains(&next) {
+                    return Err(invalid("would grow on to a square that isn't empty"));
+                }
+                grown.push(next);
+            }
+            let stacked = body.len() - squares.len();
+            let new_tail = *grown.last().unwrap();
+            grown.extend(core::iter::repeat_n(new_tail, stacked));
+            grown
+        };
+
+        let mut board = *self;
+        for square in squares {
+            board.cell_remove(square);
+        }
+        board.lay_out_body(snake_id, &new_body);
+        board.lengths[snake_id.as_usize()] = new_body.len() as u16;
+        Ok(board)
+    }
+
+    fn is_alive(&self, snake_id: SnakeId) -> bool {
+        snake_id.as_usize() < MAX_SNAKES && self.healths[snake_id.as_usize()] > 0
+    }
+
+    /// writes a body on to empty squares, head first, with stacked pieces as repeated squares.
+    /// Only the tail is ever stacked, or the head at the start of the game when every piece is
+    /// on it, and pieces past what its cell can count go in `extra_tail_pieces`
+    pub(super) fn lay_out_body(&mut self, snake_id: SnakeId, body: &[CellIndex<T>]) {
+        let head = body[0];
+        let tail = *body.last().unwrap();
+        let mut next_index = head;
+        let mut extra = 0;
+        for (count, square) in body.iter().copied().dedup_with_count() {
+            if square == head && square == tail && count >= TRIPLE_STACK {
+                self.cells[square.as_usize()].set_triple_stacked(snake_id);
+                extra = count - TRIPLE_STACK;
+            } else if square == head {
+                self.set_cell_head(square, snake_id, tail);
+            } else if count >= DOUBLE_STACK {
+                self.set_cell_double_stacked(square, snake_id, next_index);
+                extra = count - DOUBLE_STACK;
+            } else {
+                self.set_cell_body_piece(square, snake_id, next_index);
+            }
+            next_index = square;
+        }
+        self.extra_tail_pieces[snake_id.as_usize()] = extra as u16;
+    }
+}
@@ -0,0 +1,372 @@
+//! a small binary format for boards, for storing lots of positions. Version 1 is laid out as
+//! follows, with every u16 and i16 little endian:
+//!
+//! - the format version, a u8
+//! - the width and height, a u8 each
+//! - flags, a u8: bit 0 for the wrapped ruleset, bit 1 if food restores health, bit 2 if
+//!   starvation is enabled, bit 3 if turns are resolved by the v1.1.x rules, bit 4 if every
+//!   snake grows every turn
+//! - the max health, a u8, then the hazard damage, an i16
+//! - the number of snake slots, a u8. Eliminated snakes after the last living snake are left out
+//! - for each slot, its health as a u8. Eliminated snakes have 0 health and nothing else, living
+//!   snakes are followed by their length and the index of their head as u16s, how many more
+//!   pieces they have than squares as a u8, then a direction for each square after the head. The
+//!   directions are 2 bits each, up, down, left and right from 0 to 3, packed 4 to a byte
+//!   starting from the low bits
+//! - what is on every square in order, as runs of squares with the same contents. Each run is
+//!   its length as a u8, at least 1, then a u8 with bit 7 set for food, bit 6 for a wall and the
+//!   hazard count in bits 0 to 2
+
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec;
+use alloc::vec::Vec;
+
+use itertools::Itertools;
+use serde::de::{self, SeqAccess, Visitor};
+
+use crate::{
+    compact_representation::{core::dimensions::Dimensions, CellNum, MAX_HAZARD_STACK},
+    types::{Move, RulesVersion, SimulationSettings, SnakeBodyGettableGame, SnakeId},
+    Error,
+};
+
+use super::{Cell, CellBoard, CellIndex, EvaluateMode};
+
+/// bumped whenever the layout changes, buffers with any other version are refused
+const FORMAT_VERSION: u8 = 1;
+
+const WRAPPED: u8 = 1;
+const FOOD_RESTORES_HEALTH: u8 = 1 << 1;
+const STARVATION_ENABLED: u8 = 1 << 2;
+const RULES_V1_1_X: u8 = 1 << 3;
+const ALWAYS_GROW: u8 = 1 << 4;
+
+const FOOD_ON_SQUARE: u8 = 1 << 7;
+const WALL_ON_SQUARE: u8 = 1 << 6;
+
+/// the order directions are written in, fixed here rather than taken from [Move::all] so the
+/// format doesn't change if that does
+const DIRECTIONS: [Move; 4] = [Move::Up, Move::Down, Move::Left, Move::Right];
+
+fn invalid(reason: impl Into<String>) -> Error {
+    Error::InvalidBytes(reason.into())
+}
+
+fn push_u16(bytes: &mut Vec<u8>, value: u16) {
+    bytes.extend_from_slice(&value.to_le_bytes());
+}
+
+struct Reader<'a>(&'a [u8]);
+
+impl Reader<'_> {
+    fn u8(&mut self) -> Result<u8, Error> {
+        let (first, rest) = self
+            .0
+            .split_first()
+            .ok_or_else(|| invalid("the buffer ends early"))?;
+        self.0 = rest;
+        Ok(*first)
+    }
+
+    fn u16(&mut self) -> Result<u16, Error> {
+        Ok(u16::from_le_bytes([self.u8()?, self.u8()?]))
+    }
+
+    fn i16(&mut self) -> Result<i16, Error> {
+        Ok(i16::from_le_bytes([self.u8()?, self.u8()?]))
+    }
+}
+
+impl<T: CellNum, D: Dimensions, const BOARD_SIZE: usize, const MAX_SNAKES: usize>
+    CellBoard<T, D, BOARD_SIZE, MAX_SNAKES>
+{
+    /// this board in the format described in the module docs, `mode` is the ruleset the board
+    /// is being played with
+    // boards are big enough that copying them to call this isn't free
+    #[allow(clippy::wrong_self_convention)]
+    pub fn to_bytes(&self, mode: EvaluateMode) -> Vec<u8> {
+        let mut flags = 0;
+        if mode == EvaluateMode::Wrapped {
+            flags |= WRAPPED;
+        }
+        if self.settings.food_restores_health {
+            flags |= FOOD_RESTORES_HEALTH;
+        }
+        if self.settings.starvation_enabled {
+            flags |= STARVATION_ENABLED;
+        }
+        if self.settings.rules_version == RulesVersion::V1_1_x {
+            flags |= RULES_V1_1_X;
+        }
+        if self.settings.always_grow {
+            flags |= ALWAYS_GROW;
+        }
+        let mut bytes = vec![
+            FORMAT_VERSION,
+            self.get_actual_width(),
+            self.get_actual_height(),
+            flags,
+            self.settings.max_health,
+        ];
+        bytes.extend_from_slice(&self.settings.hazard_damage.to_le_bytes());
+
+        let slots = (0..MAX_SNAKES)
+            .rev()
+            .find(|i| self.healths[*i] > 0)
+            .map_or(0, |i| i + 1);
+        bytes.push(slots as u8);
+        for (i, health) in self.healths.iter().copied().enumerate().take(slots) {
+            bytes.push(health);
+            if health == 0 {
+                continue;
+            }
+            // every stacked piece is on the tail, so the length and the squares say how deep the
+            // stack is
+            let length = self.lengths[i];
+            let squares = self
+                .get_snake_body_vec(&SnakeId(i as u8))
+                .into_iter()
+                .dedup()
+                .collect_vec();
+            push_u16(&mut bytes, length);
+            push_u16(&mut bytes, squares[0].as_usize() as u16);
+            bytes.push((length as usize - squares.len()) as u8);
+
+            let directions = squares.iter().tuple_windows().map(|(from, to)| {
+                DIRECTIONS
+                    .iter()
+                    .position(|mv| self.cell_in_direction(*from, *mv, mode) == Some(*to))
+                    .expect("consecutive pieces are adjacent") as u8
+            });
+            for chunk in &directions.chunks(4) {
+                let packed = chunk
+                    .enumerate()
+                    .fold(0, |packed, (n, direction)| packed | direction << (2 * n));
+                bytes.push(packed);
+            }
+        }
+
+        let area = self.get_actual_width() as usize * self.get_actual_height() as usize;
+        let contents = self.cells[..area].iter().map(|cell| {
+            let food = if cell.is_food() { FOOD_ON_SQUARE } else { 0 };
+            let wall = if cell.is_wall() { WALL_ON_SQUARE } else { 0 };
+            food | wall | cell.hazard_count()
+        });
+        for (mut run, contents) in contents.dedup_with_count() {
+            while run > 0 {
+                let part = run.min(u8::MAX as usize);
+                bytes.push(part as u8);
+                bytes.push(contents);
+                run -= part;
+            }
+        }
+        bytes
+    }
+
+    /// reads a board written by [CellBoard::to_bytes] with the same `mode`. Errors if the buffer
+    /// was written by another version of the format, doesn't fit in this board type, or has
+    /// been corrupted in a way that would give a board the engine could never send
+    pub fn from_bytes(bytes: &[u8], mode: EvaluateMode) -> Result<Self, Error> {
+        let mut reader = Reader(bytes);
+        let version = reader.u8()?;
+        if version != FORMAT_VERSION {
+            return Err(invalid(format!(
+                "format version {} isn't supported, only {} is",
+                version, FORMAT_VERSION
+            )));
+        }
+
+        let (width, height) = (reader.u8()?, reader.u8()?);
+        if width == 0 || height == 0 {
+            return Err(Error::InvalidDimensions {
+                width: width as i32,
+                height: height as i32,
+            });
+        }
+        let area = width as usize * height as usize;
+        if area > BOARD_SIZE || !D::can_represent(width, height) {
+            return Err(Error::BoardDoesNotFit {
+                width: width as i32,
+                height: height as i32,
+            });
+        }
+
+        let flags = reader.u8()?;
+        let known =
+            WRAPPED | FOOD_RESTORES_HEALTH | STARVATION_ENABLED | RULES_V1_1_X | ALWAYS_GROW;
+        if flags & !known != 0 {
+            return Err(invalid(format!("unknown flags {:#04x}", flags)));
+        }
+        if (flags & WRAPPED != 0) != (mode == EvaluateMode::Wrapped) {
+            return Err(invalid(format!(
+                "the board was written in {:?} mode, not {:?}",
+                if flags & WRAPPED != 0 {
+                    EvaluateMode::Wrapped
+                } else {
+                    EvaluateMode::Standard
+                },
+                mode
+            )));
+        }
+        let settings = SimulationSettings {
+            max_health: reader.u8()?,
+            hazard_damage: reader.i16()?,
+            food_restores_health: flags & FOOD_RESTORES_HEALTH != 0,
+            starvation_enabled: flags & STARVATION_ENABLED != 0,
+            always_grow: flags & ALWAYS_GROW != 0,
+            rules_version: if flags & RULES_V1_1_X != 0 {
+                RulesVersion::V1_1_x
+            } else {
+                RulesVersion::Current
+            },
+        };
+
+        let mut board = CellBoard {
+            settings,
+            cells: [Cell::empty(); BOARD_SIZE],
+            healths: [0; MAX_SNAKES],
+            heads: [CellIndex::from_i32(0); MAX_SNAKES],
+            lengths: [0; MAX_SNAKES],
+            extra_tail_pieces: [0; MAX_SNAKES],
+            dimensions: D::from_dimensions(width, height),
+        };
+
+        let slots = reader.u8()? as usize;
+        if slots > MAX_SNAKES {
+            return Err(Error::TooManySnakes(slots));
+        }
+        let mut taken = vec![false; area];
+        for i in 0..slots {
+            let health = reader.u8()?;
+            if health == 0 {
+                continue;
+            }
+            let snake_id = SnakeId(i as u8);
+            let snake_invalid = |reason: &str| invalid(format!("snake {} {}", i, reason));
+
+            let length = reader.u16()? as usize;
+            let head = reader.u16()? as usize;
+            let stacked = reader.u8()? as usize;
+            let squares = length.saturating_sub(stacked);
+            // only the tail is stacked after eating, and only the head at the start of the game,
+            // when there are at least 3 pieces on it
+            if !(squares >= 2 || (squares == 1 && stacked >= 2)) {
+                return Err(snake_invalid(
+                    "isn't stacked the way the engine stacks snakes",
+                ));
+            }
+            if head >= area || core::mem::replace(&mut taken[head], true) {
+                return Err(snake_invalid("has its head on a square that isn't free"));
+            }
+
+            let mut body = vec![CellIndex::<T>::from_usize(head)];
+            let mut packed = 0;
+            for n in 0..squares - 1 {
+                if n % 4 == 0 {
+                    packed = reader.u8()?;
+                }
+                let direction = DIRECTIONS[(packed >> (2 * (n % 4))) as usize & 0b11];
+                let next = board
+                    .cell_in_direction(*body.last().unwrap(), direction, mode)
+                    .ok_or_else(|| snake_invalid("leaves the board"))?;
+                if core::mem::replace(&mut taken[next.as_usize()], true) {
+                    return Err(snake_invalid("runs in to a square that isn't free"));
+                }
+                body.push(next);
+            }
+            let used = (squares - 1) % 4;
+            if used != 0 && packed >> (2 * used) != 0 {
+                return Err(snake_invalid("has directions after its tail"));
+            }
+            let tail = *body.last().unwrap();
+            body.extend(core::iter::repeat_n(tail, stacked));
+
+            board.lay_out_body(snake_id, &body);
+            board.healths[i] = health;
+            board.heads[i] = body[0];
+            board.lengths[i] = length as u16;
+        }
+
+        let mut index = 0;
+        while index < area {
+            let run = reader.u8()? as usize;
+            let contents = reader.u8()?;
+            if run == 0 || index + run > area {
+                return Err(invalid(format!(
+                    "a run of {} squares from square {} doesn't fit on the board",
+                    run, index
+                )));
+            }
+            if contents & !(FOOD_ON_SQUARE | WALL_ON_SQUARE | MAX_HAZARD_STACK) != 0 {
+                return Err(invalid(format!(
+                    "square {} has unknown contents {:#04x}",
+                    index, contents
+                )));
+            }
+            for cell in &mut board.cells[index..index + run] {
+                if contents & FOOD_ON_SQUARE != 0 {
+                    if !cell.is_empty() {
+                        return Err(invalid(format!("square {} has food under a snake", index)));
+                    }
+                    cell.set_food();
+                }
+                if contents & WALL_ON_SQUARE != 0 {
+                    if !cell.is_empty() {
+                        return Err(invalid(format!(
+                            "square {} has a wall under a snake or food",
+                            index
+                        )));
+                    }
+                    cell.set_wall();
+                }
+                for _ in 0..contents & MAX_HAZARD_STACK {
+                    cell.add_hazard();
+                }
+            }
+            index += run;
+        }
+
+        if !reader.0.is_empty() {
+            return Err(invalid(format!(
+                "{} bytes are left over after the board",
+                reader.0.len()
+            )));
+        }
+        // anything else the engine could never send, like a snake with more than the max health
+        board
+            .check_invariants()
+            .map_err(|violation| invalid(violation.to_string()))?;
+        Ok(board)
+    }
+}
+
+/// reads the bytes of a board from any serde format, whether it stores them as bytes or as a
+/// sequence of numbers the way json does. Together with `serialize_bytes` this is what
+/// `serde_bytes` would do
+#[derive(Debug, Clone, Copy)]
+pub struct BytesVisitor;
+
+impl<'de> Visitor<'de> for BytesVisitor {
+    type Value = Vec<u8>;
+
+    fn expecting(&self, formatter: &mut core::fmt::Formatter) -> core::fmt::Result {
+        formatter.write_str("the bytes of a compact board")
+    }
+
+    fn visit_bytes<E: de::Error>(self, bytes: &[u8]) -> Result<Self::Value, E> {
+        Ok(bytes.to_vec())
+    }
+
+    fn visit_byte_buf<E: de::Error>(self, bytes: Vec<u8>) -> Result<Self::Value, E> {
+        Ok(bytes)
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+        let mut bytes = Vec::with_capacity(seq.size_hint().unwrap_or(0));
+        while let Some(byte) = seq.next_element()? {
+            bytes.push(byte);
+        }
+        Ok(bytes)
+    }
+}
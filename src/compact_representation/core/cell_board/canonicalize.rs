@@ -0,0 +1,65 @@
+use crate::{
+    compact_representation::{core::dimensions::Dimensions, CellNum},
+    types::Symmetry,
+};
+
+use super::{CellBoard, CellIndex};
+
+impl<T: CellNum, D: Dimensions, const BOARD_SIZE: usize, const MAX_SNAKES: usize>
+    CellBoard<T, D, BOARD_SIZE, MAX_SNAKES>
+{
+    /// this board reflected or rotated by `symmetry`, snakes keep their ids. Symmetries that
+    /// swap the axes should only be used on square boards
+    pub fn transformed(&self, symmetry: Symmetry) -> Self {
+        let width = self.get_actual_width();
+        let height = self.get_actual_height();
+        debug_assert!(!symmetry.swaps_axes() || width == height);
+        let transform = |ci: CellIndex<T>| {
+            let pos =
+                symmetry.transform_position(ci.into_position(width), width as u32, height as u32);
+            CellIndex::new(pos, width)
+        };
+
+        let mut board = *self;
+        for idx in 0..(width as usize * height as usize) {
+            let ci = CellIndex::<T>::from_usize(idx);
+            let mut cell = self.get_cell(ci);
+            // heads point at their tail, and body pieces at the next piece towards the head,
+            // other cells don't point anywhere
+            if cell.get_next_index().is_some()
+                || (cell.is_head() && !cell.is_triple_stacked_piece())
+            {
+                cell.set_idx(transform(cell.get_idx()));
+            }
+            board.cells[transform(ci).as_usize()] = cell;
+        }
+        for (head, health) in board.heads.iter_mut().zip(self.healths.iter()) {
+            if *health > 0 {
+                *head = transform(*head);
+            }
+        }
+
+        board
+    }
+
+    /// The same position as this board that sorts first out of all its reflections and
+    /// rotations (only the ones that keep the width and height the same on boards that aren't
+    /// square), and the symmetry that turns this board in to it. Moves found on the canonical
+    /// board can be mapped back with `mv.transformed(symmetry.inverse())`
+    pub fn canonicalize(&self) -> (Self, Symmetry) {
+        let square = self.get_actual_width() == self.get_actual_height();
+        let area = self.get_actual_width() as usize * self.get_actual_height() as usize;
+
+        Symmetry::all()
+            .iter()
+            .copied()
+            .filter(|symmetry| square || !symmetry.swaps_axes())
+            .map(|symmetry| (self.transformed(symmetry), symmetry))
+            .min_by(|(a, _), (b, _)| {
+                let a = a.cells[..area].iter().map(|cell| cell.pack_as_u32());
+                let b = b.cells[..area].iter().map(|cell| cell.pack_as_u32());
+                a.cmp(b)
+            })
+            .unwrap()
+    }
+}
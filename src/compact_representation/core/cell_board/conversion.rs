@@ -0,0 +1,301 @@
+//! converting to and from wire games and the packed hashes fixtures are kept as, which need std
+
+use std::collections::{HashMap, VecDeque};
+
+use itertools::Itertools;
+
+use crate::{
+    compact_representation::{core::dimensions::Dimensions, CellNum},
+    types::{
+        Position, RulesVersion, SimulationSettings, SnakeBodyGettableGame, SnakeIDMap, SnakeId,
+    },
+    wire_representation::{BattleSnake, Game, GameRef, GameView, Settings, TaggedGame},
+    Error,
+};
+
+use super::{Cell, CellBoard, CellIndex, EvaluateMode};
+
+impl<T: CellNum, D: Dimensions, const BOARD_SIZE: usize, const MAX_SNAKES: usize>
+    CellBoard<T, D, BOARD_SIZE, MAX_SNAKES>
+{
+    /// packs this as a hash. Doing this because getting serde to work
+    /// with const generics is hard
+    pub fn pack_as_hash(&self) -> HashMap<String, Vec<u32>> {
+        let mut hash = HashMap::new();
+        // negative damage is packed as its two's complement
+        hash.insert(
+            "hazard_damage".to_string(),
+            vec![self.settings.hazard_damage as u16 as u32],
+        );
+        hash.insert(
+            "max_health".to_string(),
+            vec![self.settings.max_health as u32],
+        );
+        hash.insert(
+            "food_restores_health".to_string(),
+            vec![self.settings.food_restores_health as u32],
+        );
+        hash.insert(
+            "starvation_enabled".to_string(),
+            vec![self.settings.starvation_enabled as u32],
+        );
+        hash.insert(
+            "always_grow".to_string(),
+            vec![self.settings.always_grow as u32],
+        );
+        hash.insert(
+            "rules_version".to_string(),
+            vec![match self.settings.rules_version {
+                RulesVersion::Current => 0,
+                RulesVersion::V1_1_x => 1,
+            }],
+        );
+        hash.insert(
+            "actual_width".to_string(),
+            vec![self.get_actual_width() as u32],
+        );
+        hash.insert(
+            "healths".to_string(),
+            self.healths.iter().map(|x| *x as u32).collect(),
+        );
+        hash.insert(
+            "lengths".to_string(),
+            self.lengths.iter().map(|x| *x as u32).collect(),
+        );
+        hash.insert(
+            "extra_tail_pieces".to_string(),
+            self.extra_tail_pieces.iter().map(|x| *x as u32).collect(),
+        );
+        hash.insert(
+            "heads".to_string(),
+            self.heads.iter().map(|x| x.as_usize() as u32).collect(),
+        );
+        hash.insert(
+            "cells".to_string(),
+            self.cells.iter().map(|x| x.pack_as_u32()).collect(),
+        );
+        hash
+    }
+
+    /// unpacks a packed hash repr back in to a CellBoard
+    pub fn from_packed_hash(hash: &HashMap<String, Vec<u32>>) -> Self {
+        // boards packed before the other settings were added are standard apart from their
+        // hazard damage
+        let defaults = SimulationSettings::default();
+        let flag = |key: &str, default: bool| hash.get(key).map(|v| v[0] != 0).unwrap_or(default);
+        let settings = SimulationSettings {
+            hazard_damage: hash.get("hazard_damage").unwrap()[0] as u16 as i16,
+            max_health: hash
+                .get("max_health")
+                .map(|v| v[0] as u8)
+                .unwrap_or(defaults.max_health),
+            food_restores_health: flag("food_restores_health", defaults.food_restores_health),
+            starvation_enabled: flag("starvation_enabled", defaults.starvation_enabled),
+            always_grow: flag("always_grow", defaults.always_grow),
+            rules_version: match hash.get("rules_version").map(|v| v[0]) {
+                Some(1) => RulesVersion::V1_1_x,
+                _ => RulesVersion::Current,
+            },
+        };
+        let actual_width = hash.get("actual_width").unwrap()[0] as u8;
+        let actual_height = hash
+            .get("actual_height")
+            .map(|h| h[0] as u8)
+            .unwrap_or(actual_width);
+
+        let mut healths = [0; MAX_SNAKES];
+        let healths_iter = hash.get("healths").unwrap().iter().map(|x| *x as u8);
+        for (idx, health) in healths_iter.enumerate() {
+            healths[idx] = health;
+        }
+
+        let mut lengths = [0; MAX_SNAKES];
+        let lengths_iter = hash.get("lengths").unwrap().iter().map(|x| *x as u16);
+        for (idx, length) in lengths_iter.enumerate() {
+            lengths[idx] = length;
+        }
+
+        // boards packed before these were counted don't have any
+        let mut extra_tail_pieces = [0; MAX_SNAKES];
+        let extra_iter = hash.get("extra_tail_pieces").into_iter().flatten();
+        for (idx, extra) in extra_iter.enumerate() {
+            extra_tail_pieces[idx] = *extra as u16;
+        }
+
+        let mut heads = [CellIndex::<T>::from_usize(0); MAX_SNAKES];
+        let heads_iter = hash.get("heads").unwrap().iter().map(|x| *x as usize);
+        for (idx, head) in heads_iter.enumerate() {
+            heads[idx] = CellIndex::<T>::from_usize(head);
+        }
+
+        let mut cells = [Cell::<T>::empty(); BOARD_SIZE];
+        let cells_iter = hash.get("cells").unwrap().iter().copied();
+        for (idx, cell) in cells_iter.enumerate() {
+            cells[idx] = Cell::<T>::from_u32(cell);
+        }
+
+        let dimensions = D::from_dimensions(actual_width, actual_height);
+
+        CellBoard {
+            settings,
+            cells,
+            healths,
+            heads,
+            lengths,
+            extra_tail_pieces,
+            dimensions,
+        }
+    }
+
+    /// Builds a cellboard from a given game, will return an error if the game doesn't match
+    /// the provided BOARD_SIZE or MAX_SNAKES, or if one of its snakes could never be sent by the
+    /// engine. You are encouraged to use `CellBoard4Snakes11x11` for the common game layout
+    pub fn convert_from_game(game: Game, snake_ids: &SnakeIDMap) -> Result<Self, Error> {
+        Self::convert_from_tagged_game(&game.with_snake_ids(snake_ids)?)
+    }
+
+    /// The same as [CellBoard::convert_from_game], for a game which already has its snakes
+    /// tagged with their ids
+    pub fn convert_from_tagged_game(tagged: &TaggedGame) -> Result<Self, Error> {
+        Self::convert_from_view(&tagged.game().view(), tagged.ids())
+    }
+
+    /// The same as [CellBoard::convert_from_game], for a game borrowed from the request body
+    pub fn convert_from_game_ref(game: &GameRef, snake_ids: &SnakeIDMap) -> Result<Self, Error> {
+        let view = game.view();
+        let ids = view.snake_ids(snake_ids)?;
+        Self::convert_from_view(&view, &ids)
+    }
+
+    /// converts the parts of a wire game, given the id of each of its snakes, None for the
+    /// snakes that have been eliminated
+    fn convert_from_view(game: &GameView, ids: &[Option<SnakeId>]) -> Result<Self, Error> {
+        game.validate()?;
+        let (width, height) = (game.width, game.height);
+        if width * height > BOARD_SIZE as i32 || !D::can_represent(width as u8, height as u8) {
+            return Err(Error::BoardDoesNotFit { width, height });
+        }
+
+        if game.snakes.len() > MAX_SNAKES {
+            return Err(Error::TooManySnakes(game.snakes.len()));
+        }
+        let snakes = || {
+            ids.iter()
+                .zip(&game.snakes)
+                .filter_map(|(id, snake)| id.map(|id| (id, snake)))
+        };
+        if let Some((snake_id, _)) = snakes().find(|(sid, _)| sid.as_usize() >= MAX_SNAKES) {
+            return Err(Error::TooManySnakes(snake_id.as_usize() + 1));
+        }
+        let width = width as u8;
+        let height = height as u8;
+
+        let dimensions = D::from_dimensions(width, height);
+        let mut board = CellBoard {
+            cells: [Cell::empty(); BOARD_SIZE],
+            heads: [CellIndex::from_i32(0); MAX_SNAKES],
+            healths: [0; MAX_SNAKES],
+            lengths: [0; MAX_SNAKES],
+            extra_tail_pieces: [0; MAX_SNAKES],
+            dimensions,
+            settings: game.settings,
+        };
+
+        for (snake_id, snake) in snakes() {
+            // the simulator moves a snake's tail up behind its head, which can't be done for
+            // a snake that is a single piece
+            if snake.health > 0 && snake.body.len() == 1 {
+                return Err(Error::InvalidSnake {
+                    id: snake.id.to_string(),
+                    reason: "is a single piece, which compact boards can't simulate".to_string(),
+                });
+            }
+            let body = snake
+                .body
+                .iter()
+                .map(|pos| CellIndex::new(*pos, width))
+                .collect_vec();
+            if !body.is_empty() {
+                board.lay_out_body(snake_id, &body);
+            }
+            board.healths[snake_id.as_usize()] = snake.health as u8;
+            board.heads[snake_id.as_usize()] = CellIndex::new(snake.head, width);
+            board.lengths[snake_id.as_usize()] = body.len() as u16;
+        }
+        let on_board = |p: &&Position| !game.off_board(**p);
+        for hazard in game.hazards.iter().filter(on_board) {
+            board.cells[CellIndex::<T>::new(*hazard, width).as_usize()].add_hazard();
+        }
+        for food in game.food.iter().filter(on_board) {
+            board.cells[CellIndex::<T>::new(*food, width).as_usize()].set_food();
+        }
+        // validating checked that no snake is on a wall, food on one could never be eaten
+        for wall in game.walls.iter().filter(on_board) {
+            board.cells[CellIndex::<T>::new(*wall, width).as_usize()].set_wall();
+        }
+
+        Ok(board)
+    }
+
+    /// A wire game with the same snakes, food, hazards and walls as this board, played in
+    /// `mode`. The board doesn't know the game it came from, so each snake's id and name are its
+    /// [SnakeId] as a string, you are snake 0 and it is turn 0. Eliminated snakes are left off,
+    /// and if you are one of them `you` has no body
+    #[allow(clippy::wrong_self_convention)]
+    pub fn to_game(&self, mode: EvaluateMode) -> Game {
+        let width = self.get_actual_width();
+        let mut game = Game::empty(width as i32, self.get_actual_height() as i32);
+        if mode == EvaluateMode::Wrapped {
+            game.game.ruleset.name = "wrapped".to_string();
+        }
+        game.game.ruleset.settings = Some(Settings {
+            food_spawn_chance: 0,
+            minimum_food: 0,
+            hazard_damage_per_turn: self.settings.hazard_damage as i32,
+            hazard_map: None,
+            hazard_map_author: None,
+            royale: None,
+            squad: None,
+        });
+
+        for (idx, cell) in self.cells.iter().enumerate() {
+            let position = CellIndex::<T>::from_usize(idx).into_position(width);
+            if cell.is_food() {
+                game.board.food.insert(position);
+            }
+            if cell.is_wall() {
+                game.board.walls.insert(position);
+            }
+            for _ in 0..cell.hazard_count() {
+                game.board.hazards.push(position);
+            }
+        }
+        for idx in 0..MAX_SNAKES {
+            let sid = SnakeId(idx as u8);
+            if self.healths[idx] == 0 {
+                continue;
+            }
+            let body = self
+                .get_snake_body_vec(&sid)
+                .into_iter()
+                .map(|ci| ci.into_position(width))
+                .collect::<VecDeque<_>>();
+            let snake = BattleSnake {
+                id: idx.to_string(),
+                name: idx.to_string(),
+                head: body[0],
+                body,
+                health: self.healths[idx] as i32,
+                shout: None,
+                squad: None,
+                latency: None,
+                actual_length: None,
+            };
+            if idx == 0 {
+                game.you = snake.clone();
+            }
+            game.board.snakes.push(snake);
+        }
+        game
+    }
+}
@@ -0,0 +1,72 @@
+//! storing a board as the cells that changed from the board it was simulated from
+
+use alloc::boxed::Box;
+
+use crate::{
+    compact_representation::{core::dimensions::Dimensions, CellNum},
+    types::SimulationSettings,
+};
+
+use super::{Cell, CellBoard, CellIndex};
+
+/// What changed between a board and one simulated from it, see [CellBoard::delta_from]. The
+/// snakes' healths, heads, lengths and stacked tails are small enough to keep whole, only the
+/// cells are diffed
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CellBoardDelta<T: CellNum, const MAX_SNAKES: usize> {
+    settings: SimulationSettings,
+    cells: Box<[(CellIndex<T>, Cell<T>)]>,
+    healths: [u8; MAX_SNAKES],
+    heads: [CellIndex<T>; MAX_SNAKES],
+    lengths: [u16; MAX_SNAKES],
+    extra_tail_pieces: [u16; MAX_SNAKES],
+}
+
+impl<T: CellNum, const MAX_SNAKES: usize> CellBoardDelta<T, MAX_SNAKES> {
+    /// roughly how many bytes this takes up, counting the cells it holds on the heap
+    pub fn size_in_bytes(&self) -> usize {
+        core::mem::size_of::<Self>() + core::mem::size_of_val(&*self.cells)
+    }
+}
+
+impl<T: CellNum, D: Dimensions, const BOARD_SIZE: usize, const MAX_SNAKES: usize>
+    CellBoard<T, D, BOARD_SIZE, MAX_SNAKES>
+{
+    /// how to get from `parent` to this board, for boards of the same size
+    pub fn delta_from(&self, parent: &Self) -> CellBoardDelta<T, MAX_SNAKES> {
+        debug_assert_eq!(
+            (self.get_actual_width(), self.get_actual_height()),
+            (parent.get_actual_width(), parent.get_actual_height())
+        );
+        let cells = self
+            .cells
+            .iter()
+            .zip(parent.cells.iter())
+            .enumerate()
+            .filter(|(_, (cell, before))| cell != before)
+            .map(|(i, (cell, _))| (CellIndex::from_usize(i), *cell))
+            .collect();
+        CellBoardDelta {
+            settings: self.settings,
+            cells,
+            healths: self.healths,
+            heads: self.heads,
+            lengths: self.lengths,
+            extra_tail_pieces: self.extra_tail_pieces,
+        }
+    }
+
+    /// the board `delta` was taken from, given the parent it was taken against
+    pub fn apply_delta(&self, delta: &CellBoardDelta<T, MAX_SNAKES>) -> Self {
+        let mut board = *self;
+        for (i, cell) in delta.cells.iter() {
+            board.cells[i.as_usize()] = *cell;
+        }
+        board.settings = delta.settings;
+        board.healths = delta.healths;
+        board.heads = delta.heads;
+        board.lengths = delta.lengths;
+        board.extra_tail_pieces = delta.extra_tail_pieces;
+        board
+    }
+}
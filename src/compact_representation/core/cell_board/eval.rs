@@ -1,10 +1,10 @@
-use std::borrow::Borrow;
-
-use itertools::Itertools;
+use core::borrow::Borrow;
 
 use crate::{
     compact_representation::{core::dimensions::Dimensions, CellNum},
-    types::{self, HeadGettableGame, Move, SnakeId, N_MOVES},
+    types::{
+        HeadGettableGame, HeadToHeadOutcome, Move, SimulationSettings, SnakeId, TurnPhase, N_MOVES,
+    },
 };
 
 use super::{CellBoard, CellIndex};
@@ -32,8 +32,8 @@ pub struct AliveMoveResult<T: CellNum> {
     new_tail: CellIndex<T>,
     /// The new health of the snake
     new_health: u8,
-    /// True if the snake ate food
-    ate_food: bool,
+    /// True if the snake grows this turn, because it ate food or every snake grows
+    grows: bool,
     /// The new length of the snake, after moving and potentially eating
     new_length: u16,
 }
@@ -47,7 +47,7 @@ pub enum SinglePlayerMoveResult<T: CellNum> {
     /// Represents the given snake is alive after phase 1 of evaluation
     Alive(AliveMoveResult<T>),
     /// Represents the snake died during phase 1. Cause it ran into a snake (including itself)
-    /// [excluding head to heads] or went out of bounds
+    /// [excluding head to heads], a wall or went out of bounds
     Dead,
 }
 
@@ -67,13 +67,55 @@ impl<T: CellNum> SinglePlayerMoveResult<T> {
 impl<T: CellNum, D: Dimensions, const BOARD_SIZE: usize, const MAX_SNAKES: usize>
     CellBoard<T, D, BOARD_SIZE, MAX_SNAKES>
 {
-    pub fn generate_state<'a, S: 'a>(
+    /// what happens if `me` and `them` both move on to `target` this turn, see
+    /// [crate::types::HeadToHeadPredictableGame]. This runs the simulator on just those two
+    /// moves, so the other snakes stand still. This uses the boards own
+    /// [CellBoard::simulation_settings]
+    pub fn predict_head_to_head(
+        &self,
+        me: SnakeId,
+        them: SnakeId,
+        target: CellIndex<T>,
+        mode: EvaluateMode,
+    ) -> HeadToHeadOutcome {
+        let move_to = |sid: SnakeId| {
+            if self.healths[sid.as_usize()] == 0 {
+                return None;
+            }
+            let head = self.heads[sid.as_usize()];
+            Move::all_iter().find(|mv| self.cell_in_direction(head, *mv, mode) == Some(target))
+        };
+        let (my_move, their_move) = match (move_to(me), move_to(them)) {
+            (Some(my_move), Some(their_move)) if me != them => (my_move, their_move),
+            _ => return HeadToHeadOutcome::NotAdjacent,
+        };
+
+        let state = self.generate_state(
+            [(me, [my_move]), (them, [their_move])].iter(),
+            mode,
+            &self.settings,
+        );
+        let next = self.evaluate_moves_with_state(&[(me, my_move), (them, their_move)], &state);
+        match (
+            next.healths[me.as_usize()] > 0,
+            next.healths[them.as_usize()] > 0,
+        ) {
+            (true, false) => HeadToHeadOutcome::IWin,
+            (false, true) => HeadToHeadOutcome::ILose,
+            _ => HeadToHeadOutcome::BothDie,
+        }
+    }
+
+    /// works out where each snake ends up for each of its moves, and its health and length
+    /// there, playing by `settings`
+    pub fn generate_state<'a, S>(
         &self,
         moves: impl Iterator<Item = &'a (SnakeId, S)>,
         mode: EvaluateMode,
+        settings: &SimulationSettings,
     ) -> [[SinglePlayerMoveResult<T>; N_MOVES]; MAX_SNAKES]
     where
-        S: Borrow<[Move]>,
+        S: Borrow<[Move]> + 'a,
     {
         let mut new_heads = [[SinglePlayerMoveResult::Dead; 4]; MAX_SNAKES];
 
@@ -94,18 +136,23 @@ impl<T: CellNum, D: Dimensions, const BOARD_SIZE: usize, const MAX_SNAKES: usize
                         )
                     });
 
-                let new_head_position =
-                    old_head.into_position(Self::width()).add_vec(m.to_vector());
+                let new_head_position = old_head
+                    .into_position(self.get_actual_width())
+                    .add_vec(m.to_vector());
                 let new_head = match mode {
                     EvaluateMode::Wrapped => self.as_wrapped_cell_index(new_head_position),
                     EvaluateMode::Standard => {
                         if self.off_board(new_head_position) {
                             continue;
                         } else {
-                            CellIndex::<T>::new(new_head_position, Self::width())
+                            CellIndex::<T>::new(new_head_position, self.get_actual_width())
                         }
                     }
                 };
+                // moving in to a wall is the same as moving off the board
+                if self.get_cell(new_head).is_wall() {
+                    continue;
+                }
 
                 // TWe calculate the 'neck' so that we can avoid the 'instant death'
                 // of moving into your neck
@@ -115,10 +162,10 @@ impl<T: CellNum, D: Dimensions, const BOARD_SIZE: usize, const MAX_SNAKES: usize
 
                     while curr != old_head {
                         prev = curr;
-                        curr = self.get_cell(curr).get_next_index().unwrap_or_else(|| {
-                            eprintln!("{}", self);
-                            panic!("snake is inconsistent")
-                        });
+                        curr = self
+                            .get_cell(curr)
+                            .get_next_index()
+                            .unwrap_or_else(|| panic!("snake is inconsistent: {}", self));
                     }
 
                     prev
@@ -136,23 +183,52 @@ impl<T: CellNum, D: Dimensions, const BOARD_SIZE: usize, const MAX_SNAKES: usize
                         .expect("We specifically went to a tail so this shouldn't fail")
                 };
 
-                let mut new_health = self.healths[id.as_usize()];
-                new_health = new_health.saturating_sub(1);
-                if self.get_cell(new_head).is_hazard() {
-                    new_health = new_health.saturating_sub(self.hazard_damage);
-                }
-
                 let ate_food = self.get_cell(new_head).is_food();
+                let grows = ate_food || settings.always_grow;
                 let mut new_length = self.lengths[id.as_usize()];
-
-                if ate_food {
-                    new_health = 100;
+                if grows {
                     new_length = new_length.saturating_add(1);
-                };
+                }
 
-                if new_health == 0 {
+                // each hazard stacked on the cell does damage
+                let hazard_damage =
+                    settings.hazard_damage as i32 * self.get_cell(new_head).hazard_count() as i32;
+                let mut new_health = self.healths[id.as_usize()] as i32;
+                let mut eliminated = false;
+                for phase in settings.rules_version.phases() {
+                    match phase {
+                        TurnPhase::ReduceHealth => {
+                            if settings.starvation_enabled {
+                                new_health -= 1;
+                            }
+                        }
+                        TurnPhase::DamageHazards => {
+                            if !ate_food {
+                                new_health -= hazard_damage;
+                            }
+                        }
+                        TurnPhase::DamageHazardsIncludingFood => {
+                            new_health -= hazard_damage;
+                            eliminated |= new_health <= 0;
+                        }
+                        TurnPhase::FeedSnakes => {
+                            if ate_food && settings.food_restores_health {
+                                new_health = settings.max_health as i32;
+                            }
+                        }
+                        TurnPhase::EliminateStarved => eliminated |= new_health <= 0,
+                    }
+                }
+                let mut new_health = new_health.min(settings.max_health as i32);
+
+                if eliminated || new_health <= 0 {
                     continue;
                 };
+                // constrictor puts every snake that survived back to full health
+                if settings.always_grow {
+                    new_health = settings.max_health as i32;
+                }
+                let new_health = new_health as u8;
 
                 new_heads[id.as_usize()][m.as_index()] =
                     SinglePlayerMoveResult::Alive(AliveMoveResult {
@@ -162,7 +238,7 @@ impl<T: CellNum, D: Dimensions, const BOARD_SIZE: usize, const MAX_SNAKES: usize
                         new_tail,
                         old_tail,
                         new_health,
-                        ate_food,
+                        grows,
                         new_length,
                     });
             }
@@ -171,15 +247,13 @@ impl<T: CellNum, D: Dimensions, const BOARD_SIZE: usize, const MAX_SNAKES: usize
         new_heads
     }
 
-    pub fn evaluate_moves_with_state<'a>(
+    /// applies one move for each snake, using the state from [CellBoard::generate_state]. This
+    /// doesn't allocate, so it can be called for every node of a search
+    pub fn evaluate_moves_with_state(
         &self,
-        moves: impl Iterator<Item = &'a (SnakeId, crate::types::Move)>,
+        moves: &[(SnakeId, Move)],
         new_heads: &[[SinglePlayerMoveResult<T>; N_MOVES]; MAX_SNAKES],
-    ) -> Self
-    where
-        <Self as types::SnakeIDGettableGame>::SnakeIDType: 'a,
-    {
-        let moves = moves.collect_vec();
+    ) -> Self {
         let mut new = *self;
 
         for (id, m) in moves.iter() {
@@ -192,7 +266,7 @@ impl<T: CellNum, D: Dimensions, const BOARD_SIZE: usize, const MAX_SNAKES: usize
                     new_tail,
                     old_tail,
                     new_health,
-                    ate_food,
+                    grows,
                     new_length,
                     ..
                 }) => {
@@ -200,9 +274,13 @@ impl<T: CellNum, D: Dimensions, const BOARD_SIZE: usize, const MAX_SNAKES: usize
                     // overriding someone elses tail which would break the representation and make it
                     // impossible to correctly remove the tail if the snake dies.
 
-                    // Remove old tail
+                    // Remove old tail, a snake that is all on one square loses the piece its
+                    // head moves off with instead
                     let old_tail_cell = new.get_cell(old_tail);
-                    if old_tail_cell.is_double_stacked_piece() {
+                    let extra = &mut new.extra_tail_pieces[id.as_usize()];
+                    if *extra > 0 && !old_tail_cell.is_triple_stacked_piece() {
+                        *extra -= 1;
+                    } else if old_tail_cell.is_double_stacked_piece() {
                         new.set_cell_body_piece(old_tail, id, old_tail_cell.get_idx());
                     } else {
                         new.cell_remove(old_tail);
@@ -215,9 +293,18 @@ impl<T: CellNum, D: Dimensions, const BOARD_SIZE: usize, const MAX_SNAKES: usize
 
                     // Step 2: Any Battlesnake that has found food will consume it
                     // Reset health to max if ate food
-                    if ate_food {
+                    // a fully stacked snake's tail is its head, which is stacked when the head
+                    // moves, and stacking it now would lose the head if the snake dies, so it
+                    // and tails that are already stacked count the piece as extra
+                    if grows {
                         let new_tail_cell = new.get_cell(new_tail);
-                        new.set_cell_double_stacked(new_tail, id, new_tail_cell.get_idx());
+                        if old_tail_cell.is_triple_stacked_piece()
+                            || new_tail_cell.is_double_stacked_piece()
+                        {
+                            new.extra_tail_pieces[id.as_usize()] += 1;
+                        } else {
+                            new.set_cell_double_stacked(new_tail, id, new_tail_cell.get_idx());
+                        }
 
                         // Food is removed naturally by overriding the Cell with the body, which will
                         // happen later
@@ -246,23 +333,36 @@ impl<T: CellNum, D: Dimensions, const BOARD_SIZE: usize, const MAX_SNAKES: usize
         }
 
         // Step 4e: Head to Head collisions
-        let grouped_heads = moves
-            .iter()
-            .map(|(id, m)| new_heads[id.as_usize()][m.as_index()])
-            .filter_map(|result| result.to_alive_struct())
-            .into_group_map_by(|t| t.new_head);
-        let head_to_head_collistions = grouped_heads
+        let alive =
+            |(id, m): &(SnakeId, Move)| new_heads[id.as_usize()][m.as_index()].to_alive_struct();
+        for (idx, first) in moves
             .iter()
-            .filter(|(_key, values)| values.len() >= 2);
-
-        for (head_to_head_collision_pos, snake_move_info) in head_to_head_collistions {
-            let max_length = snake_move_info
+            .enumerate()
+            .filter_map(|(idx, mv)| alive(mv).map(|first| (idx, first)))
+        {
+            let head_to_head_collision_pos = &first.new_head;
+            // each square is handled once, from the first snake moving on to it
+            if moves[..idx]
                 .iter()
-                .map(|i| (*i, new.get_length(i.id)))
-                .max_by_key(|x| x.1)
-                .unwrap()
-                .1;
-            let snake_ids = snake_move_info.iter().map(|i| i.id).collect_vec();
+                .filter_map(alive)
+                .any(|other| other.new_head == first.new_head)
+            {
+                continue;
+            }
+            let snake_move_info = || {
+                moves[idx..]
+                    .iter()
+                    .filter_map(alive)
+                    .filter(|other| other.new_head == first.new_head)
+            };
+            if snake_move_info().count() < 2 {
+                continue;
+            }
+
+            let max_length = snake_move_info()
+                .map(|i| new.get_length(i.id))
+                .max()
+                .unwrap();
             let cell = new.get_cell(*head_to_head_collision_pos);
             // consider this board:
             //   s . . f . . s s s 3 s
@@ -282,13 +382,13 @@ impl<T: CellNum, D: Dimensions, const BOARD_SIZE: usize, const MAX_SNAKES: usize
             // and snake 0 has a body segment (currently it's neck) on 0,
             // this will cause a head to head collision between snake 1 and snake 3 on snake 0's neck.
             // this statement needs to be added to the winner check, because if it isn't, the neck cell for
-            // snake 0 will be removed, causing the body to go in to an inconsistent state
-            let head_to_head_collision_on_another_snake = cell.is_body_segment()
-                && !cell.is_head()
-                && !snake_ids.contains(&cell.get_snake_id().unwrap());
+            // snake 0 will be removed, causing the body to go in to an inconsistent state.
+            // The same goes for another snake's head, which it has just moved off of so is now
+            // its neck
+            let head_to_head_collision_on_another_snake = cell.get_snake_id().is_some()
+                && !snake_move_info().any(|i| cell.get_snake_id() == Some(i.id));
 
-            let multiple_snakes_max_length = snake_move_info
-                .iter()
+            let multiple_snakes_max_length = snake_move_info()
                 .filter(|x| new.get_length(x.id) == max_length)
                 .count()
                 != 1;
@@ -296,24 +396,18 @@ impl<T: CellNum, D: Dimensions, const BOARD_SIZE: usize, const MAX_SNAKES: usize
             let winner = if multiple_snakes_max_length || head_to_head_collision_on_another_snake {
                 None
             } else {
-                Some(
-                    snake_move_info
-                        .iter()
-                        .map(|i| (*i, new.get_length(i.id)))
-                        .max_by_key(|x| x.1)
-                        .unwrap()
-                        .0,
-                )
+                snake_move_info().find(|i| new.get_length(i.id) == max_length)
             };
 
-            for AliveMoveResult { id: dead, .. } in snake_move_info
-                .iter()
-                .filter(|x| Some(x.id) != winner.map(|x| x.id))
+            for AliveMoveResult { id: dead, .. } in
+                snake_move_info().filter(|x| Some(x.id) != winner.map(|x| x.id))
             {
                 to_kill[dead.as_usize()] = true;
             }
 
-            if winner.is_none() && !head_to_head_collision_on_another_snake {
+            // the food is eaten, but a snake on the square is removed along with the rest of
+            // its body when it dies
+            if winner.is_none() && cell.get_snake_id().is_none() {
                 new.cell_remove(*head_to_head_collision_pos);
             }
         }
@@ -338,8 +432,12 @@ impl<T: CellNum, D: Dimensions, const BOARD_SIZE: usize, const MAX_SNAKES: usize
                     new.heads[id.as_usize()] = new_head;
                     new.set_cell_head(new_head, id, new_tail);
 
+                    // the old head is stacked if the whole snake was on it, or it is the tail
+                    // of a snake of 2 that just ate
                     let old_head_cell = self.get_cell(old_head);
-                    if old_head_cell.is_triple_stacked_piece() {
+                    if old_head_cell.is_triple_stacked_piece()
+                        || new.get_cell(old_head).is_double_stacked_piece()
+                    {
                         new.set_cell_double_stacked(old_head, id, new_head);
                     } else {
                         new.set_cell_body_piece(old_head, id, new_head);
@@ -0,0 +1,241 @@
+use alloc::vec::Vec;
+
+use crate::{
+    compact_representation::{
+        core::{
+            bitboard::{BitBoard, BoardMasks},
+            dimensions::Dimensions,
+            CellCoord,
+        },
+        CellNum,
+    },
+    types::{FloodFillOptions, Move},
+};
+
+use super::{CellBoard, CellIndex, EvaluateMode};
+
+impl<T: CellNum, D: Dimensions, const BOARD_SIZE: usize, const MAX_SNAKES: usize>
+    CellBoard<T, D, BOARD_SIZE, MAX_SNAKES>
+{
+    /// Returns the cell reached by moving from `from` in the direction of `mv`. In
+    /// `EvaluateMode::Standard` this is None if the move leaves the board, in
+    /// `EvaluateMode::Wrapped` the move wraps around to the other side of the board. Either way
+    /// it is None if the move is in to a wall
+    pub fn cell_in_direction(
+        &self,
+        from: CellIndex<T>,
+        mv: Move,
+        mode: EvaluateMode,
+    ) -> Option<CellIndex<T>> {
+        let width = self.get_actual_width();
+        self.coord_in_direction(from.into_coord(width), mv, mode)
+            .map(|coord| CellIndex::from_coord(coord, width))
+    }
+
+    /// the coordinates reached by moving from `from` in the direction of `mv`, by the same rules
+    /// as [CellBoard::cell_in_direction] but without going through a wire position
+    pub(crate) fn coord_in_direction(
+        &self,
+        from: CellCoord,
+        mv: Move,
+        mode: EvaluateMode,
+    ) -> Option<CellCoord> {
+        let wraps = mode == EvaluateMode::Wrapped;
+        // one square along an axis `size` squares long, or None if that leaves the board
+        let step = |at: u8, size: u8, forwards: bool| match (forwards, at) {
+            (true, at) if at + 1 < size => Some(at + 1),
+            (true, _) => Some(0).filter(|_| wraps),
+            (false, 0) => Some(size - 1).filter(|_| wraps),
+            (false, at) => Some(at - 1),
+        };
+        let (width, height) = (self.get_actual_width(), self.get_actual_height());
+        let to = match mv {
+            Move::Left => CellCoord::new(step(from.x, width, false)?, from.y),
+            Move::Right => CellCoord::new(step(from.x, width, true)?, from.y),
+            Move::Up => CellCoord::new(from.x, step(from.y, height, true)?),
+            Move::Down => CellCoord::new(from.x, step(from.y, height, false)?),
+        };
+        Some(to).filter(|to| !self.cell_is_wall(CellIndex::from_coord(*to, width)))
+    }
+
+    /// the cells reachable from `start` (including `start`) as a bitboard, see
+    /// [CellBoard::flood_fill_bits_with]
+    pub(crate) fn flood_fill_bits(
+        &self,
+        start: CellIndex<T>,
+        options: FloodFillOptions,
+        mode: EvaluateMode,
+        elapsed: u16,
+        limit: usize,
+    ) -> BitBoard<BOARD_SIZE> {
+        let schedule = OccupancySchedule::new(self, options.hazards_block);
+        self.flood_fill_bits_with(&schedule, start, options.max_depth, mode, elapsed, limit)
+    }
+
+    /// the cells reachable from `start` (including `start`) as a bitboard, with passable cells
+    /// taken from `schedule`. `elapsed` is the number of turns that have already passed by the
+    /// time we're at `start`, snake pieces are passable if they have moved out of the way
+    /// (assuming no snake eats) by the time we first get to them. The fill stops early once at
+    /// least `limit` cells have been found
+    pub(crate) fn flood_fill_bits_with(
+        &self,
+        schedule: &OccupancySchedule<T, BOARD_SIZE>,
+        start: CellIndex<T>,
+        max_depth: Option<usize>,
+        mode: EvaluateMode,
+        elapsed: u16,
+        limit: usize,
+    ) -> BitBoard<BOARD_SIZE> {
+        let mut occupancy = schedule.at(elapsed);
+
+        let mut reached = BitBoard::empty();
+        reached.set(start);
+        let mut frontier = reached;
+        let mut turn = elapsed;
+        let max_depth = max_depth.unwrap_or(usize::MAX);
+        let mut depth = 0;
+
+        // expand one ring at a time, so each cell is reached at the earliest turn possible
+        while depth < max_depth && reached.count() < limit {
+            depth += 1;
+            turn += 1;
+            occupancy.advance_to(turn);
+
+            frontier = schedule
+                .masks
+                .neighbors(&frontier, mode)
+                .and(&occupancy.passable())
+                .and_not(&reached);
+            if frontier.is_empty() {
+                break;
+            }
+            reached.or_assign(&frontier);
+        }
+
+        reached
+    }
+
+    /// Counts the cells reachable from `start` (which is included in the count), stopping as soon
+    /// as `limit` cells have been found. `start` is treated as being reached in one turn. The
+    /// `schedule` is built once for this board, so filling from many cells doesn't walk the
+    /// whole board for each of them
+    pub(crate) fn bounded_flood_fill(
+        &self,
+        schedule: &OccupancySchedule<T, BOARD_SIZE>,
+        start: CellIndex<T>,
+        mode: EvaluateMode,
+        limit: usize,
+    ) -> usize {
+        self.flood_fill_bits_with(schedule, start, None, mode, 1, limit)
+            .count()
+            .min(limit)
+    }
+
+    /// the cells reachable from `start`, not including `start`, see
+    /// [crate::types::FloodFillableGame]
+    pub fn flood_fill(
+        &self,
+        start: CellIndex<T>,
+        options: FloodFillOptions,
+        mode: EvaluateMode,
+    ) -> Vec<CellIndex<T>> {
+        self.flood_fill_bits(start, options, mode, 0, usize::MAX)
+            .iter()
+            .filter(|ci| *ci != start)
+            .collect()
+    }
+
+    /// the number of cells reachable from `start`, not including `start`
+    pub fn flood_fill_count(
+        &self,
+        start: CellIndex<T>,
+        options: FloodFillOptions,
+        mode: EvaluateMode,
+    ) -> usize {
+        self.flood_fill_bits(start, options, mode, 0, usize::MAX)
+            .count()
+            - 1
+    }
+}
+
+/// The cells that block and the turn each snake piece moves out of the way, worked out once per
+/// board so any number of fills can be started from it without walking the board again
+pub(crate) struct OccupancySchedule<T: CellNum, const BOARD_SIZE: usize> {
+    masks: BoardMasks<BOARD_SIZE>,
+    free: BitBoard<BOARD_SIZE>,
+    blocked: BitBoard<BOARD_SIZE>,
+    /// the cells holding snake pieces with the turn they are vacated, earliest first
+    vacate_order: Vec<(u16, CellIndex<T>)>,
+}
+
+impl<T: CellNum, const BOARD_SIZE: usize> OccupancySchedule<T, BOARD_SIZE> {
+    pub(crate) fn new<D: Dimensions, const MAX_SNAKES: usize>(
+        board: &CellBoard<T, D, BOARD_SIZE, MAX_SNAKES>,
+        hazards_block: bool,
+    ) -> Self {
+        let mut blocked = BitBoard::empty();
+        let mut free = BitBoard::empty();
+        for (idx, cell) in board.cells.iter().enumerate() {
+            let ci = CellIndex::<T>::from_usize(idx);
+            if cell.is_wall() || (hazards_block && cell.is_hazard()) {
+                blocked.set(ci);
+            }
+            if !cell.is_body_segment() && !cell.is_head() {
+                free.set(ci);
+            }
+        }
+
+        let mut vacate_order = board
+            .turns_until_vacated()
+            .iter()
+            .enumerate()
+            .filter(|(_, turns)| **turns > 0)
+            .map(|(idx, turns)| (*turns, CellIndex::from_usize(idx)))
+            .collect::<Vec<_>>();
+        vacate_order.sort_unstable();
+
+        Self {
+            masks: BoardMasks::new(board.get_actual_width(), board.get_actual_height()),
+            free,
+            blocked,
+            vacate_order,
+        }
+    }
+
+    /// the cells that are passable on `turn`, to be advanced from there
+    pub(crate) fn at(&self, turn: u16) -> Occupancy<'_, T, BOARD_SIZE> {
+        let mut occupancy = Occupancy {
+            free: self.free,
+            blocked: self.blocked,
+            remaining: &self.vacate_order,
+        };
+        occupancy.advance_to(turn);
+        occupancy
+    }
+}
+
+/// Tracks which cells are passable as turns go by, for fills that expand one turn at a time
+pub(crate) struct Occupancy<'a, T: CellNum, const BOARD_SIZE: usize> {
+    free: BitBoard<BOARD_SIZE>,
+    blocked: BitBoard<BOARD_SIZE>,
+    /// the snake pieces that haven't moved out of the way yet, earliest first
+    remaining: &'a [(u16, CellIndex<T>)],
+}
+
+impl<'a, T: CellNum, const BOARD_SIZE: usize> Occupancy<'a, T, BOARD_SIZE> {
+    /// marks the pieces of each snake that will have moved out of the way by `turn` as free
+    pub(crate) fn advance_to(&mut self, turn: u16) {
+        while let Some(((vacates_at, i), rest)) = self.remaining.split_first() {
+            if *vacates_at > turn {
+                break;
+            }
+            self.free.set(*i);
+            self.remaining = rest;
+        }
+    }
+
+    /// the cells that can be moved in to on the current turn
+    pub(crate) fn passable(&self) -> BitBoard<BOARD_SIZE> {
+        self.free.and_not(&self.blocked)
+    }
+}
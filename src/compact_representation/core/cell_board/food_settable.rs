@@ -0,0 +1,46 @@
+use alloc::format;
+
+use crate::{
+    compact_representation::{core::dimensions::Dimensions, CellNum},
+    types::FoodSettableGame,
+    Error,
+};
+
+use super::CellBoard;
+
+impl<T: CellNum, D: Dimensions, const BOARD_SIZE: usize, const MAX_SNAKES: usize> FoodSettableGame
+    for CellBoard<T, D, BOARD_SIZE, MAX_SNAKES>
+{
+    fn place_food(&mut self, pos: Self::NativePositionType) -> Result<(), Error> {
+        let squares = self.get_actual_width() as usize * self.get_actual_height() as usize;
+        if pos.as_usize() >= squares {
+            return Err(Error::InvalidFood(format!(
+                "cell {} is off the board",
+                pos.as_usize()
+            )));
+        }
+        let cell = &mut self.cells[pos.as_usize()];
+        if cell.get_snake_id().is_some() {
+            return Err(Error::InvalidFood(format!(
+                "there is a snake on cell {}",
+                pos.as_usize()
+            )));
+        }
+        if cell.is_wall() {
+            return Err(Error::InvalidFood(format!(
+                "there is a wall on cell {}",
+                pos.as_usize()
+            )));
+        }
+        cell.set_food();
+        Ok(())
+    }
+
+    fn remove_food(&mut self, pos: Self::NativePositionType) {
+        if let Some(cell) = self.cells.get_mut(pos.as_usize()) {
+            if cell.is_food() {
+                cell.remove();
+            }
+        }
+    }
+}
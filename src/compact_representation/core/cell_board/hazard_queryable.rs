@@ -12,7 +12,11 @@ impl<T: CellNum, D: Dimensions, const BOARD_SIZE: usize, const MAX_SNAKES: usize
         self.cell_is_hazard(*pos)
     }
 
-    fn get_hazard_damage(&self) -> u8 {
-        self.hazard_damage
+    fn get_hazard_damage(&self) -> i16 {
+        self.settings.hazard_damage
+    }
+
+    fn get_hazard_count(&self, pos: &Self::NativePositionType) -> u8 {
+        self.cell_hazard_count(*pos)
     }
 }
@@ -0,0 +1,211 @@
+//! checking that a board is one the simulator could have made
+
+use alloc::format;
+use alloc::string::{String, ToString};
+use core::fmt;
+
+use crate::{
+    compact_representation::{core::dimensions::Dimensions, CellNum},
+    types::SnakeId,
+};
+
+use super::{CellBoard, CellIndex};
+
+/// One way a compact board is broken, from `check_invariants` on the
+/// [standard](crate::compact_representation::standard::CellBoard::check_invariants) and
+/// [wrapped](crate::compact_representation::wrapped::CellBoard::check_invariants) boards. Boards
+/// from the simulator should never have any, so seeing one is a bug in this crate
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InvariantViolation {
+    /// the snake that is broken, if it is about a snake
+    pub snake: Option<SnakeId>,
+    /// the index of the cell that is broken, if it is about a cell
+    pub cell: Option<usize>,
+    /// what is wrong
+    pub reason: String,
+}
+
+impl InvariantViolation {
+    fn new(snake: Option<SnakeId>, cell: Option<usize>, reason: impl Into<String>) -> Self {
+        InvariantViolation {
+            snake,
+            cell,
+            reason: reason.into(),
+        }
+    }
+}
+
+impl fmt::Display for InvariantViolation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.reason)?;
+        if let Some(snake) = self.snake {
+            write!(f, ", snake {}", snake.as_usize())?;
+        }
+        if let Some(cell) = self.cell {
+            write!(f, ", cell {}", cell)?;
+        }
+        Ok(())
+    }
+}
+
+impl core::error::Error for InvariantViolation {}
+
+impl<T: CellNum, D: Dimensions, const BOARD_SIZE: usize, const MAX_SNAKES: usize>
+    CellBoard<T, D, BOARD_SIZE, MAX_SNAKES>
+{
+    /// Checks everything the simulator relies on about this board, a stricter
+    /// [CellBoard::assert_consistency] that says what is wrong. Every living snake's body has to
+    /// run from its tail to the head in `heads` through cells that are its own, be as long as
+    /// its length, and have a health no more than the max health. Every cell that has a snake on
+    /// it has to be part of a living snake's body
+    pub fn check_invariants(&self) -> Result<(), InvariantViolation> {
+        let mut owned = [false; BOARD_SIZE];
+        for i in 0..MAX_SNAKES {
+            let sid = SnakeId(i as u8);
+            let health = self.healths[i];
+            if health == 0 {
+                continue;
+            }
+            let violation = |cell: CellIndex<T>, reason: String| {
+                InvariantViolation::new(Some(sid), Some(cell.as_usize()), reason)
+            };
+            if health > self.settings.max_health {
+                return Err(InvariantViolation::new(
+                    Some(sid),
+                    None,
+                    format!(
+                        "health {} is more than the max health {}",
+                        health, self.settings.max_health
+                    ),
+                ));
+            }
+
+            let head = self.heads[i];
+            if head.as_usize() >= BOARD_SIZE {
+                return Err(violation(head, "the head is off the board".to_string()));
+            }
+            let head_cell = self.get_cell(head);
+            if !head_cell.is_head() || head_cell.get_snake_id() != Some(sid) {
+                return Err(violation(
+                    head,
+                    "the head isn't on a head of this snake".to_string(),
+                ));
+            }
+
+            let tail = head_cell.get_tail_position(head).unwrap();
+            if self.extra_tail_pieces[i] > 0 && !self.get_cell(tail).is_stacked() {
+                return Err(violation(
+                    tail,
+                    "the tail has extra pieces but isn't stacked".to_string(),
+                ));
+            }
+
+            // walks from the tail towards the head, counting segments as it goes
+            let mut index = tail;
+            let mut segments = 0;
+            loop {
+                if index.as_usize() >= BOARD_SIZE {
+                    return Err(violation(index, "the body runs off the board".to_string()));
+                }
+                if owned[index.as_usize()] {
+                    return Err(violation(index, "the body crosses a body".to_string()));
+                }
+                owned[index.as_usize()] = true;
+                let cell = self.get_cell(index);
+                if cell.get_snake_id() != Some(sid) {
+                    return Err(violation(
+                        index,
+                        "the body runs through a cell of something else".to_string(),
+                    ));
+                }
+                segments += self.pieces_on(index);
+                if index == head {
+                    break;
+                }
+                match cell.get_next_index() {
+                    Some(next) => index = next,
+                    None => {
+                        return Err(violation(
+                            index,
+                            "the body stops before the head".to_string(),
+                        ))
+                    }
+                }
+            }
+            if segments != self.lengths[i] {
+                return Err(violation(
+                    head,
+                    format!(
+                        "the body has {} segments but the length is {}",
+                        segments, self.lengths[i]
+                    ),
+                ));
+            }
+        }
+
+        for (idx, cell) in self.cells.iter().enumerate() {
+            if let Some(sid) = cell.get_snake_id() {
+                if !owned[idx] {
+                    return Err(InvariantViolation::new(
+                        Some(sid),
+                        Some(idx),
+                        "the cell isn't part of a living snake's body",
+                    ));
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::compact_representation::dimensions::Square;
+    use crate::game_fixture;
+    use crate::types::build_snake_id_map;
+
+    type Board = CellBoard<u8, Square, { 11 * 11 }, 4>;
+
+    fn board() -> Board {
+        let g = game_fixture(include_str!("../../../../fixtures/late_stage.json"));
+        let snake_ids = build_snake_id_map(&g);
+        Board::convert_from_game(g, &snake_ids).unwrap()
+    }
+
+    #[test]
+    fn test_check_invariants() {
+        let b = board();
+        assert_eq!(b.check_invariants(), Ok(()));
+
+        let mut long = b;
+        long.lengths[0] += 2;
+        let violation = long.check_invariants().unwrap_err();
+        assert_eq!(violation.snake, Some(SnakeId(0)));
+        assert_eq!(violation.cell, Some(b.heads[0].as_usize()));
+
+        let mut overfed = b;
+        overfed.healths[1] = b.settings.max_health + 1;
+        assert_eq!(
+            overfed.check_invariants().unwrap_err().snake,
+            Some(SnakeId(1))
+        );
+
+        // a dead snake that is still on the board
+        let mut dead = b;
+        dead.healths[1] = 0;
+        let violation = dead.check_invariants().unwrap_err();
+        assert_eq!(violation.snake, Some(SnakeId(1)));
+        assert!(violation.cell.is_some());
+
+        let mut moved = b;
+        moved.heads[0] = moved
+            .get_cell(b.heads[0])
+            .get_tail_position(b.heads[0])
+            .unwrap();
+        assert_eq!(
+            moved.check_invariants().unwrap_err().snake,
+            Some(SnakeId(0))
+        );
+    }
+}
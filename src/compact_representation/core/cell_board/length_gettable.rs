@@ -1,4 +1,4 @@
-use std::fmt::Display;
+use core::fmt::Display;
 
 use crate::{
     compact_representation::{
@@ -6,7 +6,7 @@ use crate::{
         CellNum,
     },
     types::LengthGettableGame,
-    wire_representation::Position,
+    types::Position,
 };
 
 use super::CellBoard;
@@ -27,17 +27,13 @@ impl<T: CellNum, D: Dimensions, const BOARD_SIZE: usize, const MAX_SNAKES: usize
 impl<T: CellNum, D: Dimensions, const BOARD_SIZE: usize, const MAX_SNAKES: usize> Display
     for CellBoard<T, D, BOARD_SIZE, MAX_SNAKES>
 {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
         let width = self.get_actual_width();
         let height = self.get_actual_height();
         writeln!(f)?;
-        for y in 0..height {
-            for x in 0..width {
-                let y = height - y - 1;
-                let position = Position {
-                    x: x as i32,
-                    y: y as i32,
-                };
+        for row in 0..height as i32 {
+            for col in 0..width as i32 {
+                let position = Position::from_row_col(row, col, height as i32);
                 let cell_idx = CellIndex::new(position, width);
                 if self.cell_is_snake_head(cell_idx) {
                     let id = self.get_snake_id_at(cell_idx);
@@ -46,6 +42,8 @@ impl<T: CellNum, D: Dimensions, const BOARD_SIZE: usize, const MAX_SNAKES: usize
                     write!(f, "f")?
                 } else if self.cell_is_body(cell_idx) {
                     write!(f, "s")?
+                } else if self.cell_is_wall(cell_idx) {
+                    write!(f, "#")?
                 } else if self.cell_is_hazard(cell_idx) {
                     write!(f, "x")?
                 } else {
@@ -56,8 +54,12 @@ impl<T: CellNum, D: Dimensions, const BOARD_SIZE: usize, const MAX_SNAKES: usize
             }
             writeln!(f)?;
         }
-        let hash_repr = self.pack_as_hash();
-        writeln!(f, "{}", serde_json::to_string(&hash_repr).unwrap())?;
+        #[cfg(feature = "std")]
+        writeln!(
+            f,
+            "{}",
+            serde_json::to_string(&self.pack_as_hash()).unwrap()
+        )?;
         Ok(())
     }
 }
@@ -1,13 +1,8 @@
-use std::collections::HashMap;
-use std::error::Error;
-use std::slice::Iter;
+use core::slice::Iter;
 
-use itertools::Itertools;
-
-use crate::types::SnakeIDMap;
+use crate::types::Position;
+use crate::types::SimulationSettings;
 use crate::types::SnakeId;
-use crate::wire_representation::Game;
-use crate::wire_representation::Position;
 
 use super::dimensions::Dimensions;
 use super::Cell;
@@ -15,22 +10,39 @@ use super::CellIndex;
 use super::CellNum as CN;
 use super::{DOUBLE_STACK, TRIPLE_STACK};
 
+mod a_star;
+mod adjust;
+mod bytes;
+mod canonicalize;
+#[cfg(feature = "std")]
+mod conversion;
+mod delta;
 mod eval;
+mod flood_fill;
 mod food_gettable;
+mod food_settable;
 mod hazard_queryable;
 mod hazard_settable;
 mod head_gettable;
 mod health_gettable;
+mod invariants;
 mod length_gettable;
 mod neck_queryable;
 mod position_gettable;
+mod reasonable_moves;
+mod respecialize;
 mod size_determinable;
 mod snake_body_gettable;
 mod snake_id_gettable;
 mod victor_determinable;
+mod voronoi;
+mod wall_queryable;
 mod you_determinable;
 
+pub use bytes::BytesVisitor;
+pub use delta::CellBoardDelta;
 pub use eval::EvaluateMode;
+pub use invariants::InvariantViolation;
 
 /// A compact board representation that is significantly faster for simulation than
 /// `battlesnake_game_types::wire_representation::Game`.
@@ -41,26 +53,18 @@ pub struct CellBoard<
     const BOARD_SIZE: usize,
     const MAX_SNAKES: usize,
 > {
-    hazard_damage: u8,
+    settings: SimulationSettings,
     cells: [Cell<T>; BOARD_SIZE],
     healths: [u8; MAX_SNAKES],
     heads: [CellIndex<T>; MAX_SNAKES],
     lengths: [u16; MAX_SNAKES],
+    /// pieces stacked on each snake's tail beyond the ones its cell counts, a tail cell counts
+    /// up to 2 and the cell of a snake that is all on one square 3. Only snakes that eat
+    /// straight after spawning, or spawn with more than 3 pieces, ever have any
+    extra_tail_pieces: [u16; MAX_SNAKES],
     dimensions: DimensionsType,
 }
 
-#[allow(dead_code)]
-fn get_snake_id(
-    snake: &crate::wire_representation::BattleSnake,
-    snake_ids: &SnakeIDMap,
-) -> Option<SnakeId> {
-    if snake.health == 0 {
-        None
-    } else {
-        Some(*snake_ids.get(&snake.id).unwrap())
-    }
-}
-
 impl<T: CN, D: Dimensions, const BOARD_SIZE: usize, const MAX_SNAKES: usize>
     CellBoard<T, D, BOARD_SIZE, MAX_SNAKES>
 {
@@ -100,79 +104,6 @@ impl<T: CN, D: Dimensions, const BOARD_SIZE: usize, const MAX_SNAKES: usize>
         true
     }
 
-    /// packs this as a hash. Doing this because getting serde to work
-    /// with const generics is hard
-    pub fn pack_as_hash(&self) -> HashMap<String, Vec<u32>> {
-        let mut hash = HashMap::new();
-        hash.insert("hazard_damage".to_string(), vec![self.hazard_damage as u32]);
-        hash.insert(
-            "actual_width".to_string(),
-            vec![self.get_actual_width() as u32],
-        );
-        hash.insert(
-            "healths".to_string(),
-            self.healths.iter().map(|x| *x as u32).collect(),
-        );
-        hash.insert(
-            "lengths".to_string(),
-            self.lengths.iter().map(|x| *x as u32).collect(),
-        );
-        hash.insert(
-            "heads".to_string(),
-            self.heads.iter().map(|x| x.as_usize() as u32).collect(),
-        );
-        hash.insert(
-            "cells".to_string(),
-            self.cells.iter().map(|x| x.pack_as_u32()).collect(),
-        );
-        hash
-    }
-
-    /// unpacks a packed hash repr back in to a CellBoard
-    pub fn from_packed_hash(hash: &HashMap<String, Vec<u32>>) -> Self {
-        let hazard_damage = hash.get("hazard_damage").unwrap()[0] as u8;
-        let actual_width = hash.get("actual_width").unwrap()[0] as u8;
-        let actual_height = hash
-            .get("actual_height")
-            .map(|h| h[0] as u8)
-            .unwrap_or(actual_width);
-
-        let mut healths = [0; MAX_SNAKES];
-        let healths_iter = hash.get("healths").unwrap().iter().map(|x| *x as u8);
-        for (idx, health) in healths_iter.enumerate() {
-            healths[idx] = health;
-        }
-
-        let mut lengths = [0; MAX_SNAKES];
-        let lengths_iter = hash.get("lengths").unwrap().iter().map(|x| *x as u16);
-        for (idx, length) in lengths_iter.enumerate() {
-            lengths[idx] = length;
-        }
-
-        let mut heads = [CellIndex::<T>::from_usize(0); MAX_SNAKES];
-        let heads_iter = hash.get("heads").unwrap().iter().map(|x| *x as usize);
-        for (idx, head) in heads_iter.enumerate() {
-            heads[idx] = CellIndex::<T>::from_usize(head);
-        }
-
-        let mut cells = [Cell::<T>::empty(); BOARD_SIZE];
-        let cells_iter = hash.get("cells").unwrap().iter().map(|x| *x as u32);
-        for (idx, cell) in cells_iter.enumerate() {
-            cells[idx] = Cell::<T>::from_u32(cell);
-        }
-
-        let dimensions = D::from_dimensions(actual_width, actual_height);
-
-        CellBoard {
-            hazard_damage,
-            cells,
-            healths,
-            heads,
-            lengths,
-            dimensions,
-        }
-    }
-
     pub fn as_wrapped_cell_index(&self, mut new_head_position: Position) -> CellIndex<T> {
         if self.off_board(new_head_position) {
             if new_head_position.x < 0 {
@@ -206,12 +137,23 @@ impl<T: CN, D: Dimensions, const BOARD_SIZE: usize, const MAX_SNAKES: usize>
             } else {
                 panic!("We should never get here");
             }
-            CellIndex::<T>::new(new_head_position, Self::width())
+            CellIndex::<T>::new(new_head_position, self.get_actual_width())
         } else {
-            CellIndex::<T>::new(new_head_position, Self::width())
+            CellIndex::<T>::new(new_head_position, self.get_actual_width())
         }
     }
 
+    /// the settings this board is simulated with by default, taken from the ruleset it was
+    /// converted from
+    pub fn simulation_settings(&self) -> SimulationSettings {
+        self.settings
+    }
+
+    /// simulate this board with `settings` from now on
+    pub fn set_simulation_settings(&mut self, settings: SimulationSettings) {
+        self.settings = settings;
+    }
+
     pub fn get_actual_width(&self) -> u8 {
         self.dimensions.width()
     }
@@ -224,6 +166,54 @@ impl<T: CN, D: Dimensions, const BOARD_SIZE: usize, const MAX_SNAKES: usize>
         self.healths[sid.0 as usize] = 0;
         self.heads[sid.0 as usize] = CellIndex::from_i32(0);
         self.lengths[sid.0 as usize] = 0;
+        self.extra_tail_pieces[sid.0 as usize] = 0;
+    }
+
+    /// how many pieces of a snake are on this cell, 0 if there isn't a snake on it
+    fn pieces_on(&self, cell_index: CellIndex<T>) -> u16 {
+        let cell = self.get_cell(cell_index);
+        let sid = match cell.get_snake_id() {
+            Some(sid) => sid,
+            None => return 0,
+        };
+        let pieces = if cell.is_triple_stacked_piece() {
+            TRIPLE_STACK
+        } else if cell.is_double_stacked_piece() {
+            DOUBLE_STACK
+        } else {
+            1
+        } as u16;
+        let head = self.heads[sid.as_usize()];
+        if self.get_cell(head).get_tail_position(head) == Some(cell_index) {
+            pieces + self.extra_tail_pieces[sid.as_usize()]
+        } else {
+            pieces
+        }
+    }
+
+    /// For every cell on the board, the number of turns until it no longer holds a snake piece,
+    /// assuming no snake eats. Cells that are currently free are 0.
+    pub(crate) fn turns_until_vacated(&self) -> [u16; BOARD_SIZE] {
+        let mut vacated = [0; BOARD_SIZE];
+
+        for (idx, health) in self.healths.iter().enumerate() {
+            if *health == 0 {
+                continue;
+            }
+            let head = self.heads[idx];
+            let mut turns = 0;
+            let mut current = self.get_cell(head).get_tail_position(head);
+
+            // walk from the tail towards the head, each piece leaves one turn after the piece
+            // behind it, stacked pieces take one turn for each piece in the stack
+            while let Some(i) = current {
+                turns += self.pieces_on(i);
+                vacated[i.as_usize()] = turns;
+                current = self.get_cell(i).get_next_index();
+            }
+        }
+
+        vacated
     }
 
     fn kill_and_remove(&mut self, sid: SnakeId) {
@@ -241,104 +231,6 @@ impl<T: CN, D: Dimensions, const BOARD_SIZE: usize, const MAX_SNAKES: usize>
         self.kill(sid);
     }
 
-    /// Builds a cellboard from a given game, will return an error if the game doesn't match
-    /// the provided BOARD_SIZE or MAX_SNAKES. You are encouraged to use `CellBoard4Snakes11x11`
-    /// for the common game layout
-    pub fn convert_from_game(game: Game, snake_ids: &SnakeIDMap) -> Result<Self, Box<dyn Error>> {
-        if game.board.width * game.board.height > BOARD_SIZE as i32 {
-            return Err("game size doesn't fit in the given board size".into());
-        }
-
-        if game.board.snakes.len() > MAX_SNAKES {
-            return Err("too many snakes".into());
-        }
-
-        for snake in &game.board.snakes {
-            let counts = &snake.body.iter().counts();
-            if counts.values().any(|v| *v == TRIPLE_STACK) && counts.len() != 1 {
-                return Err(format!("snake {} has a bad body stack (3 segs on same square and more than one unique position)", snake.id).into());
-            }
-        }
-        let width = game.board.width as u8;
-        let height = game.board.height as u8;
-
-        let mut cells = [Cell::empty(); BOARD_SIZE];
-        let mut healths: [u8; MAX_SNAKES] = [0; MAX_SNAKES];
-        let mut heads: [CellIndex<T>; MAX_SNAKES] = [CellIndex::from_i32(0); MAX_SNAKES];
-        let mut lengths: [u16; MAX_SNAKES] = [0; MAX_SNAKES];
-
-        for snake in &game.board.snakes {
-            let snake_id = match get_snake_id(snake, snake_ids) {
-                Some(value) => value,
-                None => continue,
-            };
-
-            healths[snake_id.0 as usize] = snake.health as u8;
-            if snake.health == 0 {
-                continue;
-            }
-            lengths[snake_id.0 as usize] = snake.body.len() as u16;
-
-            let counts = &snake.body.iter().counts();
-
-            let head_idx = CellIndex::new(snake.head, width);
-            let mut next_index = head_idx;
-            for (idx, pos) in snake.body.iter().unique().enumerate() {
-                let cell_idx = CellIndex::new(*pos, width);
-                let count = counts.get(pos).unwrap();
-                if idx == 0 {
-                    assert!(cell_idx == head_idx);
-                    heads[snake_id.0 as usize] = head_idx;
-                }
-                cells[cell_idx.0.as_usize()] = if *count == TRIPLE_STACK {
-                    Cell::make_triple_stacked_piece(snake_id)
-                } else if *pos == snake.head {
-                    // head can never be doubled, so let's assert it here, the cost of
-                    // one comparison is worth the saftey imo
-                    assert!(*count != DOUBLE_STACK);
-                    let tail_index = CellIndex::new(*snake.body.back().unwrap(), width);
-                    Cell::make_snake_head(snake_id, tail_index)
-                } else if *count == DOUBLE_STACK {
-                    Cell::make_double_stacked_piece(snake_id, next_index)
-                } else {
-                    Cell::make_body_piece(snake_id, next_index)
-                };
-                next_index = cell_idx;
-            }
-        }
-        for y in 0..height {
-            for x in 0..width {
-                let position = Position {
-                    x: x as i32,
-                    y: y as i32,
-                };
-                let cell_idx: CellIndex<T> = CellIndex::new(position, width);
-                if game.board.hazards.contains(&position) {
-                    cells[cell_idx.0.as_usize()].set_hazard();
-                }
-                if game.board.food.contains(&position) {
-                    cells[cell_idx.0.as_usize()].set_food();
-                }
-            }
-        }
-
-        let dimensions = D::from_dimensions(width, height);
-
-        Ok(CellBoard {
-            cells,
-            heads,
-            healths,
-            lengths,
-            dimensions,
-            hazard_damage: game
-                .game
-                .ruleset
-                .settings
-                .as_ref()
-                .map(|s| s.hazard_damage_per_turn)
-                .unwrap_or(15) as u8,
-        })
-    }
     fn get_cell(&self, cell_index: CellIndex<T>) -> Cell<T> {
         self.cells[cell_index.0.as_usize()]
     }
@@ -407,6 +299,11 @@ impl<T: CN, D: Dimensions, const BOARD_SIZE: usize, const MAX_SNAKES: usize>
         self.get_cell(cell_idx).is_hazard()
     }
 
+    /// how many hazards are stacked on this cell
+    pub fn cell_hazard_count(&self, cell_idx: CellIndex<T>) -> u8 {
+        self.get_cell(cell_idx).hazard_count()
+    }
+
     /// determines if this cell is a snake head (including triple stacked)
     pub fn cell_is_snake_head(&self, cell_idx: CellIndex<T>) -> bool {
         self.get_cell(cell_idx).is_head()
@@ -417,22 +314,41 @@ impl<T: CN, D: Dimensions, const BOARD_SIZE: usize, const MAX_SNAKES: usize>
         self.get_cell(cell_idx).is_food()
     }
 
+    /// determines if this cell is a wall
+    pub fn cell_is_wall(&self, cell_idx: CellIndex<T>) -> bool {
+        self.get_cell(cell_idx).is_wall()
+    }
+
     /// determines if this cell is a snake body piece (including double stacked)
     pub fn cell_is_body(&self, cell_idx: CellIndex<T>) -> bool {
         self.get_cell(cell_idx).is_body()
     }
 
-    /// determin the width of the CellBoard
-    pub fn width() -> u8 {
-        (BOARD_SIZE as f32).sqrt() as u8
+    /// determines if a snake moving into this cell this turn would collide with a snake or a
+    /// wall. Tails that will move out of the way this turn are not considered blocking
+    pub fn cell_is_blocked_this_turn(&self, cell_idx: CellIndex<T>) -> bool {
+        let cell = self.get_cell(cell_idx);
+        if cell.is_head() || cell.is_stacked() || cell.is_wall() {
+            return true;
+        }
+        if !cell.is_snake_body_piece() {
+            return false;
+        }
+
+        let sid = cell.get_snake_id().unwrap();
+        let head = self.heads[sid.as_usize()];
+        self.get_cell(head).get_tail_position(head) != Some(cell_idx)
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::compact_representation::dimensions::Square;
+    use crate::compact_representation::dimensions::{Custom, Square};
+    use crate::compact_representation::CellBoardBuilder;
+    use crate::types::{build_snake_id_map, Move};
+    use crate::wire_representation::Position;
 
-    use super::CellBoard;
+    use super::{CellBoard, CellIndex, EvaluateMode};
     #[test]
     fn test_assert_consistent() {
         let inconsistent_fixture = include_str!("../../../../fixtures/inconsistent_fixture.json");
@@ -440,4 +356,41 @@ mod tests {
         let game = CellBoard::<u8, Square, { 11 * 11 }, 4>::from_packed_hash(&hm);
         assert!(!game.assert_consistency());
     }
+
+    #[test]
+    fn test_cell_in_direction_matches_position_math() {
+        let mut builder = CellBoardBuilder::new(7, 5);
+        builder
+            .add_snake(vec![Position::new(1, 1), Position::new(1, 0)], 100)
+            .unwrap();
+        builder.add_wall(Position::new(0, 3)).unwrap();
+        builder.add_wall(Position::new(6, 4)).unwrap();
+        let game = builder.game().clone();
+        let snake_ids = build_snake_id_map(&game);
+        let board =
+            CellBoard::<u8, Custom, { 7 * 5 }, 4>::convert_from_game(game, &snake_ids).unwrap();
+
+        for mode in [EvaluateMode::Standard, EvaluateMode::Wrapped].iter() {
+            for idx in 0..7 * 5 {
+                let from = CellIndex::<u8>::from_usize(idx);
+                for mv in Move::all_iter() {
+                    let position = from.into_position(7).add_vec(mv.to_vector());
+                    let expected = match mode {
+                        EvaluateMode::Standard if board.off_board(position) => None,
+                        EvaluateMode::Standard => Some(CellIndex::new(position, 7)),
+                        EvaluateMode::Wrapped => Some(board.as_wrapped_cell_index(position)),
+                    }
+                    .filter(|ci| !board.cell_is_wall(*ci));
+                    assert_eq!(
+                        board.cell_in_direction(from, mv, *mode),
+                        expected,
+                        "{:?} from {:?} in {:?}",
+                        mv,
+                        from.into_position(7),
+                        mode
+                    );
+                }
+            }
+        }
+    }
 }
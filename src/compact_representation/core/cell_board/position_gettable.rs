@@ -4,7 +4,7 @@ use crate::{
         CellNum,
     },
     types::PositionGettableGame,
-    wire_representation::Position,
+    types::Position,
 };
 
 use super::CellBoard;
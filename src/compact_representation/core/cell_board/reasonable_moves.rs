@@ -0,0 +1,157 @@
+use rand::{prelude::IteratorRandom, Rng};
+
+use crate::{
+    compact_representation::{core::dimensions::Dimensions, CellNum},
+    types::{
+        choose_weighted, HeadGettableGame, Move, MoveSetPositions, NeckQueryableGame,
+        ReasonableMove, ReasonableMoveLevel, SnakeId,
+    },
+};
+
+use super::{flood_fill::OccupancySchedule, CellBoard, CellIndex, EvaluateMode};
+
+impl<T: CellNum, D: Dimensions, const BOARD_SIZE: usize, const MAX_SNAKES: usize>
+    CellBoard<T, D, BOARD_SIZE, MAX_SNAKES>
+{
+    /// picks a random reasonable move for every living snake, see
+    /// [crate::types::RandomReasonableMovesGame] for what reasonable means at each level
+    pub fn random_reasonable_moves<'a>(
+        &'a self,
+        rng: &'a mut impl Rng,
+        mode: EvaluateMode,
+        level: ReasonableMoveLevel,
+    ) -> impl Iterator<Item = (SnakeId, ReasonableMove)> + 'a {
+        // built once for every snake's fills, rather than once per candidate move
+        let schedule = match level {
+            ReasonableMoveLevel::Basic => None,
+            ReasonableMoveLevel::SpaceAware => Some(OccupancySchedule::new(self, false)),
+        };
+        self.iter_healths()
+            .enumerate()
+            .filter(|(_, health)| **health > 0)
+            .map(move |(idx, _)| {
+                let sid = SnakeId(idx as u8);
+                let head = self.get_head_as_native_position(&sid);
+                let reasonable = self.reasonable_destinations(sid, mode);
+
+                let roomy = schedule.as_ref().and_then(|schedule| {
+                    let length = self.get_length(sid) as usize;
+                    Move::all_iter()
+                        .filter(|mv| {
+                            reasonable[mv.as_index()]
+                                .map(|ci| {
+                                    self.bounded_flood_fill(schedule, ci, mode, length) >= length
+                                })
+                                .unwrap_or(false)
+                        })
+                        .choose(rng)
+                });
+
+                let choice = match roomy.or_else(|| {
+                    Move::all_iter()
+                        .filter(|mv| reasonable[mv.as_index()].is_some())
+                        .choose(rng)
+                }) {
+                    Some(mv) => ReasonableMove { mv, forced: false },
+                    None => self.forced_move(sid, head, mode, rng),
+                };
+                (sid, choice)
+            })
+    }
+
+    /// picks a reasonable move for every living snake in proportion to `weight`, see
+    /// [crate::types::WeightedReasonableMovesGame]
+    pub fn random_reasonable_moves_weighted<'a, W>(
+        &'a self,
+        rng: &'a mut impl Rng,
+        mode: EvaluateMode,
+        mut weight: W,
+    ) -> impl Iterator<Item = (SnakeId, ReasonableMove)> + 'a
+    where
+        W: FnMut(&SnakeId, Move, &CellIndex<T>) -> f64 + 'a,
+    {
+        self.iter_healths()
+            .enumerate()
+            .filter(|(_, health)| **health > 0)
+            .map(move |(idx, _)| {
+                let sid = SnakeId(idx as u8);
+                let head = self.get_head_as_native_position(&sid);
+                let reasonable = self.reasonable_destinations(sid, mode);
+
+                let mut weighted = [(Move::Up, 0.0); 4];
+                let mut count = 0;
+                for mv in Move::all_iter() {
+                    if let Some(ci) = reasonable[mv.as_index()] {
+                        weighted[count] = (mv, weight(&sid, mv, &ci));
+                        count += 1;
+                    }
+                }
+                let choice = match choose_weighted(rng, &weighted[..count]) {
+                    Some(mv) => ReasonableMove { mv, forced: false },
+                    None => self.forced_move(sid, head, mode, rng),
+                };
+                (sid, choice)
+            })
+    }
+
+    /// the squares `sid`'s head could be on next turn, see
+    /// [crate::types::HeadSquaresGame::possible_head_squares]
+    pub fn possible_head_squares(
+        &self,
+        sid: SnakeId,
+        mode: EvaluateMode,
+    ) -> MoveSetPositions<CellIndex<T>> {
+        let mut squares = MoveSetPositions::empty();
+        if self.healths[sid.as_usize()] == 0 {
+            return squares;
+        }
+        let head = self.get_head_as_native_position(&sid);
+        for mv in Move::all_iter() {
+            if let Some(ci) = self
+                .cell_in_direction(head, mv, mode)
+                .filter(|ci| !self.is_neck(&sid, ci))
+            {
+                squares.insert(mv, ci);
+            }
+        }
+        squares
+    }
+
+    /// where each of `sid`'s moves ends up, None for moves that aren't reasonable
+    fn reasonable_destinations(
+        &self,
+        sid: SnakeId,
+        mode: EvaluateMode,
+    ) -> [Option<CellIndex<T>>; 4] {
+        let squares = self.possible_head_squares(sid, mode);
+        let mut reasonable = [None; 4];
+        for (mv, ci) in squares.iter() {
+            if !self.cell_is_blocked_this_turn(*ci) {
+                reasonable[mv.as_index()] = Some(*ci);
+            }
+        }
+        reasonable
+    }
+
+    /// the least bad move for a snake without a reasonable one
+    fn forced_move(
+        &self,
+        sid: SnakeId,
+        head: CellIndex<T>,
+        mode: EvaluateMode,
+        rng: &mut impl Rng,
+    ) -> ReasonableMove {
+        let not_neck = |mv: &Move| {
+            self.cell_in_direction(head, *mv, mode)
+                .map(|ci| !self.is_neck(&sid, &ci))
+                .unwrap_or(true)
+        };
+        let mv = Move::all_iter()
+            .filter(not_neck)
+            .filter(|mv| self.cell_in_direction(head, *mv, mode).is_some())
+            .choose(rng)
+            .or_else(|| Move::all_iter().filter(not_neck).choose(rng))
+            .unwrap_or(Move::Up);
+        ReasonableMove { mv, forced: true }
+    }
+}
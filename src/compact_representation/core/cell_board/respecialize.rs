@@ -0,0 +1,56 @@
+use alloc::vec::Vec;
+
+use crate::{
+    compact_representation::{core::dimensions::Dimensions, CellNum},
+    types::SnakeId,
+    Error,
+};
+
+use super::{CellBoard, CellIndex};
+
+impl<T: CellNum, D: Dimensions, const BOARD_SIZE: usize, const MAX_SNAKES: usize>
+    CellBoard<T, D, BOARD_SIZE, MAX_SNAKES>
+{
+    /// This position on a board with room for `N` snakes, e.g. a duel board once all but one
+    /// opponent has been eliminated. You stay snake 0 and the other living snakes are numbered
+    /// after you in the order of their ids here. Along with the board comes the id each of
+    /// its snakes has on this board, indexed by its new id. Errors with
+    /// [Error::TooManySnakes] if you and the living snakes don't fit in `N`
+    pub fn respecialized<const N: usize>(
+        &self,
+    ) -> Result<(CellBoard<T, D, BOARD_SIZE, N>, Vec<SnakeId>), Error> {
+        let kept: Vec<SnakeId> = (0..MAX_SNAKES)
+            .filter(|i| *i == 0 || self.healths[*i] > 0)
+            .map(|i| SnakeId(i as u8))
+            .collect();
+        if kept.len() > N {
+            return Err(Error::TooManySnakes(kept.len()));
+        }
+
+        let mut new_ids = [None; MAX_SNAKES];
+        let mut board = CellBoard {
+            settings: self.settings,
+            cells: self.cells,
+            healths: [0; N],
+            heads: [CellIndex::from_i32(0); N],
+            lengths: [0; N],
+            extra_tail_pieces: [0; N],
+            dimensions: self.dimensions,
+        };
+        for (new, old) in kept.iter().enumerate() {
+            let old = old.as_usize();
+            new_ids[old] = Some(SnakeId(new as u8));
+            board.healths[new] = self.healths[old];
+            board.heads[new] = self.heads[old];
+            board.lengths[new] = self.lengths[old];
+            board.extra_tail_pieces[new] = self.extra_tail_pieces[old];
+        }
+        for cell in board.cells.iter_mut() {
+            if let Some(old) = cell.get_snake_id() {
+                cell.id = new_ids[old.as_usize()].expect("only living snakes are on the board");
+            }
+        }
+
+        Ok((board, kept))
+    }
+}
@@ -1,3 +1,6 @@
+use alloc::boxed::Box;
+use alloc::vec::Vec;
+
 use crate::{
     compact_representation::{core::dimensions::Dimensions, CellNum},
     types::{HeadGettableGame, SnakeBodyGettableGame},
@@ -9,12 +12,14 @@ impl<T: CellNum, D: Dimensions, const BOARD_SIZE: usize, const MAX_SNAKES: usize
     SnakeBodyGettableGame for CellBoard<T, D, BOARD_SIZE, MAX_SNAKES>
 {
     fn get_snake_body_vec(&self, snake_id: &Self::SnakeIDType) -> Vec<Self::NativePositionType> {
-        let mut body = vec![];
-        body.reserve(self.get_length(*snake_id).into());
+        let mut body = Vec::with_capacity(self.get_length(*snake_id).into());
         let head = self.get_head_as_native_position(snake_id);
 
-        let mut cur = Some(self.get_cell(head).get_tail_position(head).unwrap());
+        let tail = self.get_cell(head).get_tail_position(head).unwrap();
+        let extra = self.extra_tail_pieces[snake_id.as_usize()] as usize;
+        body.extend(core::iter::repeat_n(tail, extra));
 
+        let mut cur = Some(tail);
         while let Some(c) = cur {
             body.push(c);
             if self.get_cell(c).is_double_stacked_piece() {
@@ -40,7 +45,7 @@ impl<T: CellNum, D: Dimensions, const BOARD_SIZE: usize, const MAX_SNAKES: usize
 
         let mut cur = Some(self.get_cell(head).get_tail_position(head).unwrap());
 
-        Box::new(std::iter::from_fn(move || {
+        Box::new(core::iter::from_fn(move || {
             if let Some(c) = cur {
                 let to_return = c;
                 cur = self.get_cell(c).get_next_index();
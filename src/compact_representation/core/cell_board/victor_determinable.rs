@@ -2,7 +2,7 @@ use itertools::Itertools;
 
 use crate::{
     compact_representation::{core::dimensions::Dimensions, CellNum},
-    types::{SnakeId, VictorDeterminableGame},
+    types::{GameResult, SnakeId, VictorDeterminableGame},
 };
 
 use super::CellBoard;
@@ -40,4 +40,13 @@ impl<T: CellNum, D: Dimensions, const BOARD_SIZE: usize, const MAX_SNAKES: usize
     fn alive_snake_count(&self) -> usize {
         self.healths.iter().filter(|h| **h != 0).count()
     }
+
+    fn game_result(&self) -> GameResult<Self::SnakeIDType> {
+        let mut alive = (0..MAX_SNAKES).filter(|i| self.healths[*i] != 0);
+        match (alive.next(), alive.next()) {
+            (None, _) => GameResult::Draw,
+            (Some(winner), None) => GameResult::Winner(SnakeId(winner as u8)),
+            _ => GameResult::Ongoing,
+        }
+    }
 }
@@ -0,0 +1,122 @@
+use crate::{
+    compact_representation::{
+        core::{
+            bitboard::{BitBoard, BoardMasks},
+            dimensions::Dimensions,
+        },
+        CellNum,
+    },
+    types::{SnakeId, VoronoiOptions, VoronoiTieBreak},
+};
+
+use super::{flood_fill::OccupancySchedule, CellBoard, EvaluateMode};
+
+impl<T: CellNum, D: Dimensions, const BOARD_SIZE: usize, const MAX_SNAKES: usize>
+    CellBoard<T, D, BOARD_SIZE, MAX_SNAKES>
+{
+    /// the number of cells controlled by each snake, indexed by snake id, see
+    /// [crate::types::VoronoiGame]
+    pub fn voronoi(&self, options: VoronoiOptions, mode: EvaluateMode) -> [usize; MAX_SNAKES] {
+        let mut counts = [0; MAX_SNAKES];
+        self.voronoi_rings(options, mode, |idx, _, ring| counts[idx] += ring.count());
+        counts
+    }
+
+    /// the snake controlling each cell and the turn it gets there, indexed by cell, see
+    /// [crate::types::VoronoiGame::voronoi_owners]
+    pub fn voronoi_owners(
+        &self,
+        options: VoronoiOptions,
+        mode: EvaluateMode,
+    ) -> [Option<(SnakeId, u32)>; BOARD_SIZE] {
+        let mut owners = [None; BOARD_SIZE];
+        for (idx, health) in self.healths.iter().enumerate() {
+            if *health > 0 {
+                owners[self.heads[idx].as_usize()] = Some((SnakeId(idx as u8), 0));
+            }
+        }
+        self.voronoi_rings(options, mode, |idx, turn, ring| {
+            for ci in ring.iter::<T>() {
+                owners[ci.as_usize()] = Some((SnakeId(idx as u8), turn));
+            }
+        });
+        owners
+    }
+
+    /// expands every living snake one move at a time, calling `claim` with the snake, the turn
+    /// and the cells it takes on that turn
+    fn voronoi_rings(
+        &self,
+        options: VoronoiOptions,
+        mode: EvaluateMode,
+        mut claim: impl FnMut(usize, u32, &BitBoard<BOARD_SIZE>),
+    ) {
+        let masks = BoardMasks::new(self.get_actual_width(), self.get_actual_height());
+        let schedule = OccupancySchedule::new(self, options.hazards_block);
+        let mut occupancy = schedule.at(0);
+
+        let mut frontiers = [BitBoard::<BOARD_SIZE>::empty(); MAX_SNAKES];
+        let mut claimed = BitBoard::empty();
+        for (idx, health) in self.healths.iter().enumerate() {
+            if *health > 0 {
+                frontiers[idx].set(self.heads[idx]);
+                claimed.set(self.heads[idx]);
+            }
+        }
+
+        let mut turn = 0;
+        loop {
+            turn += 1;
+            occupancy.advance_to(turn);
+            let passable = occupancy.passable().and_not(&claimed);
+
+            // every snake expands by one ring before any cell is handed out, so cells reached by
+            // more than one snake on the same turn can be found
+            let mut reached = [BitBoard::<BOARD_SIZE>::empty(); MAX_SNAKES];
+            let mut reached_once = BitBoard::empty();
+            let mut contested = BitBoard::empty();
+            for (frontier, reached) in frontiers.iter().zip(reached.iter_mut()) {
+                if frontier.is_empty() {
+                    continue;
+                }
+                *reached = masks.neighbors(frontier, mode).and(&passable);
+                contested.or_assign(&reached_once.and(reached));
+                reached_once.or_assign(reached);
+            }
+            if reached_once.is_empty() {
+                break;
+            }
+
+            for (frontier, reached) in frontiers.iter_mut().zip(reached.iter()) {
+                *frontier = reached.and_not(&contested);
+            }
+            if let VoronoiTieBreak::LongerSnake = options.tie_break {
+                for ci in contested.iter::<T>() {
+                    let mut longest = 0;
+                    let mut winner = None;
+                    for (idx, reached) in reached.iter().enumerate() {
+                        if !reached.get(ci) {
+                            continue;
+                        }
+                        if self.lengths[idx] > longest {
+                            longest = self.lengths[idx];
+                            winner = Some(idx);
+                        } else if self.lengths[idx] == longest {
+                            winner = None;
+                        }
+                    }
+                    if let Some(idx) = winner {
+                        frontiers[idx].set(ci);
+                    }
+                }
+            }
+
+            claimed.or_assign(&reached_once);
+            for (idx, frontier) in frontiers.iter().enumerate() {
+                if !frontier.is_empty() {
+                    claim(idx, turn as u32, frontier);
+                }
+            }
+        }
+    }
+}
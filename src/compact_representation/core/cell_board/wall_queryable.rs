@@ -0,0 +1,14 @@
+use crate::{
+    compact_representation::{core::dimensions::Dimensions, CellNum},
+    types::WallQueryableGame,
+};
+
+use super::CellBoard;
+
+impl<T: CellNum, D: Dimensions, const BOARD_SIZE: usize, const MAX_SNAKES: usize> WallQueryableGame
+    for CellBoard<T, D, BOARD_SIZE, MAX_SNAKES>
+{
+    fn is_wall(&self, pos: &Self::NativePositionType) -> bool {
+        self.cell_is_wall(*pos)
+    }
+}
@@ -1,8 +1,8 @@
-use std::fmt::Display;
+use core::fmt::Display;
 
 /// Wrapper type for numbers to allow for shrinking board sizes
 pub trait CellNum:
-    std::fmt::Debug + Copy + Clone + PartialEq + Eq + std::hash::Hash + Ord + Display + 'static
+    core::fmt::Debug + Copy + Clone + PartialEq + Eq + core::hash::Hash + Ord + Display + 'static
 {
     /// converts this cellnum to a usize
     fn as_usize(&self) -> usize;
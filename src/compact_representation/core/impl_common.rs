@@ -45,6 +45,10 @@ macro_rules! impl_common_board_traits {
             fn alive_snake_count(&self) -> usize {
                 self.embedded.alive_snake_count()
             }
+
+            fn game_result(&self) -> GameResult<Self::SnakeIDType> {
+                self.embedded.game_result()
+            }
         }
 
         impl<T: CN, D: Dimensions, const BOARD_SIZE: usize, const MAX_SNAKES: usize>
@@ -62,7 +66,7 @@ macro_rules! impl_common_board_traits {
         impl<T: CN, D: Dimensions, const BOARD_SIZE: usize, const MAX_SNAKES: usize>
             FoodGettableGame for $type<T, D, BOARD_SIZE, MAX_SNAKES>
         {
-            fn get_all_food_as_positions(&self) -> Vec<$crate::wire_representation::Position> {
+            fn get_all_food_as_positions(&self) -> Vec<$crate::types::Position> {
                 self.embedded.get_all_food_as_positions()
             }
 
@@ -77,7 +81,7 @@ macro_rules! impl_common_board_traits {
             fn get_head_as_position(
                 &self,
                 snake_id: &Self::SnakeIDType,
-            ) -> $crate::wire_representation::Position {
+            ) -> $crate::types::Position {
                 self.embedded.get_head_as_position(snake_id)
             }
 
@@ -101,6 +105,18 @@ macro_rules! impl_common_board_traits {
             }
         }
 
+        impl<T: CN, D: Dimensions, const BOARD_SIZE: usize, const MAX_SNAKES: usize>
+            FoodSettableGame for $type<T, D, BOARD_SIZE, MAX_SNAKES>
+        {
+            fn place_food(&mut self, pos: Self::NativePositionType) -> Result<(), $crate::Error> {
+                self.embedded.place_food(pos)
+            }
+
+            fn remove_food(&mut self, pos: Self::NativePositionType) {
+                self.embedded.remove_food(pos)
+            }
+        }
+
         impl<T: CN, D: Dimensions, const BOARD_SIZE: usize, const MAX_SNAKES: usize>
             HazardQueryableGame for $type<T, D, BOARD_SIZE, MAX_SNAKES>
         {
@@ -108,9 +124,21 @@ macro_rules! impl_common_board_traits {
                 self.embedded.is_hazard(pos)
             }
 
-            fn get_hazard_damage(&self) -> u8 {
+            fn get_hazard_damage(&self) -> i16 {
                 self.embedded.get_hazard_damage()
             }
+
+            fn get_hazard_count(&self, pos: &Self::NativePositionType) -> u8 {
+                self.embedded.get_hazard_count(pos)
+            }
+        }
+
+        impl<T: CN, D: Dimensions, const BOARD_SIZE: usize, const MAX_SNAKES: usize>
+            WallQueryableGame for $type<T, D, BOARD_SIZE, MAX_SNAKES>
+        {
+            fn is_wall(&self, pos: &Self::NativePositionType) -> bool {
+                self.embedded.is_wall(pos)
+            }
         }
 
         impl<T: CN, D: Dimensions, const BOARD_SIZE: usize, const MAX_SNAKES: usize>
@@ -162,7 +190,7 @@ macro_rules! impl_common_board_traits {
         impl<T: CN, D: Dimensions, const BOARD_SIZE: usize, const MAX_SNAKES: usize> Display
             for $type<T, D, BOARD_SIZE, MAX_SNAKES>
         {
-            fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
                 self.embedded.fmt(f)
             }
         }
@@ -198,13 +226,14 @@ macro_rules! impl_common_board_traits {
             }
         }
 
+        #[cfg(feature = "std")]
         impl<T: CN, D: Dimensions, const BOARD_SIZE: usize, const MAX_SNAKES: usize>
-            std::convert::TryFrom<Game> for $type<T, D, BOARD_SIZE, MAX_SNAKES>
+            core::convert::TryFrom<Game> for $type<T, D, BOARD_SIZE, MAX_SNAKES>
         {
-            type Error = Box<dyn Error>;
+            type Error = $crate::Error;
 
-            fn try_from(game: Game) -> Result<Self, Box<dyn Error>> {
-                let id_map = $crate::types::build_snake_id_map(&game);
+            fn try_from(game: Game) -> Result<Self, $crate::Error> {
+                let id_map = $crate::types::try_build_snake_id_map(&game)?;
 
                 $type::convert_from_game(game, &id_map)
             }
@@ -1,16 +1,20 @@
+mod bitboard;
 mod cell_board;
 mod cell_num;
 mod impl_common;
 mod simulate;
 
+use core::convert::TryFrom;
+
 use crate::{
-    types::{Move, SnakeId},
-    wire_representation::Position,
+    types::Position,
+    types::{Move, SnakeId, Vector},
+    Error,
 };
 
-pub use cell_board::{CellBoard, EvaluateMode};
+pub use cell_board::{BytesVisitor, CellBoard, CellBoardDelta, EvaluateMode, InvariantViolation};
 pub use cell_num::CellNum;
-pub use simulate::simulate_with_moves;
+pub use simulate::{advance_with_moves, simulate_with_moves, simulate_with_moves_into};
 
 /// wrapper type for an index in to the board
 #[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Ord, PartialOrd)]
@@ -18,9 +22,11 @@ pub use simulate::simulate_with_moves;
 pub struct CellIndex<T: CellNum>(pub T);
 
 impl<T: CellNum> CellIndex<T> {
-    /// makes a new cell index from a position, needs to know the width of the board
+    /// makes a new cell index from a position, needs to know the width of the board. Cells are
+    /// indexed a row at a time from the bottom row up, `y * width + x`, so index 0 is the bottom
+    /// left square and not the top left one as the board is drawn, see [Position]
     pub fn new(pos: Position, width: u8) -> Self {
-        Self(T::from_i32(pos.y * width as i32 + pos.x))
+        Self(T::from_i32(pos.to_flat_index(width)))
     }
 
     /// build a CellIndex from a usize
@@ -45,15 +51,69 @@ impl<T: CellNum> CellIndex<T> {
 
     /// converts a cellindex to a position
     pub fn into_position(self, width: u8) -> Position {
-        let y = (self.0.as_usize() as i32 / width as i32) as i32;
-        let x = (self.0.as_usize() as i32 % width as i32) as i32;
-        Position { x, y }
+        Position::from_flat_index(self.0.as_usize() as i32, width)
     }
 
     /// Returns the CellIndex from moving in the direction of Move
     pub fn in_direction(&self, m: &Move, width: u8) -> Self {
         Self::new(self.into_position(width).add_vec(m.to_vector()), width)
     }
+
+    /// makes a cell index from coordinates, needs to know the width of the board
+    pub fn from_coord(coord: CellCoord, width: u8) -> Self {
+        Self::from_usize(coord.y as usize * width as usize + coord.x as usize)
+    }
+
+    /// the coordinates of this cell, needs to know the width of the board
+    pub fn into_coord(self, width: u8) -> CellCoord {
+        let idx = self.as_usize();
+        CellCoord {
+            x: (idx % width as usize) as u8,
+            y: (idx / width as usize) as u8,
+        }
+    }
+}
+
+/// The coordinates of a square on a compact board. Compact boards are at most 255 squares a
+/// side, so each coordinate fits in a u8, where a wire [Position] uses an i32 and a [Vector] an
+/// i64
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Ord, PartialOrd)]
+pub struct CellCoord {
+    /// x position, 0 is the left column
+    pub x: u8,
+    /// y position, 0 is the bottom row
+    pub y: u8,
+}
+
+impl CellCoord {
+    #[allow(missing_docs)]
+    pub const fn new(x: u8, y: u8) -> Self {
+        CellCoord { x, y }
+    }
+}
+
+impl TryFrom<Position> for CellCoord {
+    type Error = Error;
+
+    /// Errors with [Error::PositionDoesNotFit] if either coordinate is negative or more than 254
+    fn try_from(pos: Position) -> Result<Self, Self::Error> {
+        match (u8::try_from(pos.x), u8::try_from(pos.y)) {
+            (Ok(x), Ok(y)) if x < u8::MAX && y < u8::MAX => Ok(CellCoord { x, y }),
+            _ => Err(Error::PositionDoesNotFit(pos)),
+        }
+    }
+}
+
+impl From<CellCoord> for Position {
+    fn from(coord: CellCoord) -> Self {
+        Position::new(coord.x as i32, coord.y as i32)
+    }
+}
+
+impl From<CellCoord> for Vector {
+    fn from(coord: CellCoord) -> Self {
+        Vector::new(coord.x as i64, coord.y as i64)
+    }
 }
 
 const SNAKE_HEAD: u8 = 0x06;
@@ -62,9 +122,15 @@ const DOUBLE_STACKED_PIECE: u8 = 0x02;
 const TRIPLE_STACKED_PIECE: u8 = 0x03;
 const FOOD: u8 = 0x04;
 const EMPTY: u8 = 0x05;
+/// a square no snake can move on to, see [crate::types::WallQueryableGame]
+const WALL: u8 = 0x07;
 const KIND_MASK: u8 = 0x07;
 
-const IS_HAZARD: u8 = 0x10;
+/// how many hazards are stacked on the cell, in bits 4 to 6 of the flags
+const HAZARD_MASK: u8 = 0x70;
+const HAZARD_SHIFT: u8 = 4;
+/// the most hazards a cell can count, more than this are counted as this many
+pub const MAX_HAZARD_STACK: u8 = HAZARD_MASK >> HAZARD_SHIFT;
 
 pub const TRIPLE_STACK: usize = 3;
 pub const DOUBLE_STACK: usize = 2;
@@ -102,6 +168,7 @@ impl<T: CellNum> Cell<T> {
         value
     }
 
+    #[cfg(feature = "std")]
     pub fn from_u32(value: u32) -> Self {
         let flags = (value & 0xff) as u8;
         let id = SnakeId(((value >> 8) & 0xff) as u8);
@@ -125,16 +192,45 @@ impl<T: CellNum> Cell<T> {
         self.flags & KIND_MASK == FOOD
     }
 
+    pub fn is_wall(&self) -> bool {
+        self.flags & KIND_MASK == WALL
+    }
+
+    /// makes this cell a wall, preserving the cell's hazard status
+    pub fn set_wall(&mut self) {
+        self.flags = (self.flags & !KIND_MASK) | WALL;
+        self.id = SnakeId(0);
+        self.idx = CellIndex(T::from_i32(0));
+    }
+
+    /// makes this cell a hazard, leaving it alone if it already is one
     pub fn set_hazard(&mut self) {
-        self.flags |= IS_HAZARD
+        if !self.is_hazard() {
+            self.set_hazard_count(1);
+        }
+    }
+
+    /// stacks one more hazard on this cell
+    pub fn add_hazard(&mut self) {
+        self.set_hazard_count(self.hazard_count().saturating_add(1));
     }
 
     pub fn clear_hazard(&mut self) {
-        self.flags &= !IS_HAZARD
+        self.flags &= !HAZARD_MASK
     }
 
     pub fn is_hazard(&self) -> bool {
-        self.flags & IS_HAZARD != 0
+        self.flags & HAZARD_MASK != 0
+    }
+
+    /// how many hazards are stacked on this cell, at most [MAX_HAZARD_STACK]
+    pub fn hazard_count(&self) -> u8 {
+        (self.flags & HAZARD_MASK) >> HAZARD_SHIFT
+    }
+
+    fn set_hazard_count(&mut self, count: u8) {
+        let count = count.min(MAX_HAZARD_STACK);
+        self.flags = (self.flags & !HAZARD_MASK) | (count << HAZARD_SHIFT);
     }
 
     pub fn is_body_segment(&self) -> bool {
@@ -167,14 +263,7 @@ impl<T: CellNum> Cell<T> {
         }
     }
 
-    pub fn make_snake_head(sid: SnakeId, tail_index: CellIndex<T>) -> Self {
-        Cell {
-            flags: SNAKE_HEAD,
-            id: sid,
-            idx: tail_index,
-        }
-    }
-
+    #[cfg(test)]
     pub fn make_body_piece(sid: SnakeId, next_index: CellIndex<T>) -> Self {
         Cell {
             flags: SNAKE_BODY_PIECE,
@@ -183,6 +272,7 @@ impl<T: CellNum> Cell<T> {
         }
     }
 
+    #[cfg(test)]
     pub fn make_double_stacked_piece(sid: SnakeId, next_index: CellIndex<T>) -> Self {
         Cell {
             flags: DOUBLE_STACKED_PIECE,
@@ -191,14 +281,6 @@ impl<T: CellNum> Cell<T> {
         }
     }
 
-    pub fn make_triple_stacked_piece(sid: SnakeId) -> Self {
-        Cell {
-            flags: TRIPLE_STACKED_PIECE,
-            id: sid,
-            idx: CellIndex(T::from_i32(0)),
-        }
-    }
-
     pub fn is_snake_body_piece(&self) -> bool {
         self.flags & KIND_MASK == SNAKE_BODY_PIECE
     }
@@ -237,6 +319,12 @@ impl<T: CellNum> Cell<T> {
         self.idx = next_pos;
     }
 
+    pub fn set_triple_stacked(&mut self, sid: SnakeId) {
+        self.flags = (self.flags & !KIND_MASK) | TRIPLE_STACKED_PIECE;
+        self.id = sid;
+        self.idx = CellIndex(T::from_i32(0));
+    }
+
     pub fn get_snake_id(&self) -> Option<SnakeId> {
         if self.is_body_segment() || self.is_head() {
             Some(self.id)
@@ -248,4 +336,54 @@ impl<T: CellNum> Cell<T> {
     pub fn get_idx(&self) -> CellIndex<T> {
         self.idx
     }
+
+    pub fn set_idx(&mut self, idx: CellIndex<T>) {
+        self.idx = idx;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::mem::size_of;
+
+    use super::*;
+
+    #[test]
+    fn test_compact_sizes() {
+        // positions on compact boards, and in the queues and paths of their algorithms, are a
+        // single cell number rather than a pair of coordinates
+        assert_eq!(size_of::<CellIndex<u8>>(), 1);
+        assert_eq!(size_of::<CellCoord>(), 2);
+        assert_eq!(size_of::<CellIndex<u16>>(), 2);
+        assert_eq!(size_of::<Cell<u8>>(), 3);
+        assert_eq!(size_of::<Cell<u16>>(), 4);
+    }
+
+    #[test]
+    fn test_cell_coord_conversions() {
+        let coord = CellCoord::try_from(Position::new(3, 7)).unwrap();
+        assert_eq!(coord, CellCoord::new(3, 7));
+        assert_eq!(Position::from(coord), Position::new(3, 7));
+        assert_eq!(Vector::from(coord), Vector::new(3, 7));
+
+        let ci = CellIndex::<u8>::from_coord(coord, 11);
+        assert_eq!(ci, CellIndex::new(Position::new(3, 7), 11));
+        assert_eq!(ci.into_coord(11), coord);
+        let far = CellCoord::new(254, 254);
+        assert_eq!(CellIndex::<u16>::from_coord(far, 255).into_coord(255), far);
+
+        for pos in [
+            Position::new(-1, 3),
+            Position::new(-5, -5),
+            Position::new(2, 255),
+            Position::new(1000, 0),
+        ]
+        .iter()
+        {
+            match CellCoord::try_from(*pos) {
+                Err(Error::PositionDoesNotFit(p)) => assert_eq!(p, *pos),
+                other => panic!("{:?} converted to {:?}", pos, other),
+            }
+        }
+    }
 }
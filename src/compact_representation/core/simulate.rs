@@ -1,11 +1,24 @@
-use std::{borrow::Borrow, time::Instant};
+use alloc::boxed::Box;
+use alloc::format;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::{borrow::Borrow, time::Duration};
 
 use itertools::Itertools;
 
-use crate::types::{Action, Move, SimulatorInstruments, SnakeId, N_MOVES};
+use crate::types::{
+    check_one_move_each, Action, Move, SimulationSettings, SimulatorInstruments, SnakeId,
+    SnakeMove, N_MOVES,
+};
+use crate::Error;
 
 use super::{cell_board::EvaluateMode, dimensions::Dimensions, CellBoard, CellNum};
 
+/// Simulates the moves given for each snake, playing by `settings`. The resulting boards are
+/// evaluated lazily, so how long the simulation took, setting up and making every board that was
+/// asked for, is given to `instruments` once the iterator is used up or dropped. With the
+/// `tracing` feature each call gets a debug span, under the callers current span, with the
+/// number of snakes, the number of move combinations and that time as `elapsed_us`
 pub fn simulate_with_moves<
     'a,
     S,
@@ -16,15 +29,24 @@ pub fn simulate_with_moves<
     const MAX_SNAKES: usize,
 >(
     board: &'a CellBoard<T, D, BOARD_SIZE, MAX_SNAKES>,
-    instruments: &I,
+    instruments: &'a I,
     snake_ids_and_moves: impl IntoIterator<Item = (SnakeId, S)>,
     evaluate_mode: EvaluateMode,
+    settings: &SimulationSettings,
 ) -> Box<dyn Iterator<Item = (Action<MAX_SNAKES>, CellBoard<T, D, BOARD_SIZE, MAX_SNAKES>)> + 'a>
 where
     S: Borrow<[Move]>,
 {
-    let start = Instant::now();
+    let start = now();
     let snake_ids_and_moves = snake_ids_and_moves.into_iter().collect_vec();
+    #[cfg(any(test, feature = "tracing"))]
+    let span = tracing::debug_span!(
+        "simulate_with_moves",
+        snakes = snake_ids_and_moves.len(),
+        move_combinations = tracing::field::Empty,
+        elapsed_us = tracing::field::Empty,
+    )
+    .entered();
 
     let mut snake_ids_we_are_simulating = [false; MAX_SNAKES];
     for (snake_id, _) in snake_ids_and_moves.iter() {
@@ -35,7 +57,7 @@ where
     // sid major, move minor
     // [ some_reulst_struct, some_dead_struct ]
     // [ some_dead_struct, some_dead_struct ] // snake we didn't simulate
-    let states = board.generate_state(snake_ids_and_moves.iter(), evaluate_mode);
+    let states = board.generate_state(snake_ids_and_moves.iter(), evaluate_mode, settings);
     let mut dead_snakes_table = [[false; N_MOVES]; MAX_SNAKES];
 
     for (sid, result_row) in states.iter().enumerate() {
@@ -44,10 +66,20 @@ where
         }
     }
 
-    let ids_and_moves_product = snake_ids_and_moves
+    let moves_per_snake = snake_ids_and_moves
         .into_iter()
         .map(|(snake_id, moves)| {
-            let first_move = moves.borrow()[0];
+            let first_move = match moves.borrow().first() {
+                Some(mv) => *mv,
+                None => {
+                    #[cfg(any(test, feature = "tracing"))]
+                    tracing::error!(
+                        snake = snake_id.0,
+                        "asked to simulate a snake with no moves"
+                    );
+                    panic!("{:?} was given no moves to simulate", snake_id);
+                }
+            };
             let mvs = moves
                 .borrow()
                 .iter()
@@ -55,26 +87,624 @@ where
                 .map(|mv| (snake_id, *mv))
                 .collect_vec();
             if mvs.is_empty() {
+                #[cfg(any(test, feature = "tracing"))]
+                tracing::debug!(
+                    snake = snake_id.0,
+                    "every move kills this snake, only simulating its first move"
+                );
                 vec![(snake_id, first_move)]
             } else {
                 mvs
             }
         })
-        .multi_cartesian_product();
-    let results = ids_and_moves_product.into_iter().map(move |m| {
+        .collect_vec();
+    #[cfg(any(test, feature = "tracing"))]
+    span.record(
+        "move_combinations",
+        moves_per_snake.iter().map(Vec::len).product::<usize>(),
+    );
+    let ids_and_moves_product = moves_per_snake.into_iter().multi_cartesian_product();
+    let evaluate = move |m: &[(SnakeId, Move)]| {
         let action = Action::collect_from(m.iter());
 
-        let game = board.evaluate_moves_with_state(m.iter(), &states);
-        if !game.assert_consistency() {
-            panic!(
-                "caught an inconsistent simulate, moves: {:?} orig: {}, new: {}",
-                m, board, game
-            );
-        }
+        let game = board.evaluate_moves_with_state(m, &states);
+        check_simulated(board, m, &game);
         (action, game)
-    });
-    let return_value = Box::new(results);
-    let end = Instant::now();
-    instruments.observe_simulation(end - start);
-    return_value
+    };
+    // recording needs the instruments for every state, so the states are made up front
+    let boards: Box<dyn Iterator<Item = _> + 'a> = if records_states(instruments) {
+        let results = ids_and_moves_product
+            .map(|m| {
+                let (action, game) = evaluate(&m);
+                observe_state(instruments, &m, &game, evaluate_mode);
+                (action, game)
+            })
+            .collect_vec();
+        Box::new(results.into_iter())
+    } else {
+        Box::new(ids_and_moves_product.map(move |m| evaluate(&m)))
+    };
+    Box::new(Timed {
+        boards,
+        instruments,
+        elapsed: start.and_then(|start| Some(now()? - start)),
+        #[cfg(any(test, feature = "tracing"))]
+        span: span.exit(),
+    })
+}
+
+/// The boards from [simulate_with_moves], adding up the time spent making each of them so the
+/// whole simulation can be observed when it is used up or dropped. Time the caller spends
+/// between boards isn't counted
+struct Timed<'a, I: SimulatorInstruments, B> {
+    boards: B,
+    instruments: &'a I,
+    /// None when there is no clock
+    elapsed: Option<Duration>,
+    #[cfg(any(test, feature = "tracing"))]
+    span: tracing::Span,
+}
+
+impl<'a, I: SimulatorInstruments, B: Iterator> Iterator for Timed<'a, I, B> {
+    type Item = B::Item;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let elapsed = match self.elapsed {
+            Some(elapsed) => elapsed,
+            None => return self.boards.next(),
+        };
+        let start = now();
+        let next = self.boards.next();
+        if let (Some(start), Some(end)) = (start, now()) {
+            self.elapsed = Some(elapsed + (end - start));
+        }
+        next
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.boards.size_hint()
+    }
+}
+
+impl<'a, I: SimulatorInstruments, B> Drop for Timed<'a, I, B> {
+    fn drop(&mut self) {
+        if let Some(elapsed) = self.elapsed {
+            self.instruments.observe_simulation(elapsed);
+            #[cfg(any(test, feature = "tracing"))]
+            self.span.record("elapsed_us", elapsed.as_micros() as u64);
+        }
+    }
+}
+
+/// The same as [simulate_with_moves], but writes the results in to buffers owned by the caller
+/// instead of allocating. Both buffers are cleared first, then get one entry for each result,
+/// with the moves for `out_states[i]` at `out_moves[i]`. Moves given more than once for a snake
+/// are simulated once. Once the buffers are big enough this doesn't allocate, so a search can
+/// reuse the same two buffers for every node. `wrap` turns each board in to the type being
+/// collected
+#[allow(clippy::too_many_arguments)]
+pub fn simulate_with_moves_into<
+    S,
+    B,
+    I: SimulatorInstruments,
+    T: CellNum,
+    D: Dimensions,
+    const BOARD_SIZE: usize,
+    const MAX_SNAKES: usize,
+>(
+    board: &CellBoard<T, D, BOARD_SIZE, MAX_SNAKES>,
+    instruments: &I,
+    snake_ids_and_moves: impl IntoIterator<Item = (SnakeId, S)>,
+    evaluate_mode: EvaluateMode,
+    settings: &SimulationSettings,
+    out_states: &mut Vec<B>,
+    out_moves: &mut Vec<Action<MAX_SNAKES>>,
+    wrap: impl Fn(CellBoard<T, D, BOARD_SIZE, MAX_SNAKES>) -> B,
+) where
+    S: Borrow<[Move]>,
+{
+    let start = now();
+    out_states.clear();
+    out_moves.clear();
+
+    // the moves for each snake, in the order they were given, without repeats
+    let mut snakes = [SnakeId(0); MAX_SNAKES];
+    let mut moves = [[Move::Up; N_MOVES]; MAX_SNAKES];
+    let mut move_counts = [0; MAX_SNAKES];
+    let mut snake_count = 0;
+    for (snake_id, snake_moves) in snake_ids_and_moves {
+        let idx = snake_count;
+        snakes[idx] = snake_id;
+        snake_count += 1;
+        for mv in snake_moves.borrow() {
+            if !moves[idx][..move_counts[idx]].contains(mv) {
+                moves[idx][move_counts[idx]] = *mv;
+                move_counts[idx] += 1;
+            }
+        }
+        if move_counts[idx] == 0 {
+            panic!("{:?} was given no moves to simulate", snake_id);
+        }
+    }
+    #[cfg(any(test, feature = "tracing"))]
+    let span = tracing::debug_span!(
+        "simulate_with_moves_into",
+        snakes = snake_count,
+        move_combinations = tracing::field::Empty,
+    )
+    .entered();
+
+    let mut given: [(SnakeId, &[Move]); MAX_SNAKES] = [(SnakeId(0), &[]); MAX_SNAKES];
+    for (idx, (snake_id, snake_moves)) in given.iter_mut().take(snake_count).enumerate() {
+        *snake_id = snakes[idx];
+        *snake_moves = &moves[idx][..move_counts[idx]];
+    }
+    let states = board.generate_state(given[..snake_count].iter(), evaluate_mode, settings);
+
+    // moves that kill a snake are dropped, unless every move does
+    let mut alive_moves = [[Move::Up; N_MOVES]; MAX_SNAKES];
+    let mut alive_counts = [0; MAX_SNAKES];
+    for (idx, (snake_id, snake_moves)) in given[..snake_count].iter().enumerate() {
+        for mv in snake_moves.iter() {
+            if !states[snake_id.as_usize()][mv.as_index()].is_dead() {
+                alive_moves[idx][alive_counts[idx]] = *mv;
+                alive_counts[idx] += 1;
+            }
+        }
+        if alive_counts[idx] == 0 {
+            alive_moves[idx][0] = snake_moves[0];
+            alive_counts[idx] = 1;
+        }
+    }
+    let combinations = alive_counts[..snake_count].iter().product::<usize>();
+    #[cfg(any(test, feature = "tracing"))]
+    span.record("move_combinations", combinations);
+    out_states.reserve(combinations);
+    out_moves.reserve(combinations);
+
+    // count through every combination, the last snake's move changing fastest like
+    // multi_cartesian_product
+    let records_states = records_states(instruments);
+    let mut choice = [0; MAX_SNAKES];
+    let mut combination = [(SnakeId(0), Move::Up); MAX_SNAKES];
+    for _ in 0..combinations {
+        for idx in 0..snake_count {
+            combination[idx] = (snakes[idx], alive_moves[idx][choice[idx]]);
+        }
+        let combination = &combination[..snake_count];
+        let next = board.evaluate_moves_with_state(combination, &states);
+        check_simulated(board, combination, &next);
+        if records_states {
+            observe_state(instruments, combination, &next, evaluate_mode);
+        }
+        out_moves.push(Action::collect_from(combination.iter()));
+        out_states.push(wrap(next));
+
+        for idx in (0..snake_count).rev() {
+            choice[idx] += 1;
+            if choice[idx] < alive_counts[idx] {
+                break;
+            }
+            choice[idx] = 0;
+        }
+    }
+
+    if let (Some(start), Some(end)) = (start, now()) {
+        instruments.observe_simulation(end - start);
+    }
+}
+
+/// The one board that comes from every living snake making its move in `moves`, with a full
+/// turn of eating, starving and collisions, see
+/// [crate::types::SimulableGame::advance_turn_with_moves].
+/// Errors unless there is exactly one move for each living snake
+pub fn advance_with_moves<
+    I: SimulatorInstruments,
+    T: CellNum,
+    D: Dimensions,
+    const BOARD_SIZE: usize,
+    const MAX_SNAKES: usize,
+>(
+    board: &CellBoard<T, D, BOARD_SIZE, MAX_SNAKES>,
+    instruments: &I,
+    moves: impl IntoIterator<Item = SnakeMove<SnakeId>>,
+    evaluate_mode: EvaluateMode,
+    settings: &SimulationSettings,
+) -> Result<CellBoard<T, D, BOARD_SIZE, MAX_SNAKES>, Error> {
+    let start = now();
+    let mut snake_ids = [SnakeId(0); MAX_SNAKES];
+    let mut given = [(SnakeId(0), [Move::Up]); MAX_SNAKES];
+    let mut moves_given = [(SnakeId(0), Move::Up); MAX_SNAKES];
+    let mut count = 0;
+    for SnakeMove { snake_id, mv } in moves {
+        if count == MAX_SNAKES {
+            return Err(Error::InvalidMoves(format!(
+                "there are more moves than the {} snakes a board can have",
+                MAX_SNAKES
+            )));
+        }
+        snake_ids[count] = snake_id;
+        given[count] = (snake_id, [mv]);
+        moves_given[count] = (snake_id, mv);
+        count += 1;
+    }
+    check_one_move_each(board, &snake_ids[..count])?;
+
+    let moves = &moves_given[..count];
+    let states = board.generate_state(given[..count].iter(), evaluate_mode, settings);
+    let next = board.evaluate_moves_with_state(moves, &states);
+    check_simulated(board, moves, &next);
+    if records_states(instruments) {
+        observe_state(instruments, moves, &next, evaluate_mode);
+    }
+    if let (Some(start), Some(end)) = (start, now()) {
+        instruments.observe_simulation(end - start);
+    }
+    Ok(next)
+}
+
+/// whether the instruments are given every state, which needs std to turn them in to json
+fn records_states<I: SimulatorInstruments>(instruments: &I) -> bool {
+    cfg!(feature = "std") && instruments.records_states()
+}
+
+/// hands `next` to the instruments as the json of a wire game, see [records_states]
+fn observe_state<
+    I: SimulatorInstruments,
+    T: CellNum,
+    D: Dimensions,
+    const BOARD_SIZE: usize,
+    const MAX_SNAKES: usize,
+>(
+    instruments: &I,
+    moves: &[(SnakeId, Move)],
+    next: &CellBoard<T, D, BOARD_SIZE, MAX_SNAKES>,
+    evaluate_mode: EvaluateMode,
+) {
+    #[cfg(feature = "std")]
+    {
+        let json = serde_json::to_string(&next.to_game(evaluate_mode))
+            .expect("wire games always serialize");
+        instruments.observe_state(moves, &json);
+    }
+    #[cfg(not(feature = "std"))]
+    let _ = (instruments, moves, next, evaluate_mode);
+}
+
+/// panics if a board the simulator made is broken
+fn check_simulated<T: CellNum, D: Dimensions, const BOARD_SIZE: usize, const MAX_SNAKES: usize>(
+    board: &CellBoard<T, D, BOARD_SIZE, MAX_SNAKES>,
+    moves: &[(SnakeId, Move)],
+    next: &CellBoard<T, D, BOARD_SIZE, MAX_SNAKES>,
+) {
+    if !next.assert_consistency() {
+        panic!(
+            "caught an inconsistent simulate, moves: {:?} orig: {}, new: {}",
+            moves, board, next
+        );
+    }
+    #[cfg(any(debug_assertions, feature = "strict-invariants"))]
+    if let Err(violation) = next.check_invariants() {
+        panic!(
+            "caught a broken invariant: {}, moves: {:?} orig: {}, new: {}",
+            violation, moves, board, next
+        );
+    }
+}
+
+/// the time since the first call, to take the difference of two. None when there is no clock,
+/// without std and on wasm32-unknown-unknown where `Instant::now` panics
+#[cfg(all(
+    feature = "std",
+    not(all(target_arch = "wasm32", target_os = "unknown"))
+))]
+fn now() -> Option<Duration> {
+    static START: std::sync::OnceLock<std::time::Instant> = std::sync::OnceLock::new();
+    Some(START.get_or_init(std::time::Instant::now).elapsed())
+}
+
+#[cfg(not(all(
+    feature = "std",
+    not(all(target_arch = "wasm32", target_os = "unknown"))
+)))]
+fn now() -> Option<Duration> {
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+    use std::fmt::Debug;
+    use std::sync::{Arc, Mutex};
+
+    use tracing::field::{Field, Visit};
+    use tracing::span::{Attributes, Id, Record};
+    use tracing::{Event, Metadata, Subscriber};
+
+    use crate::compact_representation::standard::ToBestCellBoard;
+    use crate::compact_representation::StandardCellBoard4Snakes11x11;
+    use crate::types::{build_snake_id_map, SimulableGame, SnakeIDGettableGame, StatsInstruments};
+    use crate::{game_fixture, Error};
+
+    use super::*;
+
+    #[derive(Debug)]
+    struct Instruments;
+    impl SimulatorInstruments for Instruments {
+        fn observe_simulation(&self, _: std::time::Duration) {}
+    }
+
+    #[derive(Debug, Clone, Default)]
+    struct CapturedSpan {
+        name: String,
+        parent: Option<u64>,
+        fields: HashMap<String, String>,
+    }
+
+    #[derive(Debug, Default)]
+    struct Captured {
+        spans: Vec<CapturedSpan>,
+        events: Vec<HashMap<String, String>>,
+        entered: Vec<u64>,
+    }
+
+    /// records every span and event, and which span each span was created under
+    #[derive(Debug, Clone, Default)]
+    struct Capture(Arc<Mutex<Captured>>);
+
+    struct Fields<'a>(&'a mut HashMap<String, String>);
+    impl Visit for Fields<'_> {
+        fn record_debug(&mut self, field: &Field, value: &dyn Debug) {
+            self.0
+                .insert(field.name().to_string(), format!("{:?}", value));
+        }
+    }
+
+    impl Subscriber for Capture {
+        fn enabled(&self, _: &Metadata<'_>) -> bool {
+            true
+        }
+
+        fn new_span(&self, attrs: &Attributes<'_>) -> Id {
+            let mut captured = self.0.lock().unwrap();
+            let parent = match attrs.parent() {
+                Some(parent) => Some(parent.into_u64()),
+                None if attrs.is_contextual() => captured.entered.last().copied(),
+                None => None,
+            };
+            let mut span = CapturedSpan {
+                name: attrs.metadata().name().to_string(),
+                parent,
+                ..Default::default()
+            };
+            attrs.record(&mut Fields(&mut span.fields));
+            captured.spans.push(span);
+            Id::from_u64(captured.spans.len() as u64)
+        }
+
+        fn record(&self, span: &Id, values: &Record<'_>) {
+            let mut captured = self.0.lock().unwrap();
+            let span = &mut captured.spans[span.into_u64() as usize - 1];
+            values.record(&mut Fields(&mut span.fields));
+        }
+
+        fn record_follows_from(&self, _: &Id, _: &Id) {}
+
+        fn event(&self, event: &Event<'_>) {
+            let mut fields = HashMap::new();
+            event.record(&mut Fields(&mut fields));
+            self.0.lock().unwrap().events.push(fields);
+        }
+
+        fn enter(&self, span: &Id) {
+            self.0.lock().unwrap().entered.push(span.into_u64());
+        }
+
+        fn exit(&self, _: &Id) {
+            self.0.lock().unwrap().entered.pop();
+        }
+    }
+
+    #[test]
+    fn test_simulation_span_nests_under_callers_span() {
+        let g = game_fixture(include_str!("../../../fixtures/start_of_game.json"));
+        let board: StandardCellBoard4Snakes11x11 =
+            g.as_cell_board(&build_snake_id_map(&g)).unwrap();
+        let capture = Capture::default();
+
+        let results = tracing::subscriber::with_default(capture.clone(), || {
+            let request = tracing::info_span!("request");
+            let _guard = request.enter();
+            let moves = [Move::Up, Move::Left];
+            board
+                .simulate_with_moves(
+                    &Instruments,
+                    board.get_snake_ids().into_iter().map(|sid| (sid, moves)),
+                )
+                .count()
+        });
+
+        let captured = capture.0.lock().unwrap();
+        assert_eq!(captured.spans.len(), 2);
+        let (request, simulate) = (&captured.spans[0], &captured.spans[1]);
+        assert_eq!(request.name, "request");
+        assert_eq!(simulate.name, "simulate_with_moves");
+        assert_eq!(simulate.parent, Some(1));
+        assert_eq!(simulate.fields["snakes"], "4");
+        assert_eq!(simulate.fields["move_combinations"], results.to_string());
+        assert!(results > 1);
+        assert!(simulate.fields["elapsed_us"].parse::<u64>().is_ok());
+        assert!(captured.entered.is_empty());
+    }
+
+    #[test]
+    fn test_simulation_is_observed_once_its_boards_are_made() {
+        let g = game_fixture(include_str!("../../../fixtures/start_of_game.json"));
+        let board: StandardCellBoard4Snakes11x11 =
+            g.as_cell_board(&build_snake_id_map(&g)).unwrap();
+        let instruments = StatsInstruments::new();
+        let moves = board
+            .get_snake_ids()
+            .into_iter()
+            .map(|sid| (sid, Move::all()))
+            .collect_vec();
+
+        let mut results = board.simulate_with_moves(&instruments, moves.clone());
+        results.next().unwrap();
+        assert_eq!(instruments.simulations(), 0);
+        drop(results);
+        assert_eq!(instruments.simulations(), 1);
+
+        board.simulate_with_moves(&instruments, moves).count();
+        assert_eq!(instruments.simulations(), 2);
+        assert!(instruments.total_time() > Duration::ZERO);
+    }
+
+    #[test]
+    fn test_bounded_simulation() {
+        let g = game_fixture(include_str!("../../../fixtures/start_of_game.json"));
+        let board: StandardCellBoard4Snakes11x11 =
+            g.as_cell_board(&build_snake_id_map(&g)).unwrap();
+        let moves = board
+            .get_snake_ids()
+            .into_iter()
+            .map(|sid| (sid, Move::all()))
+            .collect_vec();
+
+        let results = board
+            .simulate_with_moves_bounded(&Instruments, moves.clone(), 4 * 4 * 4 * 4)
+            .unwrap();
+        let unbounded = board.simulate_with_moves(&Instruments, moves.clone());
+        assert_eq!(results.len(), unbounded.count());
+        assert!(results.iter().all(|(_, next)| next.assert_consistency()));
+
+        match board.simulate_with_moves_bounded(&Instruments, moves, 4 * 4 * 4 * 4 - 1) {
+            Err(Error::TooManyStates { states, max_states }) => {
+                assert_eq!(states, 256);
+                assert_eq!(max_states, 255);
+            }
+            other => panic!("expected too many states, got {:?}", other.map(|r| r.len())),
+        }
+        let no_moves: [(SnakeId, &[Move]); 1] = [(SnakeId(0), &[])];
+        assert!(matches!(
+            board.simulate_with_moves_bounded(&Instruments, no_moves, 1),
+            Err(Error::InvalidMoves(_))
+        ));
+    }
+
+    #[test]
+    fn test_advance_turn_with_moves() {
+        let g = game_fixture(include_str!("../../../fixtures/late_stage.json"));
+        let board: StandardCellBoard4Snakes11x11 =
+            g.as_cell_board(&build_snake_id_map(&g)).unwrap();
+        let moves = board
+            .get_snake_ids()
+            .into_iter()
+            .map(|sid| (sid, Move::Left))
+            .collect_vec();
+
+        let next = board.advance_turn_with_moves(&Instruments, &moves).unwrap();
+        let (_, expected) = board
+            .simulate_with_moves(&Instruments, moves.iter().map(|(sid, mv)| (*sid, [*mv])))
+            .next()
+            .unwrap();
+        assert_eq!(next, expected);
+
+        assert!(matches!(
+            board.advance_turn_with_moves(&Instruments, &moves[1..]),
+            Err(Error::InvalidMoves(_))
+        ));
+        let mut twice = moves.clone();
+        twice.push(moves[0]);
+        assert!(matches!(
+            board.advance_turn_with_moves(&Instruments, &twice),
+            Err(Error::InvalidMoves(_))
+        ));
+        // the board has room for four snakes but only two are in the game
+        let mut dead = moves.clone();
+        dead.push((SnakeId(3), Move::Up));
+        assert!(matches!(
+            board.advance_turn_with_moves(&Instruments, &dead),
+            Err(Error::UnknownSnake(_))
+        ));
+    }
+
+    #[test]
+    fn test_simulate_into_matches_simulate() {
+        let fixtures = [
+            include_str!("../../../fixtures/start_of_game.json"),
+            include_str!("../../../fixtures/late_stage.json"),
+            include_str!("../../../fixtures/body_collision.json"),
+            include_str!("../../../fixtures/tree_search_collision.json"),
+        ];
+        let mut states = vec![];
+        let mut moves = vec![];
+        for fixture in fixtures.iter() {
+            let g = game_fixture(fixture);
+            let board: StandardCellBoard4Snakes11x11 =
+                g.as_cell_board(&build_snake_id_map(&g)).unwrap();
+            // repeated moves are only simulated once
+            let given = [
+                Move::Up,
+                Move::Down,
+                Move::Up,
+                Move::Left,
+                Move::Right,
+                Move::Left,
+            ];
+            let snakes = || board.get_snake_ids().into_iter().map(|sid| (sid, given));
+
+            board.simulate_with_moves_into(&Instruments, snakes(), &mut states, &mut moves);
+            let expected = board
+                .simulate_with_moves(
+                    &Instruments,
+                    board
+                        .get_snake_ids()
+                        .into_iter()
+                        .map(|sid| (sid, Move::all())),
+                )
+                .collect_vec();
+            assert_eq!(states.len(), expected.len());
+            assert_eq!(moves.len(), expected.len());
+            for ((state, action), (expected_action, expected_state)) in
+                states.iter().zip(moves.iter()).zip(expected.iter())
+            {
+                assert_eq!(state, expected_state);
+                assert_eq!(action.other_moves(), expected_action.other_moves());
+            }
+        }
+    }
+
+    #[test]
+    fn test_events_for_unusual_conditions() {
+        let capture = Capture::default();
+        tracing::subscriber::with_default(capture.clone(), || {
+            let g = game_fixture(include_str!("../../../fixtures/8x8board_valid.json"));
+            assert!(g.to_best_cell_board().is_ok());
+
+            let g = game_fixture(include_str!("../../../fixtures/start_of_game.json"));
+            let board: StandardCellBoard4Snakes11x11 =
+                g.as_cell_board(&build_snake_id_map(&g)).unwrap();
+            let no_moves: [(SnakeId, &[Move]); 1] = [(SnakeId(0), &[])];
+            assert!(board
+                .try_simulate_with_moves(&Instruments, no_moves)
+                .is_err());
+        });
+
+        let captured = capture.0.lock().unwrap();
+        let messages = captured
+            .events
+            .iter()
+            .map(|fields| fields["message"].as_str())
+            .collect_vec();
+        assert_eq!(
+            messages,
+            vec![
+                "no exactly sized board for this game, using a larger one",
+                "asked to simulate a snake with no moves"
+            ]
+        );
+        assert_eq!(captured.events[0]["width"], "8");
+        assert_eq!(captured.events[1]["snake"], "SnakeId(0)");
+    }
 }
@@ -16,6 +16,11 @@ pub trait Dimensions: Debug + Copy {
 
     /// Get the height of this dimension
     fn height(&self) -> u8;
+
+    /// can a board with this width and height be stored in this dimension
+    fn can_represent(_width: u8, _height: u8) -> bool {
+        true
+    }
 }
 
 #[derive(Debug, Copy, Clone, PartialEq, Eq)]
@@ -40,6 +45,10 @@ impl Dimensions for Square {
 
         Self { width }
     }
+
+    fn can_represent(width: u8, height: u8) -> bool {
+        width == height
+    }
 }
 
 #[derive(Debug, Copy, Clone, PartialEq, Eq)]
@@ -61,6 +70,10 @@ impl<const W: u8, const H: u8> Dimensions for Fixed<W, H> {
 
         Self
     }
+
+    fn can_represent(width: u8, height: u8) -> bool {
+        width == W && height == H
+    }
 }
 
 /// Alias for a [Fixed] board at the height and width for the ArcadeMaze map
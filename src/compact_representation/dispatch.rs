@@ -0,0 +1,109 @@
+//! picking the best compact board for a game at runtime
+
+use crate::types::SnakeIDMap;
+use crate::wire_representation::Game;
+
+/// Converts a wire game to the smallest compact board that fits it, wrapped or standard
+/// depending on its ruleset, and runs `body` with that board bound to `board`. `body` is
+/// compiled once for every board type, so it can call functions that are generic over the board
+/// and its traits. Evaluates to a `Result` of whatever `body` evaluates to, erroring if the game
+/// doesn't convert or is bigger than the biggest board, 50x50 with 16 snakes. Boards that aren't
+/// square and don't have an exact size fall back to a board which stores its width and height.
+/// 11x11 games down to 2 snakes get a board with room for just 2 if their ids in `snake_ids` are
+/// 0 and 1, e.g. from building the map every turn with
+/// [build_snake_id_map](crate::types::build_snake_id_map). A board for more snakes can switch to
+/// one at the start of a turn with `respecialized`
+///
+/// ```
+/// # use battlesnake_game_types::best_cell_board;
+/// # use battlesnake_game_types::types::{build_snake_id_map, SnakeIDGettableGame};
+/// # use battlesnake_game_types::wire_representation::Game;
+/// # let game: Game = serde_json::from_str(include_str!("../../fixtures/late_stage.json")).unwrap();
+/// let snake_ids = build_snake_id_map(&game);
+/// let alive = best_cell_board!(game, &snake_ids, |board| board.get_snake_ids().len()).unwrap();
+/// assert_eq!(alive, 2);
+/// ```
+#[macro_export]
+macro_rules! best_cell_board {
+    ($game:expr, $snake_ids:expr, |$board:ident| $body:expr) => {{
+        let game: $crate::wire_representation::Game = $game;
+        let snake_ids: &$crate::types::SnakeIDMap = $snake_ids;
+        if game.is_wrapped() {
+            $crate::__best_cell_board_arms!(wrapped, game, snake_ids, $board, $body)
+        } else {
+            $crate::__best_cell_board_arms!(standard, game, snake_ids, $board, $body)
+        }
+    }};
+}
+
+/// Very internal, the match over every board in `$module`'s BestCellBoard for
+/// [crate::best_cell_board]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __best_cell_board_arms {
+    ($module:ident, $game:expr, $snake_ids:expr, $board:ident, $body:expr) => {{
+        use $crate::compact_representation::$module::BestCellBoard;
+        match BestCellBoard::from_game($game, $snake_ids) {
+            Ok(BestCellBoard::Tiny($board)) => Ok({
+                let $board = *$board;
+                $body
+            }),
+            Ok(BestCellBoard::SmallExact($board)) => Ok({
+                let $board = *$board;
+                $body
+            }),
+            Ok(BestCellBoard::Standard($board)) => Ok({
+                let $board = *$board;
+                $body
+            }),
+            Ok(BestCellBoard::MediumExact($board)) => Ok({
+                let $board = *$board;
+                $body
+            }),
+            Ok(BestCellBoard::Duel($board)) => Ok({
+                let $board = *$board;
+                $body
+            }),
+            Ok(BestCellBoard::LargestU8($board)) => Ok({
+                let $board = *$board;
+                $body
+            }),
+            Ok(BestCellBoard::LargeExact($board)) => Ok({
+                let $board = *$board;
+                $body
+            }),
+            Ok(BestCellBoard::ArcadeMaze($board)) => Ok({
+                let $board = *$board;
+                $body
+            }),
+            Ok(BestCellBoard::Large($board)) => Ok({
+                let $board = *$board;
+                $body
+            }),
+            Ok(BestCellBoard::Silly($board)) => Ok({
+                let $board = *$board;
+                $body
+            }),
+            Ok(BestCellBoard::CustomU8($board)) => Ok({
+                let $board = *$board;
+                $body
+            }),
+            Ok(BestCellBoard::Custom($board)) => Ok({
+                let $board = *$board;
+                $body
+            }),
+            Err(e) => Err::<_, $crate::Error>(e),
+        }
+    }};
+}
+
+/// whether `game` is down to exactly 2 snakes, with the ids 0 and 1 in `snake_ids`, so that it
+/// fits a duel board
+pub(crate) fn is_duel(game: &Game, snake_ids: &SnakeIDMap) -> bool {
+    game.board.snakes.len() == 2
+        && game
+            .board
+            .snakes
+            .iter()
+            .all(|snake| matches!(snake_ids.get(&snake.id), Some(id) if id.as_usize() < 2))
+}
@@ -1,25 +1,48 @@
-//! A compact board representation that is efficient for simulation
+//! A compact board representation that is efficient for simulation. Boards are made from wire
+//! games with the `std` feature, and from [standard::CellBoard::from_bytes] without it
+#[cfg(feature = "std")]
+mod builder;
 mod core;
+#[cfg(feature = "std")]
+mod dispatch;
 pub mod standard;
 pub mod wrapped;
 
+#[cfg(feature = "std")]
+pub use self::builder::CellBoardBuilder;
+pub use self::core::CellCoord;
 pub use self::core::CellIndex;
 pub use self::core::CellNum;
+pub use self::core::InvariantViolation;
+pub use self::core::MAX_HAZARD_STACK;
 
 use self::dimensions::Square;
 
 pub mod dimensions;
 
-/// A cell board for a standard game (e.g. not wrapped or constrictor)
+/// A cell board for a game that doesn't wrap, e.g. standard, royale or constrictor
 pub type StandardCellBoard<T, D, const BOARD_SIZE: usize, const MAX_SNAKES: usize> =
     standard::CellBoard<T, D, BOARD_SIZE, MAX_SNAKES>;
 
 /// A standard mode board, 11x11 with 4 snakes
 pub type StandardCellBoard4Snakes11x11 = StandardCellBoard<u8, Square, { 11 * 11 }, 4>;
 
+/// A standard mode board for a game between exactly 2 snakes, smaller and quicker to simulate
+/// than a board with room for more
+pub type DuelCellBoard<T, D, const BOARD_SIZE: usize> = StandardCellBoard<T, D, BOARD_SIZE, 2>;
+
+/// A standard mode board, 11x11 with exactly 2 snakes
+pub type StandardDuelCellBoard11x11 = DuelCellBoard<u8, Square, { 11 * 11 }>;
+
+/// A standard mode board, 19x19 with 8 snakes
+pub type StandardCellBoard8Snakes19x19 = StandardCellBoard<u16, Square, { 19 * 19 }, 8>;
+
 /// A cell board for a wrapped game
 pub type WrappedCellBoard<T, D, const BOARD_SIZE: usize, const MAX_SNAKES: usize> =
     wrapped::CellBoard<T, D, BOARD_SIZE, MAX_SNAKES>;
 
 /// A wrapped mode board, 11x11 with 4 snakes
 pub type WrappedCellBoard4Snakes11x11 = WrappedCellBoard<u8, Square, { 11 * 11 }, 4>;
+
+/// A wrapped mode board, 11x11 with exactly 2 snakes
+pub type WrappedDuelCellBoard11x11 = WrappedCellBoard<u8, Square, { 11 * 11 }, 2>;
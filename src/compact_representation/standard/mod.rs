@@ -1,31 +1,51 @@
 //! A compact board representation that is efficient for simulation
 use crate::compact_representation::core::CellNum as CN;
 use crate::impl_common_board_traits;
+#[cfg(feature = "std")]
+use crate::types::{try_build_snake_id_map, SnakeIDMap};
 use crate::types::{
-    build_snake_id_map, Action, FoodGettableGame, FoodQueryableGame, HazardQueryableGame,
-    HazardSettableGame, HeadGettableGame, HealthGettableGame, LengthGettableGame,
-    NeckQueryableGame, PositionGettableGame, RandomReasonableMovesGame, SizeDeterminableGame,
-    SnakeIDGettableGame, SnakeIDMap, SnakeId, VictorDeterminableGame, YouDeterminableGame,
+    AStarOptions, Action, ChokePointGame, FloodFillOptions, FloodFillableGame, FoodGettableGame,
+    FoodQueryableGame, FoodRaceGame, FoodSettableGame, FutureOccupancyGame, GameResult,
+    HazardQueryableGame, HazardSettableGame, HeadGettableGame, HeadSquaresGame, HeadToHeadOutcome,
+    HeadToHeadPredictableGame, HealthGettableGame, LastMoveDeterminableGame, LengthGettableGame,
+    MoveSetPositions, NeckQueryableGame, PathfindableGame, PositionGettableGame,
+    RandomReasonableMovesGame, ReasonableMove, ReasonableMoveLevel, SizeDeterminableGame,
+    SnakeIDGettableGame, SnakeId, Symmetry, TailChaseGame, TrajectoryProjectableGame,
+    VictorDeterminableGame, VoronoiGame, VoronoiOptions, WallQueryableGame,
+    WeightedReasonableMovesGame, YouDeterminableGame,
 };
 /// you almost certainly want to use the `convert_from_game` method to
 /// cast from a json represention to a `CellBoard`
-use crate::types::{NeighborDeterminableGame, SnakeBodyGettableGame};
-use crate::wire_representation::Game;
-use rand::prelude::IteratorRandom;
+use crate::types::{
+    NeighborDeterminableGame, SnakeBodyGettableGame, SnakeViewGame, NEIGHBOR_OFFSETS,
+};
+#[cfg(feature = "std")]
+use crate::wire_representation::{Game, GameRef, TaggedGame};
+use alloc::boxed::Box;
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+use core::borrow::Borrow;
+use core::fmt::Display;
+use hashbrown::HashMap;
 use rand::Rng;
-use std::borrow::Borrow;
-use std::error::Error;
-use std::fmt::Display;
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
 
 use crate::{
-    types::{Move, SimulableGame, SimulatorInstruments},
-    wire_representation::Position,
+    types::{Move, Position, SimulableGame, SimulationSettings, SimulatorInstruments, SnakeMove},
+    Error,
 };
 
+use super::core::BytesVisitor;
 use super::core::CellBoard as CCB;
+use super::core::CellBoardDelta as CoreDelta;
 use super::core::CellIndex;
-use super::core::{simulate_with_moves, EvaluateMode};
-use super::dimensions::{ArcadeMaze, Dimensions, Fixed, Square};
+use super::core::{
+    advance_with_moves, simulate_with_moves, simulate_with_moves_into, EvaluateMode,
+    InvariantViolation,
+};
+use super::dimensions::{ArcadeMaze, Custom, Dimensions, Fixed, Square};
+#[cfg(feature = "std")]
+use super::dispatch::is_duel;
 
 /// A compact board representation that is significantly faster for simulation than
 /// `battlesnake_game_types::wire_representation::Game`.
@@ -36,12 +56,34 @@ pub struct CellBoard<T: CN, D: Dimensions, const BOARD_SIZE: usize, const MAX_SN
 
 impl_common_board_traits!(CellBoard);
 
+/// serialized as the bytes from [CellBoard::to_bytes], the way `serde_bytes` would
+impl<T: CN, D: Dimensions, const BOARD_SIZE: usize, const MAX_SNAKES: usize> Serialize
+    for CellBoard<T, D, BOARD_SIZE, MAX_SNAKES>
+{
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_bytes(&self.to_bytes())
+    }
+}
+
+impl<'de, T: CN, D: Dimensions, const BOARD_SIZE: usize, const MAX_SNAKES: usize> Deserialize<'de>
+    for CellBoard<T, D, BOARD_SIZE, MAX_SNAKES>
+{
+    fn deserialize<De: Deserializer<'de>>(deserializer: De) -> Result<Self, De::Error> {
+        let bytes = deserializer.deserialize_bytes(BytesVisitor)?;
+        Self::from_bytes(&bytes).map_err(de::Error::custom)
+    }
+}
+
 /// 7x7 board with 4 snakes
 pub type CellBoard4Snakes7x7 = CellBoard<u8, Square, { 7 * 7 }, 4>;
 
 /// Used to represent the standard 11x11 game with up to 4 snakes.
 pub type CellBoard4Snakes11x11 = CellBoard<u8, Square, { 11 * 11 }, 4>;
 
+/// An 11x11 game between exactly 2 snakes, which a [CellBoard4Snakes11x11] can be
+/// [respecialized](CellBoard::respecialized) to once the others are eliminated
+pub type DuelCellBoard11x11 = CellBoard<u8, Square, { 11 * 11 }, 2>;
+
 /// Used to represent the a 15x15 board with up to 4 snakes. This is the biggest board size that
 /// can still use u8s
 pub type CellBoard8Snakes15x15 = CellBoard<u8, Square, { 15 * 15 }, 8>;
@@ -55,18 +97,131 @@ pub type CellBoard16Snakes50x50 = CellBoard<u16, Square, { 50 * 50 }, 16>;
 impl<T: CN, D: Dimensions, const BOARD_SIZE: usize, const MAX_SNAKES: usize>
     CellBoard<T, D, BOARD_SIZE, MAX_SNAKES>
 {
+    /// Asserts that the board is consistent (e.g. no snake holes)
+    pub fn assert_consistency(&self) -> bool {
+        self.embedded.assert_consistency()
+    }
+
+    /// Checks everything the simulator relies on about this board, saying what is wrong if
+    /// anything is
+    pub fn check_invariants(&self) -> Result<(), InvariantViolation> {
+        self.embedded.check_invariants()
+    }
+
+    /// the settings this board is simulated with by default, taken from the ruleset it was
+    /// converted from
+    pub fn simulation_settings(&self) -> SimulationSettings {
+        self.embedded.simulation_settings()
+    }
+
+    /// simulate this board with `settings` from now on, instead of the settings from the ruleset
+    /// it was converted from
+    pub fn set_simulation_settings(&mut self, settings: SimulationSettings) {
+        self.embedded.set_simulation_settings(settings)
+    }
+
+    /// A copy of this board where `snake_id` has `health`, health 0 eliminates the snake. For
+    /// searching the same position with a few guesses at a snakes health
+    pub fn with_health(&self, snake_id: SnakeId, health: u8) -> Self {
+        Self {
+            embedded: self.embedded.with_health(snake_id, health),
+        }
+    }
+
+    /// A copy of this board where `snake_id` is `delta` pieces longer or shorter, growing or
+    /// shrinking at the tail. Errors if there isn't room to grow straight out from the tail,
+    /// or the snake would be left with less than 2 pieces
+    pub fn with_length_adjusted(&self, snake_id: SnakeId, delta: i32) -> Result<Self, Error> {
+        Ok(Self {
+            embedded: self.embedded.with_length_adjusted(
+                snake_id,
+                delta,
+                EvaluateMode::Standard,
+            )?,
+        })
+    }
+
+    #[cfg(feature = "std")]
     /// Builds a cellboard from a given game, will return an error if the game doesn't match
     /// the provided BOARD_SIZE or MAX_SNAKES. You are encouraged to use `CellBoard4Snakes11x11`
     /// for the common game layout
-    pub fn convert_from_game(game: Game, snake_ids: &SnakeIDMap) -> Result<Self, Box<dyn Error>> {
+    pub fn convert_from_game(game: Game, snake_ids: &SnakeIDMap) -> Result<Self, Error> {
         if game.game.ruleset.name == "wrapped" {
-            return Err("Wrapped games are not supported".into());
+            return Err(Error::UnsupportedRuleset(game.game.ruleset.name));
         }
 
         let embedded = CCB::convert_from_game(game, snake_ids)?;
         Ok(CellBoard { embedded })
     }
 
+    #[cfg(feature = "std")]
+    /// The same as [CellBoard::convert_from_game], for a game which already has its snakes
+    /// tagged with [Game::with_snake_ids]
+    pub fn convert_from_tagged_game(tagged: &TaggedGame) -> Result<Self, Error> {
+        if tagged.game().is_wrapped() {
+            return Err(Error::UnsupportedRuleset(
+                tagged.game().game.ruleset.name.clone(),
+            ));
+        }
+        let embedded = CCB::convert_from_tagged_game(tagged)?;
+        Ok(CellBoard { embedded })
+    }
+
+    #[cfg(feature = "std")]
+    /// The same as [CellBoard::convert_from_game], for a game borrowed from the request body
+    pub fn convert_from_game_ref(game: &GameRef, snake_ids: &SnakeIDMap) -> Result<Self, Error> {
+        if game.is_wrapped() {
+            return Err(Error::UnsupportedRuleset(
+                game.game.ruleset.name.to_string(),
+            ));
+        }
+        let embedded = CCB::convert_from_game_ref(game, snake_ids)?;
+        Ok(CellBoard { embedded })
+    }
+
+    /// this board reflected or rotated so that it matches every other position it is
+    /// equivalent to, along with the symmetry used, see [Symmetry]
+    pub fn canonicalize(&self) -> (Self, Symmetry) {
+        let (embedded, symmetry) = self.embedded.canonicalize();
+        (CellBoard { embedded }, symmetry)
+    }
+
+    /// This position on a board with room for `N` snakes, with you as snake 0 and the other
+    /// living snakes numbered after you, and the id each of its snakes has on this board. Use it
+    /// to switch to a [DuelCellBoard11x11] at the start of a turn once only one opponent is
+    /// left, mapping the moves it finds back with the ids. Errors if the snakes don't fit
+    pub fn respecialized<const N: usize>(
+        &self,
+    ) -> Result<(CellBoard<T, D, BOARD_SIZE, N>, Vec<SnakeId>), Error> {
+        let (embedded, ids) = self.embedded.respecialized()?;
+        Ok((CellBoard { embedded }, ids))
+    }
+
+    /// this board in a small versioned binary format, tens of bytes for most games, for storing
+    /// lots of positions. Read it back with [CellBoard::from_bytes]
+    #[allow(clippy::wrong_self_convention)]
+    pub fn to_bytes(&self) -> Vec<u8> {
+        self.embedded.to_bytes(EvaluateMode::Standard)
+    }
+
+    #[cfg(feature = "std")]
+    /// a wire game of the standard ruleset with the same snakes, food and hazards as this board,
+    /// with each snake's id its [SnakeId] as a string. The turn and anything else the board
+    /// doesn't know are left as they are in [Game::empty]
+    #[allow(clippy::wrong_self_convention)]
+    pub fn to_game(&self) -> Game {
+        self.embedded.to_game(EvaluateMode::Standard)
+    }
+
+    /// reads a board written by [CellBoard::to_bytes]. Errors if the bytes were written by
+    /// another version of the format or for the wrapped ruleset, don't fit in this board type,
+    /// or have been corrupted
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, Error> {
+        Ok(Self {
+            embedded: CCB::from_bytes(bytes, EvaluateMode::Standard)?,
+        })
+    }
+
     fn off_board(&self, new_head: Position) -> bool {
         new_head.x < 0
             || new_head.x >= self.embedded.get_actual_width() as i32
@@ -78,32 +233,235 @@ impl<T: CN, D: Dimensions, const BOARD_SIZE: usize, const MAX_SNAKES: usize>
 impl<T: CN, D: Dimensions, const BOARD_SIZE: usize, const MAX_SNAKES: usize>
     RandomReasonableMovesGame for CellBoard<T, D, BOARD_SIZE, MAX_SNAKES>
 {
-    fn random_reasonable_move_for_each_snake<'a>(
+    fn random_reasonable_move_at_level_for_each_snake<'a>(
         &'a self,
         rng: &'a mut impl Rng,
-    ) -> Box<dyn std::iter::Iterator<Item = (SnakeId, Move)> + 'a> {
-        let width = self.embedded.get_actual_width();
+        level: ReasonableMoveLevel,
+    ) -> Box<dyn core::iter::Iterator<Item = (SnakeId, ReasonableMove)> + 'a> {
         Box::new(
             self.embedded
-                .iter_healths()
-                .enumerate()
-                .filter(|(_, health)| **health > 0)
-                .map(move |(idx, _)| {
-                    let head_pos = self.get_head_as_position(&SnakeId(idx as u8));
-
-                    let mv = IntoIterator::into_iter(Move::all())
-                        .filter(|mv| {
-                            let new_head = head_pos.add_vec(mv.to_vector());
-                            let ci = CellIndex::new(head_pos.add_vec(mv.to_vector()), width);
-
-                            !self.off_board(new_head)
-                                && !self.embedded.cell_is_body(ci)
-                                && !self.embedded.cell_is_snake_head(ci)
-                        })
-                        .choose(rng)
-                        .unwrap_or(Move::Up);
-                    (SnakeId(idx as u8), mv)
-                }),
+                .random_reasonable_moves(rng, EvaluateMode::Standard, level),
+        )
+    }
+}
+
+impl<T: CN, D: Dimensions, const BOARD_SIZE: usize, const MAX_SNAKES: usize>
+    WeightedReasonableMovesGame for CellBoard<T, D, BOARD_SIZE, MAX_SNAKES>
+{
+    fn random_reasonable_move_weighted<'a, W>(
+        &'a self,
+        rng: &'a mut impl Rng,
+        weight: W,
+    ) -> Box<dyn core::iter::Iterator<Item = (SnakeId, ReasonableMove)> + 'a>
+    where
+        W: FnMut(&SnakeId, Move, &CellIndex<T>) -> f64 + 'a,
+    {
+        Box::new(self.embedded.random_reasonable_moves_weighted(
+            rng,
+            EvaluateMode::Standard,
+            weight,
+        ))
+    }
+}
+
+impl<T: CN, D: Dimensions, const BOARD_SIZE: usize, const MAX_SNAKES: usize> FloodFillableGame
+    for CellBoard<T, D, BOARD_SIZE, MAX_SNAKES>
+{
+    fn flood_fill(
+        &self,
+        start: &Self::NativePositionType,
+        options: FloodFillOptions,
+    ) -> Vec<Self::NativePositionType> {
+        self.embedded
+            .flood_fill(*start, options, EvaluateMode::Standard)
+    }
+
+    fn flood_fill_count(
+        &self,
+        start: &Self::NativePositionType,
+        options: FloodFillOptions,
+    ) -> usize {
+        self.embedded
+            .flood_fill_count(*start, options, EvaluateMode::Standard)
+    }
+}
+
+impl<T: CN, D: Dimensions, const BOARD_SIZE: usize, const MAX_SNAKES: usize> PathfindableGame
+    for CellBoard<T, D, BOARD_SIZE, MAX_SNAKES>
+{
+    fn a_star(
+        &self,
+        from: &Self::NativePositionType,
+        to: &Self::NativePositionType,
+        options: AStarOptions,
+    ) -> Option<Vec<Move>> {
+        self.embedded
+            .a_star(*from, *to, options, EvaluateMode::Standard)
+    }
+
+    fn cost_map(
+        &self,
+        from: &Self::NativePositionType,
+        options: AStarOptions,
+    ) -> HashMap<Self::NativePositionType, u32> {
+        self.embedded
+            .cost_map(*from, options, EvaluateMode::Standard)
+            .iter()
+            .enumerate()
+            .filter_map(|(idx, distance)| distance.map(|d| (CellIndex::from_usize(idx), d)))
+            .collect()
+    }
+}
+
+impl<T: CN, D: Dimensions, const BOARD_SIZE: usize, const MAX_SNAKES: usize> ChokePointGame
+    for CellBoard<T, D, BOARD_SIZE, MAX_SNAKES>
+{
+}
+
+impl<T: CN, D: Dimensions, const BOARD_SIZE: usize, const MAX_SNAKES: usize> FutureOccupancyGame
+    for CellBoard<T, D, BOARD_SIZE, MAX_SNAKES>
+{
+    fn turns_until_vacated(&self) -> HashMap<CellIndex<T>, u32> {
+        self.embedded
+            .turns_until_vacated()
+            .iter()
+            .enumerate()
+            .filter(|(_, turns)| **turns > 0)
+            .map(|(idx, turns)| (CellIndex::from_usize(idx), *turns as u32))
+            .collect()
+    }
+}
+
+impl<T: CN, D: Dimensions, const BOARD_SIZE: usize, const MAX_SNAKES: usize> SnakeViewGame
+    for CellBoard<T, D, BOARD_SIZE, MAX_SNAKES>
+{
+}
+
+impl<T: CN, D: Dimensions, const BOARD_SIZE: usize, const MAX_SNAKES: usize>
+    HeadToHeadPredictableGame for CellBoard<T, D, BOARD_SIZE, MAX_SNAKES>
+{
+    fn predict_head_to_head(
+        &self,
+        me: &SnakeId,
+        them: &SnakeId,
+        target: &CellIndex<T>,
+    ) -> HeadToHeadOutcome {
+        self.embedded
+            .predict_head_to_head(*me, *them, *target, EvaluateMode::Standard)
+    }
+}
+
+impl<T: CN, D: Dimensions, const BOARD_SIZE: usize, const MAX_SNAKES: usize> FoodRaceGame
+    for CellBoard<T, D, BOARD_SIZE, MAX_SNAKES>
+{
+}
+
+impl<T: CN, D: Dimensions, const BOARD_SIZE: usize, const MAX_SNAKES: usize> HeadSquaresGame
+    for CellBoard<T, D, BOARD_SIZE, MAX_SNAKES>
+{
+    fn possible_head_squares(&self, id: &SnakeId) -> MoveSetPositions<CellIndex<T>> {
+        self.embedded
+            .possible_head_squares(*id, EvaluateMode::Standard)
+    }
+}
+
+impl<T: CN, D: Dimensions, const BOARD_SIZE: usize, const MAX_SNAKES: usize> TailChaseGame
+    for CellBoard<T, D, BOARD_SIZE, MAX_SNAKES>
+{
+}
+
+impl<T: CN, D: Dimensions, const BOARD_SIZE: usize, const MAX_SNAKES: usize>
+    LastMoveDeterminableGame for CellBoard<T, D, BOARD_SIZE, MAX_SNAKES>
+{
+}
+
+impl<T: CN, D: Dimensions, const BOARD_SIZE: usize, const MAX_SNAKES: usize>
+    TrajectoryProjectableGame for CellBoard<T, D, BOARD_SIZE, MAX_SNAKES>
+{
+}
+
+impl<T: CN, D: Dimensions, const BOARD_SIZE: usize, const MAX_SNAKES: usize> VoronoiGame
+    for CellBoard<T, D, BOARD_SIZE, MAX_SNAKES>
+{
+    fn voronoi(&self, options: VoronoiOptions) -> HashMap<SnakeId, usize> {
+        let counts = self.embedded.voronoi(options, EvaluateMode::Standard);
+        self.get_snake_ids()
+            .into_iter()
+            .map(|sid| (sid, counts[sid.as_usize()]))
+            .collect()
+    }
+
+    fn voronoi_owners(&self, options: VoronoiOptions) -> HashMap<CellIndex<T>, (SnakeId, u32)> {
+        let owners = self
+            .embedded
+            .voronoi_owners(options, EvaluateMode::Standard);
+        owners
+            .iter()
+            .enumerate()
+            .filter_map(|(idx, owner)| owner.map(|owner| (CellIndex::from_usize(idx), owner)))
+            .collect()
+    }
+}
+
+/// A board stored as the cells that changed from the board it was simulated from, which it
+/// shares with its siblings. Holding lots of states at once, like the frontier of a best first
+/// search, takes a fraction of the memory of holding whole boards
+#[derive(Debug, Clone)]
+pub struct CellBoardDelta<T: CN, D: Dimensions, const BOARD_SIZE: usize, const MAX_SNAKES: usize> {
+    parent: Arc<CellBoard<T, D, BOARD_SIZE, MAX_SNAKES>>,
+    delta: CoreDelta<T, MAX_SNAKES>,
+}
+
+impl<T: CN, D: Dimensions, const BOARD_SIZE: usize, const MAX_SNAKES: usize>
+    CellBoardDelta<T, D, BOARD_SIZE, MAX_SNAKES>
+{
+    /// the board this was simulated from
+    pub fn parent(&self) -> &Arc<CellBoard<T, D, BOARD_SIZE, MAX_SNAKES>> {
+        &self.parent
+    }
+
+    /// the whole board
+    pub fn materialize(&self) -> CellBoard<T, D, BOARD_SIZE, MAX_SNAKES> {
+        CellBoard {
+            embedded: self.parent.embedded.apply_delta(&self.delta),
+        }
+    }
+
+    /// roughly how many bytes this takes up, not counting the parent it shares
+    pub fn size_in_bytes(&self) -> usize {
+        self.delta.size_in_bytes()
+    }
+}
+
+impl<T: CN, D: Dimensions, const BOARD_SIZE: usize, const MAX_SNAKES: usize>
+    CellBoard<T, D, BOARD_SIZE, MAX_SNAKES>
+{
+    /// this board as a delta from `parent`, which has to be the same size
+    pub fn delta_from(&self, parent: &Arc<Self>) -> CellBoardDelta<T, D, BOARD_SIZE, MAX_SNAKES> {
+        CellBoardDelta {
+            parent: Arc::clone(parent),
+            delta: self.embedded.delta_from(&parent.embedded),
+        }
+    }
+
+    /// The same as [SimulableGame::simulate_with_moves], with each board as a delta from this
+    /// one
+    #[allow(clippy::type_complexity)]
+    pub fn simulate_with_moves_as_deltas<'a, I: SimulatorInstruments, S: Borrow<[Move]>>(
+        self: &'a Arc<Self>,
+        instruments: &'a I,
+        snake_ids_and_moves: impl IntoIterator<Item = (SnakeId, S)>,
+    ) -> Box<
+        dyn Iterator<
+                Item = (
+                    Action<MAX_SNAKES>,
+                    CellBoardDelta<T, D, BOARD_SIZE, MAX_SNAKES>,
+                ),
+            > + 'a,
+    > {
+        Box::new(
+            self.simulate_with_moves(instruments, snake_ids_and_moves)
+                .map(move |(action, board)| (action, board.delta_from(self))),
         )
     }
 }
@@ -117,11 +475,12 @@ impl<
     > SimulableGame<T, MAX_SNAKES> for CellBoard<N, D, BOARD_SIZE, MAX_SNAKES>
 {
     #[allow(clippy::type_complexity)]
-    fn simulate_with_moves<S>(
-        &self,
-        instruments: &T,
+    fn simulate_with_moves_and_settings<'a, S>(
+        &'a self,
+        instruments: &'a T,
         snake_ids_and_moves: impl IntoIterator<Item = (Self::SnakeIDType, S)>,
-    ) -> Box<dyn Iterator<Item = (Action<MAX_SNAKES>, Self)> + '_>
+        settings: &SimulationSettings,
+    ) -> Box<dyn Iterator<Item = (Action<MAX_SNAKES>, Self)> + 'a>
     where
         S: Borrow<[Move]>,
     {
@@ -131,6 +490,7 @@ impl<
                 instruments,
                 snake_ids_and_moves,
                 EvaluateMode::Standard,
+                settings,
             )
             .map(|v| {
                 let (action, board) = v;
@@ -138,6 +498,46 @@ impl<
             }),
         )
     }
+
+    fn simulation_settings(&self) -> SimulationSettings {
+        self.embedded.simulation_settings()
+    }
+
+    fn simulate_with_moves_into<S>(
+        &self,
+        instruments: &T,
+        snake_ids_and_moves: impl IntoIterator<Item = (Self::SnakeIDType, S)>,
+        out_states: &mut Vec<Self>,
+        out_moves: &mut Vec<Action<MAX_SNAKES>>,
+    ) where
+        S: Borrow<[Move]>,
+    {
+        simulate_with_moves_into(
+            &self.embedded,
+            instruments,
+            snake_ids_and_moves,
+            EvaluateMode::Standard,
+            &self.embedded.simulation_settings(),
+            out_states,
+            out_moves,
+            |embedded| Self { embedded },
+        )
+    }
+
+    fn advance_turn_with_moves(
+        &self,
+        instruments: &T,
+        moves: impl IntoIterator<Item = impl Into<SnakeMove<Self::SnakeIDType>>>,
+    ) -> Result<Self, Error> {
+        advance_with_moves(
+            &self.embedded,
+            instruments,
+            moves.into_iter().map(Into::into),
+            EvaluateMode::Standard,
+            &self.embedded.simulation_settings(),
+        )
+        .map(|embedded| Self { embedded })
+    }
 }
 
 impl<T: CN, D: Dimensions, const BOARD_SIZE: usize, const MAX_SNAKES: usize>
@@ -146,19 +546,20 @@ impl<T: CN, D: Dimensions, const BOARD_SIZE: usize, const MAX_SNAKES: usize>
     fn possible_moves<'a>(
         &'a self,
         pos: &Self::NativePositionType,
-    ) -> Box<(dyn std::iter::Iterator<Item = (Move, CellIndex<T>)> + 'a)> {
+    ) -> Box<dyn core::iter::Iterator<Item = (Move, CellIndex<T>)> + 'a> {
         let width = self.embedded.get_actual_width();
         let head_pos = pos.into_position(width);
 
         Box::new(
             Move::all_iter()
                 .map(move |mv| {
-                    let new_head = head_pos.add_vec(mv.to_vector());
+                    let new_head = head_pos.add_vec(NEIGHBOR_OFFSETS[mv.as_index()]);
                     let ci = CellIndex::new(new_head, width);
 
                     (mv, new_head, ci)
                 })
                 .filter(move |(_mv, new_head, _)| !self.off_board(*new_head))
+                .filter(move |(_mv, _, ci)| !self.embedded.cell_is_wall(*ci))
                 .map(|(mv, _, ci)| (mv, ci)),
         )
     }
@@ -166,19 +567,20 @@ impl<T: CN, D: Dimensions, const BOARD_SIZE: usize, const MAX_SNAKES: usize>
     fn neighbors<'a>(
         &'a self,
         pos: &Self::NativePositionType,
-    ) -> Box<(dyn Iterator<Item = CellIndex<T>> + 'a)> {
+    ) -> Box<dyn Iterator<Item = CellIndex<T>> + 'a> {
         let width = self.embedded.get_actual_width();
         let head_pos = pos.into_position(width);
 
         Box::new(
             Move::all_iter()
                 .map(move |mv| {
-                    let new_head = head_pos.add_vec(mv.to_vector());
+                    let new_head = head_pos.add_vec(NEIGHBOR_OFFSETS[mv.as_index()]);
                     let ci = CellIndex::new(new_head, width);
 
                     (new_head, ci)
                 })
                 .filter(move |(new_head, _)| !self.off_board(*new_head))
+                .filter(move |(_, ci)| !self.embedded.cell_is_wall(*ci))
                 .map(|(_, ci)| ci),
         )
     }
@@ -195,6 +597,8 @@ pub enum BestCellBoard {
     Standard(Box<CellBoard4Snakes11x11>),
     /// A exactly 11x11 board with 4 snakes
     MediumExact(Box<CellBoard<u8, Fixed<11, 11>, { 11 * 11 }, 4>>),
+    /// A exactly 11x11 board with exactly 2 snakes, which are snakes 0 and 1
+    Duel(Box<CellBoard<u8, Fixed<11, 11>, { 11 * 11 }, 2>>),
     /// A game that can have a max height and width of 15x15 and 4 snakes
     LargestU8(Box<CellBoard8Snakes15x15>),
     /// A exactly 19x19 board with 4 snakes
@@ -205,59 +609,105 @@ pub enum BestCellBoard {
     Large(Box<CellBoard8Snakes25x25>),
     /// A game that can have a max height and width of 50x50 and 16 snakes
     Silly(Box<CellBoard16Snakes50x50>),
+    /// A board that isn't square, with at most as many squares as 15x15 and 8 snakes
+    CustomU8(Box<CellBoard<u8, Custom, { 15 * 15 }, 8>>),
+    /// A board that isn't square, up to 50x50 and 16 snakes
+    Custom(Box<CellBoard<u16, Custom, { 50 * 50 }, 16>>),
 }
 
+#[cfg(feature = "std")]
 /// Trait to get the best sized cellboard for the given game. It returns the smallest Compact board
 /// that has enough room to fit the given Wire game. If the game can't fit in any of our Compact
-/// boards we panic. However the largest board available is MUCH larger than the biggest selectable
-/// board in the Battlesnake UI
+/// boards we return an error. However the largest board available is MUCH larger than the biggest
+/// selectable board in the Battlesnake UI
 pub trait ToBestCellBoard {
     #[allow(missing_docs)]
-    fn to_best_cell_board(self) -> Result<BestCellBoard, Box<dyn Error>>;
+    fn to_best_cell_board(self) -> Result<BestCellBoard, Error>;
 }
 
-impl ToBestCellBoard for Game {
-    fn to_best_cell_board(self) -> Result<BestCellBoard, Box<dyn Error>> {
-        let width = self.board.width;
-        let height = self.board.height;
-        let num_snakes = self.board.snakes.len();
-        let id_map = build_snake_id_map(&self);
+#[cfg(feature = "std")]
+impl BestCellBoard {
+    /// The smallest board that fits `game`, see [ToBestCellBoard], with snakes given the ids in
+    /// `snake_ids`
+    pub fn from_game(game: Game, snake_ids: &SnakeIDMap) -> Result<Self, Error> {
+        let width = game.board.width;
+        let height = game.board.height;
+        let num_snakes = game.board.snakes.len();
+
+        let square = width == height;
 
         let best_board = if width == 7 && height == 7 && num_snakes <= 4 {
-            BestCellBoard::SmallExact(Box::new(CellBoard::convert_from_game(self, &id_map)?))
-        } else if width <= 7 && height <= 7 && num_snakes <= 4 {
-            BestCellBoard::Tiny(Box::new(CellBoard::convert_from_game(self, &id_map)?))
+            BestCellBoard::SmallExact(Box::new(CellBoard::convert_from_game(game, snake_ids)?))
+        } else if square && width <= 7 && num_snakes <= 4 {
+            BestCellBoard::Tiny(Box::new(CellBoard::convert_from_game(game, snake_ids)?))
+        } else if width == 11 && height == 11 && is_duel(&game, snake_ids) {
+            BestCellBoard::Duel(Box::new(CellBoard::convert_from_game(game, snake_ids)?))
         } else if width == 11 && height == 11 && num_snakes <= 4 {
-            BestCellBoard::MediumExact(Box::new(CellBoard::convert_from_game(self, &id_map)?))
-        } else if width <= 11 && height <= 11 && num_snakes <= 4 {
-            BestCellBoard::Standard(Box::new(CellBoard::convert_from_game(self, &id_map)?))
-        } else if width <= 15 && height <= 15 && num_snakes <= 8 {
-            BestCellBoard::LargestU8(Box::new(CellBoard::convert_from_game(self, &id_map)?))
+            BestCellBoard::MediumExact(Box::new(CellBoard::convert_from_game(game, snake_ids)?))
+        } else if square && width <= 11 && num_snakes <= 4 {
+            BestCellBoard::Standard(Box::new(CellBoard::convert_from_game(game, snake_ids)?))
+        } else if square && width <= 15 && num_snakes <= 8 {
+            BestCellBoard::LargestU8(Box::new(CellBoard::convert_from_game(game, snake_ids)?))
         } else if width == 19 && height == 19 && num_snakes <= 4 {
-            BestCellBoard::LargeExact(Box::new(CellBoard::convert_from_game(self, &id_map)?))
+            BestCellBoard::LargeExact(Box::new(CellBoard::convert_from_game(game, snake_ids)?))
         } else if width == 19 && height == 21 && num_snakes <= 4 {
-            BestCellBoard::ArcadeMaze(Box::new(CellBoard::convert_from_game(self, &id_map)?))
-        } else if width <= 25 && height < 25 && num_snakes <= 8 {
-            BestCellBoard::Large(Box::new(CellBoard::convert_from_game(self, &id_map)?))
+            BestCellBoard::ArcadeMaze(Box::new(CellBoard::convert_from_game(game, snake_ids)?))
+        } else if square && width <= 25 && height < 25 && num_snakes <= 8 {
+            BestCellBoard::Large(Box::new(CellBoard::convert_from_game(game, snake_ids)?))
+        } else if square && width <= 50 && num_snakes <= 16 {
+            BestCellBoard::Silly(Box::new(CellBoard::convert_from_game(game, snake_ids)?))
+        } else if width * height <= 15 * 15 && num_snakes <= 8 {
+            BestCellBoard::CustomU8(Box::new(CellBoard::convert_from_game(game, snake_ids)?))
         } else if width <= 50 && height <= 50 && num_snakes <= 16 {
-            BestCellBoard::Silly(Box::new(CellBoard::convert_from_game(self, &id_map)?))
+            BestCellBoard::Custom(Box::new(CellBoard::convert_from_game(game, snake_ids)?))
         } else {
-            panic!("No board was big enough")
+            return Err(Error::BoardDoesNotFit { width, height });
         };
+        #[cfg(any(test, feature = "tracing"))]
+        if !matches!(
+            best_board,
+            BestCellBoard::SmallExact(_)
+                | BestCellBoard::MediumExact(_)
+                | BestCellBoard::Duel(_)
+                | BestCellBoard::LargeExact(_)
+                | BestCellBoard::ArcadeMaze(_)
+        ) {
+            tracing::debug!(
+                width,
+                height,
+                snakes = num_snakes,
+                "no exactly sized board for this game, using a larger one"
+            );
+        }
 
         Ok(best_board)
     }
 }
 
+#[cfg(feature = "std")]
+impl ToBestCellBoard for Game {
+    fn to_best_cell_board(self) -> Result<BestCellBoard, Error> {
+        let id_map = try_build_snake_id_map(&self)?;
+        BestCellBoard::from_game(self, &id_map)
+    }
+}
+
 #[cfg(test)]
 mod test {
 
+    use std::collections::HashSet;
+
     use itertools::Itertools;
 
     use super::*;
     use crate::{
-        compact_representation::core::Cell, game_fixture, types::build_snake_id_map,
-        wire_representation::Game as DEGame,
+        compact_representation::{core::Cell, CellBoardBuilder, MAX_HAZARD_STACK},
+        game_fixture,
+        types::{
+            build_snake_id_map, ProjectionStop, RegionInfo, RulesVersion, SquareSafety,
+            TrajectoryAssumption, VoronoiTieBreak,
+        },
+        wire_representation::{BattleSnake, Game as DEGame},
     };
     #[derive(Debug)]
     struct Instruments;
@@ -277,7 +727,7 @@ mod test {
             _ => panic!("expected standard board"),
         }
 
-        let tiny_board = game_fixture(include_str!("../../../fixtures/7x7board.json"));
+        let tiny_board = game_fixture(include_str!("../../../fixtures/7x7board_valid.json"));
         let converted = Game::to_best_cell_board(tiny_board);
         assert!(converted.is_ok());
         let u = converted.unwrap();
@@ -286,8 +736,20 @@ mod test {
             _ => panic!("expected standard board"),
         }
 
+        // a 1v1 whose snakes are 0 and 1 gets the duel board, and one whose snakes aren't
+        // doesn't fit it
+        let duel = game_fixture(include_str!("../../../fixtures/late_stage.json"));
+        let converted = Game::to_best_cell_board(duel.clone()).unwrap();
+        assert!(matches!(converted, BestCellBoard::Duel(_)));
+        let mut snake_ids = build_snake_id_map(&duel);
+        for id in snake_ids.values_mut() {
+            *id = SnakeId(id.0 * 2);
+        }
+        let converted = BestCellBoard::from_game(duel, &snake_ids).unwrap();
+        assert!(matches!(converted, BestCellBoard::MediumExact(_)));
+
         let non_standard_small_board =
-            game_fixture(include_str!("../../../fixtures/8x8board.json"));
+            game_fixture(include_str!("../../../fixtures/8x8board_valid.json"));
         let converted = Game::to_best_cell_board(non_standard_small_board);
         assert!(converted.is_ok());
         let u = converted.unwrap();
@@ -297,6 +759,31 @@ mod test {
         }
     }
 
+    #[test]
+    fn test_convert_from_tagged_game() {
+        let g = game_fixture(include_str!("../../../fixtures/late_stage.json"));
+        let snake_ids = build_snake_id_map(&g);
+        let tagged = g.with_snake_ids(&snake_ids).unwrap();
+        let from_tagged = CellBoard4Snakes11x11::convert_from_tagged_game(&tagged).unwrap();
+        let from_game: CellBoard4Snakes11x11 = g.as_cell_board(&snake_ids).unwrap();
+        assert_eq!(from_tagged, from_game);
+
+        let mut wrapped = g.clone();
+        wrapped.game.ruleset.name = "wrapped".to_string();
+        let tagged = wrapped.with_snake_ids(&snake_ids).unwrap();
+        assert!(matches!(
+            CellBoard4Snakes11x11::convert_from_tagged_game(&tagged),
+            Err(Error::UnsupportedRuleset(_))
+        ));
+
+        let mut unknown = snake_ids;
+        unknown.remove(&g.board.snakes[0].id);
+        match g.as_cell_board::<u8, Square, { 11 * 11 }, 4>(&unknown) {
+            Err(Error::UnknownSnake(id)) => assert_eq!(id, g.board.snakes[0].id),
+            other => panic!("expected an unknown snake, got {:?}", other),
+        }
+    }
+
     #[test]
     fn test_head_gettable() {
         let game_fixture = include_str!("../../../fixtures/late_stage.json");
@@ -342,91 +829,1582 @@ mod test {
     }
 
     #[test]
-    fn test_set_hazard() {
-        let mut c: Cell<u8> = Cell::empty();
-        c.set_food();
-        assert!(c.is_food());
-        c.set_hazard();
-        assert!(c.is_food());
-        assert!(c.is_hazard());
-        assert!(!c.is_head());
-        assert!(!c.is_body());
-    }
+    fn test_random_reasonable_move_fully_enclosed() {
+        use rand::{rngs::SmallRng, SeedableRng};
 
-    #[test]
-    fn test_clear_hazard() {
-        let mut c: Cell<u8> = Cell::empty();
-        c.set_food();
-        assert!(c.is_food());
-        c.set_hazard();
-        c.clear_hazard();
-        assert!(c.is_food());
-        assert!(!c.is_hazard());
-        assert!(!c.is_head());
-        assert!(!c.is_body());
-        let mut c: Cell<u8> = Cell::make_double_stacked_piece(SnakeId(0), CellIndex(0));
-        c.set_hazard();
-        c.clear_hazard();
-        assert!(c.is_body());
-        assert!(!c.is_hazard());
-    }
+        let g = game_fixture(include_str!("../../../fixtures/cornered.json"));
+        let snake_id_mapping = build_snake_id_map(&g);
+        let compact: CellBoard4Snakes11x11 = g.as_cell_board(&snake_id_mapping).unwrap();
+        let mut rng = SmallRng::seed_from_u64(4);
 
-    #[test]
-    fn test_remove() {
-        let mut c: Cell<u8> = Cell::make_body_piece(SnakeId(3), CellIndex(17));
-        c.remove();
-        c.set_hazard();
-        assert!(c.is_empty());
-        assert!(c.is_hazard());
-        assert!(c.get_snake_id().is_none());
-        assert!(c.get_idx() == CellIndex(0));
-    }
-    #[test]
-    fn test_set_food() {
-        let mut c: Cell<u8> = Cell::empty();
-        c.set_food();
-        c.set_hazard();
-        assert!(c.is_food());
-        assert!(c.is_hazard());
-        assert!(c.get_snake_id().is_none());
-        assert!(c.get_idx() == CellIndex(0));
+        for _ in 0..10 {
+            let moves = compact
+                .random_reasonable_move_with_status_for_each_snake(&mut rng)
+                .collect_vec();
+            assert_eq!(
+                moves,
+                vec![(
+                    SnakeId(0),
+                    ReasonableMove {
+                        mv: Move::Right,
+                        forced: true
+                    }
+                )]
+            );
+        }
     }
 
     #[test]
-    fn test_set_head() {
-        let mut c: Cell<u8> = Cell::empty();
-        c.set_head(SnakeId(3), CellIndex(17));
-        c.set_hazard();
-        assert!(c.is_head());
-        assert!(c.is_hazard());
-        assert!(c.get_snake_id().unwrap() == SnakeId(3));
-        assert!(c.get_idx() == CellIndex(17));
-    }
+    fn test_weighted_random_reasonable_moves() {
+        use rand::{rngs::SmallRng, SeedableRng};
 
-    #[test]
-    fn test_food_queryable() {
-        let game_fixture = include_str!("../../../fixtures/late_stage.json");
-        let g: Result<DEGame, _> = serde_json::from_slice(game_fixture.as_bytes());
-        let g = g.expect("the json literal is valid");
-        let snake_id_mapping = build_snake_id_map(&g);
-        let compact: CellBoard4Snakes11x11 = g.as_cell_board(&snake_id_mapping).unwrap();
+        let mut builder = CellBoardBuilder::new(11, 11);
+        let body = vec![
+            Position::new(5, 5),
+            Position::new(5, 4),
+            Position::new(5, 3),
+        ];
+        builder.add_snake(body, 50).unwrap();
+        let g = builder.game().clone();
+        let compact: CellBoard4Snakes11x11 = g.as_cell_board(&build_snake_id_map(&g)).unwrap();
+        let head = compact.get_head_as_native_position(&SnakeId(0));
+        let mut rng = SmallRng::seed_from_u64(7);
 
-        assert!(!compact.is_food(&CellIndex(6 * 11 + 4)));
+        let mut counts = [0; 4];
+        for _ in 0..4000 {
+            let moves = compact
+                .random_reasonable_move_weighted(&mut rng, |sid, mv, destination| {
+                    assert_eq!(*sid, SnakeId(0));
+                    assert_eq!(
+                        destination.into_position(11),
+                        head.into_position(11).add_vec(mv.to_vector())
+                    );
+                    match mv {
+                        Move::Up => 3.0,
+                        Move::Left => 1.0,
+                        _ => 0.0,
+                    }
+                })
+                .collect_vec();
+            assert_eq!(moves.len(), 1);
+            assert!(!moves[0].1.forced);
+            counts[moves[0].1.mv.as_index()] += 1;
+        }
+        assert_eq!(counts[Move::Right.as_index()], 0);
+        assert!(
+            (2850..=3150).contains(&counts[Move::Up.as_index()]),
+            "{:?}",
+            counts
+        );
 
-        assert!(compact.is_food(&CellIndex(2 * 11)));
-        assert!(compact.is_food(&CellIndex(9 * 11)));
-        assert!(compact.is_food(&CellIndex(3 * 11 + 4)));
+        // when every reasonable move is ruled out they are all equally likely
+        let moves = (0..300)
+            .map(|_| {
+                compact
+                    .random_reasonable_move_weighted(&mut rng, |_, _, _| f64::NAN)
+                    .next()
+                    .unwrap()
+                    .1
+                    .mv
+            })
+            .collect::<HashSet<_>>();
+        assert_eq!(
+            moves,
+            [Move::Up, Move::Left, Move::Right]
+                .iter()
+                .copied()
+                .collect()
+        );
+
+        let cornered = game_fixture(include_str!("../../../fixtures/cornered.json"));
+        let cornered: CellBoard4Snakes11x11 = cornered
+            .as_cell_board(&build_snake_id_map(&cornered))
+            .unwrap();
+        let moves = cornered
+            .random_reasonable_move_weighted(&mut rng, |_, _, _| -> f64 {
+                panic!("forced moves aren't weighted")
+            })
+            .collect_vec();
+        assert_eq!(
+            moves,
+            vec![(
+                SnakeId(0),
+                ReasonableMove {
+                    mv: Move::Right,
+                    forced: true
+                }
+            )]
+        );
     }
 
     #[test]
-    fn test_neighbors_and_possible_moves_start_of_game() {
-        let game_fixture = include_str!("../../../fixtures/start_of_game.json");
-        let g: Result<DEGame, _> = serde_json::from_slice(game_fixture.as_bytes());
-        let g = g.expect("the json literal is valid");
+    fn test_random_reasonable_move_vacating_tail() {
+        use rand::{rngs::SmallRng, SeedableRng};
+
+        let g = game_fixture(include_str!("../../../fixtures/tail_exit.json"));
         let snake_id_mapping = build_snake_id_map(&g);
         let compact: CellBoard4Snakes11x11 = g.as_cell_board(&snake_id_mapping).unwrap();
+        let mut rng = SmallRng::seed_from_u64(4);
 
-        let head = compact.get_head_as_native_position(&SnakeId(0));
+        for _ in 0..10 {
+            let moves = compact
+                .random_reasonable_move_with_status_for_each_snake(&mut rng)
+                .collect_vec();
+            assert_eq!(
+                moves,
+                vec![(
+                    SnakeId(0),
+                    ReasonableMove {
+                        mv: Move::Right,
+                        forced: false
+                    }
+                )]
+            );
+        }
+    }
+
+    #[test]
+    fn test_random_reasonable_moves_skip_eliminated_snakes() {
+        use rand::{rngs::SmallRng, SeedableRng};
+
+        let g = game_fixture(include_str!("../../../fixtures/4_snake_game.json"));
+        let snake_id_mapping = build_snake_id_map(&g);
+        let mut compact: CellBoard4Snakes11x11 = g.as_cell_board(&snake_id_mapping).unwrap();
+        let instruments = Instruments;
+        let mut rng = SmallRng::seed_from_u64(4);
+        let mut eliminated = vec![];
+
+        for _ in 0..1000 {
+            let (alive, dead): (Vec<SnakeId>, Vec<SnakeId>) = snake_id_mapping
+                .values()
+                .copied()
+                .sorted_by_key(|sid| sid.0)
+                .partition(|sid| compact.is_alive(sid));
+            for sid in dead {
+                if !eliminated.contains(&sid) {
+                    eliminated.push(sid);
+                }
+            }
+            if alive.len() <= 1 {
+                break;
+            }
+
+            let moves = compact
+                .random_reasonable_move_for_each_snake(&mut rng)
+                .collect_vec();
+            assert_eq!(moves.iter().map(|m| m.snake_id).collect_vec(), alive);
+            assert!(moves.iter().all(|m| !eliminated.contains(&m.snake_id)));
+
+            compact = compact
+                .simulate_with_moves(
+                    &instruments,
+                    moves
+                        .iter()
+                        .map(|m| (m.snake_id, std::slice::from_ref(&m.mv))),
+                )
+                .collect_vec()[0]
+                .1;
+        }
+        assert!(!eliminated.is_empty());
+    }
+
+    #[test]
+    fn test_space_aware_random_reasonable_move_avoids_pocket() {
+        use rand::{rngs::SmallRng, SeedableRng};
+
+        let g = game_fixture(include_str!("../../../fixtures/small_pocket.json"));
+        let snake_id_mapping = build_snake_id_map(&g);
+        let compact: CellBoard4Snakes11x11 = g.as_cell_board(&snake_id_mapping).unwrap();
+        let mut rng = SmallRng::seed_from_u64(4);
+
+        for _ in 0..50 {
+            let moves = compact
+                .random_reasonable_move_at_level_for_each_snake(
+                    &mut rng,
+                    ReasonableMoveLevel::SpaceAware,
+                )
+                .collect_vec();
+            assert_eq!(
+                moves,
+                vec![
+                    (
+                        SnakeId(0),
+                        ReasonableMove {
+                            mv: Move::Right,
+                            forced: false
+                        }
+                    ),
+                    (
+                        SnakeId(1),
+                        ReasonableMove {
+                            mv: Move::Up,
+                            forced: false
+                        }
+                    )
+                ]
+            );
+        }
+    }
+
+    #[test]
+    fn test_flood_fill_sealed_region() {
+        let g = game_fixture(include_str!("../../../fixtures/sealed_region.json"));
+        let snake_id_mapping = build_snake_id_map(&g);
+        let compact: CellBoard4Snakes11x11 = g.as_cell_board(&snake_id_mapping).unwrap();
+        let start = CellIndex::new(Position::new(0, 0), 11);
+
+        // the snake walls off the three left most columns, and the pieces next to them don't
+        // move out of the way in time to escape
+        let reached = compact.flood_fill(&start, FloodFillOptions::default());
+        assert_eq!(reached.len(), 32);
+        assert!(reached.iter().all(|ci| ci.into_position(11).x < 3));
+        assert_eq!(
+            compact.flood_fill_count(&start, FloodFillOptions::default()),
+            32
+        );
+
+        let wire = g.flood_fill(&Position::new(0, 0), FloodFillOptions::default());
+        assert_eq!(
+            reached
+                .into_iter()
+                .map(|ci| ci.into_position(11))
+                .collect_vec(),
+            wire
+        );
+
+        let hazards_block = FloodFillOptions {
+            hazards_block: true,
+            ..Default::default()
+        };
+        assert_eq!(compact.flood_fill_count(&start, hazards_block), 0);
+    }
+
+    #[test]
+    fn test_flood_fill_depth_limited() {
+        let g = game_fixture(include_str!("../../../fixtures/sealed_region.json"));
+        let snake_id_mapping = build_snake_id_map(&g);
+        let compact: CellBoard4Snakes11x11 = g.as_cell_board(&snake_id_mapping).unwrap();
+        let start = CellIndex::new(Position::new(0, 0), 11);
+
+        let counts = (0..4)
+            .map(|depth| {
+                compact.flood_fill_count(
+                    &start,
+                    FloodFillOptions {
+                        max_depth: Some(depth),
+                        ..Default::default()
+                    },
+                )
+            })
+            .collect_vec();
+        assert_eq!(counts, vec![0, 2, 5, 8]);
+
+        // following the body from its tail, each piece moves out of the way just in time
+        let next_to_tail = CellIndex::new(Position::new(9, 10), 11);
+        let along_body = compact.flood_fill(
+            &next_to_tail,
+            FloodFillOptions {
+                max_depth: Some(3),
+                ..Default::default()
+            },
+        );
+        for y in 8..=10 {
+            assert!(along_body.contains(&CellIndex::new(Position::new(10, y), 11)));
+        }
+        assert!(!along_body.contains(&CellIndex::new(Position::new(10, 7), 11)));
+    }
+
+    #[test]
+    fn test_voronoi_symmetric_duel() {
+        let g = game_fixture(include_str!("../../../fixtures/voronoi_duel.json"));
+        let snake_id_mapping = build_snake_id_map(&g);
+        let compact: CellBoard4Snakes11x11 = g.as_cell_board(&snake_id_mapping).unwrap();
+
+        for tie_break in [VoronoiTieBreak::Nobody, VoronoiTieBreak::LongerSnake] {
+            let options = VoronoiOptions {
+                tie_break,
+                ..Default::default()
+            };
+            let control = compact.voronoi(options);
+            assert_eq!(control.len(), 2);
+            assert_eq!(control[&SnakeId(0)], control[&SnakeId(1)]);
+            // the middle column is contested, so neither snake gets it
+            assert_eq!(
+                control[&SnakeId(0)] + control[&SnakeId(1)],
+                11 * 11 - 2 - 11
+            );
+
+            let wire = g.voronoi(options);
+            assert_eq!(control[&SnakeId(0)], wire["you"]);
+            assert_eq!(control[&SnakeId(1)], wire["opponent"]);
+        }
+    }
+
+    #[test]
+    fn test_voronoi_longer_snake_wins_contested_squares() {
+        let g = game_fixture(include_str!("../../../fixtures/voronoi_longer.json"));
+        let snake_id_mapping = build_snake_id_map(&g);
+        let compact: CellBoard4Snakes11x11 = g.as_cell_board(&snake_id_mapping).unwrap();
+
+        let nobody = compact.voronoi(VoronoiOptions {
+            tie_break: VoronoiTieBreak::Nobody,
+            ..Default::default()
+        });
+        let longer = compact.voronoi(VoronoiOptions {
+            tie_break: VoronoiTieBreak::LongerSnake,
+            ..Default::default()
+        });
+
+        // both heads reach every square in the middle column on the same turn
+        assert_eq!(longer[&SnakeId(0)], nobody[&SnakeId(0)] + 11);
+        assert_eq!(longer[&SnakeId(1)], nobody[&SnakeId(1)]);
+        assert!(longer[&SnakeId(0)] > longer[&SnakeId(1)]);
+
+        let wire = g.voronoi(VoronoiOptions::default());
+        assert_eq!(longer[&SnakeId(0)], wire["you"]);
+        assert_eq!(longer[&SnakeId(1)], wire["opponent"]);
+    }
+
+    #[test]
+    fn test_a_star_around_body() {
+        let g = game_fixture(include_str!("../../../fixtures/voronoi_duel.json"));
+        let snake_id_mapping = build_snake_id_map(&g);
+        let compact: CellBoard4Snakes11x11 = g.as_cell_board(&snake_id_mapping).unwrap();
+        let from = CellIndex::new(Position::new(2, 4), 11);
+        let to = CellIndex::new(Position::new(2, 6), 11);
+
+        // the tail at (1, 5) will have moved by the time we get there
+        let path = compact.a_star(&from, &to, AStarOptions::default()).unwrap();
+        assert_eq!(path.len(), 4);
+        assert_eq!(path[..2], [Move::Left, Move::Up]);
+        let wire = g.a_star(
+            &Position::new(2, 4),
+            &Position::new(2, 6),
+            AStarOptions::default(),
+        );
+        assert_eq!(wire.map(|p| p.len()), Some(4));
+
+        // otherwise we have to go all the way around the body
+        let bodies_block = AStarOptions {
+            bodies_block: true,
+            ..Default::default()
+        };
+        let path = compact.a_star(&from, &to, bodies_block).unwrap();
+        assert_eq!(path.len(), 6);
+        let mut end = from.into_position(11);
+        for mv in path {
+            end = end.add_vec(mv.to_vector());
+            assert!(!compact.position_is_snake_body(CellIndex::new(end, 11)));
+        }
+        assert_eq!(end, to.into_position(11));
+    }
+
+    #[test]
+    fn test_a_star_no_path() {
+        let g = game_fixture(include_str!("../../../fixtures/sealed_region.json"));
+        let snake_id_mapping = build_snake_id_map(&g);
+        let compact: CellBoard4Snakes11x11 = g.as_cell_board(&snake_id_mapping).unwrap();
+        let from = CellIndex::new(Position::new(0, 0), 11);
+        let to = CellIndex::new(Position::new(5, 5), 11);
+
+        assert_eq!(compact.a_star(&from, &to, AStarOptions::default()), None);
+        assert_eq!(
+            g.a_star(
+                &Position::new(0, 0),
+                &Position::new(5, 5),
+                AStarOptions::default()
+            ),
+            None
+        );
+        assert_eq!(
+            compact.a_star(&from, &from, AStarOptions::default()),
+            Some(vec![])
+        );
+    }
+
+    #[test]
+    fn test_distance_map() {
+        let g = game_fixture(include_str!("../../../fixtures/voronoi_duel.json"));
+        let snake_id_mapping = build_snake_id_map(&g);
+        let compact: CellBoard4Snakes11x11 = g.as_cell_board(&snake_id_mapping).unwrap();
+        let from = compact.get_head_as_native_position(&SnakeId(0));
+
+        let distances = compact.distance_map(&from, AStarOptions::default());
+        let distance_to = |x, y| {
+            distances
+                .get(&CellIndex::new(Position::new(x, y), 11))
+                .copied()
+        };
+        assert_eq!(distance_to(3, 5), Some(0));
+        assert_eq!(distance_to(4, 5), Some(1));
+        assert_eq!(distance_to(10, 10), Some(12));
+        // our own neck has moved out of the way once we've gone around it
+        assert_eq!(distance_to(2, 5), Some(3));
+        assert_eq!(distance_to(7, 5), Some(4));
+        assert_eq!(distances.len(), 11 * 11);
+
+        let wire = g.distance_map(&Position::new(3, 5), AStarOptions::default());
+        assert_eq!(
+            wire,
+            distances
+                .into_iter()
+                .map(|(ci, d)| (ci.into_position(11), d))
+                .collect()
+        );
+
+        let bodies_block = AStarOptions {
+            bodies_block: true,
+            ..Default::default()
+        };
+        let distances = compact.distance_map(&from, bodies_block);
+        assert_eq!(distances.len(), 11 * 11 - 5);
+
+        // hazard cost changes the costs, but not the number of moves
+        let mut g = g;
+        g.board.hazards.push(Position::new(4, 5));
+        let compact: CellBoard4Snakes11x11 = g.as_cell_board(&snake_id_mapping).unwrap();
+        let hazard_cost = AStarOptions {
+            hazard_cost: 5,
+            ..Default::default()
+        };
+        let hazard = CellIndex::new(Position::new(4, 5), 11);
+        let distances = compact.distance_map(&from, hazard_cost);
+        let costs = compact.cost_map(&from, hazard_cost);
+        assert_eq!(distances.get(&hazard), Some(&1));
+        assert_eq!(costs.get(&hazard), Some(&6));
+        assert_eq!(distances, compact.cost_map(&from, AStarOptions::default()));
+    }
+
+    #[test]
+    fn test_can_safely_chase_tail() {
+        let mut builder = CellBoardBuilder::new(11, 11);
+        let body = [(2, 3), (3, 3), (3, 2), (2, 2)]
+            .iter()
+            .map(|(x, y)| Position::new(*x, *y))
+            .collect();
+        builder.add_snake(body, 90).unwrap();
+        let below = [(2, 1), (1, 1), (0, 1)]
+            .iter()
+            .map(|(x, y)| Position::new(*x, *y))
+            .collect();
+        builder.add_snake(below, 90).unwrap();
+        let board: CellBoard4Snakes11x11 = builder.build().unwrap();
+        let analysis = board.can_safely_chase_tail(&SnakeId(0));
+        assert_eq!(analysis.cycle_length, Some(4));
+        // the snake below our tail is shorter, so it can't get in the way
+        assert!(analysis.is_safe());
+
+        builder
+            .add_snake(
+                vec![
+                    Position::new(4, 2),
+                    Position::new(5, 2),
+                    Position::new(6, 2),
+                    Position::new(7, 2),
+                ],
+                90,
+            )
+            .unwrap();
+        let board: CellBoard4Snakes11x11 = builder.build().unwrap();
+        let analysis = board.can_safely_chase_tail(&SnakeId(0));
+        // but one as long as us can get on to our neck as our head gets there
+        assert_eq!(analysis.broken_at, Some(2));
+        assert!(!analysis.is_safe());
+    }
+
+    #[test]
+    fn test_project_snake_into_wall() {
+        let mut builder = CellBoardBuilder::new(11, 11);
+        let body = [(8, 5), (7, 5), (6, 5)]
+            .iter()
+            .map(|(x, y)| Position::new(*x, *y))
+            .collect();
+        builder.add_snake(body, 90).unwrap();
+        let far = vec![Position::new(2, 8), Position::new(2, 7)];
+        builder.add_snake(far, 90).unwrap();
+        let board: CellBoard4Snakes11x11 = builder.build().unwrap();
+
+        let projected = board
+            .project_snake(&SnakeId(0), 4, &TrajectoryAssumption::Straight)
+            .unwrap();
+        assert_eq!(
+            projected
+                .heads()
+                .map(|ci| ci.into_position(11))
+                .collect_vec(),
+            vec![Position::new(9, 5), Position::new(10, 5)]
+        );
+        assert_eq!(projected.stopped, Some(ProjectionStop::LeftBoard));
+        assert_eq!(
+            board.project_snake(&SnakeId(2), 4, &TrajectoryAssumption::Straight),
+            None
+        );
+    }
+
+    #[test]
+    fn test_food_race_analysis() {
+        let g = game_fixture(include_str!("../../../fixtures/food_race.json"));
+        let snake_id_mapping = build_snake_id_map(&g);
+        let compact: CellBoard4Snakes11x11 = g.as_cell_board(&snake_id_mapping).unwrap();
+
+        let races = compact.food_race_analysis(AStarOptions::default());
+        let summary = races
+            .iter()
+            .map(|race| {
+                (
+                    race.position.into_position(11),
+                    race.my_distance,
+                    race.best_opponent_distance,
+                    race.winnable,
+                )
+            })
+            .collect_vec();
+        assert_eq!(
+            summary,
+            vec![
+                (Position::new(1, 8), Some(5), Some(9), true),
+                // both snakes get here at the same time and are the same length
+                (Position::new(5, 0), Some(7), Some(7), false),
+                (Position::new(9, 2), Some(9), Some(5), false),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_choke_points() {
+        let g = game_fixture(include_str!("../../../fixtures/choke_pocket.json"));
+        let snake_id_mapping = build_snake_id_map(&g);
+        let compact: CellBoard4Snakes11x11 = g.as_cell_board(&snake_id_mapping).unwrap();
+
+        let choke_points = compact.choke_points();
+        assert_eq!(choke_points.len(), 13);
+        assert_eq!(
+            choke_points[&CellIndex::new(Position::new(1, 2), 11)],
+            RegionInfo {
+                choke_point: CellIndex::new(Position::new(0, 5), 11),
+                region_size: 13,
+            }
+        );
+        assert_eq!(
+            g.choke_points(),
+            choke_points
+                .into_iter()
+                .map(|(ci, region)| (
+                    ci.into_position(11),
+                    RegionInfo {
+                        choke_point: region.choke_point.into_position(11),
+                        region_size: region.region_size,
+                    }
+                ))
+                .collect()
+        );
+    }
+
+    fn transform_game(g: &DEGame, symmetry: Symmetry) -> DEGame {
+        let (width, height) = (g.board.width as u32, g.board.height as u32);
+        let transform = |pos: &Position| symmetry.transform_position(*pos, width, height);
+        let mut g = g.clone();
+        for snake in g.board.snakes.iter_mut().chain(std::iter::once(&mut g.you)) {
+            snake.head = transform(&snake.head);
+            snake.body = snake.body.iter().map(transform).collect();
+        }
+        g.board.food = g.board.food.iter().map(transform).collect();
+        g.board.hazards = g.board.hazards.iter().map(transform).collect();
+        g
+    }
+
+    #[test]
+    fn test_will_be_unoccupied() {
+        let mut g = game_fixture(include_str!("../../../fixtures/choke_pocket.json"));
+        g.board.food.insert(Position::new(1, 5));
+        let snake_id_mapping = build_snake_id_map(&g);
+        let compact: CellBoard4Snakes11x11 = g.as_cell_board(&snake_id_mapping).unwrap();
+
+        for pos in compact.all_positions() {
+            for turns in 0..8 {
+                assert_eq!(
+                    compact.will_be_unoccupied(&CellIndex::new(pos, 11), turns),
+                    g.will_be_unoccupied(&pos, turns),
+                );
+            }
+        }
+        let piece = CellIndex::new(Position::new(3, 2), 11);
+        assert_eq!(
+            compact.will_be_unoccupied(&piece, 3),
+            SquareSafety::DependsOnFood
+        );
+    }
+
+    /// voronoi_duel with the opponent moved forward so both heads are next to (4, 5)
+    fn head_to_head_game(my_length: usize, their_length: usize, my_health: i32) -> DEGame {
+        let mut g = game_fixture(include_str!("../../../fixtures/voronoi_duel.json"));
+        g.board.snakes[0].body = (0..my_length)
+            .map(|i| Position::new(3 - i as i32, 5))
+            .collect();
+        g.board.snakes[0].health = my_health;
+        g.board.snakes[1].head = Position::new(5, 5);
+        g.board.snakes[1].body = (0..their_length)
+            .map(|i| Position::new(5 + i as i32, 5))
+            .collect();
+        g.you = g.board.snakes[0].clone();
+        g
+    }
+
+    #[test]
+    fn test_predict_head_to_head() {
+        let instruments = Instruments;
+        let target = CellIndex::new(Position::new(4, 5), 11);
+        let cases = [
+            (4, 3, 90, false, HeadToHeadOutcome::IWin),
+            (3, 3, 90, false, HeadToHeadOutcome::BothDie),
+            (3, 4, 90, false, HeadToHeadOutcome::ILose),
+            // we would starve before winning
+            (4, 3, 1, false, HeadToHeadOutcome::ILose),
+            // eating on the square keeps us alive, and both snakes grow so we are still longer
+            (4, 3, 1, true, HeadToHeadOutcome::IWin),
+            (3, 3, 90, true, HeadToHeadOutcome::BothDie),
+        ];
+        for (my_length, their_length, my_health, food, expected) in cases {
+            let mut g = head_to_head_game(my_length, their_length, my_health);
+            if food {
+                g.board.food.insert(Position::new(4, 5));
+            }
+            let snake_id_mapping = build_snake_id_map(&g);
+            let compact: CellBoard4Snakes11x11 = g.as_cell_board(&snake_id_mapping).unwrap();
+
+            let outcome = compact.predict_head_to_head(&SnakeId(0), &SnakeId(1), &target);
+            assert_eq!(outcome, expected);
+
+            let moves = [
+                (SnakeId(0), [Move::Right].as_slice()),
+                (SnakeId(1), [Move::Left].as_slice()),
+            ];
+            let next = compact
+                .simulate_with_moves(&instruments, moves)
+                .collect_vec()[0]
+                .1;
+            let alive = (
+                next.get_health(&SnakeId(0)) > 0,
+                next.get_health(&SnakeId(1)) > 0,
+            );
+            let simulated = match alive {
+                (true, false) => HeadToHeadOutcome::IWin,
+                (false, true) => HeadToHeadOutcome::ILose,
+                _ => HeadToHeadOutcome::BothDie,
+            };
+            assert_eq!(outcome, simulated);
+        }
+    }
+
+    #[test]
+    fn test_predict_head_to_head_not_adjacent() {
+        let g = head_to_head_game(3, 3, 90);
+        let snake_id_mapping = build_snake_id_map(&g);
+        let compact: CellBoard4Snakes11x11 = g.as_cell_board(&snake_id_mapping).unwrap();
+
+        let far = CellIndex::new(Position::new(3, 6), 11);
+        assert_eq!(
+            compact.predict_head_to_head(&SnakeId(0), &SnakeId(1), &far),
+            HeadToHeadOutcome::NotAdjacent
+        );
+        let neck = CellIndex::new(Position::new(2, 5), 11);
+        assert_eq!(
+            compact.predict_head_to_head(&SnakeId(0), &SnakeId(1), &neck),
+            HeadToHeadOutcome::NotAdjacent
+        );
+    }
+
+    #[test]
+    fn test_canonicalize_symmetric_positions() {
+        let g = game_fixture(include_str!("../../../fixtures/late_stage.json"));
+        let snake_id_mapping = build_snake_id_map(&g);
+        let compact: CellBoard4Snakes11x11 = g.as_cell_board(&snake_id_mapping).unwrap();
+        let (canonical, _) = compact.canonicalize();
+
+        for symmetry in Symmetry::all() {
+            let transformed: CellBoard4Snakes11x11 = transform_game(&g, symmetry)
+                .as_cell_board(&snake_id_mapping)
+                .unwrap();
+            let (other, _) = transformed.canonicalize();
+            assert!(other.embedded.assert_consistency());
+            assert_eq!(other, canonical);
+        }
+    }
+
+    #[test]
+    fn test_canonical_moves_map_back() {
+        let g = game_fixture(include_str!("../../../fixtures/food_race.json"));
+        let snake_id_mapping = build_snake_id_map(&g);
+        let g = transform_game(&g, Symmetry::Rotate90);
+        let compact: CellBoard4Snakes11x11 = g.as_cell_board(&snake_id_mapping).unwrap();
+        let (canonical, symmetry) = compact.canonicalize();
+        assert_ne!(symmetry, Symmetry::Identity);
+
+        let food = Symmetry::Rotate90.transform_position(Position::new(1, 8), 11, 11);
+        let head = compact.get_head_as_position(&SnakeId(0));
+        let canonical_head = canonical.get_head_as_native_position(&SnakeId(0));
+        let canonical_food = CellIndex::new(symmetry.transform_position(food, 11, 11), 11);
+        let mv = canonical
+            .a_star(&canonical_head, &canonical_food, AStarOptions::default())
+            .unwrap()[0];
+
+        let original_mv = mv.transformed(symmetry.inverse());
+        let next = head.add_vec(original_mv.to_vector());
+        assert_eq!(
+            symmetry.transform_position(next, 11, 11),
+            canonical_head.into_position(11).add_vec(mv.to_vector())
+        );
+        let distance = |p: Position| (p.x - food.x).abs() + (p.y - food.y).abs();
+        assert_eq!(distance(next), distance(head) - 1);
+    }
+
+    #[test]
+    fn test_set_hazard() {
+        let mut c: Cell<u8> = Cell::empty();
+        c.set_food();
+        assert!(c.is_food());
+        c.set_hazard();
+        assert!(c.is_food());
+        assert!(c.is_hazard());
+        assert!(!c.is_head());
+        assert!(!c.is_body());
+    }
+
+    #[test]
+    fn test_clear_hazard() {
+        let mut c: Cell<u8> = Cell::empty();
+        c.set_food();
+        assert!(c.is_food());
+        c.set_hazard();
+        c.clear_hazard();
+        assert!(c.is_food());
+        assert!(!c.is_hazard());
+        assert!(!c.is_head());
+        assert!(!c.is_body());
+        let mut c: Cell<u8> = Cell::make_double_stacked_piece(SnakeId(0), CellIndex(0));
+        c.set_hazard();
+        c.clear_hazard();
+        assert!(c.is_body());
+        assert!(!c.is_hazard());
+    }
+
+    #[test]
+    fn test_stacked_hazards() {
+        let mut c: Cell<u8> = Cell::make_body_piece(SnakeId(1), CellIndex(4));
+        for expected in 1..=MAX_HAZARD_STACK + 2 {
+            c.add_hazard();
+            assert_eq!(c.hazard_count(), expected.min(MAX_HAZARD_STACK));
+        }
+        assert!(c.is_body());
+        c.set_hazard();
+        assert_eq!(c.hazard_count(), MAX_HAZARD_STACK);
+        c.clear_hazard();
+        assert_eq!(c.hazard_count(), 0);
+        c.set_hazard();
+        assert_eq!(c.hazard_count(), 1);
+        assert_eq!(c.get_idx(), CellIndex(4));
+    }
+
+    #[test]
+    fn test_stacked_hazard_damage() {
+        let mut builder = CellBoardBuilder::new(11, 11);
+        builder
+            .add_snake(
+                vec![
+                    Position::new(5, 5),
+                    Position::new(5, 4),
+                    Position::new(5, 3),
+                ],
+                90,
+            )
+            .unwrap();
+        builder.hazard_damage(14);
+        for _ in 0..3 {
+            builder.add_hazard(Position::new(5, 6)).unwrap();
+        }
+        builder.add_hazard(Position::new(4, 5)).unwrap();
+        let board: CellBoard4Snakes11x11 = builder.build().unwrap();
+        let up = CellIndex::new(Position::new(5, 6), 11);
+        let left = CellIndex::new(Position::new(4, 5), 11);
+        assert_eq!(board.get_hazard_count(&up), 3);
+        assert_eq!(board.get_hazard_count(&left), 1);
+        assert_eq!(builder.game().get_hazard_count(&Position::new(5, 6)), 3);
+
+        let healths = board
+            .simulate_with_moves(
+                &Instruments,
+                [(SnakeId(0), [Move::Up, Move::Left, Move::Right])],
+            )
+            .map(|(action, next)| (action.own_move(), next.get_health(&SnakeId(0))))
+            .collect_vec();
+        assert_eq!(
+            healths,
+            vec![
+                (Move::Up, 90 - 1 - 3 * 14),
+                (Move::Left, 90 - 1 - 14),
+                (Move::Right, 90 - 1)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_editing_food_and_hazards() {
+        let mut builder = CellBoardBuilder::new(11, 11);
+        builder
+            .add_snake(
+                vec![
+                    Position::new(5, 5),
+                    Position::new(5, 4),
+                    Position::new(5, 3),
+                ],
+                50,
+            )
+            .unwrap();
+        let mut board: CellBoard4Snakes11x11 = builder.build().unwrap();
+        let at = |x, y| CellIndex::new(Position::new(x, y), 11);
+
+        board.place_food(at(5, 6)).unwrap();
+        board.set_hazard(at(4, 5));
+        board.place_food(at(4, 5)).unwrap();
+        board.set_hazard(at(6, 5));
+        assert!(board.is_food(&at(5, 6)));
+        assert_eq!(
+            board.get_all_food_as_native_positions(),
+            vec![at(4, 5), at(5, 6)]
+        );
+        assert!(matches!(
+            board.place_food(at(5, 4)),
+            Err(Error::InvalidFood(_))
+        ));
+        assert!(board.place_food(CellIndex::from_usize(11 * 11)).is_err());
+        assert!(board.check_invariants().is_ok());
+
+        let next = |board: &CellBoard4Snakes11x11| {
+            board
+                .simulate_with_moves(
+                    &Instruments,
+                    [(SnakeId(0), [Move::Up, Move::Left, Move::Right])],
+                )
+                .map(|(action, next)| {
+                    let sid = SnakeId(0);
+                    (
+                        action.own_move(),
+                        next.get_health(&sid),
+                        next.get_length(&sid),
+                    )
+                })
+                .collect_vec()
+        };
+        // food on a hazard still heals fully, like the engine
+        assert_eq!(
+            next(&board),
+            vec![
+                (Move::Up, 100, 4),
+                (Move::Left, 100, 4),
+                (Move::Right, 50 - 1 - 15, 3)
+            ]
+        );
+
+        board.remove_food(at(5, 6));
+        board.remove_food(at(4, 5));
+        board.clear_hazard(at(6, 5));
+        // removing the food leaves the hazard under it
+        assert!(board.is_hazard(&at(4, 5)));
+        assert!(board.get_all_food_as_native_positions().is_empty());
+        assert_eq!(
+            next(&board),
+            vec![
+                (Move::Up, 49, 3),
+                (Move::Left, 50 - 1 - 15, 3),
+                (Move::Right, 49, 3)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_simulation_settings() {
+        let mut builder = CellBoardBuilder::new(11, 11);
+        builder
+            .add_snake(
+                vec![
+                    Position::new(5, 5),
+                    Position::new(5, 4),
+                    Position::new(5, 3),
+                ],
+                40,
+            )
+            .unwrap();
+        builder.hazard_damage(0);
+        builder.add_hazard(Position::new(5, 6)).unwrap();
+        builder.add_hazard(Position::new(5, 6)).unwrap();
+        builder.add_food(Position::new(4, 5)).unwrap();
+        // a healing pool
+        let mut game = builder.game().clone();
+        let ruleset = &mut game.game.ruleset;
+        ruleset.settings.as_mut().unwrap().hazard_damage_per_turn = -10;
+        assert_eq!(ruleset.simulation_settings().hazard_damage, -10);
+        let snake_ids = build_snake_id_map(&game);
+        let board = CellBoard4Snakes11x11::convert_from_game(game, &snake_ids).unwrap();
+        assert_eq!(board.get_hazard_damage(), -10);
+
+        let moves = [(SnakeId(0), [Move::Up, Move::Left, Move::Right])];
+        let simulate = |board: &CellBoard4Snakes11x11, settings: &SimulationSettings| {
+            board
+                .simulate_with_moves_and_settings(&Instruments, moves, settings)
+                .map(|(action, next)| {
+                    assert!(next.assert_consistency());
+                    let sid = SnakeId(0);
+                    (
+                        action.own_move(),
+                        next.get_health(&sid),
+                        next.get_length(&sid),
+                    )
+                })
+                .collect_vec()
+        };
+
+        let standard = board.simulation_settings();
+        assert_eq!(
+            simulate(&board, &standard),
+            vec![(Move::Up, 59), (Move::Left, 100), (Move::Right, 39)]
+                .into_iter()
+                .map(|(mv, health)| (mv, health, if mv == Move::Left { 4 } else { 3 }))
+                .collect_vec()
+        );
+        let healths = |results: Vec<(Move, u8, u16)>| {
+            results
+                .into_iter()
+                .map(|(_, health, _)| health)
+                .collect_vec()
+        };
+
+        let max_50 = SimulationSettings {
+            max_health: 50,
+            ..standard
+        };
+        assert_eq!(healths(simulate(&board, &max_50)), vec![50, 50, 39]);
+
+        let no_food_health = SimulationSettings {
+            food_restores_health: false,
+            ..standard
+        };
+        assert_eq!(healths(simulate(&board, &no_food_health)), vec![59, 39, 39]);
+
+        let no_starvation = SimulationSettings {
+            starvation_enabled: false,
+            ..standard
+        };
+        assert_eq!(healths(simulate(&board, &no_starvation)), vec![60, 100, 40]);
+
+        // every snake grows and gets its health back, eating or not
+        let always_grow = SimulationSettings {
+            always_grow: true,
+            ..standard
+        };
+        assert_eq!(
+            simulate(&board, &always_grow),
+            vec![
+                (Move::Up, 100, 4),
+                (Move::Left, 100, 4),
+                (Move::Right, 100, 4)
+            ]
+        );
+        let mut growing = board;
+        growing.set_simulation_settings(always_grow);
+        let decoded = CellBoard4Snakes11x11::from_bytes(&growing.to_bytes()).unwrap();
+        assert_eq!(decoded.simulation_settings(), always_grow);
+
+        // the boards own settings are used without being passed in, and survive packing
+        let mut capped = board;
+        capped.set_simulation_settings(max_50);
+        let results = capped
+            .simulate_with_moves(&Instruments, moves)
+            .map(|(_, next)| next.get_health(&SnakeId(0)))
+            .collect_vec();
+        assert_eq!(results, vec![50, 50, 39]);
+        let unpacked: CCB<u8, Square, { 11 * 11 }, 4> =
+            CCB::from_packed_hash(&capped.embedded.pack_as_hash());
+        assert_eq!(unpacked.simulation_settings(), max_50);
+    }
+
+    #[test]
+    fn test_healing_hazards() {
+        let mut builder = CellBoardBuilder::new(11, 11);
+        builder
+            .add_snake(
+                vec![
+                    Position::new(5, 5),
+                    Position::new(5, 4),
+                    Position::new(5, 3),
+                ],
+                40,
+            )
+            .unwrap();
+        builder.hazard_damage(-10);
+        builder.add_hazard(Position::new(5, 6)).unwrap();
+        let board: CellBoard4Snakes11x11 = builder.build().unwrap();
+        let up = [(SnakeId(0), [Move::Up])];
+        let health_after = |board: &CellBoard4Snakes11x11, settings: &SimulationSettings| {
+            let (_, next) = board
+                .simulate_with_moves_and_settings(&Instruments, up, settings)
+                .next()
+                .unwrap();
+            next.get_health(&SnakeId(0))
+        };
+
+        // the turn's point of starvation still comes off, like in the engine
+        let settings = board.simulation_settings();
+        assert_eq!(settings.hazard_damage, -10);
+        assert_eq!(health_after(&board, &settings), 49);
+        let no_starvation = SimulationSettings {
+            starvation_enabled: false,
+            ..settings
+        };
+        assert_eq!(health_after(&board, &no_starvation), 50);
+        assert_eq!(
+            health_after(&board.with_health(SnakeId(0), 95), &settings),
+            100
+        );
+        assert_eq!(
+            health_after(&board.with_health(SnakeId(0), 1), &settings),
+            10
+        );
+    }
+
+    #[test]
+    fn test_rules_versions() {
+        let mut builder = CellBoardBuilder::new(11, 11);
+        builder
+            .add_snake(
+                vec![
+                    Position::new(5, 5),
+                    Position::new(5, 4),
+                    Position::new(5, 3),
+                ],
+                10,
+            )
+            .unwrap();
+        builder.add_food(Position::new(5, 6)).unwrap();
+        builder.add_hazard(Position::new(5, 6)).unwrap();
+        let board: CellBoard4Snakes11x11 = builder.build().unwrap();
+        let up = [(SnakeId(0), [Move::Up])];
+        let health_after = |board: &CellBoard4Snakes11x11, rules_version| {
+            let settings = SimulationSettings {
+                rules_version,
+                ..board.simulation_settings()
+            };
+            let (_, next) = board
+                .simulate_with_moves_and_settings(&Instruments, up, &settings)
+                .next()
+                .unwrap();
+            next.get_health(&SnakeId(0))
+        };
+
+        // eating on a hazard is safe now, but used to take the health first
+        assert_eq!(
+            board.simulation_settings().rules_version,
+            RulesVersion::Current
+        );
+        assert_eq!(health_after(&board, RulesVersion::Current), 100);
+        assert_eq!(health_after(&board, RulesVersion::V1_1_x), 0);
+
+        // with enough health to take the damage they both end up eating
+        let healthy = board.with_health(SnakeId(0), 50);
+        assert_eq!(health_after(&healthy, RulesVersion::Current), 100);
+        assert_eq!(health_after(&healthy, RulesVersion::V1_1_x), 100);
+
+        // and the version survives packing
+        let mut old = board;
+        old.set_simulation_settings(SimulationSettings {
+            rules_version: RulesVersion::V1_1_x,
+            ..board.simulation_settings()
+        });
+        let unpacked: CCB<u8, Square, { 11 * 11 }, 4> =
+            CCB::from_packed_hash(&old.embedded.pack_as_hash());
+        assert_eq!(unpacked.simulation_settings(), old.simulation_settings());
+        let decoded = CellBoard4Snakes11x11::from_bytes(&old.to_bytes()).unwrap();
+        assert_eq!(
+            decoded.simulation_settings().rules_version,
+            RulesVersion::V1_1_x
+        );
+    }
+
+    #[test]
+    fn test_adjusted_snakes() {
+        let body = |squares: &[(i32, i32)]| {
+            squares
+                .iter()
+                .map(|(x, y)| Position::new(*x, *y))
+                .collect_vec()
+        };
+        let to_index = |squares: &[(i32, i32)]| {
+            body(squares)
+                .into_iter()
+                .map(|p| CellIndex::new(p, 11))
+                .collect_vec()
+        };
+        let mut builder = CellBoardBuilder::new(11, 11);
+        builder
+            .add_snake(body(&[(5, 5), (5, 4), (5, 3)]), 80)
+            .unwrap();
+        builder
+            .add_snake(body(&[(8, 8), (8, 7), (8, 6), (8, 6)]), 100)
+            .unwrap();
+        builder
+            .add_snake(body(&[(2, 2), (1, 2), (0, 2)]), 50)
+            .unwrap();
+        builder
+            .add_snake(body(&[(5, 0), (6, 0), (6, 1)]), 50)
+            .unwrap();
+        builder.add_food(Position::new(6, 2)).unwrap();
+        let board: CellBoard4Snakes11x11 = builder.build().unwrap();
+        let (me, fed, edge, hungry) = (SnakeId(0), SnakeId(1), SnakeId(2), SnakeId(3));
+
+        let longer = board.with_length_adjusted(me, 2).unwrap();
+        assert_eq!(
+            longer.get_snake_body_vec(&me),
+            to_index(&[(5, 5), (5, 4), (5, 3), (5, 2), (5, 1)])
+        );
+        assert_eq!(longer.get_length(&me), 5);
+        let shorter = board.with_length_adjusted(me, -1).unwrap();
+        assert_eq!(shorter.get_snake_body_vec(&me), to_index(&[(5, 5), (5, 4)]));
+        assert_eq!(shorter.get_length(&me), 2);
+        // snakes that just ate stay stacked
+        let grown = board.with_length_adjusted(fed, 1).unwrap();
+        assert_eq!(
+            grown.get_snake_body_vec(&fed),
+            to_index(&[(8, 8), (8, 7), (8, 6), (8, 5), (8, 5)])
+        );
+        let weak = board.with_health(fed, 30).with_health(edge, 0);
+        assert_eq!(weak.get_health(&fed), 30);
+        assert_eq!(weak.get_snake_ids(), vec![me, fed, hungry]);
+        assert!(!weak
+            .embedded
+            .cell_is_body(CellIndex::new(Position::new(1, 2), 11)));
+        assert_eq!(weak.with_health(edge, 40), weak);
+
+        for adjusted in [longer, shorter, grown, weak].iter() {
+            assert!(adjusted.assert_consistency());
+            for (_, next) in adjusted.simulate(&Instruments, adjusted.get_snake_ids()) {
+                assert!(next.assert_consistency());
+            }
+        }
+
+        // growing in to another snake, off the board, or on to food
+        assert!(matches!(
+            board.with_length_adjusted(me, 3),
+            Err(Error::InvalidSnake { .. })
+        ));
+        assert!(board.with_length_adjusted(edge, 1).is_err());
+        assert!(board.with_length_adjusted(hungry, 1).is_err());
+        // too short, and not playing
+        assert!(board.with_length_adjusted(me, -2).is_err());
+        assert!(matches!(
+            weak.with_length_adjusted(edge, 1),
+            Err(Error::UnknownSnake(_))
+        ));
+
+        // snakes at the start of the game have no tail to grow from
+        let mut builder = CellBoardBuilder::new(11, 11);
+        builder
+            .add_snake(body(&[(5, 5), (5, 5), (5, 5)]), 100)
+            .unwrap();
+        let start: CellBoard4Snakes11x11 = builder.build().unwrap();
+        assert!(start.with_length_adjusted(me, 1).is_err());
+        assert!(start.with_length_adjusted(me, -1).is_err());
+        assert_eq!(start.with_length_adjusted(me, 0).unwrap(), start);
+    }
+
+    fn assert_bytes_round_trip<
+        T: CN,
+        D: Dimensions + PartialEq,
+        const BOARD_SIZE: usize,
+        const MAX_SNAKES: usize,
+    >(
+        board: &CellBoard<T, D, BOARD_SIZE, MAX_SNAKES>,
+    ) {
+        let read = CellBoard::<T, D, BOARD_SIZE, MAX_SNAKES>::from_bytes(&board.to_bytes());
+        assert_eq!(&read.unwrap(), board);
+        for (_, next) in board.simulate(&Instruments, board.get_snake_ids()) {
+            let read = CellBoard::<T, D, BOARD_SIZE, MAX_SNAKES>::from_bytes(&next.to_bytes());
+            assert_eq!(read.unwrap(), next);
+        }
+    }
+
+    #[test]
+    fn test_deltas_match_simulation() {
+        let g = game_fixture(include_str!("../../../fixtures/late_stage.json"));
+        let board: CellBoard4Snakes11x11 = g.as_cell_board(&build_snake_id_map(&g)).unwrap();
+        let parent = std::sync::Arc::new(board);
+        let all_moves = |b: &CellBoard4Snakes11x11| {
+            b.get_snake_ids()
+                .into_iter()
+                .map(|sid| (sid, Move::all()))
+                .collect_vec()
+        };
+
+        let deltas = parent
+            .simulate_with_moves_as_deltas(&Instruments, all_moves(&board))
+            .collect_vec();
+        let simulated = board
+            .simulate_with_moves(&Instruments, all_moves(&board))
+            .collect_vec();
+        assert_eq!(deltas.len(), simulated.len());
+        for ((action, delta), (expected_action, expected)) in deltas.iter().zip(simulated) {
+            assert_eq!(action.own_move(), expected_action.own_move());
+            assert_eq!(action.other_moves(), expected_action.other_moves());
+            assert!(std::sync::Arc::ptr_eq(delta.parent(), &parent));
+            assert_eq!(delta.materialize(), expected);
+            assert!(delta.size_in_bytes() < std::mem::size_of_val(&board) / 2);
+        }
+
+        // deltas from a materialized child, and from two turns back
+        for (_, delta) in deltas.iter() {
+            let child = std::sync::Arc::new(delta.materialize());
+            if child.get_snake_ids().is_empty() {
+                continue;
+            }
+            for (_, grandchild) in
+                child.simulate_with_moves_as_deltas(&Instruments, all_moves(&child))
+            {
+                let two_turns = grandchild.materialize().delta_from(&parent);
+                assert_eq!(two_turns.materialize(), grandchild.materialize());
+            }
+        }
+        assert_eq!(board.delta_from(&parent).materialize(), board);
+    }
+
+    #[test]
+    fn test_bytes_round_trip() {
+        let mut games = [
+            include_str!("../../../fixtures/start_of_game.json"),
+            include_str!("../../../fixtures/late_stage.json"),
+            include_str!("../../../fixtures/4_snake_game.json"),
+            include_str!("../../../fixtures/7x7board_valid.json"),
+            include_str!("../../../fixtures/8x8board_valid.json"),
+            include_str!("../../../fixtures/hazard_map_settings.json"),
+            include_str!("../../../fixtures/squad_game.json"),
+        ]
+        .iter()
+        .map(|json| game_fixture(json))
+        .collect_vec();
+        // there aren't fixtures for the other sizes and shapes, so lay the same snakes out on them
+        for (width, height) in [
+            (5, 5),
+            (13, 13),
+            (19, 19),
+            (19, 21),
+            (21, 21),
+            (50, 50),
+            (13, 17),
+            (31, 40),
+        ]
+        .iter()
+        {
+            let mut builder = CellBoardBuilder::new(*width, *height);
+            builder.hazard_damage(-4);
+            builder
+                .add_snake(vec![Position::new(1, 1); 3], 100)
+                .unwrap();
+            let tail = Position::new(width - 1, height - 3);
+            let body = vec![
+                Position::new(width - 1, height - 1),
+                Position::new(width - 1, height - 2),
+                tail,
+                tail,
+            ];
+            builder.add_snake(body, 100).unwrap();
+            builder
+                .add_food(Position::new(width / 2, height / 2))
+                .unwrap();
+            builder.add_hazard(Position::new(0, 0)).unwrap();
+            builder.add_hazard(Position::new(0, 0)).unwrap();
+            builder.add_hazard(Position::new(1, 1)).unwrap();
+            games.push(builder.game().clone());
+        }
+
+        let mut sizes = HashSet::new();
+        for game in games {
+            let best = game.to_best_cell_board().unwrap();
+            sizes.insert(std::mem::discriminant(&best));
+            match best {
+                BestCellBoard::Tiny(board) => assert_bytes_round_trip(&*board),
+                BestCellBoard::SmallExact(board) => assert_bytes_round_trip(&*board),
+                BestCellBoard::Standard(board) => assert_bytes_round_trip(&*board),
+                BestCellBoard::MediumExact(board) => assert_bytes_round_trip(&*board),
+                BestCellBoard::Duel(board) => assert_bytes_round_trip(&*board),
+                BestCellBoard::LargestU8(board) => assert_bytes_round_trip(&*board),
+                BestCellBoard::LargeExact(board) => assert_bytes_round_trip(&*board),
+                BestCellBoard::ArcadeMaze(board) => assert_bytes_round_trip(&*board),
+                BestCellBoard::Large(board) => assert_bytes_round_trip(&*board),
+                BestCellBoard::Silly(board) => assert_bytes_round_trip(&*board),
+                BestCellBoard::CustomU8(board) => assert_bytes_round_trip(&*board),
+                BestCellBoard::Custom(board) => assert_bytes_round_trip(&*board),
+            }
+        }
+        assert_eq!(sizes.len(), 12);
+
+        let g = game_fixture(include_str!("../../../fixtures/late_stage.json"));
+        let board: CellBoard4Snakes11x11 = g.as_cell_board(&build_snake_id_map(&g)).unwrap();
+        assert!(board.to_bytes().len() < 100);
+        let json = serde_json::to_string(&board).unwrap();
+        assert_eq!(
+            serde_json::from_str::<CellBoard4Snakes11x11>(&json).unwrap(),
+            board
+        );
+    }
+
+    #[test]
+    fn test_corrupted_bytes() {
+        let g = game_fixture(include_str!("../../../fixtures/late_stage.json"));
+        let board: CellBoard4Snakes11x11 = g.as_cell_board(&build_snake_id_map(&g)).unwrap();
+        let bytes = board.to_bytes();
+
+        for end in 0..bytes.len() {
+            assert!(CellBoard4Snakes11x11::from_bytes(&bytes[..end]).is_err());
+        }
+        let mut longer = bytes.clone();
+        longer.push(0);
+        assert!(CellBoard4Snakes11x11::from_bytes(&longer).is_err());
+        let mut version = bytes.clone();
+        version[0] = 2;
+        assert!(matches!(
+            CellBoard4Snakes11x11::from_bytes(&version),
+            Err(Error::InvalidBytes(_))
+        ));
+        assert!(matches!(
+            CellBoard4Snakes7x7::from_bytes(&bytes),
+            Err(Error::BoardDoesNotFit { .. })
+        ));
+        // a max health below the snakes' health
+        let mut max_health = bytes.clone();
+        max_health[4] = 10;
+        assert!(matches!(
+            CellBoard4Snakes11x11::from_bytes(&max_health),
+            Err(Error::InvalidBytes(_))
+        ));
+
+        // every single bit flip either errors or reads as another board the engine could send
+        for i in 0..bytes.len() {
+            for bit in 0..8 {
+                let mut corrupted = bytes.clone();
+                corrupted[i] ^= 1 << bit;
+                if let Ok(read) = CellBoard4Snakes11x11::from_bytes(&corrupted) {
+                    read.check_invariants().unwrap();
+                    assert_eq!(
+                        CellBoard4Snakes11x11::from_bytes(&read.to_bytes()).unwrap(),
+                        read
+                    );
+                    for (_, next) in read.simulate(&Instruments, read.get_snake_ids()) {
+                        assert!(next.assert_consistency());
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_unstacking_from_spawn() {
+        // a real turn 0 request, every snake is 3 pieces on its spawn square
+        let mut g = game_fixture(include_str!("../../../fixtures/this_one_crashed.json"));
+        // and a food next to one of them so it eats on its first move
+        g.board.food.insert(Position::new(5, 2));
+        let snake_ids = build_snake_id_map(&g);
+        let board: CellBoard4Snakes11x11 = g.as_cell_board(&snake_ids).unwrap();
+        let at = |x, y| CellIndex::new(Position::new(x, y), 11);
+        let sid = |id: &str| snake_ids[id];
+        let (eater, other) = (
+            sid("gs_VdKymJdhdfkDKQMXmXkF3Dy9"),
+            sid("gs_Bwx66cxdrRgf7r6qhxdVb8fK"),
+        );
+        for snake in board.get_snake_ids() {
+            assert_eq!(board.get_length(&snake), 3);
+            assert_eq!(board.get_snake_body_vec(&snake).len(), 3);
+        }
+
+        let moves = |eater_move, other_move| {
+            [
+                SnakeMove::new(eater, eater_move),
+                SnakeMove::new(other, other_move),
+                SnakeMove::new(sid("gs_G3m3637DgCMdVwWmmrWjRHbc"), Move::Up),
+                SnakeMove::new(sid("gs_JjgkHVvTWkJ8cCV3BtV3cMCY"), Move::Down),
+            ]
+        };
+        let one = board
+            .advance_turn_with_moves(&Instruments, moves(Move::Up, Move::Up))
+            .unwrap();
+        assert_eq!(one.get_length(&eater), 4);
+        assert_eq!(
+            one.get_snake_body_vec(&eater),
+            vec![at(5, 2), at(5, 1), at(5, 1), at(5, 1)]
+        );
+        assert_eq!(one.get_length(&other), 3);
+        assert_eq!(
+            one.get_snake_body_vec(&other),
+            vec![at(1, 2), at(1, 1), at(1, 1)]
+        );
+        assert_eq!(one.check_invariants(), Ok(()));
+
+        // the engine's next request says the same thing
+        let mut next = g.clone();
+        next.board.food.remove(&Position::new(5, 2));
+        for snake in next.board.snakes.iter_mut() {
+            let sid = snake_ids[&snake.id];
+            snake.body = one
+                .get_snake_body_vec(&sid)
+                .into_iter()
+                .map(|ci| ci.into_position(11))
+                .collect();
+            snake.head = snake.body[0];
+            snake.health = one.get_health_i64(&sid) as i32;
+        }
+        assert_eq!(next.board.snakes[1].body.len(), 4);
+        let converted: CellBoard4Snakes11x11 = next.as_cell_board(&snake_ids).unwrap();
+        assert_eq!(converted, one);
+        assert_bytes_round_trip(&one);
+
+        // the other snake eats its spawn food, while the tail it stacked stays put
+        let two = one
+            .advance_turn_with_moves(&Instruments, moves(Move::Up, Move::Right))
+            .unwrap();
+        assert_eq!(
+            two.get_snake_body_vec(&eater),
+            vec![at(5, 3), at(5, 2), at(5, 1), at(5, 1)]
+        );
+        assert_eq!(two.get_length(&other), 4);
+        assert_eq!(
+            two.get_snake_body_vec(&other),
+            vec![at(2, 2), at(1, 2), at(1, 1), at(1, 1)]
+        );
+        assert_eq!(two.check_invariants(), Ok(()));
+        // nothing can move on to the stacked tails next turn
+        assert!(two.embedded.cell_is_blocked_this_turn(at(5, 1)));
+        assert!(two.embedded.cell_is_blocked_this_turn(at(1, 1)));
+    }
+
+    #[test]
+    fn test_spawning_more_than_3_deep() {
+        let mut g = game_fixture(include_str!("../../../fixtures/this_one_crashed.json"));
+        let spawn = Position::new(5, 1);
+        g.board.snakes[1].body = vec![spawn; 5].into();
+        let snake_ids = build_snake_id_map(&g);
+        let sid = snake_ids[&g.board.snakes[1].id];
+        let mut board: CellBoard4Snakes11x11 = g.as_cell_board(&snake_ids).unwrap();
+        assert_eq!(
+            board.get_snake_body_vec(&sid),
+            vec![CellIndex::new(spawn, 11); 5]
+        );
+
+        // the spawn square stays taken until all 5 pieces have left it
+        let path = [Move::Up, Move::Up, Move::Up, Move::Left, Move::Left];
+        for (turn, mv) in (1..).zip(path) {
+            let (_, next) = board
+                .simulate_with_moves(&Instruments, [(sid, [mv])])
+                .next()
+                .unwrap();
+            board = next;
+            assert_eq!(next.check_invariants(), Ok(()));
+            let body = board.get_snake_body_vec(&sid);
+            assert_eq!(body.len(), 5);
+            assert_eq!(
+                body.iter()
+                    .filter(|ci| ci.into_position(11) == spawn)
+                    .count(),
+                5 - turn
+            );
+        }
+    }
+
+    #[test]
+    fn test_game_result() {
+        let g = game_fixture(include_str!("../../../fixtures/start_of_game.json"));
+        let board: CellBoard4Snakes11x11 = g.as_cell_board(&build_snake_id_map(&g)).unwrap();
+        assert_eq!(board.game_result(), GameResult::Ongoing);
+        // you're out, but the other three snakes play on
+        let eliminated = board.with_health(SnakeId(0), 0);
+        assert!(eliminated.is_over());
+        assert_eq!(eliminated.game_result(), GameResult::Ongoing);
+
+        let head_to_head = |you: Vec<Position>| {
+            let mut builder = CellBoardBuilder::new(11, 11);
+            builder.add_snake(you, 90).unwrap();
+            let them = (4..7).map(|x| Position::new(x, 3)).collect_vec();
+            builder.add_snake(them, 90).unwrap();
+            let board: CellBoard4Snakes11x11 = builder.build().unwrap();
+            let moves = [(SnakeId(0), [Move::Right]), (SnakeId(1), [Move::Left])];
+            let mut results = board.simulate_with_moves(&Instruments, moves.iter().copied());
+            results.next().unwrap().1
+        };
+
+        // snakes of the same length both die moving on to the same square
+        let draw = head_to_head((0..3).rev().map(|x| Position::new(x, 3)).collect_vec());
+        assert!(draw.is_over());
+        assert_eq!(draw.get_winner(), None);
+        assert_eq!(draw.game_result(), GameResult::Draw);
+
+        let mut longer = (0..3).rev().map(|x| Position::new(x, 3)).collect_vec();
+        longer.push(Position::new(0, 2));
+        let won = head_to_head(longer);
+        assert_eq!(won.get_winner(), Some(SnakeId(0)));
+        assert_eq!(won.game_result(), GameResult::Winner(SnakeId(0)));
+    }
+
+    #[test]
+    fn test_remove() {
+        let mut c: Cell<u8> = Cell::make_body_piece(SnakeId(3), CellIndex(17));
+        c.remove();
+        c.set_hazard();
+        assert!(c.is_empty());
+        assert!(c.is_hazard());
+        assert!(c.get_snake_id().is_none());
+        assert!(c.get_idx() == CellIndex(0));
+    }
+    #[test]
+    fn test_set_food() {
+        let mut c: Cell<u8> = Cell::empty();
+        c.set_food();
+        c.set_hazard();
+        assert!(c.is_food());
+        assert!(c.is_hazard());
+        assert!(c.get_snake_id().is_none());
+        assert!(c.get_idx() == CellIndex(0));
+    }
+
+    #[test]
+    fn test_set_head() {
+        let mut c: Cell<u8> = Cell::empty();
+        c.set_head(SnakeId(3), CellIndex(17));
+        c.set_hazard();
+        assert!(c.is_head());
+        assert!(c.is_hazard());
+        assert!(c.get_snake_id().unwrap() == SnakeId(3));
+        assert!(c.get_idx() == CellIndex(17));
+    }
+
+    #[test]
+    fn test_food_queryable() {
+        let game_fixture = include_str!("../../../fixtures/late_stage.json");
+        let g: Result<DEGame, _> = serde_json::from_slice(game_fixture.as_bytes());
+        let g = g.expect("the json literal is valid");
+        let snake_id_mapping = build_snake_id_map(&g);
+        let compact: CellBoard4Snakes11x11 = g.as_cell_board(&snake_id_mapping).unwrap();
+
+        assert!(!compact.is_food(&CellIndex(6 * 11 + 4)));
+
+        assert!(compact.is_food(&CellIndex(2 * 11)));
+        assert!(compact.is_food(&CellIndex(9 * 11)));
+        assert!(compact.is_food(&CellIndex(3 * 11 + 4)));
+    }
+
+    #[test]
+    fn test_neighbors_and_possible_moves_start_of_game() {
+        let game_fixture = include_str!("../../../fixtures/start_of_game.json");
+        let g: Result<DEGame, _> = serde_json::from_slice(game_fixture.as_bytes());
+        let g = g.expect("the json literal is valid");
+        let snake_id_mapping = build_snake_id_map(&g);
+        let compact: CellBoard4Snakes11x11 = g.as_cell_board(&snake_id_mapping).unwrap();
+
+        let head = compact.get_head_as_native_position(&SnakeId(0));
         assert_eq!(head, CellIndex(8 * 11 + 5));
 
         let expected_possible_moves = vec![
@@ -479,4 +2457,103 @@ mod test {
                 .collect::<Vec<_>>()
         );
     }
+
+    /// `board` as a wire game, with each snake renamed from its id on `board` to the position of
+    /// that id in `ids`
+    fn renamed<const N: usize>(
+        board: &CellBoard<u8, Square, { 11 * 11 }, N>,
+        ids: &[SnakeId],
+    ) -> DEGame {
+        let mut game = board.to_game();
+        // you have no id once you've been eliminated
+        let rename = |snake: &mut BattleSnake| {
+            let old = match snake.id.parse::<usize>() {
+                Ok(old) => old,
+                Err(_) => return,
+            };
+            snake.id = ids
+                .iter()
+                .position(|id| id.as_usize() == old)
+                .unwrap()
+                .to_string();
+            snake.name = snake.id.clone();
+        };
+        game.board.snakes.iter_mut().for_each(rename);
+        rename(&mut game.you);
+        game
+    }
+
+    /// checks that `general` and `duel` are the same position, with snake `ids[i]` on
+    /// `general` playing snake `i` on `duel`, and stay the same for every pair of moves
+    /// `depth` turns deep
+    fn assert_duel_matches<const N: usize>(
+        general: &CellBoard<u8, Square, { 11 * 11 }, N>,
+        duel: &DuelCellBoard11x11,
+        ids: &[SnakeId],
+        depth: usize,
+    ) {
+        duel.check_invariants().unwrap();
+        assert_eq!(renamed(general, ids), duel.to_game());
+        let new_id = |old: SnakeId| SnakeId(ids.iter().position(|id| *id == old).unwrap() as u8);
+        assert_eq!(general.get_winner().map(new_id), duel.get_winner());
+        let general_voronoi: HashMap<_, _> = general
+            .voronoi(VoronoiOptions::default())
+            .into_iter()
+            .map(|(sid, count)| (new_id(sid), count))
+            .collect();
+        assert_eq!(general_voronoi, duel.voronoi(VoronoiOptions::default()));
+
+        if depth == 0 || duel.is_over() {
+            return;
+        }
+        for (a, b) in Move::all_iter().cartesian_product(Move::all_iter()) {
+            let (_, general) = general
+                .simulate_with_moves(&Instruments, vec![(ids[0], [a]), (ids[1], [b])])
+                .next()
+                .unwrap();
+            let (_, duel) = duel
+                .simulate_with_moves(&Instruments, vec![(SnakeId(0), [a]), (SnakeId(1), [b])])
+                .next()
+                .unwrap();
+            assert_duel_matches(&general, &duel, ids, depth - 1);
+        }
+    }
+
+    #[test]
+    fn test_duel_matches_general_board() {
+        for fixture in crate::fixtures::DUELS {
+            let general: CellBoard4Snakes11x11 = fixture.compact().unwrap();
+            let duel: DuelCellBoard11x11 = fixture.compact().unwrap();
+            assert_duel_matches(&general, &duel, &[SnakeId(0), SnakeId(1)], 2);
+        }
+    }
+
+    #[test]
+    fn test_respecialized() {
+        let game = game_fixture(include_str!("../../../fixtures/start_of_game.json"));
+        let general: CellBoard4Snakes11x11 =
+            game.as_cell_board(&build_snake_id_map(&game)).unwrap();
+        assert!(matches!(
+            general.respecialized::<2>(),
+            Err(Error::TooManySnakes(4))
+        ));
+
+        let general = general.with_health(SnakeId(1), 0);
+        assert!(matches!(
+            general.respecialized::<2>(),
+            Err(Error::TooManySnakes(3))
+        ));
+        let general = general.with_health(SnakeId(2), 0);
+        let (duel, ids) = general.respecialized::<2>().unwrap();
+        assert_eq!(ids, vec![SnakeId(0), SnakeId(3)]);
+        assert_duel_matches(&general, &duel, &ids, 2);
+
+        // you stay snake 0 even once you've been eliminated
+        let (board, ids) = general
+            .with_health(SnakeId(0), 0)
+            .respecialized::<2>()
+            .unwrap();
+        assert_eq!(ids, vec![SnakeId(0), SnakeId(3)]);
+        assert_eq!(board.get_snake_ids(), vec![SnakeId(1)]);
+    }
 }
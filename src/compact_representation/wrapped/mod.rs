@@ -1,31 +1,50 @@
 //! A compact board representation that is efficient for simulation
 use crate::impl_common_board_traits;
 use crate::types::{
-    build_snake_id_map, FoodGettableGame, FoodQueryableGame, HazardQueryableGame,
-    HazardSettableGame, HeadGettableGame, HealthGettableGame, LengthGettableGame,
-    NeckQueryableGame, PositionGettableGame, RandomReasonableMovesGame, SizeDeterminableGame,
-    SnakeIDGettableGame, SnakeIDMap, SnakeId, VictorDeterminableGame, YouDeterminableGame,
+    AStarOptions, ChokePointGame, FloodFillOptions, FloodFillableGame, FoodGettableGame,
+    FoodQueryableGame, FoodRaceGame, FoodSettableGame, FutureOccupancyGame, GameResult,
+    HazardQueryableGame, HazardSettableGame, HeadGettableGame, HeadSquaresGame, HeadToHeadOutcome,
+    HeadToHeadPredictableGame, HealthGettableGame, LastMoveDeterminableGame, LengthGettableGame,
+    MoveSetPositions, NeckQueryableGame, PathfindableGame, PositionGettableGame,
+    RandomReasonableMovesGame, ReasonableMove, ReasonableMoveLevel, SizeDeterminableGame,
+    SnakeIDGettableGame, SnakeId, Symmetry, TailChaseGame, TrajectoryProjectableGame,
+    VictorDeterminableGame, VoronoiGame, VoronoiOptions, WallQueryableGame,
+    WeightedReasonableMovesGame, YouDeterminableGame,
 };
 
+#[cfg(feature = "std")]
+use crate::types::{try_build_snake_id_map, SnakeIDMap};
 /// you almost certainly want to use the `convert_from_game` method to
 /// cast from a json represention to a `CellBoard`
-use crate::types::{NeighborDeterminableGame, SnakeBodyGettableGame};
-use crate::wire_representation::Game;
-use rand::prelude::IteratorRandom;
+use crate::types::{
+    NeighborDeterminableGame, SnakeBodyGettableGame, SnakeViewGame, NEIGHBOR_OFFSETS,
+};
+#[cfg(feature = "std")]
+use crate::wire_representation::{Game, GameRef, TaggedGame};
+use alloc::boxed::Box;
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+use core::borrow::Borrow;
+use core::fmt::Display;
+use hashbrown::HashMap;
 use rand::Rng;
-use std::borrow::Borrow;
-use std::collections::HashMap;
-use std::error::Error;
-use std::fmt::Display;
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
 
 use crate::{
-    types::{Action, Move, SimulableGame, SimulatorInstruments},
-    wire_representation::Position,
+    types::{
+        Action, Move, Position, SimulableGame, SimulationSettings, SimulatorInstruments, SnakeMove,
+    },
+    Error,
 };
 
-use super::core::{simulate_with_moves, EvaluateMode};
-use super::core::{CellBoard as CCB, CellIndex};
-use super::dimensions::{ArcadeMaze, Dimensions, Fixed, Square};
+use super::core::{
+    advance_with_moves, simulate_with_moves, simulate_with_moves_into, EvaluateMode,
+    InvariantViolation,
+};
+use super::core::{BytesVisitor, CellBoard as CCB, CellBoardDelta as CoreDelta, CellIndex};
+use super::dimensions::{ArcadeMaze, Custom, Dimensions, Fixed, Square};
+#[cfg(feature = "std")]
+use super::dispatch::is_duel;
 use super::CellNum as CN;
 
 /// A compact board representation that is significantly faster for simulation than
@@ -45,34 +64,165 @@ impl<T: CN, D: Dimensions, const BOARD_SIZE: usize, const MAX_SNAKES: usize>
         self.embedded.assert_consistency()
     }
 
+    /// Checks everything the simulator relies on about this board, saying what is wrong if
+    /// anything is
+    pub fn check_invariants(&self) -> Result<(), InvariantViolation> {
+        self.embedded.check_invariants()
+    }
+
+    /// the settings this board is simulated with by default, taken from the ruleset it was
+    /// converted from
+    pub fn simulation_settings(&self) -> SimulationSettings {
+        self.embedded.simulation_settings()
+    }
+
+    /// simulate this board with `settings` from now on, instead of the settings from the ruleset
+    /// it was converted from
+    pub fn set_simulation_settings(&mut self, settings: SimulationSettings) {
+        self.embedded.set_simulation_settings(settings)
+    }
+
+    /// A copy of this board where `snake_id` has `health`, health 0 eliminates the snake. For
+    /// searching the same position with a few guesses at a snakes health
+    pub fn with_health(&self, snake_id: SnakeId, health: u8) -> Self {
+        Self {
+            embedded: self.embedded.with_health(snake_id, health),
+        }
+    }
+
+    /// A copy of this board where `snake_id` is `delta` pieces longer or shorter, growing or
+    /// shrinking at the tail. Errors if there isn't room to grow straight out from the tail, wrapping around the edges,
+    /// or the snake would be left with less than 2 pieces
+    pub fn with_length_adjusted(&self, snake_id: SnakeId, delta: i32) -> Result<Self, Error> {
+        Ok(Self {
+            embedded: self
+                .embedded
+                .with_length_adjusted(snake_id, delta, EvaluateMode::Wrapped)?,
+        })
+    }
+
+    #[cfg(feature = "std")]
     /// creates a wrapped board from a Wire Representation game
-    pub fn convert_from_game(game: Game, snake_ids: &SnakeIDMap) -> Result<Self, Box<dyn Error>> {
+    pub fn convert_from_game(game: Game, snake_ids: &SnakeIDMap) -> Result<Self, Error> {
         if game.game.ruleset.name != "wrapped" {
-            return Err("only wrapped games are supported".into());
+            return Err(Error::UnsupportedRuleset(game.game.ruleset.name));
         }
         let embedded = CCB::convert_from_game(game, snake_ids)?;
         Ok(CellBoard { embedded })
     }
 
+    #[cfg(feature = "std")]
+    /// The same as [CellBoard::convert_from_game], for a game which already has its snakes
+    /// tagged with [Game::with_snake_ids]
+    pub fn convert_from_tagged_game(tagged: &TaggedGame) -> Result<Self, Error> {
+        if !tagged.game().is_wrapped() {
+            return Err(Error::UnsupportedRuleset(
+                tagged.game().game.ruleset.name.clone(),
+            ));
+        }
+        let embedded = CCB::convert_from_tagged_game(tagged)?;
+        Ok(CellBoard { embedded })
+    }
+
+    #[cfg(feature = "std")]
+    /// The same as [CellBoard::convert_from_game], for a game borrowed from the request body
+    pub fn convert_from_game_ref(game: &GameRef, snake_ids: &SnakeIDMap) -> Result<Self, Error> {
+        if !game.is_wrapped() {
+            return Err(Error::UnsupportedRuleset(
+                game.game.ruleset.name.to_string(),
+            ));
+        }
+        let embedded = CCB::convert_from_game_ref(game, snake_ids)?;
+        Ok(CellBoard { embedded })
+    }
+
+    /// this board reflected or rotated so that it matches every other position it is
+    /// equivalent to, along with the symmetry used, see [Symmetry]
+    pub fn canonicalize(&self) -> (Self, Symmetry) {
+        let (embedded, symmetry) = self.embedded.canonicalize();
+        (CellBoard { embedded }, symmetry)
+    }
+
+    /// This position on a board with room for `N` snakes, with you as snake 0 and the other
+    /// living snakes numbered after you, and the id each of its snakes has on this board. Use it
+    /// to switch to a [DuelCellBoardSquare11x11] at the start of a turn once only one opponent is
+    /// left, mapping the moves it finds back with the ids. Errors if the snakes don't fit
+    pub fn respecialized<const N: usize>(
+        &self,
+    ) -> Result<(CellBoard<T, D, BOARD_SIZE, N>, Vec<SnakeId>), Error> {
+        let (embedded, ids) = self.embedded.respecialized()?;
+        Ok((CellBoard { embedded }, ids))
+    }
+
+    /// this board in a small versioned binary format, tens of bytes for most games, for storing
+    /// lots of positions. Read it back with [CellBoard::from_bytes]
+    #[allow(clippy::wrong_self_convention)]
+    pub fn to_bytes(&self) -> Vec<u8> {
+        self.embedded.to_bytes(EvaluateMode::Wrapped)
+    }
+
+    #[cfg(feature = "std")]
+    /// a wire game of the wrapped ruleset with the same snakes, food and hazards as this board,
+    /// with each snake's id its [SnakeId] as a string. The turn and anything else the board
+    /// doesn't know are left as they are in [Game::empty]
+    #[allow(clippy::wrong_self_convention)]
+    pub fn to_game(&self) -> Game {
+        self.embedded.to_game(EvaluateMode::Wrapped)
+    }
+
+    /// reads a board written by [CellBoard::to_bytes]. Errors if the bytes were written by
+    /// another version of the format or for the standard ruleset, don't fit in this board type,
+    /// or have been corrupted
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, Error> {
+        Ok(Self {
+            embedded: CCB::from_bytes(bytes, EvaluateMode::Wrapped)?,
+        })
+    }
+
+    #[cfg(feature = "std")]
     /// for debugging, packs this board into a custom json representation
-    pub fn pack_as_hash(&self) -> HashMap<String, Vec<u32>> {
+    pub fn pack_as_hash(&self) -> std::collections::HashMap<String, Vec<u32>> {
         self.embedded.pack_as_hash()
     }
 
-    /// for debugging, unloads a board from a custom json representation
-    pub fn from_packed_hash(hash: &HashMap<String, Vec<u32>>) -> Self {
+    #[cfg(feature = "std")]
+    /// for debugging, unloads a board from a custom json representation, panics if the hash
+    /// wasn't made by `pack_as_hash`
+    pub fn from_packed_hash(hash: &std::collections::HashMap<String, Vec<u32>>) -> Self {
         Self {
             embedded: CCB::from_packed_hash(hash),
         }
     }
 }
 
+/// serialized as the bytes from [CellBoard::to_bytes], the way `serde_bytes` would
+impl<T: CN, D: Dimensions, const BOARD_SIZE: usize, const MAX_SNAKES: usize> Serialize
+    for CellBoard<T, D, BOARD_SIZE, MAX_SNAKES>
+{
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_bytes(&self.to_bytes())
+    }
+}
+
+impl<'de, T: CN, D: Dimensions, const BOARD_SIZE: usize, const MAX_SNAKES: usize> Deserialize<'de>
+    for CellBoard<T, D, BOARD_SIZE, MAX_SNAKES>
+{
+    fn deserialize<De: Deserializer<'de>>(deserializer: De) -> Result<Self, De::Error> {
+        let bytes = deserializer.deserialize_bytes(BytesVisitor)?;
+        Self::from_bytes(&bytes).map_err(de::Error::custom)
+    }
+}
+
 /// 7x7 board with 4 snakes
 pub type CellBoard4SnakesSquare7x7 = CellBoard<u8, Square, { 7 * 7 }, 4>;
 
 /// Used to represent the standard 11x11 game with up to 4 snakes.
 pub type CellBoard4SnakesSquare11x11 = CellBoard<u8, Square, { 11 * 11 }, 4>;
 
+/// An 11x11 game between exactly 2 snakes, which a [CellBoard4SnakesSquare11x11] can be
+/// [respecialized](CellBoard::respecialized) to once the others are eliminated
+pub type DuelCellBoardSquare11x11 = CellBoard<u8, Square, { 11 * 11 }, 2>;
+
 /// Used to represent the a 15x15 board with up to 4 snakes. This is the biggest board size that
 /// can still use u8s
 pub type CellBoard8SnakesSquare15x15 = CellBoard<u8, Square, { 15 * 15 }, 8>;
@@ -94,6 +244,8 @@ pub enum BestCellBoard {
     Standard(Box<CellBoard4SnakesSquare11x11>),
     /// A exactly 11x11 board with 4 snakes
     MediumExact(Box<CellBoard<u8, Fixed<11, 11>, { 11 * 11 }, 4>>),
+    /// A exactly 11x11 board with exactly 2 snakes, which are snakes 0 and 1
+    Duel(Box<CellBoard<u8, Fixed<11, 11>, { 11 * 11 }, 2>>),
     /// A game that can have a max height and width of 15x15 and 4 snakes
     LargestU8(Box<CellBoard8SnakesSquare15x15>),
     /// A exactly 19x19 board with 4 snakes
@@ -104,79 +256,317 @@ pub enum BestCellBoard {
     Large(Box<CellBoard8SnakesSquare25x25>),
     /// A game that can have a max height and width of 50x50 and 16 snakes
     Silly(Box<CellBoard16SnakesSquare50x50>),
+    /// A board that isn't square, with at most as many squares as 15x15 and 8 snakes
+    CustomU8(Box<CellBoard<u8, Custom, { 15 * 15 }, 8>>),
+    /// A board that isn't square, up to 50x50 and 16 snakes
+    Custom(Box<CellBoard<u16, Custom, { 50 * 50 }, 16>>),
 }
 
+#[cfg(feature = "std")]
 /// Trait to get the best sized cellboard for the given game. It returns the smallest Compact board
 /// that has enough room to fit the given Wire game. If the game can't fit in any of our Compact
-/// boards we panic. However the largest board available is MUCH larger than the biggest selectable
-/// board in the Battlesnake UI
+/// boards we return an error. However the largest board available is MUCH larger than the biggest
+/// selectable board in the Battlesnake UI
 pub trait ToBestCellBoard {
     #[allow(missing_docs)]
-    fn to_best_cell_board(self) -> Result<BestCellBoard, Box<dyn Error>>;
+    fn to_best_cell_board(self) -> Result<BestCellBoard, Error>;
 }
 
-impl ToBestCellBoard for Game {
-    fn to_best_cell_board(self) -> Result<BestCellBoard, Box<dyn Error>> {
-        let width = self.board.width;
-        let height = self.board.height;
-        let num_snakes = self.board.snakes.len();
-        let id_map = build_snake_id_map(&self);
+#[cfg(feature = "std")]
+impl BestCellBoard {
+    /// The smallest board that fits `game`, see [ToBestCellBoard], with snakes given the ids in
+    /// `snake_ids`
+    pub fn from_game(game: Game, snake_ids: &SnakeIDMap) -> Result<Self, Error> {
+        let width = game.board.width;
+        let height = game.board.height;
+        let num_snakes = game.board.snakes.len();
+
+        let square = width == height;
 
         let best_board = if width == 7 && height == 7 && num_snakes <= 4 {
-            BestCellBoard::SmallExact(Box::new(CellBoard::convert_from_game(self, &id_map)?))
-        } else if width <= 7 && height <= 7 && num_snakes <= 4 {
-            BestCellBoard::Tiny(Box::new(CellBoard::convert_from_game(self, &id_map)?))
+            BestCellBoard::SmallExact(Box::new(CellBoard::convert_from_game(game, snake_ids)?))
+        } else if square && width <= 7 && num_snakes <= 4 {
+            BestCellBoard::Tiny(Box::new(CellBoard::convert_from_game(game, snake_ids)?))
+        } else if width == 11 && height == 11 && is_duel(&game, snake_ids) {
+            BestCellBoard::Duel(Box::new(CellBoard::convert_from_game(game, snake_ids)?))
         } else if width == 11 && height == 11 && num_snakes <= 4 {
-            BestCellBoard::MediumExact(Box::new(CellBoard::convert_from_game(self, &id_map)?))
-        } else if width <= 11 && height <= 11 && num_snakes <= 4 {
-            BestCellBoard::Standard(Box::new(CellBoard::convert_from_game(self, &id_map)?))
-        } else if width <= 15 && height <= 15 && num_snakes <= 8 {
-            BestCellBoard::LargestU8(Box::new(CellBoard::convert_from_game(self, &id_map)?))
+            BestCellBoard::MediumExact(Box::new(CellBoard::convert_from_game(game, snake_ids)?))
+        } else if square && width <= 11 && num_snakes <= 4 {
+            BestCellBoard::Standard(Box::new(CellBoard::convert_from_game(game, snake_ids)?))
+        } else if square && width <= 15 && num_snakes <= 8 {
+            BestCellBoard::LargestU8(Box::new(CellBoard::convert_from_game(game, snake_ids)?))
         } else if width == 19 && height == 19 && num_snakes <= 4 {
-            BestCellBoard::LargeExact(Box::new(CellBoard::convert_from_game(self, &id_map)?))
+            BestCellBoard::LargeExact(Box::new(CellBoard::convert_from_game(game, snake_ids)?))
         } else if width == 19 && height == 21 && num_snakes <= 4 {
-            BestCellBoard::ArcadeMaze(Box::new(CellBoard::convert_from_game(self, &id_map)?))
-        } else if width <= 25 && height < 25 && num_snakes <= 8 {
-            BestCellBoard::Large(Box::new(CellBoard::convert_from_game(self, &id_map)?))
+            BestCellBoard::ArcadeMaze(Box::new(CellBoard::convert_from_game(game, snake_ids)?))
+        } else if square && width <= 25 && height < 25 && num_snakes <= 8 {
+            BestCellBoard::Large(Box::new(CellBoard::convert_from_game(game, snake_ids)?))
+        } else if square && width <= 50 && num_snakes <= 16 {
+            BestCellBoard::Silly(Box::new(CellBoard::convert_from_game(game, snake_ids)?))
+        } else if width * height <= 15 * 15 && num_snakes <= 8 {
+            BestCellBoard::CustomU8(Box::new(CellBoard::convert_from_game(game, snake_ids)?))
         } else if width <= 50 && height <= 50 && num_snakes <= 16 {
-            BestCellBoard::Silly(Box::new(CellBoard::convert_from_game(self, &id_map)?))
+            BestCellBoard::Custom(Box::new(CellBoard::convert_from_game(game, snake_ids)?))
         } else {
-            panic!("No board was big enough")
+            return Err(Error::BoardDoesNotFit { width, height });
         };
+        #[cfg(any(test, feature = "tracing"))]
+        if !matches!(
+            best_board,
+            BestCellBoard::SmallExact(_)
+                | BestCellBoard::MediumExact(_)
+                | BestCellBoard::Duel(_)
+                | BestCellBoard::LargeExact(_)
+                | BestCellBoard::ArcadeMaze(_)
+        ) {
+            tracing::debug!(
+                width,
+                height,
+                snakes = num_snakes,
+                "no exactly sized board for this game, using a larger one"
+            );
+        }
 
         Ok(best_board)
     }
 }
+
+#[cfg(feature = "std")]
+impl ToBestCellBoard for Game {
+    fn to_best_cell_board(self) -> Result<BestCellBoard, Error> {
+        let id_map = try_build_snake_id_map(&self)?;
+        BestCellBoard::from_game(self, &id_map)
+    }
+}
 impl<T: CN, D: Dimensions, const BOARD_SIZE: usize, const MAX_SNAKES: usize>
     RandomReasonableMovesGame for CellBoard<T, D, BOARD_SIZE, MAX_SNAKES>
 {
-    fn random_reasonable_move_for_each_snake<'a>(
+    fn random_reasonable_move_at_level_for_each_snake<'a>(
         &'a self,
         rng: &'a mut impl Rng,
-    ) -> Box<dyn std::iter::Iterator<Item = (SnakeId, Move)> + 'a> {
-        let width = self.embedded.get_actual_width();
+        level: ReasonableMoveLevel,
+    ) -> Box<dyn core::iter::Iterator<Item = (SnakeId, ReasonableMove)> + 'a> {
         Box::new(
             self.embedded
-                .iter_healths()
-                .enumerate()
-                .filter(|(_, health)| **health > 0)
-                .map(move |(idx, _)| {
-                    let head = self.get_head_as_native_position(&SnakeId(idx as u8));
-                    let head_pos = head.into_position(width);
-
-                    let mv = Move::all()
-                        .iter()
-                        .filter(|mv| {
-                            let new_head = head_pos.add_vec(mv.to_vector());
-                            let ci = self.embedded.as_wrapped_cell_index(new_head);
-
-                            !self.embedded.cell_is_body(ci) && !self.embedded.cell_is_snake_head(ci)
-                        })
-                        .choose(rng)
-                        .copied()
-                        .unwrap_or(Move::Up);
-                    (SnakeId(idx as u8), mv)
-                }),
+                .random_reasonable_moves(rng, EvaluateMode::Wrapped, level),
+        )
+    }
+}
+
+impl<T: CN, D: Dimensions, const BOARD_SIZE: usize, const MAX_SNAKES: usize>
+    WeightedReasonableMovesGame for CellBoard<T, D, BOARD_SIZE, MAX_SNAKES>
+{
+    fn random_reasonable_move_weighted<'a, W>(
+        &'a self,
+        rng: &'a mut impl Rng,
+        weight: W,
+    ) -> Box<dyn core::iter::Iterator<Item = (SnakeId, ReasonableMove)> + 'a>
+    where
+        W: FnMut(&SnakeId, Move, &CellIndex<T>) -> f64 + 'a,
+    {
+        Box::new(
+            self.embedded
+                .random_reasonable_moves_weighted(rng, EvaluateMode::Wrapped, weight),
+        )
+    }
+}
+
+impl<T: CN, D: Dimensions, const BOARD_SIZE: usize, const MAX_SNAKES: usize> FloodFillableGame
+    for CellBoard<T, D, BOARD_SIZE, MAX_SNAKES>
+{
+    fn flood_fill(
+        &self,
+        start: &Self::NativePositionType,
+        options: FloodFillOptions,
+    ) -> Vec<Self::NativePositionType> {
+        self.embedded
+            .flood_fill(*start, options, EvaluateMode::Wrapped)
+    }
+
+    fn flood_fill_count(
+        &self,
+        start: &Self::NativePositionType,
+        options: FloodFillOptions,
+    ) -> usize {
+        self.embedded
+            .flood_fill_count(*start, options, EvaluateMode::Wrapped)
+    }
+}
+
+impl<T: CN, D: Dimensions, const BOARD_SIZE: usize, const MAX_SNAKES: usize> PathfindableGame
+    for CellBoard<T, D, BOARD_SIZE, MAX_SNAKES>
+{
+    fn a_star(
+        &self,
+        from: &Self::NativePositionType,
+        to: &Self::NativePositionType,
+        options: AStarOptions,
+    ) -> Option<Vec<Move>> {
+        self.embedded
+            .a_star(*from, *to, options, EvaluateMode::Wrapped)
+    }
+
+    fn cost_map(
+        &self,
+        from: &Self::NativePositionType,
+        options: AStarOptions,
+    ) -> HashMap<Self::NativePositionType, u32> {
+        self.embedded
+            .cost_map(*from, options, EvaluateMode::Wrapped)
+            .iter()
+            .enumerate()
+            .filter_map(|(idx, distance)| distance.map(|d| (CellIndex::from_usize(idx), d)))
+            .collect()
+    }
+}
+
+impl<T: CN, D: Dimensions, const BOARD_SIZE: usize, const MAX_SNAKES: usize> ChokePointGame
+    for CellBoard<T, D, BOARD_SIZE, MAX_SNAKES>
+{
+}
+
+impl<T: CN, D: Dimensions, const BOARD_SIZE: usize, const MAX_SNAKES: usize> FutureOccupancyGame
+    for CellBoard<T, D, BOARD_SIZE, MAX_SNAKES>
+{
+    fn turns_until_vacated(&self) -> HashMap<CellIndex<T>, u32> {
+        self.embedded
+            .turns_until_vacated()
+            .iter()
+            .enumerate()
+            .filter(|(_, turns)| **turns > 0)
+            .map(|(idx, turns)| (CellIndex::from_usize(idx), *turns as u32))
+            .collect()
+    }
+}
+
+impl<T: CN, D: Dimensions, const BOARD_SIZE: usize, const MAX_SNAKES: usize> SnakeViewGame
+    for CellBoard<T, D, BOARD_SIZE, MAX_SNAKES>
+{
+}
+
+impl<T: CN, D: Dimensions, const BOARD_SIZE: usize, const MAX_SNAKES: usize>
+    HeadToHeadPredictableGame for CellBoard<T, D, BOARD_SIZE, MAX_SNAKES>
+{
+    fn predict_head_to_head(
+        &self,
+        me: &SnakeId,
+        them: &SnakeId,
+        target: &CellIndex<T>,
+    ) -> HeadToHeadOutcome {
+        self.embedded
+            .predict_head_to_head(*me, *them, *target, EvaluateMode::Wrapped)
+    }
+}
+
+impl<T: CN, D: Dimensions, const BOARD_SIZE: usize, const MAX_SNAKES: usize> FoodRaceGame
+    for CellBoard<T, D, BOARD_SIZE, MAX_SNAKES>
+{
+}
+
+impl<T: CN, D: Dimensions, const BOARD_SIZE: usize, const MAX_SNAKES: usize> HeadSquaresGame
+    for CellBoard<T, D, BOARD_SIZE, MAX_SNAKES>
+{
+    fn possible_head_squares(&self, id: &SnakeId) -> MoveSetPositions<CellIndex<T>> {
+        self.embedded
+            .possible_head_squares(*id, EvaluateMode::Wrapped)
+    }
+}
+
+impl<T: CN, D: Dimensions, const BOARD_SIZE: usize, const MAX_SNAKES: usize> TailChaseGame
+    for CellBoard<T, D, BOARD_SIZE, MAX_SNAKES>
+{
+}
+
+impl<T: CN, D: Dimensions, const BOARD_SIZE: usize, const MAX_SNAKES: usize>
+    LastMoveDeterminableGame for CellBoard<T, D, BOARD_SIZE, MAX_SNAKES>
+{
+}
+
+impl<T: CN, D: Dimensions, const BOARD_SIZE: usize, const MAX_SNAKES: usize>
+    TrajectoryProjectableGame for CellBoard<T, D, BOARD_SIZE, MAX_SNAKES>
+{
+}
+
+impl<T: CN, D: Dimensions, const BOARD_SIZE: usize, const MAX_SNAKES: usize> VoronoiGame
+    for CellBoard<T, D, BOARD_SIZE, MAX_SNAKES>
+{
+    fn voronoi(&self, options: VoronoiOptions) -> HashMap<SnakeId, usize> {
+        let counts = self.embedded.voronoi(options, EvaluateMode::Wrapped);
+        self.get_snake_ids()
+            .into_iter()
+            .map(|sid| (sid, counts[sid.as_usize()]))
+            .collect()
+    }
+
+    fn voronoi_owners(&self, options: VoronoiOptions) -> HashMap<CellIndex<T>, (SnakeId, u32)> {
+        let owners = self.embedded.voronoi_owners(options, EvaluateMode::Wrapped);
+        owners
+            .iter()
+            .enumerate()
+            .filter_map(|(idx, owner)| owner.map(|owner| (CellIndex::from_usize(idx), owner)))
+            .collect()
+    }
+}
+
+/// A board stored as the cells that changed from the board it was simulated from, which it
+/// shares with its siblings. Holding lots of states at once, like the frontier of a best first
+/// search, takes a fraction of the memory of holding whole boards
+#[derive(Debug, Clone)]
+pub struct CellBoardDelta<T: CN, D: Dimensions, const BOARD_SIZE: usize, const MAX_SNAKES: usize> {
+    parent: Arc<CellBoard<T, D, BOARD_SIZE, MAX_SNAKES>>,
+    delta: CoreDelta<T, MAX_SNAKES>,
+}
+
+impl<T: CN, D: Dimensions, const BOARD_SIZE: usize, const MAX_SNAKES: usize>
+    CellBoardDelta<T, D, BOARD_SIZE, MAX_SNAKES>
+{
+    /// the board this was simulated from
+    pub fn parent(&self) -> &Arc<CellBoard<T, D, BOARD_SIZE, MAX_SNAKES>> {
+        &self.parent
+    }
+
+    /// the whole board
+    pub fn materialize(&self) -> CellBoard<T, D, BOARD_SIZE, MAX_SNAKES> {
+        CellBoard {
+            embedded: self.parent.embedded.apply_delta(&self.delta),
+        }
+    }
+
+    /// roughly how many bytes this takes up, not counting the parent it shares
+    pub fn size_in_bytes(&self) -> usize {
+        self.delta.size_in_bytes()
+    }
+}
+
+impl<T: CN, D: Dimensions, const BOARD_SIZE: usize, const MAX_SNAKES: usize>
+    CellBoard<T, D, BOARD_SIZE, MAX_SNAKES>
+{
+    /// this board as a delta from `parent`, which has to be the same size
+    pub fn delta_from(&self, parent: &Arc<Self>) -> CellBoardDelta<T, D, BOARD_SIZE, MAX_SNAKES> {
+        CellBoardDelta {
+            parent: Arc::clone(parent),
+            delta: self.embedded.delta_from(&parent.embedded),
+        }
+    }
+
+    /// The same as [SimulableGame::simulate_with_moves], with each board as a delta from this
+    /// one
+    #[allow(clippy::type_complexity)]
+    pub fn simulate_with_moves_as_deltas<'a, I: SimulatorInstruments, S: Borrow<[Move]>>(
+        self: &'a Arc<Self>,
+        instruments: &'a I,
+        snake_ids_and_moves: impl IntoIterator<Item = (SnakeId, S)>,
+    ) -> Box<
+        dyn Iterator<
+                Item = (
+                    Action<MAX_SNAKES>,
+                    CellBoardDelta<T, D, BOARD_SIZE, MAX_SNAKES>,
+                ),
+            > + 'a,
+    > {
+        Box::new(
+            self.simulate_with_moves(instruments, snake_ids_and_moves)
+                .map(move |(action, board)| (action, board.delta_from(self))),
         )
     }
 }
@@ -190,11 +580,12 @@ impl<
     > SimulableGame<T, MAX_SNAKES> for CellBoard<N, D, BOARD_SIZE, MAX_SNAKES>
 {
     #[allow(clippy::type_complexity)]
-    fn simulate_with_moves<S>(
-        &self,
-        instruments: &T,
+    fn simulate_with_moves_and_settings<'a, S>(
+        &'a self,
+        instruments: &'a T,
         snake_ids_and_moves: impl IntoIterator<Item = (Self::SnakeIDType, S)>,
-    ) -> Box<dyn Iterator<Item = (Action<MAX_SNAKES>, Self)> + '_>
+        settings: &SimulationSettings,
+    ) -> Box<dyn Iterator<Item = (Action<MAX_SNAKES>, Self)> + 'a>
     where
         S: Borrow<[Move]>,
     {
@@ -204,6 +595,7 @@ impl<
                 instruments,
                 snake_ids_and_moves,
                 EvaluateMode::Wrapped,
+                settings,
             )
             .map(|v| {
                 let (action, board) = v;
@@ -211,6 +603,46 @@ impl<
             }),
         )
     }
+
+    fn simulation_settings(&self) -> SimulationSettings {
+        self.embedded.simulation_settings()
+    }
+
+    fn simulate_with_moves_into<S>(
+        &self,
+        instruments: &T,
+        snake_ids_and_moves: impl IntoIterator<Item = (Self::SnakeIDType, S)>,
+        out_states: &mut Vec<Self>,
+        out_moves: &mut Vec<Action<MAX_SNAKES>>,
+    ) where
+        S: Borrow<[Move]>,
+    {
+        simulate_with_moves_into(
+            &self.embedded,
+            instruments,
+            snake_ids_and_moves,
+            EvaluateMode::Wrapped,
+            &self.embedded.simulation_settings(),
+            out_states,
+            out_moves,
+            |embedded| Self { embedded },
+        )
+    }
+
+    fn advance_turn_with_moves(
+        &self,
+        instruments: &T,
+        moves: impl IntoIterator<Item = impl Into<SnakeMove<Self::SnakeIDType>>>,
+    ) -> Result<Self, Error> {
+        advance_with_moves(
+            &self.embedded,
+            instruments,
+            moves.into_iter().map(Into::into),
+            EvaluateMode::Wrapped,
+            &self.embedded.simulation_settings(),
+        )
+        .map(|embedded| Self { embedded })
+    }
 }
 
 impl<T: CN, D: Dimensions, const BOARD_SIZE: usize, const MAX_SNAKES: usize>
@@ -219,20 +651,21 @@ impl<T: CN, D: Dimensions, const BOARD_SIZE: usize, const MAX_SNAKES: usize>
     fn possible_moves<'a>(
         &'a self,
         pos: &Self::NativePositionType,
-    ) -> Box<(dyn std::iter::Iterator<Item = (Move, CellIndex<T>)> + 'a)> {
+    ) -> Box<dyn core::iter::Iterator<Item = (Move, CellIndex<T>)> + 'a> {
         let width = self.embedded.get_actual_width();
         let head_pos = pos.into_position(width);
 
         Box::new(
             Move::all_iter()
                 .map(move |mv| {
-                    let new_head = head_pos.add_vec(mv.to_vector());
+                    let new_head = head_pos.add_vec(NEIGHBOR_OFFSETS[mv.as_index()]);
                     let ci = self.embedded.as_wrapped_cell_index(new_head);
 
                     debug_assert!(!self.embedded.off_board(ci.into_position(width)));
 
                     (mv, new_head, ci)
                 })
+                .filter(move |(_, _, ci)| !self.embedded.cell_is_wall(*ci))
                 .map(|(mv, _, ci)| (mv, ci)),
         )
     }
@@ -240,7 +673,7 @@ impl<T: CN, D: Dimensions, const BOARD_SIZE: usize, const MAX_SNAKES: usize>
     fn neighbors<'a>(
         &'a self,
         pos: &Self::NativePositionType,
-    ) -> Box<(dyn Iterator<Item = CellIndex<T>> + 'a)> {
+    ) -> Box<dyn Iterator<Item = CellIndex<T>> + 'a> {
         Box::new(self.possible_moves(pos).map(|(_, ci)| ci))
     }
 }
@@ -256,14 +689,18 @@ mod test {
         compact_representation::core::Cell,
         game_fixture,
         types::{
-            build_snake_id_map, HeadGettableGame, HealthGettableGame, Move,
-            NeighborDeterminableGame, RandomReasonableMovesGame, SimulableGame,
-            SimulatorInstruments, SnakeId,
+            build_snake_id_map, AStarOptions, ChokePointGame, FloodFillOptions, FloodFillableGame,
+            HeadGettableGame, HealthGettableGame, Move, NeighborDeterminableGame, PathfindableGame,
+            RandomReasonableMovesGame, SimulableGame, SimulatorInstruments, SnakeIDGettableGame,
+            SnakeId, TailChaseGame, TrajectoryAssumption, TrajectoryProjectableGame,
         },
         wire_representation::Position,
+        Error,
     };
 
-    use super::{CellBoard4SnakesSquare11x11, CellIndex};
+    use super::{CellBoard, CellBoard4SnakesSquare11x11, CellIndex, EvaluateMode};
+    use crate::compact_representation::dimensions::ArcadeMaze;
+    use crate::types::{HazardQueryableGame, WallQueryableGame};
 
     #[derive(Debug)]
     struct Instruments {}
@@ -294,6 +731,117 @@ mod test {
         );
     }
 
+    #[test]
+    fn test_bytes_round_trip() {
+        let fixtures = [
+            include_str!("../../../fixtures/wrapped_fixture.json"),
+            include_str!("../../../fixtures/cornered_wrapped.json"),
+            include_str!("../../../fixtures/wrapped_column.json"),
+        ];
+        for fixture in fixtures.iter() {
+            let g = game_fixture(fixture);
+            let board: CellBoard4SnakesSquare11x11 =
+                g.as_wrapped_cell_board(&build_snake_id_map(&g)).unwrap();
+            let bytes = board.to_bytes();
+            assert_eq!(
+                CellBoard4SnakesSquare11x11::from_bytes(&bytes).unwrap(),
+                board
+            );
+            assert!(matches!(
+                crate::compact_representation::StandardCellBoard4Snakes11x11::from_bytes(&bytes),
+                Err(Error::InvalidBytes(_))
+            ));
+            for (_, next) in board.simulate(&Instruments {}, board.get_snake_ids()) {
+                assert_eq!(
+                    CellBoard4SnakesSquare11x11::from_bytes(&next.to_bytes()).unwrap(),
+                    next
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_arcade_maze_walls() {
+        let g = game_fixture(include_str!("../../../fixtures/arcade_maze_map.json"));
+        let board: CellBoard<u16, ArcadeMaze, { 19 * 21 }, 4> =
+            g.as_wrapped_cell_board(&build_snake_id_map(&g)).unwrap();
+        let at = |x, y| CellIndex::new(Position::new(x, y), 19);
+        assert!(board.is_wall(&at(0, 1)));
+        assert!(!board.is_wall(&at(1, 1)));
+        assert!(!board.is_hazard(&at(0, 1)));
+        assert_eq!(
+            board.embedded.to_game(EvaluateMode::Wrapped).board.walls,
+            g.board.walls
+        );
+        assert_eq!(CellBoard::from_bytes(&board.to_bytes()).unwrap(), board);
+        assert_eq!(
+            board.neighbors(&at(1, 1)).sorted().collect_vec(),
+            vec![at(1, 0), at(2, 1), at(1, 2)]
+        );
+
+        let path = board
+            .a_star(&at(1, 1), &at(1, 7), AStarOptions::default())
+            .unwrap();
+        assert_eq!(path.len(), 8);
+        // every step is on to a square that isn't a wall
+        let end = path.iter().fold(at(1, 1), |ci, mv| {
+            board
+                .embedded
+                .cell_in_direction(ci, *mv, EvaluateMode::Wrapped)
+                .unwrap()
+        });
+        assert_eq!(end, at(1, 7));
+        assert_eq!(
+            board.a_star(&at(1, 1), &at(0, 1), AStarOptions::default()),
+            None
+        );
+
+        let reached = board.flood_fill(&at(1, 1), FloodFillOptions::default());
+        assert!(reached.iter().all(|ci| !board.is_wall(ci)));
+        assert_eq!(
+            reached.len(),
+            g.flood_fill(&Position::new(1, 1), FloodFillOptions::default())
+                .len()
+        );
+
+        // moving up from (4, 19) is in to a wall
+        let you = SnakeId(0);
+        let head = board.get_head_as_native_position(&you);
+        assert!(board.possible_moves(&head).all(|(mv, _)| mv != Move::Up));
+        let (_, next) = board
+            .simulate_with_moves(&Instruments {}, vec![(you, [Move::Up])])
+            .next()
+            .unwrap();
+        assert_eq!(next.get_health(&you), 0);
+    }
+
+    #[test]
+    fn test_deltas_match_simulation() {
+        let g = game_fixture(include_str!("../../../fixtures/wrapped_fixture.json"));
+        let board: CellBoard4SnakesSquare11x11 =
+            g.as_wrapped_cell_board(&build_snake_id_map(&g)).unwrap();
+        let parent = std::sync::Arc::new(board);
+        let deltas = parent
+            .simulate_with_moves_as_deltas(
+                &Instruments {},
+                board
+                    .get_snake_ids()
+                    .into_iter()
+                    .map(|sid| (sid, Move::all())),
+            )
+            .collect_vec();
+        let simulated = board
+            .simulate(&Instruments {}, board.get_snake_ids())
+            .collect_vec();
+        assert_eq!(deltas.len(), simulated.len());
+        for ((action, delta), (expected_action, expected)) in deltas.iter().zip(simulated) {
+            assert_eq!(action.own_move(), expected_action.own_move());
+            assert_eq!(action.other_moves(), expected_action.other_moves());
+            assert_eq!(delta.materialize(), expected);
+            assert!(delta.size_in_bytes() < std::mem::size_of_val(&board));
+        }
+    }
+
     #[test]
     fn test_cell_round_trips() {
         let mut c: Cell<u8> = Cell::empty();
@@ -321,14 +869,13 @@ mod test {
 
         // the input state isn't safe to move down in, but it is if we move one to the right
         let move_map = snake_ids
-            .clone()
-            .into_iter()
-            .map(|(_, sid)| (sid, [Move::Right].as_slice()))
+            .values()
+            .map(|sid| (*sid, [Move::Right].as_slice()))
             .collect_vec();
         let instruments = Instruments {};
         let wrapped_for_down = orig_wrapped_cell
             .clone()
-            .simulate_with_moves(&instruments, move_map.into_iter())
+            .simulate_with_moves(&instruments, move_map)
             .next()
             .unwrap()
             .1;
@@ -363,8 +910,7 @@ mod test {
         for _ in 0..15 {
             let move_map = wrapped
                 .random_reasonable_move_for_each_snake(&mut rng)
-                .into_iter()
-                .map(|(sid, mv)| (sid, [mv]))
+                .map(|m| (m.snake_id, [m.mv]))
                 .collect_vec();
             wrapped = wrapped
                 .simulate_with_moves(
@@ -389,10 +935,7 @@ mod test {
         let mut wrapped_cell = orig_wrapped_cell;
         let instruments = Instruments {};
         let start_health = wrapped_cell.get_health(&SnakeId(0));
-        let move_map = snake_ids
-            .into_iter()
-            .map(|(_, sid)| (sid, [mv]))
-            .collect_vec();
+        let move_map = snake_ids.into_values().map(|sid| (sid, [mv])).collect_vec();
         let start_y = wrapped_cell.get_head_as_position(&SnakeId(0)).y;
         let start_x = wrapped_cell.get_head_as_position(&SnakeId(0)).x;
         for _ in 0..rollout {
@@ -413,8 +956,8 @@ mod test {
             wrapped_cell.get_health(&SnakeId(0)) as i32,
             start_health as i32 - rollout
         );
-        assert_eq!(((start_y + (rollout * inc_y)).rem_euclid(11)) as i32, end_y);
-        assert_eq!(((start_x + (rollout * inc_x)).rem_euclid(11)) as i32, end_x);
+        assert_eq!((start_y + (rollout * inc_y)).rem_euclid(11), end_y);
+        assert_eq!((start_x + (rollout * inc_x)).rem_euclid(11), end_x);
     }
 
     #[test]
@@ -425,7 +968,7 @@ mod test {
         // we essentially "break" the snake in the cell representation when we kill it.
         let orig_crash_game = game_fixture(include_str!("../../../fixtures/wrapped_panic.json"));
         let snake_ids = build_snake_id_map(&orig_crash_game);
-        let compact_ids: Vec<SnakeId> = snake_ids.iter().map(|(_, v)| *v).collect();
+        let compact_ids: Vec<SnakeId> = snake_ids.values().copied().collect();
 
         let instruments = Instruments {};
         {
@@ -530,4 +1073,142 @@ mod test {
                 .collect::<Vec<_>>()
         );
     }
+
+    #[test]
+    fn test_flood_fill_wraps_across_seam() {
+        let g = game_fixture(include_str!("../../../fixtures/wrapped_column.json"));
+        let snake_id_mapping = build_snake_id_map(&g);
+        let compact: CellBoard4SnakesSquare11x11 =
+            g.as_wrapped_cell_board(&snake_id_mapping).unwrap();
+        let options = FloodFillOptions {
+            max_depth: Some(4),
+            ..Default::default()
+        };
+
+        let start = CellIndex::new(Position::new(0, 5), 11);
+        let reached = compact.flood_fill(&start, options);
+        assert_eq!(compact.flood_fill_count(&start, options), 32);
+        assert_eq!(reached.len(), 32);
+        assert!(reached.contains(&CellIndex::new(Position::new(10, 5), 11)));
+        assert!(reached.contains(&CellIndex::new(Position::new(7, 5), 11)));
+        assert!(!reached.contains(&CellIndex::new(Position::new(3, 5), 11)));
+        // the body piece at (2, 7) moves out of the way just as we get there
+        assert!(reached.contains(&CellIndex::new(Position::new(2, 7), 11)));
+
+        let wire = g.flood_fill(&Position::new(0, 5), options);
+        assert_eq!(
+            reached
+                .into_iter()
+                .map(|ci| ci.into_position(11))
+                .collect_vec(),
+            wire
+        );
+    }
+
+    #[test]
+    fn test_choke_points_wrap_across_seam() {
+        let mut g = game_fixture(include_str!("../../../fixtures/choke_pocket.json"));
+        g.game.ruleset.name = "wrapped".to_string();
+        let snake_id_mapping = build_snake_id_map(&g);
+        let compact: CellBoard4SnakesSquare11x11 =
+            g.as_wrapped_cell_board(&snake_id_mapping).unwrap();
+
+        // the pocket in the corner is open to the other side of the board
+        assert!(compact.choke_points().is_empty());
+        assert!(g.choke_points().is_empty());
+    }
+
+    #[test]
+    fn test_distance_map_across_seam() {
+        let g = game_fixture(include_str!("../../../fixtures/wrapped_column.json"));
+        let snake_id_mapping = build_snake_id_map(&g);
+        let compact: CellBoard4SnakesSquare11x11 =
+            g.as_wrapped_cell_board(&snake_id_mapping).unwrap();
+        let from = CellIndex::new(Position::new(0, 5), 11);
+
+        let distances = compact.distance_map(&from, AStarOptions::default());
+        let distance_to = |x, y| {
+            distances
+                .get(&CellIndex::new(Position::new(x, y), 11))
+                .copied()
+        };
+        assert_eq!(distance_to(10, 5), Some(1));
+        assert_eq!(distance_to(0, 10), Some(5));
+        assert_eq!(distance_to(4, 5), Some(7));
+        // pieces of the wall can be walked on once they've moved
+        assert_eq!(distance_to(2, 7), Some(4));
+        assert_eq!(distance_to(2, 5), Some(6));
+
+        let wire = g.distance_map(&Position::new(0, 5), AStarOptions::default());
+        assert_eq!(wire[&Position::new(10, 5)], 1);
+        assert_eq!(wire[&Position::new(2, 5)], 6);
+        assert_eq!(wire.len(), distances.len());
+    }
+
+    #[test]
+    fn test_chase_tail_across_seam() {
+        let mut builder = crate::compact_representation::CellBoardBuilder::new(11, 11);
+        let body = [(0, 1), (0, 2), (10, 2), (10, 1)]
+            .iter()
+            .map(|(x, y)| Position::new(*x, *y))
+            .collect();
+        builder.add_snake(body, 90).unwrap();
+        let far = vec![Position::new(5, 8), Position::new(5, 7)];
+        builder.add_snake(far, 90).unwrap();
+        let compact: CellBoard4SnakesSquare11x11 = builder.build_wrapped().unwrap();
+
+        // the head is next to the tail the other way round the board
+        let analysis = compact.can_safely_chase_tail(&SnakeId(0));
+        assert_eq!(analysis.path, Some(vec![]));
+        assert_eq!(analysis.cycle_length, Some(4));
+        assert!(analysis.is_safe());
+
+        let mut g = builder.game().clone();
+        g.game.ruleset.name = "wrapped".to_string();
+        assert!(g.can_safely_chase_tail(&g.you.id).is_safe());
+    }
+
+    #[test]
+    fn test_project_snake_across_seam() {
+        let mut builder = crate::compact_representation::CellBoardBuilder::new(11, 11);
+        let body = [(1, 5), (2, 5), (3, 5)]
+            .iter()
+            .map(|(x, y)| Position::new(*x, *y))
+            .collect();
+        builder.add_snake(body, 90).unwrap();
+        let far = vec![Position::new(5, 8), Position::new(5, 7)];
+        builder.add_snake(far, 90).unwrap();
+        let compact: CellBoard4SnakesSquare11x11 = builder.build_wrapped().unwrap();
+
+        // going left comes back in on the right
+        let projected = compact
+            .project_snake(&SnakeId(0), 3, &TrajectoryAssumption::Straight)
+            .unwrap();
+        assert_eq!(projected.stopped, None);
+        assert_eq!(
+            projected
+                .heads()
+                .map(|ci| ci.into_position(11))
+                .collect_vec(),
+            vec![
+                Position::new(0, 5),
+                Position::new(10, 5),
+                Position::new(9, 5)
+            ]
+        );
+
+        let mut g = builder.game().clone();
+        g.game.ruleset.name = "wrapped".to_string();
+        let wire = g
+            .project_snake(&g.you.id, 3, &TrajectoryAssumption::Straight)
+            .unwrap();
+        assert_eq!(
+            wire.bodies[2],
+            vec![
+                Position::new(9, 5),
+                Position::new(10, 5),
+                Position::new(0, 5)
+            ]
+        );
+    }
 }
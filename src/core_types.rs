@@ -0,0 +1,1132 @@
+//! the types and game traits that don't need the standard library, which the rest of the crate
+//! is built on. They are re-exported from [crate::types] and [crate::wire_representation], and
+//! are all that is built without the `std` feature
+
+use alloc::boxed::Box;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::fmt::{self, Debug};
+use core::hash::Hash;
+use core::iter::FromIterator;
+use core::time::Duration;
+
+use rand::distributions::{Distribution, Standard};
+use rand::Rng;
+use serde::{Deserialize, Serialize, Serializer};
+
+use crate::Error;
+
+/// A vector with which to do positional math
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Vector {
+    /// x position
+    pub x: i64,
+    /// y position
+    pub y: i64,
+}
+
+impl Vector {
+    #[allow(missing_docs)]
+    pub const fn new(x: i64, y: i64) -> Self {
+        Vector { x, y }
+    }
+
+    /// this vector plus `other`
+    pub const fn add_vec(self, other: Vector) -> Vector {
+        Vector {
+            x: self.x + other.x,
+            y: self.y + other.y,
+        }
+    }
+
+    /// this vector minus `other`
+    pub const fn sub_vec(self, other: Vector) -> Vector {
+        Vector {
+            x: self.x - other.x,
+            y: self.y - other.y,
+        }
+    }
+
+    /// the same length, pointing the other way
+    pub const fn negated(self) -> Vector {
+        Vector {
+            x: -self.x,
+            y: -self.y,
+        }
+    }
+}
+
+/// Struct that matches the `position` object from the wire representation.
+///
+/// Positions use the battlesnake convention everywhere in this crate: `(0, 0)` is the bottom
+/// left square, `x` increases to the right and `y` increases upward, so [Move::Up] adds one to
+/// `y`. Boards are drawn (and [crate::heatmap::Grid]s laid out) top row first, as most grid code
+/// expects, use [Position::to_row_col] and [Position::from_row_col] to convert to and from that
+/// `(row, col)` order instead of flipping `y` by hand
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash, Ord, PartialOrd)]
+pub struct Position {
+    /// x position, counting columns from the left
+    pub x: i32,
+    /// y position, counting rows from the bottom
+    pub y: i32,
+}
+
+impl Position {
+    #[allow(missing_docs)]
+    pub const fn new(x: i32, y: i32) -> Self {
+        Self { x, y }
+    }
+
+    /// the number of moves from `(0, 0)` to this position, ignoring everything on the board
+    pub const fn manhattan_length(&self) -> u32 {
+        (self.x.abs() + self.y.abs()) as u32
+    }
+
+    /// this position moved by `v`
+    pub const fn add_vec(&self, v: Vector) -> Position {
+        Position {
+            x: (self.x as i64 + v.x) as i32,
+            y: (self.y as i64 + v.y) as i32,
+        }
+    }
+    /// this position moved by the opposite of `v`
+    pub const fn sub_vec(&self, v: Vector) -> Position {
+        Position {
+            x: (self.x as i64 - v.x) as i32,
+            y: (self.y as i64 - v.y) as i32,
+        }
+    }
+
+    /// the vector from `(0, 0)` to this position
+    pub const fn to_vector(&self) -> Vector {
+        Vector {
+            x: self.x as i64,
+            y: self.y as i64,
+        }
+    }
+
+    /// this position as `(row, col)` on a board `height` squares tall, where row 0 is the top
+    /// row as the board is drawn
+    pub const fn to_row_col(&self, height: i32) -> (i32, i32) {
+        (height - 1 - self.y, self.x)
+    }
+
+    /// the position at `row`, `col` on a board `height` squares tall, where row 0 is the top
+    /// row as the board is drawn. The inverse of [Position::to_row_col]
+    pub const fn from_row_col(row: i32, col: i32, height: i32) -> Self {
+        Position {
+            x: col,
+            y: height - 1 - row,
+        }
+    }
+
+    /// the index of this position on a board `width` squares wide, counting a row at a time
+    /// from the bottom left, `y * width + x`, the way compact boards index their cells
+    pub const fn to_flat_index(&self, width: u8) -> i32 {
+        self.y * width as i32 + self.x
+    }
+
+    /// the position at `index` on a board `width` squares wide. The inverse of
+    /// [Position::to_flat_index] for positions on the board
+    pub const fn from_flat_index(index: i32, width: u8) -> Self {
+        Position {
+            x: index % width as i32,
+            y: index / width as i32,
+        }
+    }
+}
+
+/// there are 4 moves
+pub const N_MOVES: usize = 4;
+
+/// The vector each move adds to a position, indexed by [Move::as_index], so in the order of
+/// [Move::all]. Neighbors are found by adding these, and as they are a const they can go in to
+/// tables built at compile time too
+pub const NEIGHBOR_OFFSETS: [Vector; N_MOVES] = [
+    Move::all()[0].to_vector(),
+    Move::all()[1].to_vector(),
+    Move::all()[2].to_vector(),
+    Move::all()[3].to_vector(),
+];
+
+/// Represents a move
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Move {
+    #[allow(missing_docs)]
+    Left,
+    #[allow(missing_docs)]
+    Down,
+    #[allow(missing_docs)]
+    Up,
+    #[allow(missing_docs)]
+    Right,
+}
+
+impl fmt::Display for Move {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Move::Left => write!(f, "left"),
+            Move::Right => write!(f, "right"),
+            Move::Up => write!(f, "up"),
+            Move::Down => write!(f, "down"),
+        }
+    }
+}
+
+impl Move {
+    /// convert this move to a vector
+    pub const fn to_vector(self) -> Vector {
+        match self {
+            Move::Left => Vector { x: -1, y: 0 },
+            Move::Right => Vector { x: 1, y: 0 },
+            Move::Up => Vector { x: 0, y: 1 },
+            Move::Down => Vector { x: 0, y: -1 },
+        }
+    }
+
+    /// create a Move from the given vector, panics if it isn't one square up, down, left or
+    /// right
+    pub const fn from_vector(vector: Vector) -> Self {
+        match vector {
+            Vector { x: -1, y: 0 } => Self::Left,
+            Vector { x: 1, y: 0 } => Self::Right,
+            Vector { x: 0, y: 1 } => Self::Up,
+            Vector { x: 0, y: -1 } => Self::Down,
+            _ => panic!("not the vector of a move"),
+        }
+    }
+
+    /// returns a vec of all possible moves
+    pub const fn all() -> [Self; N_MOVES] {
+        [Move::Up, Move::Down, Move::Left, Move::Right]
+    }
+
+    /// returns an Iterator of all possible moves
+    pub fn all_iter() -> MoveIter {
+        MoveIter(0)
+    }
+
+    /// converts this move to a usize index. indices are the same order as the `Move::all()` method
+    pub const fn as_index(&self) -> usize {
+        match self {
+            Move::Up => 0,
+            Move::Down => 1,
+            Move::Left => 2,
+            Move::Right => 3,
+        }
+    }
+
+    /// converts a usize index to a move
+    pub const fn from_index(index: usize) -> Move {
+        match index {
+            0 => Move::Up,
+            1 => Move::Down,
+            2 => Move::Left,
+            3 => Move::Right,
+            _ => panic!("invalid index"),
+        }
+    }
+
+    /// a uniformly random move, the same as `rng.gen::<Move>()`
+    pub fn random(rng: &mut impl Rng) -> Move {
+        rng.gen()
+    }
+
+    /// the move that points the same way as this one after the board has been transformed by
+    /// `symmetry`
+    pub fn transformed(self, symmetry: Symmetry) -> Move {
+        Move::from_vector(symmetry.transform_vector(self.to_vector()))
+    }
+
+    /// the move in the other direction, e.g. Down for Up
+    pub const fn opposite(self) -> Move {
+        match self {
+            Move::Up => Move::Down,
+            Move::Down => Move::Up,
+            Move::Left => Move::Right,
+            Move::Right => Move::Left,
+        }
+    }
+
+    #[allow(dead_code)]
+    /// checks if a given move is not opposibe this move. e.g. Up is not opposite to Left, but is opposite to Down
+    pub const fn is_not_opposite(&self, other: &Move) -> bool {
+        !matches!(
+            (self, other),
+            (Move::Up, Move::Down)
+                | (Move::Down, Move::Up)
+                | (Move::Left, Move::Right)
+                | (Move::Right, Move::Left)
+        )
+    }
+}
+
+#[derive(Copy, Clone, Debug)]
+/// Iterator over all moves. Returned by `Move::all_iter()`
+///
+/// The iterator yields elements in the same order as `Move::all()`
+pub struct MoveIter(usize);
+
+impl Iterator for MoveIter {
+    type Item = Move;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.0 < N_MOVES {
+            let m = Move::from_index(self.0);
+            self.0 += 1;
+            Some(m)
+        } else {
+            None
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let left = N_MOVES.saturating_sub(self.0);
+        (left, Some(left))
+    }
+}
+
+impl ExactSizeIterator for MoveIter {}
+
+impl Distribution<Move> for Standard {
+    fn sample<R: Rng + ?Sized>(&self, rng: &mut R) -> Move {
+        Move::from_index(rng.gen_range(0..N_MOVES))
+    }
+}
+
+/// A set of moves, e.g. the ones a snake can make without dying
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct MoveSet(u8);
+
+impl MoveSet {
+    /// a set with no moves
+    pub fn empty() -> Self {
+        MoveSet(0)
+    }
+
+    /// a set with every move
+    pub fn all() -> Self {
+        MoveSet((1 << N_MOVES) - 1)
+    }
+
+    /// adds `mv` to the set
+    pub fn insert(&mut self, mv: Move) {
+        self.0 |= 1 << mv.as_index();
+    }
+
+    /// removes `mv` from the set
+    pub fn remove(&mut self, mv: Move) {
+        self.0 &= !(1 << mv.as_index());
+    }
+
+    /// whether `mv` is in the set
+    pub fn contains(&self, mv: Move) -> bool {
+        self.0 & (1 << mv.as_index()) != 0
+    }
+
+    /// how many moves are in the set
+    pub fn len(&self) -> usize {
+        self.0.count_ones() as usize
+    }
+
+    /// whether there are no moves in the set
+    pub fn is_empty(&self) -> bool {
+        self.0 == 0
+    }
+
+    /// the moves in the set, in the same order as `Move::all()`
+    pub fn iter(&self) -> impl Iterator<Item = Move> {
+        let set = *self;
+        Move::all_iter().filter(move |mv| set.contains(*mv))
+    }
+
+    /// a uniformly random move from the set, None if it is empty
+    pub fn random_choice(&self, rng: &mut impl Rng) -> Option<Move> {
+        if self.is_empty() {
+            return None;
+        }
+        self.iter().nth(rng.gen_range(0..self.len()))
+    }
+}
+
+impl FromIterator<Move> for MoveSet {
+    fn from_iter<I: IntoIterator<Item = Move>>(moves: I) -> Self {
+        let mut set = MoveSet::empty();
+        moves.into_iter().for_each(|mv| set.insert(mv));
+        set
+    }
+}
+
+/// A [MoveSet] along with where each of its moves ends up, e.g. the squares a snake's head can get
+/// to next turn, see [HeadSquaresGame](crate::types::HeadSquaresGame)
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct MoveSetPositions<P> {
+    moves: MoveSet,
+    positions: [Option<P>; N_MOVES],
+}
+
+impl<P> MoveSetPositions<P> {
+    /// a set with no moves
+    pub fn empty() -> Self {
+        MoveSetPositions {
+            moves: MoveSet::empty(),
+            positions: [None, None, None, None],
+        }
+    }
+
+    /// adds `mv` to the set, ending up on `position`
+    pub fn insert(&mut self, mv: Move, position: P) {
+        self.moves.insert(mv);
+        self.positions[mv.as_index()] = Some(position);
+    }
+
+    /// the moves in the set
+    pub fn moves(&self) -> MoveSet {
+        self.moves
+    }
+
+    /// where `mv` ends up, None if it isn't in the set
+    pub fn get(&self, mv: Move) -> Option<&P> {
+        self.positions[mv.as_index()].as_ref()
+    }
+
+    /// the moves in the set and where they end up, in the same order as `Move::all()`
+    pub fn iter(&self) -> impl Iterator<Item = (Move, &P)> {
+        Move::all_iter().filter_map(move |mv| self.get(mv).map(|position| (mv, position)))
+    }
+
+    /// how many moves are in the set
+    pub fn len(&self) -> usize {
+        self.moves.len()
+    }
+
+    /// whether there are no moves in the set
+    pub fn is_empty(&self) -> bool {
+        self.moves.is_empty()
+    }
+}
+
+impl<P> Default for MoveSetPositions<P> {
+    fn default() -> Self {
+        Self::empty()
+    }
+}
+
+/// One of the reflections and rotations of a board. Rotations are counter clockwise
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Symmetry {
+    /// leaves the board as it is
+    Identity,
+    #[allow(missing_docs)]
+    Rotate90,
+    #[allow(missing_docs)]
+    Rotate180,
+    #[allow(missing_docs)]
+    Rotate270,
+    /// mirrors left to right
+    FlipHorizontal,
+    /// mirrors top to bottom
+    FlipVertical,
+    /// mirrors across the line from the bottom left to the top right corner
+    FlipDiagonal,
+    /// mirrors across the line from the top left to the bottom right corner
+    FlipAntiDiagonal,
+}
+
+impl Symmetry {
+    /// all 8 symmetries of a square board
+    pub const fn all() -> [Self; 8] {
+        [
+            Symmetry::Identity,
+            Symmetry::Rotate90,
+            Symmetry::Rotate180,
+            Symmetry::Rotate270,
+            Symmetry::FlipHorizontal,
+            Symmetry::FlipVertical,
+            Symmetry::FlipDiagonal,
+            Symmetry::FlipAntiDiagonal,
+        ]
+    }
+
+    /// true if this symmetry swaps the width and height of the board, so it only maps square
+    /// boards on to themselves
+    pub fn swaps_axes(&self) -> bool {
+        matches!(
+            self,
+            Symmetry::Rotate90
+                | Symmetry::Rotate270
+                | Symmetry::FlipDiagonal
+                | Symmetry::FlipAntiDiagonal
+        )
+    }
+
+    /// the symmetry that undoes this one
+    pub fn inverse(&self) -> Self {
+        match self {
+            Symmetry::Rotate90 => Symmetry::Rotate270,
+            Symmetry::Rotate270 => Symmetry::Rotate90,
+            other => *other,
+        }
+    }
+
+    /// transforms a direction
+    pub fn transform_vector(&self, v: Vector) -> Vector {
+        let (x, y) = match self {
+            Symmetry::Identity => (v.x, v.y),
+            Symmetry::Rotate90 => (-v.y, v.x),
+            Symmetry::Rotate180 => (-v.x, -v.y),
+            Symmetry::Rotate270 => (v.y, -v.x),
+            Symmetry::FlipHorizontal => (-v.x, v.y),
+            Symmetry::FlipVertical => (v.x, -v.y),
+            Symmetry::FlipDiagonal => (v.y, v.x),
+            Symmetry::FlipAntiDiagonal => (-v.y, -v.x),
+        };
+        Vector { x, y }
+    }
+
+    /// transforms a position on a board of the given size
+    pub fn transform_position(&self, pos: Position, width: u32, height: u32) -> Position {
+        let (max_x, max_y) = (width as i32 - 1, height as i32 - 1);
+        let (x, y) = match self {
+            Symmetry::Identity => (pos.x, pos.y),
+            Symmetry::Rotate90 => (max_y - pos.y, pos.x),
+            Symmetry::Rotate180 => (max_x - pos.x, max_y - pos.y),
+            Symmetry::Rotate270 => (pos.y, max_x - pos.x),
+            Symmetry::FlipHorizontal => (max_x - pos.x, pos.y),
+            Symmetry::FlipVertical => (pos.x, max_y - pos.y),
+            Symmetry::FlipDiagonal => (pos.y, pos.x),
+            Symmetry::FlipAntiDiagonal => (max_y - pos.y, max_x - pos.x),
+        };
+        Position::new(x, y)
+    }
+}
+
+/// token to represent a snake id
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Deserialize)]
+#[repr(transparent)]
+pub struct SnakeId(pub u8);
+
+impl SnakeId {
+    /// convert this snake ID to a usize
+    pub fn as_usize(&self) -> usize {
+        self.0 as usize
+    }
+}
+
+impl Serialize for SnakeId {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_u8(self.0)
+    }
+}
+
+/// A game for which one can get the snake ids
+pub trait SnakeIDGettableGame {
+    #[allow(missing_docs)]
+    type SnakeIDType: PartialEq + Debug + Serialize + Eq + Hash + Clone + Send;
+
+    #[allow(missing_docs)]
+    fn get_snake_ids(&self) -> Vec<Self::SnakeIDType>;
+}
+
+/// Instruments to be used with simulation, for your own aggregation. For distributed tracing
+/// turn on the `tracing` feature, which gives each compact simulation a span
+pub trait SimulatorInstruments: Debug {
+    /// called with how long each simulation took, including making the boards it returns. Lazy
+    /// simulations call this once their iterator is used up or dropped. This is never called
+    /// without `std` or on wasm32-unknown-unknown, where there is no clock to measure with
+    fn observe_simulation(&self, duration: Duration);
+
+    /// asked once at the start of each simulation, whether to call
+    /// [SimulatorInstruments::observe_state] with every state it makes. Recording states turns
+    /// each one in to json and stops the states from being simulated lazily, so the default is
+    /// not to. Without `std` there's no json, so this is ignored
+    fn records_states(&self) -> bool {
+        false
+    }
+
+    /// called with each state a simulation makes when [SimulatorInstruments::records_states]
+    /// says to, with the moves that made it and the state as the json of a wire game, see
+    /// [crate::compact_representation::StandardCellBoard::to_game]
+    fn observe_state(&self, _moves: &[(SnakeId, Move)], _state_json: &str) {}
+}
+
+/// A game for which "you" is determinable
+pub trait YouDeterminableGame: Debug + SnakeIDGettableGame {
+    /// determines for a given game if a given snake id is you.
+    fn is_you(&self, snake_id: &Self::SnakeIDType) -> bool;
+
+    /// get the id for you for a given game. This is your id even once you have been eliminated,
+    /// so it can be used in the /end request to look up how you did
+    fn you_id(&self) -> &Self::SnakeIDType;
+}
+
+/// A game which can have it's winner determined
+pub trait VictorDeterminableGame: Debug + SnakeIDGettableGame {
+    #[allow(missing_docs)]
+    fn is_over(&self) -> bool;
+
+    /// get the winner for a given game, will return None in the case of a draw, or if the game is not over
+    fn get_winner(&self) -> Option<Self::SnakeIDType>;
+
+    /// How many snakes are alive
+    fn alive_snake_count(&self) -> usize;
+
+    /// How the whole game has turned out, [GameResult::Ongoing] while more than one snake (or
+    /// squad) is left even if you have been eliminated. The default is in terms of
+    /// [VictorDeterminableGame::is_over] and [VictorDeterminableGame::get_winner], so games where
+    /// those stop at your elimination should override it
+    fn game_result(&self) -> GameResult<Self::SnakeIDType> {
+        if !self.is_over() {
+            return GameResult::Ongoing;
+        }
+        self.get_winner()
+            .map_or(GameResult::Draw, GameResult::Winner)
+    }
+}
+
+/// The result of a game, see [VictorDeterminableGame::game_result]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum GameResult<SnakeIDType> {
+    /// more than one snake, or squad, is still alive
+    Ongoing,
+    /// every snake has been eliminated, e.g. the last two collided head to head
+    Draw,
+    /// the last snake alive, or in squad games a snake from the last squad alive
+    Winner(SnakeIDType),
+}
+
+/// A game played in squads, where the winner is the last squad with snakes left rather than a
+/// single snake
+pub trait SquadVictorDeterminableGame: VictorDeterminableGame {
+    /// the type squads are identified by
+    type SquadIDType;
+
+    /// the squad that won, None if the game is not over or every squad was eliminated
+    fn get_winning_squad(&self) -> Option<Self::SquadIDType>;
+}
+
+#[derive(Debug, Copy, Clone)]
+#[repr(transparent)]
+/// Represents moves taken for a given simulation
+pub struct Action<const N_SNAKES: usize> {
+    moves: [Option<Move>; N_SNAKES],
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+/// Represents only the moves taken by other snakes in a game
+pub struct OtherAction<const N_SNAKES: usize> {
+    moves: [Option<Move>; N_SNAKES],
+}
+
+impl<const N_SNAKES: usize> Action<N_SNAKES> {
+    /// create a new action from a given array of moves
+    pub fn new(moves: [Option<Move>; N_SNAKES]) -> Self {
+        Self { moves }
+    }
+
+    /// collects an action from an iterator of moves
+    pub fn collect_from<'a, T: Iterator<Item = &'a (SnakeId, Move)>>(ids_and_moves: T) -> Self {
+        let mut moves = [None; N_SNAKES];
+        for (id, mv) in ids_and_moves {
+            moves[id.as_usize()] = Some(*mv);
+        }
+        Self { moves }
+    }
+
+    /// gets your move
+    pub fn own_move(&self) -> Move {
+        self.moves[0].unwrap()
+    }
+    /// construct an OtherAction of the other sankes moves
+    pub fn other_moves(&self) -> OtherAction<N_SNAKES> {
+        let mut new_moves = self.moves;
+        new_moves[0] = None;
+        OtherAction { moves: new_moves }
+    }
+
+    /// the snakes that moved and their moves, in order of snake id
+    pub fn iter(&self) -> impl Iterator<Item = (SnakeId, Move)> + '_ {
+        self.moves
+            .iter()
+            .enumerate()
+            .filter_map(|(idx, mv)| mv.map(|mv| (SnakeId(idx as u8), mv)))
+    }
+}
+
+/// One snake's move, e.g. one of the moves a turn is advanced with. Converts to and from a
+/// `(snake_id, mv)` tuple, so code written against tuples only needs an `.into()`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct SnakeMove<SnakeIDType> {
+    /// the snake making the move
+    pub snake_id: SnakeIDType,
+    /// the move it makes
+    pub mv: Move,
+}
+
+impl<SnakeIDType> SnakeMove<SnakeIDType> {
+    /// `snake_id` making the move `mv`
+    pub fn new(snake_id: SnakeIDType, mv: Move) -> Self {
+        SnakeMove { snake_id, mv }
+    }
+}
+
+impl<SnakeIDType> From<(SnakeIDType, Move)> for SnakeMove<SnakeIDType> {
+    fn from((snake_id, mv): (SnakeIDType, Move)) -> Self {
+        SnakeMove { snake_id, mv }
+    }
+}
+
+impl<SnakeIDType: Clone> From<&(SnakeIDType, Move)> for SnakeMove<SnakeIDType> {
+    fn from((snake_id, mv): &(SnakeIDType, Move)) -> Self {
+        SnakeMove::new(snake_id.clone(), *mv)
+    }
+}
+
+impl<SnakeIDType: Clone> From<&SnakeMove<SnakeIDType>> for SnakeMove<SnakeIDType> {
+    fn from(snake_move: &SnakeMove<SnakeIDType>) -> Self {
+        snake_move.clone()
+    }
+}
+
+impl<SnakeIDType> From<SnakeMove<SnakeIDType>> for (SnakeIDType, Move) {
+    fn from(snake_move: SnakeMove<SnakeIDType>) -> Self {
+        (snake_move.snake_id, snake_move.mv)
+    }
+}
+
+/// One step of resolving a turn's health, see [RulesVersion::phases]. Snakes moving and
+/// colliding with each other always happen before and after all of these
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TurnPhase {
+    /// every snake loses a point of health, if starvation is enabled
+    ReduceHealth,
+    /// snakes on hazards take damage, apart from ones moving on to food there
+    DamageHazards,
+    /// snakes on hazards take damage even when they move on to food there, and are eliminated
+    /// right away if it takes all their health, before they get to eat
+    DamageHazardsIncludingFood,
+    /// snakes on food eat it
+    FeedSnakes,
+    /// snakes with no health left are eliminated
+    EliminateStarved,
+}
+
+/// Which version of the engine's rules the simulator plays by. They differ in the order a turn
+/// is resolved in, see [RulesVersion::phases], which matters for self hosted tournaments that
+/// pin an old engine
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum RulesVersion {
+    /// the rules the engine plays by now
+    #[default]
+    Current,
+    /// the rules of the v1.1.x engines, where hazards hurt snakes eating on them
+    #[allow(non_camel_case_types)]
+    V1_1_x,
+}
+
+impl RulesVersion {
+    /// the phases a turn is resolved in, in order
+    pub fn phases(&self) -> &'static [TurnPhase] {
+        match self {
+            RulesVersion::Current => &[
+                TurnPhase::ReduceHealth,
+                TurnPhase::DamageHazards,
+                TurnPhase::FeedSnakes,
+                TurnPhase::EliminateStarved,
+            ],
+            RulesVersion::V1_1_x => &[
+                TurnPhase::ReduceHealth,
+                TurnPhase::DamageHazardsIncludingFood,
+                TurnPhase::FeedSnakes,
+                TurnPhase::EliminateStarved,
+            ],
+        }
+    }
+}
+
+/// The rules the simulator plays by that nonstandard rulesets and maps change. Boards get these
+/// from their ruleset when they are converted, see
+/// [crate::wire_representation::Ruleset::simulation_settings], and the default is the standard
+/// rules
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct SimulationSettings {
+    /// the health snakes have after eating, health never goes above this
+    pub max_health: u8,
+    /// how much health each hazard on a square takes each turn, negative hazards heal
+    pub hazard_damage: i16,
+    /// whether eating resets health to `max_health`, snakes still grow when it doesn't
+    pub food_restores_health: bool,
+    /// whether snakes lose a point of health every turn
+    pub starvation_enabled: bool,
+    /// whether every snake that survives a turn grows by one and has its health set back to
+    /// `max_health`, whether or not it ate, the way constrictor plays
+    pub always_grow: bool,
+    /// the version of the rules turns are resolved with
+    pub rules_version: RulesVersion,
+}
+
+impl SimulationSettings {
+    /// the hazard damage used when the ruleset doesn't say
+    pub const DEFAULT_HAZARD_DAMAGE: i16 = 15;
+}
+
+impl Default for SimulationSettings {
+    fn default() -> Self {
+        Self {
+            max_health: 100,
+            hazard_damage: Self::DEFAULT_HAZARD_DAMAGE,
+            food_restores_health: true,
+            starvation_enabled: true,
+            always_grow: false,
+            rules_version: RulesVersion::Current,
+        }
+    }
+}
+
+/// A game where positions can be checked for hazards
+pub trait HazardQueryableGame: PositionGettableGame {
+    /// Is this position a hazard?
+    fn is_hazard(&self, pos: &Self::NativePositionType) -> bool;
+
+    /// how much damage do hazards do? Negative for hazards that heal
+    fn get_hazard_damage(&self) -> i16;
+
+    /// How many hazards are stacked on this position, each one does
+    /// [HazardQueryableGame::get_hazard_damage] damage. Compact boards count at most
+    /// [crate::compact_representation::MAX_HAZARD_STACK]
+    fn get_hazard_count(&self, pos: &Self::NativePositionType) -> u8 {
+        self.is_hazard(pos) as u8
+    }
+}
+
+/// A game where positions can be checked for walls, squares like the arcade_maze map's that no
+/// snake can move on to. Moving on to a wall eliminates a snake the same as moving off the board
+pub trait WallQueryableGame: PositionGettableGame {
+    /// Is this position a wall?
+    fn is_wall(&self, pos: &Self::NativePositionType) -> bool;
+}
+
+/// A game where positions can be checked for food
+pub trait FoodQueryableGame: PositionGettableGame {
+    /// Is this position a food?
+    fn is_food(&self, pos: &Self::NativePositionType) -> bool;
+}
+
+/// A game where positions can be checked to see if they are a certain snakes Neck piece
+///
+/// A neck is defined as the piece that comes immediately after the head of a snake. If the snake
+/// is fully triple stacked it has no neck piece.
+pub trait NeckQueryableGame: PositionGettableGame + SnakeIDGettableGame {
+    /// Is this position a neck for the given snake?
+    fn is_neck(&self, sid: &Self::SnakeIDType, pos: &Self::NativePositionType) -> bool;
+}
+
+/// The answer to a question about a square that might be hidden, see [MaskedQueryableGame]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Knowledge {
+    #[allow(missing_docs)]
+    Yes,
+    #[allow(missing_docs)]
+    No,
+    /// the square can't be seen
+    Unknown,
+}
+
+impl From<bool> for Knowledge {
+    fn from(known: bool) -> Self {
+        if known {
+            Knowledge::Yes
+        } else {
+            Knowledge::No
+        }
+    }
+}
+
+/// A game where only some squares can be seen, e.g. with fog of war. A game that can see the
+/// whole board never answers [Knowledge::Unknown]
+pub trait MaskedQueryableGame: PositionGettableGame {
+    /// whether what is on `pos` can be seen
+    fn is_visible(&self, pos: &Self::NativePositionType) -> bool;
+
+    /// whether there is food on `pos`
+    fn food_at(&self, pos: &Self::NativePositionType) -> Knowledge;
+
+    /// whether there is a hazard on `pos`
+    fn hazard_at(&self, pos: &Self::NativePositionType) -> Knowledge;
+
+    /// whether a piece of any snake is on `pos`
+    fn snake_body_at(&self, pos: &Self::NativePositionType) -> Knowledge;
+}
+
+/// A game where positions can have their hazards set and cleared
+pub trait HazardSettableGame: PositionGettableGame {
+    /// make this position a hazard
+    fn set_hazard(&mut self, pos: Self::NativePositionType);
+
+    /// clear this position of being a hazard
+    fn clear_hazard(&mut self, pos: Self::NativePositionType);
+}
+
+/// A game where food can be placed and removed, e.g. to set up a scenario
+pub trait FoodSettableGame: PositionGettableGame {
+    /// puts a food on this position, erroring if it is off the board or a snake is on it
+    fn place_food(&mut self, pos: Self::NativePositionType) -> Result<(), Error>;
+
+    /// removes the food on this position, if there is one
+    fn remove_food(&mut self, pos: Self::NativePositionType);
+}
+
+/// A game for which board positions can be identified and returned
+pub trait PositionGettableGame {
+    /// the native position type for this board
+    type NativePositionType: Eq + Hash + Clone + Ord + PartialOrd + Debug;
+
+    /// Check if the given position is a snake body
+    fn position_is_snake_body(&self, pos: Self::NativePositionType) -> bool;
+
+    /// Convert a position to the native type
+    fn position_from_native(&self, native: Self::NativePositionType) -> Position;
+
+    /// Convert a position to the native type
+    fn native_from_position(&self, pos: Position) -> Self::NativePositionType;
+
+    /// checks if a given position is not on this board
+    fn off_board(&self, pos: Position) -> bool;
+}
+
+/// A game for which the head of the current snake can be got.
+pub trait HeadGettableGame: PositionGettableGame + SnakeIDGettableGame {
+    /// get the head position for a given snake id, as a position struct (slow for simulation)
+    fn get_head_as_position(&self, snake_id: &Self::SnakeIDType) -> Position;
+
+    /// get the head position for a given snake as some "native" type for this game
+    fn get_head_as_native_position(&self, snake_id: &Self::SnakeIDType)
+        -> Self::NativePositionType;
+}
+
+/// A game for which the food on the board can be queries
+pub trait FoodGettableGame: PositionGettableGame + SnakeIDGettableGame {
+    /// every food on the board, as a position struct (slow for simulation). The order only
+    /// depends on the game, so anything built from it is the same every run
+    fn get_all_food_as_positions(&self) -> Vec<Position>;
+
+    /// every food on the board as some "native" type for this game, in the same order as
+    /// [FoodGettableGame::get_all_food_as_positions]
+    fn get_all_food_as_native_positions(&self) -> Vec<Self::NativePositionType>;
+}
+
+/// A game for which the length of the current snake can be got.
+pub trait LengthGettableGame: SnakeIDGettableGame {
+    /// the length type for this game
+    type LengthType: Ord + PartialOrd;
+
+    /// get the length for a given snake
+    fn get_length(&self, snake_id: &Self::SnakeIDType) -> Self::LengthType;
+
+    /// get the length for a given snake
+    fn get_length_i64(&self, snake_id: &Self::SnakeIDType) -> i64;
+}
+
+/// A game for which the health of the current snake can be got.
+pub trait HealthGettableGame: SnakeIDGettableGame {
+    /// the health type for this game
+    type HealthType: PartialEq;
+
+    /// A constant that defines what zero health means for the given game
+    const ZERO: Self::HealthType;
+
+    /// get the health for a given snake
+    fn get_health(&self, snake_id: &Self::SnakeIDType) -> Self::HealthType;
+
+    /// get the health for a given snake as an i64
+    fn get_health_i64(&self, snake_id: &Self::SnakeIDType) -> i64;
+
+    /// check wheterh a given snake is alive
+    fn is_alive(&self, snake_id: &Self::SnakeIDType) -> bool {
+        self.get_health(snake_id) != Self::ZERO
+    }
+}
+
+/// a game for which the neighbors of a given Position can be determined
+pub trait NeighborDeterminableGame: PositionGettableGame {
+    /// returns the neighboring positions
+    fn neighbors<'a>(
+        &'a self,
+        pos: &Self::NativePositionType,
+    ) -> Box<dyn Iterator<Item = Self::NativePositionType> + 'a>;
+
+    /// returns the neighboring positions, and the Move required to get to each
+    fn possible_moves<'a>(
+        &'a self,
+        pos: &Self::NativePositionType,
+    ) -> Box<dyn Iterator<Item = (Move, Self::NativePositionType)> + 'a>;
+}
+
+/// a game for which each snakes shout can be determined
+pub trait ShoutGettableGame: SnakeIDGettableGame {
+    /// get the shout for a given snake, if they shouted this turn
+    fn get_shout(&self, snake_id: &Self::SnakeIDType) -> Option<String>;
+}
+
+/// a game for which the size of the game board can be determined
+pub trait SizeDeterminableGame {
+    #[allow(missing_docs)]
+    fn get_width(&self) -> u32;
+    #[allow(missing_docs)]
+    fn get_height(&self) -> u32;
+
+    /// every position on the board, a row at a time from the top row down (the order the board
+    /// is drawn in) and left to right within each row
+    fn all_positions(&self) -> Box<dyn Iterator<Item = Position> + '_> {
+        let (width, height) = (self.get_width() as i32, self.get_height() as i32);
+        Box::new((0..height).flat_map(move |row| {
+            (0..width).map(move |col| Position::from_row_col(row, col, height))
+        }))
+    }
+
+    /// the positions along the edges of the board, in the same order as
+    /// [SizeDeterminableGame::all_positions]
+    fn border_positions(&self) -> Box<dyn Iterator<Item = Position> + '_> {
+        let (width, height) = (self.get_width() as i32, self.get_height() as i32);
+        Box::new(
+            self.all_positions()
+                .filter(move |p| p.x == 0 || p.y == 0 || p.x == width - 1 || p.y == height - 1),
+        )
+    }
+
+    /// the positions within `radius` moves (manhattan distance) of `center`, including `center`.
+    /// Positions past the edge of the board are dropped, or wrap around to the other side if
+    /// `wrapped` is set, in which case each position is only returned once. The work done is
+    /// bounded by the size of the board, however big `radius` is
+    fn positions_in_radius(
+        &self,
+        center: Position,
+        radius: u32,
+        wrapped: bool,
+    ) -> Box<dyn Iterator<Item = Position> + '_> {
+        let (width, height) = (self.get_width() as i64, self.get_height() as i64);
+        let (cx, cy) = (center.x as i64, center.y as i64);
+        let mut positions = Vec::new();
+        if !wrapped {
+            // only the rows and columns on the board are walked
+            let radius = radius as i64;
+            for y in ((cy - radius).max(0)..=(cy + radius).min(height - 1)).rev() {
+                let reach = radius - (y - cy).abs();
+                for x in (cx - reach).max(0)..=(cx + reach).min(width - 1) {
+                    positions.push(Position::new(x as i32, y as i32));
+                }
+            }
+            return Box::new(positions.into_iter());
+        }
+
+        // every square is within this many moves going around the edges
+        let radius = (radius as i64).min(width / 2 + height / 2);
+        let mut seen = alloc::vec![false; (width * height).max(0) as usize];
+        for dy in (-radius..=radius).rev() {
+            let reach = radius - dy.abs();
+            for dx in -reach..=reach {
+                let p = Position::new(
+                    (cx + dx).rem_euclid(width) as i32,
+                    (cy + dy).rem_euclid(height) as i32,
+                );
+                let index = p.to_flat_index(width as u8) as usize;
+                if seen[index] {
+                    continue;
+                }
+                seen[index] = true;
+                positions.push(p);
+            }
+        }
+        Box::new(positions.into_iter())
+    }
+}
+
+/// a game for which the current turn is determinable
+pub trait TurnDeterminableGame {
+    /// the turn the game is on, counting from 0. Wire games keep the turn in an i32, so turns
+    /// from 0 to i32::MAX are supported, and simulating past that stays on i32::MAX
+    fn turn(&self) -> u64;
+}
+
+/// A game where an entire snake body is gettable
+pub trait SnakeBodyGettableGame: PositionGettableGame + SnakeIDGettableGame {
+    /// return a Vec of the positions for a given snake body, in order from head to tail
+    fn get_snake_body_vec(&self, snake_id: &Self::SnakeIDType) -> Vec<Self::NativePositionType>;
+
+    /// return an iterator over all the snake body positions. Order is NOT guaranteed to be from head to tail
+    /// implementations are free to do any order that is efficient for them.
+    /// Positions that would be duplicate, due to a snake being double or triple stacked, may be
+    /// omitted
+    fn get_snake_body_iter(
+        &self,
+        snake_id: &Self::SnakeIDType,
+    ) -> Box<dyn Iterator<Item = Self::NativePositionType> + '_>;
+}
+
+/// One snake's id, head, health, length and whether it is alive, with its body borrowed from
+/// the game, see [SnakeViewGame::snakes]
+pub struct SnakeView<'a, G: SnakeViewGame + ?Sized> {
+    game: &'a G,
+    #[allow(missing_docs)]
+    pub id: G::SnakeIDType,
+    #[allow(missing_docs)]
+    pub head: G::NativePositionType,
+    #[allow(missing_docs)]
+    pub health: i64,
+    #[allow(missing_docs)]
+    pub length: i64,
+    #[allow(missing_docs)]
+    pub alive: bool,
+}
+
+impl<'a, G: SnakeViewGame + ?Sized> SnakeView<'a, G> {
+    /// the snake's body, in whatever order [SnakeBodyGettableGame::get_snake_body_iter] gives
+    /// it, without copying it out of the game
+    pub fn body(&self) -> Box<dyn Iterator<Item = G::NativePositionType> + 'a> {
+        self.game.get_snake_body_iter(&self.id)
+    }
+}
+
+impl<G: SnakeViewGame + ?Sized> Clone for SnakeView<'_, G> {
+    fn clone(&self) -> Self {
+        SnakeView {
+            game: self.game,
+            id: self.id.clone(),
+            head: self.head.clone(),
+            health: self.health,
+            length: self.length,
+            alive: self.alive,
+        }
+    }
+}
+
+impl<G: SnakeViewGame + ?Sized> Debug for SnakeView<'_, G> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SnakeView")
+            .field("id", &self.id)
+            .field("head", &self.head)
+            .field("health", &self.health)
+            .field("length", &self.length)
+            .field("alive", &self.alive)
+            .finish()
+    }
+}
+
+/// a game where everything about each snake can be had in one go, instead of zipping together
+/// the getters of each trait
+pub trait SnakeViewGame:
+    SnakeBodyGettableGame + HeadGettableGame + HealthGettableGame + LengthGettableGame
+{
+    /// A [SnakeView] of every snake in [SnakeIDGettableGame::get_snake_ids], in the same order.
+    /// Compact boards forget eliminated snakes, so only wire games have snakes that aren't alive
+    fn snakes(&self) -> Box<dyn Iterator<Item = SnakeView<'_, Self>> + '_> {
+        Box::new(self.get_snake_ids().into_iter().map(move |id| SnakeView {
+            game: self,
+            head: self.get_head_as_native_position(&id),
+            health: self.get_health_i64(&id),
+            length: self.get_length_i64(&id),
+            alive: self.is_alive(&id),
+            id,
+        }))
+    }
+}
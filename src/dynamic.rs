@@ -0,0 +1,388 @@
+//! Object safe versions of the game traits, for code that has to hold games as trait objects,
+//! e.g. strategies loaded as plugins. The static traits have associated types, generic
+//! instruments and return `Self`, so they can't be used as `dyn` traits. These trade some speed
+//! for that, every state is boxed, so they are for orchestrating searches rather than for their
+//! inner loops.
+//!
+//! [DynGameView] is implemented for every game with [SnakeId]s that implements the static
+//! getter traits. [SimulableGame] has the number of snakes as a const parameter, so games are
+//! wrapped in a [DynGame] to be simulated through [DynSimulableGame].
+//! [SimulatorInstruments] is already object safe, and is passed as a `&dyn SimulatorInstruments`
+//!
+//! ```
+//! # use battlesnake_game_types::compact_representation::StandardCellBoard4Snakes11x11;
+//! # use battlesnake_game_types::dynamic::{DynGame, DynSimulableGame};
+//! # use battlesnake_game_types::types::{build_snake_id_map, SimulatorInstruments};
+//! # use battlesnake_game_types::wire_representation::Game;
+//! # #[derive(Debug)]
+//! # struct Instruments;
+//! # impl SimulatorInstruments for Instruments {
+//! #     fn observe_simulation(&self, _: std::time::Duration) {}
+//! # }
+//! # let body = include_str!("../fixtures/late_stage.json");
+//! # let g: Game = serde_json::from_str(body).unwrap();
+//! let board: StandardCellBoard4Snakes11x11 = g.as_cell_board(&build_snake_id_map(&g)).unwrap();
+//! let game: Box<dyn DynSimulableGame> = DynGame::<_, 4>::boxed(board);
+//! for (moves, next) in game.simulate_all_boxed(&Instruments) {
+//!     println!("{:?} leaves {} snakes", moves, next.snake_ids().len());
+//! }
+//! ```
+
+use std::any::Any;
+use std::fmt::Debug;
+
+use crate::types::{
+    FoodGettableGame, HeadGettableGame, HealthGettableGame, LengthGettableGame, Move,
+    SimulableGame, SimulatorInstruments, SizeDeterminableGame, SnakeBodyGettableGame, SnakeId,
+    VictorDeterminableGame, YouDeterminableGame,
+};
+use crate::wire_representation::Position;
+use crate::Error;
+
+/// the getter traits in one object safe trait, with snakes identified by [SnakeId] and squares
+/// by [Position]
+pub trait DynGameView: Debug + Send + Sync {
+    /// the living snakes, see [crate::types::SnakeIDGettableGame::get_snake_ids]
+    fn snake_ids(&self) -> Vec<SnakeId>;
+
+    /// your id, see [YouDeterminableGame::you_id]
+    fn you(&self) -> SnakeId;
+
+    /// see [HealthGettableGame::get_health_i64]
+    fn health(&self, id: SnakeId) -> i64;
+
+    /// see [LengthGettableGame::get_length_i64]
+    fn length(&self, id: SnakeId) -> i64;
+
+    /// see [HeadGettableGame::get_head_as_position]
+    fn head(&self, id: SnakeId) -> Position;
+
+    /// the snake's body from head to tail, see [SnakeBodyGettableGame::get_snake_body_vec]
+    fn body(&self, id: SnakeId) -> Vec<Position>;
+
+    /// see [FoodGettableGame::get_all_food_as_positions]
+    fn food(&self) -> Vec<Position>;
+
+    #[allow(missing_docs)]
+    fn width(&self) -> u32;
+
+    #[allow(missing_docs)]
+    fn height(&self) -> u32;
+
+    /// see [VictorDeterminableGame::is_over]
+    fn finished(&self) -> bool;
+
+    /// see [VictorDeterminableGame::get_winner]
+    fn winner(&self) -> Option<SnakeId>;
+}
+
+impl<G> DynGameView for G
+where
+    G: YouDeterminableGame<SnakeIDType = SnakeId>
+        + VictorDeterminableGame
+        + HealthGettableGame
+        + LengthGettableGame
+        + HeadGettableGame
+        + SnakeBodyGettableGame
+        + FoodGettableGame
+        + SizeDeterminableGame
+        + Send
+        + Sync,
+{
+    fn snake_ids(&self) -> Vec<SnakeId> {
+        self.get_snake_ids()
+    }
+
+    fn you(&self) -> SnakeId {
+        *self.you_id()
+    }
+
+    fn health(&self, id: SnakeId) -> i64 {
+        self.get_health_i64(&id)
+    }
+
+    fn length(&self, id: SnakeId) -> i64 {
+        self.get_length_i64(&id)
+    }
+
+    fn head(&self, id: SnakeId) -> Position {
+        self.get_head_as_position(&id)
+    }
+
+    fn body(&self, id: SnakeId) -> Vec<Position> {
+        self.get_snake_body_vec(&id)
+            .into_iter()
+            .map(|pos| self.position_from_native(pos))
+            .collect()
+    }
+
+    fn food(&self) -> Vec<Position> {
+        self.get_all_food_as_positions()
+    }
+
+    fn width(&self) -> u32 {
+        self.get_width()
+    }
+
+    fn height(&self) -> u32 {
+        self.get_height()
+    }
+
+    fn finished(&self) -> bool {
+        self.is_over()
+    }
+
+    fn winner(&self) -> Option<SnakeId> {
+        self.get_winner()
+    }
+}
+
+/// the moves that made a simulated state and the state, see [DynSimulableGame]
+pub type DynSimulation = (Vec<(SnakeId, Move)>, Box<dyn DynSimulableGame>);
+
+/// an object safe version of [SimulableGame]
+pub trait DynSimulableGame: DynGameView {
+    /// Every state from each snake in `snake_ids_and_moves` making one of its moves, with the
+    /// moves that made it, see [SimulableGame::try_simulate_with_moves]. Errors if a snake
+    /// isn't alive or is given no moves
+    fn simulate_boxed(
+        &self,
+        instruments: &dyn SimulatorInstruments,
+        snake_ids_and_moves: &[(SnakeId, Vec<Move>)],
+    ) -> Result<Vec<DynSimulation>, Error>;
+
+    /// every state from each living snake making any move, see [SimulableGame::simulate]
+    fn simulate_all_boxed(&self, instruments: &dyn SimulatorInstruments) -> Vec<DynSimulation> {
+        let moves = self
+            .snake_ids()
+            .into_iter()
+            .map(|sid| (sid, Move::all().to_vec()))
+            .collect::<Vec<_>>();
+        self.simulate_boxed(instruments, &moves)
+            .expect("every living snake is given every move")
+    }
+
+    /// a boxed copy of this game
+    fn clone_boxed(&self) -> Box<dyn DynSimulableGame>;
+
+    /// the game, for downcasting back to the type it was made from
+    fn as_any(&self) -> &dyn Any;
+}
+
+impl Clone for Box<dyn DynSimulableGame> {
+    fn clone(&self) -> Self {
+        self.clone_boxed()
+    }
+}
+
+/// Wraps a game that has `N_SNAKES` snakes to implement [DynSimulableGame] for it. The game is
+/// a [DynGameView] through the wrapper too
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DynGame<G, const N_SNAKES: usize>(pub G);
+
+impl<G, const N_SNAKES: usize> DynGame<G, N_SNAKES>
+where
+    Self: DynSimulableGame + 'static,
+{
+    /// `game` wrapped and boxed as a trait object
+    pub fn boxed(game: G) -> Box<dyn DynSimulableGame> {
+        Box::new(DynGame::<G, N_SNAKES>(game))
+    }
+}
+
+/// passes calls on to a `&dyn` instruments, since the static traits need them to be sized
+#[derive(Debug)]
+pub struct DynInstruments<'a>(pub &'a dyn SimulatorInstruments);
+
+impl SimulatorInstruments for DynInstruments<'_> {
+    fn observe_simulation(&self, duration: std::time::Duration) {
+        self.0.observe_simulation(duration)
+    }
+
+    fn records_states(&self) -> bool {
+        self.0.records_states()
+    }
+
+    fn observe_state(&self, moves: &[(SnakeId, Move)], state_json: &str) {
+        self.0.observe_state(moves, state_json)
+    }
+}
+
+impl<G: DynGameView, const N_SNAKES: usize> DynGameView for DynGame<G, N_SNAKES> {
+    fn snake_ids(&self) -> Vec<SnakeId> {
+        self.0.snake_ids()
+    }
+
+    fn you(&self) -> SnakeId {
+        self.0.you()
+    }
+
+    fn health(&self, id: SnakeId) -> i64 {
+        self.0.health(id)
+    }
+
+    fn length(&self, id: SnakeId) -> i64 {
+        self.0.length(id)
+    }
+
+    fn head(&self, id: SnakeId) -> Position {
+        self.0.head(id)
+    }
+
+    fn body(&self, id: SnakeId) -> Vec<Position> {
+        self.0.body(id)
+    }
+
+    fn food(&self) -> Vec<Position> {
+        self.0.food()
+    }
+
+    fn width(&self) -> u32 {
+        self.0.width()
+    }
+
+    fn height(&self) -> u32 {
+        self.0.height()
+    }
+
+    fn finished(&self) -> bool {
+        self.0.finished()
+    }
+
+    fn winner(&self) -> Option<SnakeId> {
+        self.0.winner()
+    }
+}
+
+impl<G, const N_SNAKES: usize> DynSimulableGame for DynGame<G, N_SNAKES>
+where
+    G: DynGameView + Clone + 'static,
+    G: for<'a> SimulableGame<DynInstruments<'a>, N_SNAKES, SnakeIDType = SnakeId>,
+{
+    fn simulate_boxed(
+        &self,
+        instruments: &dyn SimulatorInstruments,
+        snake_ids_and_moves: &[(SnakeId, Vec<Move>)],
+    ) -> Result<Vec<DynSimulation>, Error> {
+        let instruments = DynInstruments(instruments);
+        let moves = snake_ids_and_moves
+            .iter()
+            .map(|(sid, moves)| (*sid, moves.as_slice()));
+        let simulations = self
+            .0
+            .try_simulate_with_moves(&instruments, moves)?
+            .map(|(action, next)| {
+                let next: Box<dyn DynSimulableGame> = Box::new(DynGame::<G, N_SNAKES>(next));
+                (action.iter().collect(), next)
+            })
+            .collect();
+        Ok(simulations)
+    }
+
+    fn clone_boxed(&self) -> Box<dyn DynSimulableGame> {
+        Box::new(self.clone())
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        &self.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use itertools::Itertools;
+
+    use super::*;
+    use crate::{
+        compact_representation::{StandardCellBoard4Snakes11x11, WrappedCellBoard4Snakes11x11},
+        game_fixture,
+        types::{build_snake_id_map, SnakeIDGettableGame},
+    };
+
+    #[derive(Debug)]
+    struct Instruments;
+
+    impl SimulatorInstruments for Instruments {
+        fn observe_simulation(&self, _: std::time::Duration) {}
+    }
+
+    /// the worst score we can be held to over `depth` turns of everyone moving at once, and the
+    /// move that gets it, only using the trait objects
+    fn maximin(game: &dyn DynSimulableGame, depth: usize) -> (f64, Option<Move>) {
+        let you = game.you();
+        if !game.snake_ids().contains(&you) {
+            return (-1.0, None);
+        }
+        if depth == 0 || game.finished() {
+            return (
+                game.health(you) as f64 / 100.0 + game.length(you) as f64,
+                None,
+            );
+        }
+        let mut worst = HashMap::new();
+        for (moves, next) in game.simulate_all_boxed(&Instruments) {
+            let own = moves.iter().find(|(sid, _)| *sid == you).unwrap().1;
+            let (score, _) = maximin(next.as_ref(), depth - 1);
+            let entry = worst.entry(own).or_insert(score);
+            *entry = f64::min(*entry, score);
+        }
+        let (mv, score) = worst
+            .into_iter()
+            .max_by(|(_, a), (_, b)| a.partial_cmp(b).unwrap())
+            .unwrap();
+        (score, Some(mv))
+    }
+
+    #[test]
+    fn test_two_ply_search() {
+        let g = game_fixture(include_str!("../fixtures/late_stage.json"));
+        let board: StandardCellBoard4Snakes11x11 =
+            g.as_cell_board(&build_snake_id_map(&g)).unwrap();
+        let game = DynGame::<_, 4>::boxed(board);
+        assert_eq!(game.snake_ids(), vec![SnakeId(0), SnakeId(1)]);
+        assert_eq!(game.head(SnakeId(0)), g.you.head);
+        assert_eq!(game.body(SnakeId(0)), Vec::from(g.you.body.clone()));
+
+        let (score, mv) = maximin(game.as_ref(), 2);
+        assert!(score > 0.0, "there is a move that survives two turns");
+        // the move survives every reply in the static simulation too
+        let mv = mv.unwrap();
+        let replies = board.simulate_with_moves(
+            &Instruments,
+            vec![(SnakeId(0), vec![mv]), (SnakeId(1), Move::all().to_vec())],
+        );
+        for (_, next) in replies {
+            assert!(next.get_snake_ids().contains(&SnakeId(0)));
+        }
+    }
+
+    #[test]
+    fn test_matches_static_simulation() {
+        let g = game_fixture(include_str!("../fixtures/wrapped_fixture.json"));
+        let board: WrappedCellBoard4Snakes11x11 =
+            g.as_wrapped_cell_board(&build_snake_id_map(&g)).unwrap();
+        let game = DynGame::<_, 4>::boxed(board);
+        let boxed = game.simulate_all_boxed(&Instruments);
+        let expected = board
+            .simulate(&Instruments, board.get_snake_ids())
+            .collect_vec();
+        assert_eq!(boxed.len(), expected.len());
+        for ((moves, next), (action, expected)) in boxed.iter().zip(&expected) {
+            assert_eq!(*moves, action.iter().collect_vec());
+            let next = next.as_any().downcast_ref::<WrappedCellBoard4Snakes11x11>();
+            assert_eq!(next, Some(expected));
+        }
+
+        let copy = game.clone();
+        assert_eq!(
+            copy.as_any().downcast_ref::<WrappedCellBoard4Snakes11x11>(),
+            Some(&board)
+        );
+        assert!(matches!(
+            game.simulate_boxed(&Instruments, &[(SnakeId(3), vec![Move::Up])]),
+            Err(Error::UnknownSnake(_))
+        ));
+    }
+}
@@ -0,0 +1,318 @@
+//! the error type for everything in this crate that can fail
+
+use alloc::string::String;
+use core::fmt;
+
+use thiserror::Error;
+
+use crate::core_types::Position;
+
+/// Everything that can go wrong when parsing, converting, or simulating a game. Functions that
+/// still panic only do so when one of this crates own invariants is broken, and say so in their
+/// docs
+#[derive(Debug, Error)]
+pub enum Error {
+    /// the json couldn't be parsed in to a game
+    #[cfg(feature = "std")]
+    #[error("couldn't parse the game: {0}")]
+    Json(#[from] serde_json::Error),
+    /// the board has a width or height that is zero, negative, or bigger than 255
+    #[error("invalid board dimensions {width}x{height}")]
+    InvalidDimensions {
+        /// the width of the board
+        width: i32,
+        /// the height of the board
+        height: i32,
+    },
+    /// the board doesn't fit in the board type it is being converted to
+    #[error("a {width}x{height} board doesn't fit in this board type")]
+    BoardDoesNotFit {
+        /// the width of the board
+        width: i32,
+        /// the height of the board
+        height: i32,
+    },
+    /// a position that can't be on a compact board, because a coordinate is negative or bigger
+    /// than the largest compact board
+    #[error("({}, {}) can't be on a compact board", .0.x, .0.y)]
+    PositionDoesNotFit(Position),
+    /// there are more snakes than the board type being converted to can hold
+    #[error("{0} snakes don't fit in this board type")]
+    TooManySnakes(usize),
+    /// a snake that was asked about isn't in the game
+    #[error("no snake with id {0}")]
+    UnknownSnake(String),
+    /// more than one snake has the same id
+    #[error("more than one snake has id {0}")]
+    DuplicateSnakeId(String),
+    /// a snake that could never be sent by the engine, e.g. one that is off the board or
+    /// crosses itself
+    #[error("snake {id} {reason}")]
+    InvalidSnake {
+        /// the id of the snake
+        id: String,
+        /// what is wrong with it
+        reason: String,
+    },
+    /// the game uses a ruleset that isn't supported here
+    #[error("the {0} ruleset isn't supported here")]
+    UnsupportedRuleset(String),
+    /// moves were passed to the simulator that it can't apply
+    #[error("invalid moves: {0}")]
+    InvalidMoves(String),
+    /// simulating would produce more states than the caller allowed, see
+    /// [SimulableGame::simulate_with_moves_bounded]
+    ///
+    /// [SimulableGame::simulate_with_moves_bounded]: crate::types::SimulableGame::simulate_with_moves_bounded
+    #[error("simulating would produce {states} states, the most allowed is {max_states}")]
+    TooManyStates {
+        /// how many combinations of moves were asked for
+        states: usize,
+        /// the most that were allowed
+        max_states: usize,
+    },
+    /// a shout the engine wouldn't accept, see [MoveResponse::try_with_shout]
+    ///
+    /// [MoveResponse::try_with_shout]: crate::wire_representation::MoveResponse::try_with_shout
+    #[error("invalid shout: {0}")]
+    InvalidShout(String),
+    /// bytes that aren't a compact board, because they were written by another version of the
+    /// format or have been corrupted
+    #[error("the bytes aren't a valid board: {0}")]
+    InvalidBytes(String),
+    /// the hazards can't be forecast, e.g. royale hazards that aren't laid out the way the
+    /// engine lays them out
+    #[error("the hazards can't be predicted: {0}")]
+    UnpredictableHazards(String),
+    /// a move was pushed on to a path key that is already full, see
+    /// [PathKey::push_dropping_oldest] for keeping the newest moves instead
+    ///
+    /// [PathKey::push_dropping_oldest]: crate::path_key::PathKey::push_dropping_oldest
+    #[error("the path key is full, it holds at most {0} moves")]
+    PathKeyFull(usize),
+    /// a path key that couldn't be made, because it isn't laid out the way path keys are or has
+    /// a snake id that doesn't fit
+    #[error("invalid path key: {0}")]
+    InvalidPathKey(String),
+    /// food that can't be placed where it was asked to be, because the square is off the board
+    /// or has a snake on it
+    #[error("can't place food: {0}")]
+    InvalidFood(String),
+    /// a hazard that can't be placed where it was asked to be, because the square is off the
+    /// board
+    #[error("can't place hazard: {0}")]
+    InvalidHazard(String),
+    /// a wall that can't be placed where it was asked to be, because the square is off the board
+    /// or already has something on it
+    #[error("can't place wall: {0}")]
+    InvalidWall(String),
+    /// a board was built without any snakes on it
+    #[error("a board needs at least one snake")]
+    NoSnakes,
+}
+
+/// Something wrong with a game that can be worked around, so [Game::validate] lets it through
+/// with a warning instead of an error
+///
+/// [Game::validate]: crate::wire_representation::Game::validate
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ValidationWarning {
+    /// food sent more than once at the same position, it is only counted once
+    DuplicateFood(Position),
+    /// food off the board, which compact boards leave out and wire games never let a snake eat
+    FoodOffBoard(Position),
+}
+
+impl fmt::Display for ValidationWarning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ValidationWarning::DuplicateFood(pos) => {
+                write!(f, "food at {:?} was sent more than once", pos)
+            }
+            ValidationWarning::FoodOffBoard(pos) => write!(f, "food at {:?} is off the board", pos),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::{rngs::SmallRng, seq::SliceRandom, Rng, SeedableRng};
+
+    use super::*;
+    use crate::{
+        compact_representation::{standard, wrapped},
+        compact_representation::{StandardCellBoard4Snakes11x11, WrappedCellBoard4Snakes11x11},
+        try_game_fixture,
+        types::{try_build_snake_id_map, Move, SimulableGame, SimulatorInstruments, SnakeId},
+        types::{SnakeIDGettableGame, SnakeIDMap},
+        wire_representation::{Game, Position},
+    };
+
+    #[derive(Debug)]
+    struct Instruments;
+    impl SimulatorInstruments for Instruments {
+        fn observe_simulation(&self, _: std::time::Duration) {}
+    }
+
+    fn random_position(rng: &mut SmallRng) -> Position {
+        Position::new(rng.gen_range(-3..15), rng.gen_range(-3..15))
+    }
+
+    /// breaks `game` in one of the ways a weird payload might be broken
+    fn mutate(game: &mut Game, rng: &mut SmallRng) {
+        let snakes = game.board.snakes.len();
+        let snake = rng.gen_range(0..snakes.max(1));
+        match rng.gen_range(0..12) {
+            0 => game.board.width = rng.gen_range(-2..60),
+            1 => game.board.height = rng.gen_range(-2..60),
+            2 if snakes > 0 => game.board.snakes[snake].health = rng.gen_range(-10..300),
+            3 if snakes > 0 => {
+                let body = &mut game.board.snakes[snake].body;
+                let piece = rng.gen_range(0..body.len().max(1));
+                if let Some(piece) = body.get_mut(piece) {
+                    *piece = random_position(rng);
+                }
+            }
+            4 if snakes > 0 => game.board.snakes[snake].head = random_position(rng),
+            5 if snakes > 0 => game.board.snakes[snake].body.clear(),
+            6 if snakes > 0 => {
+                let mut copy = game.board.snakes[snake].clone();
+                if rng.gen() {
+                    copy.id.push('!');
+                }
+                game.board.snakes.push(copy);
+            }
+            7 if snakes > 0 => {
+                let position = random_position(rng);
+                game.board.snakes[snake].body.push_back(position);
+            }
+            8 if snakes > 0 => {
+                game.board.snakes.remove(snake);
+            }
+            9 => game.you.id = rng.gen::<u8>().to_string(),
+            10 => {
+                let names = ["standard", "wrapped", "royale", "constrictor", ""];
+                game.game.ruleset.name = names.choose(rng).unwrap().to_string();
+            }
+            _ => {
+                game.board.food.insert(random_position(rng));
+                game.board.hazards.push(random_position(rng));
+            }
+        }
+    }
+
+    /// simulates every snake on the board, plus moves that should be turned away
+    fn simulate<G: SimulableGame<Instruments, 4> + SnakeIDGettableGame<SnakeIDType = SnakeId>>(
+        board: &G,
+    ) {
+        let alive = board.get_snake_ids();
+        let results = board.try_simulate(&Instruments, alive.clone()).unwrap();
+        assert!(results.count() > 0 || alive.is_empty());
+
+        let not_playing =
+            board.try_simulate_with_moves(&Instruments, vec![(SnakeId(7), Move::all())]);
+        assert!(not_playing.is_err());
+        if let Some(sid) = alive.first() {
+            let no_moves = board.try_simulate_with_moves(&Instruments, vec![(*sid, vec![])]);
+            assert!(no_moves.is_err());
+        }
+    }
+
+    fn run_pipeline(json: &str) {
+        let game = match try_game_fixture(json) {
+            Ok(game) => game,
+            Err(_) => return,
+        };
+        let snake_ids: SnakeIDMap = match try_build_snake_id_map(&game) {
+            Ok(snake_ids) => snake_ids,
+            Err(_) => return,
+        };
+
+        let _ = standard::ToBestCellBoard::to_best_cell_board(game.clone());
+        let _ = wrapped::ToBestCellBoard::to_best_cell_board(game.clone());
+        if let Ok(board) =
+            StandardCellBoard4Snakes11x11::convert_from_game(game.clone(), &snake_ids)
+        {
+            simulate(&board);
+        }
+        if let Ok(board) = WrappedCellBoard4Snakes11x11::convert_from_game(game, &snake_ids) {
+            simulate(&board);
+        }
+    }
+
+    #[test]
+    fn test_malformed_games_error_instead_of_panicking() {
+        let fixtures = [
+            include_str!("../fixtures/start_of_game.json"),
+            include_str!("../fixtures/late_stage.json"),
+            include_str!("../fixtures/4_snake_game.json"),
+            include_str!("../fixtures/7x7board_valid.json"),
+            include_str!("../fixtures/wrapped_fixture.json"),
+            include_str!("../fixtures/squad_game.json"),
+        ];
+        let mut rng = SmallRng::seed_from_u64(120);
+
+        for _ in 0..2000 {
+            let mut game = try_game_fixture(fixtures.choose(&mut rng).unwrap()).unwrap();
+            for _ in 0..rng.gen_range(1..4) {
+                mutate(&mut game, &mut rng);
+            }
+            let mut json = serde_json::to_string(&game).unwrap();
+            if rng.gen_ratio(1, 10) {
+                let mut end = rng.gen_range(0..json.len());
+                while !json.is_char_boundary(end) {
+                    end -= 1;
+                }
+                json.truncate(end);
+            }
+            run_pipeline(&json);
+        }
+    }
+
+    #[test]
+    fn test_error_messages() {
+        let mut game = try_game_fixture(include_str!("../fixtures/start_of_game.json")).unwrap();
+        game.board.snakes[0].body.clear();
+        let snake_ids = try_build_snake_id_map(&game).unwrap();
+        let err =
+            StandardCellBoard4Snakes11x11::convert_from_game(game.clone(), &snake_ids).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            format!("snake {} has an empty body", game.board.snakes[0].id)
+        );
+
+        game.board.width = 0;
+        let err = StandardCellBoard4Snakes11x11::convert_from_game(game, &snake_ids).unwrap_err();
+        assert!(matches!(
+            err,
+            Error::InvalidDimensions {
+                width: 0,
+                height: 11
+            }
+        ));
+        assert!(matches!(try_game_fixture("{"), Err(Error::Json(_))));
+    }
+
+    #[test]
+    fn test_off_board_snake_is_rejected() {
+        let fixtures = [
+            include_str!("../fixtures/7x7board.json"),
+            include_str!("../fixtures/8x8board.json"),
+        ];
+        let off_board = |err: Error| match err {
+            Error::InvalidSnake { id, .. } => id == "gs_6kQVWJXt9BFpD6dchrmX8qpM",
+            _ => false,
+        };
+        for json in fixtures.iter() {
+            let game = try_game_fixture(json).unwrap();
+            assert!(off_board(game.validate().unwrap_err()));
+            assert!(off_board(
+                standard::ToBestCellBoard::to_best_cell_board(game.clone()).unwrap_err()
+            ));
+            let snake_ids = try_build_snake_id_map(&game).unwrap();
+            assert!(off_board(
+                StandardCellBoard4Snakes11x11::convert_from_game(game, &snake_ids).unwrap_err()
+            ));
+        }
+    }
+}
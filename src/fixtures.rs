@@ -0,0 +1,132 @@
+//! Representative positions bundled with the crate, the ones its benchmarks run over, so your
+//! own benchmarks can measure the same games
+//!
+//! ```
+//! use battlesnake_game_types::compact_representation::StandardCellBoard8Snakes19x19;
+//! use battlesnake_game_types::fixtures;
+//!
+//! for fixture in fixtures::LARGE_8_SNAKES {
+//!     let board: StandardCellBoard8Snakes19x19 = fixture.compact().unwrap();
+//! }
+//! ```
+
+use crate::compact_representation::dimensions::Dimensions;
+use crate::compact_representation::{CellNum, StandardCellBoard};
+use crate::types::{build_snake_id_map, SnakeIDMap};
+use crate::wire_representation::Game;
+use crate::Error;
+
+/// A game bundled with the crate, as the json of a /move request
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Fixture {
+    /// a short name for the position, e.g. for naming a benchmark
+    pub name: &'static str,
+    /// the json of the game
+    pub json: &'static str,
+}
+
+impl Fixture {
+    /// the wire game, see [crate::game_fixture]
+    pub fn game(&self) -> Game {
+        crate::game_fixture(self.json)
+    }
+
+    /// the snake ids the fixture's compact boards are made with, see [build_snake_id_map]
+    pub fn snake_ids(&self) -> SnakeIDMap {
+        build_snake_id_map(&self.game())
+    }
+
+    /// the fixture as a standard compact board
+    pub fn compact<T: CellNum, D: Dimensions, const BOARD_SIZE: usize, const MAX_SNAKES: usize>(
+        &self,
+    ) -> Result<StandardCellBoard<T, D, BOARD_SIZE, MAX_SNAKES>, Error> {
+        let game = self.game();
+        game.as_cell_board(&build_snake_id_map(&game))
+    }
+}
+
+/// the first turn of a 4 snake 11x11 game
+pub const START_OF_GAME: Fixture = Fixture {
+    name: "start of game",
+    json: include_str!("../fixtures/start_of_game.json"),
+};
+
+/// 4 snakes spread over an 11x11 board in the middle of a game
+pub const FOUR_SNAKES: Fixture = Fixture {
+    name: "4 snakes",
+    json: include_str!("../fixtures/4_snake_game.json"),
+};
+
+/// 2 long snakes late in an 11x11 game
+pub const LATE_STAGE: Fixture = Fixture {
+    name: "late stage",
+    json: include_str!("../fixtures/late_stage.json"),
+};
+
+/// 2 snakes contesting the middle of an 11x11 board
+pub const VORONOI_DUEL: Fixture = Fixture {
+    name: "voronoi duel",
+    json: include_str!("../fixtures/voronoi_duel.json"),
+};
+
+/// 2 snakes racing for the same food on an 11x11 board
+pub const FOOD_RACE: Fixture = Fixture {
+    name: "food race",
+    json: include_str!("../fixtures/food_race.json"),
+};
+
+/// the first turn of an 8 snake 19x19 game
+pub const LARGE_START: Fixture = Fixture {
+    name: "large start",
+    json: include_str!("../fixtures/large_start_8_snakes.json"),
+};
+
+/// 8 snakes of different lengths in the middle of a 19x19 game, with a column of hazards
+pub const LARGE_MIDGAME: Fixture = Fixture {
+    name: "large midgame",
+    json: include_str!("../fixtures/large_mid_8_snakes.json"),
+};
+
+/// the 11x11 fixtures, which fit a
+/// [StandardCellBoard4Snakes11x11](crate::compact_representation::StandardCellBoard4Snakes11x11)
+pub const STANDARD_4_SNAKES: &[Fixture] = &[START_OF_GAME, FOUR_SNAKES, LATE_STAGE];
+
+/// the 11x11 fixtures with exactly 2 snakes, which fit a
+/// [StandardDuelCellBoard11x11](crate::compact_representation::StandardDuelCellBoard11x11)
+pub const DUELS: &[Fixture] = &[LATE_STAGE, VORONOI_DUEL, FOOD_RACE];
+
+/// the 19x19 fixtures, which fit a
+/// [StandardCellBoard8Snakes19x19](crate::compact_representation::StandardCellBoard8Snakes19x19)
+pub const LARGE_8_SNAKES: &[Fixture] = &[LARGE_START, LARGE_MIDGAME];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::compact_representation::{
+        StandardCellBoard4Snakes11x11, StandardCellBoard8Snakes19x19, StandardDuelCellBoard11x11,
+    };
+    use crate::types::SnakeIDGettableGame;
+
+    #[test]
+    fn test_fixtures_convert() {
+        for fixture in STANDARD_4_SNAKES {
+            let board: StandardCellBoard4Snakes11x11 = fixture.compact().unwrap();
+            board.check_invariants().unwrap();
+            assert_eq!(
+                board.get_snake_ids().len(),
+                fixture.game().get_snake_ids().len()
+            );
+        }
+        for fixture in DUELS {
+            let board: StandardDuelCellBoard11x11 = fixture.compact().unwrap();
+            board.check_invariants().unwrap();
+            assert_eq!(board.get_snake_ids().len(), 2, "{}", fixture.name);
+        }
+        for fixture in LARGE_8_SNAKES {
+            let board: StandardCellBoard8Snakes19x19 = fixture.compact().unwrap();
+            board.check_invariants().unwrap();
+            assert_eq!(board.get_snake_ids().len(), 8, "{}", fixture.name);
+            assert_eq!(fixture.snake_ids().len(), 8);
+        }
+    }
+}
@@ -5,6 +5,9 @@ use std::error::Error;
 
 use crate::{wire_representation::{Game, Position}, types::Move};
 
+mod royale;
+pub use royale::{predict_hazard_events, BoardEdge, HazardForecast, HazardStrip, PredictedShrink};
+
 /// Represents a hazard algorithms that can only be wound forward (interface permits one turn at a time)
 pub trait ForwardOnlyHazardAlgorithm<T>: Clone + std::fmt::Debug {
     /// use this to initialize the hazard algorithm. See implementation
@@ -51,8 +54,8 @@ impl ForwardOnlyHazardAlgorithm<Position> for NoopHazard {
 pub struct SpiralHazard {
     hazard_every_turns: u8,
     seed_cell: Position,
-    first_turn_seen: u16,
-    current_turn: u16,
+    first_turn_seen: u32,
+    current_turn: u32,
     next_hazard_cell: Position,
     direction: Move,
 }
@@ -86,7 +89,7 @@ impl Default for SpiralHazard {
 // but crucially not:
 // x x 
 // x x
-fn next_perfect_odd_square(n: u16) -> u16 {
+fn next_perfect_odd_square(n: u32) -> u32 {
     // 1 -> 1
     // 2 -> 9
     // 3 -> 9
@@ -94,11 +97,11 @@ fn next_perfect_odd_square(n: u16) -> u16 {
     // what's the solution?
 
     // current square base
-    let current_base = (n as f32).sqrt().floor() as u16;
+    let current_base = (n as f64).sqrt().floor() as u32;
     // next square base
     // e.g. if the input is 2, this is 2
     let mut next_base = current_base + 1;
-    if next_base % 2 == 0 {
+    if next_base.is_multiple_of(2) {
         next_base += 1;
     }
 
@@ -108,8 +111,8 @@ fn next_perfect_odd_square(n: u16) -> u16 {
     next_base * next_base
 }
 
-fn is_perfect_odd_square(n: u16) -> bool {
-    let sqrt = (n as f32).sqrt().floor() as u16;
+fn is_perfect_odd_square(n: u32) -> bool {
+    let sqrt = (n as f64).sqrt().floor() as u32;
     sqrt * sqrt == n && sqrt % 2 == 1
 }
 
@@ -132,8 +135,8 @@ impl ForwardOnlyHazardAlgorithm<Position> for SpiralHazard {
                 // TODO: no way to detect this from the payload right now
                 self.hazard_every_turns = 3;
 
-                self.first_turn_seen = game.turn as u16;
-                self.current_turn = game.turn as u16;
+                self.first_turn_seen = game.turn.max(0) as u32;
+                self.current_turn = game.turn.max(0) as u32;
 
                 self.next_hazard_cell = self.seed_cell.add_vec(Move::Up.to_vector());
                 self.direction = Move::Right;
@@ -152,13 +155,13 @@ impl ForwardOnlyHazardAlgorithm<Position> for SpiralHazard {
     }
 
     fn inc_turn(&mut self) -> Box<dyn Iterator<Item=Position>> {
-        self.current_turn += 1;
-        if self.current_turn % self.hazard_every_turns as u16 == 0 {
+        self.current_turn = self.current_turn.saturating_add(1);
+        if self.current_turn.is_multiple_of(self.hazard_every_turns as u32) {
             let turns_elapsed = self.current_turn - self.first_turn_seen;
             // plus 1 because the seed cell
-            let spawns_elapsed = (turns_elapsed / self.hazard_every_turns as u16) + 1;
+            let spawns_elapsed = (turns_elapsed / self.hazard_every_turns as u32) + 1;
             let next_square = next_perfect_odd_square(spawns_elapsed);
-            let radius = ((next_square as f32).sqrt()/2.0).floor() as u16;
+            let radius = ((next_square as f64).sqrt()/2.0).floor() as u32;
             let result = self.next_hazard_cell;
             self.next_hazard_cell = self.next_hazard_cell.add_vec(self.direction.to_vector());
 
@@ -314,8 +317,8 @@ mod tests {
             } else {
                 let new_hazards = hazard_alg.inc_turn();
                 maintained_hazards.extend(new_hazards);
-                let hazards_set = HashSet::from_iter(game.board.hazards.into_iter());
-                assert!(hazard_alg.current_turn == game.turn as u16);
+                let hazards_set = HashSet::from_iter(game.board.hazards);
+                assert!(hazard_alg.current_turn == game.turn as u32);
                 assert!(hazards_set == maintained_hazards);
 
             }
@@ -0,0 +1,324 @@
+//! forecasting the hazards of the royale ruleset. Every `shrinkEveryNTurns` turns the engine
+//! picks one of the four edges at random and the hazard comes in one more row or column from
+//! it, so which edge is random but when it happens and what it could cover isn't
+
+use std::collections::HashSet;
+
+use crate::{
+    wire_representation::{Game, Position},
+    Error,
+};
+
+/// an edge of the board that a royale shrink can come in from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum BoardEdge {
+    /// the column with x 0
+    Left,
+    /// the column with the largest x
+    Right,
+    /// the row with y 0
+    Bottom,
+    /// the row with the largest y
+    Top,
+}
+
+impl BoardEdge {
+    /// every edge, in the order the engine numbers them
+    pub fn all() -> [BoardEdge; 4] {
+        [
+            BoardEdge::Left,
+            BoardEdge::Right,
+            BoardEdge::Bottom,
+            BoardEdge::Top,
+        ]
+    }
+}
+
+/// the squares a shrink from `edge` could make hazardous
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HazardStrip {
+    /// the edge the shrink comes in from
+    pub edge: BoardEdge,
+    /// the squares it covers, in order along the edge
+    pub positions: Vec<Position>,
+}
+
+/// a shrink that is yet to happen
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PredictedShrink {
+    /// the turn the new hazards are first on the board
+    pub turn: i32,
+    /// every strip that could be added on that turn, empty once the whole board is hazardous
+    pub candidates: Vec<HazardStrip>,
+}
+
+/// what the royale hazards will do next, see [predict_hazard_events]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HazardForecast {
+    /// the bottom left and top right corners of the squares that aren't hazardous yet, None if
+    /// every square is
+    pub safe_area: Option<(Position, Position)>,
+    /// the upcoming shrinks in the order they happen
+    pub shrinks: Vec<PredictedShrink>,
+}
+
+impl HazardForecast {
+    /// every square that could be hazardous by the end of `turn` and isn't already
+    pub fn at_risk_by(&self, turn: i32) -> HashSet<Position> {
+        self.shrinks
+            .iter()
+            .take_while(|shrink| shrink.turn <= turn)
+            .flat_map(|shrink| shrink.candidates.iter())
+            .flat_map(|strip| strip.positions.iter().copied())
+            .collect()
+    }
+}
+
+/// The next `shrinks` shrinks of a royale game. Which edges have already shrunk is worked out
+/// from the hazards, which have to be everything outside a rectangle the way the engine lays
+/// them out. The next shrink adds one of exactly four strips, the edges of that rectangle.
+/// Later shrinks depend on the random edges picked before them, so their candidates are every
+/// strip that could be added given some choice of the shrinks in between, which is more than
+/// can actually happen. Errors for games that aren't royale or have hazards laid out some
+/// other way
+pub fn predict_hazard_events(game: &Game, shrinks: usize) -> Result<HazardForecast, Error> {
+    let ruleset = &game.game.ruleset;
+    if ruleset.name != "royale" {
+        return Err(Error::UnsupportedRuleset(ruleset.name.clone()));
+    }
+    let every = ruleset
+        .settings
+        .as_ref()
+        .and_then(|settings| settings.royale)
+        .map(|royale| royale.shrink_every_n_turns)
+        .ok_or_else(|| unpredictable("the game doesn't say how often the board shrinks"))?;
+    if every < 1 {
+        return Err(unpredictable(format!(
+            "the board shrinks every {} turns",
+            every
+        )));
+    }
+
+    let safe_area = safe_area(game)?;
+    // the hazards on turn t come from t / every shrinks
+    let next = (game.turn.max(0) / every + 1) * every;
+    let shrinks = (0..shrinks)
+        .map(|k| PredictedShrink {
+            turn: next + k as i32 * every,
+            candidates: safe_area
+                .map(|area| candidates(area, k))
+                .unwrap_or_default(),
+        })
+        .collect();
+    Ok(HazardForecast { safe_area, shrinks })
+}
+
+fn unpredictable(reason: impl Into<String>) -> Error {
+    Error::UnpredictableHazards(reason.into())
+}
+
+/// the rectangle the hazards are outside of
+fn safe_area(game: &Game) -> Result<Option<(Position, Position)>, Error> {
+    let (width, height) = (game.board.width, game.board.height);
+    let mut hazards = HashSet::new();
+    for hazard in &game.board.hazards {
+        if hazard.x < 0 || hazard.x >= width || hazard.y < 0 || hazard.y >= height {
+            return Err(unpredictable(format!(
+                "there is a hazard off the board at {:?}",
+                hazard
+            )));
+        }
+        hazards.insert(*hazard);
+    }
+
+    let safe = (0..width)
+        .flat_map(|x| (0..height).map(move |y| Position::new(x, y)))
+        .filter(|p| !hazards.contains(p))
+        .collect::<Vec<_>>();
+    if safe.is_empty() {
+        return Ok(None);
+    }
+    let xs = || safe.iter().map(|p| p.x);
+    let ys = || safe.iter().map(|p| p.y);
+    let min = Position::new(xs().min().unwrap(), ys().min().unwrap());
+    let max = Position::new(xs().max().unwrap(), ys().max().unwrap());
+
+    let area = ((max.x - min.x + 1) * (max.y - min.y + 1)) as usize;
+    if safe.len() != area {
+        return Err(unpredictable(
+            "the hazards aren't everything outside a rectangle",
+        ));
+    }
+    Ok(Some((min, max)))
+}
+
+/// the strips the shrink `k` after the next one could add, which are up to `k` rows or columns in
+/// from the edges of the safe area
+fn candidates((min, max): (Position, Position), k: usize) -> Vec<HazardStrip> {
+    let mut strips = vec![];
+    for depth in 0..=k as i32 {
+        for edge in BoardEdge::all().iter().copied() {
+            let positions = match edge {
+                BoardEdge::Left if min.x + depth <= max.x => (min.y..=max.y)
+                    .map(|y| Position::new(min.x + depth, y))
+                    .collect(),
+                BoardEdge::Right if max.x - depth >= min.x => (min.y..=max.y)
+                    .map(|y| Position::new(max.x - depth, y))
+                    .collect(),
+                BoardEdge::Bottom if min.y + depth <= max.y => (min.x..=max.x)
+                    .map(|x| Position::new(x, min.y + depth))
+                    .collect(),
+                BoardEdge::Top if max.y - depth >= min.y => (min.x..=max.x)
+                    .map(|x| Position::new(x, max.y - depth))
+                    .collect(),
+                _ => continue,
+            };
+            strips.push(HazardStrip { edge, positions });
+        }
+    }
+    strips
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game_fixture;
+
+    fn column(x: i32, ys: std::ops::RangeInclusive<i32>) -> Vec<Position> {
+        ys.map(|y| Position::new(x, y)).collect()
+    }
+
+    fn row(xs: std::ops::RangeInclusive<i32>, y: i32) -> Vec<Position> {
+        xs.map(|x| Position::new(x, y)).collect()
+    }
+
+    fn strip(edge: BoardEdge, positions: Vec<Position>) -> HazardStrip {
+        HazardStrip { edge, positions }
+    }
+
+    #[test]
+    fn test_before_shrinking() {
+        let g = game_fixture(include_str!("../../fixtures/this_one_crashed.json"));
+        let forecast = predict_hazard_events(&g, 2).unwrap();
+        assert_eq!(
+            forecast.safe_area,
+            Some((Position::new(0, 0), Position::new(10, 10)))
+        );
+        assert_eq!(forecast.shrinks[0].turn, 25);
+        assert_eq!(
+            forecast.shrinks[0].candidates,
+            vec![
+                strip(BoardEdge::Left, column(0, 0..=10)),
+                strip(BoardEdge::Right, column(10, 0..=10)),
+                strip(BoardEdge::Bottom, row(0..=10, 0)),
+                strip(BoardEdge::Top, row(0..=10, 10)),
+            ]
+        );
+        // the second shrink could come in from the same edge as the first
+        assert_eq!(forecast.shrinks[1].turn, 50);
+        assert_eq!(forecast.shrinks[1].candidates.len(), 8);
+        assert!(forecast.shrinks[1]
+            .candidates
+            .contains(&strip(BoardEdge::Left, column(1, 0..=10))));
+    }
+
+    #[test]
+    fn test_after_two_shrinks() {
+        // shrunk from the top and the bottom
+        let g = game_fixture(include_str!("../../fixtures/another_crash.json"));
+        let forecast = predict_hazard_events(&g, 1).unwrap();
+        assert_eq!(
+            forecast.safe_area,
+            Some((Position::new(0, 1), Position::new(10, 9)))
+        );
+        assert_eq!(forecast.shrinks.len(), 1);
+        assert_eq!(forecast.shrinks[0].turn, 75);
+        assert_eq!(
+            forecast.shrinks[0].candidates,
+            vec![
+                strip(BoardEdge::Left, column(0, 1..=9)),
+                strip(BoardEdge::Right, column(10, 1..=9)),
+                strip(BoardEdge::Bottom, row(0..=10, 1)),
+                strip(BoardEdge::Top, row(0..=10, 9)),
+            ]
+        );
+
+        assert!(forecast.at_risk_by(74).is_empty());
+        let at_risk = forecast.at_risk_by(75);
+        assert!(at_risk.contains(&Position::new(5, 1)));
+        assert!(!at_risk.contains(&Position::new(5, 0)));
+        assert!(!at_risk.contains(&Position::new(5, 5)));
+    }
+
+    #[test]
+    fn test_late_in_the_game() {
+        let g = game_fixture(include_str!("../../fixtures/late_stage.json"));
+        let forecast = predict_hazard_events(&g, 3).unwrap();
+        let (min, max) = forecast.safe_area.unwrap();
+        assert_eq!((min, max), (Position::new(1, 2), Position::new(9, 8)));
+        assert_eq!(
+            forecast.shrinks.iter().map(|s| s.turn).collect::<Vec<_>>(),
+            vec![175, 200, 225]
+        );
+
+        // every order the engine could pick edges in only ever adds squares forecast for
+        // that shrink
+        for picks in 0..4u32.pow(3) {
+            let (mut low, mut high) = (min, max);
+            for (k, shrink) in forecast.shrinks.iter().enumerate() {
+                let before = (low, high);
+                match BoardEdge::all()[(picks / 4u32.pow(k as u32) % 4) as usize] {
+                    BoardEdge::Left => low.x += 1,
+                    BoardEdge::Right => high.x -= 1,
+                    BoardEdge::Bottom => low.y += 1,
+                    BoardEdge::Top => high.y -= 1,
+                }
+                let inside = |(low, high): (Position, Position), p: &Position| {
+                    (low.x..=high.x).contains(&p.x) && (low.y..=high.y).contains(&p.y)
+                };
+                let added = (0..11)
+                    .flat_map(|x| (0..11).map(move |y| Position::new(x, y)))
+                    .filter(|p| inside(before, p) && !inside((low, high), p))
+                    .collect::<Vec<_>>();
+                assert!(shrink
+                    .candidates
+                    .iter()
+                    .any(|strip| added.iter().all(|p| strip.positions.contains(p))));
+            }
+        }
+    }
+
+    #[test]
+    fn test_fully_hazardous_board() {
+        let mut g = game_fixture(include_str!("../../fixtures/late_stage.json"));
+        g.board.hazards = (0..11)
+            .flat_map(|x| (0..11).map(move |y| Position::new(x, y)))
+            .collect();
+        let forecast = predict_hazard_events(&g, 2).unwrap();
+        assert_eq!(forecast.safe_area, None);
+        assert!(forecast.shrinks.iter().all(|s| s.candidates.is_empty()));
+    }
+
+    #[test]
+    fn test_unpredictable_hazards() {
+        let standard = game_fixture(include_str!("../../fixtures/voronoi_duel.json"));
+        assert!(matches!(
+            predict_hazard_events(&standard, 1),
+            Err(Error::UnsupportedRuleset(_))
+        ));
+
+        let mut holey = game_fixture(include_str!("../../fixtures/late_stage.json"));
+        holey.board.hazards.retain(|p| *p != Position::new(0, 4));
+        assert!(matches!(
+            predict_hazard_events(&holey, 1),
+            Err(Error::UnpredictableHazards(_))
+        ));
+
+        // captured before the engine sent settings
+        let unsettled = game_fixture(include_str!("../../fixtures/4_snake_game.json"));
+        assert!(matches!(
+            predict_hazard_events(&unsettled, 1),
+            Err(Error::UnpredictableHazards(_))
+        ));
+    }
+}
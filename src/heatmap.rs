@@ -0,0 +1,146 @@
+//! Per square values laid out as the board is drawn, for rendering as a heatmap. A [Grid]
+//! serializes as a JSON array of rows, the top row first
+
+use serde::{Serialize, Serializer};
+
+use crate::{
+    types::{SizeDeterminableGame, VoronoiGame, VoronoiOptions},
+    wire_representation::Position,
+};
+
+/// A value for every square of a board
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Grid<T> {
+    width: u32,
+    height: u32,
+    // a row at a time from the top row down, the order of SizeDeterminableGame::all_positions
+    cells: Vec<T>,
+}
+
+impl<T> Grid<T> {
+    /// a `width` by `height` grid with `value(position)` on every square
+    pub fn from_fn(width: u32, height: u32, mut value: impl FnMut(Position) -> T) -> Self {
+        let cells = (0..height as i32)
+            .flat_map(|row| {
+                (0..width as i32).map(move |col| Position::from_row_col(row, col, height as i32))
+            })
+            .map(&mut value)
+            .collect();
+        Grid {
+            width,
+            height,
+            cells,
+        }
+    }
+
+    #[allow(missing_docs)]
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    #[allow(missing_docs)]
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    /// the value on the square at `x`, `y`, None if it is off the grid
+    pub fn get(&self, x: i32, y: i32) -> Option<&T> {
+        if x < 0 || y < 0 || x >= self.width as i32 || y >= self.height as i32 {
+            return None;
+        }
+        let (row, col) = Position::new(x, y).to_row_col(self.height as i32);
+        self.cells
+            .get(row as usize * self.width as usize + col as usize)
+    }
+
+    /// the rows, from the top row down, each left to right
+    pub fn rows(&self) -> impl Iterator<Item = &[T]> {
+        self.cells.chunks(self.width.max(1) as usize)
+    }
+}
+
+impl<T: Serialize> Serialize for Grid<T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(self.rows())
+    }
+}
+
+/// `score` for every square of `game`
+pub fn heatmap<G: SizeDeterminableGame>(game: &G, score: impl FnMut(Position) -> f32) -> Grid<f32> {
+    Grid::from_fn(game.get_width(), game.get_height(), score)
+}
+
+/// the snake controlling each square of `game` and the turn it gets there, the same as
+/// [VoronoiGame::voronoi_owners]
+pub fn control_grid<G: SizeDeterminableGame + VoronoiGame>(
+    game: &G,
+    options: VoronoiOptions,
+) -> Grid<Option<(G::SnakeIDType, u32)>> {
+    let mut owners = game.voronoi_owners(options);
+    Grid::from_fn(game.get_width(), game.get_height(), |pos| {
+        owners.remove(&game.native_from_position(pos))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        compact_representation::standard::CellBoard4Snakes11x11,
+        game_fixture,
+        types::{build_snake_id_map, SnakeId},
+    };
+
+    #[test]
+    fn test_heatmap() {
+        let g = game_fixture(include_str!("../fixtures/start_of_game.json"));
+        let grid = heatmap(&g, |pos| (pos.x * 10 + pos.y) as f32);
+        assert_eq!((grid.width(), grid.height()), (11, 11));
+        assert_eq!(grid.rows().count(), 11);
+        assert!(grid.rows().all(|row| row.len() == 11));
+        assert_eq!(grid.get(3, 7), Some(&37.0));
+        assert_eq!(grid.get(11, 0), None);
+        assert_eq!(grid.rows().next().unwrap()[0], 10.0);
+
+        let small = Grid::from_fn(2, 2, |pos| pos.x + 2 * pos.y);
+        assert_eq!(serde_json::to_string(&small).unwrap(), "[[2,3],[0,1]]");
+    }
+
+    #[test]
+    fn test_control_grid() {
+        let g = game_fixture(include_str!("../fixtures/start_of_game.json"));
+        let grid = control_grid(&g, VoronoiOptions::default());
+        assert_eq!((grid.width(), grid.height()), (11, 11));
+
+        // every snake's head is its own, and the squares it controls add up to its voronoi count
+        for snake in &g.board.snakes {
+            let head = grid.get(snake.head.x, snake.head.y).unwrap();
+            assert_eq!(head, &Some((snake.id.clone(), 0)));
+        }
+        let voronoi = g.voronoi(VoronoiOptions::default());
+        for (id, count) in &voronoi {
+            let controlled = grid
+                .rows()
+                .flatten()
+                .filter(|owner| matches!(owner, Some((owner, turn)) if owner == id && *turn > 0))
+                .count();
+            assert_eq!(controlled, *count);
+        }
+
+        // the compact board agrees
+        let snake_ids = build_snake_id_map(&g);
+        let compact: CellBoard4Snakes11x11 = g.as_cell_board(&snake_ids).unwrap();
+        let compact_grid = control_grid(&compact, VoronoiOptions::default());
+        for (wire, compact) in grid.rows().flatten().zip(compact_grid.rows().flatten()) {
+            let wire = wire.as_ref().map(|(id, turn)| (snake_ids[id], *turn));
+            assert_eq!(wire, *compact);
+        }
+        let you = g.you.head;
+        assert_eq!(compact_grid.get(you.x, you.y), Some(&Some((SnakeId(0), 0))));
+        let below = compact_grid.get(you.x, you.y - 1).unwrap();
+        assert_eq!(below.map(|(_, turn)| turn), Some(1));
+        // the neck is stacked, so it takes until the whole tail leaves
+        let neck = compact_grid.get(you.x, you.y + 1).unwrap();
+        assert_eq!(neck.map(|(_, turn)| turn), Some(3));
+    }
+}
@@ -28,16 +28,74 @@
 //! vec late stage          time:   [21.124 us 21.337 us 21.592 us]
 //! Found 14 outliers among 100 measurements (14.00%)
 //! ```
+//!
+//! Positions follow the battlesnake convention of `(0, 0)` at the bottom left with `y`
+//! increasing upward, in every representation, see
+//! [Position] for converting to rows counted from the top.
+//!
+//! The [prelude] brings every game trait in to scope along with the types most snakes use.
+//!
+//! [types], [compact_representation] and simulating compact boards build with
+//! `default-features = false, features = ["alloc"]`, the wire representation and everything
+//! else is behind the default `std` feature.
+#![cfg_attr(not(feature = "std"), no_std)]
+// the docs link to the std only parts of the crate
+#![cfg_attr(not(feature = "std"), allow(rustdoc::broken_intra_doc_links))]
+
+extern crate alloc;
 
-use wire_representation::Game;
+#[cfg(not(feature = "alloc"))]
+compile_error!("battlesnake-game-types needs at least the `alloc` feature");
 
 pub mod compact_representation;
+pub mod core_types;
+#[cfg(feature = "std")]
+pub mod dynamic;
+mod error;
+#[cfg(feature = "std")]
+pub mod fixtures;
+#[cfg(feature = "std")]
+pub mod heatmap;
+#[cfg(all(feature = "std", any(test, feature = "algorithms")))]
+pub mod minimax;
+#[cfg(feature = "std")]
+pub mod opening;
+#[cfg(feature = "std")]
+pub mod path_key;
+#[cfg(feature = "std")]
+pub mod playout;
+#[cfg(feature = "std")]
+pub mod prelude;
+pub mod pruning;
+#[cfg(feature = "std")]
+pub mod ranking;
+#[cfg(feature = "std")]
+pub mod safety;
+#[cfg(all(feature = "std", any(test, feature = "recording")))]
+pub mod recording;
+#[cfg(all(feature = "std", any(test, feature = "proptest")))]
+pub mod strategies;
 pub mod types;
+#[cfg(feature = "std")]
 pub mod wire_representation;
+#[cfg(feature = "std")]
 pub mod hazard_algorithms;
 
-/// Loads a fixture from a given string
+pub use core_types::{Move, Position, SnakeId, Vector};
+pub use error::{Error, ValidationWarning};
+#[cfg(feature = "std")]
+pub use types::SnakeIDMap;
+#[cfg(feature = "std")]
+pub use wire_representation::Game;
+
+/// Loads a fixture from a given string, panics if it isn't a valid game, see [try_game_fixture]
+#[cfg(feature = "std")]
 pub fn game_fixture(game_fixture: &str) -> Game {
-    let g: Result<Game, _> = serde_json::from_str(game_fixture);
-    g.expect("the json literal is valid")
+    try_game_fixture(game_fixture).expect("the json literal is valid")
+}
+
+/// Loads a game from a given json string
+#[cfg(feature = "std")]
+pub fn try_game_fixture(game_fixture: &str) -> Result<Game, Error> {
+    Ok(serde_json::from_str(game_fixture)?)
 }
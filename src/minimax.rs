@@ -0,0 +1,315 @@
+//! A small paranoid minimax with alpha-beta pruning, for sanity checking evaluation functions
+//! and as an example of how the game traits fit together. It isn't meant to be competitive:
+//! every other snake is assumed to be playing against you together, and nothing is cached
+//! between searches. Needs the `algorithms` feature
+//!
+//! ```
+//! # use battlesnake_game_types::compact_representation::StandardCellBoard4Snakes11x11;
+//! # use battlesnake_game_types::minimax::minimax;
+//! # use battlesnake_game_types::types::*;
+//! # #[derive(Debug)]
+//! # struct Instruments;
+//! # impl SimulatorInstruments for Instruments {
+//! #     fn observe_simulation(&self, _: std::time::Duration) {}
+//! # }
+//! # let game = battlesnake_game_types::game_fixture(include_str!("../fixtures/late_stage.json"));
+//! let board: StandardCellBoard4Snakes11x11 =
+//!     game.as_cell_board(&build_snake_id_map(&game)).unwrap();
+//! let you = *board.you_id();
+//! let (mv, score) = minimax(&board, 2, |b| b.get_length_i64(&you), &Instruments);
+//! ```
+
+use crate::types::{
+    GameResult, HealthGettableGame, Move, SimulableGame, SimulatorInstruments,
+    VictorDeterminableGame, YouDeterminableGame,
+};
+#[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+use crate::wire_representation::TimeBudget;
+
+/// the score of a game you have won, less the number of turns it took to win so that sooner
+/// wins score higher. Evaluations should stay well inside [LOSS] and [WIN]
+pub const WIN: i64 = i64::MAX / 2;
+
+/// the score of a game you have lost, plus the number of turns it took to lose so that later
+/// losses score higher
+pub const LOSS: i64 = -WIN;
+
+/// the score of a game every snake was eliminated from at once
+pub const DRAW: i64 = 0;
+
+/// Searches `depth` turns ahead, at least 1, and returns your best move with its score. At each
+/// turn you pick the move with the best worst case over every combination of the other snakes'
+/// moves, so every other snake is assumed to be against you. Games that end are scored [WIN],
+/// [LOSS] or [DRAW], and the ones still going after `depth` turns by `eval`, from your point of
+/// view. A game only you are in has no one to beat, so it only ends when you are eliminated.
+/// If `game` is already over the move is [Move::Up], with the score of how it ended
+pub fn minimax<G, I, const N_SNAKES: usize>(
+    game: &G,
+    depth: usize,
+    eval: impl Fn(&G) -> i64,
+    instruments: &I,
+) -> (Move, i64)
+where
+    G: SimulableGame<I, N_SNAKES>
+        + VictorDeterminableGame
+        + YouDeterminableGame
+        + HealthGettableGame,
+    I: SimulatorInstruments,
+{
+    Search::new(game, &eval, instruments, &|| false)
+        .root(game, depth.max(1))
+        .expect("a search without a deadline always finishes")
+}
+
+/// [minimax] one turn deeper at a time, up to `max_depth`, until `budget` runs out, returning
+/// the result of the deepest search that finished. The first turn is always searched, however
+/// little time is left. Stops early once a win or a loss is certain. How deep it gets depends on
+/// how fast the machine is, so unlike [minimax] the same game can give a different move
+#[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+pub fn minimax_with_budget<G, I, const N_SNAKES: usize>(
+    game: &G,
+    max_depth: usize,
+    eval: impl Fn(&G) -> i64,
+    instruments: &I,
+    budget: &TimeBudget,
+) -> (Move, i64)
+where
+    G: SimulableGame<I, N_SNAKES>
+        + VictorDeterminableGame
+        + YouDeterminableGame
+        + HealthGettableGame,
+    I: SimulatorInstruments,
+{
+    let mut best = minimax(game, 1, &eval, instruments);
+    let out_of_time = || budget.remaining().as_nanos() == 0;
+    let search = Search::new(game, &eval, instruments, &out_of_time);
+    for depth in 2..=max_depth {
+        if is_decided(best.1) {
+            break;
+        }
+        match search.root(game, depth) {
+            Some(result) => best = result,
+            None => break,
+        }
+    }
+    best
+}
+
+/// whether `score` is a win or a loss rather than an evaluation
+fn is_decided(score: i64) -> bool {
+    score.abs() >= WIN / 2
+}
+
+struct Search<'a, G, I, E, const N_SNAKES: usize> {
+    eval: &'a E,
+    instruments: &'a I,
+    out_of_time: &'a dyn Fn() -> bool,
+    solo: bool,
+    _game: std::marker::PhantomData<G>,
+}
+
+impl<'a, G, I, E, const N_SNAKES: usize> Search<'a, G, I, E, N_SNAKES>
+where
+    G: SimulableGame<I, N_SNAKES>
+        + VictorDeterminableGame
+        + YouDeterminableGame
+        + HealthGettableGame,
+    I: SimulatorInstruments,
+    E: Fn(&G) -> i64,
+{
+    fn new(game: &G, eval: &'a E, instruments: &'a I, out_of_time: &'a dyn Fn() -> bool) -> Self {
+        Search {
+            eval,
+            instruments,
+            out_of_time,
+            solo: game.get_snake_ids().len() == 1,
+            _game: std::marker::PhantomData,
+        }
+    }
+
+    /// your best move and its score, None if time ran out
+    fn root(&self, game: &G, depth: usize) -> Option<(Move, i64)> {
+        if let Some(score) = self.ended(game, 0) {
+            return Some((Move::Up, score));
+        }
+        let mut best = (Move::Up, i64::MIN);
+        for mv in Move::all_iter() {
+            let score = self.worst_reply(game, mv, depth, 0, best.1, i64::MAX)?;
+            if score > best.1 {
+                best = (mv, score);
+            }
+        }
+        Some(best)
+    }
+
+    /// the score of `game` with `depth` turns left to search and `ply` turns already searched,
+    /// for your best move. Once it is known to be at least `beta` the rest of your moves aren't
+    /// searched
+    fn best_move(
+        &self,
+        game: &G,
+        depth: usize,
+        ply: i64,
+        mut alpha: i64,
+        beta: i64,
+    ) -> Option<i64> {
+        if let Some(score) = self.ended(game, ply) {
+            return Some(score);
+        }
+        if depth == 0 {
+            return Some((self.eval)(game));
+        }
+        if (self.out_of_time)() {
+            return None;
+        }
+        let mut best = i64::MIN;
+        for mv in Move::all_iter() {
+            best = best.max(self.worst_reply(game, mv, depth, ply, alpha, beta)?);
+            alpha = alpha.max(best);
+            if alpha >= beta {
+                break;
+            }
+        }
+        Some(best)
+    }
+
+    /// the score of you making `mv` against the other snakes' worst combination of moves for
+    /// you. Once it is known to be at most `alpha` the rest of their moves aren't searched
+    fn worst_reply(
+        &self,
+        game: &G,
+        mv: Move,
+        depth: usize,
+        ply: i64,
+        alpha: i64,
+        mut beta: i64,
+    ) -> Option<i64> {
+        let you = game.you_id();
+        let yours = [mv];
+        let all = Move::all();
+        let moves = game.get_snake_ids().into_iter().map(|sid| {
+            let moves = if sid == *you { &yours[..] } else { &all[..] };
+            (sid, moves)
+        });
+        let mut worst = i64::MAX;
+        for (_, next) in game.simulate_with_moves(self.instruments, moves) {
+            worst = worst.min(self.best_move(&next, depth - 1, ply + 1, alpha, beta)?);
+            beta = beta.min(worst);
+            if worst <= alpha {
+                break;
+            }
+        }
+        Some(worst)
+    }
+
+    /// the score of `game` if it has ended for you, `ply` turns in to the search
+    fn ended(&self, game: &G, ply: i64) -> Option<i64> {
+        let you = game.you_id();
+        if !self.solo {
+            match game.game_result() {
+                GameResult::Draw => return Some(DRAW),
+                GameResult::Winner(winner) if winner == *you => return Some(WIN - ply),
+                _ => {}
+            }
+        }
+        if !game.is_alive(you) {
+            return Some(LOSS + ply);
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::compact_representation::StandardCellBoard4Snakes11x11;
+    use crate::types::{build_snake_id_map, LengthGettableGame};
+
+    #[derive(Debug)]
+    struct Instruments;
+    impl SimulatorInstruments for Instruments {
+        fn observe_simulation(&self, _: std::time::Duration) {}
+    }
+
+    fn board(fixture: &str) -> StandardCellBoard4Snakes11x11 {
+        let game = crate::game_fixture(fixture);
+        game.as_cell_board(&build_snake_id_map(&game)).unwrap()
+    }
+
+    /// longer is better, and health breaks ties
+    fn eval(board: &StandardCellBoard4Snakes11x11) -> i64 {
+        let you = board.you_id();
+        board.get_length_i64(you) * 100 + board.get_health_i64(you)
+    }
+
+    #[test]
+    fn test_mate_in_1() {
+        // the other snake is cornered with one square to go to, and you are longer
+        let board = board(include_str!("../fixtures/minimax_mate_in_1.json"));
+        for depth in 1..=3 {
+            assert_eq!(
+                minimax(&board, depth, eval, &Instruments),
+                (Move::Up, WIN - 1),
+                "depth {}",
+                depth
+            );
+        }
+    }
+
+    #[test]
+    fn test_avoids_trap() {
+        // the food to your right can be taken head on by the longer snake
+        let board = board(include_str!("../fixtures/minimax_trap.json"));
+        assert_eq!(
+            minimax(&board, 0, eval, &Instruments),
+            minimax(&board, 1, eval, &Instruments)
+        );
+        for depth in 1..=3 {
+            let (mv, score) = minimax(&board, depth, eval, &Instruments);
+            assert_ne!(mv, Move::Right, "depth {}", depth);
+            assert_ne!(mv, Move::Down, "depth {}", depth);
+            assert!(!is_decided(score), "depth {}: {}", depth, score);
+        }
+
+        // without the other snake around the food is the best move
+        let mut alone = crate::game_fixture(include_str!("../fixtures/minimax_trap.json"));
+        alone.board.snakes.pop();
+        let alone: StandardCellBoard4Snakes11x11 =
+            alone.as_cell_board(&build_snake_id_map(&alone)).unwrap();
+        assert_eq!(minimax(&alone, 1, eval, &Instruments).0, Move::Right);
+
+        // the cornered snake can't avoid losing the head to head
+        let mut cornered = crate::game_fixture(include_str!("../fixtures/minimax_mate_in_1.json"));
+        cornered.you = cornered.board.snakes[1].clone();
+        let cornered: StandardCellBoard4Snakes11x11 = cornered
+            .as_cell_board(&build_snake_id_map(&cornered))
+            .unwrap();
+        assert_eq!(minimax(&cornered, 2, eval, &Instruments).1, LOSS + 1);
+    }
+
+    #[test]
+    #[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+    fn test_minimax_with_budget() {
+        use std::time::Duration;
+
+        let board = board(include_str!("../fixtures/minimax_trap.json"));
+        let plenty = TimeBudget::new(Duration::from_secs(60), None, Duration::ZERO);
+        assert_eq!(
+            minimax_with_budget(&board, 3, eval, &Instruments, &plenty),
+            minimax(&board, 3, eval, &Instruments)
+        );
+        // with no time the first turn is still searched
+        let none = TimeBudget::new(Duration::ZERO, None, Duration::ZERO);
+        assert_eq!(
+            minimax_with_budget(&board, 3, eval, &Instruments, &none),
+            minimax(&board, 1, eval, &Instruments)
+        );
+
+        // a certain win isn't searched any deeper
+        let mate = self::board(include_str!("../fixtures/minimax_mate_in_1.json"));
+        assert_eq!(
+            minimax_with_budget(&mate, 10, eval, &Instruments, &plenty),
+            (Move::Up, WIN - 1)
+        );
+    }
+}
@@ -0,0 +1,128 @@
+//! Helpers for the first few turns of a game. Every snake starts stacked on its spawn at the
+//! standard length, and the engine places a food diagonally next to each spawn. A snake's tail
+//! stays on its spawn until it has moved away as many times as it is long, so while
+//! `turn < length` the spawn, and the food placed for it, can still be found from the board
+
+use crate::{
+    types::{
+        FoodGettableGame, HeadGettableGame, LengthGettableGame, SnakeBodyGettableGame,
+        TurnDeterminableGame,
+    },
+    wire_representation::Position,
+};
+
+/// the length every snake starts the game at
+pub const STARTING_LENGTH: i64 = 3;
+
+/// how many turns the opening lasts, on turn 3 a snake that hasn't eaten has left its spawn
+pub const OPENING_TURNS: u64 = 3;
+
+/// whether `game` is still in its opening: before [OPENING_TURNS], with no snake longer than
+/// it could have grown to from [STARTING_LENGTH] by eating every turn. Custom games that start
+/// snakes longer are never in an opening
+pub fn is_opening<G: TurnDeterminableGame + LengthGettableGame>(game: &G) -> bool {
+    let turn = game.turn();
+    turn < OPENING_TURNS
+        && game
+            .get_snake_ids()
+            .iter()
+            .all(|id| game.get_length_i64(id) <= STARTING_LENGTH + turn as i64)
+}
+
+/// the square snake `id` spawned on, if its tail is still on it
+pub fn spawn_position<G: TurnDeterminableGame + SnakeBodyGettableGame>(
+    game: &G,
+    id: &G::SnakeIDType,
+) -> Option<Position> {
+    let body = game.get_snake_body_vec(id);
+    if game.turn() >= body.len() as u64 {
+        return None;
+    }
+    body.last()
+        .map(|tail| game.position_from_native(tail.clone()))
+}
+
+/// the food the engine placed for snake `id` at the start of the game, diagonally next to its
+/// spawn. None once the spawn can't be found any more, see [spawn_position], or the food has
+/// been eaten
+pub fn spawn_food_for<G>(game: &G, id: &G::SnakeIDType) -> Option<Position>
+where
+    G: TurnDeterminableGame + SnakeBodyGettableGame + FoodGettableGame,
+{
+    let spawn = spawn_position(game, id)?;
+    let food = game.get_all_food_as_positions();
+    [(-1, -1), (-1, 1), (1, -1), (1, 1)]
+        .iter()
+        .map(|(x, y)| Position::new(spawn.x + x, spawn.y + y))
+        .find(|diagonal| food.contains(diagonal))
+}
+
+/// how many moves snake `id`'s head is from its [spawn_food_for], ignoring anything in the way
+/// and the edges of wrapped boards
+pub fn distance_to_own_spawn_food<G>(game: &G, id: &G::SnakeIDType) -> Option<u32>
+where
+    G: TurnDeterminableGame + SnakeBodyGettableGame + FoodGettableGame + HeadGettableGame,
+{
+    let food = spawn_food_for(game, id)?;
+    let head = game.get_head_as_position(id);
+    Some(head.sub_vec(food.to_vector()).manhattan_length())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{game_fixture, types::SnakeIDGettableGame};
+
+    #[test]
+    fn test_turn_0() {
+        let g = game_fixture(include_str!("../fixtures/opening_turn_0.json"));
+        assert!(is_opening(&g));
+
+        let expected = [
+            ((9, 1), (8, 2)),
+            ((5, 1), (6, 0)),
+            ((1, 1), (2, 2)),
+            ((1, 9), (2, 10)),
+        ];
+        for (id, (spawn, food)) in g.get_snake_ids().iter().zip(expected.iter()) {
+            assert_eq!(
+                spawn_position(&g, id),
+                Some(Position::new(spawn.0, spawn.1))
+            );
+            assert_eq!(spawn_food_for(&g, id), Some(Position::new(food.0, food.1)));
+            assert_eq!(distance_to_own_spawn_food(&g, id), Some(2));
+        }
+
+        let mut custom = game_fixture(include_str!("../fixtures/goes_for_food.json"));
+        assert!(!is_opening(&custom));
+        custom.board.snakes[0].body.truncate(3);
+        assert!(is_opening(&custom));
+    }
+
+    #[test]
+    fn test_turn_3() {
+        let g = game_fixture(include_str!("../fixtures/opening_turn_3.json"));
+        assert!(!is_opening(&g));
+
+        let ids = g.get_snake_ids();
+        // the first and third snakes ate their spawn food on turn 2, so their tails are still on
+        // their spawns, the others have moved off theirs
+        assert_eq!(spawn_position(&g, &ids[0]), Some(Position::new(9, 1)));
+        assert_eq!(spawn_position(&g, &ids[1]), None);
+        assert_eq!(spawn_position(&g, &ids[2]), Some(Position::new(1, 1)));
+        assert_eq!(spawn_position(&g, &ids[3]), None);
+        for id in &ids {
+            assert_eq!(spawn_food_for(&g, id), None);
+            assert_eq!(distance_to_own_spawn_food(&g, id), None);
+        }
+
+        // a spawn that is still under a tail can still have food next to it
+        let mut respawned = g.clone();
+        respawned.board.food.insert(Position::new(10, 2));
+        assert_eq!(
+            spawn_food_for(&respawned, &ids[0]),
+            Some(Position::new(10, 2))
+        );
+        assert_eq!(distance_to_own_spawn_food(&respawned, &ids[0]), Some(3));
+    }
+}
@@ -0,0 +1,368 @@
+//! Short move histories packed in to a u64, for keying tables like killer moves or the history
+//! heuristic by the last few plies of a search.
+//!
+//! The layout is stable, so keys can be stored and compared across versions. Entries are
+//! packed from the least significant bit, oldest first, with entry `i` at bits
+//! `i * width..(i + 1) * width`. The number of entries is in the top six bits, 58 to 63, so
+//! sequences of different lengths never share a key, and every bit between the entries and the
+//! length is zero. A move is two bits, its [Move::as_index]. [PathKey] packs just moves, 29 of
+//! them, and [JointPathKey] packs a snake id in the low four bits of an entry and the move in the
+//! next two, 9 of them, for snake ids below 16.
+
+use crate::{
+    types::{Move, SnakeId, SnakeMove},
+    Error,
+};
+
+const LEN_SHIFT: u32 = 58;
+const MOVE_BITS: u32 = 2;
+const SNAKE_ID_BITS: u32 = 4;
+
+/// the entries, which are `WIDTH` bits each, and their count packed the way the module docs say
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, PartialOrd, Ord)]
+struct Packed<const WIDTH: u32>(u64);
+
+impl<const WIDTH: u32> Packed<WIDTH> {
+    const CAPACITY: usize = (LEN_SHIFT / WIDTH) as usize;
+    const MASK: u64 = (1 << WIDTH) - 1;
+
+    fn from_u64(raw: u64) -> Result<Self, Error> {
+        let packed = Packed(raw);
+        if packed.len() > Self::CAPACITY {
+            return Err(Error::InvalidPathKey(format!(
+                "{} entries is more than the {} that fit",
+                packed.len(),
+                Self::CAPACITY
+            )));
+        }
+        if (raw & ((1 << LEN_SHIFT) - 1)) >> (packed.len() as u32 * WIDTH) != 0 {
+            return Err(Error::InvalidPathKey(
+                "there are bits set after the last entry".to_string(),
+            ));
+        }
+        Ok(packed)
+    }
+
+    fn len(&self) -> usize {
+        (self.0 >> LEN_SHIFT) as usize
+    }
+
+    fn get(&self, i: usize) -> Option<u64> {
+        if i < self.len() {
+            Some((self.0 >> (i as u32 * WIDTH)) & Self::MASK)
+        } else {
+            None
+        }
+    }
+
+    fn set_len(&mut self, len: usize) {
+        self.0 = (self.0 & ((1 << LEN_SHIFT) - 1)) | ((len as u64) << LEN_SHIFT);
+    }
+
+    fn push(&mut self, entry: u64) -> Result<(), Error> {
+        let len = self.len();
+        if len == Self::CAPACITY {
+            return Err(Error::PathKeyFull(Self::CAPACITY));
+        }
+        self.0 |= entry << (len as u32 * WIDTH);
+        self.set_len(len + 1);
+        Ok(())
+    }
+
+    fn push_dropping_oldest(&mut self, entry: u64) {
+        if self.len() == Self::CAPACITY {
+            let len = self.len();
+            self.0 = (self.0 & ((1 << LEN_SHIFT) - 1)) >> WIDTH;
+            self.set_len(len - 1);
+        }
+        self.push(entry)
+            .expect("there is room after dropping the oldest");
+    }
+
+    fn pop(&mut self) -> Option<u64> {
+        let last = self.len().checked_sub(1)?;
+        let entry = self.get(last)?;
+        self.0 &= !(Self::MASK << (last as u32 * WIDTH));
+        self.set_len(last);
+        Some(entry)
+    }
+}
+
+/// Up to [PathKey::CAPACITY] moves packed in to a u64, oldest first, see the
+/// [module docs](self) for the layout
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, PartialOrd, Ord)]
+pub struct PathKey(Packed<MOVE_BITS>);
+
+impl PathKey {
+    /// the most moves a key can hold
+    pub const CAPACITY: usize = Packed::<MOVE_BITS>::CAPACITY;
+
+    /// a key with no moves
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// reads a key back from [PathKey::as_u64], erroring if it isn't laid out like one
+    pub fn from_u64(raw: u64) -> Result<Self, Error> {
+        Packed::from_u64(raw).map(PathKey)
+    }
+
+    /// the packed key
+    pub fn as_u64(&self) -> u64 {
+        (self.0).0
+    }
+
+    /// how many moves are in the key
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    /// whether there are no moves in the key
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// the `i`th move, oldest first
+    pub fn get(&self, i: usize) -> Option<Move> {
+        self.0.get(i).map(|entry| Move::from_index(entry as usize))
+    }
+
+    /// adds `mv` after the other moves, erroring and leaving the key as it was if it is full
+    pub fn push(&mut self, mv: Move) -> Result<(), Error> {
+        self.0.push(mv.as_index() as u64)
+    }
+
+    /// adds `mv` after the other moves, dropping the oldest move if the key is full, so the key
+    /// is always the last [PathKey::CAPACITY] moves
+    pub fn push_dropping_oldest(&mut self, mv: Move) {
+        self.0.push_dropping_oldest(mv.as_index() as u64)
+    }
+
+    /// removes the newest move
+    pub fn pop(&mut self) -> Option<Move> {
+        self.0.pop().map(|entry| Move::from_index(entry as usize))
+    }
+
+    /// the moves, oldest first
+    pub fn iter(&self) -> impl Iterator<Item = Move> {
+        let key = *self;
+        (0..key.len()).filter_map(move |i| key.get(i))
+    }
+}
+
+/// Up to [JointPathKey::CAPACITY] moves of snakes with ids below 16 packed in to a u64, oldest
+/// first, see the [module docs](self) for the layout
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, PartialOrd, Ord)]
+pub struct JointPathKey(Packed<{ SNAKE_ID_BITS + MOVE_BITS }>);
+
+impl JointPathKey {
+    /// the most moves a key can hold
+    pub const CAPACITY: usize = Packed::<{ SNAKE_ID_BITS + MOVE_BITS }>::CAPACITY;
+
+    /// a key with no moves
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// reads a key back from [JointPathKey::as_u64], erroring if it isn't laid out like one
+    pub fn from_u64(raw: u64) -> Result<Self, Error> {
+        Packed::from_u64(raw).map(JointPathKey)
+    }
+
+    /// the packed key
+    pub fn as_u64(&self) -> u64 {
+        (self.0).0
+    }
+
+    /// how many moves are in the key
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    /// whether there are no moves in the key
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// the `i`th snake and its move, oldest first
+    pub fn get(&self, i: usize) -> Option<SnakeMove<SnakeId>> {
+        self.0.get(i).map(Self::unpack)
+    }
+
+    /// adds the move after the other moves, erroring and leaving the key as it was if it is
+    /// full or the snake id is 16 or more
+    pub fn push(&mut self, snake_move: impl Into<SnakeMove<SnakeId>>) -> Result<(), Error> {
+        self.0.push(Self::pack(snake_move.into())?)
+    }
+
+    /// adds the move after the other moves, dropping the oldest move if the key is full. Errors
+    /// and leaves the key as it was if the snake id is 16 or more
+    pub fn push_dropping_oldest(
+        &mut self,
+        snake_move: impl Into<SnakeMove<SnakeId>>,
+    ) -> Result<(), Error> {
+        self.0.push_dropping_oldest(Self::pack(snake_move.into())?);
+        Ok(())
+    }
+
+    /// removes the newest move
+    pub fn pop(&mut self) -> Option<SnakeMove<SnakeId>> {
+        self.0.pop().map(Self::unpack)
+    }
+
+    /// the snakes and their moves, oldest first
+    pub fn iter(&self) -> impl Iterator<Item = SnakeMove<SnakeId>> {
+        let key = *self;
+        (0..key.len()).filter_map(move |i| key.get(i))
+    }
+
+    fn pack(SnakeMove { snake_id: sid, mv }: SnakeMove<SnakeId>) -> Result<u64, Error> {
+        if sid.0 as u64 >= 1 << SNAKE_ID_BITS {
+            return Err(Error::InvalidPathKey(format!(
+                "snake id {} doesn't fit",
+                sid.0
+            )));
+        }
+        Ok(sid.0 as u64 | ((mv.as_index() as u64) << SNAKE_ID_BITS))
+    }
+
+    fn unpack(entry: u64) -> SnakeMove<SnakeId> {
+        let sid = SnakeId((entry & ((1 << SNAKE_ID_BITS) - 1)) as u8);
+        SnakeMove::new(sid, Move::from_index((entry >> SNAKE_ID_BITS) as usize))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use itertools::Itertools;
+    use rand::{rngs::SmallRng, Rng, SeedableRng};
+
+    use super::*;
+
+    #[test]
+    fn test_round_trip() {
+        let mut rng = SmallRng::seed_from_u64(3);
+        for len in 0..=PathKey::CAPACITY {
+            let moves = (0..len)
+                .map(|_| Move::from_index(rng.gen_range(0..4)))
+                .collect::<Vec<_>>();
+            let mut key = PathKey::new();
+            for mv in &moves {
+                key.push(*mv).unwrap();
+            }
+            assert_eq!(key.len(), len);
+            assert_eq!(key.iter().collect::<Vec<_>>(), moves);
+            assert_eq!(PathKey::from_u64(key.as_u64()).unwrap(), key);
+
+            let mut popped = vec![];
+            while let Some(mv) = key.pop() {
+                popped.push(mv);
+            }
+            popped.reverse();
+            assert_eq!(popped, moves);
+            assert_eq!(key, PathKey::new());
+        }
+
+        for len in 0..=JointPathKey::CAPACITY {
+            let moves = (0..len)
+                .map(|_| {
+                    let sid = SnakeId(rng.gen_range(0..16));
+                    SnakeMove::new(sid, Move::from_index(rng.gen_range(0..4)))
+                })
+                .collect::<Vec<_>>();
+            let mut key = JointPathKey::new();
+            for snake_move in &moves {
+                key.push(snake_move).unwrap();
+            }
+            assert_eq!(key.iter().collect::<Vec<_>>(), moves);
+            assert_eq!(JointPathKey::from_u64(key.as_u64()).unwrap(), key);
+            assert_eq!(key.pop(), moves.last().copied());
+        }
+    }
+
+    #[test]
+    fn test_distinct_sequences_have_distinct_keys() {
+        let mut keys = HashSet::new();
+        let mut sequences = 0;
+        for len in 0..=5 {
+            for moves in (0..len).map(|_| Move::all()).multi_cartesian_product() {
+                let mut key = PathKey::new();
+                moves.iter().for_each(|mv| key.push(*mv).unwrap());
+                keys.insert(key.as_u64());
+                sequences += 1;
+            }
+        }
+        // multi_cartesian_product of nothing is nothing, rather than the empty sequence
+        keys.insert(PathKey::new().as_u64());
+        assert_eq!(keys.len(), sequences + 1);
+
+        let entries = (0..2)
+            .cartesian_product(Move::all().iter().copied())
+            .map(|(sid, mv)| (SnakeId(sid), mv))
+            .collect::<Vec<_>>();
+        let mut keys = HashSet::new();
+        let mut sequences = 1;
+        keys.insert(JointPathKey::new());
+        for len in 1..=3 {
+            for moves in (0..len).map(|_| entries.clone()).multi_cartesian_product() {
+                let mut key = JointPathKey::new();
+                for snake_move in moves {
+                    key.push(snake_move).unwrap();
+                }
+                keys.insert(key);
+                sequences += 1;
+            }
+        }
+        assert_eq!(keys.len(), sequences);
+    }
+
+    #[test]
+    fn test_full_keys() {
+        let mut key = PathKey::new();
+        for _ in 0..PathKey::CAPACITY {
+            key.push(Move::Left).unwrap();
+        }
+        let full = key;
+        assert!(matches!(key.push(Move::Up), Err(Error::PathKeyFull(29))));
+        assert_eq!(key, full);
+
+        key.push_dropping_oldest(Move::Up);
+        assert_eq!(key.len(), PathKey::CAPACITY);
+        assert_eq!(key.get(0), Some(Move::Left));
+        assert_eq!(key.iter().last(), Some(Move::Up));
+        assert_eq!(key.iter().filter(|mv| *mv == Move::Left).count(), 28);
+
+        let mut joint = JointPathKey::new();
+        assert!(joint.push((SnakeId(16), Move::Up)).is_err());
+        assert!(joint.is_empty());
+        for i in 0..JointPathKey::CAPACITY {
+            joint.push((SnakeId(i as u8), Move::Down)).unwrap();
+        }
+        assert!(matches!(
+            joint.push((SnakeId(0), Move::Up)),
+            Err(Error::PathKeyFull(9))
+        ));
+        joint
+            .push_dropping_oldest((SnakeId(15), Move::Right))
+            .unwrap();
+        assert_eq!(joint.get(0), Some(SnakeMove::new(SnakeId(1), Move::Down)));
+        assert_eq!(joint.pop(), Some(SnakeMove::new(SnakeId(15), Move::Right)));
+    }
+
+    #[test]
+    fn test_layout() {
+        let mut key = PathKey::new();
+        key.push(Move::Down).unwrap();
+        key.push(Move::Right).unwrap();
+        assert_eq!(key.as_u64(), 2 << 58 | 3 << 2 | 1);
+
+        let mut joint = JointPathKey::new();
+        joint.push((SnakeId(5), Move::Left)).unwrap();
+        assert_eq!(joint.as_u64(), 1 << 58 | 2 << 4 | 5);
+
+        assert!(PathKey::from_u64(30 << 58).is_err());
+        assert!(PathKey::from_u64(1 << 58 | 1 << 2).is_err());
+    }
+}
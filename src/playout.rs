@@ -0,0 +1,453 @@
+//! random playouts, playing a game out with random reasonable moves to see who wins, and flat
+//! monte carlo move evaluation built on them. With the `rayon` feature the playouts for each
+//! move run in parallel
+
+use rand::rngs::SmallRng;
+use rand::{Rng, SeedableRng};
+#[cfg(feature = "rayon")]
+use rayon::prelude::*;
+
+use crate::types::{
+    GameResult, HealthGettableGame, Move, RandomReasonableMovesGame, SimulableGame,
+    SimulatorInstruments, SnakeMove, VictorDeterminableGame, WeightedReasonableMovesGame,
+    YouDeterminableGame,
+};
+
+/// how a playout ended for you
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum PlayoutOutcome {
+    /// you were the last snake standing
+    Win,
+    /// every remaining snake was eliminated on the same turn
+    Draw,
+    /// you were eliminated
+    Loss,
+    /// the game was still going after the most turns the playout was allowed
+    CappedOut,
+}
+
+/// the end of a single playout
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Playout {
+    /// how it ended
+    pub outcome: PlayoutOutcome,
+    /// the number of turns played
+    pub turns: usize,
+}
+
+/// Plays `game` out with every snake making random reasonable moves, until you are eliminated,
+/// the game is over, or `max_turns` turns have been played. A game only you are in has no one
+/// to beat, so it is played until you are eliminated or capped out
+pub fn playout<G, I, const N_SNAKES: usize>(
+    game: G,
+    rng: &mut impl Rng,
+    max_turns: usize,
+    instruments: &I,
+) -> Playout
+where
+    G: SimulableGame<I, N_SNAKES>
+        + RandomReasonableMovesGame
+        + VictorDeterminableGame
+        + YouDeterminableGame
+        + HealthGettableGame,
+    I: SimulatorInstruments,
+{
+    play_out(game, rng, max_turns, instruments).0
+}
+
+/// [playout], along with the game it ended on
+fn play_out<G, I, const N_SNAKES: usize>(
+    mut game: G,
+    rng: &mut impl Rng,
+    max_turns: usize,
+    instruments: &I,
+) -> (Playout, G)
+where
+    G: SimulableGame<I, N_SNAKES>
+        + RandomReasonableMovesGame
+        + VictorDeterminableGame
+        + YouDeterminableGame
+        + HealthGettableGame,
+    I: SimulatorInstruments,
+{
+    let solo = game.get_snake_ids().len() == 1;
+    let mut turns = 0;
+    loop {
+        if let Some(outcome) = outcome(&game, solo) {
+            return (Playout { outcome, turns }, game);
+        }
+        if turns >= max_turns {
+            let capped = Playout {
+                outcome: PlayoutOutcome::CappedOut,
+                turns,
+            };
+            return (capped, game);
+        }
+        let moves = game
+            .random_reasonable_move_for_each_snake(rng)
+            .collect::<Vec<_>>();
+        game = step(&game, instruments, moves);
+        turns += 1;
+    }
+}
+
+/// how `game` ended for you, None if it's still going
+fn outcome<G: VictorDeterminableGame + YouDeterminableGame + HealthGettableGame>(
+    game: &G,
+    solo: bool,
+) -> Option<PlayoutOutcome> {
+    let you = game.you_id();
+    if !game.is_alive(you) {
+        return Some(PlayoutOutcome::Loss);
+    }
+    match game.game_result() {
+        GameResult::Winner(winner) if winner == *you && !solo => Some(PlayoutOutcome::Win),
+        GameResult::Winner(winner) if winner != *you => Some(PlayoutOutcome::Loss),
+        GameResult::Draw => Some(PlayoutOutcome::Draw),
+        _ => None,
+    }
+}
+
+/// the game after every snake makes its move in `moves`
+fn step<G, I, const N_SNAKES: usize>(
+    game: &G,
+    instruments: &I,
+    moves: Vec<SnakeMove<G::SnakeIDType>>,
+) -> G
+where
+    G: SimulableGame<I, N_SNAKES>,
+    I: SimulatorInstruments,
+{
+    game.simulate_with_moves(instruments, moves.into_iter().map(|m| (m.snake_id, [m.mv])))
+        .next()
+        .expect("one move for each snake simulates one game")
+        .1
+}
+
+/// how the playouts after one of your moves went
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct PlayoutStats {
+    /// playouts you won
+    pub wins: u32,
+    /// playouts that ended in a draw
+    pub draws: u32,
+    /// playouts you lost
+    pub losses: u32,
+    /// playouts that were still going at the turn cap
+    pub capped_out: u32,
+    /// the turns played across every playout, counting your move
+    pub total_turns: u64,
+}
+
+impl PlayoutStats {
+    /// the number of playouts. Every count stops at u32::MAX, and the turns at u64::MAX, rather
+    /// than wrapping
+    pub fn playouts(&self) -> u32 {
+        self.wins
+            .saturating_add(self.draws)
+            .saturating_add(self.losses)
+            .saturating_add(self.capped_out)
+    }
+
+    /// the fraction of playouts you won, 0 without any playouts
+    pub fn win_rate(&self) -> f64 {
+        if self.playouts() == 0 {
+            return 0.0;
+        }
+        self.wins as f64 / self.playouts() as f64
+    }
+
+    /// the mean number of turns a playout lasted, 0 without any playouts
+    pub fn mean_length(&self) -> f64 {
+        if self.playouts() == 0 {
+            return 0.0;
+        }
+        self.total_turns as f64 / self.playouts() as f64
+    }
+
+    fn record(mut self, playout: Playout) -> Self {
+        match playout.outcome {
+            PlayoutOutcome::Win => self.wins = self.wins.saturating_add(1),
+            PlayoutOutcome::Draw => self.draws = self.draws.saturating_add(1),
+            PlayoutOutcome::Loss => self.losses = self.losses.saturating_add(1),
+            PlayoutOutcome::CappedOut => self.capped_out = self.capped_out.saturating_add(1),
+        }
+        self.total_turns = self.total_turns.saturating_add(playout.turns as u64);
+        self
+    }
+
+    #[cfg(feature = "rayon")]
+    fn merge(self, other: Self) -> Self {
+        PlayoutStats {
+            wins: self.wins.saturating_add(other.wins),
+            draws: self.draws.saturating_add(other.draws),
+            losses: self.losses.saturating_add(other.losses),
+            capped_out: self.capped_out.saturating_add(other.capped_out),
+            total_turns: self.total_turns.saturating_add(other.total_turns),
+        }
+    }
+}
+
+/// the playout statistics for each of your moves, see [evaluate_moves_by_playout]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MoveEvaluation {
+    stats: [Option<PlayoutStats>; 4],
+}
+
+impl MoveEvaluation {
+    /// the statistics for `mv`, None if it wasn't a reasonable move so wasn't played out
+    pub fn get(&self, mv: Move) -> Option<&PlayoutStats> {
+        self.stats[mv.as_index()].as_ref()
+    }
+
+    /// every reasonable move and its statistics, in the order of [Move::all]
+    pub fn iter(&self) -> impl Iterator<Item = (Move, &PlayoutStats)> + '_ {
+        Move::all_iter().filter_map(move |mv| self.get(mv).map(|stats| (mv, stats)))
+    }
+
+    /// the reasonable move with the best win rate, ties going to the earliest in
+    /// [Move::all]. None if you have no reasonable moves
+    pub fn best_move(&self) -> Option<Move> {
+        self.iter()
+            .fold(None, |best: Option<(Move, f64)>, (mv, stats)| match best {
+                Some((_, rate)) if rate >= stats.win_rate() => best,
+                _ => Some((mv, stats.win_rate())),
+            })
+            .map(|(mv, _)| mv)
+    }
+}
+
+/// the bounds the games and instruments of [evaluate_moves_by_playout] need, `Sync` with the
+/// `rayon` feature so playouts can share them across threads and nothing without it
+#[cfg(feature = "rayon")]
+pub trait PlayoutSync: Sync {}
+#[cfg(feature = "rayon")]
+impl<T: Sync> PlayoutSync for T {}
+/// the bounds the games and instruments of [evaluate_moves_by_playout] need, `Sync` with the
+/// `rayon` feature so playouts can share them across threads and nothing without it
+#[cfg(not(feature = "rayon"))]
+pub trait PlayoutSync {}
+#[cfg(not(feature = "rayon"))]
+impl<T> PlayoutSync for T {}
+
+/// Flat monte carlo, plays out `playouts_per_move` random games after each of your reasonable
+/// moves, see [playout], with `max_turns` counting your move. The other snakes make random
+/// reasonable moves on your turn too. Moves that aren't reasonable, e.g. in to a wall, aren't
+/// played out. Every playout gets its own rng derived from `rng_seed`, so the same seed gives
+/// the same statistics whether or not the playouts run in parallel
+pub fn evaluate_moves_by_playout<G, I, const N_SNAKES: usize>(
+    game: &G,
+    rng_seed: u64,
+    playouts_per_move: usize,
+    max_turns: usize,
+    instruments: &I,
+) -> MoveEvaluation
+where
+    G: SimulableGame<I, N_SNAKES>
+        + WeightedReasonableMovesGame
+        + VictorDeterminableGame
+        + YouDeterminableGame
+        + HealthGettableGame
+        + PlayoutSync,
+    G::SnakeIDType: PlayoutSync,
+    I: SimulatorInstruments + PlayoutSync,
+{
+    let you = game.you_id();
+    // weights are asked for exactly the reasonable moves
+    let mut reasonable = [false; 4];
+    game.random_reasonable_move_weighted(&mut SmallRng::seed_from_u64(rng_seed), |sid, mv, _| {
+        if sid == you {
+            reasonable[mv.as_index()] = true;
+        }
+        1.0
+    })
+    .for_each(drop);
+
+    let mut stats = [None; 4];
+    for mv in Move::all_iter().filter(|mv| reasonable[mv.as_index()]) {
+        let play = |i: usize| {
+            let stream = (mv.as_index() * playouts_per_move + i) as u64;
+            let mut rng = SmallRng::seed_from_u64(rng_seed.wrapping_mul(PHI) ^ stream);
+            let moves = game
+                .random_reasonable_move_for_each_snake(&mut rng)
+                .map(|other| {
+                    if other.snake_id == *you {
+                        SnakeMove::new(other.snake_id, mv)
+                    } else {
+                        other
+                    }
+                })
+                .collect();
+            let next = step(game, instruments, moves);
+            let rest = playout(next, &mut rng, max_turns.saturating_sub(1), instruments);
+            Playout {
+                outcome: rest.outcome,
+                turns: rest.turns + 1,
+            }
+        };
+        #[cfg(feature = "rayon")]
+        let move_stats = (0..playouts_per_move)
+            .into_par_iter()
+            .map(|i| PlayoutStats::default().record(play(i)))
+            .reduce(PlayoutStats::default, PlayoutStats::merge);
+        #[cfg(not(feature = "rayon"))]
+        let move_stats = (0..playouts_per_move)
+            .map(play)
+            .fold(PlayoutStats::default(), PlayoutStats::record);
+        stats[mv.as_index()] = Some(move_stats);
+    }
+    MoveEvaluation { stats }
+}
+
+/// spreads the seed's bits out so nearby seeds don't share playout rngs
+const PHI: u64 = 0x9e37_79b9_7f4a_7c15;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::compact_representation::dimensions::Custom;
+    use crate::compact_representation::CellBoardBuilder;
+    use crate::compact_representation::{StandardCellBoard, StandardCellBoard4Snakes11x11};
+    use crate::types::{
+        build_snake_id_map, LengthGettableGame, SimulationSettings, SnakeIDGettableGame,
+    };
+    use crate::wire_representation::Position;
+
+    #[derive(Debug)]
+    struct Instruments;
+    impl SimulatorInstruments for Instruments {
+        fn observe_simulation(&self, _: std::time::Duration) {}
+    }
+
+    /// you at the top of a 7x7 board heading up, so only left and right are reasonable, with another snake
+    /// in the bottom corner
+    fn duel() -> StandardCellBoard4Snakes11x11 {
+        let mut builder = CellBoardBuilder::new(7, 7);
+        builder
+            .add_snake(
+                vec![
+                    Position::new(3, 6),
+                    Position::new(3, 5),
+                    Position::new(3, 4),
+                ],
+                100,
+            )
+            .unwrap();
+        builder
+            .add_snake(
+                vec![
+                    Position::new(0, 0),
+                    Position::new(1, 0),
+                    Position::new(2, 0),
+                ],
+                100,
+            )
+            .unwrap();
+        builder.add_food(Position::new(5, 3)).unwrap();
+        let game = builder.game().clone();
+        game.as_cell_board(&build_snake_id_map(&game)).unwrap()
+    }
+
+    #[test]
+    fn test_playout_ends() {
+        let mut rng = SmallRng::seed_from_u64(3);
+        for _ in 0..20 {
+            let result = playout(duel(), &mut rng, 50, &Instruments);
+            assert!(result.turns <= 50);
+            assert_eq!(
+                result.outcome == PlayoutOutcome::CappedOut,
+                result.turns == 50
+            );
+        }
+        let capped = playout(duel(), &mut rng, 0, &Instruments);
+        assert_eq!(
+            capped,
+            Playout {
+                outcome: PlayoutOutcome::CappedOut,
+                turns: 0
+            }
+        );
+    }
+
+    #[test]
+    fn test_long_stalemate() {
+        // two snakes chasing their tails round the two halves of a 4x2 board, where the only
+        // reasonable move each has is in to its own tail, and starvation is turned off so
+        // nothing ends the game
+        let mut builder = CellBoardBuilder::new(4, 2);
+        let body = |cells: &[(i32, i32)]| cells.iter().map(|&(x, y)| Position::new(x, y)).collect();
+        builder
+            .add_snake(body(&[(1, 1), (0, 1), (0, 0), (1, 0)]), 100)
+            .unwrap();
+        builder
+            .add_snake(body(&[(2, 1), (3, 1), (3, 0), (2, 0)]), 100)
+            .unwrap();
+        let mut start: StandardCellBoard<u8, Custom, { 4 * 2 }, 2> = builder.build().unwrap();
+        start.set_simulation_settings(SimulationSettings {
+            starvation_enabled: false,
+            ..start.simulation_settings()
+        });
+
+        let max_turns = 6000;
+        let mut rng = SmallRng::seed_from_u64(7);
+        let (result, end) = play_out(start, &mut rng, max_turns, &Instruments);
+        assert_eq!(
+            result,
+            Playout {
+                outcome: PlayoutOutcome::CappedOut,
+                turns: max_turns
+            }
+        );
+        end.check_invariants().unwrap();
+        for id in end.get_snake_ids() {
+            assert_eq!(end.get_health_i64(&id), 100);
+            assert_eq!(end.get_length_i64(&id), 4);
+        }
+        // every snake is back where it started after each lap of four turns
+        assert_eq!(end, start);
+    }
+
+    #[test]
+    fn test_evaluate_moves_by_playout() {
+        let evaluation = evaluate_moves_by_playout(&duel(), 42, 100, 200, &Instruments);
+        // in to the wall and back in to your neck
+        assert_eq!(evaluation.get(Move::Up), None);
+        assert_eq!(evaluation.get(Move::Down), None);
+        for mv in [Move::Left, Move::Right].iter() {
+            let stats = evaluation.get(*mv).unwrap();
+            assert_eq!(stats.playouts(), 100);
+            assert!(stats.mean_length() >= 1.0 && stats.mean_length() <= 200.0);
+        }
+        // the same seed gives the same statistics, with or without rayon
+        assert_eq!(
+            evaluate_moves_by_playout(&duel(), 42, 100, 200, &Instruments),
+            evaluation
+        );
+        assert_ne!(
+            evaluate_moves_by_playout(&duel(), 43, 100, 200, &Instruments),
+            evaluation
+        );
+
+        // SmallRng is a different generator on 32 bit targets, so these only hold on 64 bit ones
+        if cfg!(target_pointer_width = "64") {
+            let stats = |wins, losses, total_turns| PlayoutStats {
+                wins,
+                draws: 0,
+                losses,
+                capped_out: 0,
+                total_turns,
+            };
+            assert_eq!(
+                evaluation
+                    .iter()
+                    .map(|(mv, stats)| (mv, *stats))
+                    .collect::<Vec<_>>(),
+                vec![
+                    (Move::Left, stats(55, 45, 8666)),
+                    (Move::Right, stats(54, 46, 8660))
+                ]
+            );
+            assert_eq!(evaluation.best_move(), Some(Move::Left));
+        }
+    }
+}
@@ -0,0 +1,45 @@
+//! Everything most snakes need in one import
+//!
+//! ```
+//! use battlesnake_game_types::prelude::*;
+//!
+//! let game = battlesnake_game_types::game_fixture(include_str!("../fixtures/late_stage.json"));
+//! let snake_ids = build_snake_id_map(&game);
+//! let board: StandardCellBoard4Snakes11x11 = game.as_cell_board(&snake_ids).unwrap();
+//! assert_eq!(board.get_snake_ids().len(), game.get_snake_ids().len());
+//! ```
+//!
+//! This re-exports every game trait, so their methods are in scope, along with the types that
+//! come up in nearly every snake. Items are only ever added to the prelude, nothing is removed or
+//! renamed outside of a major version. A new trait can still make a method call ambiguous
+//! with a trait of your own that has a method of the same name, importing that trait by name
+//! fixes it.
+//!
+//! The two `ToBestCellBoard` traits, from [standard](crate::compact_representation::standard)
+//! and [wrapped](crate::compact_representation::wrapped), aren't included because they share a
+//! method name, import the one you want from its module
+
+pub use crate::compact_representation::dimensions::Dimensions;
+pub use crate::compact_representation::{
+    CellBoardBuilder, CellNum, DuelCellBoard, StandardCellBoard, StandardCellBoard4Snakes11x11,
+    StandardDuelCellBoard11x11, WrappedCellBoard, WrappedCellBoard4Snakes11x11,
+    WrappedDuelCellBoard11x11,
+};
+pub use crate::dynamic::{DynGame, DynGameView, DynInstruments, DynSimulableGame};
+pub use crate::hazard_algorithms::ForwardOnlyHazardAlgorithm;
+pub use crate::playout::PlayoutSync;
+pub use crate::pruning::MovePruner;
+pub use crate::types::{
+    build_snake_id_map, Action, ChokePointGame, FloodFillableGame, FoodGettableGame,
+    FoodQueryableGame, FoodRaceGame, FoodSettableGame, FutureOccupancyGame, HazardQueryableGame,
+    HazardSettableGame, HeadGettableGame, HeadSquaresGame, HeadToHeadPredictableGame,
+    HealthGettableGame, LastMoveDeterminableGame, LengthGettableGame, MaskedQueryableGame, Move,
+    NeckQueryableGame, NeighborDeterminableGame, PathfindableGame, PositionGettableGame,
+    RandomReasonableMovesGame, ShoutGettableGame, SimulableGame, SimulatorInstruments,
+    SizeDeterminableGame, SnakeBodyGettableGame, SnakeIDGettableGame, SnakeIDMap, SnakeId,
+    SnakeViewGame, SquadVictorDeterminableGame, TailChaseGame, TrajectoryProjectableGame,
+    TurnDeterminableGame, Vector, VictorDeterminableGame, VoronoiGame, WallQueryableGame,
+    WeightedReasonableMovesGame, YouDeterminableGame,
+};
+pub use crate::wire_representation::{Game, Position};
+pub use crate::Error;
@@ -0,0 +1,241 @@
+//! pruning moves before they are simulated, so deep searches don't spend their time on moves
+//! that can't matter, see [crate::types::SimulableGame::simulate_with_moves_pruned]
+
+use crate::types::{
+    FloodFillOptions, FloodFillableGame, FoodQueryableGame, HeadGettableGame, HealthGettableGame,
+    LengthGettableGame, Move, NeighborDeterminableGame, PositionGettableGame, SnakeIDGettableGame,
+};
+
+/// Decides which moves are left out of a simulation. Every combination with a pruned move in it
+/// is left out, so pruning can only ever remove results. Combine pruners with [AndPruner]
+pub trait MovePruner<G: SnakeIDGettableGame + PositionGettableGame> {
+    /// true to leave out `snake` moving `mv`, which takes its head to `destination`
+    fn prune(
+        &self,
+        game: &G,
+        snake: &G::SnakeIDType,
+        mv: Move,
+        destination: &G::NativePositionType,
+    ) -> bool;
+}
+
+/// Prunes nothing, simulating with it is the same as not pruning
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct NoPruning;
+
+impl<G: SnakeIDGettableGame + PositionGettableGame> MovePruner<G> for NoPruning {
+    fn prune(&self, _: &G, _: &G::SnakeIDType, _: Move, _: &G::NativePositionType) -> bool {
+        false
+    }
+}
+
+/// Prunes a move if either of two pruners does, so only the moves both keep are simulated
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct AndPruner<A, B> {
+    /// the first pruner
+    pub first: A,
+    /// the second pruner
+    pub second: B,
+}
+
+impl<A, B> AndPruner<A, B> {
+    /// combines `first` and `second`
+    pub fn new(first: A, second: B) -> Self {
+        Self { first, second }
+    }
+}
+
+impl<G, A, B> MovePruner<G> for AndPruner<A, B>
+where
+    G: SnakeIDGettableGame + PositionGettableGame,
+    A: MovePruner<G>,
+    B: MovePruner<G>,
+{
+    fn prune(
+        &self,
+        game: &G,
+        snake: &G::SnakeIDType,
+        mv: Move,
+        destination: &G::NativePositionType,
+    ) -> bool {
+        self.first.prune(game, snake, mv, destination)
+            || self.second.prune(game, snake, mv, destination)
+    }
+}
+
+/// Prunes every move of a snake that starves this turn whatever it does: it has a point of
+/// health left and no food next to its head. It assumes the standard rules, where a snake loses
+/// a point of health a turn, and ignores hazard damage, which only starves more snakes
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct StarvationPruner;
+
+impl<G> MovePruner<G> for StarvationPruner
+where
+    G: HealthGettableGame + HeadGettableGame + FoodQueryableGame + NeighborDeterminableGame,
+{
+    fn prune(&self, game: &G, snake: &G::SnakeIDType, _: Move, _: &G::NativePositionType) -> bool {
+        let head = game.get_head_as_native_position(snake);
+        game.get_health_i64(snake) <= 1 && !game.neighbors(&head).any(|pos| game.is_food(&pos))
+    }
+}
+
+/// Prunes a move in to a corner smaller than the snake, when another of its moves leads
+/// somewhere with room for all of it. The room is counted with
+/// [FloodFillableGame::flood_fill_count] on the board before the move, so it is a guess, and a
+/// square with a snake on it has no room at all
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct CornerPruner;
+
+impl<G> MovePruner<G> for CornerPruner
+where
+    G: FloodFillableGame + LengthGettableGame + HeadGettableGame + NeighborDeterminableGame,
+{
+    fn prune(
+        &self,
+        game: &G,
+        snake: &G::SnakeIDType,
+        mv: Move,
+        destination: &G::NativePositionType,
+    ) -> bool {
+        let length = game.get_length_i64(snake);
+        let room = |pos: &G::NativePositionType| {
+            if game.position_is_snake_body(pos.clone()) {
+                return 0;
+            }
+            game.flood_fill_count(pos, FloodFillOptions::default()) as i64 + 1
+        };
+        if room(destination) >= length {
+            return false;
+        }
+        let head = game.get_head_as_native_position(snake);
+        game.possible_moves(&head)
+            .any(|(other, pos)| other != mv && room(&pos) >= length)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::compact_representation::{CellIndex, StandardCellBoard4Snakes11x11};
+    use crate::game_fixture;
+    use crate::types::{build_snake_id_map, SimulableGame, SimulatorInstruments, SnakeId};
+
+    #[derive(Debug)]
+    struct Instruments;
+    impl SimulatorInstruments for Instruments {
+        fn observe_simulation(&self, _: std::time::Duration) {}
+    }
+
+    fn compact(fixture: &str) -> StandardCellBoard4Snakes11x11 {
+        let game = game_fixture(fixture);
+        game.as_cell_board(&build_snake_id_map(&game)).unwrap()
+    }
+
+    /// (moves, state) for every combination simulated, with the moves in snake id order
+    fn simulated(
+        board: &StandardCellBoard4Snakes11x11,
+        pruner: &impl MovePruner<StandardCellBoard4Snakes11x11>,
+    ) -> Vec<(Vec<(SnakeId, Move)>, StandardCellBoard4Snakes11x11)> {
+        let moves = Move::all();
+        board
+            .simulate_with_moves_pruned(
+                &Instruments,
+                board
+                    .get_snake_ids()
+                    .into_iter()
+                    .map(|sid| (sid, moves.as_slice())),
+                pruner,
+            )
+            .map(|(action, state)| (action.iter().collect(), state))
+            .collect()
+    }
+
+    /// the moves `sid` makes in any of `results`
+    fn moves_of(
+        results: &[(Vec<(SnakeId, Move)>, StandardCellBoard4Snakes11x11)],
+        sid: SnakeId,
+    ) -> Vec<Move> {
+        let mut moves = results
+            .iter()
+            .filter_map(|(moves, _)| moves.iter().find(|(id, _)| *id == sid))
+            .map(|(_, mv)| *mv)
+            .collect::<Vec<_>>();
+        moves.sort_by_key(Move::as_index);
+        moves.dedup();
+        moves
+    }
+
+    #[test]
+    fn test_starvation_pruner() {
+        let board = compact(include_str!("../fixtures/starving_opponent.json"));
+        let kept = |sid: SnakeId| {
+            let head = board.get_head_as_native_position(&sid);
+            board
+                .possible_moves(&head)
+                .filter(|(mv, pos)| !StarvationPruner.prune(&board, &sid, *mv, pos))
+                .count()
+        };
+        // the starving snake has no food in reach, fed is as hungry but next to food
+        assert_eq!(kept(SnakeId(1)), 0);
+        assert_eq!(kept(SnakeId(0)), 4);
+        assert_eq!(kept(SnakeId(2)), 4);
+        assert_eq!(kept(SnakeId(3)), 4);
+
+        let unpruned = simulated(&board, &NoPruning);
+        let pruned = simulated(&board, &StarvationPruner);
+        assert!(pruned.iter().all(|result| unpruned.contains(result)));
+        assert_eq!(moves_of(&pruned, SnakeId(1)), vec![Move::Up]);
+        for sid in [SnakeId(0), SnakeId(2), SnakeId(3)].iter() {
+            assert_eq!(moves_of(&pruned, *sid), moves_of(&unpruned, *sid));
+        }
+    }
+
+    #[test]
+    fn test_corner_pruner() {
+        let board = compact(include_str!("../fixtures/small_pocket.json"));
+        let you = SnakeId(0);
+        let head = board.get_head_as_native_position(&you);
+        let pruned = board
+            .possible_moves(&head)
+            .filter(|(mv, pos)| CornerPruner.prune(&board, &you, *mv, pos))
+            .map(|(mv, _)| mv)
+            .collect::<Vec<_>>();
+        // moving on to its own neck leaves no room at all
+        assert_eq!(pruned, vec![Move::Up, Move::Down, Move::Left]);
+
+        let unpruned = simulated(&board, &NoPruning);
+        let results = simulated(&board, &CornerPruner);
+        assert!(results.len() < unpruned.len());
+        assert!(results.iter().all(|result| unpruned.contains(result)));
+    }
+
+    #[test]
+    fn test_and_pruner() {
+        let board = compact(include_str!("../fixtures/starving_opponent.json"));
+        let unpruned = simulated(&board, &NoPruning);
+        let starving = simulated(&board, &StarvationPruner);
+        let both = simulated(&board, &AndPruner::new(StarvationPruner, CornerPruner));
+        assert!(both.iter().all(|result| starving.contains(result)));
+        assert!(starving.iter().all(|result| unpruned.contains(result)));
+        assert_eq!(
+            simulated(&board, &AndPruner::new(NoPruning, NoPruning)),
+            unpruned
+        );
+
+        // a snake always has at least one move left
+        #[derive(Debug)]
+        struct Everything;
+        impl MovePruner<StandardCellBoard4Snakes11x11> for Everything {
+            fn prune(
+                &self,
+                _: &StandardCellBoard4Snakes11x11,
+                _: &SnakeId,
+                _: Move,
+                _: &CellIndex<u8>,
+            ) -> bool {
+                true
+            }
+        }
+        assert_eq!(simulated(&board, &Everything).len(), 1);
+    }
+}
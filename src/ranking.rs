@@ -0,0 +1,408 @@
+//! ranking a snake's moves by the crate's heuristics, for a good default move or for ordering
+//! the moves a search tries first, see [rank_moves]
+
+use rand::rngs::SmallRng;
+use rand::SeedableRng;
+
+use crate::types::{
+    AStarOptions, FloodFillOptions, FloodFillableGame, FoodRaceGame, HazardQueryableGame,
+    HeadSquaresGame, HeadToHeadOutcome, HeadToHeadPredictableGame, Move,
+    WeightedReasonableMovesGame,
+};
+
+/// How safe a move is, the worst first
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum SafetyTier {
+    /// not a reasonable move, see [crate::types::RandomReasonableMovesGame]: off the board, on
+    /// to a body, or on to enough hazard to kill
+    Fatal,
+    /// reasonable, but in to fewer squares than the snake is long
+    Cramped,
+    /// reasonable, with room for the whole snake
+    Safe,
+}
+
+/// How much each part of a move's score counts for in [rank_moves]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct HeuristicWeights {
+    /// added for a [SafetyTier::Fatal] move
+    pub fatal: f64,
+    /// added for a [SafetyTier::Cramped] move
+    pub cramped: f64,
+    /// times the natural log of one more than the area after the move, so each square counts
+    /// for less than the one before it
+    pub area: f64,
+    /// added if a snake could win a head to head on the square, or both snakes would die
+    pub head_to_head_loss: f64,
+    /// added if a snake could move on to the square and would lose the head to head
+    pub head_to_head_win: f64,
+    /// times `1 / (1 + distance)` to the nearest winnable food after the move
+    pub food: f64,
+    /// times the hazard damage taken moving on to the square
+    pub hazard: f64,
+}
+
+impl Default for HeuristicWeights {
+    fn default() -> Self {
+        Self {
+            fatal: -1000.0,
+            cramped: -200.0,
+            area: 10.0,
+            head_to_head_loss: -500.0,
+            head_to_head_win: 25.0,
+            food: 30.0,
+            hazard: -2.0,
+        }
+    }
+}
+
+/// What each heuristic added to a [ScoredMove::score]
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct ScoreBreakdown {
+    #[allow(missing_docs)]
+    pub safety: f64,
+    #[allow(missing_docs)]
+    pub area: f64,
+    #[allow(missing_docs)]
+    pub head_to_head: f64,
+    #[allow(missing_docs)]
+    pub food: f64,
+    #[allow(missing_docs)]
+    pub hazard: f64,
+}
+
+impl ScoreBreakdown {
+    /// the score these add up to
+    pub fn total(&self) -> f64 {
+        self.safety + self.area + self.head_to_head + self.food + self.hazard
+    }
+}
+
+/// A move with its score from [rank_moves], and what went in to it
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ScoredMove {
+    #[allow(missing_docs)]
+    pub mv: Move,
+    /// the weighted sum of the heuristics, higher is better
+    pub score: f64,
+    #[allow(missing_docs)]
+    pub safety: SafetyTier,
+    /// the squares reachable after the move, including the one moved on to, 0 for fatal moves
+    pub area: usize,
+    /// the worst head to head the move could end up in, None if no other snake can get to the
+    /// square
+    pub head_to_head: Option<HeadToHeadOutcome>,
+    /// the distance from the square moved on to to the nearest winnable food, None if there
+    /// isn't any or the move is fatal
+    pub food_distance: Option<u32>,
+    /// the hazard damage taken on the square moved on to, negative for hazards that heal
+    pub hazard_damage: i32,
+    /// what each heuristic added to `score`
+    pub breakdown: ScoreBreakdown,
+}
+
+/// Scores each of `my_id`'s four moves by [HeuristicWeights], best first, with moves that score
+/// the same in the order of [Move::all]:
+///
+/// * the move's [SafetyTier], from the same reasonable moves
+///   [WeightedReasonableMovesGame::random_reasonable_move_weighted] picks from
+/// * the area after the move, from [FloodFillableGame::flood_fill_count]
+/// * head to head risk against every snake that could move on to the same square, from
+///   [HeadToHeadPredictableGame::predict_head_to_head]
+/// * the distance to the nearest food, using
+///   [PathfindableGame::distance_map](crate::types::PathfindableGame::distance_map). For you only
+///   food [FoodRaceGame::food_race_analysis] says is winnable counts, for other snakes any food
+/// * the hazard damage on the square
+///
+/// A snake that has been eliminated has four fatal moves. The compact boards have every trait
+/// this needs
+pub fn rank_moves<G>(
+    game: &G,
+    my_id: &G::SnakeIDType,
+    weights: &HeuristicWeights,
+) -> [ScoredMove; 4]
+where
+    G: WeightedReasonableMovesGame
+        + FloodFillableGame
+        + HeadSquaresGame
+        + HeadToHeadPredictableGame
+        + FoodRaceGame
+        + HazardQueryableGame,
+{
+    // the weight is asked for every reasonable move, the move picked doesn't matter
+    let mut reasonable = Vec::with_capacity(4);
+    game.random_reasonable_move_weighted(&mut SmallRng::seed_from_u64(0), |sid, mv, _| {
+        if sid == my_id {
+            reasonable.push(mv);
+        }
+        1.0
+    })
+    .for_each(|_| {});
+
+    let options = AStarOptions::default();
+    let food = if game.is_you(my_id) {
+        game.food_race_analysis(options)
+            .into_iter()
+            .filter(|race| race.winnable)
+            .map(|race| race.position)
+            .collect::<Vec<_>>()
+    } else {
+        game.get_all_food_as_native_positions()
+    };
+    let length = game.get_length_i64(my_id) as usize;
+    let others = game
+        .get_snake_ids()
+        .into_iter()
+        .filter(|sid| sid != my_id)
+        .collect::<Vec<_>>();
+    let squares = game.possible_head_squares(my_id);
+
+    let mut ranked = Move::all().map(|mv| {
+        let destination = squares
+            .iter()
+            .find(|(other, _)| *other == mv)
+            .map(|(_, pos)| pos.clone())
+            .filter(|_| reasonable.contains(&mv));
+        let mut scored = ScoredMove {
+            mv,
+            score: 0.0,
+            safety: SafetyTier::Fatal,
+            area: 0,
+            head_to_head: None,
+            food_distance: None,
+            hazard_damage: 0,
+            breakdown: ScoreBreakdown::default(),
+        };
+        if let Some(destination) = destination {
+            scored.area = game.flood_fill_count(&destination, FloodFillOptions::default()) + 1;
+            scored.safety = if scored.area < length {
+                SafetyTier::Cramped
+            } else {
+                SafetyTier::Safe
+            };
+            scored.head_to_head = others
+                .iter()
+                .filter(|other| {
+                    game.possible_head_squares(other)
+                        .iter()
+                        .any(|(_, pos)| *pos == destination)
+                })
+                .map(|other| game.predict_head_to_head(my_id, other, &destination))
+                .filter(|outcome| *outcome != HeadToHeadOutcome::NotAdjacent)
+                .min_by_key(|outcome| match outcome {
+                    HeadToHeadOutcome::ILose => 0,
+                    HeadToHeadOutcome::BothDie => 1,
+                    _ => 2,
+                });
+            let distances = game.distance_map(&destination, options);
+            scored.food_distance = food
+                .iter()
+                .filter_map(|pos| distances.get(pos))
+                .min()
+                .copied();
+            scored.hazard_damage =
+                game.get_hazard_count(&destination) as i32 * game.get_hazard_damage() as i32;
+        }
+
+        scored.breakdown = ScoreBreakdown {
+            safety: match scored.safety {
+                SafetyTier::Fatal => weights.fatal,
+                SafetyTier::Cramped => weights.cramped,
+                SafetyTier::Safe => 0.0,
+            },
+            area: weights.area * (scored.area as f64).ln_1p(),
+            head_to_head: match scored.head_to_head {
+                Some(HeadToHeadOutcome::ILose) | Some(HeadToHeadOutcome::BothDie) => {
+                    weights.head_to_head_loss
+                }
+                Some(HeadToHeadOutcome::IWin) => weights.head_to_head_win,
+                _ => 0.0,
+            },
+            food: scored
+                .food_distance
+                .map_or(0.0, |distance| weights.food / (1.0 + distance as f64)),
+            hazard: weights.hazard * scored.hazard_damage as f64,
+        };
+        scored.score = scored.breakdown.total();
+        scored
+    });
+    ranked.sort_by(|a, b| b.score.total_cmp(&a.score));
+    ranked
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::compact_representation::{CellBoardBuilder, StandardCellBoard4Snakes11x11};
+    use crate::types::SnakeId;
+    use crate::wire_representation::Position;
+
+    fn moves(ranked: &[ScoredMove]) -> Vec<Move> {
+        ranked.iter().map(|scored| scored.mv).collect()
+    }
+
+    #[test]
+    fn test_food_grab() {
+        let mut builder = CellBoardBuilder::new(11, 11);
+        builder
+            .add_snake(
+                vec![
+                    Position::new(5, 5),
+                    Position::new(5, 4),
+                    Position::new(5, 3),
+                ],
+                30,
+            )
+            .unwrap();
+        builder
+            .add_snake(
+                vec![
+                    Position::new(9, 9),
+                    Position::new(9, 8),
+                    Position::new(9, 7),
+                ],
+                100,
+            )
+            .unwrap();
+        builder.add_food(Position::new(6, 5)).unwrap();
+        let board: StandardCellBoard4Snakes11x11 = builder.build().unwrap();
+
+        let ranked = rank_moves(&board, &SnakeId(0), &HeuristicWeights::default());
+        assert_eq!(ranked[0].mv, Move::Right);
+        assert_eq!(ranked[0].food_distance, Some(0));
+        assert_eq!(ranked[0].safety, SafetyTier::Safe);
+        assert_eq!(ranked[3].mv, Move::Down);
+        assert_eq!(ranked[3].safety, SafetyTier::Fatal);
+        for scored in &ranked {
+            assert_eq!(scored.score, scored.breakdown.total());
+        }
+        assert!(ranked.windows(2).all(|w| w[0].score >= w[1].score));
+    }
+
+    #[test]
+    fn test_trap_avoidance() {
+        // the square to our left is walled in by our own body, which won't move out of the way
+        // in time
+        let mut builder = CellBoardBuilder::new(11, 11);
+        builder
+            .add_snake(
+                vec![
+                    Position::new(5, 5),
+                    Position::new(5, 6),
+                    Position::new(4, 6),
+                    Position::new(3, 6),
+                    Position::new(3, 5),
+                    Position::new(3, 4),
+                    Position::new(4, 4),
+                    Position::new(5, 4),
+                ],
+                100,
+            )
+            .unwrap();
+        builder
+            .add_snake(
+                vec![
+                    Position::new(9, 9),
+                    Position::new(9, 8),
+                    Position::new(9, 7),
+                ],
+                100,
+            )
+            .unwrap();
+        let board: StandardCellBoard4Snakes11x11 = builder.build().unwrap();
+
+        let ranked = rank_moves(&board, &SnakeId(0), &HeuristicWeights::default());
+        let mut safe = moves(&ranked[..2]);
+        safe.sort_by_key(Move::as_index);
+        assert_eq!(safe, vec![Move::Down, Move::Right]);
+        assert!(ranked[..2].iter().all(|s| s.safety == SafetyTier::Safe));
+        assert_eq!(ranked[2].mv, Move::Left);
+        assert_eq!(ranked[2].safety, SafetyTier::Cramped);
+        assert_eq!(ranked[2].area, 1);
+        assert_eq!(ranked[3].mv, Move::Up);
+        assert_eq!(ranked[3].safety, SafetyTier::Fatal);
+        assert_eq!(ranked[3].area, 0);
+    }
+
+    #[test]
+    fn test_forced_tail_chase() {
+        // coiled in the corner, walled in by a long snake, so the only way out is our own tail
+        let mut builder = CellBoardBuilder::new(11, 11);
+        builder
+            .add_snake(
+                vec![
+                    Position::new(0, 1),
+                    Position::new(0, 0),
+                    Position::new(1, 0),
+                    Position::new(1, 1),
+                ],
+                100,
+            )
+            .unwrap();
+        builder
+            .add_snake(
+                vec![
+                    Position::new(4, 4),
+                    Position::new(3, 4),
+                    Position::new(2, 4),
+                    Position::new(1, 4),
+                    Position::new(0, 4),
+                    Position::new(0, 3),
+                    Position::new(0, 2),
+                    Position::new(1, 2),
+                    Position::new(2, 2),
+                    Position::new(2, 1),
+                    Position::new(2, 0),
+                ],
+                100,
+            )
+            .unwrap();
+        let board: StandardCellBoard4Snakes11x11 = builder.build().unwrap();
+
+        let ranked = rank_moves(&board, &SnakeId(0), &HeuristicWeights::default());
+        assert_eq!(ranked[0].mv, Move::Right);
+        assert_ne!(ranked[0].safety, SafetyTier::Fatal);
+        assert!(ranked[1..].iter().all(|s| s.safety == SafetyTier::Fatal));
+        // the rest are in the order of Move::all
+        assert_eq!(moves(&ranked[1..]), vec![Move::Up, Move::Down, Move::Left]);
+    }
+
+    #[test]
+    fn test_head_to_head() {
+        // a longer snake could meet us on the square above
+        let mut builder = CellBoardBuilder::new(11, 11);
+        builder
+            .add_snake(
+                vec![
+                    Position::new(5, 5),
+                    Position::new(5, 4),
+                    Position::new(5, 3),
+                ],
+                100,
+            )
+            .unwrap();
+        builder
+            .add_snake(
+                vec![
+                    Position::new(5, 7),
+                    Position::new(5, 8),
+                    Position::new(5, 9),
+                    Position::new(5, 10),
+                ],
+                100,
+            )
+            .unwrap();
+        let board: StandardCellBoard4Snakes11x11 = builder.build().unwrap();
+
+        let ranked = rank_moves(&board, &SnakeId(0), &HeuristicWeights::default());
+        let up = ranked.iter().find(|s| s.mv == Move::Up).unwrap();
+        assert_eq!(up.head_to_head, Some(HeadToHeadOutcome::ILose));
+        assert_eq!(up.breakdown.head_to_head, -500.0);
+        assert_ne!(ranked[0].mv, Move::Up);
+
+        // and it would win against us
+        let theirs = rank_moves(&board, &SnakeId(1), &HeuristicWeights::default());
+        let down = theirs.iter().find(|s| s.mv == Move::Down).unwrap();
+        assert_eq!(down.head_to_head, Some(HeadToHeadOutcome::IWin));
+    }
+}
@@ -0,0 +1,137 @@
+//! Instruments that write the states a simulation makes to a json lines log, for finding out
+//! exactly what a search saw at a node it got wrong. Each line is an object with the `moves`
+//! that made the state, as [SnakeMove]s, and the `state` as a wire game, see
+//! [crate::compact_representation::StandardCellBoard::to_game]
+
+use std::fmt;
+use std::io::{self, Write};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::Duration;
+
+use crate::types::{Move, SimulatorInstruments, SnakeId, SnakeMove};
+
+/// Records every state made by one in every `sample_every` simulations to a writer, as a line
+/// of json each. Every state of a sampled simulation is recorded, so a node's successors are
+/// always logged together
+pub struct RecordingInstruments<W: Write> {
+    writer: Mutex<W>,
+    error: Mutex<Option<io::Error>>,
+    sample_every: u64,
+    simulations: AtomicU64,
+}
+
+impl<W: Write> RecordingInstruments<W> {
+    /// records the first simulation and one in every `sample_every` after it to `writer`, 0
+    /// records every one like 1 does
+    pub fn new(writer: W, sample_every: u64) -> Self {
+        RecordingInstruments {
+            writer: Mutex::new(writer),
+            error: Mutex::new(None),
+            sample_every: sample_every.max(1),
+            simulations: AtomicU64::new(0),
+        }
+    }
+
+    /// the writer back, or the first error writing to it. Nothing is recorded after an error
+    pub fn into_inner(self) -> Result<W, io::Error> {
+        if let Some(error) = self.error.into_inner().unwrap() {
+            return Err(error);
+        }
+        Ok(self.writer.into_inner().unwrap())
+    }
+}
+
+impl<W: Write> fmt::Debug for RecordingInstruments<W> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RecordingInstruments")
+            .field("sample_every", &self.sample_every)
+            .field("simulations", &self.simulations.load(Ordering::Relaxed))
+            .finish()
+    }
+}
+
+impl<W: Write> SimulatorInstruments for RecordingInstruments<W> {
+    fn observe_simulation(&self, _: Duration) {}
+
+    fn records_states(&self) -> bool {
+        let simulation = self.simulations.fetch_add(1, Ordering::Relaxed);
+        simulation.is_multiple_of(self.sample_every) && self.error.lock().unwrap().is_none()
+    }
+
+    fn observe_state(&self, moves: &[(SnakeId, Move)], state_json: &str) {
+        let moves = moves.iter().map(SnakeMove::from).collect::<Vec<_>>();
+        let moves = serde_json::to_string(&moves).expect("moves always serialize");
+        let mut writer = self.writer.lock().unwrap();
+        if let Err(e) = writeln!(writer, r#"{{"moves":{},"state":{}}}"#, moves, state_json) {
+            self.error.lock().unwrap().get_or_insert(e);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use itertools::Itertools;
+
+    use super::*;
+    use crate::{
+        compact_representation::StandardCellBoard4Snakes11x11,
+        game_fixture,
+        types::{build_snake_id_map, Action, SimulableGame, SnakeIDGettableGame},
+        wire_representation::Game,
+    };
+
+    fn board() -> StandardCellBoard4Snakes11x11 {
+        let g = game_fixture(include_str!("../fixtures/late_stage.json"));
+        g.as_cell_board(&build_snake_id_map(&g)).unwrap()
+    }
+
+    #[test]
+    fn test_recorded_states_parse() {
+        let board = board();
+        let instruments = RecordingInstruments::new(vec![], 1);
+        let results = board
+            .simulate(&instruments, board.get_snake_ids())
+            .collect_vec();
+        let log = String::from_utf8(instruments.into_inner().unwrap()).unwrap();
+        let lines = log.lines().collect_vec();
+        assert_eq!(lines.len(), results.len());
+
+        for (line, (action, next)) in lines.iter().zip(&results) {
+            let record: serde_json::Value = serde_json::from_str(line).unwrap();
+            let moves: Vec<SnakeMove<SnakeId>> =
+                serde_json::from_value(record["moves"].clone()).unwrap();
+            let moves = moves.into_iter().map(<(SnakeId, Move)>::from).collect_vec();
+            let recorded = Action::<4>::collect_from(moves.iter());
+            assert_eq!(recorded.own_move(), action.own_move());
+            assert_eq!(recorded.other_moves(), action.other_moves());
+            let state: Game = serde_json::from_value(record["state"].clone()).unwrap();
+            let reparsed: StandardCellBoard4Snakes11x11 =
+                state.as_cell_board(&build_snake_id_map(&state)).unwrap();
+            assert_eq!(reparsed, *next);
+        }
+    }
+
+    #[test]
+    fn test_sampling() {
+        let board = board();
+        let instruments = RecordingInstruments::new(vec![], 3);
+        let mut per_simulation = 0;
+        for _ in 0..6 {
+            per_simulation = board.simulate(&instruments, board.get_snake_ids()).count();
+        }
+        let log = instruments.into_inner().unwrap();
+        assert_eq!(
+            log.iter().filter(|b| **b == b'\n').count(),
+            2 * per_simulation
+        );
+
+        // the states are still simulated lazily when nothing is recorded
+        let quiet = RecordingInstruments::new(io::sink(), 2);
+        quiet.records_states();
+        assert_eq!(
+            board.simulate(&quiet, board.get_snake_ids()).count(),
+            per_simulation
+        );
+    }
+}
@@ -0,0 +1,303 @@
+//! classifying a snake's moves by whether they kill it this turn, for when there is no time left
+//! to search, see [classify_moves]
+
+use crate::types::{
+    FoodQueryableGame, FutureOccupancyGame, HazardQueryableGame, HeadSquaresGame,
+    HeadToHeadOutcome, HeadToHeadPredictableGame, SquareSafety,
+};
+
+/// Why a move is fatal this turn whatever the other snakes do
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum FatalReason {
+    /// the snake has already been eliminated, so it has no moves
+    Eliminated,
+    /// the move leaves the board, or runs in to a wall
+    OffBoard,
+    /// the move reverses in to the snake's own neck
+    Neck,
+    /// the move runs in to a body piece that will still be there, including the stacked tail of
+    /// a snake that has just eaten
+    Body,
+    /// the snake runs out of health, with no food on the square
+    Starvation,
+    /// hazard damage on the square takes the rest of the snake's health, with no food on the
+    /// square
+    Hazard,
+}
+
+/// Why a move might be fatal this turn, depending on what else happens
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum RiskReason {
+    /// another snake could move on to the square and the snake would lose the head to head, or
+    /// both would die
+    HeadToHead,
+    /// hazard damage on the square would take the rest of the snake's health, but there is food
+    /// on it. Whether eating it in time saves the snake depends on the rules version
+    HazardFood,
+}
+
+/// How safe a move is this turn, see [classify_moves]. Ordered from worst to best, so the
+/// largest of a snake's moves is its safest
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum MoveSafety {
+    /// the move kills the snake whatever the other snakes do
+    Fatal(FatalReason),
+    /// the move might kill the snake
+    Risky(RiskReason),
+    /// nothing can kill the snake this turn on the square moved on to
+    Safe,
+}
+
+impl MoveSafety {
+    /// true for [MoveSafety::Fatal]
+    pub fn is_fatal(&self) -> bool {
+        matches!(self, MoveSafety::Fatal(_))
+    }
+
+    /// true for [MoveSafety::Safe]
+    pub fn is_safe(&self) -> bool {
+        *self == MoveSafety::Safe
+    }
+}
+
+/// Classifies each of `id`'s four moves, indexed by
+/// [Move::as_index](crate::types::Move::as_index), without searching, which makes it a last line
+/// of defence when a search runs out of time. A move is fatal if it leaves the board or hits a
+/// wall, runs in to a body piece [FutureOccupancyGame::will_be_unoccupied] says will still be
+/// there next turn, or takes the rest of the snake's health, assuming the snake loses a point of
+/// health a turn. A move that isn't fatal is risky if
+/// [HeadToHeadPredictableGame::predict_head_to_head] says another snake could beat it to the
+/// square, or it relies on food to survive a hazard. Moves in to a pocket too small to live in
+/// are still safe, see [crate::ranking::rank_moves] for that
+pub fn classify_moves<G>(game: &G, id: &G::SnakeIDType) -> [MoveSafety; 4]
+where
+    G: HeadSquaresGame
+        + FutureOccupancyGame
+        + HazardQueryableGame
+        + FoodQueryableGame
+        + HeadToHeadPredictableGame,
+{
+    let mut safety = [MoveSafety::Fatal(FatalReason::Eliminated); 4];
+    if !game.is_alive(id) {
+        return safety;
+    }
+    for entry in safety.iter_mut() {
+        *entry = MoveSafety::Fatal(FatalReason::OffBoard);
+    }
+
+    let head = game.get_head_as_native_position(id);
+    let health = game.get_health_i64(id);
+    let others = game
+        .get_snake_ids()
+        .into_iter()
+        .filter(|other| other != id && game.is_alive(other))
+        .collect::<Vec<_>>();
+    for (mv, pos) in game.possible_moves(&head) {
+        safety[mv.as_index()] = if game.is_neck(id, &pos) {
+            MoveSafety::Fatal(FatalReason::Neck)
+        } else {
+            classify_square(game, id, health, &others, &pos)
+        };
+    }
+    safety
+}
+
+fn classify_square<G>(
+    game: &G,
+    id: &G::SnakeIDType,
+    health: i64,
+    others: &[G::SnakeIDType],
+    pos: &G::NativePositionType,
+) -> MoveSafety
+where
+    G: HeadSquaresGame
+        + FutureOccupancyGame
+        + HazardQueryableGame
+        + FoodQueryableGame
+        + HeadToHeadPredictableGame,
+{
+    if game.will_be_unoccupied(pos, 1) == SquareSafety::Occupied {
+        return MoveSafety::Fatal(FatalReason::Body);
+    }
+
+    let damage = game.get_hazard_damage() as i64 * game.get_hazard_count(pos) as i64;
+    let food = game.is_food(pos);
+    if health - 1 <= 0 && !food {
+        return MoveSafety::Fatal(FatalReason::Starvation);
+    }
+    let hazard_kills = damage > 0 && health - 1 - damage <= 0;
+    if hazard_kills && !food {
+        return MoveSafety::Fatal(FatalReason::Hazard);
+    }
+
+    let loses_head_to_head = others.iter().any(|other| {
+        game.possible_head_squares(other)
+            .iter()
+            .any(|(_, square)| square == pos)
+            && matches!(
+                game.predict_head_to_head(id, other, pos),
+                HeadToHeadOutcome::ILose | HeadToHeadOutcome::BothDie
+            )
+    });
+    if loses_head_to_head {
+        MoveSafety::Risky(RiskReason::HeadToHead)
+    } else if hazard_kills {
+        MoveSafety::Risky(RiskReason::HazardFood)
+    } else {
+        MoveSafety::Safe
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::compact_representation::{CellBoardBuilder, StandardCellBoard4Snakes11x11};
+    use crate::types::{Move, SnakeId};
+    use crate::wire_representation::Position;
+
+    fn snake(body: &[(i32, i32)]) -> Vec<Position> {
+        body.iter().map(|&(x, y)| Position::new(x, y)).collect()
+    }
+
+    fn classify(builder: &CellBoardBuilder) -> [MoveSafety; 4] {
+        let board: StandardCellBoard4Snakes11x11 = builder.build().unwrap();
+        classify_moves(&board, &SnakeId(0))
+    }
+
+    fn safety_of(safety: &[MoveSafety; 4], mv: Move) -> MoveSafety {
+        safety[mv.as_index()]
+    }
+
+    #[test]
+    fn test_walls_necks_and_bodies() {
+        let mut builder = CellBoardBuilder::new(11, 11);
+        builder
+            .add_snake(snake(&[(0, 5), (1, 5), (2, 5)]), 100)
+            .unwrap();
+        builder
+            .add_snake(snake(&[(1, 7), (0, 7), (0, 6), (0, 6)]), 100)
+            .unwrap();
+        let safety = classify(&builder);
+        assert_eq!(
+            safety_of(&safety, Move::Left),
+            MoveSafety::Fatal(FatalReason::OffBoard)
+        );
+        assert_eq!(
+            safety_of(&safety, Move::Right),
+            MoveSafety::Fatal(FatalReason::Neck)
+        );
+        // the other snake just ate, so its tail stays where it is
+        assert_eq!(
+            safety_of(&safety, Move::Up),
+            MoveSafety::Fatal(FatalReason::Body)
+        );
+        assert_eq!(safety_of(&safety, Move::Down), MoveSafety::Safe);
+    }
+
+    #[test]
+    fn test_tail_that_vacates() {
+        // the same, but the other snake didn't just eat, so its tail moves out of the way
+        let mut builder = CellBoardBuilder::new(11, 11);
+        builder
+            .add_snake(snake(&[(0, 5), (1, 5), (2, 5)]), 100)
+            .unwrap();
+        builder
+            .add_snake(snake(&[(1, 7), (0, 7), (0, 6)]), 100)
+            .unwrap();
+        let safety = classify(&builder);
+        assert_eq!(safety_of(&safety, Move::Up), MoveSafety::Safe);
+    }
+
+    #[test]
+    fn test_head_to_head() {
+        let mut builder = CellBoardBuilder::new(11, 11);
+        builder
+            .add_snake(snake(&[(5, 5), (5, 4), (5, 3)]), 100)
+            .unwrap();
+        builder
+            .add_snake(snake(&[(7, 5), (8, 5), (9, 5), (10, 5)]), 100)
+            .unwrap();
+        builder.add_snake(snake(&[(3, 5), (2, 5)]), 100).unwrap();
+        let safety = classify(&builder);
+        // the longer snake wins on the right, the shorter one loses on the left
+        assert_eq!(
+            safety_of(&safety, Move::Right),
+            MoveSafety::Risky(RiskReason::HeadToHead)
+        );
+        assert_eq!(safety_of(&safety, Move::Left), MoveSafety::Safe);
+        assert_eq!(safety_of(&safety, Move::Up), MoveSafety::Safe);
+        assert_eq!(
+            safety_of(&safety, Move::Down),
+            MoveSafety::Fatal(FatalReason::Neck)
+        );
+    }
+
+    #[test]
+    fn test_health() {
+        let mut builder = CellBoardBuilder::new(11, 11);
+        builder.hazard_damage(15);
+        builder
+            .add_snake(snake(&[(5, 5), (5, 4), (5, 3)]), 10)
+            .unwrap();
+        builder
+            .add_snake(snake(&[(9, 9), (9, 8), (9, 7)]), 100)
+            .unwrap();
+        builder.add_hazard(Position::new(4, 5)).unwrap();
+        builder.add_hazard(Position::new(6, 5)).unwrap();
+        builder.add_food(Position::new(6, 5)).unwrap();
+        let safety = classify(&builder);
+        assert_eq!(
+            safety_of(&safety, Move::Left),
+            MoveSafety::Fatal(FatalReason::Hazard)
+        );
+        assert_eq!(
+            safety_of(&safety, Move::Right),
+            MoveSafety::Risky(RiskReason::HazardFood)
+        );
+        assert_eq!(safety_of(&safety, Move::Up), MoveSafety::Safe);
+
+        let mut builder = CellBoardBuilder::new(11, 11);
+        builder
+            .add_snake(snake(&[(5, 5), (5, 4), (5, 3)]), 1)
+            .unwrap();
+        builder
+            .add_snake(snake(&[(9, 9), (9, 8), (9, 7)]), 100)
+            .unwrap();
+        builder.add_food(Position::new(6, 5)).unwrap();
+        let safety = classify(&builder);
+        assert_eq!(
+            safety_of(&safety, Move::Left),
+            MoveSafety::Fatal(FatalReason::Starvation)
+        );
+        assert_eq!(safety_of(&safety, Move::Right), MoveSafety::Safe);
+        // the safest move is the one that eats
+        let best = Move::all()
+            .iter()
+            .copied()
+            .max_by_key(|mv| safety_of(&safety, *mv))
+            .unwrap();
+        assert_eq!(best, Move::Right);
+    }
+
+    #[test]
+    fn test_eliminated() {
+        let mut builder = CellBoardBuilder::new(11, 11);
+        builder
+            .add_snake(snake(&[(5, 5), (5, 4), (5, 3)]), 100)
+            .unwrap();
+        builder
+            .add_snake(snake(&[(9, 9), (9, 8), (9, 7)]), 100)
+            .unwrap();
+        let board: StandardCellBoard4Snakes11x11 = builder.build().unwrap();
+        assert!(classify_moves(&board, &SnakeId(3))
+            .iter()
+            .all(|safety| *safety == MoveSafety::Fatal(FatalReason::Eliminated)));
+        assert_eq!(
+            classify_moves(&board, &SnakeId(1))
+                .iter()
+                .filter(|safety| safety.is_safe())
+                .count(),
+            3
+        );
+    }
+}
@@ -0,0 +1,400 @@
+//! [proptest](https://docs.rs/proptest) strategies for generating games, enabled with the
+//! `proptest` feature. Every game generated passes [Game::validate]: bodies are connected and only
+//! stacked at the tail, at least 3 pieces long, food is only on empty squares, and snakes with a stacked tail have just
+//! eaten so have full health.
+//!
+//! The strategies generate indexes in to the free squares and directions to walk in, which are
+//! then laid out on the board skipping anything that doesn't fit. This means shrinking removes
+//! snakes, body pieces and food instead of producing games that aren't valid.
+
+use std::collections::HashSet;
+use std::ops::RangeInclusive;
+
+use proptest::collection::vec;
+use proptest::prelude::*;
+use proptest::sample::{select, Index};
+
+use crate::compact_representation::dimensions::Dimensions;
+use crate::compact_representation::{
+    CellBoardBuilder, CellNum, StandardCellBoard, WrappedCellBoard,
+};
+use crate::types::{try_build_snake_id_map, Move};
+use crate::wire_representation::{Game, Position};
+
+/// the most squares a generated body covers
+const MAX_BODY_LENGTH: usize = 20;
+/// the fewest pieces a generated body has, counting stacked pieces
+const MIN_LENGTH: usize = 3;
+
+#[derive(Debug, Clone)]
+struct SnakeParts {
+    head: Index,
+    directions: Vec<Move>,
+    stacked: bool,
+    health: i32,
+}
+
+#[derive(Debug, Clone)]
+struct GameParts {
+    snakes: Vec<SnakeParts>,
+    food: Vec<Index>,
+    hazards: Vec<(i32, i32)>,
+    hazard_damage: Option<i16>,
+}
+
+/// Standard games of the given size with a number of snakes in `snakes`, the first snake is you.
+/// There can be fewer snakes if there is no room left on the board for them
+pub fn game(width: i32, height: i32, snakes: RangeInclusive<usize>) -> BoxedStrategy<Game> {
+    game_parts(width, height, snakes)
+        .prop_map(move |parts| lay_out(width, height, false, parts))
+        .boxed()
+}
+
+/// the same as [game] but for the wrapped ruleset, bodies can cross the edges of the board
+pub fn wrapped_game(width: i32, height: i32, snakes: RangeInclusive<usize>) -> BoxedStrategy<Game> {
+    game_parts(width, height, snakes)
+        .prop_map(move |parts| lay_out(width, height, true, parts))
+        .boxed()
+}
+
+/// Standard compact boards made from [game]. Panics if the board or number of snakes doesn't fit
+/// in BOARD_SIZE and MAX_SNAKES
+pub fn cell_board<T: CellNum, D: Dimensions, const BOARD_SIZE: usize, const MAX_SNAKES: usize>(
+    width: i32,
+    height: i32,
+    snakes: RangeInclusive<usize>,
+) -> BoxedStrategy<StandardCellBoard<T, D, BOARD_SIZE, MAX_SNAKES>> {
+    check_fits::<D, BOARD_SIZE, MAX_SNAKES>(width, height, &snakes);
+    game(width, height, snakes)
+        .prop_map(|g| {
+            let snake_ids = try_build_snake_id_map(&g).expect("generated ids are unique");
+            g.as_cell_board(&snake_ids)
+                .expect("generated games are valid and fit")
+        })
+        .boxed()
+}
+
+/// Wrapped compact boards made from [wrapped_game]. Panics if the board or number of snakes
+/// doesn't fit in BOARD_SIZE and MAX_SNAKES
+pub fn wrapped_cell_board<
+    T: CellNum,
+    D: Dimensions,
+    const BOARD_SIZE: usize,
+    const MAX_SNAKES: usize,
+>(
+    width: i32,
+    height: i32,
+    snakes: RangeInclusive<usize>,
+) -> BoxedStrategy<WrappedCellBoard<T, D, BOARD_SIZE, MAX_SNAKES>> {
+    check_fits::<D, BOARD_SIZE, MAX_SNAKES>(width, height, &snakes);
+    wrapped_game(width, height, snakes)
+        .prop_map(|g| {
+            let snake_ids = try_build_snake_id_map(&g).expect("generated ids are unique");
+            g.as_wrapped_cell_board(&snake_ids)
+                .expect("generated games are valid and fit")
+        })
+        .boxed()
+}
+
+fn check_fits<D: Dimensions, const BOARD_SIZE: usize, const MAX_SNAKES: usize>(
+    width: i32,
+    height: i32,
+    snakes: &RangeInclusive<usize>,
+) {
+    assert!(
+        (1..=u8::MAX as i32).contains(&width)
+            && (1..=u8::MAX as i32).contains(&height)
+            && width * height <= BOARD_SIZE as i32
+            && D::can_represent(width as u8, height as u8),
+        "a {}x{} board doesn't fit",
+        width,
+        height
+    );
+    assert!(
+        *snakes.end() <= MAX_SNAKES,
+        "{} snakes don't fit",
+        snakes.end()
+    );
+}
+
+fn game_parts(
+    width: i32,
+    height: i32,
+    snakes: RangeInclusive<usize>,
+) -> impl Strategy<Value = GameParts> {
+    assert!(width > 0 && height > 0, "boards need at least one square");
+    let area = (width * height) as usize;
+    let snake = (
+        any::<Index>(),
+        vec(select(Move::all().to_vec()), 0..MAX_BODY_LENGTH),
+        any::<bool>(),
+        1..=100i32,
+    )
+        .prop_map(|(head, directions, stacked, health)| SnakeParts {
+            head,
+            directions,
+            stacked,
+            health,
+        });
+    (
+        vec(snake, snakes),
+        vec(any::<Index>(), 0..=area / 4),
+        vec((0..width, 0..height), 0..=area / 4),
+        proptest::option::of(-30..=100i16),
+    )
+        .prop_map(|(snakes, food, hazards, hazard_damage)| GameParts {
+            snakes,
+            food,
+            hazards,
+            hazard_damage,
+        })
+}
+
+/// the squares without a snake or food on them, in a fixed order so indexes shrink predictably
+fn free_squares(game: &Game) -> Vec<Position> {
+    let taken = game
+        .board
+        .snakes
+        .iter()
+        .flat_map(|s| s.body.iter())
+        .chain(game.board.food.iter())
+        .copied()
+        .collect::<HashSet<_>>();
+    (0..game.board.height)
+        .flat_map(|y| (0..game.board.width).map(move |x| Position::new(x, y)))
+        .filter(|p| !taken.contains(p))
+        .collect()
+}
+
+fn step(position: Position, mv: Move, width: i32, height: i32, wrapped: bool) -> Option<Position> {
+    let next = position.add_vec(mv.to_vector());
+    if wrapped {
+        Some(Position::new(
+            next.x.rem_euclid(width),
+            next.y.rem_euclid(height),
+        ))
+    } else if next.x < 0 || next.x >= width || next.y < 0 || next.y >= height {
+        None
+    } else {
+        Some(next)
+    }
+}
+
+fn lay_out(width: i32, height: i32, wrapped: bool, parts: GameParts) -> Game {
+    let mut builder = CellBoardBuilder::new(width, height);
+    if let Some(damage) = parts.hazard_damage {
+        builder.hazard_damage(damage);
+    }
+
+    for snake in parts.snakes {
+        let free = free_squares(builder.game());
+        if free.is_empty() {
+            break;
+        }
+        let mut body = vec![*snake.head.get(&free)];
+        let free = free.into_iter().collect::<HashSet<_>>();
+        for mv in snake.directions {
+            let next = step(*body.last().unwrap(), mv, width, height, wrapped);
+            if let Some(next) = next.filter(|n| free.contains(n) && !body.contains(n)) {
+                body.push(next);
+            }
+        }
+        // snakes start 3 long and never shrink, so shorter bodies are stacked up to 3 pieces
+        // like at the start of the game
+        let health = if snake.stacked || body.len() < MIN_LENGTH {
+            let tail = *body.last().unwrap();
+            let extra = MIN_LENGTH.saturating_sub(body.len()).max(1);
+            body.extend(std::iter::repeat_n(tail, extra));
+            100
+        } else {
+            snake.health
+        };
+        builder
+            .add_snake(body, health)
+            .expect("bodies are laid out on free squares");
+    }
+
+    for index in parts.food {
+        let free = free_squares(builder.game());
+        if free.is_empty() {
+            break;
+        }
+        builder
+            .add_food(*index.get(&free))
+            .expect("food is placed on free squares");
+    }
+    for (x, y) in parts.hazards {
+        builder
+            .add_hazard(Position::new(x, y))
+            .expect("hazards are on the board");
+    }
+
+    let mut game = builder.game().clone();
+    if wrapped {
+        game.game.ruleset.name = "wrapped".to_string();
+    }
+    game
+}
+
+#[cfg(test)]
+mod tests {
+    use itertools::Itertools;
+
+    use super::*;
+    use crate::compact_representation::dimensions::{Custom, Square};
+    use crate::compact_representation::{CellIndex, InvariantViolation, MAX_HAZARD_STACK};
+    use crate::types::{
+        build_snake_id_map, FoodGettableGame, HazardQueryableGame, HeadGettableGame,
+        HealthGettableGame, LengthGettableGame, SimulableGame, SimulatorInstruments,
+        SnakeBodyGettableGame, SnakeIDGettableGame, VictorDeterminableGame,
+    };
+    use rand::{rngs::SmallRng, seq::SliceRandom, SeedableRng};
+
+    #[derive(Debug)]
+    struct Instruments;
+    impl SimulatorInstruments for Instruments {
+        fn observe_simulation(&self, _: std::time::Duration) {}
+    }
+
+    /// plays `board` out for up to `turns` turns with every snake moving at random, checking
+    /// each board on the way
+    fn random_playout<G, const N: usize>(
+        mut board: G,
+        seed: u64,
+        turns: usize,
+        check: impl Fn(&G) -> Result<(), InvariantViolation>,
+    ) -> Result<(), TestCaseError>
+    where
+        G: SimulableGame<Instruments, N> + SnakeIDGettableGame + VictorDeterminableGame,
+    {
+        let mut rng = SmallRng::seed_from_u64(seed);
+        for _ in 0..turns {
+            if board.is_over() {
+                break;
+            }
+            let mut next = board
+                .simulate(&Instruments, board.get_snake_ids())
+                .map(|(_, next)| next)
+                .collect::<Vec<_>>();
+            next.shuffle(&mut rng);
+            board = match next.pop() {
+                Some(next) => next,
+                None => break,
+            };
+            if let Err(violation) = check(&board) {
+                return Err(TestCaseError::fail(violation.to_string()));
+            }
+        }
+        Ok(())
+    }
+
+    proptest! {
+        #[test]
+        fn test_generated_games_are_valid(
+            g in game(11, 11, 1..=4),
+            small in game(3, 2, 0..=8),
+            wrapped in wrapped_game(7, 5, 1..=4),
+        ) {
+            for g in [g, small, wrapped].iter() {
+                prop_assert!(g.validate().is_ok(), "{:?}", g.validate());
+                prop_assert!(g.board.snakes.len() <= 8);
+                for snake in &g.board.snakes {
+                    prop_assert!(snake.health == 100 || snake.body.iter().all_unique());
+                }
+                if let Some(first) = g.board.snakes.first() {
+                    prop_assert_eq!(&g.you.id, &first.id);
+                }
+            }
+        }
+
+        #[test]
+        fn test_cell_board_matches_game(g in game(11, 11, 1..=4)) {
+            let snake_ids = build_snake_id_map(&g);
+            let board = g
+                .as_cell_board::<u8, Square, { 11 * 11 }, 4>(&snake_ids)
+                .unwrap();
+            let to_index = |p: &Position| CellIndex::<u8>::new(*p, 11);
+
+            prop_assert!(board.assert_consistency());
+            prop_assert_eq!(board.get_snake_ids().len(), g.board.snakes.len());
+            for snake in &g.board.snakes {
+                let sid = &snake_ids[&snake.id];
+                prop_assert_eq!(board.get_head_as_native_position(sid), to_index(&snake.head));
+                prop_assert_eq!(board.get_health_i64(sid), snake.health as i64);
+                prop_assert_eq!(board.get_length_i64(sid), snake.body.len() as i64);
+                prop_assert_eq!(
+                    board.get_snake_body_vec(sid),
+                    snake.body.iter().map(to_index).collect::<Vec<_>>()
+                );
+            }
+            let mut food = board.get_all_food_as_native_positions();
+            food.sort();
+            let mut expected = g.board.food.iter().map(to_index).collect::<Vec<_>>();
+            expected.sort();
+            prop_assert_eq!(food, expected);
+            for hazard in &g.board.hazards {
+                prop_assert_eq!(
+                    board.get_hazard_count(&to_index(hazard)),
+                    g.get_hazard_count(hazard).min(MAX_HAZARD_STACK)
+                );
+            }
+        }
+
+        #[test]
+        fn test_simulation_stays_consistent(
+            board in cell_board::<u8, Square, { 11 * 11 }, 4>(11, 11, 1..=3),
+            wrapped in wrapped_cell_board::<u8, Custom, { 11 * 11 }, 4>(7, 5, 1..=3),
+        ) {
+            for (_, next) in board.simulate(&Instruments, board.get_snake_ids()) {
+                prop_assert!(next.assert_consistency());
+            }
+            for (_, next) in wrapped.simulate(&Instruments, wrapped.get_snake_ids()) {
+                prop_assert!(next.assert_consistency());
+            }
+        }
+
+        #[test]
+        fn test_random_playouts_keep_invariants(
+            board in cell_board::<u8, Square, { 11 * 11 }, 4>(11, 11, 1..=4),
+            wrapped in wrapped_cell_board::<u8, Custom, { 11 * 11 }, 4>(7, 5, 1..=4),
+            seed in any::<u64>(),
+        ) {
+            random_playout(board, seed, 200, |b| b.check_invariants())?;
+            random_playout(wrapped, seed, 200, |b| b.check_invariants())?;
+        }
+
+        #[test]
+        fn test_deltas_materialize_to_simulated_boards(
+            board in cell_board::<u8, Square, { 11 * 11 }, 4>(11, 11, 1..=4),
+            wrapped in wrapped_cell_board::<u8, Custom, { 11 * 11 }, 4>(7, 5, 1..=4),
+        ) {
+            let parent = std::sync::Arc::new(board);
+            let deltas = parent.simulate_with_moves_as_deltas(
+                &Instruments,
+                board.get_snake_ids().into_iter().map(|sid| (sid, Move::all())),
+            );
+            let simulated = board.simulate(&Instruments, board.get_snake_ids());
+            for ((_, delta), (_, next)) in deltas.zip(simulated) {
+                prop_assert_eq!(delta.materialize(), next);
+            }
+            let parent = std::sync::Arc::new(wrapped);
+            let deltas = parent.simulate_with_moves_as_deltas(
+                &Instruments,
+                wrapped.get_snake_ids().into_iter().map(|sid| (sid, Move::all())),
+            );
+            let simulated = wrapped.simulate(&Instruments, wrapped.get_snake_ids());
+            for ((_, delta), (_, next)) in deltas.zip(simulated) {
+                prop_assert_eq!(delta.materialize(), next);
+            }
+        }
+
+        #[test]
+        fn test_bytes_round_trip(
+            board in cell_board::<u8, Square, { 11 * 11 }, 4>(11, 11, 1..=4),
+            wrapped in wrapped_cell_board::<u8, Custom, { 11 * 11 }, 4>(7, 5, 1..=4),
+        ) {
+            prop_assert_eq!(StandardCellBoard::from_bytes(&board.to_bytes())?, board);
+            prop_assert_eq!(WrappedCellBoard::from_bytes(&wrapped.to_bytes())?, wrapped);
+        }
+    }
+}
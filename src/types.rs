@@ -1,165 +1,42 @@
 //! various types that are useful for working with battlesnake
-use crate::wire_representation::{Game, Position};
+pub use crate::core_types::*;
+use crate::pruning::MovePruner;
+#[cfg(feature = "std")]
+use crate::wire_representation::Game;
+use crate::Error;
+use alloc::boxed::Box;
+use alloc::collections::{BinaryHeap, VecDeque};
+use alloc::format;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::borrow::Borrow;
+use core::cmp::Reverse;
+#[cfg(feature = "std")]
+use core::convert::TryFrom;
+use core::fmt::Debug;
+use core::hash::Hash;
+use hashbrown::HashMap;
+use rand::seq::IteratorRandom;
 use rand::Rng;
-use serde::{Deserialize, Serialize, Serializer};
-use std::borrow::Borrow;
-use std::collections::HashMap;
-use std::fmt::{self, Debug};
-use std::hash::Hash;
+#[cfg(feature = "std")]
+use std::sync::atomic::{AtomicU64, Ordering};
+#[cfg(feature = "std")]
 use std::time::Duration;
 
 /// Represents the snake IDs for a given game. This should be established once on the `/start` request and then
 /// stored, so that `SnakeIds` are stable throughout the game.
-pub type SnakeIDMap = HashMap<String, SnakeId>;
-
-/// A vector with which to do positional math
-#[derive(Debug, Clone, Copy)]
-pub struct Vector {
-    /// x position
-    pub x: i64,
-    /// y position
-    pub y: i64,
-}
-
-/// there are 4 moves
-pub const N_MOVES: usize = 4;
-
-/// Represents a move
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize)]
-pub enum Move {
-    #[allow(missing_docs)]
-    Left,
-    #[allow(missing_docs)]
-    Down,
-    #[allow(missing_docs)]
-    Up,
-    #[allow(missing_docs)]
-    Right,
-}
-
-impl std::fmt::Display for Move {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        match self {
-            Move::Left => write!(f, "left"),
-            Move::Right => write!(f, "right"),
-            Move::Up => write!(f, "up"),
-            Move::Down => write!(f, "down"),
-        }
-    }
-}
-
-impl Move {
-    /// convert this move to a vector
-    pub fn to_vector(self) -> Vector {
-        match self {
-            Move::Left => Vector { x: -1, y: 0 },
-            Move::Right => Vector { x: 1, y: 0 },
-            Move::Up => Vector { x: 0, y: 1 },
-            Move::Down => Vector { x: 0, y: -1 },
-        }
-    }
-
-    /// create a Move from the given vector
-    pub fn from_vector(vector: Vector) -> Self {
-        match vector {
-            Vector { x: -1, y: 0 } => Self::Left,
-            Vector { x: 1, y: 0 } => Self::Right,
-            Vector { x: 0, y: 1 } => Self::Up,
-            Vector { x: 0, y: -1 } => Self::Down,
-            _ => panic!(),
-        }
-    }
-
-    /// returns a vec of all possible moves
-    pub const fn all() -> [Self; N_MOVES] {
-        [Move::Up, Move::Down, Move::Left, Move::Right]
-    }
-
-    /// returns an Iterator of all possible moves
-    pub fn all_iter() -> MoveIter {
-        MoveIter(0)
-    }
-
-    /// converts this move to a usize index. indices are the same order as the `Move::all()` method
-    pub fn as_index(&self) -> usize {
-        match self {
-            Move::Up => 0,
-            Move::Down => 1,
-            Move::Left => 2,
-            Move::Right => 3,
-        }
-    }
-
-    /// converts a usize index to a move
-    pub fn from_index(index: usize) -> Move {
-        match index {
-            0 => Move::Up,
-            1 => Move::Down,
-            2 => Move::Left,
-            3 => Move::Right,
-            _ => panic!("invalid index"),
-        }
-    }
-
-    #[allow(dead_code)]
-    /// checks if a given move is not opposibe this move. e.g. Up is not opposite to Left, but is opposite to Down
-    pub fn is_not_opposite(&self, other: &Move) -> bool {
-        !matches!(
-            (self, other),
-            (Move::Up, Move::Down)
-                | (Move::Down, Move::Up)
-                | (Move::Left, Move::Right)
-                | (Move::Right, Move::Left)
-        )
-    }
-}
-
-#[derive(Copy, Clone, Debug)]
-/// Iterator over all moves. Returned by `Move::all_iter()`
-///
-/// The iterator yields elements in the same order as `Move::all()`
-pub struct MoveIter(usize);
-
-impl Iterator for MoveIter {
-    type Item = Move;
-
-    fn next(&mut self) -> Option<Self::Item> {
-        if self.0 < N_MOVES {
-            let m = Move::from_index(self.0);
-            self.0 += 1;
-            Some(m)
-        } else {
-            None
-        }
-    }
-}
-
-/// token to represent a snake id
-#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Deserialize)]
-#[repr(transparent)]
-pub struct SnakeId(pub u8);
-
-impl SnakeId {
-    /// convert this snake ID to a usize
-    pub fn as_usize(&self) -> usize {
-        self.0 as usize
-    }
-}
-
-impl Serialize for SnakeId {
-    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
-        serializer.serialize_u8(self.0)
-    }
-}
+#[cfg(feature = "std")]
+pub type SnakeIDMap = std::collections::HashMap<String, SnakeId>;
 
 /// builds a snake ID map for a given game, mapping snakes to
 /// integers. The snake in "you" is always ID 0. Instead of
 /// calling this on every game you are given, you should call
 /// this function once per game at the start, and store the result
 /// that way you can stabally have integer IDs for a given snake
-/// throughout a game
+/// throughout a game. Panics if there are more than 256 snakes, see [try_build_snake_id_map]
+#[cfg(feature = "std")]
 pub fn build_snake_id_map(g: &Game) -> SnakeIDMap {
-    let mut hm = HashMap::new();
+    let mut hm = SnakeIDMap::new();
     hm.insert(g.you.id.clone(), SnakeId(0));
     let mut i = 1;
     for snake in g.board.snakes.iter() {
@@ -172,93 +49,98 @@ pub fn build_snake_id_map(g: &Game) -> SnakeIDMap {
     hm
 }
 
-/// A game for which one can get the snake ids
-pub trait SnakeIDGettableGame {
-    #[allow(missing_docs)]
-    type SnakeIDType: PartialEq + Debug + Serialize + Eq + Hash + Clone + Send;
+/// The same as [build_snake_id_map], but returns an error instead of panicking if there are too
+/// many snakes to number, or if two snakes on the board share an id
+#[cfg(feature = "std")]
+pub fn try_build_snake_id_map(g: &Game) -> Result<SnakeIDMap, Error> {
+    let mut hm = SnakeIDMap::new();
+    hm.insert(g.you.id.clone(), SnakeId(0));
+    let mut seen = std::collections::HashSet::new();
+    for snake in g.board.snakes.iter() {
+        if !seen.insert(snake.id.as_str()) {
+            return Err(Error::DuplicateSnakeId(snake.id.clone()));
+        }
+        if snake.id != g.you.id {
+            let i = u8::try_from(hm.len()).map_err(|_| Error::TooManySnakes(seen.len()))?;
+            hm.insert(snake.id.clone(), SnakeId(i));
+        }
+    }
 
-    #[allow(missing_docs)]
-    fn get_snake_ids(&self) -> Vec<Self::SnakeIDType>;
+    Ok(hm)
 }
 
-/// Instruments to be used with simulation
-pub trait SimulatorInstruments: std::fmt::Debug {
-    #[allow(missing_docs)]
-    fn observe_simulation(&self, duration: Duration);
+/// [SimulatorInstruments] that count the simulations they observe and how long they took, for
+/// logging with [crate::wire_representation::DecisionRecord]. The counts can be read while
+/// simulations are still running on other threads. Nothing is counted on wasm32-unknown-unknown,
+/// see [SimulatorInstruments::observe_simulation]
+#[cfg(feature = "std")]
+#[derive(Debug, Default)]
+pub struct StatsInstruments {
+    simulations: AtomicU64,
+    total_nanos: AtomicU64,
+    longest_nanos: AtomicU64,
 }
 
-/// A game for which "you" is determinable
-pub trait YouDeterminableGame: std::fmt::Debug + SnakeIDGettableGame {
-    /// determines for a given game if a given snake id is you.
-    fn is_you(&self, snake_id: &Self::SnakeIDType) -> bool;
+#[cfg(feature = "std")]
+impl StatsInstruments {
+    /// instruments that haven't observed anything yet
+    pub fn new() -> Self {
+        Self::default()
+    }
 
-    /// get the id for you for a given game
-    fn you_id(&self) -> &Self::SnakeIDType;
-}
+    /// how many simulations have been observed
+    pub fn simulations(&self) -> u64 {
+        self.simulations.load(Ordering::Relaxed)
+    }
 
-/// A game which can have it's winner determined
-pub trait VictorDeterminableGame: std::fmt::Debug + SnakeIDGettableGame {
-    #[allow(missing_docs)]
-    fn is_over(&self) -> bool;
+    /// how long all the simulations observed took together
+    pub fn total_time(&self) -> Duration {
+        Duration::from_nanos(self.total_nanos.load(Ordering::Relaxed))
+    }
 
-    /// get the winner for a given game, will return None in the case of a draw, or if the game is not over
-    fn get_winner(&self) -> Option<Self::SnakeIDType>;
+    /// how long the longest simulation observed took
+    pub fn longest_time(&self) -> Duration {
+        Duration::from_nanos(self.longest_nanos.load(Ordering::Relaxed))
+    }
 
-    /// How many snakes are alive
-    fn alive_snake_count(&self) -> usize;
-}
-#[derive(Debug, Copy, Clone)]
-#[repr(transparent)]
-/// Represents moves taken for a given simulation
-pub struct Action<const N_SNAKES: usize> {
-    moves: [Option<Move>; N_SNAKES],
+    /// forgets everything observed so far, e.g. at the start of each turn
+    pub fn reset(&self) {
+        self.simulations.store(0, Ordering::Relaxed);
+        self.total_nanos.store(0, Ordering::Relaxed);
+        self.longest_nanos.store(0, Ordering::Relaxed);
+    }
 }
 
-#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
-/// Represents only the moves taken by other snakes in a game
-pub struct OtherAction<const N_SNAKES: usize> {
-    moves: [Option<Move>; N_SNAKES],
+/// adds to a counter, stopping at u64::MAX instead of wrapping back round to 0
+#[cfg(feature = "std")]
+fn saturating_add(counter: &AtomicU64, n: u64) {
+    let _ = counter.fetch_update(Ordering::Relaxed, Ordering::Relaxed, |count| {
+        Some(count.saturating_add(n))
+    });
 }
 
-impl<const N_SNAKES: usize> Action<N_SNAKES> {
-    /// create a new action from a given array of moves
-    pub fn new(moves: [Option<Move>; N_SNAKES]) -> Self {
-        Self { moves }
-    }
-
-    /// collects an action from an iterator of moves
-    pub fn collect_from<'a, T: Iterator<Item = &'a (SnakeId, Move)>>(ids_and_moves: T) -> Self {
-        let mut moves = [None; N_SNAKES];
-        for (id, mv) in ids_and_moves {
-            moves[id.as_usize()] = Some(*mv);
-        }
-        Self { moves }
-    }
-
-    /// gets your move
-    pub fn own_move(&self) -> Move {
-        self.moves[0].unwrap()
-    }
-    /// construct an OtherAction of the other sankes moves
-    pub fn other_moves(&self) -> OtherAction<N_SNAKES> {
-        let mut new_moves = self.moves;
-        new_moves[0] = None;
-        OtherAction { moves: new_moves }
+#[cfg(feature = "std")]
+impl SimulatorInstruments for StatsInstruments {
+    fn observe_simulation(&self, duration: Duration) {
+        let nanos = u64::try_from(duration.as_nanos()).unwrap_or(u64::MAX);
+        saturating_add(&self.simulations, 1);
+        saturating_add(&self.total_nanos, nanos);
+        self.longest_nanos.fetch_max(nanos, Ordering::Relaxed);
     }
 }
 
 /// a game for which future states can be simulated
 pub trait SimulableGame<T: SimulatorInstruments, const N_SNAKES: usize>:
-    std::fmt::Debug + Sized + SnakeIDGettableGame
+    Debug + Sized + SnakeIDGettableGame
 {
     /// simulates all possible future games for a given game returning the snake ids, moves that
     /// got to a given state, plus that state
     #[allow(clippy::type_complexity)]
-    fn simulate(
-        &self,
-        instruments: &T,
+    fn simulate<'a>(
+        &'a self,
+        instruments: &'a T,
         snake_ids: Vec<Self::SnakeIDType>,
-    ) -> Box<dyn Iterator<Item = (Action<N_SNAKES>, Self)> + '_> {
+    ) -> Box<dyn Iterator<Item = (Action<N_SNAKES>, Self)> + 'a> {
         let moves_to_simulate = Move::all();
         let build = snake_ids
             .into_iter()
@@ -268,177 +150,1239 @@ pub trait SimulableGame<T: SimulatorInstruments, const N_SNAKES: usize>:
     /// simulates the next possible states for a a game with a given set of snakes and moves, producing a list of the new games,
     /// along with the moves that got to that position
     #[allow(clippy::type_complexity)]
-    fn simulate_with_moves<S>(
+    ///
+    /// May panic if a snake isn't alive or is given no moves, see
+    /// [SimulableGame::try_simulate_with_moves]. Any other panic is a bug in the simulator.
+    /// Every combination of the moves is simulated, which grows quickly with the number of
+    /// snakes, see [SimulableGame::simulate_with_moves_bounded] to refuse large ones
+    fn simulate_with_moves<'a, S>(
+        &'a self,
+        instruments: &'a T,
+        snake_ids_and_moves: impl IntoIterator<Item = (Self::SnakeIDType, S)>,
+    ) -> Box<dyn Iterator<Item = (Action<N_SNAKES>, Self)> + 'a>
+    where
+        S: Borrow<[Move]>,
+    {
+        let settings = self.simulation_settings();
+        self.simulate_with_moves_and_settings(instruments, snake_ids_and_moves, &settings)
+    }
+
+    /// The same as [SimulableGame::simulate_with_moves], playing by `settings` instead of this
+    /// games own [SimulableGame::simulation_settings]
+    #[allow(clippy::type_complexity)]
+    fn simulate_with_moves_and_settings<'a, S>(
+        &'a self,
+        instruments: &'a T,
+        snake_ids_and_moves: impl IntoIterator<Item = (Self::SnakeIDType, S)>,
+        settings: &SimulationSettings,
+    ) -> Box<dyn Iterator<Item = (Action<N_SNAKES>, Self)> + 'a>
+    where
+        S: Borrow<[Move]>;
+
+    /// the settings this game is simulated with by default
+    fn simulation_settings(&self) -> SimulationSettings;
+
+    /// The same as [SimulableGame::simulate_with_moves], but clears `out_states` and
+    /// `out_moves` and fills them with the results instead of returning them, the moves for
+    /// `out_states[i]` are at `out_moves[i]`. Reusing the same buffers for every node of a
+    /// search avoids allocating for each one, compact boards don't allocate at all once the
+    /// buffers are big enough
+    fn simulate_with_moves_into<S>(
         &self,
         instruments: &T,
         snake_ids_and_moves: impl IntoIterator<Item = (Self::SnakeIDType, S)>,
-    ) -> Box<dyn Iterator<Item = (Action<N_SNAKES>, Self)> + '_>
+        out_states: &mut Vec<Self>,
+        out_moves: &mut Vec<Action<N_SNAKES>>,
+    ) where
+        S: Borrow<[Move]>,
+    {
+        out_states.clear();
+        out_moves.clear();
+        let results = self.simulate_with_moves(instruments, snake_ids_and_moves);
+        let (lower, _) = results.size_hint();
+        out_states.reserve(lower);
+        out_moves.reserve(lower);
+        for (action, state) in results {
+            out_moves.push(action);
+            out_states.push(state);
+        }
+    }
+
+    /// The same as [SimulableGame::simulate_with_moves], leaving out every combination with a
+    /// move that `pruner` prunes, see [crate::pruning]. A snake that has all of its moves pruned
+    /// is simulated with only the first of them, since it still has to make one
+    #[allow(clippy::type_complexity)]
+    fn simulate_with_moves_pruned<'a, S, P>(
+        &'a self,
+        instruments: &'a T,
+        snake_ids_and_moves: impl IntoIterator<Item = (Self::SnakeIDType, S)>,
+        pruner: &P,
+    ) -> Box<dyn Iterator<Item = (Action<N_SNAKES>, Self)> + 'a>
     where
-        S: Borrow<[Move]>;
+        S: Borrow<[Move]>,
+        P: MovePruner<Self>,
+        Self: HeadGettableGame + NeighborDeterminableGame,
+    {
+        let pruned = snake_ids_and_moves
+            .into_iter()
+            .map(|(sid, moves)| {
+                let moves = moves.borrow();
+                let head = self.get_head_as_native_position(&sid);
+                let destinations = self.possible_moves(&head).collect::<Vec<_>>();
+                let mut kept = moves
+                    .iter()
+                    .copied()
+                    .filter(|mv| {
+                        // moves off the board have nowhere to go, the simulator deals with them
+                        !destinations
+                            .iter()
+                            .any(|(other, pos)| other == mv && pruner.prune(self, &sid, *mv, pos))
+                    })
+                    .collect::<Vec<_>>();
+                if kept.is_empty() {
+                    kept.extend(moves.first().copied());
+                }
+                (sid, kept)
+            })
+            .collect::<Vec<_>>();
+        self.simulate_with_moves(instruments, pruned)
+    }
+
+    /// The same as [SimulableGame::simulate], but returns an error instead of panicking if any
+    /// of the snakes aren't alive
+    #[allow(clippy::type_complexity)]
+    fn try_simulate<'a>(
+        &'a self,
+        instruments: &'a T,
+        snake_ids: Vec<Self::SnakeIDType>,
+    ) -> Result<Box<dyn Iterator<Item = (Action<N_SNAKES>, Self)> + 'a>, Error> {
+        let moves_to_simulate = Move::all();
+        let build = snake_ids
+            .into_iter()
+            .map(|s| (s, moves_to_simulate.as_slice()));
+        self.try_simulate_with_moves(instruments, build)
+    }
+
+    /// The same as [SimulableGame::simulate_with_moves], but returns an error instead of
+    /// panicking if any of the snakes aren't alive, are given no moves, or are listed more than
+    /// once
+    #[allow(clippy::type_complexity)]
+    fn try_simulate_with_moves<'a, S>(
+        &'a self,
+        instruments: &'a T,
+        snake_ids_and_moves: impl IntoIterator<Item = (Self::SnakeIDType, S)>,
+    ) -> Result<Box<dyn Iterator<Item = (Action<N_SNAKES>, Self)> + 'a>, Error>
+    where
+        S: Borrow<[Move]>,
+    {
+        let alive = self.get_snake_ids();
+        let snake_ids_and_moves = snake_ids_and_moves.into_iter().collect::<Vec<_>>();
+        for (idx, (sid, moves)) in snake_ids_and_moves.iter().enumerate() {
+            if !alive.contains(sid) {
+                return Err(Error::UnknownSnake(format!("{:?}", sid)));
+            }
+            if moves.borrow().is_empty() {
+                #[cfg(any(test, feature = "tracing"))]
+                tracing::warn!(snake = ?sid, "asked to simulate a snake with no moves");
+                return Err(Error::InvalidMoves(format!("{:?} has no moves", sid)));
+            }
+            if snake_ids_and_moves[..idx]
+                .iter()
+                .any(|(other, _)| other == sid)
+            {
+                return Err(Error::InvalidMoves(format!("{:?} is listed twice", sid)));
+            }
+        }
+        Ok(self.simulate_with_moves(instruments, snake_ids_and_moves))
+    }
+
+    /// The same as [SimulableGame::try_simulate_with_moves], but collects the results, and
+    /// errors with [Error::TooManyStates] before simulating anything if there are more than
+    /// `max_states` combinations of moves. The count is the product of the number of moves
+    /// given for each snake, so it is an upper bound: moves that kill a snake get dropped while
+    /// simulating. To simulate without a bound use the lazy iterator from
+    /// [SimulableGame::simulate_with_moves] and consume only as much as you need
+    #[allow(clippy::type_complexity)]
+    fn simulate_with_moves_bounded<S>(
+        &self,
+        instruments: &T,
+        snake_ids_and_moves: impl IntoIterator<Item = (Self::SnakeIDType, S)>,
+        max_states: usize,
+    ) -> Result<Vec<(Action<N_SNAKES>, Self)>, Error>
+    where
+        S: Borrow<[Move]>,
+    {
+        let snake_ids_and_moves = snake_ids_and_moves.into_iter().collect::<Vec<_>>();
+        let states = snake_ids_and_moves
+            .iter()
+            .fold(1usize, |states, (_, moves)| {
+                states.saturating_mul(moves.borrow().len())
+            });
+        if states > max_states {
+            return Err(Error::TooManyStates { states, max_states });
+        }
+        Ok(self
+            .try_simulate_with_moves(instruments, snake_ids_and_moves)?
+            .collect())
+    }
+
+    /// The one game that comes from every living snake making exactly the move given for it,
+    /// with a full turn of eating, starving and collisions, e.g. to replay a recorded game.
+    /// Unlike [SimulableGame::simulate_with_moves] nothing is left out: every living snake has
+    /// to be given one move, and snakes that are dead or not in the game can't be. A move that
+    /// kills a snake is applied like any other. The moves can be [SnakeMove]s or
+    /// `(snake_id, mv)` tuples
+    fn advance_turn_with_moves(
+        &self,
+        instruments: &T,
+        moves: impl IntoIterator<Item = impl Into<SnakeMove<Self::SnakeIDType>>>,
+    ) -> Result<Self, Error> {
+        let moves = moves.into_iter().map(Into::into).collect::<Vec<_>>();
+        let snake_ids = moves
+            .iter()
+            .map(|snake_move| snake_move.snake_id.clone())
+            .collect::<Vec<_>>();
+        check_one_move_each(self, &snake_ids)?;
+        let (_, next) = self
+            .simulate_with_moves(
+                instruments,
+                moves.iter().map(|snake_move| {
+                    (
+                        snake_move.snake_id.clone(),
+                        core::slice::from_ref(&snake_move.mv),
+                    )
+                }),
+            )
+            .next()
+            .expect("one move for each snake has one result");
+        Ok(next)
+    }
 }
 
-/// A game where positions can be checked for hazards
-pub trait HazardQueryableGame: PositionGettableGame {
-    /// Is this position a hazard?
-    fn is_hazard(&self, pos: &Self::NativePositionType) -> bool;
+/// errors unless there is exactly one of `snake_ids` for each living snake in `game`
+pub(crate) fn check_one_move_each<G: SnakeIDGettableGame>(
+    game: &G,
+    snake_ids: &[G::SnakeIDType],
+) -> Result<(), Error> {
+    let alive = game.get_snake_ids();
+    for (idx, sid) in snake_ids.iter().enumerate() {
+        if !alive.contains(sid) {
+            return Err(Error::UnknownSnake(format!("{:?}", sid)));
+        }
+        if snake_ids[..idx].contains(sid) {
+            return Err(Error::InvalidMoves(format!("{:?} is listed twice", sid)));
+        }
+    }
+    if let Some(missing) = alive.iter().find(|sid| !snake_ids.contains(sid)) {
+        return Err(Error::InvalidMoves(format!("{:?} has no move", missing)));
+    }
+    Ok(())
+}
 
-    /// how much damage do hazards do?
-    fn get_hazard_damage(&self) -> u8;
+/// A move picked for a snake by [RandomReasonableMovesGame]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ReasonableMove {
+    /// the move that was picked
+    pub mv: Move,
+    /// true if no move was reasonable for this snake, and `mv` is only the least bad option.
+    /// A snake with a forced move will almost certainly be eliminated this turn
+    pub forced: bool,
 }
 
-/// A game where positions can be checked for food
-pub trait FoodQueryableGame: PositionGettableGame {
-    /// Is this position a food?
-    fn is_food(&self, pos: &Self::NativePositionType) -> bool;
+/// How picky [RandomReasonableMovesGame] is when picking moves
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ReasonableMoveLevel {
+    /// rule out moves that are immediately fatal
+    Basic,
+    /// additionally rule out moves in to pockets with fewer free squares than the snake is long.
+    /// Body pieces are counted as free if they will have moved out of the way by the time the
+    /// snake gets to them. The flood fill stops as soon as enough space has been found, so the
+    /// cost is bounded by the snakes length. If every move fails this check, this falls back to
+    /// `Basic`
+    SpaceAware,
 }
 
-/// A game where positions can be checked to see if they are a certain snakes Neck piece
+/// a game for which random reasonable moves for a given snake can be determined. e.g. do not collide with yourself
 ///
-/// A neck is defined as the piece that comes immediately after the head of a snake. If the snake
-/// is fully triple stacked it has no neck piece.
-pub trait NeckQueryableGame: PositionGettableGame + SnakeIDGettableGame {
-    /// Is this position a neck for the given snake?
-    fn is_neck(&self, sid: &Self::SnakeIDType, pos: &Self::NativePositionType) -> bool;
-}
+/// A move is reasonable if it stays on the board and does not run into a snake body. Tails which
+/// will vacate this turn (e.g. the snake did not just eat) are not considered blocking.
+/// When a snake has no reasonable move it is given the least bad move instead: a random move
+/// that stays on the board and doesn't reverse into its own neck, falling back to any move that
+/// doesn't reverse into its neck if the snake is cornered against a wall.
+///
+/// Snakes which have been eliminated are skipped, so exactly one move is yielded for each living
+/// snake.
+///
+/// Wire games also rule out moves on to hazards that would take all of a snakes health, and
+/// prefer hazards that heal when the snake is below its max health.
+pub trait RandomReasonableMovesGame: SnakeIDGettableGame {
+    /// get a random reasonable move for each snake, see the trait docs for the fallback when no
+    /// move is reasonable
+    fn random_reasonable_move_for_each_snake<'a>(
+        &'a self,
+        rng: &'a mut impl Rng,
+    ) -> Box<dyn Iterator<Item = SnakeMove<Self::SnakeIDType>> + 'a> {
+        Box::new(
+            self.random_reasonable_move_with_status_for_each_snake(rng)
+                .map(|(sid, reasonable)| SnakeMove::new(sid, reasonable.mv)),
+        )
+    }
 
-/// A game where positions can have their hazards set and cleared
-pub trait HazardSettableGame: PositionGettableGame {
-    /// make this position a hazard
-    fn set_hazard(&mut self, pos: Self::NativePositionType);
+    /// like `random_reasonable_move_for_each_snake`, but reports whether each move was forced
+    /// because no reasonable move existed
+    fn random_reasonable_move_with_status_for_each_snake<'a>(
+        &'a self,
+        rng: &'a mut impl Rng,
+    ) -> Box<dyn Iterator<Item = (Self::SnakeIDType, ReasonableMove)> + 'a> {
+        self.random_reasonable_move_at_level_for_each_snake(rng, ReasonableMoveLevel::Basic)
+    }
 
-    /// clear this position of being a hazard
-    fn clear_hazard(&mut self, pos: Self::NativePositionType);
+    /// get a random reasonable move for each snake, using the given level of filtering
+    fn random_reasonable_move_at_level_for_each_snake<'a>(
+        &'a self,
+        rng: &'a mut impl Rng,
+        level: ReasonableMoveLevel,
+    ) -> Box<dyn Iterator<Item = (Self::SnakeIDType, ReasonableMove)> + 'a>;
 }
 
-/// A game for which board positions can be identified and returned
-pub trait PositionGettableGame {
-    /// the native position type for this board
-    type NativePositionType: Eq + Hash + Clone + Ord + PartialOrd + Debug;
+/// a game where the random reasonable moves can be biased by the caller, e.g. to have opponents
+/// in a rollout head for food when they're hungry
+pub trait WeightedReasonableMovesGame: RandomReasonableMovesGame + PositionGettableGame {
+    /// Picks a reasonable move for each living snake, the same moves as
+    /// `random_reasonable_move_with_status_for_each_snake`, but in proportion to
+    /// `weight(snake_id, move, destination)` instead of uniformly. Zero, negative and NaN weights
+    /// rule a move out, and if every reasonable move is ruled out one of them is picked
+    /// uniformly. Snakes without a reasonable move get the same forced move as before, and
+    /// `weight` isn't called for them. The weights replace any preferences of the game's own,
+    /// e.g. wire games preferring hazards that heal
+    fn random_reasonable_move_weighted<'a, W>(
+        &'a self,
+        rng: &'a mut impl Rng,
+        weight: W,
+    ) -> Box<dyn Iterator<Item = (Self::SnakeIDType, ReasonableMove)> + 'a>
+    where
+        W: FnMut(&Self::SnakeIDType, Move, &Self::NativePositionType) -> f64 + 'a;
+}
 
-    /// Check if the given position is a snake body
-    fn position_is_snake_body(&self, pos: Self::NativePositionType) -> bool;
+/// picks one of `weighted` in proportion to its weight for [WeightedReasonableMovesGame], None
+/// if there are no moves to pick from
+pub(crate) fn choose_weighted(rng: &mut impl Rng, weighted: &[(Move, f64)]) -> Option<Move> {
+    let usable = || weighted.iter().filter(|(_, weight)| *weight > 0.0);
+    let total: f64 = usable().map(|(_, weight)| weight).sum();
+    if total.is_infinite() {
+        return usable()
+            .filter(|(_, weight)| weight.is_infinite())
+            .map(|(mv, _)| *mv)
+            .choose(rng);
+    }
+    if total > 0.0 {
+        let mut target = rng.gen_range(0.0..total);
+        for (mv, weight) in usable() {
+            if target < *weight {
+                return Some(*mv);
+            }
+            target -= weight;
+        }
+        // rounding can leave a sliver past the last weight
+        return usable().next_back().map(|(mv, _)| *mv);
+    }
+    weighted.iter().map(|(mv, _)| *mv).choose(rng)
+}
 
-    /// Convert a position to the native type
-    fn position_from_native(&self, native: Self::NativePositionType) -> Position;
+/// a game for which the squares each snake's head could be on next turn can be determined
+///
+/// A snake can move on to any square next to its head, wrapping around the edges of wrapped
+/// boards, except back on to its own neck. Nothing else is ruled out, so moves that run in to a
+/// body or a hazard are included. The reasonable moves of [RandomReasonableMovesGame] are always a
+/// subset of these.
+pub trait HeadSquaresGame:
+    NeighborDeterminableGame + NeckQueryableGame + HeadGettableGame + HealthGettableGame
+{
+    /// the squares snake `id`'s head could be on next turn, and the move that gets it there.
+    /// Empty if the snake has been eliminated
+    fn possible_head_squares(
+        &self,
+        id: &Self::SnakeIDType,
+    ) -> MoveSetPositions<Self::NativePositionType> {
+        let mut squares = MoveSetPositions::empty();
+        if self.get_health_i64(id) <= 0 {
+            return squares;
+        }
+        let head = self.get_head_as_native_position(id);
+        for (mv, pos) in self.possible_moves(&head) {
+            if !self.is_neck(id, &pos) {
+                squares.insert(mv, pos);
+            }
+        }
+        squares
+    }
 
-    /// Convert a position to the native type
-    fn native_from_position(&self, pos: Position) -> Self::NativePositionType;
+    /// every square any snake's head could be on next turn, with the snakes that could get
+    /// there, see [HeadSquaresGame::possible_head_squares]
+    fn all_threat_squares(&self) -> HashMap<Position, Vec<Self::SnakeIDType>> {
+        let mut threats: HashMap<Position, Vec<Self::SnakeIDType>> = HashMap::new();
+        for id in self.get_snake_ids() {
+            for (_, pos) in self.possible_head_squares(&id).iter() {
+                threats
+                    .entry(self.position_from_native(pos.clone()))
+                    .or_default()
+                    .push(id.clone());
+            }
+        }
+        threats
+    }
+}
 
-    /// checks if a given position is not on this board
-    fn off_board(&self, pos: Position) -> bool;
+/// Options for [FloodFillableGame]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct FloodFillOptions {
+    /// treat hazard squares as if they were walls
+    pub hazards_block: bool,
+    /// only count squares that can be reached in at most this many moves, None for no limit
+    pub max_depth: Option<usize>,
 }
 
-/// A game for which the head of the current snake can be got.
-pub trait HeadGettableGame: PositionGettableGame + SnakeIDGettableGame {
-    /// get the head position for a given snake id, as a position struct (slow for simulation)
-    fn get_head_as_position(
+/// a game for which the squares reachable from a given position can be determined
+///
+/// Snake bodies block, but a body piece is reachable if it will have moved out of the way by the
+/// time we could first get to it, assuming no snake eats. On wrapped boards the fill wraps around
+/// the edges of the board.
+pub trait FloodFillableGame: PositionGettableGame {
+    /// all positions reachable from `start`, not including `start` itself. They are sorted in the
+    /// order squares are indexed, a row at a time from the bottom row up (see
+    /// [Position::to_flat_index](crate::wire_representation::Position::to_flat_index)), so every
+    /// representation returns them in the same order
+    fn flood_fill(
         &self,
-        snake_id: &Self::SnakeIDType,
-    ) -> crate::wire_representation::Position;
+        start: &Self::NativePositionType,
+        options: FloodFillOptions,
+    ) -> Vec<Self::NativePositionType>;
 
-    /// get the head position for a given snake as some "native" type for this game
-    fn get_head_as_native_position(&self, snake_id: &Self::SnakeIDType)
-        -> Self::NativePositionType;
+    /// the number of positions reachable from `start`, not including `start` itself
+    fn flood_fill_count(
+        &self,
+        start: &Self::NativePositionType,
+        options: FloodFillOptions,
+    ) -> usize {
+        self.flood_fill(start, options).len()
+    }
 }
 
-/// A game for which the food on the board can be queries
-pub trait FoodGettableGame: PositionGettableGame + SnakeIDGettableGame {
-    /// get the head position for a given snake id, as a position struct (slow for simulation)
-    fn get_all_food_as_positions(&self) -> Vec<crate::wire_representation::Position>;
+/// How [VoronoiGame] assigns a square that several snakes reach on the same turn
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum VoronoiTieBreak {
+    /// contested squares belong to nobody
+    Nobody,
+    /// contested squares go to the longest snake reaching them, and to nobody if the longest
+    /// snakes are the same length
+    #[default]
+    LongerSnake,
+}
 
-    /// get the head position for a given snake as some "native" type for this game
-    fn get_all_food_as_native_positions(&self) -> Vec<Self::NativePositionType>;
+/// Options for [VoronoiGame]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct VoronoiOptions {
+    /// how squares reached by several snakes at once are assigned
+    pub tie_break: VoronoiTieBreak,
+    /// treat hazard squares as if they were walls
+    pub hazards_block: bool,
 }
 
-/// A game for which the length of the current snake can be got.
-pub trait LengthGettableGame: SnakeIDGettableGame {
-    /// the length type for this game
-    type LengthType: Ord + PartialOrd;
+/// a game for which the squares each snake controls can be determined
+///
+/// A square is controlled by the snake whose head can reach it first. All snakes are expanded
+/// together one move at a time, and squares are passable by the same rules as
+/// [FloodFillableGame].
+pub trait VoronoiGame: SnakeIDGettableGame + PositionGettableGame {
+    /// the number of squares controlled by each living snake, not counting its head
+    fn voronoi(&self, options: VoronoiOptions) -> HashMap<Self::SnakeIDType, usize>;
+
+    /// the snake controlling each square and the turn its head first gets there, with each
+    /// living snake's head at 0. Squares nobody controls are absent
+    fn voronoi_owners(
+        &self,
+        options: VoronoiOptions,
+    ) -> HashMap<Self::NativePositionType, (Self::SnakeIDType, u32)>;
+}
 
-    /// get the length for a given snake
-    fn get_length(&self, snake_id: &Self::SnakeIDType) -> Self::LengthType;
+/// Options for [PathfindableGame]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct AStarOptions {
+    /// treat every snake piece as a wall, rather than passable once it will have moved out of
+    /// the way
+    pub bodies_block: bool,
+    /// treat hazard squares as if they were walls
+    pub hazards_block: bool,
+    /// the extra cost of moving on to a hazard square, when hazards don't block
+    pub hazard_cost: u32,
+}
 
-    /// get the length for a given snake
-    fn get_length_i64(&self, snake_id: &Self::SnakeIDType) -> i64;
+/// a game for which paths between positions can be found
+///
+/// Squares are passable by the same rules as simulation: a snake piece is passable if it will
+/// have moved out of the way (assuming no snake eats) by the time the path gets there, and paths
+/// wrap around the edges of wrapped boards.
+pub trait PathfindableGame: PositionGettableGame {
+    /// the cheapest sequence of moves from `from` to `to` using A*, or None if there is no path.
+    /// Every move costs one, plus `hazard_cost` if it lands on a hazard
+    fn a_star(
+        &self,
+        from: &Self::NativePositionType,
+        to: &Self::NativePositionType,
+        options: AStarOptions,
+    ) -> Option<Vec<Move>>;
+
+    /// the cost of the cheapest path from `from` to every reachable position (including `from`
+    /// itself at 0), by the same rules as `a_star`. With a `hazard_cost` these are costs rather
+    /// than numbers of moves, a square behind a hazard can cost more than one the long way round.
+    /// Positions that can't be reached are absent
+    fn cost_map(
+        &self,
+        from: &Self::NativePositionType,
+        options: AStarOptions,
+    ) -> HashMap<Self::NativePositionType, u32>;
+
+    /// the number of moves on the shortest path from `from` to every reachable position
+    /// (including `from` itself at 0), a breadth first search by the same passability rules as
+    /// `a_star`. `hazard_cost` is ignored, see `cost_map` to weigh hazards. Positions that can't
+    /// be reached are absent
+    fn distance_map(
+        &self,
+        from: &Self::NativePositionType,
+        options: AStarOptions,
+    ) -> HashMap<Self::NativePositionType, u32> {
+        let unweighted = AStarOptions {
+            hazard_cost: 0,
+            ..options
+        };
+        self.cost_map(from, unweighted)
+    }
 }
 
-/// A game for which the health of the current snake can be got.
-pub trait HealthGettableGame: SnakeIDGettableGame {
-    /// the health type for this game
-    type HealthType: PartialEq;
+/// How a race to a piece of food looks, see [FoodRaceGame]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FoodRace<P> {
+    /// where the food is
+    pub position: P,
+    /// the cost of our cheapest path to the food, None if we can't reach it
+    pub my_distance: Option<u32>,
+    /// the cost of the cheapest path to the food for any opponent, None if none of them can
+    /// reach it
+    pub best_opponent_distance: Option<u32>,
+    /// true if we get there first, or at the same time as the closest opponents while being
+    /// longer than all of them
+    pub winnable: bool,
+}
 
-    /// A constant that defines what zero health means for the given game
-    const ZERO: Self::HealthType;
+/// A piece of food we can get to no later than an opponent, to keep it from them, see
+/// [FoodRaceGame::food_denial_opportunities]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FoodDenial<P, S> {
+    /// where the food is
+    pub food: P,
+    /// the opponent we can keep it from
+    pub opponent: S,
+    /// the cost of the opponent's cheapest path to the food
+    pub their_distance: u32,
+    /// the cost of our cheapest path to the food, never more than `their_distance`
+    pub my_distance: u32,
+    /// the opponent's health now
+    pub their_health: i64,
+}
 
-    /// get the health for a given snake
-    fn get_health(&self, snake_id: &Self::SnakeIDType) -> Self::HealthType;
+impl<P, S> FoodDenial<P, S> {
+    /// how badly the opponent needs the food, lower is more urgent: their health less how far
+    /// they are from it
+    pub fn urgency(&self) -> i64 {
+        self.their_health - self.their_distance as i64
+    }
+}
 
-    /// get the health for a given snake as an i64
-    fn get_health_i64(&self, snake_id: &Self::SnakeIDType) -> i64;
+/// a game for which we can work out which food we can get to before our opponents
+pub trait FoodRaceGame:
+    PathfindableGame
+    + FoodGettableGame
+    + HeadGettableGame
+    + HealthGettableGame
+    + LengthGettableGame
+    + YouDeterminableGame
+{
+    /// races every living snake to every piece of food, using [PathfindableGame::cost_map]
+    /// with the given options (e.g. to include the cost of crossing hazards). Sorted by our
+    /// distance, with food we can't reach last
+    fn food_race_analysis(&self, options: AStarOptions) -> Vec<FoodRace<Self::NativePositionType>> {
+        let you = self.you_id();
+        let my_distances = self.cost_map(&self.get_head_as_native_position(you), options);
+        let opponents = self
+            .get_snake_ids()
+            .into_iter()
+            .filter(|sid| sid != you && self.is_alive(sid))
+            .map(|sid| {
+                let distances = self.cost_map(&self.get_head_as_native_position(&sid), options);
+                (self.get_length_i64(&sid), distances)
+            })
+            .collect::<Vec<_>>();
+        let my_length = self.get_length_i64(you);
+
+        let mut races = self
+            .get_all_food_as_native_positions()
+            .into_iter()
+            .map(|position| {
+                let my_distance = my_distances.get(&position).copied();
+                let best_opponent_distance = opponents
+                    .iter()
+                    .filter_map(|(_, distances)| distances.get(&position).copied())
+                    .min();
+                let winnable = match (my_distance, best_opponent_distance) {
+                    (None, _) => false,
+                    (Some(_), None) => true,
+                    (Some(mine), Some(theirs)) => {
+                        mine < theirs
+                            || (mine == theirs
+                                && opponents.iter().all(|(length, distances)| {
+                                    distances.get(&position) != Some(&theirs) || my_length > *length
+                                }))
+                    }
+                };
+                FoodRace {
+                    position,
+                    my_distance,
+                    best_opponent_distance,
+                    winnable,
+                }
+            })
+            .collect::<Vec<_>>();
+        races.sort_by_key(|race| race.my_distance.unwrap_or(u32::MAX));
+        races
+    }
 
-    /// check wheterh a given snake is alive
-    fn is_alive(&self, snake_id: &Self::SnakeIDType) -> bool {
-        self.get_health(snake_id) != Self::ZERO
+    /// Every food and living opponent where `my_id` can get to the food at the same time as the
+    /// opponent or sooner, so the food can be taken or contested before they eat it, whether or
+    /// not we need it. Distances come from [PathfindableGame::cost_map] with the given
+    /// options. Sorted by [FoodDenial::urgency], the hungriest opponents nearest their food first
+    fn food_denial_opportunities(
+        &self,
+        my_id: &Self::SnakeIDType,
+        options: AStarOptions,
+    ) -> Vec<FoodDenial<Self::NativePositionType, Self::SnakeIDType>> {
+        if !self.is_alive(my_id) {
+            return vec![];
+        }
+        let my_distances = self.cost_map(&self.get_head_as_native_position(my_id), options);
+        let food = self.get_all_food_as_native_positions();
+        let mut denials = vec![];
+        for sid in self.get_snake_ids() {
+            if sid == *my_id || !self.is_alive(&sid) {
+                continue;
+            }
+            let their_distances = self.cost_map(&self.get_head_as_native_position(&sid), options);
+            let their_health = self.get_health_i64(&sid);
+            for position in &food {
+                if let (Some(mine), Some(theirs)) =
+                    (my_distances.get(position), their_distances.get(position))
+                {
+                    if mine <= theirs {
+                        denials.push(FoodDenial {
+                            food: position.clone(),
+                            opponent: sid.clone(),
+                            their_distance: *theirs,
+                            my_distance: *mine,
+                            their_health,
+                        });
+                    }
+                }
+            }
+        }
+        denials.sort_by_key(FoodDenial::urgency);
+        denials
     }
 }
 
-/// a game for which random reasonable moves for a given snake can be determined. e.g. do not collide with yourself
-pub trait RandomReasonableMovesGame: SnakeIDGettableGame {
-    #[allow(missing_docs)]
-    fn random_reasonable_move_for_each_snake<'a>(
-        &'a self,
-        rng: &'a mut impl Rng,
-    ) -> Box<dyn Iterator<Item = (Self::SnakeIDType, Move)> + 'a>;
+/// A group of empty squares that can only be reached through a single square, see
+/// [ChokePointGame]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct RegionInfo<P> {
+    /// the square every path in to the region goes through
+    pub choke_point: P,
+    /// the number of empty squares in the region, not counting the choke point
+    pub region_size: usize,
 }
 
-/// a game for which the neighbors of a given Position can be determined
-pub trait NeighborDeterminableGame: PositionGettableGame {
-    /// returns the neighboring positions
-    fn neighbors<'a>(
-        &'a self,
-        pos: &Self::NativePositionType,
-    ) -> Box<dyn Iterator<Item = Self::NativePositionType> + 'a>;
+/// a game for which we can find the dead ends that an opponent could seal off
+pub trait ChokePointGame:
+    NeighborDeterminableGame + HeadGettableGame + SizeDeterminableGame
+{
+    /// For every empty square that can only be reached from the main open area (the largest
+    /// connected group of empty squares) through a single square, the largest such region it is
+    /// in and the square that cuts it off. Squares are empty if no snake is on them right now,
+    /// bodies moving out of the way are not taken in to account. The regions are found from the
+    /// articulation points of the graph of empty squares.
+    fn choke_points(
+        &self,
+    ) -> HashMap<Self::NativePositionType, RegionInfo<Self::NativePositionType>> {
+        let heads = self
+            .get_snake_ids()
+            .iter()
+            .map(|sid| self.get_head_as_native_position(sid))
+            .collect::<Vec<_>>();
+        let mut squares = vec![];
+        for y in 0..self.get_height() {
+            for x in 0..self.get_width() {
+                let pos = self.native_from_position(Position::new(x as i32, y as i32));
+                if !self.position_is_snake_body(pos.clone()) && !heads.contains(&pos) {
+                    squares.push(pos);
+                }
+            }
+        }
+        let index = squares
+            .iter()
+            .cloned()
+            .enumerate()
+            .map(|(i, pos)| (pos, i))
+            .collect::<HashMap<_, _>>();
+        let adjacency = squares
+            .iter()
+            .map(|pos| {
+                let mut neighbors = self
+                    .neighbors(pos)
+                    .filter_map(|neighbor| index.get(&neighbor).copied())
+                    .collect::<Vec<_>>();
+                // on tiny wrapped boards the same square can be a neighbor more than once
+                neighbors.sort_unstable();
+                neighbors.dedup();
+                neighbors
+            })
+            .collect::<Vec<_>>();
+
+        // Tarjan's algorithm, iteratively. `order` holds the squares in the order they were
+        // discovered, so every subtree of the search is a contiguous range of it
+        let mut discovered = vec![usize::MAX; squares.len()];
+        let mut low = vec![0; squares.len()];
+        let mut parent = vec![usize::MAX; squares.len()];
+        let mut subtree_size = vec![1; squares.len()];
+        let mut order = vec![];
+        let mut main_area = 0..0;
+        for root in 0..squares.len() {
+            if discovered[root] != usize::MAX {
+                continue;
+            }
+            let start = order.len();
+            discovered[root] = start;
+            low[root] = start;
+            order.push(root);
+            let mut stack = vec![(root, 0)];
+            while let Some((v, next)) = stack.last_mut() {
+                let v = *v;
+                if let Some(&w) = adjacency[v].get(*next) {
+                    *next += 1;
+                    if discovered[w] == usize::MAX {
+                        parent[w] = v;
+                        discovered[w] = order.len();
+                        low[w] = order.len();
+                        order.push(w);
+                        stack.push((w, 0));
+                    } else if w != parent[v] {
+                        low[v] = low[v].min(discovered[w]);
+                    }
+                } else {
+                    stack.pop();
+                    let u = parent[v];
+                    if u != usize::MAX {
+                        low[u] = low[u].min(low[v]);
+                        subtree_size[u] += subtree_size[v];
+                    }
+                }
+            }
+            if order.len() - start > main_area.len() {
+                main_area = start..order.len();
+            }
+        }
 
-    /// returns the neighboring positions, and the Move required to get to each
-    fn possible_moves<'a>(
-        &'a self,
+        let mut regions: HashMap<usize, RegionInfo<usize>> = HashMap::new();
+        let mut record = |cells: &mut dyn Iterator<Item = usize>, choke_point, region_size| {
+            for cell in cells {
+                let region = regions.entry(cell).or_insert(RegionInfo {
+                    choke_point,
+                    region_size,
+                });
+                if region_size > region.region_size {
+                    *region = RegionInfo {
+                        choke_point,
+                        region_size,
+                    };
+                }
+            }
+        };
+        for &v in &order[main_area.clone()] {
+            let u = parent[v];
+            if u == usize::MAX || low[v] < discovered[u] {
+                continue;
+            }
+            // removing u cuts the subtree under v off from the rest of the area, whichever side
+            // is smaller is the region
+            let subtree = discovered[v]..discovered[v] + subtree_size[v];
+            let rest = main_area.len() - 1 - subtree.len();
+            if subtree.len() <= rest {
+                record(
+                    &mut order[subtree.clone()].iter().copied(),
+                    u,
+                    subtree.len(),
+                );
+            }
+            if rest <= subtree.len() && rest > 0 {
+                record(
+                    &mut order[main_area.clone()]
+                        .iter()
+                        .copied()
+                        .filter(|cell| *cell != u && !subtree.contains(&discovered[*cell])),
+                    u,
+                    rest,
+                );
+            }
+        }
+
+        regions
+            .into_iter()
+            .map(|(cell, region)| {
+                (
+                    squares[cell].clone(),
+                    RegionInfo {
+                        choke_point: squares[region.choke_point].clone(),
+                        region_size: region.region_size,
+                    },
+                )
+            })
+            .collect()
+    }
+}
+
+/// Whether a square will be free of the snakes that are on it now, see [FutureOccupancyGame]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SquareSafety {
+    /// no snake piece will be here, even if snakes eat
+    Safe,
+    /// a snake piece will still be here, even if no snake eats
+    Occupied,
+    /// the square will be free, unless the snake on it eats enough food to hold its tail back
+    DependsOnFood,
+}
+
+/// a game for which we can tell when the snake pieces on the board will move out of the way
+pub trait FutureOccupancyGame:
+    SnakeBodyGettableGame + HealthGettableGame + FoodGettableGame + NeighborDeterminableGame
+{
+    /// For every square holding a snake piece, the number of turns until it is free, assuming
+    /// no snake eats. Stacked pieces take a turn each to leave
+    fn turns_until_vacated(&self) -> HashMap<Self::NativePositionType, u32> {
+        let mut vacated = HashMap::new();
+        for sid in self.get_snake_ids() {
+            if !self.is_alive(&sid) {
+                continue;
+            }
+            let body = self.get_snake_body_vec(&sid);
+            for (i, pos) in body.iter().enumerate() {
+                let turns = vacated.entry(pos.clone()).or_insert(0);
+                *turns = (*turns).max((body.len() - i) as u32);
+            }
+        }
+        vacated
+    }
+
+    /// Whether `pos` will be free of the pieces that are on it now, `turns_from_now` turns from
+    /// now. A piece `n` from the end of a snake (counting the tail as 1) leaves after `n` turns,
+    /// but each food the snake eats before then holds it back a turn, so if the snake has enough
+    /// food within reach of its head the answer is [SquareSafety::DependsOnFood]. Snakes that
+    /// move on to the square in the meantime are not taken in to account
+    fn will_be_unoccupied(
+        &self,
         pos: &Self::NativePositionType,
-    ) -> Box<dyn Iterator<Item = (Move, Self::NativePositionType)> + 'a>;
+        turns_from_now: u32,
+    ) -> SquareSafety {
+        let vacates_after = match self.turns_until_vacated().get(pos) {
+            Some(turns) => *turns,
+            None => return SquareSafety::Safe,
+        };
+        let mut safety = SquareSafety::Safe;
+        for sid in self.get_snake_ids() {
+            if !self.is_alive(&sid) {
+                continue;
+            }
+            let body = self.get_snake_body_vec(&sid);
+            if !body.contains(pos) {
+                continue;
+            }
+            if vacates_after > turns_from_now {
+                return SquareSafety::Occupied;
+            }
+
+            // food eaten on the last turn is too late to hold the tail back
+            let needed = turns_from_now - vacates_after + 1;
+            let reach = turns_from_now - 1;
+            let food = self.get_all_food_as_native_positions();
+            let mut seen = vec![body[0].clone()];
+            let mut frontier = seen.clone();
+            let mut food_in_reach = 0;
+            for _ in 0..reach {
+                let mut next = vec![];
+                for p in &frontier {
+                    for neighbor in self.neighbors(p) {
+                        if !seen.contains(&neighbor) {
+                            if food.contains(&neighbor) {
+                                food_in_reach += 1;
+                            }
+                            seen.push(neighbor.clone());
+                            next.push(neighbor);
+                        }
+                    }
+                }
+                frontier = next;
+            }
+            if food_in_reach >= needed {
+                safety = SquareSafety::DependsOnFood;
+            }
+        }
+        safety
+    }
+}
+
+/// Whether chasing a snake's own tail is sound, see [TailChaseGame]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct TailChaseAnalysis<P> {
+    /// the empty squares the head goes through to get to the tail, empty if the head is next to
+    /// the tail already, None if the tail can't be reached through empty squares
+    pub path: Option<Vec<P>>,
+    /// the number of squares in the loop, the path and the body, so how many turns it takes the
+    /// head to get back to where it is now. None if there is no path
+    pub cycle_length: Option<usize>,
+    /// whether the tail leaves its square next turn, which it doesn't when the snake has just
+    /// eaten
+    pub tail_vacates: bool,
+    /// whether there is food on the path, which would hold the tail back when it is eaten
+    pub food_on_path: bool,
+    /// the first turn from now on which another snake could be on a square of the loop before
+    /// the head gets there, or meet the head there and not be shorter
+    pub broken_at: Option<u32>,
+}
+
+impl<P> TailChaseAnalysis<P> {
+    /// whether chasing the tail around the loop is safe for as long as nothing new turns up,
+    /// like food spawning on the path
+    pub fn is_safe(&self) -> bool {
+        self.cycle_length.is_some()
+            && self.tail_vacates
+            && !self.food_on_path
+            && self.broken_at.is_none()
+    }
+}
+
+/// a game for which we can tell whether a snake can go round and round after its own tail
+pub trait TailChaseGame: FutureOccupancyGame + LengthGettableGame {
+    /// Whether `snake_id` can safely chase its tail: follow the shortest path through empty
+    /// squares to its tail, and then the body, round and round. That needs the tail to leave
+    /// every turn, so the snake mustn't have just eaten or have food on the path, and no other
+    /// snake to get in the way. Other snakes are assumed to be able to wait around until the
+    /// head is about to get to a square, and to be able to go over pieces of bodies that will
+    /// have moved out of the way by the time they get there. A snake can get in the way of a
+    /// square by being on it before the head, or on it at the same time if it isn't shorter
+    fn can_safely_chase_tail(
+        &self,
+        snake_id: &Self::SnakeIDType,
+    ) -> TailChaseAnalysis<Self::NativePositionType> {
+        let body = self.get_snake_body_vec(snake_id);
+        let mut analysis = TailChaseAnalysis {
+            path: None,
+            cycle_length: None,
+            tail_vacates: false,
+            food_on_path: false,
+            broken_at: None,
+        };
+        if !self.is_alive(snake_id) || body.len() < 2 {
+            return analysis;
+        }
+        let (head, tail) = (&body[0], &body[body.len() - 1]);
+        analysis.tail_vacates = body[body.len() - 2] != *tail;
+
+        let vacates = self.turns_until_vacated();
+        let living = self
+            .get_snake_ids()
+            .into_iter()
+            .filter(|sid| self.is_alive(sid))
+            .collect::<Vec<_>>();
+        let bodies = living
+            .iter()
+            .map(|sid| self.get_snake_body_vec(sid))
+            .collect::<Vec<_>>();
+
+        let path = {
+            // moving on to the neck is never allowed, even when it is the tail
+            let reaches_tail = |pos: &Self::NativePositionType| {
+                self.neighbors(pos)
+                    .any(|n| n == *tail && (pos != head || body[1] != *tail))
+            };
+            let mut came_from = HashMap::new();
+            let mut frontier = vec![head.clone()];
+            let mut found = None;
+            while found.is_none() && !frontier.is_empty() {
+                let mut next = vec![];
+                for pos in frontier {
+                    if reaches_tail(&pos) {
+                        found = Some(pos);
+                        break;
+                    }
+                    for neighbor in self.neighbors(&pos) {
+                        if !vacates.contains_key(&neighbor) && !came_from.contains_key(&neighbor) {
+                            came_from.insert(neighbor.clone(), pos.clone());
+                            next.push(neighbor);
+                        }
+                    }
+                }
+                frontier = next;
+            }
+            found.map(|mut pos| {
+                let mut path = vec![];
+                while pos != *head {
+                    let previous = came_from[&pos].clone();
+                    path.push(pos);
+                    pos = previous;
+                }
+                path.reverse();
+                path
+            })
+        };
+        let path = match path {
+            Some(path) => path,
+            None => return analysis,
+        };
+
+        // the turn the head first gets to each square of the loop
+        let food = self.get_all_food_as_native_positions();
+        let mut loop_squares = path.clone();
+        for pos in body.iter().rev() {
+            if loop_squares.last() != Some(pos) {
+                loop_squares.push(pos.clone());
+            }
+        }
+        let gap = path.len() as u32;
+        let cycle = loop_squares.len() as u32;
+        analysis.food_on_path = path.iter().any(|pos| food.contains(pos));
+        analysis.cycle_length = Some(loop_squares.len());
+        analysis.path = Some(path);
+
+        let my_length = self.get_length(snake_id);
+        for (sid, snake) in living.iter().zip(&bodies) {
+            if sid == snake_id {
+                continue;
+            }
+            let not_shorter = self.get_length(sid) >= my_length;
+            // the earliest each square can be got to, waiting for it to be left if need be
+            let start = match snake.first() {
+                Some(head) => head.clone(),
+                None => continue,
+            };
+            let mut arrivals = HashMap::new();
+            let mut queue = BinaryHeap::new();
+            queue.push(Reverse((0, start)));
+            while let Some(Reverse((turn, pos))) = queue.pop() {
+                if arrivals.contains_key(&pos) {
+                    continue;
+                }
+                for neighbor in self.neighbors(&pos) {
+                    if !arrivals.contains_key(&neighbor) {
+                        let free_from = vacates.get(&neighbor).copied().unwrap_or(0);
+                        queue.push(Reverse(((turn + 1).max(free_from), neighbor)));
+                    }
+                }
+                arrivals.insert(pos, turn);
+            }
+
+            for (i, pos) in loop_squares.iter().enumerate() {
+                let arrival = match arrivals.get(pos) {
+                    Some(arrival) if *arrival > 0 => *arrival,
+                    _ => continue,
+                };
+                // the head gets to the square on `enters` and every cycle after, and the square
+                // is empty for the `gap` turns before that
+                let enters = i as u32 + 1;
+                let cycles = arrival.saturating_sub(enters).div_ceil(cycle);
+                let head_gets_there = enters + cycles * cycle;
+                let turn = arrival.max(head_gets_there.saturating_sub(gap));
+                let broken_at = if turn < head_gets_there || not_shorter {
+                    Some(turn)
+                } else if gap > 0 {
+                    Some(head_gets_there + cycle - gap)
+                } else {
+                    None
+                };
+                analysis.broken_at = match (analysis.broken_at, broken_at) {
+                    (Some(a), Some(b)) => Some(a.min(b)),
+                    (a, b) => a.or(b),
+                };
+            }
+        }
+        analysis
+    }
 }
 
-/// a game for which each snakes shout can be determined
-pub trait ShoutGettableGame: SnakeIDGettableGame {
-    /// get the shout for a given snake, if they shouted this turn
-    fn get_shout(&self, snake_id: &Self::SnakeIDType) -> Option<String>;
+/// a game for which the move each snake made last turn can be worked out from its body
+///
+/// Requests don't say what moves were made, but a snake's neck is where its head was a turn
+/// ago, so the last move is the one from its neck to its head, across the edge of wrapped boards.
+/// Snakes that just ate are stacked at the tail, which doesn't get in the way.
+pub trait LastMoveDeterminableGame: SnakeBodyGettableGame + NeighborDeterminableGame {
+    /// the move `snake_id` made last turn, None on the first turn while it is still stacked on
+    /// its spawn, or if it has no body
+    fn last_move(&self, snake_id: &Self::SnakeIDType) -> Option<Move> {
+        let body = self.get_snake_body_vec(snake_id);
+        let (head, neck) = (body.first()?, body.get(1)?);
+        self.possible_moves(neck)
+            .find(|(_, pos)| pos == head)
+            .map(|(mv, _)| mv)
+    }
+
+    /// the [LastMoveDeterminableGame::last_move] of every snake, in the order of
+    /// [SnakeIDGettableGame::get_snake_ids]
+    fn infer_last_moves(&self) -> Vec<(Self::SnakeIDType, Option<Move>)> {
+        self.get_snake_ids()
+            .into_iter()
+            .map(|sid| {
+                let mv = self.last_move(&sid);
+                (sid, mv)
+            })
+            .collect()
+    }
 }
 
-/// a game for which the size of the game board can be determined
-pub trait SizeDeterminableGame {
-    #[allow(missing_docs)]
-    fn get_width(&self) -> u32;
-    #[allow(missing_docs)]
-    fn get_height(&self) -> u32;
+/// How a snake is assumed to move, see [TrajectoryProjectableGame]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum TrajectoryAssumption {
+    /// it keeps going the way its last move went
+    Straight,
+    /// it makes these moves in order, starting over after the last one
+    Repeating(Vec<Move>),
 }
 
-/// a game for which the current turn is determinable
-pub trait TurnDeterminableGame {
-    #[allow(missing_docs)]
-    fn turn(&self) -> u64;
+/// Why a projection stopped before the turns asked for, see [TrajectoryProjectableGame]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ProjectionStop {
+    /// the next move takes the head off the board
+    LeftBoard,
+    /// the next move takes the head on to a body, its own or another snake's
+    Collided,
 }
 
-/// A game where an entire snake body is gettable
-pub trait SnakeBodyGettableGame: PositionGettableGame + SnakeIDGettableGame {
-    /// return a Vec of the positions for a given snake body, in order from head to tail
-    fn get_snake_body_vec(&self, snake_id: &Self::SnakeIDType) -> Vec<Self::NativePositionType>;
+/// Where a snake would be over the next few turns, see [TrajectoryProjectableGame]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ProjectedSnake<P> {
+    /// the body, head first, after each turn, so the first is the body after the next turn
+    pub bodies: Vec<Vec<P>>,
+    /// why the projection stopped on the turn after the last body, None if it went on for
+    /// every turn
+    pub stopped: Option<ProjectionStop>,
+}
+
+impl<P> ProjectedSnake<P> {
+    /// the head after each turn
+    pub fn heads(&self) -> impl Iterator<Item = &P> {
+        self.bodies.iter().map(|body| &body[0])
+    }
+}
 
-    /// return an iterator over all the snake body positions. Order is NOT guaranteed to be from head to tail
-    /// implementations are free to do any order that is efficient for them.
-    /// Positions that would be duplicate, due to a snake being double or triple stacked, may be
-    /// omitted
-    fn get_snake_body_iter(
+/// a game for which we can tell where a snake would go if it kept moving the same way
+pub trait TrajectoryProjectableGame: LastMoveDeterminableGame + HealthGettableGame {
+    /// Where `snake_id` would be after each of the next `turns` turns if it moved the way
+    /// `assumption` says and ate nothing. Stops early if the head would leave the board or run
+    /// in to a body. Other snakes are assumed to be on their squares until their pieces would
+    /// have left them, but not to move anywhere new. None if the snake is dead or has no body,
+    /// or is going straight without having made a move yet or is given no moves to repeat
+    fn project_snake(
         &self,
         snake_id: &Self::SnakeIDType,
-    ) -> Box<dyn Iterator<Item = Self::NativePositionType> + '_>;
+        turns: usize,
+        assumption: &TrajectoryAssumption,
+    ) -> Option<ProjectedSnake<Self::NativePositionType>> {
+        if !self.is_alive(snake_id) {
+            return None;
+        }
+        let mut body = self
+            .get_snake_body_vec(snake_id)
+            .into_iter()
+            .collect::<VecDeque<_>>();
+        if body.is_empty() {
+            return None;
+        }
+        let mut moves: Box<dyn Iterator<Item = Move>> = match assumption {
+            TrajectoryAssumption::Straight => {
+                Box::new(core::iter::repeat(self.last_move(snake_id)?))
+            }
+            TrajectoryAssumption::Repeating(moves) if moves.is_empty() => return None,
+            TrajectoryAssumption::Repeating(moves) => Box::new(moves.iter().copied().cycle()),
+        };
+
+        // how many turns until each square of another snake is left by the piece on it, the
+        // piece nearest the head of a stack leaves last
+        let mut vacates = HashMap::new();
+        for sid in self.get_snake_ids() {
+            if sid == *snake_id || !self.is_alive(&sid) {
+                continue;
+            }
+            let other = self.get_snake_body_vec(&sid);
+            for (i, pos) in other.iter().enumerate() {
+                let left = other.len() - i;
+                let entry = vacates.entry(pos.clone()).or_insert(left);
+                *entry = (*entry).max(left);
+            }
+        }
+
+        let mut projected = ProjectedSnake {
+            bodies: vec![],
+            stopped: None,
+        };
+        for turn in 1..=turns {
+            let mv = moves.next().unwrap();
+            let head = match self.possible_moves(&body[0]).find(|(m, _)| *m == mv) {
+                Some((_, head)) => head,
+                None => {
+                    projected.stopped = Some(ProjectionStop::LeftBoard);
+                    break;
+                }
+            };
+            body.pop_back();
+            let occupied = vacates.get(&head).map(|left| *left > turn).unwrap_or(false);
+            if occupied || body.contains(&head) {
+                projected.stopped = Some(ProjectionStop::Collided);
+                break;
+            }
+            body.push_front(head);
+            projected.bodies.push(body.iter().cloned().collect());
+        }
+        Some(projected)
+    }
+}
+
+/// Who survives two snakes moving on to the same square, see [HeadToHeadPredictableGame]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum HeadToHeadOutcome {
+    /// we survive and they die
+    IWin,
+    /// they survive and we die
+    ILose,
+    /// neither of us survives
+    BothDie,
+    /// one of us can't move on to the square this turn
+    NotAdjacent,
+}
+
+/// a game for which the result of a head to head collision can be predicted
+pub trait HeadToHeadPredictableGame: SnakeIDGettableGame + PositionGettableGame {
+    /// What happens if `me` and `them` both move their heads on to `target` this turn, taking in
+    /// to account food on `target`, health and hazards, with the same rules as the simulator.
+    /// Other snakes are assumed to stay where they are
+    fn predict_head_to_head(
+        &self,
+        me: &Self::SnakeIDType,
+        them: &Self::SnakeIDType,
+        target: &Self::NativePositionType,
+    ) -> HeadToHeadOutcome;
 }
 
 #[cfg(test)]
@@ -446,8 +1390,228 @@ mod test {
 
     use super::*;
 
+    /// the flat index of each cell's neighbors on an 11x11 board, built at compile time, -1 for
+    /// neighbors off the board
+    const NEIGHBORS_11X11: [[i32; N_MOVES]; 11 * 11] = {
+        let mut table = [[-1; N_MOVES]; 11 * 11];
+        let mut index = 0;
+        while index < 11 * 11 {
+            let pos = Position::from_flat_index(index as i32, 11);
+            let mut mv = 0;
+            while mv < N_MOVES {
+                let neighbor = pos.add_vec(NEIGHBOR_OFFSETS[mv]);
+                if neighbor.x >= 0 && neighbor.x < 11 && neighbor.y >= 0 && neighbor.y < 11 {
+                    table[index][mv] = neighbor.to_flat_index(11);
+                }
+                mv += 1;
+            }
+            index += 1;
+        }
+        table
+    };
+
+    // the checks are done by the compiler, this doesn't build if any of them fail
+    const _: () = {
+        let up = Move::Up.to_vector();
+        assert!(up.x == 0 && up.y == 1);
+        assert!(Move::Up.as_index() == 0 && Move::Right.as_index() == 3);
+        assert!(Move::from_index(Move::Left.as_index()).as_index() == Move::Left.as_index());
+        assert!(Move::Up.opposite().as_index() == Move::Down.as_index());
+        assert!(!Move::Left.is_not_opposite(&Move::Right));
+
+        let mut mv = 0;
+        while mv < N_MOVES {
+            let there_and_back =
+                NEIGHBOR_OFFSETS[mv].add_vec(Move::from_index(mv).opposite().to_vector());
+            assert!(there_and_back.x == 0 && there_and_back.y == 0);
+            let back = NEIGHBOR_OFFSETS[mv].negated();
+            assert!(
+                Move::from_vector(back).as_index() == Move::from_index(mv).opposite().as_index()
+            );
+            let unmoved = Vector::new(0, 0).sub_vec(back);
+            assert!(unmoved.x == NEIGHBOR_OFFSETS[mv].x && unmoved.y == NEIGHBOR_OFFSETS[mv].y);
+            mv += 1;
+        }
+
+        assert!(Position::new(3, 2).to_flat_index(11) == 25);
+        assert!(Position::from_flat_index(25, 11).x == 3);
+        // the bottom left corner only has neighbors up and to the right
+        assert!(NEIGHBORS_11X11[0][Move::Up.as_index()] == 11);
+        assert!(NEIGHBORS_11X11[0][Move::Right.as_index()] == 1);
+        assert!(NEIGHBORS_11X11[0][Move::Down.as_index()] == -1);
+        assert!(NEIGHBORS_11X11[0][Move::Left.as_index()] == -1);
+    };
+
+    #[test]
+    fn test_neighbor_offsets() {
+        for mv in Move::all_iter() {
+            assert_eq!(NEIGHBOR_OFFSETS[mv.as_index()], mv.to_vector());
+            assert_eq!(mv.opposite().opposite(), mv);
+            assert!(!mv.is_not_opposite(&mv.opposite()));
+        }
+        let g = crate::game_fixture(include_str!("../fixtures/start_of_game.json"));
+        for pos in g.all_positions() {
+            let index = pos.to_flat_index(11) as usize;
+            assert_eq!(Position::from_flat_index(index as i32, 11), pos);
+            for (mv, neighbor) in g.possible_moves(&pos) {
+                assert_eq!(
+                    NEIGHBORS_11X11[index][mv.as_index()],
+                    neighbor.to_flat_index(11)
+                );
+            }
+            let on_board = NEIGHBORS_11X11[index].iter().filter(|n| **n >= 0).count();
+            assert_eq!(on_board, g.neighbors(&pos).count());
+        }
+    }
+
     #[test]
     fn test_move_all_order_matches_iter() {
         assert_eq!(Move::all().to_vec(), Move::all_iter().collect::<Vec<_>>());
     }
+
+    #[test]
+    fn test_random_moves() {
+        use rand::{rngs::SmallRng, SeedableRng};
+
+        let mut rng = SmallRng::seed_from_u64(7);
+        let mut counts = [0; N_MOVES];
+        for _ in 0..4000 {
+            counts[rng.gen::<Move>().as_index()] += 1;
+            counts[Move::random(&mut rng).as_index()] += 1;
+        }
+        // each move is picked 2000 times on average, so this is many standard deviations out
+        assert!(counts.iter().all(|count| (1700..2300).contains(count)));
+        assert!(Move::all_iter().choose(&mut rng).is_some());
+
+        let set = [Move::Left, Move::Up].iter().copied().collect::<MoveSet>();
+        assert_eq!(set.len(), 2);
+        let mut counts = [0; N_MOVES];
+        for _ in 0..4000 {
+            counts[set.random_choice(&mut rng).unwrap().as_index()] += 1;
+        }
+        assert_eq!(counts[Move::Down.as_index()], 0);
+        assert_eq!(counts[Move::Right.as_index()], 0);
+        assert!((1800..2200).contains(&counts[Move::Left.as_index()]));
+        assert!((1800..2200).contains(&counts[Move::Up.as_index()]));
+
+        let mut single = MoveSet::all();
+        single.remove(Move::Up);
+        single.remove(Move::Down);
+        single.remove(Move::Left);
+        assert_eq!(single.random_choice(&mut rng), Some(Move::Right));
+        assert_eq!(MoveSet::empty().random_choice(&mut rng), None);
+    }
+
+    #[test]
+    fn test_snake_move_conversions() {
+        let snake_move = SnakeMove::new(SnakeId(2), Move::Left);
+        assert_eq!(SnakeMove::from((SnakeId(2), Move::Left)), snake_move);
+        assert_eq!(
+            <(SnakeId, Move)>::from(snake_move),
+            (SnakeId(2), Move::Left)
+        );
+
+        let json = serde_json::to_string(&snake_move).unwrap();
+        assert_eq!(json, r#"{"snake_id":2,"mv":"Left"}"#);
+        assert_eq!(
+            serde_json::from_str::<SnakeMove<SnakeId>>(&json).unwrap(),
+            snake_move
+        );
+    }
+
+    #[test]
+    fn test_all_and_border_positions() {
+        let g = crate::game_fixture(include_str!("../fixtures/7x7board_valid.json"));
+        let snake_ids = build_snake_id_map(&g);
+        let compact: crate::compact_representation::standard::CellBoard4Snakes7x7 =
+            g.as_cell_board(&snake_ids).unwrap();
+
+        let all = g.all_positions().collect::<Vec<_>>();
+        assert_eq!(all.len(), 7 * 7);
+        assert_eq!(all[0], Position::new(0, 6));
+        assert_eq!(all[48], Position::new(6, 0));
+        assert_eq!(g.border_positions().count(), 2 * 7 + 2 * 7 - 4);
+        assert_eq!(compact.all_positions().collect::<Vec<_>>(), all);
+        assert_eq!(
+            compact.border_positions().collect::<Vec<_>>(),
+            g.border_positions().collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn test_positions_in_radius() {
+        let g = crate::game_fixture(include_str!("../fixtures/start_of_game.json"));
+        let center = Position::new(5, 5);
+        assert_eq!(g.positions_in_radius(center, 0, false).count(), 1);
+        assert_eq!(g.positions_in_radius(center, 2, false).count(), 13);
+
+        // in the corner most of the ball is off the board, unless it wraps
+        let corner = Position::new(0, 0);
+        assert_eq!(g.positions_in_radius(corner, 2, false).count(), 6);
+        let wrapped = g.positions_in_radius(corner, 2, true).collect::<Vec<_>>();
+        assert_eq!(wrapped.len(), 13);
+        assert!(wrapped.contains(&Position::new(10, 10)));
+        assert!(wrapped.contains(&Position::new(9, 0)));
+
+        // a ball bigger than the board covers every position exactly once
+        assert_eq!(g.positions_in_radius(center, 20, true).count(), 11 * 11);
+
+        // huge radii are bounded by the board rather than walked, and don't wrap negative
+        assert_eq!(
+            g.positions_in_radius(center, u32::MAX, false).count(),
+            11 * 11
+        );
+        assert_eq!(
+            g.positions_in_radius(center, u32::MAX, true).count(),
+            11 * 11
+        );
+        let far = Position::new(-1000, 5);
+        assert_eq!(g.positions_in_radius(far, 1000, false).count(), 1);
+        assert_eq!(
+            g.positions_in_radius(far, 1_000_000, false).count(),
+            11 * 11
+        );
+    }
+
+    fn assert_views_match<G: SnakeViewGame>(game: &G) {
+        let views = game.snakes().collect::<Vec<_>>();
+        assert_eq!(
+            views.iter().map(|view| view.id.clone()).collect::<Vec<_>>(),
+            game.get_snake_ids()
+        );
+        for view in views {
+            assert_eq!(view.head, game.get_head_as_native_position(&view.id));
+            assert_eq!(view.health, game.get_health_i64(&view.id));
+            assert_eq!(view.length, game.get_length_i64(&view.id));
+            assert_eq!(view.alive, game.is_alive(&view.id));
+            assert_eq!(
+                view.body().collect::<Vec<_>>(),
+                game.get_snake_body_iter(&view.id).collect::<Vec<_>>()
+            );
+        }
+    }
+
+    #[test]
+    fn test_snake_views() {
+        let mut g = crate::game_fixture(include_str!("../fixtures/late_stage.json"));
+        let dead = g.board.snakes[1].id.clone();
+        g.board.snakes[1].health = 0;
+        assert_views_match(&g);
+        let view = g.snakes().find(|view| view.id == dead).unwrap();
+        assert!(!view.alive);
+        assert_eq!(view.body().count(), g.board.snakes[1].body.len());
+
+        let compact: crate::compact_representation::StandardCellBoard4Snakes11x11 =
+            g.as_cell_board(&build_snake_id_map(&g)).unwrap();
+        assert_views_match(&compact);
+        // compact boards only know about the living snakes
+        assert_eq!(compact.snakes().count(), g.board.snakes.len() - 1);
+        assert!(compact.snakes().all(|view| view.alive));
+
+        let wrapped = crate::game_fixture(include_str!("../fixtures/wrapped_fixture.json"));
+        let wrapped: crate::compact_representation::WrappedCellBoard4Snakes11x11 = wrapped
+            .as_wrapped_cell_board(&build_snake_id_map(&wrapped))
+            .unwrap();
+        assert_views_match(&wrapped);
+    }
 }
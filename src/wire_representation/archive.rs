@@ -0,0 +1,638 @@
+//! types to match the engine's game archive export, a game and the list of frames played in it,
+//! so that finished games can be replayed through this crate, and checked against its simulator
+//! with [Archive::find_divergence]
+//! ```
+//! # use battlesnake_game_types::wire_representation::archive::Archive;
+//! # let body = include_bytes!("../../fixtures/archive_4_snakes.json");
+//! let archive: Archive = serde_json::from_slice(body).unwrap();
+//! let games = archive.games_for("gs_red").unwrap();
+//! ```
+
+use std::collections::{HashMap, VecDeque};
+use std::str::FromStr;
+
+use serde::{Deserialize, Serialize};
+
+use super::{
+    dedup_food, diff_games, map_walls, non_empty_str, BattleSnake, Board, Game, GameDiff,
+    NestedGame,
+};
+use super::{Position, RoyaleSettings, Ruleset, Settings, SquadSettings, TurnTrace};
+use crate::types::{Move, SnakeMove};
+use crate::Error;
+
+/// A game and every frame that was played in it
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "PascalCase")]
+pub struct Archive {
+    pub game: ArchiveGame,
+    pub frames: Vec<Frame>,
+}
+
+/// The settings of an archived game
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "PascalCase")]
+pub struct ArchiveGame {
+    #[serde(rename = "ID")]
+    pub id: String,
+    #[serde(default)]
+    pub status: String,
+    pub width: i32,
+    pub height: i32,
+    /// every setting is a string in the archive, e.g. `"minimumFood": "1"`
+    pub ruleset: HashMap<String, String>,
+    pub snake_timeout: i64,
+    #[serde(default, deserialize_with = "non_empty_str")]
+    pub map: Option<String>,
+    #[serde(default, deserialize_with = "non_empty_str")]
+    pub source: Option<String>,
+}
+
+/// A single turn of an archived game. Snakes that have been eliminated stay in the frame, with
+/// a [Death]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "PascalCase")]
+pub struct Frame {
+    pub turn: i32,
+    pub snakes: Vec<FrameSnake>,
+    #[serde(default)]
+    pub food: Vec<FramePosition>,
+    #[serde(default)]
+    pub hazards: Vec<FramePosition>,
+}
+
+/// A position in an archived frame
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[serde(rename_all = "PascalCase")]
+pub struct FramePosition {
+    pub x: i32,
+    pub y: i32,
+}
+
+impl From<FramePosition> for Position {
+    fn from(p: FramePosition) -> Self {
+        Position::new(p.x, p.y)
+    }
+}
+
+/// A snake in an archived frame, cosmetic fields (color, latency...) are dropped
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "PascalCase")]
+pub struct FrameSnake {
+    #[serde(rename = "ID")]
+    pub id: String,
+    pub name: String,
+    pub body: Vec<FramePosition>,
+    pub health: i32,
+    pub death: Option<Death>,
+    #[serde(default)]
+    pub shout: String,
+    #[serde(default)]
+    pub squad: String,
+}
+
+impl FrameSnake {
+    pub fn is_alive(&self) -> bool {
+        self.death.is_none()
+    }
+
+    fn to_battlesnake(&self) -> Result<BattleSnake, Error> {
+        let body = self
+            .body
+            .iter()
+            .copied()
+            .map(Position::from)
+            .collect::<VecDeque<_>>();
+        let head = *body.front().ok_or_else(|| Error::InvalidSnake {
+            id: self.id.clone(),
+            reason: "has an empty body".to_string(),
+        })?;
+        Ok(BattleSnake {
+            id: self.id.clone(),
+            name: self.name.clone(),
+            head,
+            body,
+            health: if self.is_alive() { self.health } else { 0 },
+            shout: Some(self.shout.clone()).filter(|s| !s.is_empty()),
+            squad: Some(self.squad.clone()).filter(|s| !s.is_empty()),
+            latency: None,
+            actual_length: None,
+        })
+    }
+}
+
+/// How and when a snake was eliminated
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "PascalCase")]
+pub struct Death {
+    pub cause: DeathCause,
+    pub turn: i32,
+    /// the id of the snake that eliminated this one, empty if no snake did
+    #[serde(default)]
+    pub eliminated_by: String,
+}
+
+/// The reason a snake was eliminated, as reported by the engine
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Hash)]
+#[serde(from = "String", into = "String")]
+pub enum DeathCause {
+    /// ran in to another snake's body
+    SnakeCollision,
+    /// ran in to its own body
+    SelfCollision,
+    /// starved, or was worn down by hazards
+    OutOfHealth,
+    /// lost a head to head collision
+    HeadCollision,
+    /// moved off the board
+    WallCollision,
+    /// eliminated along with the rest of its squad
+    SquadEliminated,
+    /// a cause this crate doesn't know about
+    Other(String),
+}
+
+impl From<String> for DeathCause {
+    fn from(cause: String) -> Self {
+        match cause.as_str() {
+            "snake-collision" => DeathCause::SnakeCollision,
+            "snake-self-collision" => DeathCause::SelfCollision,
+            "out-of-health" => DeathCause::OutOfHealth,
+            "head-collision" => DeathCause::HeadCollision,
+            "wall-collision" => DeathCause::WallCollision,
+            "squad-eliminated" => DeathCause::SquadEliminated,
+            _ => DeathCause::Other(cause),
+        }
+    }
+}
+
+impl From<DeathCause> for String {
+    fn from(cause: DeathCause) -> Self {
+        match cause {
+            DeathCause::SnakeCollision => "snake-collision".to_string(),
+            DeathCause::SelfCollision => "snake-self-collision".to_string(),
+            DeathCause::OutOfHealth => "out-of-health".to_string(),
+            DeathCause::HeadCollision => "head-collision".to_string(),
+            DeathCause::WallCollision => "wall-collision".to_string(),
+            DeathCause::SquadEliminated => "squad-eliminated".to_string(),
+            DeathCause::Other(cause) => cause,
+        }
+    }
+}
+
+impl ArchiveGame {
+    fn nested_game(&self) -> NestedGame {
+        fn parse<V: FromStr>(ruleset: &HashMap<String, String>, name: &str) -> Option<V> {
+            ruleset.get(name).and_then(|v| v.parse().ok())
+        }
+        let setting = |name: &str| parse::<i32>(&self.ruleset, name);
+        let flag = |name: &str| parse::<bool>(&self.ruleset, name);
+        let settings = match (
+            setting("foodSpawnChance"),
+            setting("minimumFood"),
+            setting("hazardDamagePerTurn"),
+        ) {
+            (Some(food_spawn_chance), Some(minimum_food), Some(hazard_damage_per_turn)) => {
+                Some(Settings {
+                    food_spawn_chance,
+                    minimum_food,
+                    hazard_damage_per_turn,
+                    hazard_map: None,
+                    hazard_map_author: None,
+                    royale: setting("shrinkEveryNTurns").map(|shrink_every_n_turns| {
+                        RoyaleSettings {
+                            shrink_every_n_turns,
+                        }
+                    }),
+                    squad: match (
+                        flag("allowBodyCollisions"),
+                        flag("sharedElimination"),
+                        flag("sharedHealth"),
+                        flag("sharedLength"),
+                    ) {
+                        (
+                            Some(allow_body_collisions),
+                            Some(shared_elimination),
+                            Some(shared_health),
+                            Some(shared_length),
+                        ) => Some(SquadSettings {
+                            allow_body_collisions,
+                            shared_elimination,
+                            shared_health,
+                            shared_length,
+                        }),
+                        _ => None,
+                    },
+                })
+            }
+            _ => None,
+        };
+
+        NestedGame {
+            id: self.id.clone(),
+            ruleset: Ruleset {
+                name: self
+                    .ruleset
+                    .get("name")
+                    .cloned()
+                    .unwrap_or_else(|| "standard".to_string()),
+                version: self.ruleset.get("version").cloned().unwrap_or_default(),
+                settings,
+            },
+            timeout: self.snake_timeout,
+            map: self.map.clone(),
+            source: self.source.clone(),
+        }
+    }
+}
+
+impl Frame {
+    /// the snakes eliminated on or before this frame
+    pub fn eliminated(&self) -> impl Iterator<Item = (&FrameSnake, &Death)> {
+        self.snakes
+            .iter()
+            .filter_map(|s| s.death.as_ref().map(|death| (s, death)))
+    }
+
+    /// the board for this frame without picking a snake to play as, only snakes that are still
+    /// alive are on it. Errors with [Error::InvalidSnake] if one of them has an empty body
+    pub fn to_board(&self, game: &ArchiveGame) -> Result<Board, Error> {
+        let (food, duplicate_food) =
+            dedup_food(self.food.iter().copied().map(Position::from).collect());
+        let mut hazards = self.hazards.iter().copied().map(Position::from).collect();
+        let walls = map_walls(game.map.as_deref(), &mut hazards);
+        Ok(Board {
+            height: game.height,
+            width: game.width,
+            food,
+            snakes: self
+                .snakes
+                .iter()
+                .filter(|s| s.is_alive())
+                .map(FrameSnake::to_battlesnake)
+                .collect::<Result<_, _>>()?,
+            hazards,
+            walls,
+            duplicate_food,
+        })
+    }
+
+    /// this frame as seen by the snake with id `you`, the way it would have been sent in a
+    /// /move request. If `you` has been eliminated it is still `you`, with 0 health, but isn't on
+    /// the board. Errors with [Error::InvalidSnake] if `you` or a snake on the board has an empty
+    /// body
+    pub fn to_game(&self, game: &ArchiveGame, you: &str) -> Result<Game, Error> {
+        let you = self
+            .snakes
+            .iter()
+            .find(|s| s.id == you)
+            .ok_or_else(|| Error::UnknownSnake(you.to_string()))?;
+        Ok(Game {
+            you: you.to_battlesnake()?,
+            board: self.to_board(game)?,
+            turn: self.turn,
+            game: game.nested_game(),
+        })
+    }
+}
+
+/// What [Archive::find_divergence] doesn't count as a divergence
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct ReplayOptions {
+    /// food spawns are random, so food in a frame that wasn't in the one before it can be left
+    /// out of the comparison. Food that should have been eaten is still compared
+    pub ignore_spawned_food: bool,
+}
+
+/// The first frame of an archive that simulating the frame before it didn't give
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Divergence {
+    /// the turn of the frame that didn't match
+    pub turn: i32,
+    /// the moves worked out from the frame before and this one
+    pub moves: Vec<SnakeMove<String>>,
+    /// the differences between the simulated game and the frame
+    pub diff: GameDiff,
+    /// how the simulated turn was worked out
+    pub trace: TurnTrace,
+}
+
+impl Archive {
+    /// every frame of the game as seen by the snake with id `you`, see [Frame::to_game]
+    pub fn games_for(&self, you: &str) -> Result<Vec<Game>, Error> {
+        self.frames
+            .iter()
+            .map(|frame| frame.to_game(&self.game, you))
+            .collect()
+    }
+
+    /// Walks the game a turn at a time, working out each snake's move from where its head went
+    /// and simulating the turn with [Game::simulate_turn_traced]. Returns the first frame that
+    /// doesn't match the simulated one, or None if the whole game replays the way it was played.
+    /// Errors if a snake disappears from a frame, or its head moves somewhere a single move
+    /// couldn't take it
+    pub fn find_divergence(&self, options: ReplayOptions) -> Result<Option<Divergence>, Error> {
+        let you = match self.frames.first().and_then(|f| f.snakes.first()) {
+            Some(snake) => snake.id.clone(),
+            None => return Ok(None),
+        };
+        for (frame, next_frame) in self.frames.iter().zip(self.frames.iter().skip(1)) {
+            let game = frame.to_game(&self.game, &you)?;
+            let moves = game
+                .board
+                .snakes
+                .iter()
+                .map(|snake| {
+                    let moved = next_frame
+                        .snakes
+                        .iter()
+                        .find(|s| s.id == snake.id)
+                        .ok_or_else(|| Error::UnknownSnake(snake.id.clone()))?;
+                    let to = moved.body.first().copied().map(Position::from);
+                    Move::all_iter()
+                        .find(|mv| Some(game.new_head_position(snake.head, *mv)) == to)
+                        .map(|mv| SnakeMove {
+                            snake_id: snake.id.clone(),
+                            mv,
+                        })
+                        .ok_or_else(|| Error::InvalidSnake {
+                            id: snake.id.clone(),
+                            reason: format!(
+                                "moved from {:?} to {:?} on turn {}",
+                                snake.head, to, next_frame.turn
+                            ),
+                        })
+                })
+                .collect::<Result<Vec<_>, _>>()?;
+
+            let (predicted, trace) = game.simulate_turn_traced(moves.clone());
+            let actual = next_frame.to_game(&self.game, &you)?;
+            let mut diff = diff_games(&predicted, &actual);
+            if options.ignore_spawned_food {
+                diff.food_only_in_actual
+                    .retain(|pos| game.board.food.contains(pos));
+            }
+            if !diff.is_empty() {
+                return Ok(Some(Divergence {
+                    turn: next_frame.turn,
+                    moves,
+                    diff,
+                    trace,
+                }));
+            }
+        }
+        Ok(None)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use itertools::Itertools;
+
+    use crate::compact_representation::StandardCellBoard4Snakes11x11;
+    use crate::types::{
+        build_snake_id_map, FoodGettableGame, FoodQueryableGame, HealthGettableGame, Move,
+        SimulableGame, SimulatorInstruments, SnakeBodyGettableGame, VictorDeterminableGame,
+    };
+
+    fn archive() -> Archive {
+        serde_json::from_str(include_str!("../../fixtures/archive_4_snakes.json")).unwrap()
+    }
+
+    #[test]
+    fn test_parse_archive() {
+        let archive = archive();
+        assert_eq!(archive.game.width, 11);
+        assert_eq!(archive.frames.len(), 5);
+        assert_eq!(archive.frames[0].snakes.len(), 4);
+        assert_eq!(archive.frames[0].eliminated().count(), 0);
+
+        let games = archive.games_for("gs_red").unwrap();
+        assert_eq!(games.len(), 5);
+        let first = &games[0];
+        assert_eq!(first.you.id, "gs_red");
+        assert_eq!(first.you.body.len(), 3);
+        assert_eq!(first.board.snakes.len(), 4);
+        assert_eq!(first.board.food.len(), 3);
+        assert_eq!(first.game.ruleset.name, "standard");
+        assert_eq!(
+            first
+                .game
+                .ruleset
+                .settings
+                .as_ref()
+                .unwrap()
+                .hazard_damage_per_turn,
+            14
+        );
+
+        // red eats on turn 2
+        assert_eq!(games[2].you.health, 100);
+        assert_eq!(games[2].you.body.len(), 4);
+    }
+
+    #[test]
+    fn test_frames_with_deaths() {
+        let archive = archive();
+
+        let turn_2 = &archive.frames[2];
+        let eliminated = turn_2.eliminated().collect::<Vec<_>>();
+        assert_eq!(eliminated.len(), 1);
+        assert_eq!(eliminated[0].0.id, "gs_blue");
+        assert_eq!(eliminated[0].1.cause, DeathCause::WallCollision);
+        assert_eq!(turn_2.to_board(&archive.game).unwrap().snakes.len(), 3);
+
+        let last = archive.frames.last().unwrap();
+        let causes = last
+            .eliminated()
+            .map(|(s, death)| {
+                (
+                    s.id.as_str(),
+                    death.cause.clone(),
+                    death.eliminated_by.as_str(),
+                )
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            causes,
+            vec![
+                ("gs_blue", DeathCause::WallCollision, ""),
+                ("gs_green", DeathCause::HeadCollision, "gs_yellow"),
+                ("gs_yellow", DeathCause::HeadCollision, "gs_green"),
+            ]
+        );
+
+        let game = last.to_game(&archive.game, "gs_red").unwrap();
+        assert!(game.is_over());
+        assert!(game.you_are_winner());
+        let dead = last.to_game(&archive.game, "gs_green").unwrap();
+        assert_eq!(dead.you.health, 0);
+        assert!(!dead.you_are_winner());
+        assert!(last.to_game(&archive.game, "nobody").is_err());
+
+        // every frame can be used with the compact representation too
+        for game in archive.games_for("gs_red").unwrap() {
+            let snake_ids = build_snake_id_map(&game);
+            let compact: StandardCellBoard4Snakes11x11 = game.as_cell_board(&snake_ids).unwrap();
+            assert_eq!(compact.is_over(), game.is_over());
+        }
+    }
+
+    #[test]
+    fn test_replay_archive() {
+        #[derive(Debug)]
+        struct Instruments;
+        impl SimulatorInstruments for Instruments {
+            fn observe_simulation(&self, _: std::time::Duration) {}
+        }
+
+        let archive = archive();
+        let first = archive.frames[0].to_game(&archive.game, "gs_red").unwrap();
+        let snake_ids = build_snake_id_map(&first);
+        let mut board: StandardCellBoard4Snakes11x11 = first.as_cell_board(&snake_ids).unwrap();
+
+        for (frame, next_frame) in archive.frames.iter().tuple_windows() {
+            // the moves played are where each snake's head went, including into walls
+            let moves = frame
+                .snakes
+                .iter()
+                .filter(|s| s.is_alive())
+                .map(|s| {
+                    let to = next_frame.snakes.iter().find(|n| n.id == s.id).unwrap();
+                    let step =
+                        Position::from(to.body[0]).sub_vec(Position::from(s.body[0]).to_vector());
+                    (snake_ids[&s.id], Move::from_vector(step.to_vector()))
+                })
+                .collect::<Vec<_>>();
+            board = board.advance_turn_with_moves(&Instruments, &moves).unwrap();
+
+            let recorded: StandardCellBoard4Snakes11x11 = next_frame
+                .to_game(&archive.game, "gs_red")
+                .unwrap()
+                .as_cell_board(&snake_ids)
+                .unwrap();
+            for s in &next_frame.snakes {
+                let sid = snake_ids[&s.id];
+                assert_eq!(
+                    board.is_alive(&sid),
+                    s.is_alive(),
+                    "turn {}",
+                    next_frame.turn
+                );
+                if s.is_alive() {
+                    assert_eq!(board.get_health_i64(&sid), s.health as i64);
+                    assert_eq!(
+                        board.get_snake_body_vec(&sid),
+                        recorded.get_snake_body_vec(&sid)
+                    );
+                }
+            }
+            // food spawns are random, so only the food that was eaten has to match
+            for pos in board.get_all_food_as_native_positions() {
+                assert!(recorded.is_food(&pos), "turn {}", next_frame.turn);
+            }
+        }
+        assert!(board.is_over());
+    }
+
+    #[test]
+    fn test_find_divergence() {
+        let archive = archive();
+        assert_eq!(
+            archive.find_divergence(ReplayOptions::default()).unwrap(),
+            None
+        );
+
+        // red is a point of health off from what the engine would have made on turn 3
+        let mut perturbed = archive.clone();
+        perturbed.frames[3].snakes[0].health -= 1;
+        let divergence = perturbed
+            .find_divergence(ReplayOptions::default())
+            .unwrap()
+            .unwrap();
+        assert_eq!(divergence.turn, 3);
+        assert_eq!(divergence.trace.turn, 2);
+        assert_eq!(divergence.diff.snakes.len(), 1);
+        assert_eq!(divergence.diff.snakes[0].id, "gs_red");
+        assert_eq!(divergence.diff.snakes[0].health, Some((99, 98)));
+        assert!(divergence
+            .moves
+            .contains(&("gs_red".to_string(), Move::Right).into()));
+        assert_eq!(divergence.trace.snake("gs_red").unwrap().health_after, 99);
+    }
+
+    #[test]
+    fn test_find_divergence_spawned_food() {
+        // food spawned on turn 3 stays for the rest of the game
+        let mut archive = archive();
+        for frame in &mut archive.frames[3..] {
+            frame.food.push(FramePosition { x: 7, y: 7 });
+        }
+
+        let divergence = archive
+            .find_divergence(ReplayOptions::default())
+            .unwrap()
+            .unwrap();
+        assert_eq!(divergence.turn, 3);
+        assert_eq!(
+            divergence.diff.food_only_in_actual,
+            vec![Position::new(7, 7)]
+        );
+        let options = ReplayOptions {
+            ignore_spawned_food: true,
+        };
+        assert_eq!(archive.find_divergence(options).unwrap(), None);
+
+        // food that should have been eaten still counts
+        archive.frames[2].food.push(FramePosition { x: 1, y: 3 });
+        let divergence = archive.find_divergence(options).unwrap().unwrap();
+        assert_eq!(divergence.turn, 2);
+        assert_eq!(
+            divergence.diff.food_only_in_actual,
+            vec![Position::new(1, 3)]
+        );
+
+        // a head that jumps can't be replayed
+        let mut archive = self::archive();
+        archive.frames[1].snakes[0].body[0] = FramePosition { x: 5, y: 5 };
+        assert!(matches!(
+            archive.find_divergence(options),
+            Err(Error::InvalidSnake { .. })
+        ));
+    }
+
+    #[test]
+    fn test_empty_body_is_an_error() {
+        let mut archive = archive();
+        let frame = &mut archive.frames[0];
+        frame.snakes[1].body.clear();
+        let id = frame.snakes[1].id.clone();
+
+        let invalid = |result: Result<Game, Error>| match result {
+            Err(Error::InvalidSnake { id, .. }) => Some(id),
+            _ => None,
+        };
+        assert!(matches!(
+            frame.to_board(&archive.game),
+            Err(Error::InvalidSnake { .. })
+        ));
+        assert_eq!(
+            invalid(frame.to_game(&archive.game, "gs_red")),
+            Some(id.clone())
+        );
+        assert_eq!(invalid(frame.to_game(&archive.game, &id)), Some(id));
+        assert!(archive.games_for("gs_red").is_err());
+        assert!(archive.find_divergence(ReplayOptions::default()).is_err());
+    }
+
+    #[test]
+    fn test_unknown_death_cause() {
+        let cause: DeathCause = serde_json::from_str("\"eaten-by-a-grue\"").unwrap();
+        assert_eq!(cause, DeathCause::Other("eaten-by-a-grue".to_string()));
+        assert_eq!(
+            serde_json::to_string(&DeathCause::OutOfHealth).unwrap(),
+            "\"out-of-health\""
+        );
+    }
+}
@@ -0,0 +1,351 @@
+//! A wire game that borrows its strings from the request body instead of allocating one for
+//! every id, name and shout. It is enough to look snakes up in a
+//! [SnakeIDMap](crate::types::SnakeIDMap) and convert to a compact board, and can be turned in to
+//! an owned [Game] for everything else. Strings with escapes in them can't be borrowed, so those
+//! few are still allocated
+//!
+//! ```
+//! # use battlesnake_game_types::compact_representation::StandardCellBoard4Snakes11x11;
+//! # use battlesnake_game_types::types::build_snake_id_map;
+//! # use battlesnake_game_types::wire_representation::GameRef;
+//! # let body = include_str!("../../fixtures/late_stage.json");
+//! let game: GameRef = serde_json::from_str(body).unwrap();
+//! // the map is usually built once for the whole game, e.g. in the /start request
+//! let snake_ids = build_snake_id_map(&game.to_game());
+//! let board: StandardCellBoard4Snakes11x11 = game.as_cell_board(&snake_ids).unwrap();
+//! ```
+
+use std::borrow::Cow;
+use std::collections::{HashSet, VecDeque};
+
+use serde::{Deserialize, Deserializer};
+
+use crate::compact_representation::dimensions::Dimensions;
+use crate::compact_representation::{wrapped, CellNum, StandardCellBoard};
+use crate::types::SnakeIDMap;
+
+use super::{
+    dedup_food, map_walls, simulation_settings, BattleSnake, Board, Game, GameView, NestedGame,
+    Position, Ruleset, Settings, SnakeView,
+};
+
+/// The same as [BattleSnake], borrowing its strings
+#[derive(Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct BattleSnakeRef<'a> {
+    #[serde(borrow)]
+    pub id: Cow<'a, str>,
+    #[serde(borrow)]
+    pub name: Cow<'a, str>,
+    pub head: Position,
+    pub body: VecDeque<Position>,
+    pub health: i32,
+    #[serde(default, borrow, deserialize_with = "borrowed_str")]
+    pub shout: Option<Cow<'a, str>>,
+    #[serde(default, borrow, deserialize_with = "borrowed_non_empty_str")]
+    pub squad: Option<Cow<'a, str>>,
+    #[serde(default, borrow, deserialize_with = "borrowed_non_empty_str")]
+    pub latency: Option<Cow<'a, str>>,
+}
+
+/// The same as [Board], borrowing its snakes' strings
+#[derive(Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(from = "WireBoardRef<'a>", bound(deserialize = "'de: 'a"))]
+pub struct BoardRef<'a> {
+    pub height: i32,
+    pub width: i32,
+    /// see [Board::food]
+    pub food: HashSet<Position>,
+    pub snakes: Vec<BattleSnakeRef<'a>>,
+    pub hazards: Vec<Position>,
+    /// see [Board::walls]
+    pub walls: HashSet<Position>,
+    /// see [Board::duplicate_food]
+    pub duplicate_food: Vec<Position>,
+}
+
+/// a [BoardRef] the way it is sent, before its food is deduplicated
+#[derive(Deserialize)]
+struct WireBoardRef<'a> {
+    height: i32,
+    width: i32,
+    food: Vec<Position>,
+    #[serde(borrow)]
+    snakes: Vec<BattleSnakeRef<'a>>,
+    hazards: Vec<Position>,
+    #[serde(default)]
+    walls: HashSet<Position>,
+}
+
+impl<'a> From<WireBoardRef<'a>> for BoardRef<'a> {
+    fn from(board: WireBoardRef<'a>) -> Self {
+        let (food, duplicate_food) = dedup_food(board.food);
+        BoardRef {
+            height: board.height,
+            width: board.width,
+            food,
+            snakes: board.snakes,
+            hazards: board.hazards,
+            walls: board.walls,
+            duplicate_food,
+        }
+    }
+}
+
+/// The same as [Ruleset], borrowing its strings
+#[derive(Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct RulesetRef<'a> {
+    #[serde(borrow)]
+    pub name: Cow<'a, str>,
+    #[serde(borrow)]
+    pub version: Cow<'a, str>,
+    pub settings: Option<Settings>,
+}
+
+/// The same as [NestedGame], borrowing its strings
+#[derive(Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct NestedGameRef<'a> {
+    #[serde(borrow)]
+    pub id: Cow<'a, str>,
+    #[serde(borrow)]
+    pub ruleset: RulesetRef<'a>,
+    pub timeout: i64,
+    #[serde(default, borrow, deserialize_with = "borrowed_non_empty_str")]
+    pub map: Option<Cow<'a, str>>,
+    #[serde(default, borrow, deserialize_with = "borrowed_non_empty_str")]
+    pub source: Option<Cow<'a, str>>,
+}
+
+/// The same as [Game], borrowing its strings from the request body it is deserialized from
+#[derive(Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(from = "WireGameRef<'a>", bound(deserialize = "'de: 'a"))]
+pub struct GameRef<'a> {
+    pub you: BattleSnakeRef<'a>,
+    pub board: BoardRef<'a>,
+    pub turn: i32,
+    pub game: NestedGameRef<'a>,
+}
+
+/// a [GameRef] the way it is sent, before the walls of its map are taken out of its hazards
+#[derive(Deserialize)]
+struct WireGameRef<'a> {
+    #[serde(borrow)]
+    you: BattleSnakeRef<'a>,
+    #[serde(borrow)]
+    board: BoardRef<'a>,
+    turn: i32,
+    #[serde(borrow)]
+    game: NestedGameRef<'a>,
+}
+
+impl<'a> From<WireGameRef<'a>> for GameRef<'a> {
+    fn from(game: WireGameRef<'a>) -> Self {
+        let mut board = game.board;
+        let walls = map_walls(game.game.map.as_deref(), &mut board.hazards);
+        board.walls.extend(walls);
+        GameRef {
+            you: game.you,
+            board,
+            turn: game.turn,
+            game: game.game,
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct Borrowed<'a>(#[serde(borrow)] Cow<'a, str>);
+
+fn borrowed_str<'de: 'a, 'a, D: Deserializer<'de>>(d: D) -> Result<Option<Cow<'a, str>>, D::Error> {
+    Ok(Option::<Borrowed>::deserialize(d)?.map(|Borrowed(s)| s))
+}
+
+fn borrowed_non_empty_str<'de: 'a, 'a, D: Deserializer<'de>>(
+    d: D,
+) -> Result<Option<Cow<'a, str>>, D::Error> {
+    Ok(borrowed_str(d)?.filter(|s| !s.is_empty()))
+}
+
+fn owned(s: &Option<Cow<'_, str>>) -> Option<String> {
+    s.as_ref().map(|s| s.to_string())
+}
+
+impl GameRef<'_> {
+    /// Returns a boolean indicating whether this game is using the wrapped ruleset
+    pub fn is_wrapped(&self) -> bool {
+        self.game.ruleset.name == "wrapped"
+    }
+
+    /// the same as [Game::validate]
+    pub fn validate(&self) -> Result<Vec<crate::ValidationWarning>, crate::Error> {
+        self.view().validate()
+    }
+
+    /// the same as [Game::as_cell_board], without allocating the snakes' ids
+    pub fn as_cell_board<
+        T: CellNum,
+        D: Dimensions,
+        const BOARD_SIZE: usize,
+        const MAX_SNAKES: usize,
+    >(
+        &self,
+        snake_ids: &SnakeIDMap,
+    ) -> Result<StandardCellBoard<T, D, BOARD_SIZE, MAX_SNAKES>, crate::Error> {
+        StandardCellBoard::convert_from_game_ref(self, snake_ids)
+    }
+
+    /// the same as [Game::as_wrapped_cell_board], without allocating the snakes' ids
+    pub fn as_wrapped_cell_board<
+        T: CellNum,
+        D: Dimensions,
+        const BOARD_SIZE: usize,
+        const MAX_SNAKES: usize,
+    >(
+        &self,
+        snake_ids: &SnakeIDMap,
+    ) -> Result<wrapped::CellBoard<T, D, BOARD_SIZE, MAX_SNAKES>, crate::Error> {
+        wrapped::CellBoard::convert_from_game_ref(self, snake_ids)
+    }
+
+    /// this game with its strings copied, the same as deserializing a [Game]
+    #[allow(clippy::wrong_self_convention)]
+    pub fn to_game(&self) -> Game {
+        Game::from(self)
+    }
+
+    pub(crate) fn view(&self) -> GameView<'_> {
+        GameView {
+            width: self.board.width,
+            height: self.board.height,
+            wrapped: self.is_wrapped(),
+            snakes: self
+                .board
+                .snakes
+                .iter()
+                .map(|snake| SnakeView {
+                    id: &snake.id,
+                    head: snake.head,
+                    body: &snake.body,
+                    health: snake.health,
+                })
+                .collect(),
+            food: &self.board.food,
+            duplicate_food: &self.board.duplicate_food,
+            hazards: &self.board.hazards,
+            walls: &self.board.walls,
+            settings: simulation_settings(
+                &self.game.ruleset.name,
+                self.game.ruleset.settings.as_ref(),
+            ),
+        }
+    }
+}
+
+impl From<&BattleSnakeRef<'_>> for BattleSnake {
+    fn from(snake: &BattleSnakeRef<'_>) -> Self {
+        BattleSnake {
+            id: snake.id.to_string(),
+            name: snake.name.to_string(),
+            head: snake.head,
+            body: snake.body.clone(),
+            health: snake.health,
+            shout: owned(&snake.shout),
+            squad: owned(&snake.squad),
+            latency: owned(&snake.latency),
+            actual_length: None,
+        }
+    }
+}
+
+impl From<&GameRef<'_>> for Game {
+    fn from(game: &GameRef<'_>) -> Self {
+        Game {
+            you: BattleSnake::from(&game.you),
+            board: Board {
+                height: game.board.height,
+                width: game.board.width,
+                food: game.board.food.clone(),
+                snakes: game.board.snakes.iter().map(BattleSnake::from).collect(),
+                hazards: game.board.hazards.clone(),
+                walls: game.board.walls.clone(),
+                duplicate_food: game.board.duplicate_food.clone(),
+            },
+            turn: game.turn,
+            game: NestedGame {
+                id: game.game.id.to_string(),
+                ruleset: Ruleset {
+                    name: game.game.ruleset.name.to_string(),
+                    version: game.game.ruleset.version.to_string(),
+                    settings: game.game.ruleset.settings.clone(),
+                },
+                timeout: game.game.timeout,
+                map: owned(&game.game.map),
+                source: owned(&game.game.source),
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::compact_representation::{
+        StandardCellBoard4Snakes11x11, WrappedCellBoard4Snakes11x11,
+    };
+    use crate::types::build_snake_id_map;
+
+    const FIXTURES: &[&str] = &[
+        include_str!("../../fixtures/4_snake_game.json"),
+        include_str!("../../fixtures/7x7board_valid.json"),
+        include_str!("../../fixtures/8x8board_valid.json"),
+        include_str!("../../fixtures/late_stage.json"),
+        include_str!("../../fixtures/start_of_game.json"),
+        include_str!("../../fixtures/squad_game.json"),
+        include_str!("../../fixtures/hazard_map_settings.json"),
+        include_str!("../../fixtures/empty_str_hazard.json"),
+        include_str!("../../fixtures/end_lost.json"),
+        include_str!("../../fixtures/opening_turn_0.json"),
+        include_str!("../../fixtures/this_one_crashed.json"),
+        include_str!("../../fixtures/body_collision.json"),
+        include_str!("../../fixtures/arcade_maze_map.json"),
+        include_str!("../../fixtures/wrapped_fixture.json"),
+        include_str!("../../fixtures/wrapped_panic.json"),
+        include_str!("../../fixtures/cornered_wrapped.json"),
+        include_str!("../../fixtures/anomalous_food.json"),
+    ];
+
+    #[test]
+    fn test_borrowed_parse_matches_owned() {
+        for json in FIXTURES {
+            let owned: Game = serde_json::from_str(json).unwrap();
+            let borrowed: GameRef = serde_json::from_str(json).unwrap();
+            assert_eq!(borrowed.to_game(), owned);
+            assert!(matches!(borrowed.you.id, Cow::Borrowed(_)));
+            assert_eq!(borrowed.validate().ok(), owned.validate().ok());
+
+            let snake_ids = build_snake_id_map(&owned);
+            if owned.is_wrapped() {
+                let expected: Result<WrappedCellBoard4Snakes11x11, _> =
+                    owned.as_wrapped_cell_board(&snake_ids);
+                let actual: Result<WrappedCellBoard4Snakes11x11, _> =
+                    borrowed.as_wrapped_cell_board(&snake_ids);
+                assert_eq!(actual.ok(), expected.ok());
+            } else {
+                let expected: Result<StandardCellBoard4Snakes11x11, _> =
+                    owned.as_cell_board(&snake_ids);
+                let actual: Result<StandardCellBoard4Snakes11x11, _> =
+                    borrowed.as_cell_board(&snake_ids);
+                assert_eq!(actual.ok(), expected.ok());
+            }
+        }
+    }
+
+    #[test]
+    fn test_escaped_strings() {
+        let json =
+            include_str!("../../fixtures/late_stage.json").replace("marrrvin", "mar\\\"rrvin");
+        let borrowed: GameRef = serde_json::from_str(&json).unwrap();
+        let owned: Game = serde_json::from_str(&json).unwrap();
+        assert_eq!(borrowed.board.snakes[0].name, "mar\"rrvin");
+        assert!(matches!(borrowed.board.snakes[0].name, Cow::Owned(_)));
+        assert_eq!(borrowed.to_game(), owned);
+    }
+}
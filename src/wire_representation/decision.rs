@@ -0,0 +1,263 @@
+//! a record of the move made on a turn and what went in to it, for logging and looking at after
+//! the game, see [DecisionRecord]
+
+use std::convert::TryFrom;
+use std::time::Duration;
+
+use serde::{Serialize, Serializer};
+
+use crate::types::{
+    FloodFillOptions, FloodFillableGame, LengthGettableGame, Move, StatsInstruments,
+    YouDeterminableGame,
+};
+
+use super::Game;
+
+/// The version of the json [DecisionRecord] serializes to. It goes up whenever a field is
+/// renamed, removed or changes meaning, new fields can be added without it changing
+pub const DECISION_RECORD_SCHEMA_VERSION: u32 = 1;
+
+/// the area after each move, see [DecisionRecord::area]
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct MoveAreas {
+    pub up: Option<u32>,
+    pub down: Option<u32>,
+    pub left: Option<u32>,
+    pub right: Option<u32>,
+}
+
+impl MoveAreas {
+    /// the area after `mv`
+    pub fn get(&self, mv: Move) -> Option<u32> {
+        match mv {
+            Move::Up => self.up,
+            Move::Down => self.down,
+            Move::Left => self.left,
+            Move::Right => self.right,
+        }
+    }
+
+    fn set(&mut self, mv: Move, area: Option<u32>) {
+        match mv {
+            Move::Up => self.up = area,
+            Move::Down => self.down = area,
+            Move::Left => self.left = area,
+            Move::Right => self.right = area,
+        }
+    }
+}
+
+/// what a [StatsInstruments] observed while deciding on a move
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct SimulationStats {
+    /// how many simulations were run
+    pub simulations: u64,
+    /// how long they took together, in microseconds
+    pub total_time_us: u64,
+    /// how long the longest one took, in microseconds
+    pub longest_time_us: u64,
+}
+
+impl SimulationStats {
+    /// everything `instruments` has observed since they were made or last reset
+    pub fn from_instruments(instruments: &StatsInstruments) -> Self {
+        SimulationStats {
+            simulations: instruments.simulations(),
+            total_time_us: micros(instruments.total_time()),
+            longest_time_us: micros(instruments.longest_time()),
+        }
+    }
+}
+
+fn micros(duration: Duration) -> u64 {
+    u64::try_from(duration.as_micros()).unwrap_or(u64::MAX)
+}
+
+fn serialize_move<S: Serializer>(mv: &Option<Move>, serializer: S) -> Result<S::Ok, S::Error> {
+    match mv {
+        Some(mv) => serializer.collect_str(mv),
+        None => serializer.serialize_none(),
+    }
+}
+
+/// The move you made on a turn and what went in to it, serialized to a stable json schema with
+/// a [DECISION_RECORD_SCHEMA_VERSION], so a log of them can be analysed after the game. What can
+/// be worked out from the game is filled in by [DecisionRecord::new], and what your search found
+/// is added with the `with_` methods
+///
+/// ```
+/// # use std::time::Duration;
+/// # use battlesnake_game_types::types::{Move, StatsInstruments};
+/// # use battlesnake_game_types::wire_representation::{DecisionRecord, SimulationStats};
+/// # let game = battlesnake_game_types::game_fixture(include_str!("../../fixtures/start_of_game.json"));
+/// let instruments = StatsInstruments::new();
+/// // ... search with the instruments
+/// let record = DecisionRecord::new(&game)
+///     .with_move(Move::Up)
+///     .with_search(4, 1200)
+///     .with_time_used(Duration::from_millis(250))
+///     .with_simulation(SimulationStats::from_instruments(&instruments));
+/// let log_line = serde_json::to_string(&record).unwrap();
+/// ```
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct DecisionRecord {
+    /// always [DECISION_RECORD_SCHEMA_VERSION]
+    pub schema_version: u32,
+    pub game_id: String,
+    pub turn: i32,
+    /// your id
+    pub snake_id: String,
+    /// the move made, None until [DecisionRecord::with_move]
+    #[serde(serialize_with = "serialize_move")]
+    pub chosen_move: Option<Move>,
+    pub health: i32,
+    pub length: u32,
+    /// how many of your moves were reasonable, see
+    /// [RandomReasonableMovesGame](crate::types::RandomReasonableMovesGame)
+    pub reasonable_moves: u32,
+    /// the squares reachable after each reasonable move, including the one moved on to, from
+    /// [FloodFillableGame::flood_fill_count]. None for moves that aren't reasonable
+    pub area: MoveAreas,
+    /// how many turns ahead the search got
+    pub search_depth: Option<u32>,
+    /// how many positions the search looked at
+    pub nodes_visited: Option<u64>,
+    /// how long deciding took, in microseconds
+    pub time_used_us: Option<u64>,
+    pub simulation: Option<SimulationStats>,
+}
+
+impl DecisionRecord {
+    /// a record of the turn `game` is on, with no search metrics yet
+    pub fn new(game: &Game) -> Self {
+        let you = &game.you;
+        let mut area = MoveAreas::default();
+        let reasonable = game.reasonable_moves(you);
+        for mv in &reasonable {
+            let destination = game.new_head_position(you.head, *mv);
+            let reachable = game.flood_fill_count(&destination, FloodFillOptions::default()) + 1;
+            area.set(*mv, Some(reachable as u32));
+        }
+        DecisionRecord {
+            schema_version: DECISION_RECORD_SCHEMA_VERSION,
+            game_id: game.game.id.clone(),
+            turn: game.turn,
+            snake_id: you.id.clone(),
+            chosen_move: None,
+            health: you.health,
+            length: game.get_length_i64(game.you_id()) as u32,
+            reasonable_moves: reasonable.len() as u32,
+            area,
+            search_depth: None,
+            nodes_visited: None,
+            time_used_us: None,
+            simulation: None,
+        }
+    }
+
+    /// records the move that was made
+    pub fn with_move(mut self, mv: Move) -> Self {
+        self.chosen_move = Some(mv);
+        self
+    }
+
+    /// records how deep the search got and how many positions it looked at
+    pub fn with_search(mut self, depth: u32, nodes_visited: u64) -> Self {
+        self.search_depth = Some(depth);
+        self.nodes_visited = Some(nodes_visited);
+        self
+    }
+
+    /// records how long deciding took
+    pub fn with_time_used(mut self, time_used: Duration) -> Self {
+        self.time_used_us = Some(micros(time_used));
+        self
+    }
+
+    /// records what the simulations run while deciding were, see
+    /// [SimulationStats::from_instruments]
+    pub fn with_simulation(mut self, simulation: SimulationStats) -> Self {
+        self.simulation = Some(simulation);
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::SimulatorInstruments;
+
+    #[test]
+    fn test_schema() {
+        let game = crate::game_fixture(include_str!("../../fixtures/start_of_game.json"));
+        let instruments = StatsInstruments::new();
+        instruments.observe_simulation(Duration::from_micros(30));
+        instruments.observe_simulation(Duration::from_micros(50));
+        let record = DecisionRecord::new(&game)
+            .with_move(Move::Down)
+            .with_search(6, 1234)
+            .with_time_used(Duration::from_millis(312))
+            .with_simulation(SimulationStats::from_instruments(&instruments));
+
+        // analysis reads this, changing it needs a new schema version
+        assert_eq!(
+            serde_json::to_string(&record).unwrap(),
+            concat!(
+                r#"{"schema_version":1,"game_id":"506514ef-249f-48b8-827b-7bf8d17ac7ad","#,
+                r#""turn":1,"snake_id":"gs_vbvwfwk6jBc4jmCrKCbdJh3G","chosen_move":"down","#,
+                r#""health":99,"length":3,"reasonable_moves":3,"#,
+                r#""area":{"up":null,"down":121,"left":121,"right":121},"#,
+                r#""search_depth":6,"nodes_visited":1234,"time_used_us":312000,"#,
+                r#""simulation":{"simulations":2,"total_time_us":80,"longest_time_us":50}}"#,
+            )
+        );
+    }
+
+    #[test]
+    fn test_schema_without_metrics() {
+        let game = crate::game_fixture(include_str!("../../fixtures/late_stage.json"));
+        let record = DecisionRecord::new(&game);
+        let json = serde_json::to_value(&record).unwrap();
+        let keys = json.as_object().unwrap().keys().collect::<Vec<_>>();
+        assert_eq!(
+            keys,
+            vec![
+                "area",
+                "chosen_move",
+                "game_id",
+                "health",
+                "length",
+                "nodes_visited",
+                "reasonable_moves",
+                "schema_version",
+                "search_depth",
+                "simulation",
+                "snake_id",
+                "time_used_us",
+                "turn",
+            ]
+        );
+        assert!(json["chosen_move"].is_null());
+        assert!(json["simulation"].is_null());
+        assert_eq!(
+            record.reasonable_moves,
+            Move::all()
+                .iter()
+                .filter(|mv| record.area.get(**mv).is_some())
+                .count() as u32
+        );
+        for mv in Move::all().iter() {
+            if let Some(area) = record.area.get(*mv) {
+                assert!(area >= 1);
+            }
+        }
+
+        let instruments = StatsInstruments::new();
+        instruments.observe_simulation(Duration::from_micros(30));
+        instruments.reset();
+        assert_eq!(
+            SimulationStats::from_instruments(&instruments),
+            SimulationStats::default()
+        );
+    }
+}
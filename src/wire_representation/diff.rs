@@ -0,0 +1,294 @@
+//! compares two games, e.g. the state we predicted for the next turn against the one we were sent
+
+use std::collections::HashMap;
+use std::fmt;
+
+use super::{BattleSnake, Game, Position};
+
+/// a body piece that is in a different place in the two games, `None` if the body is too short to
+/// have a piece at `index`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BodyPieceDiff {
+    /// index in to the body, the head is 0
+    pub index: usize,
+    /// the piece in the predicted game, the first one given to [diff_games]
+    pub predicted: Option<Position>,
+    /// the piece in the actual game, the second one given to [diff_games]
+    pub actual: Option<Position>,
+}
+
+/// the differences for a snake that is in both games
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SnakeDiff {
+    /// the id of the snake
+    pub id: String,
+    /// (predicted, actual), if they differ
+    pub health: Option<(i32, i32)>,
+    /// (predicted, actual), if they differ
+    pub length: Option<(usize, usize)>,
+    /// the body pieces that differ, head first
+    pub body: Vec<BodyPieceDiff>,
+}
+
+/// Everything that differs between two games. Cosmetic fields (names, shouts, latency) and the
+/// game settings are not compared
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct GameDiff {
+    /// (predicted, actual), if they differ
+    pub turn: Option<(i32, i32)>,
+    /// ids of snakes in the predicted game, the first one given to [diff_games], but not the
+    /// actual one
+    pub snakes_only_in_predicted: Vec<String>,
+    /// ids of snakes in the actual game, the second one given to [diff_games], but not the
+    /// predicted one
+    pub snakes_only_in_actual: Vec<String>,
+    /// the snakes in both games that differ, in the order of the predicted game
+    pub snakes: Vec<SnakeDiff>,
+    /// food in the predicted game but not the actual one
+    pub food_only_in_predicted: Vec<Position>,
+    /// food in the actual game but not the predicted one
+    pub food_only_in_actual: Vec<Position>,
+    /// hazards in the predicted game but not the actual one. Stacked hazards are listed once for
+    /// each time they are stacked
+    pub hazards_only_in_predicted: Vec<Position>,
+    /// hazards in the actual game but not the predicted one, counted the same way
+    pub hazards_only_in_actual: Vec<Position>,
+}
+
+impl GameDiff {
+    /// true if the two games matched
+    pub fn is_empty(&self) -> bool {
+        *self == GameDiff::default()
+    }
+}
+
+/// compares `predicted` against `actual`, listing snakes, food and hazards in a stable order
+pub fn diff_games(predicted: &Game, actual: &Game) -> GameDiff {
+    let actual_snakes = actual
+        .board
+        .snakes
+        .iter()
+        .map(|s| (s.id.as_str(), s))
+        .collect::<HashMap<_, _>>();
+    let predicted_snakes = predicted
+        .board
+        .snakes
+        .iter()
+        .map(|s| (s.id.as_str(), s))
+        .collect::<HashMap<_, _>>();
+
+    let mut diff = GameDiff {
+        turn: changed(predicted.turn, actual.turn),
+        ..Default::default()
+    };
+    for snake in &predicted.board.snakes {
+        match actual_snakes.get(snake.id.as_str()) {
+            Some(other) => {
+                if let Some(snake_diff) = diff_snakes(snake, other) {
+                    diff.snakes.push(snake_diff);
+                }
+            }
+            None => diff.snakes_only_in_predicted.push(snake.id.clone()),
+        }
+    }
+    for snake in &actual.board.snakes {
+        if !predicted_snakes.contains_key(snake.id.as_str()) {
+            diff.snakes_only_in_actual.push(snake.id.clone());
+        }
+    }
+
+    // food is a HashSet, so it is sorted to report it in the same order every run
+    let mut predicted_food = predicted.board.food.iter().copied().collect::<Vec<_>>();
+    predicted_food.sort_unstable();
+    let mut actual_food = actual.board.food.iter().copied().collect::<Vec<_>>();
+    actual_food.sort_unstable();
+    diff.food_only_in_predicted = difference(&predicted_food, &actual_food);
+    diff.food_only_in_actual = difference(&actual_food, &predicted_food);
+    diff.hazards_only_in_predicted = difference(&predicted.board.hazards, &actual.board.hazards);
+    diff.hazards_only_in_actual = difference(&actual.board.hazards, &predicted.board.hazards);
+
+    diff
+}
+
+/// [diff_games] on normalized copies of the games, so bodies that only differ in how they are
+/// represented aren't reported, see [Game::normalize]
+pub fn diff_games_normalized(predicted: &Game, actual: &Game) -> GameDiff {
+    diff_games(&predicted.normalized(), &actual.normalized())
+}
+
+fn diff_snakes(predicted: &BattleSnake, actual: &BattleSnake) -> Option<SnakeDiff> {
+    let pieces = predicted.body.len().max(actual.body.len());
+    let body = (0..pieces)
+        .map(|index| BodyPieceDiff {
+            index,
+            predicted: predicted.body.get(index).copied(),
+            actual: actual.body.get(index).copied(),
+        })
+        .filter(|piece| piece.predicted != piece.actual)
+        .collect::<Vec<_>>();
+    let diff = SnakeDiff {
+        id: predicted.id.clone(),
+        health: changed(predicted.health, actual.health),
+        length: changed(predicted.body.len(), actual.body.len()),
+        body,
+    };
+
+    if diff.health.is_none() && diff.length.is_none() && diff.body.is_empty() {
+        None
+    } else {
+        Some(diff)
+    }
+}
+
+fn changed<T: PartialEq>(predicted: T, actual: T) -> Option<(T, T)> {
+    if predicted == actual {
+        None
+    } else {
+        Some((predicted, actual))
+    }
+}
+
+/// the positions in `a` that aren't in `b`, counting repeats, sorted
+fn difference(a: &[Position], b: &[Position]) -> Vec<Position> {
+    let mut remaining = HashMap::new();
+    for p in b {
+        *remaining.entry(*p).or_insert(0) += 1;
+    }
+    let mut out = vec![];
+    for p in a {
+        match remaining.get_mut(p) {
+            Some(count) if *count > 0 => *count -= 1,
+            _ => out.push(*p),
+        }
+    }
+    out.sort();
+    out
+}
+
+fn fmt_position(p: Option<Position>) -> String {
+    match p {
+        Some(p) => format!("({}, {})", p.x, p.y),
+        None => "nothing".to_string(),
+    }
+}
+
+impl fmt::Display for GameDiff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.is_empty() {
+            return writeln!(f, "no differences");
+        }
+        if let Some((predicted, actual)) = self.turn {
+            writeln!(f, "turn: predicted {}, actual {}", predicted, actual)?;
+        }
+        for id in &self.snakes_only_in_predicted {
+            writeln!(f, "snake {} only in predicted", id)?;
+        }
+        for id in &self.snakes_only_in_actual {
+            writeln!(f, "snake {} only in actual", id)?;
+        }
+        for snake in &self.snakes {
+            if let Some((predicted, actual)) = snake.health {
+                writeln!(
+                    f,
+                    "snake {} health: predicted {}, actual {}",
+                    snake.id, predicted, actual
+                )?;
+            }
+            if let Some((predicted, actual)) = snake.length {
+                writeln!(
+                    f,
+                    "snake {} length: predicted {}, actual {}",
+                    snake.id, predicted, actual
+                )?;
+            }
+            for piece in &snake.body {
+                writeln!(
+                    f,
+                    "snake {} body[{}]: predicted {}, actual {}",
+                    snake.id,
+                    piece.index,
+                    fmt_position(piece.predicted),
+                    fmt_position(piece.actual)
+                )?;
+            }
+        }
+        let positions = [
+            ("food only in predicted", &self.food_only_in_predicted),
+            ("food only in actual", &self.food_only_in_actual),
+            ("hazards only in predicted", &self.hazards_only_in_predicted),
+            ("hazards only in actual", &self.hazards_only_in_actual),
+        ];
+        for (label, positions) in positions.iter() {
+            if !positions.is_empty() {
+                let positions = positions
+                    .iter()
+                    .map(|p| fmt_position(Some(*p)))
+                    .collect::<Vec<_>>();
+                writeln!(f, "{}: {}", label, positions.join(", "))?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_identical_games_have_no_diff() {
+        let g = crate::game_fixture(include_str!("../../fixtures/late_stage.json"));
+        let diff = diff_games(&g, &g.clone());
+        assert!(diff.is_empty());
+        assert_eq!(diff.to_string(), "no differences\n");
+    }
+
+    #[test]
+    fn test_single_body_piece_diff() {
+        let predicted = crate::game_fixture(include_str!("../../fixtures/food_race.json"));
+        let mut actual = predicted.clone();
+        actual.board.snakes[1].body[2] = Position::new(9, 4);
+        actual.board.snakes[1].shout = Some("cosmetic".to_string());
+
+        let diff = diff_games(&predicted, &actual);
+        assert_eq!(
+            diff,
+            GameDiff {
+                snakes: vec![SnakeDiff {
+                    id: "opponent".to_string(),
+                    health: None,
+                    length: None,
+                    body: vec![BodyPieceDiff {
+                        index: 2,
+                        predicted: Some(Position::new(9, 5)),
+                        actual: Some(Position::new(9, 4)),
+                    }],
+                }],
+                ..Default::default()
+            }
+        );
+        assert_eq!(
+            diff.to_string(),
+            "snake opponent body[2]: predicted (9, 5), actual (9, 4)\n"
+        );
+    }
+
+    #[test]
+    fn test_food_and_hazard_diff() {
+        let predicted = crate::game_fixture(include_str!("../../fixtures/food_race.json"));
+        let mut actual = predicted.clone();
+        actual.board.food.remove(&Position::new(1, 8));
+        actual.board.hazards = vec![Position::new(0, 0), Position::new(0, 0)];
+        actual.board.snakes.pop();
+
+        let diff = diff_games(&predicted, &actual);
+        assert_eq!(diff.food_only_in_predicted, vec![Position::new(1, 8)]);
+        assert!(diff.food_only_in_actual.is_empty());
+        assert_eq!(
+            diff.hazards_only_in_actual,
+            vec![Position::new(0, 0), Position::new(0, 0)]
+        );
+        assert_eq!(diff.snakes_only_in_predicted, vec!["opponent".to_string()]);
+        assert!(!diff.is_empty());
+    }
+}
@@ -0,0 +1,330 @@
+//! fog of war, a game where only the squares near your head can be seen, see [mask_visibility]
+
+use std::collections::HashSet;
+
+use crate::types::{
+    Knowledge, MaskedQueryableGame, Move, NeighborDeterminableGame, PositionGettableGame,
+    SizeDeterminableGame, SnakeIDGettableGame, TurnDeterminableGame, WallQueryableGame,
+    YouDeterminableGame,
+};
+
+use super::{BattleSnake, Game, Position};
+
+/// A game with everything more than `radius` moves from `center` hidden, see
+/// [mask_visibility]. You always know where all of your own body is
+#[derive(Debug, Clone)]
+pub struct MaskedGame {
+    game: Game,
+    center: Position,
+    radius: u32,
+    visible: HashSet<Position>,
+}
+
+/// Hides every square more than `radius` moves (manhattan distance) from `center`, usually
+/// your head, wrapping around the edges of wrapped boards. What is on the hidden squares can
+/// only be found out as [Knowledge::Unknown]
+pub fn mask_visibility(game: &Game, center: Position, radius: u32) -> MaskedGame {
+    let visible = game
+        .positions_in_radius(center, radius, game.is_wrapped())
+        .collect();
+    MaskedGame {
+        game: game.clone(),
+        center,
+        radius,
+        visible,
+    }
+}
+
+impl MaskedGame {
+    /// the square visibility is measured from
+    pub fn center(&self) -> Position {
+        self.center
+    }
+
+    /// how many moves from [MaskedGame::center] can be seen
+    pub fn radius(&self) -> u32 {
+        self.radius
+    }
+
+    /// every square that can be seen
+    pub fn visible_positions(&self) -> impl Iterator<Item = Position> + '_ {
+        self.visible.iter().copied()
+    }
+
+    /// the pieces of snake `id` that can be seen, head first, all of them for you
+    pub fn visible_body(&self, id: &str) -> Vec<Position> {
+        self.game
+            .snake_by_id(id)
+            .map(|snake| self.visible_pieces(snake).collect())
+            .unwrap_or_default()
+    }
+
+    fn visible_pieces<'a>(&'a self, snake: &'a BattleSnake) -> impl Iterator<Item = Position> + 'a {
+        let yours = snake.id == self.game.you.id;
+        snake
+            .body
+            .iter()
+            .copied()
+            .filter(move |pos| yours || self.visible.contains(pos))
+    }
+
+    /// Fills in the hidden squares with the worst they could be for you, so the game can be
+    /// simulated: every hidden square has a hazard and no food. Snakes only have the pieces
+    /// that can be seen, head first, so a snake whose head is hidden looks like its head is
+    /// its first piece that can be seen. Snakes that can't be seen at all are left out
+    pub fn to_worst_case(&self) -> Game {
+        let mut game = self.game.clone();
+        game.board.food.retain(|pos| self.visible.contains(pos));
+        game.board.hazards.retain(|pos| self.visible.contains(pos));
+        game.board.hazards.extend(
+            self.game
+                .all_positions()
+                .filter(|pos| !self.visible.contains(pos)),
+        );
+        game.board.snakes = self
+            .game
+            .board
+            .snakes
+            .iter()
+            .filter_map(|snake| {
+                let body = self.visible_pieces(snake).collect::<Vec<_>>();
+                let head = *body.first()?;
+                Some(BattleSnake {
+                    head,
+                    body: body.into_iter().collect(),
+                    ..snake.clone()
+                })
+            })
+            .collect();
+        game
+    }
+
+    fn knowledge(&self, pos: &Position, known: impl FnOnce() -> bool) -> Knowledge {
+        if self.visible.contains(pos) {
+            known().into()
+        } else {
+            Knowledge::Unknown
+        }
+    }
+}
+
+impl MaskedQueryableGame for MaskedGame {
+    fn is_visible(&self, pos: &Self::NativePositionType) -> bool {
+        self.visible.contains(pos)
+    }
+
+    fn food_at(&self, pos: &Self::NativePositionType) -> Knowledge {
+        self.knowledge(pos, || self.game.board.food.contains(pos))
+    }
+
+    fn hazard_at(&self, pos: &Self::NativePositionType) -> Knowledge {
+        self.knowledge(pos, || self.game.board.hazards.contains(pos))
+    }
+
+    fn snake_body_at(&self, pos: &Self::NativePositionType) -> Knowledge {
+        if self.game.you.body.contains(pos) {
+            return Knowledge::Yes;
+        }
+        self.knowledge(pos, || self.game.position_is_snake_body(*pos))
+    }
+}
+
+impl PositionGettableGame for MaskedGame {
+    type NativePositionType = Position;
+
+    /// true for hidden squares too, since a snake could be on them
+    fn position_is_snake_body(&self, pos: Self::NativePositionType) -> bool {
+        self.snake_body_at(&pos) != Knowledge::No
+    }
+
+    fn position_from_native(&self, native: Self::NativePositionType) -> Position {
+        native
+    }
+
+    fn native_from_position(&self, pos: Position) -> Self::NativePositionType {
+        pos
+    }
+
+    fn off_board(&self, pos: Position) -> bool {
+        self.game.off_board(pos)
+    }
+}
+
+/// walls are part of the map, so they can be seen on hidden squares too
+impl WallQueryableGame for MaskedGame {
+    fn is_wall(&self, pos: &Self::NativePositionType) -> bool {
+        self.game.is_wall(pos)
+    }
+}
+
+impl NeighborDeterminableGame for MaskedGame {
+    fn neighbors<'a>(
+        &'a self,
+        pos: &Self::NativePositionType,
+    ) -> Box<dyn Iterator<Item = Self::NativePositionType> + 'a> {
+        self.game.neighbors(pos)
+    }
+
+    fn possible_moves<'a>(
+        &'a self,
+        pos: &Self::NativePositionType,
+    ) -> Box<dyn Iterator<Item = (Move, Self::NativePositionType)> + 'a> {
+        self.game.possible_moves(pos)
+    }
+}
+
+impl SnakeIDGettableGame for MaskedGame {
+    type SnakeIDType = String;
+
+    /// the snakes with at least one piece that can be seen
+    fn get_snake_ids(&self) -> Vec<Self::SnakeIDType> {
+        self.game
+            .board
+            .snakes
+            .iter()
+            .filter(|snake| self.visible_pieces(snake).next().is_some())
+            .map(|snake| snake.id.clone())
+            .collect()
+    }
+}
+
+impl YouDeterminableGame for MaskedGame {
+    fn is_you(&self, snake_id: &Self::SnakeIDType) -> bool {
+        self.game.is_you(snake_id)
+    }
+
+    fn you_id(&self) -> &Self::SnakeIDType {
+        self.game.you_id()
+    }
+}
+
+impl SizeDeterminableGame for MaskedGame {
+    fn get_width(&self) -> u32 {
+        self.game.get_width()
+    }
+
+    fn get_height(&self) -> u32 {
+        self.game.get_height()
+    }
+}
+
+impl TurnDeterminableGame for MaskedGame {
+    fn turn(&self) -> u64 {
+        self.game.turn()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{FoodQueryableGame, HazardQueryableGame};
+
+    #[test]
+    fn test_known_squares_match() {
+        let game = crate::game_fixture(include_str!("../../fixtures/late_stage.json"));
+        let masked = mask_visibility(&game, game.you.head, 3);
+        assert_eq!(masked.visible_positions().count(), 25);
+
+        for pos in game.all_positions() {
+            if masked.is_visible(&pos) {
+                assert!(pos.sub_vec(game.you.head.to_vector()).manhattan_length() <= 3);
+                assert_eq!(masked.food_at(&pos), game.food_at(&pos));
+                assert_eq!(masked.hazard_at(&pos), game.hazard_at(&pos));
+                assert_eq!(masked.snake_body_at(&pos), game.snake_body_at(&pos));
+            } else if game.you.body.contains(&pos) {
+                assert_eq!(masked.snake_body_at(&pos), Knowledge::Yes);
+            } else {
+                assert_eq!(masked.food_at(&pos), Knowledge::Unknown);
+                assert_eq!(masked.hazard_at(&pos), Knowledge::Unknown);
+                assert_eq!(masked.snake_body_at(&pos), Knowledge::Unknown);
+                assert!(masked.position_is_snake_body(pos));
+            }
+        }
+        for snake in &game.board.snakes {
+            let visible = masked.visible_body(&snake.id);
+            assert!(visible.iter().all(|pos| snake.body.contains(pos)));
+            assert_eq!(
+                masked.get_snake_ids().contains(&snake.id),
+                !visible.is_empty()
+            );
+        }
+        assert_eq!(
+            masked.visible_body(&game.you.id),
+            Vec::from(game.you.body.clone())
+        );
+        assert_eq!(masked.turn(), game.turn());
+    }
+
+    #[test]
+    fn test_worst_case_round_trip() {
+        let game = crate::game_fixture(include_str!("../../fixtures/late_stage.json"));
+        let masked = mask_visibility(&game, game.you.head, 3);
+        let worst = masked.to_worst_case();
+
+        for pos in game.all_positions() {
+            if masked.is_visible(&pos) {
+                assert_eq!(worst.is_food(&pos), game.is_food(&pos));
+                assert_eq!(worst.get_hazard_count(&pos), game.get_hazard_count(&pos));
+            } else {
+                // nothing can be counted on from a square that can't be seen
+                assert!(!worst.is_food(&pos));
+                assert!(worst.is_hazard(&pos));
+            }
+            // no snake is made up, and every piece that can be seen is kept
+            if worst.position_is_snake_body(pos) {
+                assert!(game.position_is_snake_body(pos));
+            }
+            if masked.snake_body_at(&pos) == Knowledge::Yes {
+                assert!(worst.position_is_snake_body(pos));
+            }
+        }
+        assert_eq!(worst.you, game.you);
+        assert_eq!(
+            worst.snake_by_id(&game.you.id).unwrap(),
+            game.snake_by_id(&game.you.id).unwrap()
+        );
+
+        // masking the worst case again changes nothing that can be seen
+        let again = mask_visibility(&worst, game.you.head, 3);
+        for pos in masked.visible_positions() {
+            assert_eq!(again.food_at(&pos), masked.food_at(&pos));
+            assert_eq!(again.hazard_at(&pos), masked.hazard_at(&pos));
+            assert_eq!(again.snake_body_at(&pos), masked.snake_body_at(&pos));
+        }
+
+        // it can be fed to the simulator
+        let moves = worst
+            .get_snake_ids()
+            .into_iter()
+            .map(|id| (id, Move::Up))
+            .collect::<Vec<_>>();
+        worst.simulate_turn(moves);
+    }
+
+    #[test]
+    fn test_visibility_wraps() {
+        let game = crate::game_fixture(include_str!("../../fixtures/wrapped_fixture.json"));
+        let corner = Position::new(0, 0);
+        let masked = mask_visibility(&game, corner, 2);
+        for pos in [
+            Position::new(10, 0),
+            Position::new(0, 10),
+            Position::new(9, 0),
+            Position::new(10, 10),
+        ]
+        .iter()
+        {
+            assert!(masked.is_visible(pos), "{:?}", pos);
+        }
+        assert!(!masked.is_visible(&Position::new(9, 10)));
+        assert_eq!(masked.visible_positions().count(), 13);
+
+        let standard = crate::game_fixture(include_str!("../../fixtures/late_stage.json"));
+        assert_eq!(
+            mask_visibility(&standard, corner, 2)
+                .visible_positions()
+                .count(),
+            6
+        );
+    }
+}
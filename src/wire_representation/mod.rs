@@ -6,13 +6,42 @@ use crate::compact_representation::dimensions::Dimensions;
 use crate::compact_representation::CellNum;
 use crate::compact_representation::StandardCellBoard;
 use crate::types::*;
+use hashbrown::HashMap;
 use rand::prelude::IteratorRandom;
 use serde::{Deserialize, Serialize};
-use std::collections::{VecDeque, HashSet};
-use std::convert::TryInto;
-use std::error::Error;
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashSet, VecDeque};
 use std::fmt::{self, Display};
 
+pub mod archive;
+mod borrowed;
+mod decision;
+mod diff;
+mod fog;
+mod response;
+mod squad;
+#[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+mod store;
+mod tagged;
+mod time_budget;
+mod trace;
+mod tracker;
+mod view;
+
+pub use crate::core_types::Position;
+pub use borrowed::{BattleSnakeRef, BoardRef, GameRef, NestedGameRef, RulesetRef};
+pub use decision::{DecisionRecord, MoveAreas, SimulationStats, DECISION_RECORD_SCHEMA_VERSION};
+pub use diff::{diff_games, diff_games_normalized, BodyPieceDiff, GameDiff, SnakeDiff};
+pub use fog::{mask_visibility, MaskedGame};
+pub use response::{MoveResponse, MAX_SHOUT_BYTES};
+#[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+pub use store::GameStateStore;
+pub use tagged::TaggedGame;
+pub use time_budget::TimeBudget;
+pub use trace::{EliminationCause, SnakeTrace, TurnTrace};
+pub use tracker::{GameSummary, GameTracker, MoveCounts, SnakeSummary};
+pub(crate) use view::{GameView, SnakeView};
+
 /// Struct that matches the `battlesnake` object from the wire representation
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
 pub struct BattleSnake {
@@ -22,72 +51,245 @@ pub struct BattleSnake {
     pub body: VecDeque<Position>,
     pub health: i32,
     pub shout: Option<String>,
+    /// the squad this snake is in, only set in squad games
+    #[serde(default, deserialize_with = "non_empty_str")]
+    pub squad: Option<String>,
+    /// how long the engine took to get this snakes last response in milliseconds, sent as a
+    /// string, see [BattleSnake::latency_ms]
+    #[serde(default, deserialize_with = "non_empty_str")]
+    pub latency: Option<String>,
+    /// the snake's length when it isn't the length of its body, e.g. from a representation that
+    /// shows growth that is owed without stacking it on the tail, see [BattleSnake::normalize_body]
     #[serde(skip)]
     pub actual_length: Option<i32>,
 }
 
-/// Struct that matches the `position` object from the wire representation
-#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash, Ord, PartialOrd)]
-pub struct Position {
-    pub x: i32,
-    pub y: i32,
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(from = "WireBoard")]
+pub struct Board {
+    pub height: i32,
+    pub width: i32,
+    /// each food once, even if it was sent more than once. Iterating it visits the food in a
+    /// different order every run, it is serialized sorted
+    #[serde(serialize_with = "serialize_sorted")]
+    pub food: HashSet<Position>,
+    pub snakes: Vec<BattleSnake>,
+    pub hazards: Vec<Position>,
+    /// squares no snake can move on to, like the walls of the arcade_maze map, which sends them
+    /// as hazards. Moving on to one eliminates a snake the same as moving off the board. The
+    /// engine doesn't send walls, so they are left out when there aren't any
+    #[serde(
+        default,
+        skip_serializing_if = "HashSet::is_empty",
+        serialize_with = "serialize_sorted"
+    )]
+    pub walls: HashSet<Position>,
+    /// the food that was sent more than once, once for each time after the first, for
+    /// [Game::validate] to warn about. It isn't serialized
+    #[serde(skip)]
+    pub duplicate_food: Vec<Position>,
+}
+
+/// a [Board] the way it is sent, before its food is deduplicated
+#[derive(Deserialize)]
+struct WireBoard {
+    height: i32,
+    width: i32,
+    food: Vec<Position>,
+    snakes: Vec<BattleSnake>,
+    hazards: Vec<Position>,
+    #[serde(default)]
+    walls: HashSet<Position>,
 }
 
-impl Position {
-    pub fn new(x: i32, y: i32) -> Self {
-        Self { x, y }
+impl From<WireBoard> for Board {
+    fn from(board: WireBoard) -> Self {
+        let (food, duplicate_food) = dedup_food(board.food);
+        Board {
+            height: board.height,
+            width: board.width,
+            food,
+            snakes: board.snakes,
+            hazards: board.hazards,
+            walls: board.walls,
+            duplicate_food,
+        }
+    }
+}
+
+/// a set of positions as a sorted list, so the same game always serializes the same way
+fn serialize_sorted<S: serde::Serializer>(
+    positions: &HashSet<Position>,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    let mut sorted = positions.iter().collect::<Vec<_>>();
+    sorted.sort();
+    serializer.collect_seq(sorted)
+}
+
+/// the maps that send their walls as hazards
+const WALLED_MAPS: &[&str] = &["arcade_maze"];
+
+/// takes the hazards out of `hazards` that are really walls on `map`, which are all of them on a
+/// walled map and none on any other
+pub(crate) fn map_walls(map: Option<&str>, hazards: &mut Vec<Position>) -> HashSet<Position> {
+    match map {
+        Some(map) if WALLED_MAPS.contains(&map) => hazards.drain(..).collect(),
+        _ => HashSet::new(),
+    }
+}
+
+/// the food sent, each once, and the food that was sent again
+pub(crate) fn dedup_food(sent: Vec<Position>) -> (HashSet<Position>, Vec<Position>) {
+    let mut food = HashSet::with_capacity(sent.len());
+    let duplicates = sent.into_iter().filter(|pos| !food.insert(*pos)).collect();
+    (food, duplicates)
+}
+
+impl Board {
+    /// a board with nothing on it
+    pub fn empty(width: i32, height: i32) -> Self {
+        Board {
+            height,
+            width,
+            food: HashSet::new(),
+            snakes: vec![],
+            hazards: vec![],
+            walls: HashSet::new(),
+            duplicate_food: vec![],
+        }
+    }
+
+    /// The snake on the board with `id` in `snake_ids`, None if there isn't one. This searches
+    /// the map and the board, see [Game::with_snake_ids] for looking up lots of snakes. The
+    /// engine leaves eliminated snakes off the board
+    pub fn snake(&self, id: &SnakeId, snake_ids: &SnakeIDMap) -> Option<&BattleSnake> {
+        let (name, _) = snake_ids.iter().find(|(_, sid)| *sid == id)?;
+        self.snakes.iter().find(|s| s.id == *name)
     }
 
-    pub fn manhattan_length(&self) -> u32 {
-        (self.x.abs() + self.y.abs()) as u32
+    fn off_board(&self, position: Position) -> bool {
+        position.x < 0 || position.x >= self.width || position.y < 0 || position.y >= self.height
     }
 
-    pub fn add_vec(&self, v: Vector) -> Position {
-        Position {
-            x: (self.x as i64 + v.x) as i32,
-            y: (self.y as i64 + v.y) as i32,
+    /// `position` as `(x, y)` if it is off the board, to say why it can't be added
+    fn check_on_board(&self, position: Position) -> Result<(), String> {
+        if self.off_board(position) {
+            return Err(format!("({}, {}) is off the board", position.x, position.y));
         }
+        Ok(())
     }
-    pub fn sub_vec(&self, v: Vector) -> Position {
-        Position {
-            x: (self.x as i64 - v.x) as i32,
-            y: (self.y as i64 - v.y) as i32,
+
+    /// true if there is food, a wall or a snake at `position`
+    fn is_taken(&self, position: Position) -> bool {
+        self.food.contains(&position)
+            || self.walls.contains(&position)
+            || self.snakes.iter().any(|s| s.body.contains(&position))
+    }
+
+    /// Adds a snake, `body` starts at the head. Errors if the body is empty, goes off the board,
+    /// crosses itself, overlaps another snake, food or a wall, or if health isn't between 1 and
+    /// 100. Stacked pieces are allowed as long as they are next to each other in the body
+    pub fn add_snake(
+        &mut self,
+        id: impl Into<String>,
+        body: Vec<Position>,
+        health: i32,
+    ) -> Result<(), crate::Error> {
+        let id = id.into();
+        if self.snakes.iter().any(|s| s.id == id) {
+            return Err(crate::Error::DuplicateSnakeId(id));
+        }
+        let invalid = |reason: String| crate::Error::InvalidSnake {
+            id: id.clone(),
+            reason,
+        };
+        if !(1..=100).contains(&health) {
+            return Err(invalid(format!("has health {}", health)));
         }
+        let head = *body
+            .first()
+            .ok_or_else(|| invalid("has an empty body".to_string()))?;
+        for (idx, pos) in body.iter().enumerate() {
+            self.check_on_board(*pos)
+                .map_err(|reason| invalid(format!("goes off the board, {}", reason)))?;
+            let stacked = idx > 0 && body[idx - 1] == *pos;
+            if !stacked && body[..idx].contains(pos) {
+                return Err(invalid(format!("crosses itself at ({}, {})", pos.x, pos.y)));
+            }
+            if self.is_taken(*pos) {
+                return Err(invalid(format!("overlaps ({}, {})", pos.x, pos.y)));
+            }
+        }
+
+        self.snakes.push(BattleSnake {
+            name: id.clone(),
+            id,
+            head,
+            body: body.into(),
+            health,
+            shout: None,
+            squad: None,
+            latency: None,
+            actual_length: None,
+        });
+        Ok(())
     }
 
-    pub fn to_vector(&self) -> Vector {
-        Vector {
-            x: self.x as i64,
-            y: self.y as i64,
+    /// Adds a food, errors with [crate::Error::InvalidFood] if it is off the board, already
+    /// food, a wall, or under a snake
+    pub fn add_food(&mut self, position: Position) -> Result<(), crate::Error> {
+        self.check_on_board(position)
+            .map_err(crate::Error::InvalidFood)?;
+        if self.is_taken(position) {
+            return Err(crate::Error::InvalidFood(format!(
+                "({}, {}) is already taken",
+                position.x, position.y
+            )));
         }
+        self.food.insert(position);
+        Ok(())
     }
-}
 
-#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
-pub struct Board {
-    pub height: i32,
-    pub width: i32,
-    pub food: HashSet<Position>,
-    pub snakes: Vec<BattleSnake>,
-    pub hazards: Vec<Position>,
+    /// Adds a hazard, errors with [crate::Error::InvalidHazard] if it is off the board. Hazards
+    /// can stack and can be under snakes and food
+    pub fn add_hazard(&mut self, position: Position) -> Result<(), crate::Error> {
+        self.check_on_board(position)
+            .map_err(crate::Error::InvalidHazard)?;
+        self.hazards.push(position);
+        Ok(())
+    }
+
+    /// Adds a wall, errors with [crate::Error::InvalidWall] if it is off the board, already a
+    /// wall, food, or under a snake
+    pub fn add_wall(&mut self, position: Position) -> Result<(), crate::Error> {
+        self.check_on_board(position)
+            .map_err(crate::Error::InvalidWall)?;
+        if self.is_taken(position) {
+            return Err(crate::Error::InvalidWall(format!(
+                "({}, {}) is already taken",
+                position.x, position.y
+            )));
+        }
+        self.walls.insert(position);
+        Ok(())
+    }
 }
 
 impl fmt::Display for Board {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         writeln!(f)?;
-        for i in 0..self.height {
-            let k = self.height - i - 1;
-            for j in 0..self.width {
-                let position = Position {
-                    x: j,
-                    y: k,
-                };
+        for row in 0..self.height {
+            for col in 0..self.width {
+                let position = Position::from_row_col(row, col, self.height);
                 if self.food.contains(&position) {
                     write!(f, "f")?;
                 } else if self.snakes.iter().any(|s| s.head == position) {
                     write!(f, "H")?;
                 } else if self.snakes.iter().any(|s| s.body.contains(&position)) {
                     write!(f, "s")?;
+                } else if self.walls.contains(&position) {
+                    write!(f, "#")?;
                 } else if self.hazards.contains(&position) {
                     write!(f, "x")?;
                 } else {
@@ -126,6 +328,30 @@ pub struct Ruleset {
     pub settings: Option<Settings>,
 }
 
+impl Ruleset {
+    /// The rules compact boards converted from this game are simulated with. Constrictor makes
+    /// every snake grow and get its health back each turn, and the hazard damage per turn is
+    /// capped to fit in an i16
+    pub fn simulation_settings(&self) -> SimulationSettings {
+        simulation_settings(&self.name, self.settings.as_ref())
+    }
+}
+
+/// see [Ruleset::simulation_settings]
+fn simulation_settings(name: &str, settings: Option<&Settings>) -> SimulationSettings {
+    let defaults = SimulationSettings::default();
+    SimulationSettings {
+        hazard_damage: settings
+            .map(|s| {
+                s.hazard_damage_per_turn
+                    .clamp(i16::MIN as i32, i16::MAX as i32) as i16
+            })
+            .unwrap_or(defaults.hazard_damage),
+        always_grow: name == "constrictor",
+        ..defaults
+    }
+}
+
 fn non_empty_str<'de, D: serde::Deserializer<'de>>(d: D) -> Result<Option<String>, D::Error> {
     let o: Option<String> = Option::deserialize(d)?;
     Ok(o.filter(|s| !s.is_empty()))
@@ -148,6 +374,7 @@ pub struct Settings {
     )]
     pub hazard_map_author: Option<String>,
     pub royale: Option<RoyaleSettings>,
+    pub squad: Option<SquadSettings>,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
@@ -156,6 +383,19 @@ pub struct RoyaleSettings {
     pub shrink_every_n_turns: i32,
 }
 
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SquadSettings {
+    #[serde(rename = "allowBodyCollisions")]
+    pub allow_body_collisions: bool,
+    /// when one snake in a squad is eliminated the rest of the squad is too
+    #[serde(rename = "sharedElimination")]
+    pub shared_elimination: bool,
+    #[serde(rename = "sharedHealth")]
+    pub shared_health: bool,
+    #[serde(rename = "sharedLength")]
+    pub shared_length: bool,
+}
+
 /// Root object from the battlesnake server in start, move, and end requests, you
 /// probably want to do:
 /// ```
@@ -164,23 +404,95 @@ pub struct RoyaleSettings {
 /// let g: Result<Game, _> = serde_json::from_slice(body);
 /// ```
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(from = "WireGame")]
 pub struct Game {
     pub you: BattleSnake,
     /// optional, so as to not break backwards compatibility
     pub board: Board,
+    /// from 0 to i32::MAX, simulating a turn on i32::MAX leaves it there rather than wrapping
     pub turn: i32,
     pub game: NestedGame,
 }
 
+/// a [Game] the way it is sent, before the walls of its map are taken out of its hazards
+#[derive(Deserialize)]
+struct WireGame {
+    you: BattleSnake,
+    board: Board,
+    turn: i32,
+    game: NestedGame,
+}
+
+impl From<WireGame> for Game {
+    fn from(game: WireGame) -> Self {
+        let mut game = Game {
+            you: game.you,
+            board: game.board,
+            turn: game.turn,
+            game: game.game,
+        };
+        game.apply_map_walls();
+        game
+    }
+}
+
 impl Game {
-    pub fn you_are_winner(&self) -> bool {
-        if self.you.health == 0 {
-            false
-        } else {
-            self.board.snakes.len() == 1 && self.board.snakes[0].id == self.you.id
+    /// An empty standard game on turn 0, add snakes with [Game::add_you] and
+    /// [Board::add_snake]. Until you are added `you` is a snake with an empty id and body
+    pub fn empty(width: i32, height: i32) -> Self {
+        Game {
+            you: BattleSnake {
+                id: String::new(),
+                name: String::new(),
+                head: Position::new(0, 0),
+                body: VecDeque::new(),
+                health: 0,
+                shout: None,
+                squad: None,
+                latency: None,
+                actual_length: None,
+            },
+            board: Board::empty(width, height),
+            turn: 0,
+            game: NestedGame {
+                id: String::new(),
+                ruleset: Ruleset {
+                    name: "standard".to_string(),
+                    version: String::new(),
+                    settings: None,
+                },
+                timeout: 500,
+                map: None,
+                source: None,
+            },
         }
     }
 
+    /// adds a snake to the board, see [Board::add_snake], and makes it you
+    pub fn add_you(
+        &mut self,
+        id: impl Into<String>,
+        body: Vec<Position>,
+        health: i32,
+    ) -> Result<(), crate::Error> {
+        self.board.add_snake(id, body, health)?;
+        self.you = self.board.snakes.last().unwrap().clone();
+        Ok(())
+    }
+
+    pub fn you_are_winner(&self) -> bool {
+        self.you_are_alive()
+            && self.board.snakes.len() == 1
+            && self.board.snakes[0].id == self.you.id
+    }
+
+    /// whether you are still on the board with some health. In the /end request after you lose,
+    /// and from some self hosted engines mid game, `you` is sent even though you have been
+    /// eliminated, so `you.health` can't be relied on
+    pub fn you_are_alive(&self) -> bool {
+        self.get_health(&self.you.id) > 0
+    }
+
     pub fn as_cell_board<
         T: CellNum,
         D: Dimensions,
@@ -189,7 +501,7 @@ impl Game {
     >(
         &self,
         snake_ids: &SnakeIDMap,
-    ) -> Result<StandardCellBoard<T, D, BOARD_SIZE, MAX_SNAKES>, Box<dyn Error>> {
+    ) -> Result<StandardCellBoard<T, D, BOARD_SIZE, MAX_SNAKES>, crate::Error> {
         StandardCellBoard::convert_from_game(self.clone(), snake_ids)
     }
 
@@ -203,13 +515,9 @@ impl Game {
         snake_ids: &SnakeIDMap,
     ) -> Result<
         compact_representation::wrapped::CellBoard<T, D, BOARD_SIZE, MAX_SNAKES>,
-        Box<dyn Error>,
+        crate::Error,
     > {
-        if self.is_wrapped() {
-            compact_representation::wrapped::CellBoard::convert_from_game(self.clone(), snake_ids)
-        } else {
-            panic!("Cannot convert a non-wrapped game to a wrapped game")
-        }
+        compact_representation::wrapped::CellBoard::convert_from_game(self.clone(), snake_ids)
     }
 
     pub fn off_board(&self, position: Position) -> bool {
@@ -232,67 +540,595 @@ impl Game {
         self.game.ruleset.name == "wrapped"
     }
 
+    /// Checks that this game is one the engine could send: the board is between 1x1 and
+    /// 255x255, snake ids are unique, and every living snake has a sensible health and a body
+    /// which is connected, only stacked at its tail, and doesn't overlap other snakes or food.
+    /// Eliminated snakes are not checked. Food sent more than once or off the board is let
+    /// through, with a [ValidationWarning](crate::ValidationWarning) for each
+    pub fn validate(&self) -> Result<Vec<crate::ValidationWarning>, crate::Error> {
+        self.view().validate()
+    }
+
+    /// the parts of this game validating and converting it look at
+    pub(crate) fn view(&self) -> GameView<'_> {
+        GameView {
+            width: self.board.width,
+            height: self.board.height,
+            wrapped: self.is_wrapped(),
+            snakes: self
+                .board
+                .snakes
+                .iter()
+                .map(|snake| SnakeView {
+                    id: &snake.id,
+                    head: snake.head,
+                    body: &snake.body,
+                    health: snake.health,
+                })
+                .collect(),
+            food: &self.board.food,
+            duplicate_food: &self.board.duplicate_food,
+            hazards: &self.board.hazards,
+            walls: &self.board.walls,
+            settings: self.game.ruleset.simulation_settings(),
+        }
+    }
+
     pub fn is_arcade_maze_map(&self) -> bool {
         self.game.map == Some("arcade_maze".to_owned())
     }
+
+    /// Moves the hazards of a map that sends its walls as hazards, like arcade_maze, in to
+    /// [Board::walls]. Parsing a game already does this, so it's only needed for games built or
+    /// edited by hand. Does nothing on other maps, or once the walls have been moved
+    pub fn apply_map_walls(&mut self) {
+        let walls = map_walls(self.game.map.as_deref(), &mut self.board.hazards);
+        self.board.walls.extend(walls);
+    }
+
+    /// Returns a boolean indicating whether this game is using the squad ruleset
+    pub fn is_squad(&self) -> bool {
+        self.game.ruleset.name == "squad"
+    }
+
+    /// the position of your head, this is taken from `you` so it still works in the /end
+    /// request after you have been eliminated
+    pub fn your_head(&self) -> Position {
+        self.you.head
+    }
+
+    /// your health, the same as [HealthGettableGame::get_health], so 0 once you have been
+    /// eliminated and removed from the board
+    pub fn your_health(&self) -> i32 {
+        self.get_health(&self.you.id)
+    }
+
+    /// your length, taken from `you` so it still works after you have been eliminated
+    pub fn your_length(&self) -> usize {
+        self.you.body.len()
+    }
+
+    /// every snake on the board other than you
+    pub fn other_snakes(&self) -> impl Iterator<Item = &BattleSnake> {
+        let you = self.you.id.as_str();
+        self.board.snakes.iter().filter(move |s| s.id != you)
+    }
+
+    /// the snake on the board with the given id, None if there isn't one
+    pub fn snake_by_id(&self, id: &str) -> Option<&BattleSnake> {
+        self.board.snakes.iter().find(|s| s.id == id)
+    }
+
+    /// the snake on the board with the given id, or `you` if it is your id and you aren't on the
+    /// board, so the getters keep working on your snake after you have been eliminated
+    fn snake_or_you(&self, id: &str) -> Option<&BattleSnake> {
+        self.snake_by_id(id)
+            .or_else(|| Some(&self.you).filter(|you| you.id == id))
+    }
+
+    /// [BattleSnake::normalize_body] for every snake, with `you` made the same as your snake on
+    /// the board while you are on it
+    pub fn normalize(&mut self) {
+        for snake in self.board.snakes.iter_mut() {
+            snake.normalize_body();
+        }
+        self.you.normalize_body();
+        if let Some(you) = self.snake_by_id(&self.you.id) {
+            self.you = you.clone();
+        }
+    }
+
+    /// a copy of this game with [Game::normalize] applied
+    pub fn normalized(&self) -> Game {
+        let mut game = self.clone();
+        game.normalize();
+        game
+    }
+
+    /// true if the two games are the same once normalized, see [Game::normalize]
+    pub fn eq_normalized(&self, other: &Game) -> bool {
+        self.normalized() == other.normalized()
+    }
+
+    /// the squads that still have snakes in the game. With shared elimination a squad is out as
+    /// soon as any of its snakes is eliminated
+    fn living_squads(&self) -> Vec<&str> {
+        let shared_elimination = self
+            .game
+            .ruleset
+            .settings
+            .as_ref()
+            .and_then(|settings| settings.squad)
+            .map(|squad| squad.shared_elimination)
+            .unwrap_or(false);
+        let eliminated = self
+            .board
+            .snakes
+            .iter()
+            .chain(std::iter::once(&self.you))
+            .filter(|s| s.health <= 0)
+            .filter_map(|s| s.squad.as_deref())
+            .collect::<Vec<_>>();
+
+        let mut squads = vec![];
+        for snake in self.board.snakes.iter().filter(|s| s.health > 0) {
+            let squad = match snake.squad.as_deref() {
+                Some(squad) => squad,
+                None => continue,
+            };
+            let out = shared_elimination && eliminated.contains(&squad);
+            if !out && !squads.contains(&squad) {
+                squads.push(squad);
+            }
+        }
+        squads
+    }
 }
 
-impl RandomReasonableMovesGame for Game {
-    fn random_reasonable_move_for_each_snake<'a>(
-        &'a self,
-        rng: &'a mut impl rand::Rng,
-    ) -> Box<dyn Iterator<Item = (Self::SnakeIDType, Move)> + 'a> {
-        Box::new(self.board.snakes.iter().map(move |s| {
-            let all_moves = Move::all();
-            let moves = all_moves.iter().filter(|mv| {
-                let mut new_head = s.head.add_vec(mv.to_vector());
+impl BattleSnake {
+    /// the reported latency as a number of milliseconds, None if it wasn't sent or can't be
+    /// parsed
+    pub fn latency_ms(&self) -> Option<u64> {
+        self.latency.as_ref().and_then(|l| l.parse().ok())
+    }
 
+    /// Puts this snake's body in to the form the engine sends, so snakes that show the same thing
+    /// differently compare equal: the head is the first piece of the body, an `actual_length`
+    /// longer than the body is stacked on to the tail, stacked tail pieces beyond a shorter
+    /// `actual_length` are dropped, and `actual_length` is then cleared so the length is the
+    /// body's. An empty body becomes just the head
+    pub fn normalize_body(&mut self) {
+        match self.body.front() {
+            Some(head) => self.head = *head,
+            None => self.body.push_back(self.head),
+        }
+        if let Some(length) = self.actual_length.take() {
+            let length = length.max(1) as usize;
+            while self.body.len() > length && self.vacating_tail().is_none() {
+                self.body.pop_back();
+            }
+            let tail = self.body[self.body.len() - 1];
+            while self.body.len() < length {
+                self.body.push_back(tail);
+            }
+        }
+    }
+
+    /// the position of this snakes tail if it will move out of the way this turn, e.g. the snake
+    /// did not just eat
+    fn vacating_tail(&self) -> Option<Position> {
+        let len = self.body.len();
+        if len >= 2 && self.body[len - 1] != self.body[len - 2] {
+            Some(self.body[len - 1])
+        } else {
+            None
+        }
+    }
+
+    /// the body piece immediately after the head, None if the snake is fully stacked
+    fn neck(&self) -> Option<Position> {
+        self.body.get(1).copied().filter(|neck| *neck != self.head)
+    }
+}
+
+impl Game {
+    /// the snakes which have not been eliminated
+    fn living_snakes(&self) -> impl Iterator<Item = &BattleSnake> {
+        self.board.snakes.iter().filter(|s| s.health > 0)
+    }
+
+    fn new_head_position(&self, head: Position, mv: Move) -> Position {
+        let new_head = head.add_vec(mv.to_vector());
+
+        if self.is_wrapped() {
+            Position {
+                x: new_head.x.rem_euclid(self.get_width() as i32),
+                y: new_head.y.rem_euclid(self.get_height() as i32),
+            }
+        } else {
+            new_head
+        }
+    }
+
+    /// The squares reachable from `start` (including `start`). `elapsed` is the number of turns
+    /// that have already passed by the time we're at `start`, snake pieces are passable if they
+    /// have vacated by the time we first get to them. The fill stops early once at least `limit`
+    /// squares have been found
+    fn flood_fill_from(
+        &self,
+        start: Position,
+        options: FloodFillOptions,
+        elapsed: usize,
+        limit: usize,
+    ) -> HashSet<Position> {
+        let vacated = self.turns_until_vacated();
+        let blocked: HashSet<Position> = if options.hazards_block {
+            self.board.hazards.iter().copied().collect()
+        } else {
+            HashSet::new()
+        };
+
+        let mut reached = HashSet::new();
+        reached.insert(start);
+        let mut frontier = vec![start];
+        let max_depth = options.max_depth.unwrap_or(usize::MAX);
+        let mut depth = 0;
+
+        // expand one ring at a time, so each square is reached at the earliest turn possible
+        while !frontier.is_empty() && depth < max_depth && reached.len() < limit {
+            depth += 1;
+            let turn = elapsed + depth;
+
+            let mut next = vec![];
+            for pos in frontier.iter() {
+                for neighbor in self.neighbors(pos) {
+                    if !blocked.contains(&neighbor)
+                        && vacated.get(&neighbor).copied().unwrap_or(0) as usize <= turn
+                        && reached.insert(neighbor)
+                    {
+                        next.push(neighbor);
+                    }
+                }
+            }
+            frontier = next;
+        }
+
+        reached
+    }
+
+    /// Counts the squares reachable from `start`, stopping once `limit` have been found, with
+    /// `start` being reached in one turn
+    fn bounded_flood_fill(&self, start: Position, limit: usize) -> usize {
+        self.flood_fill_from(start, FloodFillOptions::default(), 1, limit)
+            .len()
+            .min(limit)
+    }
+}
+
+impl FloodFillableGame for Game {
+    fn flood_fill(&self, start: &Position, options: FloodFillOptions) -> Vec<Position> {
+        let mut reached = self.flood_fill_from(*start, options, 0, usize::MAX);
+        reached.remove(start);
+        let mut reached: Vec<Position> = reached.into_iter().collect();
+        reached.sort_by_key(|pos| (pos.y, pos.x));
+        reached
+    }
+}
+
+impl Game {
+    /// searches outwards from `from` in order of path cost, returning the cheapest cost to each
+    /// square and the move used to get there. With a target this is A* and stops once the
+    /// target is reached, without one it is Dijkstra over the whole board
+    #[allow(clippy::type_complexity)]
+    fn path_search(
+        &self,
+        from: Position,
+        to: Option<Position>,
+        options: AStarOptions,
+    ) -> (HashMap<Position, u32>, HashMap<Position, (Position, Move)>) {
+        let vacated = self.turns_until_vacated();
+        let heuristic = |pos: &Position| match to {
+            Some(to) => {
+                let dx = (pos.x - to.x).unsigned_abs();
+                let dy = (pos.y - to.y).unsigned_abs();
                 if self.is_wrapped() {
-                    let wrapped_x = new_head.x.rem_euclid(self.get_width() as i32);
-                    let wrapped_y = new_head.y.rem_euclid(self.get_height() as i32);
+                    dx.min(self.get_width() - dx) + dy.min(self.get_height() - dy)
+                } else {
+                    dx + dy
+                }
+            }
+            None => 0,
+        };
+
+        let mut costs: HashMap<Position, u32> = HashMap::new();
+        let mut steps: HashMap<Position, usize> = HashMap::new();
+        let mut came_from: HashMap<Position, (Position, Move)> = HashMap::new();
+        let mut open = BinaryHeap::new();
+        costs.insert(from, 0);
+        steps.insert(from, 0);
+        open.push(Reverse((heuristic(&from), 0, from)));
+
+        while let Some(Reverse((_, cost, current))) = open.pop() {
+            if Some(current) == to {
+                break;
+            }
+            if cost > costs[&current] {
+                // a cheaper path here was already expanded
+                continue;
+            }
+
+            let next_steps = steps[&current] + 1;
+            for (mv, next) in self.possible_moves(&current) {
+                let occupied_for = vacated.get(&next).copied().unwrap_or(0);
+                if occupied_for > 0 && (options.bodies_block || occupied_for as usize > next_steps)
+                {
+                    continue;
+                }
+                let next_cost = if self.board.hazards.contains(&next) {
+                    if options.hazards_block {
+                        continue;
+                    }
+                    cost + 1 + options.hazard_cost
+                } else {
+                    cost + 1
+                };
 
-                    new_head = Position {
-                        x: wrapped_x,
-                        y: wrapped_y,
-                    };
+                if costs.get(&next).is_none_or(|c| next_cost < *c) {
+                    costs.insert(next, next_cost);
+                    steps.insert(next, next_steps);
+                    came_from.insert(next, (current, mv));
+                    open.push(Reverse((next_cost + heuristic(&next), next_cost, next)));
                 }
+            }
+        }
+
+        (costs, came_from)
+    }
+}
 
-                let hazard_damage: i32 = self.get_hazard_damage().into();
+impl PathfindableGame for Game {
+    fn a_star(&self, from: &Position, to: &Position, options: AStarOptions) -> Option<Vec<Move>> {
+        let (costs, came_from) = self.path_search(*from, Some(*to), options);
+        costs.get(to)?;
 
-                let unreasonable = self.off_board(new_head)
-                    || self.board.snakes.iter().any(|s| s.body.contains(&new_head))
-                    || (self.board.hazards.contains(&new_head) && hazard_damage >= s.health);
+        let mut path = vec![];
+        let mut current = *to;
+        while let Some((previous, mv)) = came_from.get(&current) {
+            path.push(*mv);
+            current = *previous;
+        }
+        path.reverse();
+        Some(path)
+    }
 
-                !unreasonable
-            });
-            (
-                s.id.clone(),
-                moves.choose(rng).copied().unwrap_or_else(|| {
-                    Move::all()
+    fn cost_map(&self, from: &Position, options: AStarOptions) -> HashMap<Position, u32> {
+        self.path_search(*from, None, options).0
+    }
+}
+
+impl ChokePointGame for Game {}
+
+impl FoodRaceGame for Game {}
+
+impl FutureOccupancyGame for Game {}
+
+impl TailChaseGame for Game {}
+
+impl LastMoveDeterminableGame for Game {}
+
+impl TrajectoryProjectableGame for Game {}
+
+impl VoronoiGame for Game {
+    fn voronoi(&self, options: VoronoiOptions) -> HashMap<String, usize> {
+        let mut counts = self
+            .living_snakes()
+            .map(|s| (s.id.clone(), 0))
+            .collect::<HashMap<_, _>>();
+        for (id, turn) in self.voronoi_owners(options).into_values() {
+            if turn > 0 {
+                *counts.get_mut(&id).unwrap() += 1;
+            }
+        }
+        counts
+    }
+
+    fn voronoi_owners(&self, options: VoronoiOptions) -> HashMap<Position, (String, u32)> {
+        let vacated = self.turns_until_vacated();
+        let blocked: HashSet<Position> = if options.hazards_block {
+            self.board.hazards.iter().copied().collect()
+        } else {
+            HashSet::new()
+        };
+
+        let snakes = self.living_snakes().collect::<Vec<_>>();
+        let mut frontiers = snakes.iter().map(|s| vec![s.head]).collect::<Vec<_>>();
+        let mut claimed = snakes.iter().map(|s| s.head).collect::<HashSet<_>>();
+        let mut owners = snakes
+            .iter()
+            .enumerate()
+            .map(|(idx, s)| (s.head, (idx, 0)))
+            .collect::<HashMap<_, _>>();
+        let mut turn = 0;
+
+        loop {
+            turn += 1;
+
+            // every snake expands by one ring before any square is handed out, so squares reached
+            // by more than one snake on the same turn can be found
+            let mut reached_by: HashMap<Position, Vec<usize>> = HashMap::new();
+            for (idx, frontier) in frontiers.iter().enumerate() {
+                for pos in frontier.iter() {
+                    for neighbor in self.neighbors(pos) {
+                        if claimed.contains(&neighbor)
+                            || blocked.contains(&neighbor)
+                            || vacated.get(&neighbor).copied().unwrap_or(0) > turn
+                        {
+                            continue;
+                        }
+                        let reached = reached_by.entry(neighbor).or_default();
+                        if !reached.contains(&idx) {
+                            reached.push(idx);
+                        }
+                    }
+                }
+            }
+            if reached_by.is_empty() {
+                break;
+            }
+
+            frontiers.iter_mut().for_each(|f| f.clear());
+            for (pos, reached) in reached_by {
+                claimed.insert(pos);
+                let winner = if reached.len() == 1 {
+                    Some(reached[0])
+                } else {
+                    match options.tie_break {
+                        VoronoiTieBreak::Nobody => None,
+                        VoronoiTieBreak::LongerSnake => {
+                            let longest = reached.iter().map(|i| snakes[*i].body.len()).max();
+                            let mut longest_snakes = reached
+                                .iter()
+                                .filter(|i| Some(snakes[**i].body.len()) == longest);
+                            match (longest_snakes.next(), longest_snakes.next()) {
+                                (Some(i), None) => Some(*i),
+                                _ => None,
+                            }
+                        }
+                    }
+                };
+                if let Some(idx) = winner {
+                    frontiers[idx].push(pos);
+                    owners.insert(pos, (idx, turn));
+                }
+            }
+        }
+
+        owners
+            .into_iter()
+            .map(|(pos, (idx, turn))| (pos, (snakes[idx].id.clone(), turn)))
+            .collect()
+    }
+}
+
+/// a random move from `moves`, picking from the `preferred` ones if there are any
+fn choose_preferring(
+    rng: &mut impl rand::Rng,
+    moves: &[Move],
+    preferred: impl Fn(&Move) -> bool,
+) -> Option<Move> {
+    moves
+        .iter()
+        .copied()
+        .filter(|mv| preferred(mv))
+        .choose(rng)
+        .or_else(|| moves.iter().copied().choose(rng))
+}
+
+impl RandomReasonableMovesGame for Game {
+    fn random_reasonable_move_at_level_for_each_snake<'a>(
+        &'a self,
+        rng: &'a mut impl rand::Rng,
+        level: ReasonableMoveLevel,
+    ) -> Box<dyn Iterator<Item = (Self::SnakeIDType, ReasonableMove)> + 'a> {
+        Box::new(self.living_snakes().map(move |s| {
+            let hazard_damage: i32 = self.get_hazard_damage().into();
+            let max_health = self.game.ruleset.simulation_settings().max_health as i32;
+            let heals = |mv: &Move| {
+                hazard_damage < 0
+                    && s.health < max_health
+                    && self.get_hazard_count(&self.new_head_position(s.head, *mv)) > 0
+            };
+            let reasonable = self.reasonable_moves(s);
+
+            let roomy = match level {
+                ReasonableMoveLevel::Basic => None,
+                ReasonableMoveLevel::SpaceAware => {
+                    let roomy = reasonable
                         .iter()
+                        .copied()
                         .filter(|mv| {
-                            let new_head = s.head.add_vec(mv.to_vector());
-                            new_head != s.body[1]
+                            let new_head = self.new_head_position(s.head, *mv);
+                            self.bounded_flood_fill(new_head, s.body.len()) >= s.body.len()
                         })
-                        .choose(rng)
-                        .copied()
-                        .unwrap()
-                }),
-            )
+                        .collect::<Vec<_>>();
+                    choose_preferring(rng, &roomy, heals)
+                }
+            };
+
+            let choice = match roomy.or_else(|| choose_preferring(rng, &reasonable, heals)) {
+                Some(mv) => ReasonableMove { mv, forced: false },
+                None => self.forced_move(s, rng),
+            };
+            (s.id.clone(), choice)
+        }))
+    }
+}
+
+impl WeightedReasonableMovesGame for Game {
+    fn random_reasonable_move_weighted<'a, W>(
+        &'a self,
+        rng: &'a mut impl rand::Rng,
+        mut weight: W,
+    ) -> Box<dyn Iterator<Item = (Self::SnakeIDType, ReasonableMove)> + 'a>
+    where
+        W: FnMut(&Self::SnakeIDType, Move, &Self::NativePositionType) -> f64 + 'a,
+    {
+        Box::new(self.living_snakes().map(move |s| {
+            let weighted = self
+                .reasonable_moves(s)
+                .into_iter()
+                .map(|mv| (mv, weight(&s.id, mv, &self.new_head_position(s.head, mv))))
+                .collect::<Vec<_>>();
+            let choice = match choose_weighted(rng, &weighted) {
+                Some(mv) => ReasonableMove { mv, forced: false },
+                None => self.forced_move(s, rng),
+            };
+            (s.id.clone(), choice)
         }))
     }
 }
 
+impl Game {
+    /// the moves for `s` that don't hit a wall, its neck, a body that isn't about to move out of
+    /// the way, or enough hazard to kill it
+    fn reasonable_moves(&self, s: &BattleSnake) -> Vec<Move> {
+        let hazard_damage: i32 = self.get_hazard_damage().into();
+        self.possible_head_squares(&s.id)
+            .iter()
+            .filter(|(_, new_head)| {
+                let unreasonable = self.living_snakes().any(|other| {
+                    other.body.contains(new_head) && other.vacating_tail() != Some(**new_head)
+                }) || self.get_hazard_count(new_head) as i32 * hazard_damage
+                    >= s.health;
+
+                !unreasonable
+            })
+            .map(|(mv, _)| mv)
+            .collect()
+    }
+
+    /// the least bad move for a snake without a reasonable one
+    fn forced_move(&self, s: &BattleSnake, rng: &mut impl rand::Rng) -> ReasonableMove {
+        let not_neck = |mv: &Move| Some(self.new_head_position(s.head, *mv)) != s.neck();
+        let mv = Move::all_iter()
+            .filter(not_neck)
+            .filter(|mv| {
+                let new_head = self.new_head_position(s.head, *mv);
+                !self.off_board(new_head) && !self.board.walls.contains(&new_head)
+            })
+            .choose(rng)
+            .or_else(|| Move::all_iter().filter(not_neck).choose(rng))
+            .unwrap_or(Move::Up);
+        ReasonableMove { mv, forced: true }
+    }
+}
+
 impl Display for Game {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         writeln!(f)?;
-        for i in 0..self.board.height {
-            let k = self.board.height - i - 1;
-            for j in 0..self.board.width {
-                let position = Position {
-                    x: j,
-                    y: k,
-                };
+        for row in 0..self.board.height {
+            for col in 0..self.board.width {
+                let position = Position::from_row_col(row, col, self.board.height);
                 if self.board.food.contains(&position) {
                     write!(f, "f")?;
                 } else if self.board.snakes.iter().any(|s| s.head == position) {
@@ -303,6 +1139,8 @@ impl Display for Game {
                     }
                 } else if self.board.snakes.iter().any(|s| s.body.contains(&position)) {
                     write!(f, "s")?;
+                } else if self.board.walls.contains(&position) {
+                    write!(f, "#")?;
                 } else if self.board.hazards.contains(&position) {
                     write!(f, "x")?;
                 } else {
@@ -323,12 +1161,24 @@ impl Display for Game {
     }
 }
 
+/// In squad games the game is over once at most one squad is left, and the winner is a snake
+/// from the winning squad (you, if you're still in it)
 impl VictorDeterminableGame for Game {
     fn is_over(&self) -> bool {
-        self.you.health == 0 || self.board.snakes.len() == 1
+        if self.is_squad() {
+            return self.living_squads().len() <= 1;
+        }
+        !self.you_are_alive() || self.board.snakes.len() == 1
     }
 
     fn get_winner(&self) -> Option<String> {
+        if self.is_squad() {
+            let squad = self.get_winning_squad()?;
+            return snakes_in_squad(self, &squad)
+                .find(|s| s.id == self.you.id)
+                .or_else(|| snakes_in_squad(self, &squad).next())
+                .map(|s| s.id.clone());
+        }
         if self.is_over() {
             Some(
                 self.snake_ids()
@@ -345,15 +1195,51 @@ impl VictorDeterminableGame for Game {
     fn alive_snake_count(&self) -> usize {
         self.board.snakes.iter().filter(|s| s.health > 0).count()
     }
+
+    fn game_result(&self) -> GameResult<String> {
+        if self.is_squad() {
+            if self.living_squads().len() > 1 {
+                return GameResult::Ongoing;
+            }
+            return self
+                .get_winner()
+                .map_or(GameResult::Draw, GameResult::Winner);
+        }
+        let mut alive = self.board.snakes.iter().filter(|s| s.health > 0);
+        match (alive.next(), alive.next()) {
+            (None, _) => GameResult::Draw,
+            (Some(winner), None) => GameResult::Winner(winner.id.clone()),
+            _ => GameResult::Ongoing,
+        }
+    }
 }
 
-impl YouDeterminableGame for Game {
-    /// determines for a given game if a given snake id is you.
+fn snakes_in_squad<'a>(game: &'a Game, squad: &'a str) -> impl Iterator<Item = &'a BattleSnake> {
+    game.board
+        .snakes
+        .iter()
+        .filter(move |s| s.health > 0 && s.squad.as_deref() == Some(squad))
+}
+
+impl SquadVictorDeterminableGame for Game {
+    type SquadIDType = String;
+
+    fn get_winning_squad(&self) -> Option<String> {
+        match self.living_squads().as_slice() {
+            [squad] if self.is_over() => Some(squad.to_string()),
+            _ => None,
+        }
+    }
+}
+
+impl YouDeterminableGame for Game {
+    /// determines for a given game if a given snake id is you.
     fn is_you(&self, snake_id: &Self::SnakeIDType) -> bool {
         snake_id == &self.you.id
     }
 
-    /// get the id for you for a given game
+    /// get the id for you for a given game, taken from `you` so it is still your id after you
+    /// have been eliminated
     fn you_id(&self) -> &Self::SnakeIDType {
         &self.you.id
     }
@@ -362,14 +1248,12 @@ impl YouDeterminableGame for Game {
 impl LengthGettableGame for Game {
     type LengthType = usize;
 
+    /// your length is taken from `you` once you have been eliminated, other snakes that aren't
+    /// on the board have no length
     fn get_length(&self, snake_id: &Self::SnakeIDType) -> Self::LengthType {
-        self.board
-            .snakes
-            .iter()
-            .find(|s| &s.id == snake_id)
-            .unwrap()
-            .body
-            .len()
+        self.snake_or_you(snake_id)
+            .map(|s| s.body.len())
+            .unwrap_or(0)
     }
 
     fn get_length_i64(&self, snake_id: &Self::SnakeIDType) -> i64 {
@@ -397,9 +1281,41 @@ impl PositionGettableGame for Game {
     }
 }
 
+impl NeckQueryableGame for Game {
+    fn is_neck(&self, sid: &Self::SnakeIDType, pos: &Self::NativePositionType) -> bool {
+        self.snake_by_id(sid).and_then(BattleSnake::neck) == Some(*pos)
+    }
+}
+
+impl HeadSquaresGame for Game {}
+
+impl SnakeViewGame for Game {}
+
+impl WallQueryableGame for Game {
+    fn is_wall(&self, pos: &Self::NativePositionType) -> bool {
+        self.board.walls.contains(pos)
+    }
+}
+
+/// food off the board is ignored, see [Game::validate]
+impl FoodQueryableGame for Game {
+    fn is_food(&self, pos: &Self::NativePositionType) -> bool {
+        self.board.food.contains(pos) && !self.off_board(*pos)
+    }
+}
+
+/// food off the board is ignored, see [Game::validate], and the food is sorted
 impl FoodGettableGame for Game {
     fn get_all_food_as_positions(&self) -> Vec<crate::wire_representation::Position> {
-        self.board.food.clone().into_iter().collect()
+        let mut food = self
+            .board
+            .food
+            .iter()
+            .copied()
+            .filter(|pos| !self.off_board(*pos))
+            .collect::<Vec<_>>();
+        food.sort();
+        food
     }
 
     fn get_all_food_as_native_positions(&self) -> Vec<Self::NativePositionType> {
@@ -440,28 +1356,21 @@ impl HeadGettableGame for Game {
         self.get_head_as_native_position(snake_id)
     }
 
+    /// your head is taken from `you` once you have been eliminated. Panics for any other snake
+    /// that isn't on the board, since it has no head
     fn get_head_as_native_position(
         &self,
         snake_id: &Self::SnakeIDType,
     ) -> Self::NativePositionType {
-        self.board
-            .snakes
-            .iter()
-            .find(|s| &s.id == snake_id)
-            .unwrap()
+        self.snake_or_you(snake_id)
+            .unwrap_or_else(|| panic!("no snake with id {}", snake_id))
             .head
     }
 }
 
 impl ShoutGettableGame for Game {
     fn get_shout(&self, snake_id: &Self::SnakeIDType) -> Option<String> {
-        self.board
-            .snakes
-            .iter()
-            .find(|s| &s.id == snake_id)
-            .unwrap()
-            .shout
-            .clone()
+        self.snake_or_you(snake_id)?.shout.clone()
     }
 }
 
@@ -477,7 +1386,7 @@ impl SizeDeterminableGame for Game {
 
 impl TurnDeterminableGame for Game {
     fn turn(&self) -> u64 {
-        self.turn.try_into().unwrap()
+        self.turn.max(0) as u64
     }
 }
 
@@ -490,15 +1399,12 @@ impl SnakeBodyGettableGame for Game {
         &self,
         snake_id: &Self::SnakeIDType,
     ) -> Box<dyn Iterator<Item = Self::NativePositionType> + '_> {
+        // your body is taken from `you` once you have been eliminated, other snakes that aren't
+        // on the board have no body
         Box::new(
-            self.board
-                .snakes
-                .iter()
-                .find(|s| &s.id == snake_id)
-                .unwrap()
-                .body
-                .iter()
-                .cloned(),
+            self.snake_or_you(snake_id)
+                .into_iter()
+                .flat_map(|s| s.body.iter().copied()),
         )
     }
 }
@@ -508,13 +1414,31 @@ impl HazardQueryableGame for Game {
         self.board.hazards.contains(pos)
     }
 
-    fn get_hazard_damage(&self) -> u8 {
-        self.game
-            .ruleset
-            .settings
-            .as_ref()
-            .map(|settings| settings.hazard_damage_per_turn)
-            .unwrap_or(15) as u8
+    fn get_hazard_count(&self, pos: &Self::NativePositionType) -> u8 {
+        let count = self.board.hazards.iter().filter(|h| *h == pos).count();
+        count.min(u8::MAX as usize) as u8
+    }
+
+    fn get_hazard_damage(&self) -> i16 {
+        self.game.ruleset.simulation_settings().hazard_damage
+    }
+}
+
+impl MaskedQueryableGame for Game {
+    fn is_visible(&self, pos: &Self::NativePositionType) -> bool {
+        !self.off_board(*pos)
+    }
+
+    fn food_at(&self, pos: &Self::NativePositionType) -> Knowledge {
+        self.is_food(pos).into()
+    }
+
+    fn hazard_at(&self, pos: &Self::NativePositionType) -> Knowledge {
+        self.is_hazard(pos).into()
+    }
+
+    fn snake_body_at(&self, pos: &Self::NativePositionType) -> Knowledge {
+        self.position_is_snake_body(*pos).into()
     }
 }
 
@@ -528,6 +1452,35 @@ impl HazardSettableGame for Game {
     }
 }
 
+impl FoodSettableGame for Game {
+    fn place_food(&mut self, pos: Self::NativePositionType) -> Result<(), crate::Error> {
+        if self.off_board(pos) {
+            return Err(crate::Error::InvalidFood(format!(
+                "({}, {}) is off the board",
+                pos.x, pos.y
+            )));
+        }
+        if self.board.snakes.iter().any(|s| s.body.contains(&pos)) {
+            return Err(crate::Error::InvalidFood(format!(
+                "there is a snake at ({}, {})",
+                pos.x, pos.y
+            )));
+        }
+        if self.board.walls.contains(&pos) {
+            return Err(crate::Error::InvalidFood(format!(
+                "there is a wall at ({}, {})",
+                pos.x, pos.y
+            )));
+        }
+        self.board.food.insert(pos);
+        Ok(())
+    }
+
+    fn remove_food(&mut self, pos: Self::NativePositionType) {
+        self.board.food.remove(&pos);
+    }
+}
+
 impl NeighborDeterminableGame for Game {
     fn neighbors<'a>(
         &'a self,
@@ -542,7 +1495,7 @@ impl NeighborDeterminableGame for Game {
     ) -> Box<dyn Iterator<Item = (Move, Self::NativePositionType)> + 'a> {
         let clone = *pos;
         Box::new(Move::all_iter().filter_map(move |m| {
-            let v = m.to_vector();
+            let v = NEIGHBOR_OFFSETS[m.as_index()];
 
             let mut new_pos = clone.add_vec(v);
 
@@ -564,6 +1517,9 @@ impl NeighborDeterminableGame for Game {
 
                 return None;
             }
+            if self.board.walls.contains(&new_pos) {
+                return None;
+            }
 
             Some((m, new_pos))
         }))
@@ -582,6 +1538,285 @@ mod tests {
         g.expect("the json literal is valid")
     }
 
+    #[test]
+    fn test_game_from_parts() {
+        let p = Position::new;
+        let mut g = Game::empty(7, 7);
+        g.add_you("you", vec![p(1, 3), p(1, 2), p(1, 2)], 100)
+            .unwrap();
+        g.board
+            .add_snake("opponent", vec![p(5, 3), p(5, 4), p(5, 5)], 90)
+            .unwrap();
+        g.board.add_food(p(3, 3)).unwrap();
+        g.board.add_hazard(p(0, 0)).unwrap();
+        g.board.add_hazard(p(0, 0)).unwrap();
+
+        assert_eq!(g.your_head(), p(1, 3));
+        assert_eq!(g.get_snake_ids(), vec!["you", "opponent"]);
+        assert_eq!(g.get_length(&"opponent".to_string()), 3);
+        assert_eq!(g.board.hazards.len(), 2);
+        assert!(!g.is_over());
+
+        let illegal = vec![
+            ("on a snake", vec![p(5, 5)], 100),
+            ("on food", vec![p(3, 3)], 100),
+            ("off the board", vec![p(7, 0)], 100),
+            ("empty", vec![], 100),
+            ("dead", vec![p(3, 0)], 0),
+            ("you", vec![p(3, 0)], 100),
+            (
+                "crossing",
+                vec![p(3, 0), p(4, 0), p(4, 1), p(3, 1), p(3, 0)],
+                100,
+            ),
+        ];
+        for (id, body, health) in illegal {
+            assert!(g.board.add_snake(id, body, health).is_err(), "{}", id);
+        }
+        assert!(matches!(
+            g.board.add_snake("you", vec![p(3, 0)], 100),
+            Err(crate::Error::DuplicateSnakeId(_))
+        ));
+        assert!(matches!(
+            g.board.add_snake("off", vec![p(7, 0)], 100),
+            Err(crate::Error::InvalidSnake { .. })
+        ));
+        assert!(matches!(
+            g.board.add_food(p(1, 2)),
+            Err(crate::Error::InvalidFood(_))
+        ));
+        assert!(g.board.add_food(p(3, 3)).is_err());
+        assert!(g.board.add_food(p(-1, 3)).is_err());
+        assert!(matches!(
+            g.board.add_hazard(p(0, 7)),
+            Err(crate::Error::InvalidHazard(_))
+        ));
+        assert_eq!(g.board.snakes.len(), 2);
+        assert_eq!(g.board.food.len(), 1);
+    }
+
+    #[test]
+    fn test_orientation() {
+        // food in the top left corner and you in the bottom right, heading up
+        let p = Position::new;
+        let mut g = Game::empty(7, 7);
+        g.add_you("you", vec![p(6, 1), p(6, 0), p(6, 0)], 100)
+            .unwrap();
+        g.board.add_food(p(0, 6)).unwrap();
+
+        assert_eq!(p(0, 6).to_row_col(7), (0, 0));
+        assert_eq!(p(6, 0).to_row_col(7), (6, 6));
+        for pos in g.all_positions() {
+            let (row, col) = pos.to_row_col(7);
+            assert_eq!(Position::from_row_col(row, col, 7), pos);
+        }
+        assert_eq!(g.all_positions().next(), Some(p(0, 6)));
+        assert_eq!(g.new_head_position(g.your_head(), Move::Up), p(6, 2));
+
+        let drawn = g.to_string();
+        let rows = drawn.lines().skip(1).take(7).collect_vec();
+        assert!(rows[0].starts_with('f'));
+        assert!(rows[5].ends_with("S "));
+        assert!(rows[6].ends_with("s "));
+
+        let grid = crate::heatmap::Grid::from_fn(7, 7, |pos| g.is_food(&pos));
+        assert!(grid.rows().next().unwrap()[0]);
+        assert_eq!(grid.get(0, 6), Some(&true));
+
+        // compact boards index cells from the bottom left, but draw the same way
+        let compact: compact_representation::standard::CellBoard4Snakes7x7 =
+            g.as_cell_board(&build_snake_id_map(&g)).unwrap();
+        let food = compact.native_from_position(p(0, 6));
+        assert_eq!(food.as_usize(), 6 * 7);
+        assert!(compact.is_food(&food));
+        assert_eq!(food.into_position(7), p(0, 6));
+        let drawn = compact.to_string();
+        let rows = drawn.lines().skip(1).take(7).collect_vec();
+        assert!(rows[0].starts_with('f'));
+        assert!(rows[5].ends_with("0 "));
+    }
+
+    #[test]
+    fn test_validate() {
+        let g = crate::game_fixture(include_str!("../../fixtures/late_stage.json"));
+        assert!(g.validate().is_ok());
+
+        let mut disconnected = g.clone();
+        disconnected.board.snakes[0].body[1].x += 2;
+        assert!(matches!(
+            disconnected.validate(),
+            Err(crate::Error::InvalidSnake { .. })
+        ));
+
+        let mut duplicated = g.clone();
+        duplicated.board.snakes[1].id = duplicated.board.snakes[0].id.clone();
+        assert!(matches!(
+            duplicated.validate(),
+            Err(crate::Error::DuplicateSnakeId(_))
+        ));
+
+        let mut eliminated = disconnected;
+        eliminated.board.snakes[0].health = 0;
+        assert!(eliminated.validate().is_ok());
+
+        // eating straight after spawning leaves 3 pieces on the tail
+        let mut deep_tail = g.clone();
+        let tail = *deep_tail.board.snakes[0].body.back().unwrap();
+        deep_tail.board.snakes[0].body.extend([tail, tail]);
+        assert!(deep_tail.validate().is_ok());
+
+        let mut stacked_neck = g.clone();
+        let neck = stacked_neck.board.snakes[0].body[1];
+        stacked_neck.board.snakes[0].body.insert(1, neck);
+        assert!(stacked_neck.validate().is_err());
+
+        let mut two_on_one_square = g;
+        let head = two_on_one_square.board.snakes[0].head;
+        two_on_one_square.board.snakes[0].body = vec![head, head].into();
+        assert!(two_on_one_square.validate().is_err());
+    }
+
+    #[test]
+    fn test_anomalous_food() {
+        let g = crate::game_fixture(include_str!("../../fixtures/anomalous_food.json"));
+        // seven sent, one of them twice and one at x == width
+        assert_eq!(g.board.food.len(), 6);
+        assert_eq!(g.get_all_food_as_positions().len(), 5);
+        assert!(!g.is_food(&Position::new(11, 3)));
+        assert_eq!(
+            g.validate().unwrap(),
+            vec![
+                crate::ValidationWarning::DuplicateFood(Position::new(4, 8)),
+                crate::ValidationWarning::FoodOffBoard(Position::new(11, 3)),
+            ]
+        );
+        assert!(
+            crate::game_fixture(include_str!("../../fixtures/start_of_game.json"))
+                .validate()
+                .unwrap()
+                .is_empty()
+        );
+
+        let snake_ids = build_snake_id_map(&g);
+        let compact: compact_representation::StandardCellBoard4Snakes11x11 =
+            g.as_cell_board(&snake_ids).unwrap();
+        assert_eq!(compact.get_all_food_as_positions().len(), 5);
+
+        // the food sent twice is only eaten once
+        let eater = g.you.id.clone();
+        assert_eq!(g.you.head, Position::new(5, 8));
+        let moves = g
+            .get_snake_ids()
+            .into_iter()
+            .map(|id| {
+                let head = g.get_head_as_native_position(&id);
+                let mv = if id == eater {
+                    Move::Left
+                } else {
+                    Move::all_iter()
+                        .find(|mv| !g.is_food(&g.new_head_position(head, *mv)))
+                        .unwrap()
+                };
+                (id, mv)
+            })
+            .collect_vec();
+        let next = g.simulate_turn(moves.clone());
+        assert_eq!(next.get_all_food_as_positions().len(), 4);
+        assert!(!next.is_food(&Position::new(4, 8)));
+
+        let compact_moves = moves
+            .iter()
+            .map(|(id, mv)| (snake_ids[id], [*mv]))
+            .collect_vec();
+        let (_, compact_next) = compact
+            .simulate_with_moves(&StatsInstruments::new(), compact_moves)
+            .next()
+            .unwrap();
+        assert_eq!(compact_next.get_all_food_as_positions().len(), 4);
+    }
+
+    #[test]
+    fn test_you_accessors() {
+        let g = fixture();
+        let you = g.you_id().clone();
+        assert_eq!(g.your_head(), g.get_head_as_native_position(&you));
+        assert_eq!(g.your_health(), g.get_health(&you));
+        assert_eq!(g.your_length(), g.get_length(&you));
+        assert_eq!(g.snake_by_id(&you), Some(&g.you));
+        assert_eq!(g.snake_by_id("not a snake"), None);
+
+        let others = g.other_snakes().map(|s| s.id.clone()).collect_vec();
+        assert_eq!(others.len(), g.board.snakes.len() - 1);
+        assert!(!others.contains(&you));
+        for id in others {
+            assert!(g.snake_by_id(&id).is_some());
+        }
+    }
+
+    #[test]
+    fn test_you_accessors_after_elimination() {
+        let mut g = fixture();
+        let you = g.you.id.clone();
+        g.board.snakes.retain(|s| s.id != you);
+
+        assert_eq!(g.your_head(), g.you.head);
+        assert_eq!(g.your_health(), 0);
+        assert_eq!(g.your_length(), g.you.body.len());
+        assert_eq!(g.snake_by_id(&you), None);
+        assert_eq!(g.other_snakes().count(), g.board.snakes.len());
+    }
+
+    #[test]
+    fn test_end_request_you_lost() {
+        let g = crate::game_fixture(include_str!("../../fixtures/end_lost.json"));
+        // you ran into your own neck, so `you` still has health but isn't on the board
+        assert!(g.you.health > 0);
+        assert!(!g.you_are_alive());
+        assert!(!g.you_are_winner());
+        assert!(g.is_over());
+        assert_eq!(g.you_id(), &g.you.id);
+        assert_eq!(g.get_winner(), Some(g.board.snakes[0].id.clone()));
+        // the getters fall back to `you`, rather than panicking because you aren't on the board
+        assert_eq!(g.get_length(g.you_id()), g.you.body.len());
+        assert_eq!(g.get_head_as_native_position(g.you_id()), g.you.head);
+        assert_eq!(g.get_shout(g.you_id()), g.you.shout);
+        assert_eq!(
+            g.get_snake_body_vec(g.you_id()),
+            g.you.body.iter().copied().collect::<Vec<_>>()
+        );
+        assert_eq!(g.get_health(g.you_id()), 0);
+        assert_eq!(g.get_length(&"not a snake".to_string()), 0);
+
+        let mut starved = g.clone();
+        starved.you.health = 0;
+        starved.board.snakes.push(starved.you.clone());
+        assert!(!starved.you_are_alive());
+
+        for g in [g, starved] {
+            let snake_ids = build_snake_id_map(&g);
+            let compact: compact_representation::StandardCellBoard4Snakes11x11 =
+                g.as_cell_board(&snake_ids).unwrap();
+            assert!(!compact.is_alive(compact.you_id()));
+            assert!(compact.is_alive(&snake_ids[&g.board.snakes[0].id]));
+            assert_eq!(compact.check_invariants(), Ok(()));
+            assert!(compact.is_over());
+        }
+    }
+
+    #[test]
+    fn test_end_request_you_won() {
+        let g = crate::game_fixture(include_str!("../../fixtures/end_won.json"));
+        assert!(g.you_are_alive());
+        assert!(g.you_are_winner());
+        assert!(g.is_over());
+        assert_eq!(g.get_winner().as_ref(), Some(g.you_id()));
+
+        let compact: compact_representation::StandardCellBoard4Snakes11x11 =
+            g.as_cell_board(&build_snake_id_map(&g)).unwrap();
+        assert!(compact.is_alive(compact.you_id()));
+        assert_eq!(compact.get_winner(), Some(SnakeId(0)));
+    }
+
     #[test]
     fn test_hazard_deserialization() {
         let empty_string_hazard = include_str!("../../fixtures/empty_str_hazard.json");
@@ -743,6 +1978,996 @@ mod tests {
         assert_eq!(possible_moves, expected);
     }
 
+    #[test]
+    fn test_possible_head_squares() {
+        use rand::{rngs::SmallRng, SeedableRng};
+
+        let p = Position::new;
+        let squares = |g: &Game, id: &str| {
+            g.possible_head_squares(&id.to_string())
+                .iter()
+                .map(|(mv, pos)| (mv, *pos))
+                .collect_vec()
+        };
+        // you on the left edge heading left, an opponent that can meet you on (0, 4) and a
+        // snake with only a head on the bottom edge
+        let mut g = Game::empty(7, 7);
+        g.add_you("you", vec![p(0, 3), p(1, 3), p(2, 3)], 100)
+            .unwrap();
+        g.board
+            .add_snake("opponent", vec![p(1, 4), p(2, 4), p(3, 4)], 100)
+            .unwrap();
+        g.board.add_snake("head", vec![p(4, 0)], 100).unwrap();
+
+        assert_eq!(
+            squares(&g, "you"),
+            vec![(Move::Up, p(0, 4)), (Move::Down, p(0, 2))]
+        );
+        assert_eq!(
+            squares(&g, "head"),
+            vec![
+                (Move::Up, p(4, 1)),
+                (Move::Left, p(3, 0)),
+                (Move::Right, p(5, 0))
+            ]
+        );
+        let threats = g.all_threat_squares();
+        assert_eq!(threats[&p(0, 4)], vec!["you", "opponent"]);
+        // moving on to a body is still a possible square, only the neck is ruled out
+        assert_eq!(threats[&p(1, 3)], vec!["opponent"]);
+        assert!(!threats.contains_key(&p(2, 4)));
+        assert_eq!(threats.values().map(Vec::len).sum::<usize>(), 8);
+
+        // the reasonable moves never leave the possible squares
+        let mut rng = SmallRng::seed_from_u64(3);
+        for _ in 0..20 {
+            for (id, reasonable) in g.random_reasonable_move_with_status_for_each_snake(&mut rng) {
+                assert!(g.possible_head_squares(&id).moves().contains(reasonable.mv));
+            }
+        }
+
+        let mut wrapped = g.clone();
+        wrapped.game.ruleset.name = "wrapped".to_string();
+        assert_eq!(
+            squares(&wrapped, "you"),
+            vec![
+                (Move::Up, p(0, 4)),
+                (Move::Down, p(0, 2)),
+                (Move::Left, p(6, 3))
+            ]
+        );
+        assert_eq!(squares(&wrapped, "head").len(), 4);
+        assert_eq!(wrapped.all_threat_squares()[&p(4, 6)], vec!["head"]);
+
+        // the compact boards agree, though they can't hold a snake with only a head
+        g.board.snakes.pop();
+        wrapped.board.snakes.pop();
+        let snake_ids = build_snake_id_map(&g);
+        let compact: compact_representation::standard::CellBoard4Snakes7x7 =
+            g.as_cell_board(&snake_ids).unwrap();
+        let compact_wrapped: compact_representation::wrapped::CellBoard4SnakesSquare7x7 =
+            wrapped.as_wrapped_cell_board(&snake_ids).unwrap();
+        let by_id = |threats: HashMap<Position, Vec<String>>| {
+            threats
+                .into_iter()
+                .map(|(pos, ids)| (pos, ids.iter().map(|id| snake_ids[id]).collect_vec()))
+                .collect::<HashMap<_, _>>()
+        };
+        assert_eq!(compact.all_threat_squares(), by_id(g.all_threat_squares()));
+        assert_eq!(
+            compact_wrapped.all_threat_squares(),
+            by_id(wrapped.all_threat_squares())
+        );
+
+        // eliminated snakes can't go anywhere
+        g.board.snakes[1].health = 0;
+        assert!(squares(&g, "opponent").is_empty());
+    }
+
+    #[test]
+    fn test_random_reasonable_move_fully_enclosed() {
+        use rand::{rngs::SmallRng, SeedableRng};
+
+        let g = crate::game_fixture(include_str!("../../fixtures/cornered.json"));
+        let mut rng = SmallRng::seed_from_u64(4);
+
+        for _ in 0..10 {
+            let moves = g
+                .random_reasonable_move_with_status_for_each_snake(&mut rng)
+                .collect_vec();
+            // boxed in by our own body, the least bad move is in to our body rather than off the
+            // board or back in to our neck
+            assert_eq!(
+                moves,
+                vec![(
+                    "you".to_string(),
+                    ReasonableMove {
+                        mv: Move::Right,
+                        forced: true
+                    }
+                )]
+            );
+        }
+    }
+
+    #[test]
+    fn test_weighted_random_reasonable_moves() {
+        use rand::{rngs::SmallRng, SeedableRng};
+
+        let mut builder = crate::compact_representation::CellBoardBuilder::new(11, 11);
+        let body = vec![
+            Position::new(5, 5),
+            Position::new(5, 4),
+            Position::new(5, 3),
+        ];
+        builder.add_snake(body, 50).unwrap();
+        // healing hazards are only preferred without weights
+        builder.hazard_damage(-10);
+        builder.add_hazard(Position::new(6, 5)).unwrap();
+        let g = builder.game().clone();
+        let mut rng = SmallRng::seed_from_u64(7);
+
+        let mut counts = HashMap::new();
+        for _ in 0..4000 {
+            let moves = g
+                .random_reasonable_move_weighted(&mut rng, |id, mv, destination| {
+                    assert_eq!(id, &g.you.id);
+                    assert_eq!(*destination, Position::new(5, 5).add_vec(mv.to_vector()));
+                    match mv {
+                        Move::Up => 3.0,
+                        Move::Left => 1.0,
+                        _ => 0.0,
+                    }
+                })
+                .collect_vec();
+            assert_eq!(moves.len(), 1);
+            assert!(!moves[0].1.forced);
+            *counts.entry(moves[0].1.mv).or_insert(0) += 1;
+        }
+        assert_eq!(counts.get(&Move::Right), None);
+        assert!((2850..=3150).contains(&counts[&Move::Up]), "{:?}", counts);
+
+        // when every reasonable move is ruled out they are all equally likely
+        let moves = (0..300)
+            .map(|_| {
+                g.random_reasonable_move_weighted(&mut rng, |_, _, _| 0.0)
+                    .next()
+                    .unwrap()
+                    .1
+                    .mv
+            })
+            .collect::<HashSet<_>>();
+        assert_eq!(
+            moves,
+            [Move::Up, Move::Left, Move::Right]
+                .iter()
+                .copied()
+                .collect()
+        );
+
+        let cornered = crate::game_fixture(include_str!("../../fixtures/cornered.json"));
+        let moves = cornered
+            .random_reasonable_move_weighted(&mut rng, |_, _, _| -> f64 {
+                panic!("forced moves aren't weighted")
+            })
+            .collect_vec();
+        assert_eq!(
+            moves,
+            vec![(
+                "you".to_string(),
+                ReasonableMove {
+                    mv: Move::Right,
+                    forced: true
+                }
+            )]
+        );
+    }
+
+    #[test]
+    fn test_random_reasonable_move_vacating_tail() {
+        use rand::{rngs::SmallRng, SeedableRng};
+
+        let g = crate::game_fixture(include_str!("../../fixtures/tail_exit.json"));
+        let mut rng = SmallRng::seed_from_u64(4);
+
+        for _ in 0..10 {
+            let moves = g
+                .random_reasonable_move_with_status_for_each_snake(&mut rng)
+                .collect_vec();
+            assert_eq!(
+                moves,
+                vec![(
+                    "you".to_string(),
+                    ReasonableMove {
+                        mv: Move::Right,
+                        forced: false
+                    }
+                )]
+            );
+        }
+    }
+
+    #[test]
+    fn test_random_reasonable_moves_with_healing_hazards() {
+        use rand::{rngs::SmallRng, SeedableRng};
+
+        let mut builder = crate::compact_representation::CellBoardBuilder::new(11, 11);
+        let body = vec![
+            Position::new(5, 5),
+            Position::new(5, 4),
+            Position::new(5, 3),
+        ];
+        builder.add_snake(body, 4).unwrap();
+        builder.hazard_damage(-10);
+        builder.add_hazard(Position::new(4, 5)).unwrap();
+        let mut g = builder.game().clone();
+        assert_eq!(g.get_hazard_damage(), -10);
+        let mut rng = SmallRng::seed_from_u64(4);
+
+        // a hurt snake heads for the healing square, however little health it has left
+        for level in [ReasonableMoveLevel::Basic, ReasonableMoveLevel::SpaceAware].iter() {
+            for _ in 0..10 {
+                let moves = g
+                    .random_reasonable_move_at_level_for_each_snake(&mut rng, *level)
+                    .map(|(_, mv)| mv.mv)
+                    .collect_vec();
+                assert_eq!(moves, vec![Move::Left]);
+            }
+        }
+
+        // at full health it doesn't need to
+        g.board.snakes[0].health = 100;
+        g.you.health = 100;
+        let moves = (0..100)
+            .map(|_| {
+                g.random_reasonable_move_for_each_snake(&mut rng)
+                    .next()
+                    .unwrap()
+                    .mv
+            })
+            .collect::<HashSet<_>>();
+        assert_eq!(moves.len(), 3);
+    }
+
+    #[test]
+    fn test_random_reasonable_moves_skip_eliminated_snakes() {
+        use rand::{rngs::SmallRng, SeedableRng};
+
+        let mut g = fixture();
+        let eliminated = g.board.snakes[1].id.clone();
+        g.board.snakes[1].health = 0;
+        let mut rng = SmallRng::seed_from_u64(4);
+
+        let moves = g
+            .random_reasonable_move_for_each_snake(&mut rng)
+            .map(|m| m.snake_id)
+            .collect_vec();
+        assert_eq!(moves.len(), g.alive_snake_count());
+        assert!(!moves.contains(&eliminated));
+    }
+
+    #[test]
+    fn test_space_aware_random_reasonable_move_avoids_pocket() {
+        use rand::{rngs::SmallRng, SeedableRng};
+
+        let g = crate::game_fixture(include_str!("../../fixtures/small_pocket.json"));
+        let mut rng = SmallRng::seed_from_u64(4);
+
+        let mut basic_moves = HashSet::new();
+        for _ in 0..50 {
+            let (_, basic) = g
+                .random_reasonable_move_with_status_for_each_snake(&mut rng)
+                .find(|(id, _)| id == "you")
+                .unwrap();
+            basic_moves.insert(basic.mv);
+
+            let (_, space_aware) = g
+                .random_reasonable_move_at_level_for_each_snake(
+                    &mut rng,
+                    ReasonableMoveLevel::SpaceAware,
+                )
+                .find(|(id, _)| id == "you")
+                .unwrap();
+            assert_eq!(
+                space_aware,
+                ReasonableMove {
+                    mv: Move::Right,
+                    forced: false
+                }
+            );
+        }
+        assert!(basic_moves.contains(&Move::Left) || basic_moves.contains(&Move::Down));
+    }
+
+    #[test]
+    fn test_flood_fill_sealed_region() {
+        let g = crate::game_fixture(include_str!("../../fixtures/sealed_region.json"));
+        let start = Position::new(0, 0);
+
+        let reached = g.flood_fill(&start, FloodFillOptions::default());
+        assert_eq!(reached.len(), 32);
+        assert!(reached.iter().all(|p| p.x < 3));
+
+        let hazards_block = FloodFillOptions {
+            hazards_block: true,
+            ..Default::default()
+        };
+        assert_eq!(g.flood_fill_count(&start, hazards_block), 0);
+
+        let depth_limited = FloodFillOptions {
+            max_depth: Some(2),
+            ..Default::default()
+        };
+        assert_eq!(g.flood_fill_count(&start, depth_limited), 5);
+    }
+
+    #[test]
+    fn test_flood_fill_wraps_across_seam() {
+        let mut g = crate::game_fixture(include_str!("../../fixtures/wrapped_column.json"));
+        let options = FloodFillOptions {
+            max_depth: Some(4),
+            ..Default::default()
+        };
+        let start = Position::new(0, 5);
+
+        let reached = g.flood_fill(&start, options);
+        assert_eq!(reached.len(), 32);
+        assert!(reached.contains(&Position::new(10, 5)));
+
+        g.game.ruleset.name = "standard".to_string();
+        let reached = g.flood_fill(&start, options);
+        assert_eq!(reached.len(), 16);
+        assert!(!reached.contains(&Position::new(10, 5)));
+    }
+
+    #[test]
+    fn test_voronoi_symmetric_duel() {
+        let g = crate::game_fixture(include_str!("../../fixtures/voronoi_duel.json"));
+
+        let control = g.voronoi(VoronoiOptions::default());
+        assert_eq!(control.len(), 2);
+        assert_eq!(control["you"], control["opponent"]);
+    }
+
+    #[test]
+    fn test_voronoi_hazards_block() {
+        let mut g = crate::game_fixture(include_str!("../../fixtures/voronoi_duel.json"));
+        // wall off the opponent's side of the board, leaving the middle column uncontested
+        g.board.hazards = (0..11).map(|y| Position::new(6, y)).collect();
+        let options = VoronoiOptions {
+            hazards_block: true,
+            ..Default::default()
+        };
+
+        let control = g.voronoi(options);
+        assert_eq!(control["opponent"], 4 * 11 - 1);
+        assert_eq!(control["you"], 6 * 11 - 1);
+    }
+
+    #[test]
+    fn test_a_star_threads_vacating_body() {
+        let mut g = crate::game_fixture(include_str!("../../fixtures/wrapped_column.json"));
+        g.game.ruleset.name = "standard".to_string();
+        let from = Position::new(0, 5);
+        let to = Position::new(4, 5);
+
+        // the column at x = 2 is a solid wall, but (2, 7) moves out of the way just in time
+        let path = g.a_star(&from, &to, AStarOptions::default()).unwrap();
+        assert_eq!(path.len(), 8);
+        let mut current = from;
+        let steps = path
+            .iter()
+            .map(|mv| {
+                current = current.add_vec(mv.to_vector());
+                current
+            })
+            .collect_vec();
+        assert!(steps.contains(&Position::new(2, 7)));
+        assert_eq!(current, to);
+
+        let bodies_block = AStarOptions {
+            bodies_block: true,
+            ..Default::default()
+        };
+        assert_eq!(g.a_star(&from, &to, bodies_block), None);
+    }
+
+    #[test]
+    fn test_a_star_hazards() {
+        let mut g = crate::game_fixture(include_str!("../../fixtures/voronoi_duel.json"));
+        g.board.hazards = vec![Position::new(5, 9)];
+        let from = Position::new(5, 8);
+        let to = Position::new(5, 10);
+
+        let cheap_hazard = AStarOptions {
+            hazard_cost: 1,
+            ..Default::default()
+        };
+        assert_eq!(
+            g.a_star(&from, &to, cheap_hazard),
+            Some(vec![Move::Up, Move::Up])
+        );
+
+        let expensive_hazard = AStarOptions {
+            hazard_cost: 5,
+            ..Default::default()
+        };
+        assert_eq!(g.a_star(&from, &to, expensive_hazard).unwrap().len(), 4);
+
+        let hazards_block = AStarOptions {
+            hazards_block: true,
+            ..Default::default()
+        };
+        assert_eq!(g.a_star(&from, &to, hazards_block).unwrap().len(), 4);
+    }
+
+    #[test]
+    fn test_food_race_analysis() {
+        let mut g = crate::game_fixture(include_str!("../../fixtures/food_race.json"));
+        let summary = |g: &Game, options| {
+            g.food_race_analysis(options)
+                .into_iter()
+                .map(|race| (race.position, race.my_distance, race.winnable))
+                .collect_vec()
+        };
+        assert_eq!(
+            summary(&g, AStarOptions::default()),
+            vec![
+                (Position::new(1, 8), Some(5), true),
+                (Position::new(5, 0), Some(7), false),
+                (Position::new(9, 2), Some(9), false),
+            ]
+        );
+
+        // crossing the hazard costs us, but the opponent has to cross it too
+        g.board.hazards = vec![Position::new(1, 8)];
+        let hazard_cost = AStarOptions {
+            hazard_cost: 10,
+            ..Default::default()
+        };
+        assert_eq!(
+            summary(&g, hazard_cost),
+            vec![
+                (Position::new(5, 0), Some(7), false),
+                (Position::new(9, 2), Some(9), false),
+                (Position::new(1, 8), Some(15), true),
+            ]
+        );
+
+        // being longer wins a tie
+        g.you.body.push_back(Position::new(1, 4));
+        g.board.snakes[0].body.push_back(Position::new(1, 4));
+        assert_eq!(
+            summary(&g, AStarOptions::default())[1],
+            (Position::new(5, 0), Some(7), true)
+        );
+    }
+
+    #[test]
+    fn test_food_denial_opportunities() {
+        // we're a square from the food the starving opponent is two squares from, and the
+        // healthy one gets to the other food well before us
+        let g = crate::game_fixture(include_str!("../../fixtures/food_denial.json"));
+        let summary = |denials: Vec<FoodDenial<Position, String>>| {
+            denials
+                .into_iter()
+                .map(|d| {
+                    (
+                        d.food,
+                        d.opponent,
+                        d.my_distance,
+                        d.their_distance,
+                        d.their_health,
+                    )
+                })
+                .collect_vec()
+        };
+        let you = "you".to_string();
+        let denials = g.food_denial_opportunities(&you, AStarOptions::default());
+        assert_eq!(
+            denials.iter().map(FoodDenial::urgency).collect_vec(),
+            vec![-4, 3, 85]
+        );
+        assert_eq!(
+            summary(denials),
+            vec![
+                (Position::new(0, 6), "starving".to_string(), 6, 9, 5),
+                (Position::new(4, 3), "starving".to_string(), 1, 2, 5),
+                (Position::new(4, 3), "healthy".to_string(), 1, 5, 90),
+            ]
+        );
+
+        // the compact board agrees
+        let snake_ids = build_snake_id_map(&g);
+        let compact: compact_representation::standard::CellBoard4Snakes7x7 =
+            g.as_cell_board(&snake_ids).unwrap();
+        let compact_denials = compact
+            .food_denial_opportunities(&SnakeId(0), AStarOptions::default())
+            .into_iter()
+            .map(|d| {
+                (
+                    compact.position_from_native(d.food),
+                    d.opponent,
+                    d.my_distance,
+                )
+            })
+            .collect_vec();
+        assert_eq!(
+            compact_denials,
+            vec![
+                (Position::new(0, 6), snake_ids["starving"], 6),
+                (Position::new(4, 3), snake_ids["starving"], 1),
+                (Position::new(4, 3), snake_ids["healthy"], 1),
+            ]
+        );
+
+        // a snake that has been eliminated can't deny anything, or be denied
+        let mut eliminated = g.clone();
+        eliminated.board.snakes.retain(|s| s.id != "starving");
+        assert_eq!(
+            eliminated
+                .food_denial_opportunities(&you, AStarOptions::default())
+                .len(),
+            1
+        );
+        assert!(eliminated
+            .food_denial_opportunities(&"starving".to_string(), AStarOptions::default())
+            .is_empty());
+    }
+
+    #[test]
+    fn test_choke_points_single_entrance_pocket() {
+        let g = crate::game_fixture(include_str!("../../fixtures/choke_pocket.json"));
+        let choke_points = g.choke_points();
+
+        // the 3x4 pocket in the corner and the corridor leading to it can only be reached
+        // through (0, 5)
+        assert_eq!(choke_points.len(), 13);
+        let region = RegionInfo {
+            choke_point: Position::new(0, 5),
+            region_size: 13,
+        };
+        for x in 0..3 {
+            for y in 0..4 {
+                assert_eq!(choke_points[&Position::new(x, y)], region);
+            }
+        }
+        assert_eq!(choke_points[&Position::new(0, 4)], region);
+    }
+
+    #[test]
+    fn test_choke_points_two_entrances() {
+        let mut g = crate::game_fixture(include_str!("../../fixtures/choke_pocket.json"));
+        // opening up (3, 0) gives the pocket a second way in
+        g.you.body.pop_back();
+        g.board.snakes[0].body.pop_back();
+
+        assert_eq!(g.choke_points(), HashMap::new());
+    }
+
+    #[test]
+    fn test_will_be_unoccupied() {
+        let mut g = crate::game_fixture(include_str!("../../fixtures/choke_pocket.json"));
+        // third piece from the end of our tail
+        let piece = Position::new(3, 2);
+        assert_eq!(g.will_be_unoccupied(&piece, 2), SquareSafety::Occupied);
+        assert_eq!(g.will_be_unoccupied(&piece, 3), SquareSafety::Safe);
+        assert_eq!(
+            g.will_be_unoccupied(&Position::new(5, 5), 0),
+            SquareSafety::Safe
+        );
+
+        // eating the food next to our head would hold the tail back a turn
+        g.board.food.insert(Position::new(1, 5));
+        assert_eq!(g.will_be_unoccupied(&piece, 3), SquareSafety::DependsOnFood);
+        // but one food isn't enough to hold it back three turns
+        assert_eq!(g.will_be_unoccupied(&piece, 5), SquareSafety::Safe);
+    }
+
+    #[test]
+    fn test_editing_food_and_hazards() {
+        let mut g = crate::game_fixture(include_str!("../../fixtures/start_of_game.json"));
+        let empty = Position::new(3, 6);
+        assert!(!g.is_food(&empty));
+        g.remove_food(Position::new(5, 5));
+        assert!(!g.is_food(&Position::new(5, 5)));
+
+        g.place_food(empty).unwrap();
+        assert!(g.is_food(&empty));
+        assert!(g.get_all_food_as_positions().contains(&empty));
+        g.remove_food(empty);
+        assert!(!g.is_food(&empty));
+
+        let head = g.you.head;
+        assert!(matches!(
+            g.place_food(head),
+            Err(crate::Error::InvalidFood(_))
+        ));
+        assert!(g.place_food(Position::new(-1, 0)).is_err());
+        assert!(!g.is_food(&head));
+
+        g.set_hazard(empty);
+        g.set_hazard(empty);
+        assert_eq!(g.get_hazard_count(&empty), 2);
+        g.clear_hazard(empty);
+        assert!(!g.is_hazard(&empty));
+    }
+
+    #[test]
+    fn test_can_safely_chase_tail() {
+        let positions = |squares: &[(i32, i32)]| {
+            squares
+                .iter()
+                .map(|(x, y)| Position::new(*x, *y))
+                .collect::<Vec<_>>()
+        };
+        // chasing round a two by two square in the corner, with a shorter snake far away
+        let chase = |mine: &[(i32, i32)], theirs: &[(i32, i32)]| {
+            let mut builder = crate::compact_representation::CellBoardBuilder::new(7, 7);
+            builder.add_snake(positions(mine), 90).unwrap();
+            builder.add_snake(positions(theirs), 90).unwrap();
+            builder.game().clone()
+        };
+        let corner = [(0, 1), (1, 1), (1, 0), (0, 0)];
+        let g = chase(&corner, &[(6, 6), (6, 5), (6, 4)]);
+        let analysis = g.can_safely_chase_tail(&g.you.id);
+        assert_eq!(analysis.path, Some(vec![]));
+        assert_eq!(analysis.cycle_length, Some(4));
+        assert_eq!(analysis.broken_at, None);
+        assert!(analysis.is_safe());
+
+        // the same snake, but as long as us, can meet us head to head
+        let g = chase(&corner, &[(6, 6), (6, 5), (6, 4), (6, 3)]);
+        let analysis = g.can_safely_chase_tail(&g.you.id);
+        assert!(analysis.broken_at.is_some());
+        assert!(!analysis.is_safe());
+
+        // the way to the tail is through (0, 0), which has food on it
+        let mut g = chase(&[(1, 0), (1, 1), (1, 2), (0, 2), (0, 1)], &[(6, 6), (6, 5)]);
+        g.board.food.insert(Position::new(0, 0));
+        let analysis = g.can_safely_chase_tail(&g.you.id);
+        assert_eq!(analysis.path, Some(vec![Position::new(0, 0)]));
+        assert_eq!(analysis.cycle_length, Some(6));
+        assert!(analysis.food_on_path);
+        assert!(!analysis.is_safe());
+
+        // a snake as long as us right below our tail gets there on the turn we do
+        let g = chase(
+            &[(2, 3), (3, 3), (3, 2), (2, 2)],
+            &[(2, 1), (1, 1), (0, 1), (0, 0)],
+        );
+        let analysis = g.can_safely_chase_tail(&g.you.id);
+        assert_eq!(analysis.cycle_length, Some(4));
+        assert_eq!(analysis.broken_at, Some(1));
+        assert!(!analysis.is_safe());
+
+        // having just eaten the tail stays where it is next turn
+        let g = chase(&[(0, 1), (1, 1), (1, 0), (0, 0), (0, 0)], &[(6, 6), (6, 5)]);
+        assert!(!g.can_safely_chase_tail(&g.you.id).tail_vacates);
+    }
+
+    #[test]
+    fn test_infer_last_moves() {
+        // one snake came up over the bottom edge last turn, and one left over the right edge
+        let g = crate::game_fixture(include_str!("../../fixtures/wrapped_fixture.json"));
+        assert_eq!(
+            g.last_move(&"gs_9vgc6W4xBDKBkdhk46fPJhkQ".to_string()),
+            Some(Move::Up)
+        );
+        let panic = crate::game_fixture(include_str!("../../fixtures/wrapped_panic.json"));
+        assert_eq!(
+            panic.last_move(&"gs_WQKtDg7CjJhmYRPHvtGCdFQ7".to_string()),
+            Some(Move::Left)
+        );
+
+        // the compact boards agree
+        let snake_ids = build_snake_id_map(&g);
+        let compact: compact_representation::WrappedCellBoard4Snakes11x11 =
+            g.as_wrapped_cell_board(&snake_ids).unwrap();
+        let moves = g.infer_last_moves();
+        assert_eq!(moves.len(), g.board.snakes.len());
+        assert!(moves.iter().all(|(_, mv)| mv.is_some()));
+        let by_id = moves
+            .into_iter()
+            .map(|(id, mv)| (snake_ids[&id], mv))
+            .collect_vec();
+        assert_eq!(compact.infer_last_moves(), by_id);
+
+        // nobody has moved on the first turn, and a turn later the tails are still stacked
+        let spawn = crate::game_fixture(include_str!("../../fixtures/opening_turn_0.json"));
+        assert!(spawn.infer_last_moves().iter().all(|(_, mv)| mv.is_none()));
+        let start = crate::game_fixture(include_str!("../../fixtures/start_of_game.json"));
+        assert_eq!(
+            start
+                .infer_last_moves()
+                .into_iter()
+                .map(|(_, mv)| mv)
+                .collect_vec(),
+            vec![
+                Some(Move::Left),
+                Some(Move::Down),
+                Some(Move::Left),
+                Some(Move::Down)
+            ]
+        );
+
+        // eating stacks the tail, not the neck
+        let p = Position::new;
+        let mut ate = Game::empty(7, 7);
+        ate.add_you("you", vec![p(2, 5), p(2, 4), p(2, 3), p(2, 3)], 100)
+            .unwrap();
+        ate.board.add_snake("head", vec![p(5, 5)], 100).unwrap();
+        assert_eq!(
+            ate.infer_last_moves(),
+            vec![
+                ("you".to_string(), Some(Move::Up)),
+                ("head".to_string(), None)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_degenerate_snakes() {
+        use rand::{rngs::SmallRng, SeedableRng};
+
+        // a snake of 1 piece, one of 2, one whose head isn't at the start of its body, and one
+        // with no body at all. None of them are valid, but nothing should panic on them
+        let g = crate::game_fixture(include_str!("../../fixtures/degenerate_snakes.json"));
+        assert!(g.validate().is_err());
+        let snake_ids = build_snake_id_map(&g);
+        assert!(g
+            .as_cell_board::<u8, compact_representation::dimensions::Square, { 11 * 11 }, 4>(
+                &snake_ids
+            )
+            .is_err());
+
+        let single = "single".to_string();
+        let pair = "pair".to_string();
+        // without a neck every direction is open
+        assert_eq!(g.possible_head_squares(&single).len(), 4);
+        assert_eq!(
+            g.possible_head_squares(&pair).moves().iter().collect_vec(),
+            vec![Move::Down, Move::Left, Move::Right]
+        );
+        assert_eq!(g.last_move(&single), None);
+        assert_eq!(g.last_move(&pair), Some(Move::Down));
+        assert_eq!(g.infer_last_moves().len(), 4);
+        assert_eq!(
+            g.project_snake(&single, 3, &TrajectoryAssumption::Straight),
+            None
+        );
+
+        let mut rng = SmallRng::seed_from_u64(1);
+        for level in [ReasonableMoveLevel::Basic, ReasonableMoveLevel::SpaceAware] {
+            let moves = g
+                .random_reasonable_move_at_level_for_each_snake(&mut rng, level)
+                .collect_vec();
+            assert_eq!(moves.len(), 4);
+        }
+        let weighted = g
+            .random_reasonable_move_weighted(&mut rng, |_, _, _| 1.0)
+            .collect_vec();
+        assert_eq!(weighted.len(), 4);
+
+        g.all_threat_squares();
+        g.voronoi(VoronoiOptions::default());
+        g.choke_points();
+        g.food_race_analysis(AStarOptions::default());
+        g.food_denial_opportunities(&single, AStarOptions::default());
+        let corner = Position::new(10, 10);
+        for id in g.get_snake_ids() {
+            let head = g.get_head_as_native_position(&id);
+            g.flood_fill(&head, FloodFillOptions::default());
+            g.a_star(&head, &corner, AStarOptions::default());
+            g.cost_map(&head, AStarOptions::default());
+            g.distance_map(&head, AStarOptions::default());
+            g.can_safely_chase_tail(&id);
+            g.project_snake(&id, 5, &TrajectoryAssumption::Straight);
+            g.project_snake(&id, 5, &TrajectoryAssumption::Repeating(vec![Move::Left]));
+        }
+        for pos in g.all_positions() {
+            g.will_be_unoccupied(&pos, 2);
+        }
+        g.to_string();
+        g.is_over();
+        g.get_winner();
+
+        let moves = g
+            .get_snake_ids()
+            .into_iter()
+            .map(|id| (id, Move::Up))
+            .collect_vec();
+        let (next, trace) = g.simulate_turn_traced(moves.clone());
+        assert_eq!(next, g.simulate_turn(moves));
+        assert_eq!(trace.snakes.len(), 4);
+        // a single piece moves without leaving a neck behind
+        assert_eq!(next.snake_by_id(&single).unwrap().body.len(), 1);
+        assert_eq!(next.last_move(&single), None);
+    }
+
+    #[test]
+    fn test_normalize() {
+        let mut canonical = crate::game_fixture(include_str!("../../fixtures/late_stage.json"));
+        // the opponent has just eaten
+        let opponent = &mut canonical.board.snakes[0];
+        let tail = *opponent.body.back().unwrap();
+        opponent.body.push_back(tail);
+        assert_eq!(canonical.normalized(), canonical);
+
+        // the same game from something that shows growth that is owed without stacking it on the
+        // tail, stacks your tail more than your length, and lets heads get out of step
+        let mut other = canonical.clone();
+        let opponent = &mut other.board.snakes[0];
+        opponent.body.pop_back();
+        opponent.actual_length = Some(opponent.body.len() as i32 + 1);
+        opponent.head = opponent.body[1];
+        let you = &mut other.board.snakes[1];
+        you.actual_length = Some(you.body.len() as i32);
+        let tail = *you.body.back().unwrap();
+        you.body.extend([tail, tail].iter());
+        other.you.head = Position::new(0, 0);
+
+        assert_ne!(other, canonical);
+        assert!(!diff_games(&other, &canonical).is_empty());
+        assert!(other.eq_normalized(&canonical));
+        assert!(diff_games_normalized(&other, &canonical).is_empty());
+
+        // and they play out the same
+        let mut other = other.normalized();
+        for _ in 0..3 {
+            let moves = canonical
+                .board
+                .snakes
+                .iter()
+                .map(|snake| (snake.id.clone(), canonical.reasonable_moves(snake)[0]))
+                .collect_vec();
+            other = other.simulate_turn(moves.clone());
+            canonical = canonical.simulate_turn(moves);
+            assert_eq!(other, canonical);
+        }
+
+        let degenerate = crate::game_fixture(include_str!("../../fixtures/degenerate_snakes.json"));
+        for snake in &degenerate.normalized().board.snakes {
+            assert_eq!(snake.body.front(), Some(&snake.head));
+        }
+    }
+
+    #[test]
+    fn test_project_snake() {
+        let positions = |squares: &[(i32, i32)]| {
+            squares
+                .iter()
+                .map(|(x, y)| Position::new(*x, *y))
+                .collect::<Vec<_>>()
+        };
+        let mut builder = crate::compact_representation::CellBoardBuilder::new(7, 7);
+        builder
+            .add_snake(positions(&[(3, 3), (2, 3), (1, 3)]), 90)
+            .unwrap();
+        builder
+            .add_snake(
+                positions(&[(5, 0), (5, 1), (5, 2), (6, 2), (6, 3), (6, 4)]),
+                90,
+            )
+            .unwrap();
+        let g = builder.game().clone();
+
+        // going right runs in to the wall after three turns, the other snake's tail has left
+        // (6, 3) by the time we get there
+        let projected = g
+            .project_snake(&g.you.id, 5, &TrajectoryAssumption::Straight)
+            .unwrap();
+        assert_eq!(
+            projected.heads().copied().collect::<Vec<_>>(),
+            positions(&[(4, 3), (5, 3), (6, 3)])
+        );
+        assert_eq!(projected.bodies[2], positions(&[(6, 3), (5, 3), (4, 3)]));
+        assert_eq!(projected.stopped, Some(ProjectionStop::LeftBoard));
+
+        // going round in a square never runs in to the body
+        let circle =
+            TrajectoryAssumption::Repeating(vec![Move::Up, Move::Left, Move::Down, Move::Right]);
+        let projected = g.project_snake(&g.you.id, 8, &circle).unwrap();
+        assert_eq!(projected.bodies.len(), 8);
+        assert_eq!(projected.stopped, None);
+        assert_eq!(projected.bodies[7], positions(&[(3, 3), (2, 3), (2, 4)]));
+
+        // turning back on to the neck
+        let back = TrajectoryAssumption::Repeating(vec![Move::Left]);
+        let projected = g.project_snake(&g.you.id, 3, &back).unwrap();
+        assert!(projected.bodies.is_empty());
+        assert_eq!(projected.stopped, Some(ProjectionStop::Collided));
+
+        // but its body is still at (5, 2)
+        let stairs = TrajectoryAssumption::Repeating(vec![Move::Right, Move::Down]);
+        let projected = g.project_snake(&g.you.id, 4, &stairs).unwrap();
+        assert_eq!(projected.bodies.len(), 2);
+        assert_eq!(projected.stopped, Some(ProjectionStop::Collided));
+
+        // the other snake is going down, off the board
+        let other = &g.board.snakes[1].id;
+        let projected = g
+            .project_snake(other, 2, &TrajectoryAssumption::Straight)
+            .unwrap();
+        assert!(projected.bodies.is_empty());
+        assert_eq!(projected.stopped, Some(ProjectionStop::LeftBoard));
+
+        let nothing = TrajectoryAssumption::Repeating(vec![]);
+        assert_eq!(g.project_snake(&g.you.id, 3, &nothing), None);
+    }
+
+    #[test]
+    fn test_squad_teammate_eliminated() {
+        let mut g = crate::game_fixture(include_str!("../../fixtures/squad_game.json"));
+        assert!(g.is_squad());
+        assert!(!g.is_over());
+
+        // one of each squad is left, so the game goes on even though only two snakes remain
+        g.board
+            .snakes
+            .retain(|s| s.id != "ally" && s.id != "blue-2");
+        assert!(!g.is_over());
+        assert_eq!(g.get_winning_squad(), None);
+        assert_eq!(g.get_winner(), None);
+    }
+
+    #[test]
+    fn test_squad_eliminated() {
+        let mut g = crate::game_fixture(include_str!("../../fixtures/squad_game.json"));
+        g.board.snakes.retain(|s| s.squad.as_deref() != Some("red"));
+        g.you.health = 0;
+
+        // both of our snakes are gone, the two blue snakes win together
+        assert!(g.is_over());
+        assert_eq!(g.get_winning_squad(), Some("blue".to_string()));
+        assert_eq!(g.get_winner(), Some("blue-1".to_string()));
+    }
+
+    #[test]
+    fn test_squad_shared_elimination() {
+        let mut g = crate::game_fixture(include_str!("../../fixtures/squad_game.json"));
+        g.board.snakes[3].health = 0;
+        assert!(!g.is_over());
+
+        let settings = g.game.ruleset.settings.as_mut().unwrap();
+        settings.squad.as_mut().unwrap().shared_elimination = true;
+        assert!(g.is_over());
+        assert_eq!(g.get_winning_squad(), Some("red".to_string()));
+        assert_eq!(g.get_winner(), Some("you".to_string()));
+    }
+
+    #[test]
+    fn test_game_result() {
+        let mut g = crate::game_fixture(include_str!("../../fixtures/late_stage.json"));
+        assert_eq!(g.game_result(), GameResult::Ongoing);
+        let other = g
+            .board
+            .snakes
+            .iter()
+            .find(|s| s.id != g.you.id)
+            .unwrap()
+            .clone();
+        g.board.snakes.retain(|s| s.id == other.id);
+        g.you.health = 0;
+        assert_eq!(g.game_result(), GameResult::Winner(other.id));
+        g.board.snakes.clear();
+        assert_eq!(g.game_result(), GameResult::Draw);
+
+        let mut squads = crate::game_fixture(include_str!("../../fixtures/squad_game.json"));
+        assert_eq!(squads.game_result(), GameResult::Ongoing);
+        squads
+            .board
+            .snakes
+            .retain(|s| s.squad.as_deref() != Some("red"));
+        squads.you.health = 0;
+        assert_eq!(
+            squads.game_result(),
+            GameResult::Winner("blue-1".to_string())
+        );
+        squads.board.snakes.clear();
+        assert_eq!(squads.game_result(), GameResult::Draw);
+    }
+
     #[test]
     fn test_map_json() {
         let game_fixture = include_str!("../../fixtures/arcade_maze_map.json");
@@ -751,4 +2976,57 @@ mod tests {
 
         assert!(g.is_arcade_maze_map());
     }
+
+    #[test]
+    fn test_arcade_maze_walls() {
+        let g = crate::game_fixture(include_str!("../../fixtures/arcade_maze_map.json"));
+        assert_eq!(g.board.walls.len(), 187);
+        assert!(g.board.hazards.is_empty());
+        assert!(g.is_wall(&Position::new(0, 1)));
+        assert!(!g.is_wall(&Position::new(1, 1)));
+        let json = serde_json::to_string(&g).unwrap();
+        assert_eq!(serde_json::from_str::<Game>(&json).unwrap(), g);
+        let mut applied = g.clone();
+        applied.apply_map_walls();
+        assert_eq!(applied, g);
+
+        let neighbors = g.neighbors(&Position::new(1, 1)).sorted().collect_vec();
+        assert_eq!(
+            neighbors,
+            vec![
+                Position::new(1, 0),
+                Position::new(1, 2),
+                Position::new(2, 1)
+            ]
+        );
+
+        // the wall at (1, 4) is in the way, so the path has to go around it
+        let (from, to) = (Position::new(1, 1), Position::new(1, 7));
+        let path = g.a_star(&from, &to, AStarOptions::default()).unwrap();
+        assert!(path.len() > 6);
+        let mut pos = from;
+        for mv in path {
+            pos = g.new_head_position(pos, mv);
+            assert!(!g.is_wall(&pos));
+        }
+        assert_eq!(pos, to);
+        assert_eq!(
+            g.a_star(&from, &Position::new(0, 1), AStarOptions::default()),
+            None
+        );
+
+        let reached = g.flood_fill(&from, FloodFillOptions::default());
+        assert!(reached.iter().all(|pos| !g.is_wall(pos)));
+        assert!(reached.len() < 19 * 21 - 187);
+
+        // moving up from (4, 19) is in to a wall
+        let you = g.you.id.clone();
+        let (next, trace) = g.simulate_turn_traced(vec![(you.clone(), Move::Up)]);
+        assert!(next.snake_by_id(&you).is_none());
+        assert_eq!(
+            trace.snake(&you).unwrap().eliminated,
+            Some(EliminationCause::WallCollision)
+        );
+        assert!(g.board.clone().add_wall(g.you.head).is_err());
+    }
 }
@@ -0,0 +1,182 @@
+//! the body we send back to the engine for a move request
+
+use serde::{Serialize, Serializer};
+
+use crate::types::Move;
+use crate::Error;
+
+/// The longest shout the engine accepts, in bytes. The API docs say 256 characters, but the engine
+/// measures the shout with Go's `len`, which counts UTF-8 bytes, so a shout of 256 emoji would be
+/// over the limit. Counting bytes keeps us under both readings
+pub const MAX_SHOUT_BYTES: usize = 256;
+
+/// what a truncated shout ends with
+const TRUNCATION_INDICATOR: &str = "...";
+
+/// The response to a move request
+/// ```
+/// # use battlesnake_game_types::types::Move;
+/// # use battlesnake_game_types::wire_representation::MoveResponse;
+/// let response = MoveResponse::new(Move::Up).with_shout_lossy("depth 7\nscore 12");
+/// assert_eq!(
+///     serde_json::to_string(&response).unwrap(),
+///     r#"{"move":"up","shout":"depth 7 score 12"}"#
+/// );
+/// ```
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct MoveResponse {
+    #[serde(rename = "move", serialize_with = "serialize_move")]
+    mv: Move,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    shout: Option<String>,
+}
+
+fn serialize_move<S: Serializer>(mv: &Move, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.collect_str(mv)
+}
+
+impl MoveResponse {
+    /// a response making `mv` without a shout
+    pub fn new(mv: Move) -> Self {
+        Self { mv, shout: None }
+    }
+
+    /// the move being made
+    pub fn mv(&self) -> Move {
+        self.mv
+    }
+
+    /// the shout, None if there isn't one
+    pub fn shout(&self) -> Option<&str> {
+        self.shout.as_deref()
+    }
+
+    /// Shout `shout`, fixing it up so the engine accepts it. Newlines and tabs become spaces,
+    /// other control characters are removed, and anything over [MAX_SHOUT_BYTES] is cut at a char
+    /// boundary and ends with "...". An empty shout clears the shout
+    pub fn with_shout_lossy(mut self, shout: impl Into<String>) -> Self {
+        let mut shout = sanitize(shout.into());
+        if shout.len() > MAX_SHOUT_BYTES {
+            let mut end = MAX_SHOUT_BYTES - TRUNCATION_INDICATOR.len();
+            while !shout.is_char_boundary(end) {
+                end -= 1;
+            }
+            shout.truncate(end);
+            shout.push_str(TRUNCATION_INDICATOR);
+        }
+        self.shout = Some(shout).filter(|s| !s.is_empty());
+        self
+    }
+
+    /// Shout `shout` exactly, erroring if it has control characters or is over
+    /// [MAX_SHOUT_BYTES]. An empty shout clears the shout
+    pub fn try_with_shout(mut self, shout: impl Into<String>) -> Result<Self, Error> {
+        let shout = shout.into();
+        if shout.chars().any(char::is_control) {
+            return Err(Error::InvalidShout(
+                "the shout has control characters".to_string(),
+            ));
+        }
+        if shout.len() > MAX_SHOUT_BYTES {
+            return Err(Error::InvalidShout(format!(
+                "the shout is {} bytes, the most allowed is {}",
+                shout.len(),
+                MAX_SHOUT_BYTES
+            )));
+        }
+        self.shout = Some(shout).filter(|s| !s.is_empty());
+        Ok(self)
+    }
+}
+
+impl From<Move> for MoveResponse {
+    fn from(mv: Move) -> Self {
+        Self::new(mv)
+    }
+}
+
+fn sanitize(shout: String) -> String {
+    if !shout.chars().any(char::is_control) {
+        return shout;
+    }
+    shout
+        .chars()
+        .filter_map(|c| match c {
+            '\n' | '\r' | '\t' => Some(' '),
+            c if c.is_control() => None,
+            c => Some(c),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_long_ascii_shout() {
+        let shout = "a".repeat(300);
+        let response = MoveResponse::new(Move::Left).with_shout_lossy(shout.clone());
+        let lossy = response.shout().unwrap();
+        assert_eq!(lossy.len(), MAX_SHOUT_BYTES);
+        assert!(lossy.ends_with(TRUNCATION_INDICATOR));
+        assert!(shout.starts_with(&lossy[..MAX_SHOUT_BYTES - TRUNCATION_INDICATOR.len()]));
+
+        let err = MoveResponse::new(Move::Left).try_with_shout(shout);
+        assert!(matches!(err, Err(Error::InvalidShout(_))));
+
+        let exact = "a".repeat(MAX_SHOUT_BYTES);
+        let response = MoveResponse::new(Move::Left)
+            .try_with_shout(exact.clone())
+            .unwrap();
+        assert_eq!(response.shout(), Some(exact.as_str()));
+        let response = MoveResponse::new(Move::Left).with_shout_lossy(exact.clone());
+        assert_eq!(response.shout(), Some(exact.as_str()));
+    }
+
+    #[test]
+    fn test_emoji_shout() {
+        // 100 chars, but 400 bytes
+        let shout = "🐍".repeat(100);
+        assert!(MoveResponse::new(Move::Up)
+            .try_with_shout(shout.clone())
+            .is_err());
+
+        let response = MoveResponse::new(Move::Up).with_shout_lossy(shout);
+        let lossy = response.shout().unwrap();
+        assert!(lossy.len() <= MAX_SHOUT_BYTES);
+        assert_eq!(lossy, format!("{}...", "🐍".repeat(63)));
+
+        let fits = "🐍".repeat(64);
+        let response = MoveResponse::new(Move::Up)
+            .try_with_shout(fits.clone())
+            .unwrap();
+        assert_eq!(response.shout(), Some(fits.as_str()));
+    }
+
+    #[test]
+    fn test_control_characters() {
+        let response = MoveResponse::new(Move::Down).with_shout_lossy("depth 4\nscore\t3\u{7}!");
+        assert_eq!(response.shout(), Some("depth 4 score 3!"));
+
+        let err = MoveResponse::new(Move::Down).try_with_shout("line\nbreak");
+        assert!(matches!(err, Err(Error::InvalidShout(_))));
+
+        let response = MoveResponse::new(Move::Down).with_shout_lossy("\u{0}");
+        assert_eq!(response.shout(), None);
+    }
+
+    #[test]
+    fn test_serialize() {
+        let response = MoveResponse::from(Move::Right);
+        assert_eq!(
+            serde_json::to_string(&response).unwrap(),
+            r#"{"move":"right"}"#
+        );
+        let response = response.try_with_shout("hi").unwrap();
+        assert_eq!(
+            serde_json::to_string(&response).unwrap(),
+            r#"{"move":"right","shout":"hi"}"#
+        );
+    }
+}
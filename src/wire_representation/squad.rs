@@ -0,0 +1,601 @@
+//! simulating a turn of a wire game, with the squad ruleset's settings
+
+use std::collections::{HashMap, HashSet};
+
+use crate::types::{LastMoveDeterminableGame, Move, SnakeMove, TurnPhase};
+
+use super::{BattleSnake, EliminationCause, Game, SnakeTrace, SquadSettings, TurnTrace};
+
+impl Game {
+    /// The squad settings turns are played by, None if this isn't a squad game. A squad game
+    /// that doesn't send settings plays with all of them off
+    pub fn squad_settings(&self) -> Option<SquadSettings> {
+        if !self.is_squad() {
+            return None;
+        }
+        let settings = self.game.ruleset.settings.as_ref().and_then(|s| s.squad);
+        Some(settings.unwrap_or(SquadSettings {
+            allow_body_collisions: false,
+            shared_elimination: false,
+            shared_health: false,
+            shared_length: false,
+        }))
+    }
+
+    /// The game a turn later, after every snake has made its move in `moves`. A snake without
+    /// a move keeps going the way it last went, or up if it can't tell. Turns are played by the
+    /// ruleset's [crate::types::SimulationSettings], and in squad games by its
+    /// [Game::squad_settings] too:
+    ///
+    /// * `allow_body_collisions` lets snakes move through their squad mates' bodies
+    /// * `shared_health` gives every snake in a squad the health of the healthiest of them
+    /// * `shared_length` grows every snake in a squad to the length of the longest of them
+    /// * `shared_elimination` eliminates a squad when any of its snakes are
+    ///
+    /// Unlike compact boards, which don't know about squads, the wire game can have more than one
+    /// snake on a square. No food is spawned and hazards don't change. Eliminated snakes are
+    /// removed from the board, and if you are one of them `you` is left how you were eliminated
+    pub fn simulate_turn<M: Into<SnakeMove<String>>>(
+        &self,
+        moves: impl IntoIterator<Item = M>,
+    ) -> Game {
+        self.play_turn(moves, None)
+    }
+
+    /// the same as [Game::simulate_turn], along with a [TurnTrace] of how each snake's move,
+    /// health and elimination were worked out, for finding out why a simulation disagrees with
+    /// the engine
+    pub fn simulate_turn_traced<M: Into<SnakeMove<String>>>(
+        &self,
+        moves: impl IntoIterator<Item = M>,
+    ) -> (Game, TurnTrace) {
+        let mut trace = TurnTrace {
+            turn: self.turn,
+            snakes: vec![],
+        };
+        let next = self.play_turn(moves, Some(&mut trace.snakes));
+        (next, trace)
+    }
+
+    fn play_turn<M: Into<SnakeMove<String>>>(
+        &self,
+        moves: impl IntoIterator<Item = M>,
+        mut trace: Option<&mut Vec<SnakeTrace>>,
+    ) -> Game {
+        let settings = self.game.ruleset.simulation_settings();
+        let squad = self.squad_settings();
+        let moves: HashMap<String, Move> = moves
+            .into_iter()
+            .map(|m| {
+                let m = m.into();
+                (m.snake_id, m.mv)
+            })
+            .collect();
+
+        let mut snakes: Vec<BattleSnake> = self.living_snakes().cloned().collect();
+        let mut eliminated: Vec<Option<EliminationCause>> = vec![None; snakes.len()];
+        let mut eaten = HashSet::new();
+        for (snake, eliminated) in snakes.iter_mut().zip(eliminated.iter_mut()) {
+            let mv = moves
+                .get(&snake.id)
+                .copied()
+                .or_else(|| self.last_move(&snake.id))
+                .unwrap_or(Move::Up);
+            let head = self.new_head_position(snake.head, mv);
+            let mut snake_trace = trace.as_ref().map(|_| SnakeTrace {
+                id: snake.id.clone(),
+                mv,
+                head_before: snake.head,
+                head_after: head,
+                health_before: snake.health,
+                phases: vec![],
+                ate_food: false,
+                hazard_damage: 0,
+                health_after: snake.health,
+                eliminated: None,
+            });
+            snake.body.pop_back();
+            snake.body.push_front(head);
+            snake.head = head;
+            if self.off_board(head) || self.board.walls.contains(&head) {
+                *eliminated = Some(EliminationCause::WallCollision);
+                if let (Some(trace), Some(snake_trace)) = (trace.as_mut(), snake_trace) {
+                    trace.push(snake_trace);
+                }
+                continue;
+            }
+
+            let ate_food = self.board.food.contains(&head);
+            let hazard_damage = settings.hazard_damage as i32
+                * self.board.hazards.iter().filter(|h| **h == head).count() as i32;
+            let mut health = snake.health;
+            let mut starved = false;
+            for phase in settings.rules_version.phases() {
+                match phase {
+                    TurnPhase::ReduceHealth => {
+                        if settings.starvation_enabled {
+                            health -= 1;
+                        }
+                    }
+                    TurnPhase::DamageHazards => {
+                        if !ate_food {
+                            health -= hazard_damage;
+                        }
+                    }
+                    TurnPhase::DamageHazardsIncludingFood => {
+                        health -= hazard_damage;
+                        starved |= health <= 0;
+                    }
+                    TurnPhase::FeedSnakes => {
+                        if ate_food && settings.food_restores_health {
+                            health = settings.max_health as i32;
+                        }
+                    }
+                    TurnPhase::EliminateStarved => starved |= health <= 0,
+                }
+                if let Some(snake_trace) = snake_trace.as_mut() {
+                    snake_trace.phases.push((*phase, health));
+                }
+            }
+            snake.health = health.clamp(0, settings.max_health as i32);
+            if starved || snake.health == 0 {
+                *eliminated = Some(EliminationCause::OutOfHealth);
+            } else if settings.always_grow {
+                // constrictor puts every snake that survived back to full health
+                snake.health = settings.max_health as i32;
+            }
+            if ate_food {
+                eaten.insert(head);
+            }
+            if ate_food || settings.always_grow {
+                snake.body.push_back(*snake.body.back().unwrap());
+            }
+            if let (Some(trace), Some(mut snake_trace)) = (trace.as_mut(), snake_trace) {
+                snake_trace.ate_food = ate_food;
+                snake_trace.hazard_damage = hazard_damage;
+                trace.push(snake_trace);
+            }
+        }
+
+        // collisions are all worked out before any snake is removed, so the order snakes are in
+        // doesn't matter
+        let squad_mates =
+            |a: &BattleSnake, b: &BattleSnake| a.squad.is_some() && a.squad == b.squad;
+        let collided = (0..snakes.len())
+            .map(|i| {
+                let snake = &snakes[i];
+                if eliminated[i].is_some() {
+                    return None;
+                }
+                let others = || {
+                    snakes
+                        .iter()
+                        .enumerate()
+                        .filter(|(j, _)| eliminated[*j].is_none())
+                };
+                let hit_body = others().find(|(j, other)| {
+                    let passes_through = i != *j
+                        && squad.is_some_and(|s| s.allow_body_collisions)
+                        && squad_mates(snake, other);
+                    !passes_through && other.body.iter().skip(1).any(|p| *p == snake.head)
+                });
+                if let Some((j, other)) = hit_body {
+                    return Some(if i == j {
+                        EliminationCause::SelfCollision
+                    } else {
+                        EliminationCause::BodyCollision(other.id.clone())
+                    });
+                }
+                others()
+                    .find(|(j, other)| {
+                        i != *j && other.head == snake.head && snake.body.len() <= other.body.len()
+                    })
+                    .map(|(_, other)| EliminationCause::HeadCollision(other.id.clone()))
+            })
+            .collect::<Vec<_>>();
+        for (eliminated, collided) in eliminated.iter_mut().zip(collided) {
+            if eliminated.is_none() {
+                *eliminated = collided;
+            }
+        }
+
+        if let Some(squad) = squad {
+            let shared = snakes
+                .iter()
+                .map(|snake| {
+                    let mates = || {
+                        snakes
+                            .iter()
+                            .zip(&eliminated)
+                            .filter(move |(other, _)| squad_mates(snake, other))
+                    };
+                    let living = || mates().filter(|(_, e)| e.is_none()).map(|(s, _)| s);
+                    (
+                        living().map(|s| s.health).max(),
+                        living().map(|s| s.body.len()).max(),
+                        mates().any(|(_, e)| e.is_some()),
+                    )
+                })
+                .collect::<Vec<_>>();
+            let shared = snakes.iter_mut().zip(eliminated.iter_mut()).zip(shared);
+            for ((snake, eliminated), (health, length, squad_eliminated)) in shared {
+                if eliminated.is_some() {
+                    continue;
+                }
+                if squad.shared_health {
+                    snake.health = health.unwrap_or(snake.health);
+                }
+                if squad.shared_length {
+                    let tail = *snake.body.back().unwrap();
+                    while snake.body.len() < length.unwrap_or(0) {
+                        snake.body.push_back(tail);
+                    }
+                }
+                if squad.shared_elimination && squad_eliminated {
+                    *eliminated = Some(EliminationCause::SquadEliminated);
+                }
+            }
+        }
+
+        if let Some(trace) = trace {
+            for ((snake_trace, snake), eliminated) in trace.iter_mut().zip(&snakes).zip(&eliminated)
+            {
+                snake_trace.health_after = snake.health;
+                snake_trace.eliminated = eliminated.clone();
+            }
+        }
+
+        let mut next = self.clone();
+        next.turn = next.turn.saturating_add(1);
+        next.board.food.retain(|f| !eaten.contains(f));
+        next.board.snakes = snakes
+            .into_iter()
+            .zip(eliminated)
+            .filter_map(|(snake, eliminated)| {
+                if snake.id == self.you.id {
+                    next.you = snake.clone();
+                }
+                if eliminated.is_some() {
+                    None
+                } else {
+                    Some(snake)
+                }
+            })
+            .collect();
+        next
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{TurnDeterminableGame, VictorDeterminableGame};
+    use crate::wire_representation::{Position, Settings};
+
+    fn squad_game(squad: SquadSettings, squads: [&str; 3]) -> Game {
+        let p = Position::new;
+        let mut g = Game::empty(7, 7);
+        g.add_you("a", vec![p(1, 3), p(1, 2), p(1, 1)], 50).unwrap();
+        g.board
+            .add_snake("b", vec![p(2, 4), p(2, 3), p(2, 2)], 80)
+            .unwrap();
+        g.board
+            .add_snake("c", vec![p(5, 1), p(5, 2), p(5, 3)], 90)
+            .unwrap();
+        g.board.add_food(p(5, 0)).unwrap();
+        for (snake, squad) in g.board.snakes.iter_mut().zip(squads.iter()) {
+            snake.squad = Some(squad.to_string());
+        }
+        g.game.ruleset.name = "squad".to_string();
+        g.game.ruleset.settings = Some(Settings {
+            food_spawn_chance: 15,
+            minimum_food: 1,
+            hazard_damage_per_turn: 14,
+            hazard_map: None,
+            hazard_map_author: None,
+            royale: None,
+            squad: Some(squad),
+        });
+        g
+    }
+
+    fn settings() -> SquadSettings {
+        SquadSettings {
+            allow_body_collisions: false,
+            shared_elimination: false,
+            shared_health: false,
+            shared_length: false,
+        }
+    }
+
+    fn snake<'a>(g: &'a Game, id: &str) -> Option<&'a BattleSnake> {
+        g.board.snakes.iter().find(|s| s.id == id)
+    }
+
+    #[test]
+    fn test_body_collisions() {
+        // b moves up, leaving its tail on (2, 3) for a to run in to
+        let moves = [
+            ("a".to_string(), Move::Right),
+            ("b".to_string(), Move::Up),
+            ("c".to_string(), Move::Left),
+        ];
+        let next = squad_game(settings(), ["red", "red", "blue"]).simulate_turn(moves.clone());
+        assert_eq!(snake(&next, "a"), None);
+        assert_eq!(next.you.head, Position::new(2, 3));
+        assert!(!next.you_are_alive());
+
+        let pass_through = SquadSettings {
+            allow_body_collisions: true,
+            ..settings()
+        };
+        let next = squad_game(pass_through, ["red", "red", "blue"]).simulate_turn(moves.clone());
+        let a = snake(&next, "a").unwrap();
+        assert_eq!(a.head, Position::new(2, 3));
+        assert_eq!(a.health, 49);
+        assert_eq!(next.you, *a);
+
+        // only squad mates can be moved through
+        let next = squad_game(pass_through, ["red", "blue", "blue"]).simulate_turn(moves.clone());
+        assert_eq!(snake(&next, "a"), None);
+
+        // and only in squad games
+        let mut standard = squad_game(pass_through, ["red", "red", "blue"]);
+        standard.game.ruleset.name = "standard".to_string();
+        assert_eq!(snake(&standard.simulate_turn(moves), "a"), None);
+    }
+
+    #[test]
+    fn test_shared_health_and_length() {
+        // c eats, and a and b keep going the way they were
+        let moves = [("c".to_string(), Move::Down)];
+        let unshared = squad_game(settings(), ["red", "blue", "blue"]).simulate_turn(moves.clone());
+        assert_eq!(snake(&unshared, "c").unwrap().health, 100);
+        assert_eq!(snake(&unshared, "c").unwrap().body.len(), 4);
+        assert_eq!(snake(&unshared, "b").unwrap().health, 79);
+        assert_eq!(snake(&unshared, "b").unwrap().body.len(), 3);
+        assert_eq!(snake(&unshared, "b").unwrap().head, Position::new(2, 5));
+        assert!(unshared.board.food.is_empty());
+
+        let shared = SquadSettings {
+            shared_health: true,
+            shared_length: true,
+            ..settings()
+        };
+        let next = squad_game(shared, ["red", "blue", "blue"]).simulate_turn(moves);
+        let (b, c) = (snake(&next, "b").unwrap(), snake(&next, "c").unwrap());
+        assert_eq!((b.health, c.health), (100, 100));
+        assert_eq!((b.body.len(), c.body.len()), (4, 4));
+        assert_eq!(b.body.back(), b.body.get(2));
+        // a isn't in their squad
+        assert_eq!(snake(&next, "a").unwrap().health, 49);
+        assert_eq!(snake(&next, "a").unwrap().body.len(), 3);
+    }
+
+    #[test]
+    fn test_turn_saturates() {
+        let mut g = squad_game(settings(), ["red", "blue", "blue"]);
+        g.turn = i32::MAX - 1;
+        let next = g.simulate_turn([("c".to_string(), Move::Down)]);
+        assert_eq!(next.turn, i32::MAX);
+        let next = next.simulate_turn([("c".to_string(), Move::Left)]);
+        assert_eq!(next.turn, i32::MAX);
+        assert_eq!(next.turn(), i32::MAX as u64);
+
+        g.turn = -1;
+        assert_eq!(g.turn(), 0);
+    }
+
+    #[test]
+    fn test_shared_elimination() {
+        // a turns back in to its own neck
+        let moves = [("a".to_string(), Move::Down)];
+        let next = squad_game(settings(), ["red", "red", "blue"]).simulate_turn(moves.clone());
+        assert_eq!(snake(&next, "a"), None);
+        assert!(snake(&next, "b").is_some());
+
+        let shared = SquadSettings {
+            shared_elimination: true,
+            ..settings()
+        };
+        let next = squad_game(shared, ["red", "red", "blue"]).simulate_turn(moves);
+        assert_eq!(next.snake_ids(), vec!["c"]);
+        assert_eq!(next.turn, 1);
+        assert!(next.is_over());
+    }
+
+    #[test]
+    fn test_traced_turn() {
+        let p = Position::new;
+        let mut g = Game::empty(7, 7);
+        g.add_you("a", vec![p(1, 3), p(1, 2), p(1, 1)], 50).unwrap();
+        g.board
+            .add_snake("b", vec![p(4, 3), p(4, 2), p(4, 1)], 80)
+            .unwrap();
+        g.board
+            .add_snake("c", vec![p(6, 3), p(6, 2), p(6, 1)], 90)
+            .unwrap();
+        g.board
+            .add_snake("d", vec![p(2, 3), p(3, 3), p(3, 2)], 70)
+            .unwrap();
+        g.board.add_food(p(1, 4)).unwrap();
+        g.board.add_hazard(p(4, 4)).unwrap();
+        g.game.ruleset.settings = Some(Settings {
+            food_spawn_chance: 15,
+            minimum_food: 1,
+            hazard_damage_per_turn: 14,
+            hazard_map: None,
+            hazard_map_author: None,
+            royale: None,
+            squad: None,
+        });
+        g.turn = 12;
+
+        // a eats, b moves on to a hazard, c runs off the board and d in to a's body
+        let moves = [
+            ("a".to_string(), Move::Up),
+            ("b".to_string(), Move::Up),
+            ("c".to_string(), Move::Right),
+            ("d".to_string(), Move::Left),
+        ];
+        let (next, trace) = g.simulate_turn_traced(moves.clone());
+        assert_eq!(next, g.simulate_turn(moves));
+        assert_eq!(trace.turn, 12);
+        assert_eq!(trace.snakes.len(), 4);
+
+        let a = trace.snake("a").unwrap();
+        assert_eq!((a.head_before, a.head_after), (p(1, 3), p(1, 4)));
+        assert!(a.ate_food);
+        assert_eq!(a.hazard_damage, 0);
+        assert_eq!(
+            a.phases,
+            vec![
+                (TurnPhase::ReduceHealth, 49),
+                (TurnPhase::DamageHazards, 49),
+                (TurnPhase::FeedSnakes, 100),
+                (TurnPhase::EliminateStarved, 100),
+            ]
+        );
+        assert_eq!((a.health_before, a.health_after), (50, 100));
+        assert_eq!(a.eliminated, None);
+
+        let b = trace.snake("b").unwrap();
+        assert!(!b.ate_food);
+        assert_eq!(b.hazard_damage, 14);
+        assert_eq!(b.phases[1], (TurnPhase::DamageHazards, 65));
+        assert_eq!(b.health_after, 65);
+        assert_eq!(b.eliminated, None);
+
+        let c = trace.snake("c").unwrap();
+        assert_eq!(c.head_after, p(7, 3));
+        assert!(c.phases.is_empty());
+        assert_eq!(c.eliminated, Some(EliminationCause::WallCollision));
+
+        let d = trace.snake("d").unwrap();
+        assert_eq!(
+            d.eliminated,
+            Some(EliminationCause::BodyCollision("a".to_string()))
+        );
+        assert_eq!(next.snake_ids(), vec!["a", "b"]);
+
+        let printed = trace.to_string();
+        assert!(printed.starts_with("turn 12\n"));
+        assert!(printed.contains(
+            "a: up (1, 3) -> (1, 4), ate food\n  health 50, reduce health 49, damage hazards 49, \
+             feed snakes 100, eliminate starved 100 -> 100\n"
+        ));
+        assert!(printed.contains("b: up (4, 3) -> (4, 4), 14 hazard damage\n"));
+        assert!(printed.contains(
+            "c: right (6, 3) -> (7, 3)\n  health 90 -> 90\n  eliminated: wall collision\n"
+        ));
+        assert!(printed.contains("  eliminated: collided with a's body\n"));
+    }
+
+    #[test]
+    fn test_traced_eliminations() {
+        // a turns back in to its own neck, and b and c meet head to head the same length
+        let mut g = squad_game(settings(), ["red", "red", "blue"]);
+        g.board.snakes[2].body = vec![
+            Position::new(3, 5),
+            Position::new(4, 5),
+            Position::new(5, 5),
+        ]
+        .into();
+        g.board.snakes[2].head = Position::new(3, 5);
+        let moves = [
+            ("a".to_string(), Move::Down),
+            ("b".to_string(), Move::Up),
+            ("c".to_string(), Move::Left),
+        ];
+        let (next, trace) = g.simulate_turn_traced(moves.clone());
+        assert!(next.board.snakes.is_empty());
+        let causes = trace
+            .snakes
+            .iter()
+            .map(|s| s.eliminated.clone().unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            causes,
+            vec![
+                EliminationCause::SelfCollision,
+                EliminationCause::HeadCollision("c".to_string()),
+                EliminationCause::HeadCollision("b".to_string()),
+            ]
+        );
+
+        let shared = SquadSettings {
+            shared_elimination: true,
+            ..settings()
+        };
+        let mut g = squad_game(shared, ["red", "red", "blue"]);
+        let (_, trace) = g.simulate_turn_traced([("a".to_string(), Move::Down)]);
+        assert_eq!(
+            trace.snake("b").unwrap().eliminated,
+            Some(EliminationCause::SquadEliminated)
+        );
+        assert_eq!(trace.snake("c").unwrap().eliminated, None);
+        g.board.snakes.clear();
+        assert!(g.simulate_turn_traced(moves).1.snakes.is_empty());
+    }
+
+    #[test]
+    fn test_matches_compact_simulation() {
+        let standard = crate::game_fixture(include_str!("../../fixtures/late_stage.json"));
+        // constrictor boards have no food, every snake grows instead
+        let mut constrictor = standard.clone();
+        constrictor.game.ruleset.name = "constrictor".to_string();
+        constrictor.board.food.clear();
+        for g in [standard, constrictor] {
+            assert_matches_compact_simulation(&g);
+        }
+    }
+
+    fn assert_matches_compact_simulation(g: &Game) {
+        use crate::compact_representation::StandardCellBoard4Snakes11x11;
+        use crate::types::{build_snake_id_map, SimulableGame, SnakeId};
+        use itertools::Itertools;
+
+        let snake_ids = build_snake_id_map(g);
+        let names: HashMap<_, _> = snake_ids
+            .iter()
+            .map(|(id, sid)| (*sid, id.clone()))
+            .collect();
+        let board: StandardCellBoard4Snakes11x11 = g.as_cell_board(&snake_ids).unwrap();
+        for (my_move, their_move) in Move::all_iter().cartesian_product(Move::all_iter()) {
+            let moves = [(SnakeId(0), [my_move]), (SnakeId(1), [their_move])];
+            let (_, next) = board
+                .simulate_with_moves(&Instruments, moves.iter().copied())
+                .next()
+                .unwrap();
+            let moves = moves
+                .iter()
+                .map(|(sid, mv)| (names[sid].clone(), mv[0]))
+                .collect::<Vec<_>>();
+            // compact boards remove a snake that turns back in to its neck before anything
+            // else moves, where the engine still has it there to be run in to
+            let turns_back = moves.iter().any(|(id, mv)| {
+                let snake = g.snake_by_id(id).unwrap();
+                snake.neck() == Some(g.new_head_position(snake.head, *mv))
+            });
+            if turns_back {
+                continue;
+            }
+            let wire = g.simulate_turn(moves.clone());
+            if g.game.ruleset.name == "constrictor" {
+                for snake in &wire.board.snakes {
+                    let before = g.snake_by_id(&snake.id).unwrap();
+                    assert_eq!(snake.body.len(), before.body.len() + 1);
+                    assert_eq!(snake.health, 100);
+                }
+            }
+            let converted: StandardCellBoard4Snakes11x11 = wire.as_cell_board(&snake_ids).unwrap();
+            assert_eq!(converted, next, "{:?}", moves);
+        }
+    }
+
+    #[derive(Debug)]
+    struct Instruments;
+    impl crate::types::SimulatorInstruments for Instruments {
+        fn observe_simulation(&self, _: std::time::Duration) {}
+    }
+}
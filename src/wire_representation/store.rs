@@ -0,0 +1,271 @@
+//! keeping the [SnakeIDMap] of every game a server is playing between requests, see
+//! [GameStateStore]
+
+use std::collections::HashMap;
+use std::sync::{Mutex, MutexGuard};
+use std::time::{Duration, Instant};
+
+use crate::types::{try_build_snake_id_map, SnakeIDMap};
+use crate::Error;
+
+use super::{Game, GameSummary, GameTracker};
+
+/// what is kept for one snake in one game
+#[derive(Debug)]
+struct Entry {
+    snake_ids: SnakeIDMap,
+    tracker: Option<GameTracker>,
+    last_seen: Instant,
+}
+
+/// The state of every game a server is playing, keyed by the game's id and the id of the snake
+/// playing it, since one server can play several snakes in the same game, and a snake can be in
+/// several games at once. It is [Sync], so one store can be shared by every request handler
+/// behind an `Arc`
+///
+/// ```
+/// # use std::time::Duration;
+/// # use battlesnake_game_types::wire_representation::GameStateStore;
+/// # let game = battlesnake_game_types::game_fixture(include_str!("../../fixtures/start_of_game.json"));
+/// let store = GameStateStore::new(Duration::from_secs(600));
+/// // in /start
+/// store.on_start(&game).unwrap();
+/// // in /move
+/// let snake_ids = store.on_move(&game).unwrap();
+/// // in /end
+/// store.on_end(&game);
+/// ```
+///
+/// Games that never get an /end, e.g. because the engine crashed, are dropped by
+/// [GameStateStore::sweep] once they haven't been seen for the store's time to live
+#[derive(Debug)]
+pub struct GameStateStore {
+    ttl: Duration,
+    track: bool,
+    games: Mutex<HashMap<(String, String), Entry>>,
+}
+
+impl GameStateStore {
+    /// an empty store, where games are dropped once they haven't been seen for `ttl`
+    pub fn new(ttl: Duration) -> Self {
+        GameStateStore {
+            ttl,
+            track: false,
+            games: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// keeps a [GameTracker] for each game as well, fed every turn by [GameStateStore::on_move]
+    pub fn with_tracking(mut self) -> Self {
+        self.track = true;
+        self
+    }
+
+    /// a panic in one handler while holding the lock doesn't stop every other game being played
+    fn games(&self) -> MutexGuard<'_, HashMap<(String, String), Entry>> {
+        self.games
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    fn key(game: &Game) -> (String, String) {
+        (game.game.id.clone(), game.you.id.clone())
+    }
+
+    fn entry(&self, game: &Game) -> Result<Entry, Error> {
+        let snake_ids = try_build_snake_id_map(game)?;
+        let tracker = if self.track {
+            let mut tracker = GameTracker::new(snake_ids.clone());
+            tracker.observe(game)?;
+            Some(tracker)
+        } else {
+            None
+        };
+        Ok(Entry {
+            snake_ids,
+            tracker,
+            last_seen: Instant::now(),
+        })
+    }
+
+    /// Builds the [SnakeIDMap] for a game that is starting and keeps it, replacing anything kept
+    /// for the same game and snake. Errors if the game has two snakes with the same id
+    pub fn on_start(&self, game: &Game) -> Result<SnakeIDMap, Error> {
+        let entry = self.entry(game)?;
+        let snake_ids = entry.snake_ids.clone();
+        self.games().insert(Self::key(game), entry);
+        Ok(snake_ids)
+    }
+
+    /// The [SnakeIDMap] for a turn of a game, which counts as the game being seen. A game that
+    /// wasn't started, e.g. because its /start was missed or it was swept, is started now. Errors
+    /// if it can't be started, or the turn can't be tracked
+    pub fn on_move(&self, game: &Game) -> Result<SnakeIDMap, Error> {
+        let mut games = self.games();
+        if let Some(entry) = games.get_mut(&Self::key(game)) {
+            entry.last_seen = Instant::now();
+            if let Some(tracker) = entry.tracker.as_mut() {
+                tracker.observe(game)?;
+            }
+            return Ok(entry.snake_ids.clone());
+        }
+        let entry = self.entry(game)?;
+        let snake_ids = entry.snake_ids.clone();
+        games.insert(Self::key(game), entry);
+        Ok(snake_ids)
+    }
+
+    /// the [SnakeIDMap] kept for snake `snake_id` in game `game_id`, None if there isn't one
+    pub fn get(&self, game_id: &str, snake_id: &str) -> Option<SnakeIDMap> {
+        self.games()
+            .get(&(game_id.to_string(), snake_id.to_string()))
+            .map(|entry| entry.snake_ids.clone())
+    }
+
+    /// the summary of a game tracked so far, None if it isn't being tracked
+    pub fn summary(&self, game_id: &str, snake_id: &str) -> Option<GameSummary> {
+        self.games()
+            .get(&(game_id.to_string(), snake_id.to_string()))
+            .and_then(|entry| entry.tracker.as_ref())
+            .map(GameTracker::summary)
+    }
+
+    /// Drops what is kept for a game that has ended, returning the summary of the whole game if
+    /// it was tracked
+    pub fn on_end(&self, game: &Game) -> Option<GameSummary> {
+        let mut entry = self.games().remove(&Self::key(game))?;
+        let tracker = entry.tracker.as_mut()?;
+        // the last turn is only sent to /end, the game is over either way
+        let _ = tracker.observe(game);
+        Some(tracker.summary())
+    }
+
+    /// drops every game that hasn't been seen for the store's time to live, returning how many
+    /// were dropped
+    pub fn sweep(&self) -> usize {
+        self.sweep_at(Instant::now())
+    }
+
+    fn sweep_at(&self, now: Instant) -> usize {
+        let mut games = self.games();
+        let before = games.len();
+        games.retain(|_, entry| now.saturating_duration_since(entry.last_seen) < self.ttl);
+        before - games.len()
+    }
+
+    /// how many games are kept
+    pub fn len(&self) -> usize {
+        self.games().len()
+    }
+
+    /// true if no games are kept
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+
+    use crate::types::SnakeId;
+    use crate::wire_representation::archive::Archive;
+
+    fn games_for(you: &str) -> Vec<Game> {
+        let archive: Archive =
+            serde_json::from_str(include_str!("../../fixtures/archive_4_snakes.json")).unwrap();
+        archive.games_for(you).unwrap()
+    }
+
+    #[test]
+    fn test_interleaved_games() {
+        fn assert_shareable<T: Send + Sync>() {}
+        assert_shareable::<GameStateStore>();
+
+        let store = Arc::new(GameStateStore::new(Duration::from_secs(60)).with_tracking());
+        let first = games_for("gs_red");
+        // the same game, played by a different snake of ours, and a second game at the same time
+        let green = games_for("gs_green");
+        let mut second = games_for("gs_red");
+        for game in &mut second {
+            game.game.id = "another game".to_string();
+        }
+
+        store.on_start(&first[0]).unwrap();
+        store.on_start(&green[0]).unwrap();
+        store.on_start(&second[0]).unwrap();
+        assert_eq!(store.len(), 3);
+
+        let handles = vec![first.clone(), second.clone()]
+            .into_iter()
+            .map(|games| {
+                let store = Arc::clone(&store);
+                std::thread::spawn(move || {
+                    for game in &games[1..] {
+                        let snake_ids = store.on_move(game).unwrap();
+                        assert_eq!(snake_ids.len(), 4);
+                    }
+                })
+            })
+            .collect::<Vec<_>>();
+        for handle in handles {
+            handle.join().unwrap();
+        }
+
+        let red = store.get(&first[0].game.id, "gs_red").unwrap();
+        assert_eq!(red["gs_red"], SnakeId(0));
+        assert_eq!(
+            store.get(&first[0].game.id, "gs_green").unwrap()["gs_green"],
+            SnakeId(0)
+        );
+        assert!(store.get("another game", "gs_green").is_none());
+        assert!(store.get("no such game", "gs_red").is_none());
+
+        // each game was tracked on its own, green only saw its start
+        let summary = store.summary("another game", "gs_red").unwrap();
+        assert_eq!(summary.turn, Some(4));
+        let green_summary = store.summary(&first[0].game.id, "gs_green").unwrap();
+        assert_eq!(green_summary.turn, Some(0));
+
+        let ended = store.on_end(first.last().unwrap()).unwrap();
+        assert_eq!(ended.turn, Some(4));
+        assert_eq!(store.len(), 2);
+        assert!(store.get(&first[0].game.id, "gs_red").is_none());
+        assert!(store.on_end(first.last().unwrap()).is_none());
+    }
+
+    #[test]
+    fn test_expiry() {
+        let ttl = Duration::from_secs(60);
+        let store = GameStateStore::new(ttl);
+        let games = games_for("gs_red");
+        let mut abandoned = games[0].clone();
+        abandoned.game.id = "abandoned".to_string();
+
+        store.on_start(&abandoned).unwrap();
+        store.on_start(&games[0]).unwrap();
+        let now = Instant::now();
+        assert_eq!(store.sweep(), 0);
+        assert_eq!(store.sweep_at(now + ttl / 2), 0);
+
+        // the other game carries on being played, while the abandoned one never ends
+        store.on_move(&games[1]).unwrap();
+        store
+            .games()
+            .get_mut(&GameStateStore::key(&games[1]))
+            .unwrap()
+            .last_seen = now + ttl / 2;
+        assert!(store.summary("abandoned", "gs_red").is_none());
+        assert_eq!(store.sweep_at(now + ttl), 1);
+        assert!(store.get("abandoned", "gs_red").is_none());
+        assert!(store.get(&games[0].game.id, "gs_red").is_some());
+        assert_eq!(store.sweep_at(now + ttl + ttl / 2), 1);
+        assert!(store.is_empty());
+
+        // a game whose /start was missed, or that was swept, starts again on its next move
+        let snake_ids = store.on_move(&games[2]).unwrap();
+        assert_eq!(snake_ids, try_build_snake_id_map(&games[2]).unwrap());
+        assert_eq!(store.len(), 1);
+    }
+}
@@ -0,0 +1,161 @@
+//! tagging snakes with their integer ids once per request, so nothing after that has to look up
+//! snake id strings
+
+use crate::types::{SnakeIDMap, SnakeId};
+use crate::Error;
+
+use super::{BattleSnake, Game};
+
+/// A game where each living snake has been tagged with its [SnakeId], see
+/// [Game::with_snake_ids]. The ids are guaranteed to be unique, and snakes can be looked up by
+/// them without searching the board
+#[derive(Debug, Clone)]
+pub struct TaggedGame<'a> {
+    game: &'a Game,
+    /// one per snake on the board, None for snakes which have been eliminated
+    ids: Vec<Option<SnakeId>>,
+    /// where each id's snake is on the board, by id
+    positions: Vec<Option<usize>>,
+    you: Option<SnakeId>,
+}
+
+impl<'a> TaggedGame<'a> {
+    /// the game that was tagged
+    pub fn game(&self) -> &'a Game {
+        self.game
+    }
+
+    /// the living snakes and their ids, in the order they are on the board
+    pub fn snakes(&self) -> impl Iterator<Item = (SnakeId, &'a BattleSnake)> + '_ {
+        self.ids
+            .iter()
+            .zip(self.game.board.snakes.iter())
+            .filter_map(|(id, snake)| id.map(|id| (id, snake)))
+    }
+
+    /// the living snake with `id`, None if it has been eliminated or was never in the game
+    pub fn snake(&self, id: &SnakeId) -> Option<&'a BattleSnake> {
+        let position = (*self.positions.get(id.as_usize())?)?;
+        Some(&self.game.board.snakes[position])
+    }
+
+    /// your id, which is SnakeId(0) for maps from [crate::types::build_snake_id_map]. None if
+    /// you aren't in the map
+    pub fn you_id(&self) -> Option<SnakeId> {
+        self.you
+    }
+
+    /// one per snake on the board, None for snakes which have been eliminated
+    pub(crate) fn ids(&self) -> &[Option<SnakeId>] {
+        &self.ids
+    }
+
+    /// you as you are on the board, the same as looking up [TaggedGame::you_id]. None once you
+    /// have been eliminated
+    pub fn you(&self) -> Option<&'a BattleSnake> {
+        self.snake(&self.you?)
+    }
+}
+
+impl Game {
+    /// Looks up each living snake in `snake_ids`, once. Errors if a snake isn't in the map or if
+    /// two snakes would get the same id
+    pub fn with_snake_ids(&self, snake_ids: &SnakeIDMap) -> Result<TaggedGame<'_>, Error> {
+        let ids = self.view().snake_ids(snake_ids)?;
+
+        let mut positions = vec![];
+        for (position, id) in ids.iter().enumerate() {
+            if let Some(id) = id {
+                if positions.len() <= id.as_usize() {
+                    positions.resize(id.as_usize() + 1, None);
+                }
+                positions[id.as_usize()] = Some(position);
+            }
+        }
+        Ok(TaggedGame {
+            game: self,
+            ids,
+            positions,
+            you: snake_ids.get(&self.you.id).copied(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::types::build_snake_id_map;
+
+    use super::*;
+
+    #[test]
+    fn test_with_snake_ids() {
+        let g = crate::game_fixture(include_str!("../../fixtures/late_stage.json"));
+        let snake_ids = build_snake_id_map(&g);
+        let tagged = g.with_snake_ids(&snake_ids).unwrap();
+
+        let tags = tagged.snakes().collect::<Vec<_>>();
+        assert_eq!(tags.len(), g.board.snakes.len());
+        for (sid, snake) in tags {
+            assert_eq!(snake_ids[&snake.id], sid);
+        }
+
+        let mut eliminated = g.clone();
+        eliminated.board.snakes[1].health = 0;
+        let tagged = eliminated.with_snake_ids(&snake_ids).unwrap();
+        assert_eq!(tagged.snakes().count(), 1);
+    }
+
+    #[test]
+    fn test_snake_lookup() {
+        let g = crate::game_fixture(include_str!("../../fixtures/4_snake_game.json"));
+        let snake_ids = build_snake_id_map(&g);
+        let tagged = g.with_snake_ids(&snake_ids).unwrap();
+
+        for snake in &g.board.snakes {
+            let sid = snake_ids[&snake.id];
+            assert_eq!(tagged.snake(&sid), Some(snake));
+            assert_eq!(g.board.snake(&sid, &snake_ids), Some(snake));
+        }
+        assert_eq!(tagged.you_id(), Some(SnakeId(0)));
+        assert_eq!(tagged.you().map(|you| &you.id), Some(&g.you.id));
+        assert_eq!(tagged.snake(&SnakeId(0)), tagged.you());
+        assert_eq!(tagged.snake(&SnakeId(9)), None);
+
+        // the engine leaves eliminated snakes off the board, but they stay in the map
+        let mut eliminated = g.clone();
+        let dead = eliminated.board.snakes.remove(2);
+        let sid = snake_ids[&dead.id];
+        let tagged = eliminated.with_snake_ids(&snake_ids).unwrap();
+        assert_eq!(tagged.snake(&sid), None);
+        assert_eq!(eliminated.board.snake(&sid, &snake_ids), None);
+        for snake in &eliminated.board.snakes {
+            assert_eq!(tagged.snake(&snake_ids[&snake.id]), Some(snake));
+        }
+
+        let mut you_eliminated = g.clone();
+        let you = you_eliminated.you.id.clone();
+        you_eliminated.board.snakes.retain(|s| s.id != you);
+        let tagged = you_eliminated.with_snake_ids(&snake_ids).unwrap();
+        assert_eq!(tagged.you_id(), Some(SnakeId(0)));
+        assert_eq!(tagged.you(), None);
+    }
+
+    #[test]
+    fn test_with_snake_ids_errors() {
+        let g = crate::game_fixture(include_str!("../../fixtures/late_stage.json"));
+        let mut snake_ids = build_snake_id_map(&g);
+        let unknown = g.board.snakes[0].id.clone();
+
+        snake_ids.insert(unknown.clone(), SnakeId(0));
+        assert!(matches!(
+            g.with_snake_ids(&snake_ids),
+            Err(Error::DuplicateSnakeId(_))
+        ));
+
+        snake_ids.remove(&unknown);
+        match g.with_snake_ids(&snake_ids) {
+            Err(Error::UnknownSnake(id)) => assert_eq!(id, unknown),
+            other => panic!("expected an unknown snake, got {:?}", other),
+        }
+    }
+}
@@ -0,0 +1,177 @@
+//! working out how long we can search for before we have to respond. There is no clock on
+//! wasm32-unknown-unknown, so there only the methods that take the elapsed time are available
+
+use std::time::Duration;
+#[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+use std::time::Instant;
+
+use super::Game;
+
+/// How long we have to respond to a move request: the game timeout, minus the round trip to the
+/// engine, minus a safety margin. The round trip is estimated from the latency the engine
+/// reports for our snake, smoothed with an exponential moving average
+#[derive(Debug, Clone, Copy)]
+pub struct TimeBudget {
+    #[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+    start: Instant,
+    timeout: Duration,
+    safety_margin: Duration,
+    latency: Option<Duration>,
+    smoothing: f64,
+}
+
+impl TimeBudget {
+    /// how much weight a newly reported latency gets in the moving average by default
+    pub const DEFAULT_SMOOTHING: f64 = 0.25;
+
+    /// A budget for a turn starting now. `latency` is the expected round trip, None if it
+    /// isn't known yet
+    pub fn new(timeout: Duration, latency: Option<Duration>, safety_margin: Duration) -> Self {
+        Self {
+            #[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+            start: Instant::now(),
+            timeout,
+            safety_margin,
+            latency,
+            smoothing: Self::DEFAULT_SMOOTHING,
+        }
+    }
+
+    /// A budget for a turn starting now, using the games timeout and the latency reported for
+    /// you. The engine reports a latency of 0 before we have responded to anything, so that is
+    /// treated as not knowing the latency yet
+    pub fn from_game(game: &Game, safety_margin: Duration) -> Self {
+        let timeout = Duration::from_millis(game.game.timeout.max(0) as u64);
+        let latency = game
+            .you
+            .latency_ms()
+            .filter(|l| *l > 0)
+            .map(Duration::from_millis);
+        Self::new(timeout, latency, safety_margin)
+    }
+
+    /// use `smoothing` (between 0 and 1) as the weight given to each new latency, higher values
+    /// follow changes in latency faster. Values outside of that are clamped to it, and NaN uses
+    /// [TimeBudget::DEFAULT_SMOOTHING]
+    pub fn with_smoothing(mut self, smoothing: f64) -> Self {
+        self.smoothing = if smoothing.is_nan() {
+            Self::DEFAULT_SMOOTHING
+        } else {
+            smoothing.clamp(0.0, 1.0)
+        };
+        self
+    }
+
+    /// Call this when each new move request arrives with the latency reported for you. This
+    /// folds the latency in to the moving average and restarts the clock for the new turn. The
+    /// first latency reported replaces the estimate outright
+    pub fn update(&mut self, latency: Duration) {
+        self.latency = Some(match self.latency {
+            Some(average) => {
+                average.mul_f64(1.0 - self.smoothing) + latency.mul_f64(self.smoothing)
+            }
+            None => latency,
+        });
+        #[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+        {
+            self.start = Instant::now();
+        }
+    }
+
+    /// the current estimate of the round trip to the engine, zero if there isn't one yet
+    pub fn latency(&self) -> Duration {
+        self.latency.unwrap_or_default()
+    }
+
+    /// the total time we have for this turn, zero if the latency and safety margin use up the
+    /// whole timeout
+    pub fn budget(&self) -> Duration {
+        self.timeout
+            .saturating_sub(self.latency())
+            .saturating_sub(self.safety_margin)
+    }
+
+    /// how long is left once `elapsed` has passed since the request arrived, zero once the
+    /// budget is used up. For when the time is measured with something other than `Instant`
+    pub fn remaining_after(&self, elapsed: Duration) -> Duration {
+        self.budget().saturating_sub(elapsed)
+    }
+
+    /// when we have to stop searching this turn
+    #[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+    pub fn deadline(&self) -> Instant {
+        self.start + self.budget()
+    }
+
+    /// how long is left until the deadline, zero once it has passed
+    #[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+    pub fn remaining(&self) -> Duration {
+        self.deadline().saturating_duration_since(Instant::now())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ms(millis: u64) -> Duration {
+        Duration::from_millis(millis)
+    }
+
+    #[test]
+    #[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+    fn test_from_game() {
+        let g = crate::game_fixture(include_str!("../../fixtures/4_snake_game.json"));
+        let budget = TimeBudget::from_game(&g, ms(50));
+        assert_eq!(budget.latency(), ms(100));
+        assert_eq!(budget.budget(), ms(350));
+        assert_eq!(budget.remaining_after(ms(100)), ms(250));
+        assert!(budget.remaining() <= ms(350));
+        assert!(budget.deadline() <= Instant::now() + ms(350));
+
+        let mut g = g;
+        g.you.latency = Some("0".to_string());
+        assert_eq!(TimeBudget::from_game(&g, ms(50)).budget(), ms(450));
+    }
+
+    #[test]
+    fn test_latency_moving_average() {
+        let mut budget = TimeBudget::new(ms(500), None, ms(20)).with_smoothing(0.5);
+        assert_eq!(budget.budget(), ms(480));
+
+        let expected = [(100, 100), (200, 150), (50, 100), (100, 100), (300, 200)];
+        for (reported, average) in expected.iter() {
+            budget.update(ms(*reported));
+            assert_eq!(budget.latency(), ms(*average));
+            assert_eq!(budget.budget(), ms(480 - average));
+        }
+    }
+
+    #[test]
+    fn test_smoothing_out_of_range() {
+        let latency_after = |smoothing| {
+            let mut budget =
+                TimeBudget::new(ms(500), Some(ms(100)), ms(20)).with_smoothing(smoothing);
+            budget.update(ms(300));
+            budget.latency()
+        };
+        assert_eq!(latency_after(2.0), ms(300));
+        assert_eq!(latency_after(f64::INFINITY), ms(300));
+        assert_eq!(latency_after(-1.0), ms(100));
+        assert_eq!(latency_after(f64::NAN), ms(150));
+    }
+
+    #[test]
+    #[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+    fn test_budget_saturates() {
+        let mut budget = TimeBudget::new(ms(500), Some(ms(480)), ms(50));
+        assert_eq!(budget.budget(), Duration::ZERO);
+        assert_eq!(budget.remaining(), Duration::ZERO);
+        assert_eq!(budget.remaining_after(ms(10)), Duration::ZERO);
+
+        budget.update(ms(2000));
+        assert_eq!(budget.budget(), Duration::ZERO);
+        assert!(budget.deadline() <= Instant::now());
+        assert_eq!(budget.remaining(), Duration::ZERO);
+    }
+}
@@ -0,0 +1,120 @@
+//! A record of how a turn was resolved, see
+//! [Game::simulate_turn_traced](super::Game::simulate_turn_traced)
+
+use std::fmt;
+
+use serde::Serialize;
+
+use crate::types::{Move, TurnPhase};
+
+use super::Position;
+
+/// why a snake was eliminated, the same causes the engine reports
+#[derive(Serialize, Debug, Clone, PartialEq, Eq, Hash)]
+pub enum EliminationCause {
+    /// moved off the edge of the board
+    WallCollision,
+    /// ran out of health, from starving or from hazards
+    OutOfHealth,
+    /// moved on to its own body
+    SelfCollision,
+    /// moved on to the body of the snake with this id
+    BodyCollision(String),
+    /// lost a head to head with the snake with this id, or drew with it
+    HeadCollision(String),
+    /// a squad mate was eliminated, with the squad ruleset's shared elimination
+    SquadEliminated,
+}
+
+impl fmt::Display for EliminationCause {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            EliminationCause::WallCollision => write!(f, "wall collision"),
+            EliminationCause::OutOfHealth => write!(f, "out of health"),
+            EliminationCause::SelfCollision => write!(f, "self collision"),
+            EliminationCause::BodyCollision(id) => write!(f, "collided with {}'s body", id),
+            EliminationCause::HeadCollision(id) => write!(f, "head to head with {}", id),
+            EliminationCause::SquadEliminated => write!(f, "squad eliminated"),
+        }
+    }
+}
+
+/// how one snake's turn was resolved
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SnakeTrace {
+    pub id: String,
+    /// the move the snake made, which is the way it was already going if it wasn't given one
+    pub mv: Move,
+    pub head_before: Position,
+    pub head_after: Position,
+    pub health_before: i32,
+    /// the snake's health after each phase of the turn, in the order they were played. Empty if
+    /// the snake moved off the board or in to a wall, since that eliminates it before any of them
+    pub phases: Vec<(TurnPhase, i32)>,
+    pub ate_food: bool,
+    /// the damage from the hazards on `head_after`, whether or not it was taken
+    pub hazard_damage: i32,
+    /// the snake's health at the end of the turn, after any squad sharing
+    pub health_after: i32,
+    /// None if the snake survived the turn
+    pub eliminated: Option<EliminationCause>,
+}
+
+/// how each snake that started a turn alive had its turn resolved
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TurnTrace {
+    /// the turn the game was on before it was simulated
+    pub turn: i32,
+    pub snakes: Vec<SnakeTrace>,
+}
+
+impl TurnTrace {
+    /// the trace of the snake with `id`, None if it wasn't alive at the start of the turn
+    pub fn snake(&self, id: &str) -> Option<&SnakeTrace> {
+        self.snakes.iter().find(|s| s.id == id)
+    }
+}
+
+fn phase_name(phase: TurnPhase) -> &'static str {
+    match phase {
+        TurnPhase::ReduceHealth => "reduce health",
+        TurnPhase::DamageHazards => "damage hazards",
+        TurnPhase::DamageHazardsIncludingFood => "damage hazards including food",
+        TurnPhase::FeedSnakes => "feed snakes",
+        TurnPhase::EliminateStarved => "eliminate starved",
+    }
+}
+
+impl fmt::Display for TurnTrace {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "turn {}", self.turn)?;
+        for snake in &self.snakes {
+            write!(
+                f,
+                "{}: {} ({}, {}) -> ({}, {})",
+                snake.id,
+                snake.mv,
+                snake.head_before.x,
+                snake.head_before.y,
+                snake.head_after.x,
+                snake.head_after.y
+            )?;
+            if snake.ate_food {
+                write!(f, ", ate food")?;
+            }
+            if snake.hazard_damage > 0 {
+                write!(f, ", {} hazard damage", snake.hazard_damage)?;
+            }
+            writeln!(f)?;
+            write!(f, "  health {}", snake.health_before)?;
+            for (phase, health) in &snake.phases {
+                write!(f, ", {} {}", phase_name(*phase), health)?;
+            }
+            writeln!(f, " -> {}", snake.health_after)?;
+            if let Some(cause) = &snake.eliminated {
+                writeln!(f, "  eliminated: {}", cause)?;
+            }
+        }
+        Ok(())
+    }
+}
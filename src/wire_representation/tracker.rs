@@ -0,0 +1,407 @@
+//! per snake statistics built up over a whole game from the game sent on each turn, for
+//! modelling opponents or logging once the game is over
+
+use std::collections::{HashMap, HashSet};
+
+use itertools::Itertools;
+use serde::Serialize;
+
+use crate::types::{LastMoveDeterminableGame, Move, SnakeIDMap, SnakeId};
+use crate::Error;
+
+use super::{EliminationCause, Game};
+
+/// the most move combinations tried when working out why snakes were eliminated
+const MAX_COMBINATIONS: usize = 4096;
+
+/// how many times a snake made each move
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct MoveCounts {
+    pub up: u32,
+    pub down: u32,
+    pub left: u32,
+    pub right: u32,
+}
+
+impl MoveCounts {
+    /// how many times `mv` was made
+    pub fn get(&self, mv: Move) -> u32 {
+        match mv {
+            Move::Up => self.up,
+            Move::Down => self.down,
+            Move::Left => self.left,
+            Move::Right => self.right,
+        }
+    }
+
+    /// how many moves were counted
+    pub fn total(&self) -> u32 {
+        self.up + self.down + self.left + self.right
+    }
+
+    fn record(&mut self, mv: Move) {
+        match mv {
+            Move::Up => self.up += 1,
+            Move::Down => self.down += 1,
+            Move::Left => self.left += 1,
+            Move::Right => self.right += 1,
+        }
+    }
+}
+
+/// Everything seen of one snake so far. Moves and food are only counted between turns that were
+/// both observed
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct SnakeSummary {
+    pub id: String,
+    /// the moves it made, inferred from its body, see [LastMoveDeterminableGame]
+    pub moves: MoveCounts,
+    /// the food it ate, from how much it grew
+    pub food_eaten: u32,
+    /// `(turn, health)` for every turn it was seen alive on
+    pub health: Vec<(i32, i32)>,
+    /// the mean of the latencies reported for it, None until one has been
+    pub average_latency_ms: Option<f64>,
+    /// how many latencies went in to the average
+    pub latency_samples: u32,
+    /// the snakes whose eliminations were put down to this one
+    pub kills: u32,
+    /// the first turn it was seen eliminated on
+    pub eliminated_on: Option<i32>,
+    /// why it was eliminated, None if it is still alive or the turns before and after weren't
+    /// enough to tell
+    pub elimination_cause: Option<EliminationCause>,
+}
+
+impl SnakeSummary {
+    fn new(id: &str) -> Self {
+        SnakeSummary {
+            id: id.to_string(),
+            moves: MoveCounts::default(),
+            food_eaten: 0,
+            health: vec![],
+            average_latency_ms: None,
+            latency_samples: 0,
+            kills: 0,
+            eliminated_on: None,
+            elimination_cause: None,
+        }
+    }
+
+    fn record_latency(&mut self, latency: u64) {
+        self.latency_samples += 1;
+        let average = self.average_latency_ms.unwrap_or(0.0);
+        self.average_latency_ms =
+            Some(average + (latency as f64 - average) / self.latency_samples as f64);
+    }
+}
+
+/// every snake's statistics, ordered by their [SnakeId]
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct GameSummary {
+    /// the last turn observed, None if none have been
+    pub turn: Option<i32>,
+    pub snakes: Vec<SnakeSummary>,
+}
+
+/// Builds up a [SnakeSummary] for every snake from the game sent each turn. The snakes are
+/// identified by the [SnakeIDMap] built at the start of the game, so they keep the same
+/// [SnakeId] however many others are eliminated
+///
+/// ```
+/// # use battlesnake_game_types::types::build_snake_id_map;
+/// # use battlesnake_game_types::wire_representation::GameTracker;
+/// # let game = battlesnake_game_types::game_fixture(include_str!("../../fixtures/start_of_game.json"));
+/// let mut tracker = GameTracker::new(build_snake_id_map(&game));
+/// tracker.observe(&game).unwrap();
+/// let log = serde_json::to_string(&tracker.summary()).unwrap();
+/// ```
+#[derive(Debug, Clone)]
+pub struct GameTracker {
+    snake_ids: SnakeIDMap,
+    snakes: HashMap<SnakeId, SnakeSummary>,
+    previous: Option<Game>,
+}
+
+impl GameTracker {
+    /// a tracker that hasn't seen any turns, for the snakes in `snake_ids`
+    pub fn new(snake_ids: SnakeIDMap) -> Self {
+        GameTracker {
+            snake_ids,
+            snakes: HashMap::new(),
+            previous: None,
+        }
+    }
+
+    /// Adds the next turn of the game. Moves, food and eliminations are worked out from the
+    /// turn before, so a turn that was missed makes those uncounted. Turns that aren't after the
+    /// last one observed are ignored. Errors if a snake isn't in the [SnakeIDMap]
+    pub fn observe(&mut self, game: &Game) -> Result<(), Error> {
+        if let Some(previous) = &self.previous {
+            if game.turn <= previous.turn {
+                return Ok(());
+            }
+        }
+        let living = game
+            .board
+            .snakes
+            .iter()
+            .filter(|s| s.health > 0)
+            .collect_vec();
+        for snake in &living {
+            if !self.snake_ids.contains_key(&snake.id) {
+                return Err(Error::UnknownSnake(snake.id.clone()));
+            }
+        }
+
+        let previous = self
+            .previous
+            .take()
+            .filter(|previous| previous.turn + 1 == game.turn);
+        for snake in &living {
+            let stats = self
+                .snakes
+                .entry(self.snake_ids[&snake.id])
+                .or_insert_with(|| SnakeSummary::new(&snake.id));
+            stats.health.push((game.turn, snake.health));
+            if let Some(latency) = snake.latency_ms() {
+                stats.record_latency(latency);
+            }
+            let before = match previous.as_ref().and_then(|p| p.snake_by_id(&snake.id)) {
+                Some(before) if before.health > 0 => before,
+                _ => continue,
+            };
+            if let Some(mv) = game.last_move(&snake.id) {
+                stats.moves.record(mv);
+            }
+            stats.food_eaten += snake.body.len().saturating_sub(before.body.len()) as u32;
+        }
+
+        // snakes we have seen that aren't alive any more
+        let alive = living.iter().map(|s| s.id.as_str()).collect::<HashSet<_>>();
+        let eliminated = self
+            .snakes
+            .values()
+            .filter(|stats| stats.eliminated_on.is_none() && !alive.contains(stats.id.as_str()))
+            .map(|stats| stats.id.clone())
+            .collect_vec();
+        let causes = match &previous {
+            Some(previous) => elimination_causes(previous, game, &eliminated),
+            None => HashMap::new(),
+        };
+        let snake_ids = &self.snake_ids;
+        for id in eliminated {
+            let cause = causes.get(&id).cloned();
+            let killer = match &cause {
+                Some(EliminationCause::BodyCollision(by)) => Some(by),
+                Some(EliminationCause::HeadCollision(by)) => Some(by),
+                _ => None,
+            };
+            if let Some(killer) = killer.and_then(|by| snake_ids.get(by)) {
+                if let Some(stats) = self.snakes.get_mut(killer) {
+                    stats.kills += 1;
+                }
+            }
+            let stats = self.snakes.get_mut(&snake_ids[&id]).unwrap();
+            stats.eliminated_on = Some(game.turn);
+            stats.elimination_cause = cause;
+        }
+
+        self.previous = Some(game.clone());
+        Ok(())
+    }
+
+    /// what has been seen of the snake with `id`, None if it hasn't been seen alive
+    pub fn stats(&self, id: SnakeId) -> Option<&SnakeSummary> {
+        self.snakes.get(&id)
+    }
+
+    /// every snake seen so far, for logging
+    pub fn summary(&self) -> GameSummary {
+        GameSummary {
+            turn: self.previous.as_ref().map(|g| g.turn),
+            snakes: self
+                .snakes
+                .iter()
+                .sorted_by_key(|(sid, _)| sid.0)
+                .map(|(_, stats)| stats.clone())
+                .collect(),
+        }
+    }
+}
+
+/// Why each of `eliminated` was eliminated between `previous` and `game`, by simulating every
+/// combination of moves that could have got from one to the other: the survivors' moves
+/// inferred from their bodies, and any move for the snakes that were eliminated. A cause is
+/// only given if every combination that eliminates exactly those snakes agrees on it. Snakes
+/// hardly ever turn back on to their necks, so those moves are only tried if nothing else
+/// explains the turn
+fn elimination_causes(
+    previous: &Game,
+    game: &Game,
+    eliminated: &[String],
+) -> HashMap<String, EliminationCause> {
+    consistent_causes(previous, game, eliminated, false)
+        .or_else(|| consistent_causes(previous, game, eliminated, true))
+        .unwrap_or_default()
+}
+
+/// the causes every consistent combination of moves agrees on, see [elimination_causes], None
+/// if no combination is consistent or there are too many to try
+fn consistent_causes(
+    previous: &Game,
+    game: &Game,
+    eliminated: &[String],
+    onto_neck: bool,
+) -> Option<HashMap<String, EliminationCause>> {
+    let moves = previous
+        .living_snakes()
+        .map(|s| {
+            let moves = match game.snake_by_id(&s.id).and_then(|_| game.last_move(&s.id)) {
+                Some(mv) if !eliminated.contains(&s.id) => vec![mv],
+                _ => Move::all_iter()
+                    .filter(|mv| {
+                        onto_neck || Some(previous.new_head_position(s.head, *mv)) != s.neck()
+                    })
+                    .collect(),
+            };
+            moves.into_iter().map(move |mv| (s.id.clone(), mv))
+        })
+        .collect_vec();
+    let combinations = moves.iter().map(|m| m.len()).product::<usize>();
+    if moves.is_empty() || combinations > MAX_COMBINATIONS {
+        return None;
+    }
+
+    let mut causes: Option<HashMap<String, Option<EliminationCause>>> = None;
+    for combination in moves.into_iter().multi_cartesian_product() {
+        let (_, trace) = previous.simulate_turn_traced(combination);
+        let consistent = trace.snakes.iter().all(|s| {
+            let expected_alive = !eliminated.contains(&s.id);
+            s.eliminated.is_none() == expected_alive
+                && (!expected_alive
+                    || game.snake_by_id(&s.id).map(|after| after.head) == Some(s.head_after))
+        });
+        if !consistent {
+            continue;
+        }
+        let causes = causes.get_or_insert_with(HashMap::new);
+        for s in trace.snakes {
+            if let Some(cause) = s.eliminated {
+                causes
+                    .entry(s.id)
+                    .and_modify(|agreed| {
+                        if agreed.as_ref() != Some(&cause) {
+                            *agreed = None;
+                        }
+                    })
+                    .or_insert(Some(cause));
+            }
+        }
+    }
+    causes.map(|causes| {
+        causes
+            .into_iter()
+            .filter_map(|(id, cause)| cause.map(|cause| (id, cause)))
+            .collect()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::build_snake_id_map;
+    use crate::wire_representation::archive::{Archive, DeathCause};
+
+    #[test]
+    fn test_replay_archive() {
+        let archive: Archive =
+            serde_json::from_slice(include_bytes!("../../fixtures/archive_4_snakes.json")).unwrap();
+        let games = archive.games_for("gs_red").unwrap();
+        let snake_ids = build_snake_id_map(&games[0]);
+        let mut tracker = GameTracker::new(snake_ids.clone());
+        for game in &games {
+            tracker.observe(game).unwrap();
+        }
+        let last = archive.frames.last().unwrap();
+
+        let summary = tracker.summary();
+        assert_eq!(summary.turn, Some(last.turn));
+        assert_eq!(
+            summary.snakes.iter().map(|s| s.id.as_str()).collect_vec(),
+            vec!["gs_red", "gs_blue", "gs_green", "gs_yellow"]
+        );
+        for snake in &last.snakes {
+            let stats = tracker.stats(snake_ids[&snake.id]).unwrap();
+            let turns_alive = match &snake.death {
+                Some(death) => death.turn,
+                None => last.turn + 1,
+            };
+            assert_eq!(stats.health.len() as i32, turns_alive, "{}", snake.id);
+            assert_eq!(stats.moves.total() as i32, turns_alive - 1, "{}", snake.id);
+            assert_eq!(
+                stats.food_eaten as usize,
+                snake.body.len() - 3,
+                "{}",
+                snake.id
+            );
+            assert_eq!(stats.eliminated_on, snake.death.as_ref().map(|d| d.turn));
+
+            let expected = snake.death.as_ref().map(|death| match death.cause {
+                DeathCause::WallCollision => EliminationCause::WallCollision,
+                DeathCause::HeadCollision => {
+                    EliminationCause::HeadCollision(death.eliminated_by.clone())
+                }
+                _ => unreachable!("the archive only has walls and head to heads"),
+            });
+            assert_eq!(stats.elimination_cause, expected, "{}", snake.id);
+        }
+        // green and yellow took each other out head to head
+        let kills = summary.snakes.iter().map(|s| s.kills).collect_vec();
+        assert_eq!(kills, vec![0, 0, 1, 1]);
+
+        // the summary is plain json for logging
+        let json = serde_json::to_value(&summary).unwrap();
+        assert_eq!(json["snakes"][1]["elimination_cause"], "WallCollision");
+        assert_eq!(json["snakes"][0]["health"][0], serde_json::json!([0, 100]));
+
+        // turns already seen aren't counted twice
+        tracker.observe(&games[2]).unwrap();
+        assert_eq!(tracker.summary(), summary);
+    }
+
+    #[test]
+    fn test_latency_and_missed_turns() {
+        let mut game = crate::game_fixture(include_str!("../../fixtures/4_snake_game.json"));
+        let snake_ids = build_snake_id_map(&game);
+        let mut tracker = GameTracker::new(snake_ids.clone());
+        let id = game.board.snakes[0].id.clone();
+        for (turn, latency) in [(10, "100"), (11, "200"), (13, "")].iter() {
+            game.turn = *turn;
+            game.board.snakes[0].latency = Some(latency.to_string());
+            tracker.observe(&game).unwrap();
+        }
+        let stats = tracker.stats(snake_ids[&id]).unwrap();
+        assert_eq!(stats.average_latency_ms, Some(150.0));
+        assert_eq!(stats.latency_samples, 2);
+        // moves are only counted between turns next to each other, and turn 12 was missed
+        assert_eq!(stats.health.len(), 3);
+        assert_eq!(stats.moves.total(), 1);
+        assert_eq!(stats.food_eaten, 0);
+
+        // a snake that is eliminated across a missed turn has no cause
+        game.turn = 15;
+        game.board.snakes.remove(0);
+        tracker.observe(&game).unwrap();
+        let stats = tracker.stats(snake_ids[&id]).unwrap();
+        assert_eq!(stats.eliminated_on, Some(15));
+        assert_eq!(stats.elimination_cause, None);
+
+        game.board.snakes[0].id = "stranger".to_string();
+        game.turn = 16;
+        assert!(matches!(
+            tracker.observe(&game),
+            Err(Error::UnknownSnake(id)) if id == "stranger"
+        ));
+    }
+}
@@ -0,0 +1,175 @@
+//! the parts of a wire game that validating it and converting it to a compact board look at,
+//! so [Game](super::Game) and [GameRef](super::GameRef) are checked and converted the same way
+
+use std::collections::{HashSet, VecDeque};
+
+use itertools::Itertools;
+
+use crate::types::{SimulationSettings, SnakeIDMap, SnakeId};
+use crate::{Error, ValidationWarning};
+
+use super::Position;
+
+/// the most pieces of a snake that can be stacked on one square, so the compact boards can
+/// count them in a byte
+const MAX_STACK: usize = u8::MAX as usize + 1;
+
+#[derive(Debug, Clone)]
+pub(crate) struct SnakeView<'a> {
+    pub(crate) id: &'a str,
+    pub(crate) head: Position,
+    pub(crate) body: &'a VecDeque<Position>,
+    pub(crate) health: i32,
+}
+
+#[derive(Debug, Clone)]
+pub(crate) struct GameView<'a> {
+    pub(crate) width: i32,
+    pub(crate) height: i32,
+    pub(crate) wrapped: bool,
+    pub(crate) snakes: Vec<SnakeView<'a>>,
+    pub(crate) food: &'a HashSet<Position>,
+    pub(crate) duplicate_food: &'a [Position],
+    pub(crate) hazards: &'a [Position],
+    pub(crate) walls: &'a HashSet<Position>,
+    pub(crate) settings: SimulationSettings,
+}
+
+impl GameView<'_> {
+    pub(crate) fn off_board(&self, position: Position) -> bool {
+        position.x < 0 || position.x >= self.width || position.y < 0 || position.y >= self.height
+    }
+
+    /// see [Game::validate](super::Game::validate)
+    pub fn validate(&self) -> Result<Vec<ValidationWarning>, Error> {
+        let (width, height) = (self.width, self.height);
+        if !(1..=u8::MAX as i32).contains(&width) || !(1..=u8::MAX as i32).contains(&height) {
+            return Err(Error::InvalidDimensions { width, height });
+        }
+
+        let mut occupied = HashSet::new();
+        for (idx, snake) in self.snakes.iter().enumerate() {
+            // comparing is cheaper than hashing for the handful of snakes in a game
+            if self.snakes[..idx].iter().any(|s| s.id == snake.id) {
+                return Err(Error::DuplicateSnakeId(snake.id.to_string()));
+            }
+            let invalid = |reason: String| Error::InvalidSnake {
+                id: snake.id.to_string(),
+                reason,
+            };
+            if !(0..=u8::MAX as i32).contains(&snake.health) {
+                return Err(invalid(format!("has health {}", snake.health)));
+            }
+            if snake.health == 0 {
+                continue;
+            }
+            snake.check_body(self).map_err(invalid)?;
+            for pos in snake.body.iter().unique() {
+                if !occupied.insert(*pos) {
+                    return Err(invalid(format!("overlaps another snake at {:?}", pos)));
+                }
+                if self.food.contains(pos) {
+                    return Err(invalid(format!("is on top of food at {:?}", pos)));
+                }
+                if self.walls.contains(pos) {
+                    return Err(invalid(format!("is on a wall at {:?}", pos)));
+                }
+            }
+        }
+
+        let mut warnings = self
+            .duplicate_food
+            .iter()
+            .map(|pos| ValidationWarning::DuplicateFood(*pos))
+            .collect_vec();
+        let mut off_board = self
+            .food
+            .iter()
+            .filter(|pos| self.off_board(**pos))
+            .collect_vec();
+        off_board.sort();
+        warnings.extend(
+            off_board
+                .into_iter()
+                .map(|pos| ValidationWarning::FoodOffBoard(*pos)),
+        );
+        Ok(warnings)
+    }
+
+    /// the id of each snake in `snake_ids`, None for snakes which have been eliminated. Errors
+    /// if a living snake isn't in the map or if two snakes would get the same id
+    pub(crate) fn snake_ids(&self, snake_ids: &SnakeIDMap) -> Result<Vec<Option<SnakeId>>, Error> {
+        let mut used = [false; u8::MAX as usize + 1];
+        self.snakes
+            .iter()
+            .map(|snake| {
+                if snake.health <= 0 {
+                    return Ok(None);
+                }
+                let id = *snake_ids
+                    .get(snake.id)
+                    .ok_or_else(|| Error::UnknownSnake(snake.id.to_string()))?;
+                if std::mem::replace(&mut used[id.as_usize()], true) {
+                    return Err(Error::DuplicateSnakeId(snake.id.to_string()));
+                }
+                Ok(Some(id))
+            })
+            .collect()
+    }
+}
+
+impl SnakeView<'_> {
+    /// Checks that a living snakes body is one the engine could send: on the board, starting at the
+    /// head, connected, not crossing itself, and only stacked at the tail (or fully stacked at the
+    /// start of the game, on 1 piece or at least 3). No square can have more than [MAX_STACK]
+    /// pieces on it
+    fn check_body(&self, game: &GameView) -> Result<(), String> {
+        let (width, height) = (game.width, game.height);
+        if self.body.is_empty() {
+            return Err("has an empty body".to_string());
+        }
+        if self.body.len() > u16::MAX as usize {
+            return Err("is too long".to_string());
+        }
+        if self.body[0] != self.head {
+            return Err("has a head that isn't at the start of its body".to_string());
+        }
+        if let Some(pos) = self.body.iter().find(|pos| game.off_board(**pos)) {
+            return Err(format!("has a piece off the board at {:?}", pos));
+        }
+
+        // snakes spawn with every piece on one square, and grow by stacking a piece on their
+        // tail, so that is the only place pieces can be stacked
+        let runs = self.body.iter().dedup_with_count().collect_vec();
+        let (tail_count, _) = runs[runs.len() - 1];
+        if tail_count > MAX_STACK {
+            return Err(format!("is {} pieces stacked on one square", tail_count));
+        }
+        if runs.len() == 1 {
+            if tail_count == 2 {
+                return Err("is 2 pieces stacked on one square".to_string());
+            }
+            return Ok(());
+        }
+        if runs[..runs.len() - 1].iter().any(|(count, _)| *count > 1) {
+            return Err("has pieces stacked away from its tail".to_string());
+        }
+        if runs.iter().map(|(_, pos)| *pos).unique().count() != runs.len() {
+            return Err("crosses itself".to_string());
+        }
+        for ((_, a), (_, b)) in runs.iter().tuple_windows() {
+            let dx = (a.x - b.x).abs();
+            let dy = (a.y - b.y).abs();
+            let (dx, dy) = if game.wrapped {
+                (dx.min(width - dx), dy.min(height - dy))
+            } else {
+                (dx, dy)
+            };
+            if dx + dy != 1 {
+                return Err(format!("isn't connected between {:?} and {:?}", a, b));
+            }
+        }
+
+        Ok(())
+    }
+}
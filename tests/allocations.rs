@@ -0,0 +1,113 @@
+//! Checks that reusing buffers with `simulate_with_moves_into` doesn't allocate once the buffers
+//! are big enough. This is its own test binary so the counting allocator only sees this test
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::cell::Cell;
+
+use battlesnake_game_types::compact_representation::{
+    StandardCellBoard4Snakes11x11, WrappedCellBoard4Snakes11x11,
+};
+use battlesnake_game_types::game_fixture;
+use battlesnake_game_types::types::{
+    build_snake_id_map, Action, Move, SimulableGame, SimulatorInstruments, SnakeIDGettableGame,
+};
+
+/// counts allocations made on the current thread, so the test harness's own threads don't count
+struct CountingAllocator;
+
+thread_local! {
+    static ALLOCATIONS: Cell<usize> = const { Cell::new(0) };
+}
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.with(|count| count.set(count.get() + 1));
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        ALLOCATIONS.with(|count| count.set(count.get() + 1));
+        System.realloc(ptr, layout, new_size)
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+fn allocations() -> usize {
+    ALLOCATIONS.with(Cell::get)
+}
+
+#[derive(Debug)]
+struct Instruments;
+impl SimulatorInstruments for Instruments {
+    fn observe_simulation(&self, _: std::time::Duration) {}
+}
+
+/// simulates every board reachable in two turns from `board`, returning how many allocations
+/// were made after the first call
+fn allocations_after_warm_up<G: SimulableGame<Instruments, 4> + SnakeIDGettableGame + Clone>(
+    board: &G,
+) -> usize {
+    let mut states: Vec<G> = Vec::new();
+    let mut moves: Vec<Action<4>> = Vec::new();
+    let mut next_states: Vec<G> = Vec::new();
+    let all = Move::all();
+    let ids = board.get_snake_ids();
+    board.simulate_with_moves_into(
+        &Instruments,
+        ids.iter().map(|sid| (sid.clone(), all.as_slice())),
+        &mut states,
+        &mut moves,
+    );
+    let nodes = states.clone();
+    let node_ids = nodes.iter().map(|n| n.get_snake_ids()).collect::<Vec<_>>();
+    // warm up the buffer for the next turn, which can be bigger than the first
+    for (node, ids) in nodes.iter().zip(node_ids.iter()) {
+        node.simulate_with_moves_into(
+            &Instruments,
+            ids.iter().map(|sid| (sid.clone(), all.as_slice())),
+            &mut next_states,
+            &mut moves,
+        );
+    }
+
+    let before = allocations();
+    for _ in 0..10 {
+        for (node, ids) in nodes.iter().zip(node_ids.iter()) {
+            node.simulate_with_moves_into(
+                &Instruments,
+                ids.iter().map(|sid| (sid.clone(), all.as_slice())),
+                &mut next_states,
+                &mut moves,
+            );
+            assert!(!next_states.is_empty());
+            assert_eq!(next_states.len(), moves.len());
+        }
+    }
+    allocations() - before
+}
+
+#[test]
+fn test_simulate_into_does_not_allocate_after_warm_up() {
+    let g = game_fixture(include_str!("../fixtures/late_stage.json"));
+    let board =
+        StandardCellBoard4Snakes11x11::convert_from_game(g.clone(), &build_snake_id_map(&g))
+            .unwrap();
+    assert_eq!(allocations_after_warm_up(&board), 0);
+
+    let g = game_fixture(include_str!("../fixtures/wrapped_fixture.json"));
+    let board = WrappedCellBoard4Snakes11x11::convert_from_game(g.clone(), &build_snake_id_map(&g))
+        .unwrap();
+    assert_eq!(allocations_after_warm_up(&board), 0);
+
+    // the iterator api allocates for every call, which is what the buffers are avoiding
+    let before = allocations();
+    let ids = board.get_snake_ids();
+    assert!(board.simulate(&Instruments, ids).count() > 0);
+    assert!(allocations() > before);
+}
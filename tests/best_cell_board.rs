@@ -0,0 +1,158 @@
+//! A worked example of calling a search that is generic over the board through the
+//! `best_cell_board!` dispatcher, so the same code runs on whichever compact board fits the game
+
+use battlesnake_game_types::best_cell_board;
+use battlesnake_game_types::compact_representation::CellBoardBuilder;
+use battlesnake_game_types::game_fixture;
+use battlesnake_game_types::types::{
+    build_snake_id_map, LengthGettableGame, Move, SimulableGame, SimulatorInstruments,
+    SizeDeterminableGame, SnakeIDGettableGame, VictorDeterminableGame, YouDeterminableGame,
+};
+use battlesnake_game_types::wire_representation::{Game, Position};
+
+#[derive(Debug)]
+struct Instruments;
+
+impl SimulatorInstruments for Instruments {
+    fn observe_simulation(&self, _: std::time::Duration) {}
+}
+
+const WIN: i64 = 1_000_000;
+
+/// every trait the search needs, so the bounds are written once
+trait SearchableGame<const N: usize>:
+    SimulableGame<Instruments, N>
+    + VictorDeterminableGame
+    + YouDeterminableGame
+    + LengthGettableGame
+    + SnakeIDGettableGame
+{
+}
+
+impl<G, const N: usize> SearchableGame<N> for G where
+    G: SimulableGame<Instruments, N>
+        + VictorDeterminableGame
+        + YouDeterminableGame
+        + LengthGettableGame
+        + SnakeIDGettableGame
+{
+}
+
+fn evaluate<G: SearchableGame<N>, const N: usize>(board: &G) -> i64 {
+    let me = board.you_id();
+    let alive = board.get_snake_ids();
+    if !alive.contains(me) {
+        return -WIN;
+    }
+    if board.is_over() {
+        return WIN;
+    }
+    let longest_other = alive
+        .iter()
+        .filter(|sid| *sid != me)
+        .map(|sid| board.get_length_i64(sid))
+        .max()
+        .unwrap_or(0);
+    board.get_length_i64(me) - longest_other
+}
+
+/// the best move for you and its score, assuming every other snake moves to make it as bad as
+/// possible for you
+fn minimax<G: SearchableGame<N>, const N: usize>(board: &G, depth: usize) -> (Move, i64) {
+    // the worst outcome of each of your moves, moves that are never simulated are always fatal
+    let mut worst = [-WIN; 4];
+    let mut seen = [false; 4];
+    for (action, next) in board.simulate(&Instruments, board.get_snake_ids()) {
+        let score = if depth <= 1 || next.is_over() {
+            evaluate(&next)
+        } else {
+            minimax(&next, depth - 1).1
+        };
+        let i = Move::all()
+            .iter()
+            .position(|mv| *mv == action.own_move())
+            .unwrap();
+        worst[i] = if seen[i] { worst[i].min(score) } else { score };
+        seen[i] = true;
+    }
+    let (i, score) = worst
+        .iter()
+        .copied()
+        .enumerate()
+        .max_by_key(|(_, score)| *score)
+        .unwrap();
+    (Move::all()[i], score)
+}
+
+/// a game where you are in the bottom left corner facing the wall, so the only way out is up,
+/// with another snake far away
+fn cornered(width: i32, height: i32) -> Game {
+    let mut builder = CellBoardBuilder::new(width, height);
+    builder
+        .add_snake(
+            vec![
+                Position::new(0, 0),
+                Position::new(1, 0),
+                Position::new(2, 0),
+            ],
+            90,
+        )
+        .unwrap();
+    let (x, y) = (width - 1, height - 1);
+    builder
+        .add_snake(
+            vec![
+                Position::new(x, y),
+                Position::new(x, y - 1),
+                Position::new(x, y - 2),
+            ],
+            90,
+        )
+        .unwrap();
+    builder.game().clone()
+}
+
+/// searches `game` on whichever board fits it, along with the size of the board searched
+fn search(game: Game, depth: usize) -> ((u32, u32), Move, i64) {
+    let snake_ids = build_snake_id_map(&game);
+    best_cell_board!(game, &snake_ids, |board| {
+        let (mv, score) = minimax(&board, depth);
+        ((board.get_width(), board.get_height()), mv, score)
+    })
+    .unwrap()
+}
+
+#[test]
+fn test_minimax_through_dispatch() {
+    let seven = game_fixture(include_str!("../fixtures/7x7board_valid.json"));
+    let (size, _, score) = search(seven, 1);
+    assert_eq!(size, (7, 7));
+    assert!(score > -WIN);
+
+    let eleven = game_fixture(include_str!("../fixtures/late_stage.json"));
+    let (size, _, score) = search(eleven, 2);
+    assert_eq!(size, (11, 11));
+    assert!(score > -WIN);
+
+    for (width, height) in [(7, 7), (11, 11), (19, 19), (13, 17)].iter() {
+        let (size, mv, score) = search(cornered(*width, *height), 2);
+        assert_eq!(size, (*width as u32, *height as u32));
+        assert_eq!(mv, Move::Up);
+        assert!(score > -WIN);
+    }
+
+    let mut wrapped = cornered(13, 17);
+    wrapped.game.ruleset.name = "wrapped".to_string();
+    // wrapped boards have no walls, so left wraps around to the other side
+    let (size, mv, _) = search(wrapped, 2);
+    assert_eq!(size, (13, 17));
+    assert_ne!(mv, Move::Right);
+}
+
+#[test]
+fn test_dispatch_errors() {
+    let game = cornered(60, 60);
+    let snake_ids = build_snake_id_map(&game);
+    let result = best_cell_board!(game, &snake_ids, |board| board.get_snake_ids().len());
+    assert!(result.is_err());
+}
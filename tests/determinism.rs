@@ -0,0 +1,121 @@
+//! Checks that a seeded search gives the same move after simulating the same nodes every time it
+//! runs, from games parsed separately so nothing can lean on the order a parse happened to put
+//! the food in
+
+use battlesnake_game_types::compact_representation::StandardCellBoard4Snakes11x11;
+use battlesnake_game_types::game_fixture;
+use battlesnake_game_types::playout::{evaluate_moves_by_playout, playout, PlayoutOutcome};
+use battlesnake_game_types::types::{
+    build_snake_id_map, FoodGettableGame, Move, RandomReasonableMovesGame, SimulableGame,
+    StatsInstruments, YouDeterminableGame,
+};
+use battlesnake_game_types::wire_representation::{diff_games, Game};
+use rand::rngs::SmallRng;
+use rand::SeedableRng;
+
+const NODES: u64 = 500;
+
+fn parse() -> Game {
+    game_fixture(include_str!("../fixtures/late_stage.json"))
+}
+
+fn board(game: &Game) -> StandardCellBoard4Snakes11x11 {
+    game.as_cell_board(&build_snake_id_map(game)).unwrap()
+}
+
+/// Flat monte carlo over each of your moves in turn, all from one rng seeded with `seed`, until
+/// `NODES` simulations have been made. The move with the most wins, and the nodes simulated
+fn search(game: &Game, seed: u64) -> (Move, u64) {
+    let board = board(game);
+    let you = *board.you_id();
+    let instruments = StatsInstruments::new();
+    let mut rng = SmallRng::seed_from_u64(seed);
+    let mut wins = [0; 4];
+    while instruments.simulations() < NODES {
+        for mv in Move::all_iter() {
+            let moves = board
+                .random_reasonable_move_for_each_snake(&mut rng)
+                .map(|other| {
+                    let mv = if other.snake_id == you { mv } else { other.mv };
+                    (other.snake_id, [mv])
+                })
+                .collect::<Vec<_>>();
+            let (_, next) = board
+                .simulate_with_moves(&instruments, moves)
+                .next()
+                .unwrap();
+            let remaining = NODES.saturating_sub(instruments.simulations()) as usize;
+            if playout(next, &mut rng, remaining, &instruments).outcome == PlayoutOutcome::Win {
+                wins[mv.as_index()] += 1;
+            }
+        }
+    }
+    let best = Move::all_iter()
+        .max_by_key(|mv| wins[mv.as_index()])
+        .unwrap();
+    (best, instruments.simulations())
+}
+
+#[test]
+fn test_wire_boundaries() {
+    let (first, second) = (parse(), parse());
+    assert_eq!(
+        first.get_all_food_as_positions(),
+        second.get_all_food_as_positions()
+    );
+    assert_eq!(
+        serde_json::to_string(&first).unwrap(),
+        serde_json::to_string(&second).unwrap()
+    );
+    assert_eq!(board(&first), board(&second));
+}
+
+#[test]
+fn test_food_diffs() {
+    let food_only_in_predicted = || {
+        let predicted = parse();
+        let mut actual = parse();
+        actual.board.food.clear();
+        diff_games(&predicted, &actual).food_only_in_predicted
+    };
+    let first = food_only_in_predicted();
+    assert!(first.len() > 1);
+    assert!(first.windows(2).all(|pair| pair[0] < pair[1]));
+    for _ in 0..4 {
+        assert_eq!(food_only_in_predicted(), first);
+    }
+}
+
+#[test]
+fn test_seeded_search() {
+    let first = search(&parse(), 11);
+    assert!(first.1 >= NODES);
+    for _ in 0..2 {
+        assert_eq!(search(&parse(), 11), first);
+    }
+}
+
+#[test]
+fn test_seeded_playout_evaluation() {
+    let evaluate = || {
+        let instruments = StatsInstruments::new();
+        let evaluation = evaluate_moves_by_playout(&board(&parse()), 5, 8, 20, &instruments);
+        (
+            evaluation,
+            evaluation.best_move(),
+            instruments.simulations(),
+        )
+    };
+    let first = evaluate();
+    assert_eq!(evaluate(), first);
+
+    // the playouts run on however many threads rayon has, and get the same results on any
+    #[cfg(feature = "rayon")]
+    for threads in [1, 3].iter() {
+        let pool = rayon::ThreadPoolBuilder::new()
+            .num_threads(*threads)
+            .build()
+            .unwrap();
+        assert_eq!(pool.install(evaluate), first);
+    }
+}
@@ -0,0 +1,63 @@
+//! Checks that the compact boards and simulation still work without the standard library, by
+//! running the tests of tests/no_std_crate, a `no_std` crate that depends on this one with
+//! `default-features = false, features = ["alloc"]`
+
+use std::{fs, path::Path, process::Command, time::Duration};
+
+use battlesnake_game_types::compact_representation::StandardCellBoard4Snakes11x11;
+use battlesnake_game_types::game_fixture;
+use battlesnake_game_types::types::{
+    build_snake_id_map, LengthGettableGame, SimulableGame, SimulatorInstruments,
+    SnakeIDGettableGame,
+};
+
+#[derive(Debug)]
+struct Instruments;
+
+impl SimulatorInstruments for Instruments {
+    fn observe_simulation(&self, _: Duration) {}
+}
+
+#[test]
+fn test_boards_simulate_without_std() {
+    let manifest_dir = Path::new(env!("CARGO_MANIFEST_DIR"));
+    // a separate target directory, so this doesn't wait on the lock held by `cargo test`
+    let target_dir = manifest_dir.join("target/no-std-check");
+    fs::create_dir_all(&target_dir).unwrap();
+
+    // without std there are no wire games, so the board goes over as bytes
+    let g = game_fixture(include_str!("../fixtures/late_stage.json"));
+    let board =
+        StandardCellBoard4Snakes11x11::convert_from_game(g.clone(), &build_snake_id_map(&g))
+            .unwrap();
+    let bytes_path = target_dir.join("late_stage.bin");
+    fs::write(&bytes_path, board.to_bytes()).unwrap();
+    // the number of states simulating it makes and the total length of the snakes in them, which
+    // should be the same without std
+    let states = board
+        .simulate(&Instruments, board.get_snake_ids())
+        .map(|(_, next)| {
+            next.get_snake_ids()
+                .iter()
+                .map(|id| next.get_length(id) as u64)
+                .sum::<u64>()
+        })
+        .collect::<Vec<_>>();
+    let simulated = format!("{} {}", states.len(), states.iter().sum::<u64>());
+
+    let cargo = std::env::var("CARGO").unwrap_or_else(|_| "cargo".to_string());
+    let status = Command::new(cargo)
+        .arg("test")
+        .arg("--manifest-path")
+        .arg(manifest_dir.join("tests/no_std_crate/Cargo.toml"))
+        .arg("--target-dir")
+        .arg(&target_dir)
+        .env("LATE_STAGE_BYTES", &bytes_path)
+        .env("LATE_STAGE_SIMULATED", simulated)
+        .status()
+        .expect("cargo can be run");
+    assert!(
+        status.success(),
+        "the compact boards don't build or simulate without std"
+    );
+}
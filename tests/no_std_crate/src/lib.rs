@@ -0,0 +1,65 @@
+//! Reads a compact board from bytes and simulates it without the standard library. The board is
+//! `fixtures/late_stage.json` as bytes, written by tests/no_std.rs to the file in
+//! `LATE_STAGE_BYTES`, which also passes what simulating it with std gives in
+//! `LATE_STAGE_SIMULATED`
+#![no_std]
+
+use core::time::Duration;
+
+use battlesnake_game_types::compact_representation::StandardCellBoard4Snakes11x11;
+use battlesnake_game_types::types::{
+    LengthGettableGame, SimulableGame, SimulatorInstruments, SnakeIDGettableGame,
+};
+use battlesnake_game_types::Error;
+
+/// the bytes of `fixtures/late_stage.json`
+pub const LATE_STAGE: &[u8] = include_bytes!(env!("LATE_STAGE_BYTES"));
+
+#[derive(Debug)]
+struct Instruments;
+
+impl SimulatorInstruments for Instruments {
+    fn observe_simulation(&self, _: Duration) {}
+}
+
+/// reads a board from bytes and returns how many states simulating every move of every snake
+/// makes, and the total length of the snakes in them
+pub fn simulate_from_bytes(bytes: &[u8]) -> Result<(usize, u64), Error> {
+    let board = StandardCellBoard4Snakes11x11::from_bytes(bytes)?;
+    let mut states = 0;
+    let mut total_length = 0;
+    for (_, next) in board.simulate(&Instruments, board.get_snake_ids()) {
+        states += 1;
+        total_length += next
+            .get_snake_ids()
+            .iter()
+            .map(|id| next.get_length(id) as u64)
+            .sum::<u64>();
+    }
+    Ok((states, total_length))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_round_trips_bytes() {
+        let board = StandardCellBoard4Snakes11x11::from_bytes(LATE_STAGE).unwrap();
+        assert_eq!(board.to_bytes(), LATE_STAGE);
+    }
+
+    #[test]
+    fn test_simulates_like_std() {
+        let (states, total_length) = simulate_from_bytes(LATE_STAGE).unwrap();
+        let (expected_states, expected_length) =
+            env!("LATE_STAGE_SIMULATED").split_once(' ').unwrap();
+        assert_eq!(states, expected_states.parse().unwrap());
+        assert_eq!(total_length, expected_length.parse().unwrap());
+    }
+
+    #[test]
+    fn test_rejects_truncated_bytes() {
+        assert!(simulate_from_bytes(&LATE_STAGE[..LATE_STAGE.len() / 2]).is_err());
+    }
+}
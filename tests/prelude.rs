@@ -0,0 +1,118 @@
+//! Checks that the prelude alone is enough to use every game trait, so it stays complete as
+//! traits are added and moved
+
+use battlesnake_game_types::compact_representation::dimensions::Square;
+use battlesnake_game_types::hazard_algorithms::NoopHazard;
+use battlesnake_game_types::prelude::*;
+use battlesnake_game_types::types::{
+    AStarOptions, FloodFillOptions, TrajectoryAssumption, VoronoiOptions,
+};
+use rand::{rngs::SmallRng, SeedableRng};
+
+#[derive(Debug)]
+struct Instruments;
+impl SimulatorInstruments for Instruments {
+    fn observe_simulation(&self, _: std::time::Duration) {}
+}
+
+fn assert_bounds<T: CellNum, D: Dimensions, P: PlayoutSync>() {}
+
+#[test]
+fn test_prelude_wire() {
+    let mut game =
+        battlesnake_game_types::game_fixture(include_str!("../fixtures/late_stage.json"));
+    let you = game.you_id().clone();
+    let head = game.get_head_as_native_position(&you);
+    let corner = Position::new(0, 0);
+
+    assert!(game.is_you(&you));
+    assert!(!game.is_over());
+    assert_eq!(game.get_winning_squad(), None);
+    assert!(game.get_winner().is_none());
+    assert!(game.get_health_i64(&you) > 0);
+    assert!(game.get_length(&you) > 0);
+    assert_eq!(game.get_snake_body_vec(&you)[0], head);
+    assert_eq!(game.position_from_native(head), head);
+    assert_eq!(game.get_width(), 11);
+    assert!(game.turn() > 0);
+    assert!(!game.get_all_food_as_positions().is_empty());
+    assert!(!game.is_food(&head));
+    assert!(!game.is_hazard(&head));
+    assert!(!game.is_neck(&you, &head));
+    assert!(game.is_visible(&head));
+    game.get_shout(&you);
+    assert_eq!(game.possible_moves(&head).count(), 4);
+    game.possible_head_squares(&you);
+    game.last_move(&you);
+    game.flood_fill(&head, FloodFillOptions::default());
+    game.voronoi(VoronoiOptions::default());
+    game.a_star(&head, &corner, AStarOptions::default());
+    game.food_race_analysis(AStarOptions::default());
+    game.choke_points();
+    game.will_be_unoccupied(&corner, 1);
+    game.can_safely_chase_tail(&you);
+    game.project_snake(&you, 2, &TrajectoryAssumption::Straight);
+
+    let mut rng = SmallRng::seed_from_u64(4);
+    assert!(game.random_reasonable_move_for_each_snake(&mut rng).count() > 0);
+    assert!(
+        game.random_reasonable_move_weighted(&mut rng, |_, _, _| 1.0)
+            .count()
+            > 0
+    );
+
+    game.set_hazard(corner);
+    assert!(game.is_hazard(&corner));
+    game.place_food(corner).unwrap();
+    assert!(game.is_food(&corner));
+
+    let mut hazards = NoopHazard();
+    assert!(hazards.observe(&game).is_ok());
+
+    let _: Move = Move::Up;
+    let _: Vector = Move::Up.to_vector();
+    assert_bounds::<u8, Square, Game>();
+}
+
+#[test]
+fn test_prelude_compact() {
+    let game = battlesnake_game_types::game_fixture(include_str!("../fixtures/late_stage.json"));
+    let snake_ids: SnakeIDMap = build_snake_id_map(&game);
+    let board: StandardCellBoard4Snakes11x11 = game.as_cell_board(&snake_ids).unwrap();
+    let ids: Vec<SnakeId> = board.get_snake_ids();
+    let head = board.get_head_as_native_position(&ids[0]);
+    let target = board.possible_moves(&head).next().unwrap().1;
+
+    assert!(board.simulate(&Instruments, ids.clone()).count() > 0);
+    board.predict_head_to_head(&ids[0], &ids[1], &target);
+
+    let boxed = DynGame::<_, 4>::boxed(board);
+    assert_eq!(boxed.snake_ids().len(), ids.len());
+    assert!(!boxed
+        .simulate_all_boxed(&DynInstruments(&Instruments))
+        .is_empty());
+
+    let wrapped_game =
+        battlesnake_game_types::game_fixture(include_str!("../fixtures/wrapped_fixture.json"));
+    let wrapped: WrappedCellBoard4Snakes11x11 = wrapped_game
+        .as_wrapped_cell_board(&build_snake_id_map(&wrapped_game))
+        .unwrap();
+    assert!(!wrapped.is_over());
+
+    let mut builder = CellBoardBuilder::new(7, 7);
+    builder
+        .add_snake(
+            vec![
+                Position::new(1, 1),
+                Position::new(1, 2),
+                Position::new(1, 3),
+            ],
+            100,
+        )
+        .unwrap();
+    let built: StandardCellBoard<u8, Square, { 7 * 7 }, 2> = builder.build().unwrap();
+    assert_eq!(built.get_snake_ids().len(), 1);
+
+    let invalid: Result<Game, Error> = battlesnake_game_types::try_game_fixture("{}");
+    assert!(invalid.is_err());
+}
@@ -0,0 +1,49 @@
+//! Checks that the crate still builds for wasm32-unknown-unknown with the `wasm` feature. This
+//! runs `cargo check` for that target, and is skipped when the target isn't installed
+
+use std::{path::Path, process::Command};
+
+const TARGET: &str = "wasm32-unknown-unknown";
+
+fn target_installed() -> bool {
+    let sysroot = match Command::new("rustc").args(["--print", "sysroot"]).output() {
+        Ok(output) if output.status.success() => output.stdout,
+        _ => return false,
+    };
+    let sysroot = String::from_utf8_lossy(&sysroot);
+    Path::new(sysroot.trim())
+        .join("lib/rustlib")
+        .join(TARGET)
+        .exists()
+}
+
+#[test]
+fn test_checks_for_wasm32() {
+    if !target_installed() {
+        eprintln!(
+            "skipping, run `rustup target add {}` to check the wasm build",
+            TARGET
+        );
+        return;
+    }
+
+    let manifest_dir = env!("CARGO_MANIFEST_DIR");
+    let cargo = std::env::var("CARGO").unwrap_or_else(|_| "cargo".to_string());
+    let status = Command::new(cargo)
+        .current_dir(manifest_dir)
+        .args([
+            "check",
+            "--lib",
+            "--tests",
+            "--features",
+            "wasm",
+            "--target",
+            TARGET,
+        ])
+        // a separate target directory, so this doesn't wait on the lock held by `cargo test`
+        .arg("--target-dir")
+        .arg(Path::new(manifest_dir).join("target/wasm-check"))
+        .status()
+        .expect("cargo can be run");
+    assert!(status.success(), "the crate doesn't build for {}", TARGET);
+}